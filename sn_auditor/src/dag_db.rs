@@ -17,7 +17,7 @@ use serde::{Deserialize, Serialize};
 use sn_client::transfers::{
     Hash, NanoTokens, SignedSpend, SpendAddress, DEFAULT_PAYMENT_FORWARD_SK,
 };
-use sn_client::transfers::{DEFAULT_NETWORK_ROYALTIES_PK, NETWORK_ROYALTIES_PK};
+use sn_client::transfers::{DEFAULT_NETWORK_ROYALTIES_PK, NETWORK_ROYALTIES_SPLIT};
 use sn_client::{Client, SpendDag, SpendDagGet};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Write;
@@ -412,12 +412,17 @@ impl SpendDagDb {
         beta_tracking.total_accumulated_utxo += spend.spend.spent_tx.outputs.len() as u64;
         beta_tracking.total_on_track_utxo += utxos_for_further_track;
 
-        // Collect royalties
+        // Collect royalties, paid to any of the royalties beneficiaries
         let royalty_pubkeys: BTreeSet<_> = spend
             .spend
             .network_royalties
             .iter()
-            .map(|derivation_idx| NETWORK_ROYALTIES_PK.new_unique_pubkey(derivation_idx))
+            .flat_map(|derivation_idx| {
+                NETWORK_ROYALTIES_SPLIT
+                    .beneficiaries()
+                    .iter()
+                    .map(|(main_pubkey, _)| main_pubkey.new_unique_pubkey(derivation_idx))
+            })
             .collect();
         let default_royalty_pubkeys: BTreeSet<_> = spend
             .spend
@@ -437,7 +442,11 @@ impl SpendDagDb {
             }
         }
 
-        if royalties.len() > (spend.spend.spent_tx.outputs.len() - 1) / 2 {
+        // each payment pays a node and at most each of the beneficiaries, besides the change
+        let beneficiaries = NETWORK_ROYALTIES_SPLIT.beneficiaries().len();
+        let max_royalties =
+            (spend.spend.spent_tx.outputs.len() - 1) * beneficiaries / (beneficiaries + 1);
+        if royalties.len() > max_royalties {
             eprintln!(
                 "Spend: {:?} has incorrect royalty of {}, with amount {} with reason {:?}",
                 spend.spend.unique_pubkey,
@@ -956,6 +956,12 @@ mod tests {
             show_holders: false,
             max_repayments_for_failed_data: 1,
             collect_registers: false,
+            max_concurrent_uploads: None,
+            max_uploads_per_sec: None,
+            max_spend: None,
+            verification_sample: None,
+            check_existing_chunks: true,
+            single_payment: false,
         };
         let make_data_public = false;
         (cfg, make_data_public)
@@ -11,9 +11,12 @@ extern crate tracing;
 
 mod subcommands;
 
+#[cfg(all(feature = "mount", any(target_os = "linux", target_os = "macos")))]
+use subcommands::mount::mount_cmd;
 use subcommands::{
     files::files_cmds,
     folders::folders_cmds,
+    profile::profile_cmds,
     register::register_cmds,
     wallet::{
         hot_wallet::{wallet_cmds, wallet_cmds_without_client, WalletCmds},
@@ -27,7 +30,10 @@ use clap::Parser;
 use color_eyre::Result;
 use indicatif::ProgressBar;
 use sn_client::transfers::bls_secret_from_hex;
-use sn_client::{Client, ClientEvent, ClientEventsBroadcaster, ClientEventsReceiver};
+use sn_client::{
+    BandwidthLimits, Client, ClientEvent, ClientEventsBroadcaster, ClientEventsReceiver,
+    NetworkProfiles,
+};
 #[cfg(feature = "metrics")]
 use sn_logging::{metrics::init_metrics, Level, LogBuilder, LogFormat};
 use std::{io, path::PathBuf, time::Duration};
@@ -73,12 +79,30 @@ async fn main() -> Result<()> {
     );
 
     let client_data_dir_path = get_client_data_dir_path()?;
+    if let SubCmd::Profile(cmds) = opt.cmd {
+        return profile_cmds(cmds, &client_data_dir_path);
+    }
+    let profile = NetworkProfiles::load(&client_data_dir_path)?
+        .select(opt.profile.as_deref())?
+        .cloned();
+    // each network keeps its own client key and wallet.
+    let client_data_dir_path = match &profile {
+        Some(profile) => {
+            info!("Using the network profile {}", profile.name);
+            println!("Using the network profile {}", profile.name);
+            profile.apply_network_key()?;
+            std::fs::create_dir_all(&profile.root_dir)?;
+            profile.root_dir.clone()
+        }
+        None => client_data_dir_path,
+    };
     // Perform actions that do not require us connecting to the network and return early
     if let SubCmd::Wallet(cmds) = &opt.cmd {
         if let WalletCmds::Address { .. }
         | WalletCmds::Balance { .. }
         | WalletCmds::Create { .. }
         | WalletCmds::Sign { .. }
+        | WalletCmds::AddressBook(_)
         | WalletCmds::Status { .. }
         | WalletCmds::Encrypt { .. } = cmds
         {
@@ -102,7 +126,12 @@ async fn main() -> Result<()> {
     println!("Instantiating a SAFE client...");
     let secret_key = get_client_secret_key(&client_data_dir_path)?;
 
-    let bootstrap_peers = opt.peers.get_peers().await?;
+    let bootstrap_peers = match &profile {
+        Some(profile) if opt.peers.peers.is_empty() && !profile.peers.is_empty() => {
+            profile.bootstrap_peers()?
+        }
+        _ => opt.peers.get_peers().await?,
+    };
 
     println!(
         "Connecting to the network with {} peers",
@@ -137,6 +166,14 @@ async fn main() -> Result<()> {
         }
     };
     progress_bar_handler.await?;
+    client.set_bandwidth_limits(BandwidthLimits {
+        upload: opt.upload_limit,
+        download: opt.download_limit,
+    });
+    #[cfg(feature = "open-metrics")]
+    if let Some(port) = opt.metrics_server_port {
+        client.serve_metrics(port);
+    }
 
     // default to verifying storage
     let should_verify_store = !opt.no_verify;
@@ -160,6 +197,9 @@ async fn main() -> Result<()> {
         SubCmd::Register(cmds) => {
             register_cmds(cmds, &client, &client_data_dir_path, should_verify_store).await
         }
+        #[cfg(all(feature = "mount", any(target_os = "linux", target_os = "macos")))]
+        SubCmd::Mount(args) => mount_cmd(args, &client, &client_data_dir_path).await,
+        SubCmd::Profile(_) => unreachable!("profile commands are run without a client"),
     };
     println!("Completed with {result:?} of execute {cmd_str:?}");
 
@@ -7,8 +7,8 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use autonomi::{
-    download_file, download_files, ChunkManager, Estimator, FilesUploader, UploadedFile,
-    UPLOADED_FILES,
+    download_file, download_files, ChunkManager, Estimator, FilesUploader, UploadIndex,
+    UploadedFile, UPLOADED_FILES,
 };
 use clap::Parser;
 use color_eyre::{
@@ -17,6 +17,7 @@ use color_eyre::{
 };
 use sn_client::{
     protocol::storage::{Chunk, ChunkAddress, RetryStrategy},
+    transfers::NanoTokens,
     UploadCfg,
 };
 use sn_client::{Client, FilesApi, BATCH_SIZE};
@@ -40,9 +41,9 @@ pub enum FilesCmds {
     Upload {
         /// The location of the file(s) to upload.
         ///
-        /// Can be a file or a directory.
-        #[clap(name = "path", value_name = "PATH")]
-        file_path: PathBuf,
+        /// Each can be a file or a directory. They are all chunked together and uploaded as one batch.
+        #[clap(name = "path", value_name = "PATH", required = true)]
+        file_paths: Vec<PathBuf>,
         /// The batch_size to split chunks into parallel handling batches
         /// during payment and upload processing.
         #[clap(long, default_value_t = BATCH_SIZE, short='b')]
@@ -56,6 +57,33 @@ pub enum FilesCmds {
         /// to 'persistent' (most effort).
         #[clap(long, default_value_t = RetryStrategy::Quick, short = 'r', help = "Sets the retry strategy on upload failure. Options: 'quick' for minimal effort, 'balanced' for moderate effort, or 'persistent' for maximum effort.")]
         retry_strategy: RetryStrategy,
+        /// The maximum number of chunks being uploaded at the same time.
+        ///
+        /// Defaults to the batch size.
+        #[clap(long)]
+        max_concurrent_uploads: Option<usize>,
+        /// The maximum number of chunk uploads started per second.
+        #[clap(long)]
+        max_uploads_per_sec: Option<u32>,
+        /// The maximum amount of tokens the upload may spend, e.g. "0.5".
+        ///
+        /// The upload stops before making a payment which would exceed it, and can be resumed later.
+        #[clap(long)]
+        max_spend: Option<NanoTokens>,
+        /// Verify a fraction of the uploaded chunks once the upload is done, e.g. "1.0" for all of them.
+        ///
+        /// The chunks which can't be verified are uploaded again.
+        #[clap(long, value_parser = parse_verification_sample)]
+        verify_sample: Option<f64>,
+        /// Quote all the chunks first and pay for them in a single transaction, rather than in one per batch.
+        ///
+        /// This makes a single spend for the whole upload. The quotes expire if the quoting takes too long, so
+        /// this is best suited to uploads of a moderate size.
+        #[clap(long)]
+        single_payment: bool,
+        /// Add the uploaded files to the local index, to find them later on with 'files search'.
+        #[clap(long)]
+        index: bool,
     },
     Download {
         /// The name to apply to the downloaded file.
@@ -86,6 +114,28 @@ pub enum FilesCmds {
         #[clap(long, default_value_t = RetryStrategy::Quick, short = 'r', help = "Sets the retry strategy on download failure. Options: 'quick' for minimal effort, 'balanced' for moderate effort, or 'persistent' for maximum effort.")]
         retry_strategy: RetryStrategy,
     },
+    /// Add all the files uploaded by the current user to the local index.
+    ///
+    /// The files uploaded with '--index' are indexed already, along with the path they were uploaded from.
+    Index,
+    /// Search the local index of the uploaded files by file name, path and tags.
+    Search {
+        /// The words to search for. The files matching all of them are listed, best matches first.
+        #[clap(name = "query", required = true)]
+        query: Vec<String>,
+    },
+    /// Tag an indexed file, to find it by the tags with 'files search'.
+    Tag {
+        /// The hex address of the file.
+        #[clap(name = "address")]
+        file_addr: String,
+        /// The tags to add, or to remove with '--remove'.
+        #[clap(name = "tags", required = true)]
+        tags: Vec<String>,
+        /// Remove the tags instead of adding them.
+        #[clap(long)]
+        remove: bool,
+    },
 }
 
 pub(crate) async fn files_cmds(
@@ -106,33 +156,49 @@ pub(crate) async fn files_cmds(
                 .await?
         }
         FilesCmds::Upload {
-            file_path,
+            file_paths,
             batch_size,
             retry_strategy,
             make_data_public,
+            max_concurrent_uploads,
+            max_uploads_per_sec,
+            max_spend,
+            verify_sample,
+            single_payment,
+            index,
         } => {
-            let files_count = count_files_in_path_recursively(&file_path);
+            for file_path in file_paths.iter() {
+                let files_count = count_files_in_path_recursively(file_path);
 
-            if files_count == 0 {
-                if file_path.is_dir() {
-                    bail!(
-                        "The directory specified for upload is empty. \
-                    Please verify the provided path."
-                    );
-                } else {
-                    bail!("The provided file path is invalid. Please verify the path.");
+                if files_count == 0 {
+                    if file_path.is_dir() {
+                        bail!(
+                            "The directory specified for upload is empty. \
+                        Please verify the provided path."
+                        );
+                    } else {
+                        bail!("The provided file path is invalid. Please verify the path.");
+                    }
                 }
             }
             let upload_cfg = UploadCfg {
                 batch_size,
                 verify_store,
                 retry_strategy,
+                max_concurrent_uploads,
+                max_uploads_per_sec,
+                max_spend,
+                verification_sample: verify_sample,
+                single_payment,
                 ..Default::default()
             };
-            let files_uploader = FilesUploader::new(client.clone(), root_dir.to_path_buf())
+            let mut files_uploader = FilesUploader::new(client.clone(), root_dir.to_path_buf())
                 .set_make_data_public(make_data_public)
-                .set_upload_cfg(upload_cfg)
-                .insert_path(&file_path);
+                .set_index_uploads(index)
+                .set_upload_cfg(upload_cfg);
+            for file_path in file_paths.iter() {
+                files_uploader = files_uploader.insert_path(file_path);
+            }
 
             let _summary = files_uploader.start_upload().await?;
         }
@@ -245,6 +311,55 @@ pub(crate) async fn files_cmds(
                 }
             }
         }
+        FilesCmds::Index => {
+            let mut index = UploadIndex::open(root_dir)?;
+            let indexed = index.index_uploaded_files(root_dir)?;
+            index.save()?;
+            println!(
+                "Indexed {indexed} more uploaded files, {} in total.",
+                index.len()
+            );
+        }
+        FilesCmds::Search { query } => {
+            let index = UploadIndex::open(root_dir)?;
+            if index.is_empty() {
+                println!("No uploaded file is indexed. Run 'files index' to index them.");
+                return Ok(());
+            }
+            let found = index.search(&query.join(" "));
+            if found.is_empty() {
+                println!("No uploaded file matches the search.");
+            }
+            for file in found {
+                println!("\"{}\" {}", file.file_name, file.address.to_hex());
+                if let Some(path) = &file.path {
+                    println!("    uploaded from {}", path.display());
+                }
+                if !file.tags.is_empty() {
+                    let tags: Vec<_> = file.tags.iter().map(String::as_str).collect();
+                    println!("    tags: {}", tags.join(", "));
+                }
+            }
+        }
+        FilesCmds::Tag {
+            file_addr,
+            tags,
+            remove,
+        } => {
+            let xorname = hex::decode(&file_addr)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(XorName)
+                .ok_or_else(|| eyre!("Invalid address {file_addr}"))?;
+            let address = ChunkAddress::new(xorname);
+            let mut index = UploadIndex::open(root_dir)?;
+            if remove {
+                index.untag(&address, &tags)?;
+            } else {
+                index.tag(&address, tags)?;
+            }
+            index.save()?;
+        }
     }
     Ok(())
 }
@@ -260,3 +375,11 @@ fn count_files_in_path_recursively(file_path: &PathBuf) -> u32 {
     });
     count
 }
+
+fn parse_verification_sample(sample: &str) -> Result<f64> {
+    let sample: f64 = sample.parse()?;
+    if !(sample > 0.0 && sample <= 1.0) {
+        bail!("The verification sample must be greater than 0, and at most 1");
+    }
+    Ok(sample)
+}
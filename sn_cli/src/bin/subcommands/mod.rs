@@ -8,6 +8,9 @@
 
 pub(crate) mod files;
 pub(crate) mod folders;
+#[cfg(all(feature = "mount", any(target_os = "linux", target_os = "macos")))]
+pub(crate) mod mount;
+pub(crate) mod profile;
 pub(crate) mod register;
 pub(crate) mod wallet;
 
@@ -61,6 +64,25 @@ pub(crate) struct Opt {
     /// This may increase operation speed, but offers no guarantees that operations were successful.
     #[clap(global = true, long = "no-verify", short = 'x')]
     pub no_verify: bool,
+
+    /// The maximum bandwidth used to send data to the network, in bytes per second.
+    #[clap(long, global = true)]
+    pub upload_limit: Option<u64>,
+
+    /// The maximum bandwidth used to fetch data from the network, in bytes per second.
+    #[clap(long, global = true)]
+    pub download_limit: Option<u64>,
+
+    /// The network profile to use, which sets the peers, the network key and the data dir.
+    ///
+    /// Falls back to the `SAFE_NETWORK_PROFILE` environment variable, then to the default profile.
+    #[clap(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Serve the metrics of the client on this port, at `http://localhost:<port>/metrics`.
+    #[cfg(feature = "open-metrics")]
+    #[clap(long, global = true)]
+    pub metrics_server_port: Option<u16>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -83,4 +105,15 @@ pub(super) enum SubCmd {
     #[clap(name = "register", subcommand)]
     /// Commands for register management
     Register(register::RegisterCmds),
+    #[clap(name = "profile", subcommand)]
+    /// Commands for network profiles management
+    Profile(profile::ProfileCmds),
+    #[cfg(all(feature = "mount", any(target_os = "linux", target_os = "macos")))]
+    #[clap(name = "mount")]
+    /// Mount a files container as a read-only filesystem.
+    ///
+    /// The content of the files is fetched from the network as it is read, and the chunks are kept
+    /// in a local cache. The filesystem is unmounted with `fusermount -u <mountpoint>`, or `umount`
+    /// on macOS.
+    Mount(mount::MountArgs),
 }
@@ -0,0 +1,62 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use clap::Args;
+use color_eyre::{eyre::eyre, Result};
+use sn_client::{protocol::storage::RegisterAddress, Client};
+use std::path::{Path, PathBuf};
+use tokio::runtime::Handle;
+
+/// The name of the directory the fetched chunks are cached in, in the root directory of the client.
+const CHUNK_CACHE_DIR_NAME: &str = "chunk_cache";
+
+#[derive(Args, Debug)]
+pub struct MountArgs {
+    /// The hex encoded address of the container.
+    address: String,
+
+    /// The directory to mount the container on.
+    mountpoint: PathBuf,
+
+    /// The directory the fetched chunks are cached in.
+    ///
+    /// Defaults to the `chunk_cache` directory in the data directory of the client.
+    #[clap(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// The maximum size of the chunk cache, in bytes.
+    #[clap(long, default_value_t = 1024 * 1024 * 1024)]
+    cache_size: u64,
+}
+
+pub(crate) async fn mount_cmd(args: MountArgs, client: &Client, root_dir: &Path) -> Result<()> {
+    let address = RegisterAddress::from_hex(&args.address)
+        .map_err(|err| eyre!("Invalid container address {}: {err}", args.address))?;
+    let cache_dir = args
+        .cache_dir
+        .unwrap_or_else(|| root_dir.join(CHUNK_CACHE_DIR_NAME));
+
+    println!("Retrieving the container at {address:?}...");
+    let fs = sn_mount::retrieve_container(
+        client.clone(),
+        root_dir,
+        address,
+        &cache_dir,
+        args.cache_size,
+    )
+    .await?;
+
+    println!(
+        "Mounting the container on {}. Unmount it to exit.",
+        args.mountpoint.display()
+    );
+    // the FUSE callbacks block on the runtime, so they can't be run from within it.
+    let runtime = Handle::current();
+    tokio::task::spawn_blocking(move || sn_mount::mount(fs, runtime, &args.mountpoint)).await??;
+    Ok(())
+}
@@ -0,0 +1,116 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use clap::Subcommand;
+use color_eyre::Result;
+use sn_client::{NetworkProfile, NetworkProfiles};
+use std::path::{Path, PathBuf};
+
+/// The dir the data of the profiles are stored in by default, in the client data dir.
+const PROFILES_DIR: &str = "profiles";
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCmds {
+    /// Add a network profile, or replace the one of the same name.
+    Add {
+        /// The name the profile is selected by, with `--profile` or `SAFE_NETWORK_PROFILE`.
+        #[clap(name = "name")]
+        name: String,
+        /// Peer(s) to bootstrap from, in a 'multiaddr' format containing the peer ID.
+        #[clap(long = "peer", value_name = "multiaddr", value_delimiter = ',')]
+        peers: Vec<String>,
+        /// The genesis key of the network, hex or bech32m encoded. The one built in the client by
+        /// default.
+        #[clap(long)]
+        network_key: Option<String>,
+        /// The dir the client key and the wallet of the network are stored in.
+        /// Defaults to a dir of its own in the client data dir.
+        #[clap(long)]
+        root_dir: Option<PathBuf>,
+        /// Use the profile when none is selected.
+        #[clap(long)]
+        default: bool,
+    },
+    /// List the network profiles.
+    List,
+    /// Remove a network profile. Its data are left in place.
+    Remove {
+        #[clap(name = "name")]
+        name: String,
+    },
+    /// Set the profile used when none is selected, or unset it if no name is given.
+    Default {
+        #[clap(name = "name")]
+        name: Option<String>,
+    },
+}
+
+pub(crate) fn profile_cmds(cmds: ProfileCmds, client_data_dir: &Path) -> Result<()> {
+    let mut profiles = NetworkProfiles::load(client_data_dir)?;
+    match cmds {
+        ProfileCmds::Add {
+            name,
+            peers,
+            network_key,
+            root_dir,
+            default,
+        } => {
+            let root_dir =
+                root_dir.unwrap_or_else(|| client_data_dir.join(PROFILES_DIR).join(&name));
+            let mut profile = NetworkProfile::new(&name, root_dir)?;
+            profile.peers = peers;
+            profile.network_key = network_key;
+            // catch typos before the profile is used.
+            let _ = profile.bootstrap_peers()?;
+            let _ = profile.network_key()?;
+            if profiles.add(profile).is_some() {
+                println!("Replaced the profile {name}");
+            } else {
+                println!("Added the profile {name}");
+            }
+            if default {
+                profiles.set_default(Some(&name))?;
+            }
+        }
+        ProfileCmds::List => {
+            let default = profiles
+                .default_profile()
+                .map(|profile| profile.name.clone());
+            for profile in profiles.profiles() {
+                let marker = if Some(&profile.name) == default.as_ref() {
+                    " (default)"
+                } else {
+                    ""
+                };
+                println!("{}{marker}", profile.name);
+                println!("  data dir: {}", profile.root_dir.display());
+                println!("  peers: {}", profile.peers.len());
+                if let Some(key) = &profile.network_key {
+                    println!("  network key: {key}");
+                }
+            }
+            return Ok(());
+        }
+        ProfileCmds::Remove { name } => {
+            let profile = profiles.remove(&name)?;
+            println!(
+                "Removed the profile {name}, its data are left in {}",
+                profile.root_dir.display()
+            );
+        }
+        ProfileCmds::Default { name } => {
+            profiles.set_default(name.as_deref())?;
+            match name {
+                Some(name) => println!("The profile {name} is used by default"),
+                None => println!("No profile is used by default"),
+            }
+        }
+    }
+    profiles.save()?;
+    Ok(())
+}
@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+pub(crate) mod address_book;
 mod audit;
 pub(crate) mod helpers;
 pub(crate) mod hot_wallet;
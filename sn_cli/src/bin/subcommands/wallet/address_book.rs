@@ -0,0 +1,93 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use clap::Parser;
+use color_eyre::Result;
+use sn_client::transfers::{AddressBook, MainPubkey, WALLET_DIR_NAME};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+// Please do not remove the blank lines in these doc comments.
+// They are used for inserting line breaks when the help menu is rendered in the UI.
+#[derive(Parser, Debug)]
+pub enum AddressBookCmds {
+    /// Add a label for an address, to be used instead of it in the 'send' command.
+    Add {
+        /// The label, e.g. the name of the recipient.
+        #[clap(name = "label")]
+        label: String,
+        /// The address, or hex-encoded public key, to be labelled.
+        #[clap(name = "address")]
+        address: String,
+    },
+    /// Remove a label from the address book.
+    Remove {
+        /// The label to remove.
+        #[clap(name = "label")]
+        label: String,
+    },
+    /// List all labels and their addresses.
+    List,
+    /// Write the address book to a JSON file.
+    Export {
+        /// Path of the file to write.
+        #[clap(name = "path")]
+        path: PathBuf,
+    },
+    /// Add the labels of an exported address book.
+    ///
+    /// Nothing is imported if one of the labels is already given to another address.
+    Import {
+        /// Path of the exported file.
+        #[clap(name = "path")]
+        path: PathBuf,
+    },
+}
+
+/// Loads the address book kept in the wallet dir.
+pub(crate) fn load_address_book(root_dir: &Path) -> Result<AddressBook> {
+    let wallet_dir = root_dir.join(WALLET_DIR_NAME);
+    fs::create_dir_all(&wallet_dir)?;
+    Ok(AddressBook::load_from(&wallet_dir)?)
+}
+
+pub(crate) fn address_book_cmds(cmds: &AddressBookCmds, root_dir: &Path) -> Result<()> {
+    let mut address_book = load_address_book(root_dir)?;
+    match cmds {
+        AddressBookCmds::Add { label, address } => {
+            let main_pubkey = MainPubkey::from_str(address)?;
+            address_book.add(label, main_pubkey)?;
+            println!("Added {label:?} for {}", main_pubkey.to_address());
+        }
+        AddressBookCmds::Remove { label } => {
+            let main_pubkey = address_book.remove(label)?;
+            println!("Removed {label:?}, was {}", main_pubkey.to_address());
+        }
+        AddressBookCmds::List => {
+            for (label, main_pubkey) in address_book.entries() {
+                println!("{label}\t{}", main_pubkey.to_address());
+            }
+        }
+        AddressBookCmds::Export { path } => {
+            address_book.export(path)?;
+            println!(
+                "Exported {} labels to {}",
+                address_book.entries().len(),
+                path.display()
+            );
+        }
+        AddressBookCmds::Import { path } => {
+            let imported = address_book.import(path)?;
+            println!("Imported {imported} new labels from {}", path.display());
+        }
+    }
+    Ok(())
+}
@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
+    address_book::{address_book_cmds, load_address_book, AddressBookCmds},
     audit::{audit, verify_spend_at},
     helpers::{get_faucet, receive},
     WalletApiHelper,
@@ -23,8 +24,7 @@ use color_eyre::{
 use dialoguer::Confirm;
 use sn_client::acc_packet::{load_or_create_mnemonic, secret_key_from_mnemonic};
 use sn_client::transfers::{
-    HotWallet, MainPubkey, MainSecretKey, NanoTokens, Transfer, TransferError, UnsignedTransfer,
-    WalletError,
+    HotWallet, MainSecretKey, NanoTokens, Transfer, TransferError, UnsignedTransfer, WalletError,
 };
 use sn_client::{
     acc_packet::load_account_wallet_or_create_with_mnemonic, Client, Error as ClientError,
@@ -87,10 +87,14 @@ pub enum WalletCmds {
         /// The number of SafeNetworkTokens to send.
         #[clap(name = "amount")]
         amount: String,
-        /// Hex-encoded public address of the recipient.
+        /// Address of the recipient, its hex-encoded public key,
+        /// or its label in the address book.
         #[clap(name = "to")]
         to: String,
     },
+    /// Manage the labels of the addresses you send to.
+    #[clap(subcommand)]
+    AddressBook(AddressBookCmds),
     /// Signs a transaction to be then broadcasted to the network.
     Sign {
         /// Hex-encoded unsigned transaction. It requires a hot-wallet was created for CLI.
@@ -149,8 +153,8 @@ pub(crate) async fn wallet_cmds_without_client(cmds: &WalletCmds, root_dir: &Pat
         WalletCmds::Address => {
             let wallet = WalletApiHelper::load_from(root_dir)?;
             match wallet {
-                WalletApiHelper::WatchOnlyWallet(w) => println!("{:?}", w.address()),
-                WalletApiHelper::HotWallet(w) => println!("{:?}", w.address()),
+                WalletApiHelper::WatchOnlyWallet(w) => println!("{}", w.address().to_address()),
+                WalletApiHelper::HotWallet(w) => println!("{}", w.address().to_address()),
             }
             Ok(())
         }
@@ -249,6 +253,7 @@ pub(crate) async fn wallet_cmds_without_client(cmds: &WalletCmds, root_dir: &Pat
             Ok(())
         }
         WalletCmds::Sign { tx, force } => sign_transaction(tx, root_dir, *force),
+        WalletCmds::AddressBook(cmds) => address_book_cmds(cmds, root_dir),
         WalletCmds::Status => {
             let mut wallet = WalletApiHelper::load_from(root_dir)?;
             println!("{}", wallet.balance());
@@ -327,7 +332,7 @@ async fn send(
             return Err(err.into());
         }
     };
-    let to = match MainPubkey::from_hex(to) {
+    let to = match load_address_book(root_dir)?.resolve(&to) {
         Ok(to) => to,
         Err(err) => {
             println!("Error while parsing the recipient's 'to' key: {err:?}");
@@ -6,7 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{audit::verify_spend_at, watch_only_wallet_from_pk, WalletApiHelper};
+use super::{
+    address_book::load_address_book, audit::verify_spend_at, watch_only_wallet_from_pk,
+    WalletApiHelper,
+};
 
 use bls::PublicKey;
 use clap::Parser;
@@ -75,7 +78,8 @@ pub enum WatchOnlyWalletCmds {
         /// The number of SafeNetworkTokens to transfer.
         #[clap(name = "amount")]
         amount: String,
-        /// Hex-encoded public address of the recipient.
+        /// Address of the recipient, its hex-encoded public key,
+        /// or its label in the address book.
         #[clap(name = "to")]
         to: String,
     },
@@ -224,7 +228,7 @@ fn build_unsigned_transaction(from: &str, amount: &str, to: &str, root_dir: &Pat
             return Err(err.into());
         }
     };
-    let to = match MainPubkey::from_hex(to) {
+    let to = match load_address_book(root_dir)?.resolve(to) {
         Ok(to) => to,
         Err(err) => {
             println!("Error while parsing the recipient's 'to' key: {err:?}");
@@ -11,12 +11,14 @@ mod download;
 mod estimate;
 mod files_uploader;
 mod upload;
+mod upload_index;
 
 pub use chunk_manager::ChunkManager;
 pub use download::{download_file, download_files};
 pub use estimate::Estimator;
 pub use files_uploader::{FilesUploadStatusNotifier, FilesUploadSummary, FilesUploader};
 pub use upload::{UploadedFile, UPLOADED_FILES};
+pub use upload_index::{IndexedFile, UploadIndex, UPLOAD_INDEX_FILE};
 
 use color_eyre::Result;
 use indicatif::{ProgressBar, ProgressStyle};
@@ -8,7 +8,7 @@
 
 use super::get_progress_bar;
 use crate::utils::duration_to_minute_seconds_string;
-use crate::ChunkManager;
+use crate::{ChunkManager, UploadIndex};
 use bytes::Bytes;
 use color_eyre::{eyre::eyre, Report, Result};
 use futures::StreamExt;
@@ -80,6 +80,7 @@ pub struct FilesUploader {
     status_notifier: Option<Box<dyn FilesUploadStatusNotifier>>,
     /// config
     make_data_public: bool,
+    index_uploads: bool,
     upload_cfg: UploadCfg,
 }
 
@@ -94,6 +95,7 @@ impl FilesUploader {
             entries_to_upload: Default::default(),
             status_notifier: Some(status_notifier),
             make_data_public: false,
+            index_uploads: false,
             upload_cfg: Default::default(),
         }
     }
@@ -108,6 +110,12 @@ impl FilesUploader {
         self
     }
 
+    /// Add the uploaded files to the `UploadIndex` of the root dir, to search them later on.
+    pub fn set_index_uploads(mut self, index_uploads: bool) -> Self {
+        self.index_uploads = index_uploads;
+        self
+    }
+
     /// Override the default status notifier. By default we print things to stdout.
     pub fn set_status_notifier(
         mut self,
@@ -146,7 +154,7 @@ impl FilesUploader {
         }
 
         let now = Instant::now();
-        let mut uploader = Uploader::new(self.client, self.root_dir);
+        let mut uploader = Uploader::new(self.client, self.root_dir.clone());
         uploader.set_upload_cfg(self.upload_cfg);
         uploader.insert_chunk_paths(chunks_to_upload);
 
@@ -190,6 +198,14 @@ impl FilesUploader {
                 })
                 .collect(),
         };
+        if self.index_uploads {
+            let mut index = UploadIndex::open(&self.root_dir)?;
+            for (path, file_name, head_address) in &summary.completed_files {
+                let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+                index.add_file(*head_address, &file_name.to_string_lossy(), Some(&path));
+            }
+            index.save()?;
+        }
         Ok(summary)
     }
 
@@ -437,6 +453,17 @@ impl FilesUploadStatusNotifier for StdOutPrinter {
             the leftover {} chunks in {elapsed}",
             upload_sum.skipped_count, upload_sum.uploaded_count,
         );
+        if let Some(report) = &upload_sum.verification {
+            println!(
+                "Verified {} uploaded chunks, {} were missing and uploaded again",
+                report.verified_count,
+                report.missing_chunks.len()
+            );
+            info!(
+                "Verified {} uploaded chunks, missing ones: {:?}",
+                report.verified_count, report.missing_chunks
+            );
+        }
         println!("**************************************");
         println!("*          Payment Details           *");
         println!("**************************************");
@@ -448,6 +475,12 @@ impl FilesUploadStatusNotifier for StdOutPrinter {
             "Made payment of {:?} for royalties fees",
             upload_sum.royalty_fees
         );
+        if !upload_sum.deduplication_savings.is_zero() {
+            println!(
+                "Saved {:?} on chunks already stored by the network",
+                upload_sum.deduplication_savings
+            );
+        }
         println!("New wallet balance: {}", upload_sum.final_balance);
     }
 }
@@ -0,0 +1,329 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::upload::{UploadedFile, UPLOADED_FILES};
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+use sn_client::protocol::storage::ChunkAddress;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+use tracing::{debug, warn};
+use walkdir::WalkDir;
+use xor_name::XorName;
+
+/// File storing the index of the uploaded files, in the root dir
+pub const UPLOAD_INDEX_FILE: &str = "upload_index";
+
+/// Weight of the terms of the file names and tags, which describe the file best.
+const NAME_WEIGHT: u32 = 3;
+/// Weight of the terms of the paths the files were uploaded from.
+const PATH_WEIGHT: u32 = 1;
+
+/// A file the user uploaded, as indexed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    /// The address the file can be downloaded from.
+    pub address: ChunkAddress,
+    pub file_name: String,
+    /// The path the file was uploaded from, if known.
+    pub path: Option<PathBuf>,
+    pub tags: BTreeSet<String>,
+}
+
+/// A local index of the files uploaded by the user, searchable by file name, path and tags,
+/// so they can be found again without remembering their addresses.
+///
+/// The files are persisted in the root dir, while the terms they are searched by are indexed
+/// in memory when the index is opened.
+pub struct UploadIndex {
+    index_path: PathBuf,
+    files: BTreeMap<XorName, IndexedFile>,
+    /// The files by term, with the weight of the term for each file.
+    terms: BTreeMap<String, BTreeMap<XorName, u32>>,
+}
+
+impl UploadIndex {
+    /// Open the index in the root dir, which is empty if no file was indexed yet.
+    pub fn open(root_dir: &Path) -> Result<Self> {
+        let index_path = root_dir.join(UPLOAD_INDEX_FILE);
+        let files: Vec<IndexedFile> = if index_path.exists() {
+            rmp_serde::from_slice(&std::fs::read(&index_path)?)?
+        } else {
+            vec![]
+        };
+        let mut index = Self {
+            index_path,
+            files: BTreeMap::new(),
+            terms: BTreeMap::new(),
+        };
+        for file in files {
+            index.insert(file);
+        }
+        Ok(index)
+    }
+
+    /// Index the files recorded as uploaded in the root dir, e.g. the ones uploaded before the
+    /// index was used. Returns the number of files newly indexed.
+    pub fn index_uploaded_files(&mut self, root_dir: &Path) -> Result<usize> {
+        let uploaded_files = root_dir.join(UPLOADED_FILES);
+        if !uploaded_files.exists() {
+            return Ok(0);
+        }
+        let mut indexed = 0;
+        for entry in WalkDir::new(&uploaded_files).into_iter().flatten() {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(xorname) = entry
+                .file_name()
+                .to_str()
+                .and_then(|hex| hex::decode(hex).ok())
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(XorName)
+            else {
+                warn!(
+                    "Skipping unexpected uploaded file record {:?}",
+                    entry.path()
+                );
+                continue;
+            };
+            if self.files.contains_key(&xorname) {
+                continue;
+            }
+            let uploaded = UploadedFile::read(entry.path())?;
+            self.add_file(
+                ChunkAddress::new(xorname),
+                &uploaded.filename.to_string_lossy(),
+                None,
+            );
+            indexed += 1;
+        }
+        Ok(indexed)
+    }
+
+    /// Index a file uploaded from the path, keeping its tags if it was indexed already.
+    pub fn add_file(&mut self, address: ChunkAddress, file_name: &str, path: Option<&Path>) {
+        let tags = self
+            .remove(address.xorname())
+            .map(|file| file.tags)
+            .unwrap_or_default();
+        self.insert(IndexedFile {
+            address,
+            file_name: file_name.to_string(),
+            path: path.map(Path::to_path_buf),
+            tags,
+        });
+    }
+
+    /// Tag an indexed file, e.g. with a topic or project, to find it by.
+    pub fn tag(
+        &mut self,
+        address: &ChunkAddress,
+        tags: impl IntoIterator<Item = String>,
+    ) -> Result<()> {
+        let mut file = self
+            .remove(address.xorname())
+            .ok_or_else(|| eyre!("No file at {} in the index", address.to_hex()))?;
+        file.tags
+            .extend(tags.into_iter().map(|tag| tag.trim().to_lowercase()));
+        file.tags.retain(|tag| !tag.is_empty());
+        self.insert(file);
+        Ok(())
+    }
+
+    /// Remove tags from an indexed file.
+    pub fn untag(&mut self, address: &ChunkAddress, tags: &[String]) -> Result<()> {
+        let mut file = self
+            .remove(address.xorname())
+            .ok_or_else(|| eyre!("No file at {} in the index", address.to_hex()))?;
+        for tag in tags {
+            let _ = file.tags.remove(&tag.trim().to_lowercase());
+        }
+        self.insert(file);
+        Ok(())
+    }
+
+    /// Return the indexed file at the address.
+    pub fn get(&self, address: &ChunkAddress) -> Option<&IndexedFile> {
+        self.files.get(address.xorname())
+    }
+
+    /// Return the number of indexed files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Return whether no file is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Return the files matching every word of the query, best matches first.
+    ///
+    /// A word matches the terms of the file names, tags and paths it is a prefix of, exact
+    /// matches and matches on names and tags ranking higher than others.
+    pub fn search(&self, query: &str) -> Vec<&IndexedFile> {
+        let words = tokenize(query);
+        if words.is_empty() {
+            return vec![];
+        }
+
+        let mut scores: Option<BTreeMap<XorName, u32>> = None;
+        for word in words {
+            let mut word_scores = BTreeMap::new();
+            for (term, files) in self
+                .terms
+                .range(word.clone()..)
+                .take_while(|(term, _)| term.starts_with(&word))
+            {
+                let exact = if *term == word { 2 } else { 1 };
+                for (xorname, weight) in files {
+                    let score: &mut u32 = word_scores.entry(*xorname).or_default();
+                    *score = (*score).max(weight * exact);
+                }
+            }
+            scores = Some(match scores {
+                None => word_scores,
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(xorname, score)| {
+                        word_scores
+                            .get(&xorname)
+                            .map(|word_score| (xorname, score + word_score))
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut matches: Vec<_> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(xorname, score)| self.files.get(&xorname).map(|file| (score, file)))
+            .collect();
+        matches.sort_by(|(score_a, file_a), (score_b, file_b)| {
+            score_b
+                .cmp(score_a)
+                .then_with(|| file_a.file_name.cmp(&file_b.file_name))
+        });
+        matches.into_iter().map(|(_, file)| file).collect()
+    }
+
+    /// Persist the index in the root dir.
+    pub fn save(&self) -> Result<()> {
+        let files: Vec<&IndexedFile> = self.files.values().collect();
+        std::fs::write(&self.index_path, rmp_serde::to_vec(&files)?)?;
+        debug!("Saved the index of {} uploaded files", files.len());
+        Ok(())
+    }
+
+    fn insert(&mut self, file: IndexedFile) {
+        let xorname = *file.address.xorname();
+        for (term, weight) in file_terms(&file) {
+            let file_weight = self
+                .terms
+                .entry(term)
+                .or_default()
+                .entry(xorname)
+                .or_default();
+            *file_weight = (*file_weight).max(weight);
+        }
+        let _ = self.files.insert(xorname, file);
+    }
+
+    fn remove(&mut self, xorname: &XorName) -> Option<IndexedFile> {
+        let file = self.files.remove(xorname)?;
+        for (term, _) in file_terms(&file) {
+            if let Some(files) = self.terms.get_mut(&term) {
+                let _ = files.remove(xorname);
+                if files.is_empty() {
+                    let _ = self.terms.remove(&term);
+                }
+            }
+        }
+        Some(file)
+    }
+}
+
+// The terms a file is searched by, along with their weight.
+fn file_terms(file: &IndexedFile) -> Vec<(String, u32)> {
+    let mut terms: Vec<_> = tokenize(&file.file_name)
+        .into_iter()
+        .chain(file.tags.iter().flat_map(|tag| tokenize(tag)))
+        .map(|term| (term, NAME_WEIGHT))
+        .collect();
+    if let Some(path) = &file.path {
+        terms.extend(
+            tokenize(&path.to_string_lossy())
+                .into_iter()
+                .map(|term| (term, PATH_WEIGHT)),
+        );
+    }
+    terms
+}
+
+// Split the text into lowercase alphanumeric terms.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn uploaded_files_are_searchable_across_restarts() -> Result<()> {
+        let root_dir = TempDir::new()?;
+        let mut rng = rand::thread_rng();
+        let report = ChunkAddress::new(XorName::random(&mut rng));
+        let holiday = ChunkAddress::new(XorName::random(&mut rng));
+        let reports_dir = Path::new("/home/me/Documents/reports/holiday.pdf");
+
+        let mut index = UploadIndex::open(root_dir.path())?;
+        index.add_file(report, "Report-2024.pdf", Some(reports_dir));
+        index.add_file(holiday, "holiday.jpg", None);
+        index.tag(&holiday, ["Beach".to_string()])?;
+        index.save()?;
+
+        let mut index = UploadIndex::open(root_dir.path())?;
+        assert_eq!(index.len(), 2);
+        let found = |index: &UploadIndex, query| -> Vec<ChunkAddress> {
+            index
+                .search(query)
+                .iter()
+                .map(|file| file.address)
+                .collect()
+        };
+        assert_eq!(found(&index, "report"), vec![report]);
+        assert_eq!(found(&index, "2024 PDF"), vec![report]);
+        assert_eq!(found(&index, "beach"), vec![holiday]);
+        // the file named holiday ranks above the one uploaded from a path mentioning it
+        assert_eq!(found(&index, "holi"), vec![holiday, report]);
+        assert!(found(&index, "report beach").is_empty());
+        assert!(found(&index, "").is_empty());
+
+        // re-indexing a file keeps its tags, untagging drops them
+        index.add_file(
+            holiday,
+            "holiday.jpg",
+            Some(Path::new("/photos/holiday.jpg")),
+        );
+        assert_eq!(found(&index, "beach photos"), vec![holiday]);
+        index.untag(&holiday, &["beach".to_string()])?;
+        assert!(found(&index, "beach").is_empty());
+        assert!(index
+            .tag(&ChunkAddress::new(XorName::random(&mut rng)), vec![])
+            .is_err());
+        Ok(())
+    }
+}
@@ -13,5 +13,6 @@ pub mod utils;
 pub use acc_packet::AccountPacket;
 pub use files::{
     download_file, download_files, ChunkManager, Estimator, FilesUploadStatusNotifier,
-    FilesUploadSummary, FilesUploader, UploadedFile, UPLOADED_FILES,
+    FilesUploadSummary, FilesUploader, IndexedFile, UploadIndex, UploadedFile, UPLOADED_FILES,
+    UPLOAD_INDEX_FILE,
 };
@@ -7,20 +7,30 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
+    bandwidth::Direction,
     error::{Error, Result},
-    Client, ClientEvent, ClientEventsBroadcaster, ClientEventsReceiver, ClientRegister,
-    WalletClient,
+    retry::Retries,
+    BandwidthLimits, ChunkCache, Client, ClientEvent, ClientEventsBroadcaster,
+    ClientEventsReceiver, ClientRegister, ProgressEvent, QuorumRead, ReadQuorum, RetryObserver,
+    RetryOperation, RetryPolicy, TimeoutOperation, WalletClient,
 };
+#[cfg(feature = "open-metrics")]
+use crate::{metrics::RequestKind, ClientMetrics};
 use bls::{PublicKey, SecretKey, Signature};
+use bytes::Bytes;
 use libp2p::{
     identity::Keypair,
     kad::{Quorum, Record},
     Multiaddr, PeerId,
 };
+#[cfg(feature = "open-metrics")]
+use prometheus_client::{metrics::info::Info, registry::Registry};
 use rand::{thread_rng, Rng};
+#[cfg(feature = "open-metrics")]
+use sn_networking::run_metrics_server;
 use sn_networking::{
     get_signed_spend_from_record, multiaddr_is_global,
-    target_arch::{interval, spawn, timeout, Instant},
+    target_arch::{interval, sleep, spawn, timeout, Instant},
     GetRecordCfg, GetRecordError, NetworkBuilder, NetworkError, NetworkEvent, PutRecordCfg,
     VerificationKind,
 };
@@ -117,6 +127,12 @@ impl Client {
             network: network.clone(),
             events_broadcaster,
             signer: Arc::new(signer),
+            chunk_cache: None,
+            retry_policies: Default::default(),
+            timeouts: Default::default(),
+            bandwidth: Default::default(),
+            #[cfg(feature = "open-metrics")]
+            metrics: Default::default(),
         };
 
         // subscribe to our events channel first, so we don't have intermittent
@@ -267,6 +283,10 @@ impl Client {
                         their_protocol,
                     });
             }
+            NetworkEvent::PubSubMessage { topic, msg } => {
+                self.events_broadcaster
+                    .broadcast(ClientEvent::PubSubMessage { topic, msg });
+            }
             _other => {}
         }
 
@@ -299,6 +319,30 @@ impl Client {
         self.events_broadcaster.subscribe()
     }
 
+    /// Subscribe to the pub/sub topic, the messages published on it being broadcasted as
+    /// `ClientEvent::PubSubMessage`s on the events channel.
+    pub async fn subscribe_to_topic(&self, topic: String) -> Result<()> {
+        info!("Subscribing to pub/sub topic {topic:?}");
+        self.network.subscribe_to_topic(topic).await?;
+        Ok(())
+    }
+
+    /// Unsubscribe from the pub/sub topic.
+    pub async fn unsubscribe_from_topic(&self, topic: String) -> Result<()> {
+        info!("Unsubscribing from pub/sub topic {topic:?}");
+        self.network.unsubscribe_from_topic(topic).await?;
+        Ok(())
+    }
+
+    /// Publish a lightweight message on the pub/sub topic, to the peers subscribed to it, e.g. a
+    /// presence or a cache invalidation. It's not stored, so only the peers subscribed at the time
+    /// receive it. Errors out if it's over the size or the rate limit of the topic.
+    pub async fn publish_on_topic(&self, topic: String, msg: Bytes) -> Result<()> {
+        info!("Publishing {} bytes on pub/sub topic {topic:?}", msg.len());
+        self.network.publish_on_topic(topic, msg).await?;
+        Ok(())
+    }
+
     /// Sign the given data.
     ///
     /// # Arguments
@@ -405,6 +449,103 @@ impl Client {
         self.signer = Arc::new(sk);
     }
 
+    /// Sets the on-disk cache used to serve the chunks that were already fetched, or removes it with `None`.
+    /// Every chunk fetched from the network is then added to the cache.
+    ///
+    /// By default, no cache is used.
+    pub fn set_chunk_cache(&mut self, chunk_cache: Option<ChunkCache>) {
+        self.chunk_cache = chunk_cache;
+    }
+
+    /// Sets how the given operation is retried when it fails, replacing its default policy.
+    pub fn set_retry_policy(&mut self, operation: RetryOperation, policy: RetryPolicy) {
+        self.retry_policies.set_policy(operation, policy);
+    }
+
+    /// Returns the policy used to retry the given operation.
+    pub fn retry_policy(&self, operation: RetryOperation) -> RetryPolicy {
+        self.retry_policies.policy(operation)
+    }
+
+    /// Sets the function called before every retry of any operation, or removes it with `None`.
+    pub fn set_retry_observer(&mut self, observer: Option<RetryObserver>) {
+        self.retry_policies.set_observer(observer);
+    }
+
+    /// Sets how long the given operation may take, its retries included, before it's given up on
+    /// with `Error::OperationTimeout`, replacing its default timeout.
+    pub fn set_operation_timeout(&mut self, operation: TimeoutOperation, duration: Duration) {
+        self.timeouts.set_timeout(operation, duration);
+    }
+
+    /// Returns how long the given operation may take.
+    pub fn operation_timeout(&self, operation: TimeoutOperation) -> Duration {
+        self.timeouts.timeout(operation)
+    }
+
+    /// Sets the bandwidth the client may use, shared by all its clones and concurrent requests.
+    /// It can be changed at any time, e.g. to free the connection while a background sync runs.
+    ///
+    /// By default, the bandwidth is not limited.
+    pub fn set_bandwidth_limits(&self, limits: BandwidthLimits) {
+        info!("Setting the bandwidth limits to {limits:?}");
+        self.bandwidth.set_limits(limits);
+    }
+
+    /// Returns the bandwidth the client may use.
+    pub fn bandwidth_limits(&self) -> BandwidthLimits {
+        self.bandwidth.limits()
+    }
+
+    pub(crate) async fn throttle(&self, direction: Direction, size: usize) {
+        self.bandwidth.acquire(direction, size).await;
+    }
+
+    /// The metrics recorded by the client, to be registered in the registry of the application.
+    #[cfg(feature = "open-metrics")]
+    pub fn metrics(&self) -> &ClientMetrics {
+        &self.metrics
+    }
+
+    /// Serves the metrics of the client at `http://localhost:<port>/metrics`, in the OpenMetrics
+    /// format, for a Prometheus server to scrape.
+    #[cfg(feature = "open-metrics")]
+    pub fn serve_metrics(&self, port: u16) {
+        let mut metrics_registry = Registry::default();
+        self.metrics.register(&mut metrics_registry);
+
+        let mut metadata_registry = Registry::default();
+        metadata_registry
+            .sub_registry_with_prefix("sn_client")
+            .register(
+                "sn_client_version",
+                "The version of the safe client",
+                Info::new(vec![(
+                    "sn_client_version".to_string(),
+                    env!("CARGO_PKG_VERSION").to_string(),
+                )]),
+            );
+        run_metrics_server(metrics_registry, metadata_registry, port);
+    }
+
+    pub(crate) fn start_retries(&self, operation: RetryOperation) -> Retries {
+        let retries = self
+            .retry_policies
+            .start(operation)
+            .with_events_broadcaster(self.events_broadcaster.clone());
+        #[cfg(feature = "open-metrics")]
+        let retries = retries.with_metrics(self.metrics.clone());
+        retries
+    }
+
+    // Broadcasts the progress made storing data, for the listeners of the client events.
+    pub(crate) fn report_progress(&self, event: ProgressEvent) {
+        #[cfg(feature = "open-metrics")]
+        self.metrics.record_progress(&event);
+        self.events_broadcaster
+            .broadcast(ClientEvent::Progress(event));
+    }
+
     /// Get a register from network
     ///
     /// # Arguments
@@ -440,29 +581,70 @@ impl Client {
         address: RegisterAddress,
         is_verifying: bool,
     ) -> Result<SignedRegister> {
-        let key = NetworkAddress::from_register_address(address).to_record_key();
-        let get_quorum = if is_verifying {
-            Quorum::Majority
+        let (quorum, retry_strategy) = if is_verifying {
+            (ReadQuorum::Majority, RetryStrategy::Balanced)
         } else {
-            Quorum::One
-        };
-        let retry_strategy = if is_verifying {
-            Some(RetryStrategy::Balanced)
-        } else {
-            Some(RetryStrategy::Quick)
+            (ReadQuorum::First, RetryStrategy::Quick)
         };
+        let register = self
+            .fetch_signed_register(address, quorum, retry_strategy)
+            .await?;
+        Ok(register.value)
+    }
+
+    /// Get a `SignedRegister`, requiring the given number of its holders to return the same copy.
+    ///
+    /// Diverging copies are merged with `ReadQuorum::First` and `ReadQuorum::Majority`, but make
+    /// the read fail with `ReadQuorum::All`.
+    pub async fn get_signed_register_with_quorum(
+        &self,
+        address: RegisterAddress,
+        quorum: ReadQuorum,
+    ) -> Result<QuorumRead<SignedRegister>> {
+        self.fetch_signed_register(address, quorum, RetryStrategy::Balanced)
+            .await
+    }
+
+    async fn fetch_signed_register(
+        &self,
+        address: RegisterAddress,
+        quorum: ReadQuorum,
+        retry_strategy: RetryStrategy,
+    ) -> Result<QuorumRead<SignedRegister>> {
+        let key = NetworkAddress::from_register_address(address).to_record_key();
         let get_cfg = GetRecordCfg {
-            get_quorum,
-            retry_strategy,
+            get_quorum: quorum.kad_quorum(),
+            retry_strategy: Some(retry_strategy),
             target_record: None,
             expected_holders: Default::default(),
         };
 
-        let maybe_record = self.network.get_record_from_network(key, &get_cfg).await;
+        #[cfg(feature = "open-metrics")]
+        let started = Instant::now();
+        let maybe_record = self
+            .timeouts
+            .run(
+                TimeoutOperation::Register,
+                self.network.get_record_from_network(key, &get_cfg),
+            )
+            .await?;
+        #[cfg(feature = "open-metrics")]
+        self.metrics
+            .record_request(RequestKind::GetRegister, started.elapsed());
         let record = match &maybe_record {
-            Ok(r) => r,
-            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { result_map })) => {
+            Ok(r) => {
+                self.throttle(Direction::Download, r.value.len()).await;
+                r
+            }
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { result_map }))
+                if quorum != ReadQuorum::All =>
+            {
                 return merge_split_register_records(address, result_map)
+                    .map(|register| QuorumRead::new(register, quorum))
+            }
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { .. })) => {
+                warn!("The holders of {address:?} returned diverging copies");
+                return Err(Error::ReadQuorumNotMet(quorum));
             }
             Err(e) => {
                 warn!("Failed to get record at {address:?} from the network: {e:?}");
@@ -477,7 +659,7 @@ impl Client {
 
         let register = get_register_from_record(record)
             .map_err(|_| ProtocolError::RegisterNotFound(Box::new(address)))?;
-        Ok(register)
+        Ok(QuorumRead::new(register, quorum))
     }
 
     /// Retrieve a Register from the network.
@@ -582,10 +764,17 @@ impl Client {
         let reg_address = reg.address();
         if verify_store {
             debug!("We should verify stored at {address:?}");
-            let mut stored = self.verify_register_stored(*reg_address).await.is_ok();
-
-            while !stored {
-                info!("Register not completely stored on the network yet. Retrying...");
+            let mut retries = self.start_retries(RetryOperation::RegisterStore);
+            let mut verification = self.verify_register_stored(*reg_address).await;
+
+            while let Err(err) = verification {
+                let Some(delay) = retries.on_failure(&err, true) else {
+                    return Err(err);
+                };
+                info!(
+                    "Register not completely stored on the network yet. Retrying in {delay:?}..."
+                );
+                sleep(delay).await;
                 // this verify store call here ensures we get the record from Quorum::all
                 let (reg, top_up_cost, royalties_top_up) = ClientRegister::create_online(
                     self.clone(),
@@ -605,7 +794,7 @@ impl Client {
                     .ok_or(Error::Wallet(sn_transfers::WalletError::from(
                         sn_transfers::TransferError::ExcessiveNanoValue,
                     )))?;
-                stored = self.verify_register_stored(*reg_address).await.is_ok();
+                verification = self.verify_register_stored(*reg_address).await;
             }
         }
 
@@ -674,7 +863,19 @@ impl Client {
             use_put_record_to: Some(vec![payee]),
             verification,
         };
-        Ok(self.network.put_record(record, &put_cfg).await?)
+        self.throttle(Direction::Upload, record.value.len()).await;
+        #[cfg(feature = "open-metrics")]
+        let started = Instant::now();
+        self.timeouts
+            .run(
+                TimeoutOperation::ChunkPut,
+                self.network.put_record(record, &put_cfg),
+            )
+            .await??;
+        #[cfg(feature = "open-metrics")]
+        self.metrics
+            .record_request(RequestKind::PutChunk, started.elapsed());
+        Ok(())
     }
 
     /// Get chunk from chunk address.
@@ -713,7 +914,42 @@ impl Client {
         show_holders: bool,
         retry_strategy: Option<RetryStrategy>,
     ) -> Result<Chunk> {
-        info!("Getting chunk: {address:?}");
+        let chunk = self
+            .fetch_chunk(address, show_holders, retry_strategy, ReadQuorum::First)
+            .await?;
+        Ok(chunk.value)
+    }
+
+    /// Get a chunk, requiring the given number of its holders to return it.
+    /// The chunk cache is only used with `ReadQuorum::First`.
+    pub async fn get_chunk_with_quorum(
+        &self,
+        address: ChunkAddress,
+        quorum: ReadQuorum,
+        retry_strategy: Option<RetryStrategy>,
+    ) -> Result<QuorumRead<Chunk>> {
+        self.fetch_chunk(address, false, retry_strategy, quorum)
+            .await
+    }
+
+    async fn fetch_chunk(
+        &self,
+        address: ChunkAddress,
+        show_holders: bool,
+        retry_strategy: Option<RetryStrategy>,
+        quorum: ReadQuorum,
+    ) -> Result<QuorumRead<Chunk>> {
+        info!("Getting chunk: {address:?} with quorum {quorum}");
+        // the holders can only be shown by fetching the chunk from the network.
+        if let Some(chunk) = self
+            .chunk_cache
+            .as_ref()
+            .filter(|_| !show_holders && quorum == ReadQuorum::First)
+            .and_then(|cache| cache.get(&address))
+        {
+            debug!("Chunk {address:?} served from the cache");
+            return Ok(QuorumRead::new(chunk, quorum));
+        }
         let key = NetworkAddress::from_chunk_address(address).to_record_key();
 
         let expected_holders = if show_holders {
@@ -730,16 +966,33 @@ impl Client {
         };
 
         let get_cfg = GetRecordCfg {
-            get_quorum: Quorum::One,
+            get_quorum: quorum.kad_quorum(),
             retry_strategy: Some(retry_strategy.unwrap_or(RetryStrategy::Quick)),
             target_record: None,
             expected_holders,
         };
-        let record = self.network.get_record_from_network(key, &get_cfg).await?;
+        #[cfg(feature = "open-metrics")]
+        let started = Instant::now();
+        let record = self
+            .timeouts
+            .run(
+                TimeoutOperation::ChunkGet,
+                self.network.get_record_from_network(key, &get_cfg),
+            )
+            .await??;
+        #[cfg(feature = "open-metrics")]
+        self.metrics
+            .record_request(RequestKind::GetChunk, started.elapsed());
+        self.throttle(Direction::Download, record.value.len()).await;
         let header = RecordHeader::from_record(&record)?;
         if let RecordKind::Chunk = header.kind {
-            let chunk: Chunk = try_deserialize_record(&record)?;
-            Ok(chunk)
+            let chunk = verify_chunk(address, try_deserialize_record(&record)?, record.publisher)?;
+            if let Some(cache) = &self.chunk_cache {
+                if let Err(err) = cache.insert(&chunk) {
+                    warn!("Could not cache chunk {address:?}: {err:?}");
+                }
+            }
+            Ok(QuorumRead::new(chunk, quorum))
         } else {
             Err(NetworkError::RecordKindMismatch(RecordKind::Chunk).into())
         }
@@ -772,6 +1025,21 @@ impl Client {
         Ok(())
     }
 
+    /// Returns how many of the nodes closest to a `Chunk` prove they hold its exact content.
+    pub async fn count_chunk_holders(&self, chunk: &Chunk) -> Result<usize> {
+        let address = chunk.network_address();
+        let random_nonce = thread_rng().gen::<u64>();
+        let record_value = try_serialize_record(&chunk, RecordKind::Chunk)?;
+        let expected_proof = ChunkProof::new(record_value.as_ref(), random_nonce);
+
+        let holders = self
+            .network
+            .count_chunk_holders(address.clone(), random_nonce, expected_proof)
+            .await?;
+        debug!("Chunk {address:?} is held by {holders} of its closest nodes");
+        Ok(holders)
+    }
+
     /// Verify if a `Register` is stored by expected nodes on the network.
     ///
     /// # Arguments
@@ -857,7 +1125,11 @@ impl Client {
 
         let key = network_address.to_record_key();
         let pretty_key = PrettyPrintRecordKey::from(&key);
-        trace!("Sending spend {unique_pubkey:?} to the network via put_record, with addr of {cash_note_addr:?} - {pretty_key:?}");
+        // retries send the exact same spend, with the same submission token. The token is only
+        // logged here to trace the retries, the nodes deriving it from the spends they receive to
+        // acknowledge the ones they already hold without validating them again.
+        let submission_token = spend.submission_token();
+        trace!("Sending spend {unique_pubkey:?} ({submission_token}) to the network via put_record, with addr of {cash_note_addr:?} - {pretty_key:?}");
         let record_kind = RecordKind::Spend;
         let record = Record {
             key,
@@ -892,7 +1164,13 @@ impl Client {
             use_put_record_to: None,
             verification: Some((VerificationKind::Network, verification_cfg)),
         };
-        Ok(self.network.put_record(record, &put_cfg).await?)
+        #[cfg(feature = "open-metrics")]
+        let started = Instant::now();
+        self.network.put_record(record, &put_cfg).await?;
+        #[cfg(feature = "open-metrics")]
+        self.metrics
+            .record_request(RequestKind::PutSpend, started.elapsed());
+        Ok(())
     }
 
     /// Get a spend from network.
@@ -937,6 +1215,26 @@ impl Client {
         .await
     }
 
+    /// Get a spend, requiring the given number of its holders to return the same copy.
+    pub async fn get_spend_with_quorum(
+        &self,
+        address: SpendAddress,
+        quorum: ReadQuorum,
+    ) -> Result<QuorumRead<SignedSpend>> {
+        let spend = self
+            .try_fetch_spend_from_network(
+                address,
+                GetRecordCfg {
+                    get_quorum: quorum.kad_quorum(),
+                    retry_strategy: Some(RetryStrategy::Balanced),
+                    target_record: None,
+                    expected_holders: Default::default(),
+                },
+            )
+            .await?;
+        Ok(QuorumRead::new(spend, quorum))
+    }
+
     /// Try to peek a spend by just fetching one copy of it.
     /// Useful to help decide whether a re-put is necessary, or a spend exists already
     /// (client side verification).
@@ -987,9 +1285,12 @@ impl Client {
             PrettyPrintRecordKey::from(&key)
         );
         let record = self
-            .network
-            .get_record_from_network(key.clone(), &get_cfg)
-            .await?;
+            .timeouts
+            .run(
+                TimeoutOperation::SpendQuery,
+                self.network.get_record_from_network(key.clone(), &get_cfg),
+            )
+            .await??;
         info!(
             "For spend at {address:?} got record from the network, {:?}",
             PrettyPrintRecordKey::from(&record.key)
@@ -1121,6 +1422,15 @@ fn merge_split_register_records(
     Ok(register)
 }
 
+// The address of a chunk is the hash of its content, so a holder can't serve anything else under it.
+fn verify_chunk(address: ChunkAddress, chunk: Chunk, holder: Option<PeerId>) -> Result<Chunk> {
+    if chunk.address != address {
+        error!("Chunk {address:?} served by {holder:?} is corrupt");
+        return Err(Error::CorruptChunk { address, holder });
+    }
+    Ok(chunk)
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
@@ -1213,4 +1523,27 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn chunks_not_matching_their_address_are_rejected() -> eyre::Result<()> {
+        let chunk = Chunk::new(bytes::Bytes::from_static(b"chunk content"));
+        let address = *chunk.address();
+        assert!(verify_chunk(address, chunk.clone(), None).is_ok());
+
+        let holder = PeerId::random();
+        let tampered = Chunk {
+            address,
+            value: bytes::Bytes::from_static(b"tampered content"),
+        };
+        // deserialising recomputes the address from the content, as fetching a chunk does.
+        let tampered: Chunk = rmp_serde::from_slice(&rmp_serde::to_vec(&tampered)?)?;
+        match verify_chunk(address, tampered, Some(holder)) {
+            Err(Error::CorruptChunk {
+                address: corrupt,
+                holder: Some(peer),
+            }) => assert_eq!((corrupt, peer), (address, holder)),
+            res => panic!("Unexpected result {res:?}"),
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,383 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, Client, ClientRegister, Error, WalletClient};
+use bytes::Bytes;
+use self_encryption::MAX_CHUNK_SIZE;
+use serde::{Deserialize, Serialize};
+use sn_protocol::{
+    storage::{Chunk, ChunkAddress},
+    NetworkAddress,
+};
+use sn_registers::{ConflictPolicy, Entry, RegisterAddress};
+use sn_transfers::NanoTokens;
+use std::{collections::BTreeMap, sync::Arc};
+use xor_name::XorName;
+
+/// Size of the entries a segment holds at most, keeping room in its Chunk for the framing.
+const MAX_SEGMENT_SIZE: usize = MAX_CHUNK_SIZE - 1024;
+/// Framing of each entry in a segment, at most.
+const ENTRY_OVERHEAD: usize = 5;
+/// How many heads the Register of a log holds before it is compacted on `sync`.
+const HEADS_BEFORE_COMPACTION: u64 = 64;
+
+/// A segment of a log, stored as a Chunk linking to the segment before it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct LogSegment {
+    /// The sequence number of the first entry of the segment.
+    first_seq: u64,
+    entries: Vec<Bytes>,
+    /// The address of the Chunk of the previous segment, if any.
+    previous: Option<XorName>,
+}
+
+/// The head of a log, written to its Register whenever a segment is sealed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct LogHead {
+    /// The address of the Chunk of the latest segment.
+    segment: XorName,
+    /// The sequence number of the entry appended after the ones of the latest segment.
+    next_seq: u64,
+}
+
+/// An append-only log, for high-volume streams of entries like events or telemetry.
+///
+/// The entries are numbered in the order they're appended, and batched into segments stored
+/// as Chunks, each linking to the one before. The log's Register only holds the head, i.e. the
+/// address of the latest segment, and is compacted as it grows: appending costs a Chunk per
+/// segment rather than a Register entry per entry, and the latest entries are read from the
+/// head without walking the whole log.
+///
+/// The entries appended are buffered locally till the segment they're in is sealed,
+/// which `sync` does, uploading the segments sealed since the last sync.
+#[derive(Clone)]
+pub struct AppendLog {
+    register: ClientRegister,
+    head: Option<LogHead>,
+    /// The entries appended since the latest segment was sealed.
+    pending: Vec<Bytes>,
+    pending_size: usize,
+    /// The segments sealed but not uploaded yet.
+    sealed: BTreeMap<XorName, Chunk>,
+}
+
+impl AppendLog {
+    /// Create a new log, stored on the network on `sync`.
+    pub fn create(client: Client, meta: XorName) -> Self {
+        Self {
+            register: ClientRegister::create(client, meta),
+            head: None,
+            pending: vec![],
+            pending_size: 0,
+            sealed: BTreeMap::new(),
+        }
+    }
+
+    /// Retrieve the log at the address from the network, to read it or append to it.
+    pub async fn open(client: Client, address: RegisterAddress) -> Result<Self> {
+        let register = ClientRegister::retrieve(client, address).await?;
+        let head = latest_head(&register)?;
+        Ok(Self {
+            register,
+            head,
+            pending: vec![],
+            pending_size: 0,
+            sealed: BTreeMap::new(),
+        })
+    }
+
+    /// Return the address of the log (its Register address) on the network.
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Return the number of entries appended to the log, i.e. the sequence number of the next one.
+    pub fn len(&self) -> u64 {
+        self.head.map_or(0, |head| head.next_seq) + self.pending.len() as u64
+    }
+
+    /// Return whether no entry was appended to the log.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append an entry to the log, returning its sequence number.
+    /// The entry is uploaded on the next `sync`.
+    pub fn append(&mut self, entry: impl Into<Bytes>) -> Result<u64> {
+        let entry = entry.into();
+        let size = entry.len() + ENTRY_OVERHEAD;
+        if size > MAX_SEGMENT_SIZE {
+            return Err(Error::LogEntryTooBig(entry.len(), MAX_SEGMENT_SIZE));
+        }
+        if self.pending_size + size > MAX_SEGMENT_SIZE {
+            self.seal()?;
+        }
+
+        let seq = self.len();
+        self.pending.push(entry);
+        self.pending_size += size;
+        Ok(seq)
+    }
+
+    /// Return the entry with the sequence number.
+    pub async fn get(&self, seq: u64) -> Result<Bytes> {
+        let next_seq = self.head.map_or(0, |head| head.next_seq);
+        if seq >= next_seq {
+            return usize::try_from(seq - next_seq)
+                .ok()
+                .and_then(|index| self.pending.get(index).cloned())
+                .ok_or(Error::NoSuchLogEntry(seq));
+        }
+
+        let mut next = self.head.map(|head| head.segment);
+        while let Some(name) = next {
+            let segment = self.segment(name).await?;
+            if seq >= segment.first_seq {
+                return usize::try_from(seq - segment.first_seq)
+                    .ok()
+                    .and_then(|index| segment.entries.get(index).cloned())
+                    .ok_or(Error::InvalidLogSegment(name));
+            }
+            next = segment.previous;
+        }
+        Err(Error::NoSuchLogEntry(seq))
+    }
+
+    /// Return the last `count` entries of the log along with their sequence numbers,
+    /// oldest first, only fetching the latest segments.
+    pub async fn tail(&self, count: usize) -> Result<Vec<(u64, Bytes)>> {
+        let next_seq = self.head.map_or(0, |head| head.next_seq);
+        let mut tail: Vec<(u64, Bytes)> = numbered(next_seq, self.pending.clone())
+            .rev()
+            .take(count)
+            .collect();
+
+        let mut next = self.head.map(|head| head.segment);
+        while let Some(name) = next.filter(|_| tail.len() < count) {
+            let segment = self.segment(name).await?;
+            let missing = count - tail.len();
+            tail.extend(
+                numbered(segment.first_seq, segment.entries)
+                    .rev()
+                    .take(missing),
+            );
+            next = segment.previous;
+        }
+        tail.reverse();
+        Ok(tail)
+    }
+
+    /// Seal the entries appended into a segment, upload the segments sealed since the last sync,
+    /// and sync the log's Register with its replicas on the network.
+    ///
+    /// Return the storage cost and the royalties paid, for the segments and the Register.
+    pub async fn sync(
+        &mut self,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(NanoTokens, NanoTokens)> {
+        self.seal()?;
+        let (segments_cost, segments_royalties) =
+            self.upload_segments(wallet_client, verify_store).await?;
+
+        if self.register.size() > HEADS_BEFORE_COMPACTION {
+            if let Some((hash, _)) = self.register.resolve(&head_policy()) {
+                self.register.compact(hash)?;
+            }
+        }
+        let (register_cost, register_royalties) = self
+            .register
+            .sync(wallet_client, verify_store, None)
+            .await?;
+        self.head = latest_head(&self.register)?;
+
+        Ok((
+            segments_cost
+                .checked_add(register_cost)
+                .unwrap_or(segments_cost),
+            segments_royalties
+                .checked_add(register_royalties)
+                .unwrap_or(segments_royalties),
+        ))
+    }
+
+    // Seal the pending entries into a segment, and write its address to the Register.
+    fn seal(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let entries = std::mem::take(&mut self.pending);
+        self.pending_size = 0;
+        let (head, chunk) = seal_segment(self.head, entries)?;
+        let _ = self.register.write_merging_branches(&encode_head(&head)?)?;
+        let _ = self.sealed.insert(head.segment, chunk);
+        self.head = Some(head);
+        Ok(())
+    }
+
+    // Pay for and upload the segments sealed since the last sync.
+    async fn upload_segments(
+        &mut self,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(NanoTokens, NanoTokens)> {
+        if self.sealed.is_empty() {
+            return Ok((NanoTokens::zero(), NanoTokens::zero()));
+        }
+
+        debug!(
+            "Uploading {} segments of the log at {:?}",
+            self.sealed.len(),
+            self.address()
+        );
+        let payment_result = wallet_client
+            .pay_for_storage(
+                self.sealed
+                    .keys()
+                    .map(|name| NetworkAddress::ChunkAddress(ChunkAddress::new(*name))),
+            )
+            .await?;
+        wallet_client.store_local_wallet()?;
+
+        let client = self.register.client().clone();
+        while let Some((name, chunk)) = self.sealed.pop_first() {
+            let chunk_addr = chunk.network_address();
+            let result = match wallet_client.get_recent_payment_for_addr(&chunk_addr) {
+                Ok((payment, payee)) => {
+                    client
+                        .store_chunk(chunk.clone(), payee, payment, verify_store, None)
+                        .await
+                }
+                Err(err) => Err(err.into()),
+            };
+            if let Err(err) = result {
+                warn!("Failed to upload the log segment {name:?}: {err:?}");
+                // We keep the segment for next sync to retry
+                let _ = self.sealed.insert(name, chunk);
+                return Err(err);
+            }
+            wallet_client.remove_payment_for_addr(&chunk_addr)?;
+        }
+
+        Ok((payment_result.storage_cost, payment_result.royalty_fees))
+    }
+
+    // Return the segment at the address, from the ones not uploaded yet or from the network.
+    async fn segment(&self, name: XorName) -> Result<LogSegment> {
+        let chunk = match self.sealed.get(&name) {
+            Some(chunk) => chunk.clone(),
+            None => {
+                self.register
+                    .client()
+                    .get_chunk(ChunkAddress::new(name), false, None)
+                    .await?
+            }
+        };
+        decode_segment(name, &chunk)
+    }
+}
+
+// Number the entries from the sequence number of the first one.
+fn numbered(first_seq: u64, entries: Vec<Bytes>) -> impl DoubleEndedIterator<Item = (u64, Bytes)> {
+    entries
+        .into_iter()
+        .enumerate()
+        .map(move |(index, entry)| (first_seq + index as u64, entry))
+}
+
+// The heads written concurrently are resolved to the one of the longest log.
+fn head_policy() -> ConflictPolicy {
+    ConflictPolicy::LastWriterWins(Arc::new(|entry: &Entry| {
+        decode_head(entry).map(|head| head.next_seq)
+    }))
+}
+
+// The head of the log, as written to its Register.
+fn latest_head(register: &ClientRegister) -> Result<Option<LogHead>> {
+    match register.resolve(&head_policy()) {
+        Some((hash, entry)) => decode_head(&entry)
+            .map(Some)
+            .ok_or(Error::InvalidLogHead(hash)),
+        None => Ok(None),
+    }
+}
+
+// Seal the entries into a segment following the head, returning the new head and the Chunk.
+fn seal_segment(head: Option<LogHead>, entries: Vec<Bytes>) -> Result<(LogHead, Chunk)> {
+    let first_seq = head.map_or(0, |head| head.next_seq);
+    let next_seq = first_seq + entries.len() as u64;
+    let segment = LogSegment {
+        first_seq,
+        entries,
+        previous: head.map(|head| head.segment),
+    };
+    let chunk = Chunk::new(Bytes::from(rmp_serde::to_vec(&segment)?));
+    let head = LogHead {
+        segment: *chunk.name(),
+        next_seq,
+    };
+    Ok((head, chunk))
+}
+
+fn decode_segment(name: XorName, chunk: &Chunk) -> Result<LogSegment> {
+    rmp_serde::from_slice(chunk.value()).map_err(|_| Error::InvalidLogSegment(name))
+}
+
+fn encode_head(head: &LogHead) -> Result<Vec<u8>> {
+    Ok(rmp_serde::to_vec(head)?)
+}
+
+fn decode_head(entry: &[u8]) -> Option<LogHead> {
+    rmp_serde::from_slice(entry).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_are_numbered_and_linked() -> Result<()> {
+        let (head, first) = seal_segment(None, vec![Bytes::from("a"), Bytes::from("b")])?;
+        assert_eq!(head.next_seq, 2);
+        assert_eq!(head.segment, *first.name());
+
+        let (head, second) = seal_segment(Some(head), vec![Bytes::from("c")])?;
+        assert_eq!(head.next_seq, 3);
+        let segment = decode_segment(head.segment, &second)?;
+        assert_eq!(segment.first_seq, 2);
+        assert_eq!(segment.entries, vec![Bytes::from("c")]);
+        assert_eq!(segment.previous, Some(*first.name()));
+
+        assert_eq!(decode_head(&encode_head(&head)?), Some(head));
+        assert!(matches!(
+            decode_segment(head.segment, &Chunk::new(Bytes::from("garbage"))),
+            Err(Error::InvalidLogSegment(name)) if name == head.segment
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_heads_resolve_to_the_longest_log() {
+        let shorter = encode_head(&LogHead {
+            segment: XorName::default(),
+            next_seq: 10,
+        });
+        let longer = encode_head(&LogHead {
+            segment: XorName::default(),
+            next_seq: 20,
+        });
+        let (Ok(shorter), Ok(longer)) = (shorter, longer) else {
+            panic!("heads should serialise");
+        };
+        let ConflictPolicy::LastWriterWins(timestamp) = head_policy() else {
+            panic!("heads are resolved by sequence number");
+        };
+        assert!(timestamp(&longer) > timestamp(&shorter));
+        assert_eq!(timestamp(&b"not a head".to_vec()), None);
+    }
+}
@@ -12,7 +12,7 @@ use futures::{future::join_all, StreamExt};
 use sn_networking::{GetRecordError, NetworkError};
 use sn_transfers::{
     NanoTokens, SignedSpend, SpendAddress, SpendReason, WalletError, WalletResult,
-    DEFAULT_NETWORK_ROYALTIES_PK, GENESIS_SPEND_UNIQUE_KEY, NETWORK_ROYALTIES_PK,
+    DEFAULT_NETWORK_ROYALTIES_PK, GENESIS_SPEND_UNIQUE_KEY, NETWORK_ROYALTIES_SPLIT,
 };
 use std::{
     collections::{BTreeMap, BTreeSet},
@@ -539,12 +539,17 @@ impl Client {
 /// Helper function to analyze spend for beta_tracking optimization.
 /// returns the new_utxos that needs to be further tracked.
 fn beta_track_analyze_spend(spend: &SignedSpend) -> BTreeSet<(SpendAddress, NanoTokens)> {
-    // Filter out royalty outputs
+    // Filter out royalty outputs, paid to any of the royalties beneficiaries
     let royalty_pubkeys: BTreeSet<_> = spend
         .spend
         .network_royalties
         .iter()
-        .map(|derivation_idx| NETWORK_ROYALTIES_PK.new_unique_pubkey(derivation_idx))
+        .flat_map(|derivation_idx| {
+            NETWORK_ROYALTIES_SPLIT
+                .beneficiaries()
+                .iter()
+                .map(|(main_pubkey, _)| main_pubkey.new_unique_pubkey(derivation_idx))
+        })
         .collect();
     let default_royalty_pubkeys: BTreeSet<_> = spend
         .spend
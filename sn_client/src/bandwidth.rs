@@ -0,0 +1,159 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_networking::target_arch::{sleep, Instant};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// The bandwidth a client may use, in bytes per second. `None` leaves a direction unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthLimits {
+    pub upload: Option<u64>,
+    pub download: Option<u64>,
+}
+
+/// The direction data is transferred in.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Direction {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Default)]
+struct BandwidthState {
+    limits: BandwidthLimits,
+    // When the next byte is allowed to be sent, and received.
+    next_upload: Option<Instant>,
+    next_download: Option<Instant>,
+}
+
+/// Caps the bandwidth used by all the clones of a client, across all their concurrent requests.
+///
+/// Every transfer reserves the next free slot of its direction and waits until it is reached.
+/// A download only knows its size once it was received, so it delays the next transfers
+/// instead. The uploads of the `Uploader` go through it too. The limits can be changed at any time,
+/// the next transfers being held to the new limits.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BandwidthLimiter {
+    state: Arc<Mutex<BandwidthState>>,
+}
+
+impl BandwidthLimiter {
+    pub(crate) fn limits(&self) -> BandwidthLimits {
+        self.state().limits
+    }
+
+    pub(crate) fn set_limits(&self, limits: BandwidthLimits) {
+        let mut state = self.state();
+        state.limits = BandwidthLimits {
+            upload: limits.upload.filter(|limit| *limit > 0),
+            download: limits.download.filter(|limit| *limit > 0),
+        };
+        // the slots reserved under the previous limits no longer hold.
+        state.next_upload = None;
+        state.next_download = None;
+    }
+
+    /// Waits until transferring `size` bytes in the direction stays within the limits.
+    pub(crate) async fn acquire(&self, direction: Direction, size: usize) {
+        let delay = self.reserve(direction, size, Instant::now());
+        if !delay.is_zero() {
+            trace!(
+                "Delaying the {direction:?} of {size} bytes by {delay:?} to stay within the limits"
+            );
+            sleep(delay).await;
+        }
+    }
+
+    /// Reserves the slot for `size` bytes, returning how long to wait from `now` for it.
+    fn reserve(&self, direction: Direction, size: usize, now: Instant) -> Duration {
+        let mut state = self.state();
+        let (limit, next) = match direction {
+            Direction::Upload => (state.limits.upload, &mut state.next_upload),
+            Direction::Download => (state.limits.download, &mut state.next_download),
+        };
+        let Some(limit) = limit else {
+            return Duration::ZERO;
+        };
+
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + Duration::from_secs_f64(size as f64 / limit as f64));
+        slot.saturating_duration_since(now)
+    }
+
+    fn state(&self) -> MutexGuard<'_, BandwidthState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfers_are_spread_across_the_clones_of_the_limiter() {
+        let now = Instant::now();
+        let limiter = BandwidthLimiter::default();
+        assert_eq!(
+            limiter.reserve(Direction::Upload, 1024, now),
+            Duration::ZERO
+        );
+
+        limiter.set_limits(BandwidthLimits {
+            upload: Some(1024),
+            download: Some(0),
+        });
+        assert_eq!(
+            limiter.limits(),
+            BandwidthLimits {
+                upload: Some(1024),
+                download: None,
+            }
+        );
+        let clone = limiter.clone();
+        assert_eq!(
+            limiter.reserve(Direction::Upload, 2048, now),
+            Duration::ZERO
+        );
+        assert_eq!(
+            clone.reserve(Direction::Upload, 512, now),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            clone.reserve(Direction::Download, 1024 * 1024, now),
+            Duration::ZERO
+        );
+
+        // new limits apply right away.
+        clone.set_limits(BandwidthLimits {
+            upload: Some(2048),
+            download: Some(1024),
+        });
+        assert_eq!(
+            limiter.reserve(Direction::Upload, 4096, now),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.reserve(Direction::Upload, 1, now),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            limiter.reserve(Direction::Download, 1024, now),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.reserve(Direction::Download, 1024, now + Duration::from_secs(5)),
+            Duration::ZERO
+        );
+    }
+}
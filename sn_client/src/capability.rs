@@ -0,0 +1,238 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, Client, ClientRegister, WalletClient};
+use crate::{chunks::UserKey, Error};
+use bls::{serde_impl::SerdeSecret, Ciphertext, PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::{ChunkAddress, RegisterAddress};
+use sn_transfers::NanoTokens;
+use xor_name::XorName;
+
+/// The data a `Capability` gives access to.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CapabilityTarget {
+    /// A file, given the address of its data map, with the key it was encrypted with if it was
+    /// uploaded with `FilesApi::chunk_file_with_user_key`.
+    File {
+        data_map: ChunkAddress,
+        user_key: Option<UserKey>,
+    },
+    /// A Register. If a writer key is given, it must be one of the writers of the Register, which
+    /// gives write access to whoever holds the capability.
+    Register {
+        address: RegisterAddress,
+        writer: Option<SerdeSecret<SecretKey>>,
+    },
+}
+
+/// The access a `Capability` gives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+// What the Register of a capability holds, encrypted to the key of the capability.
+#[derive(Serialize, Deserialize)]
+enum CapabilityRecord {
+    Granted(CapabilityTarget),
+    Revoked,
+}
+
+/// A capability to access some data, which can be handed to another party out of band, as a hex
+/// string, instead of the secret keys the data is owned with.
+///
+/// The capability doesn't hold the target itself, but the address of a Register owned by the
+/// granter, which holds the target encrypted to the key of the capability. The granter can then
+/// revoke the capability by overwriting it: the capability no longer resolves to its target.
+///
+/// What was read before the revocation stays known to the holder, and the writer key of a Register
+/// stays a writer of it, so write access is revoked by moving to a new Register with a new writer,
+/// and granting new capabilities to it to the remaining collaborators.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Capability {
+    record: RegisterAddress,
+    key: SerdeSecret<SecretKey>,
+}
+
+impl Capability {
+    /// Grants access to the target, paying for the Register recording it, owned by the client.
+    ///
+    /// Returns the capability, with the storage cost and the royalties paid.
+    pub async fn grant(
+        client: &Client,
+        target: CapabilityTarget,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(Self, NanoTokens, NanoTokens)> {
+        let key = SecretKey::random();
+        let mut register =
+            ClientRegister::create(client.clone(), XorName::random(&mut rand::thread_rng()));
+        let _ = register.write(&seal(
+            &key.public_key(),
+            &CapabilityRecord::Granted(target),
+        )?)?;
+        let (storage_cost, royalties) = register.sync(wallet_client, verify_store, None).await?;
+        info!("Granted a capability recorded at {:?}", register.address());
+
+        let capability = Self {
+            record: *register.address(),
+            key: SerdeSecret(key),
+        };
+        Ok((capability, storage_cost, royalties))
+    }
+
+    /// Revokes the capability. Only the client which granted it can.
+    pub async fn revoke(&self, client: &Client, verify_store: bool) -> Result<()> {
+        let mut register = client.get_register(self.record).await?;
+        let revoked = seal(&self.key.public_key(), &CapabilityRecord::Revoked)?;
+        register
+            .write_merging_branches_online(&revoked, verify_store)
+            .await?;
+        info!("Revoked the capability recorded at {:?}", self.record);
+        Ok(())
+    }
+
+    /// Returns the target of the capability, unless it was revoked.
+    pub async fn resolve(&self, client: &Client) -> Result<CapabilityTarget> {
+        let register = client.get_register(self.record).await?;
+        self.target_in(&register)
+    }
+
+    /// Fetches the Register the capability gives access to, signing the writes to it with the
+    /// writer key of the capability if it has one.
+    pub async fn open_register(&self, client: &Client) -> Result<ClientRegister> {
+        match self.resolve(client).await? {
+            CapabilityTarget::Register { address, writer } => {
+                let mut client = client.clone();
+                if let Some(writer) = writer {
+                    client.set_signer_key(writer.inner().clone());
+                }
+                client.get_register(address).await
+            }
+            CapabilityTarget::File { .. } => Err(Error::InvalidCapability(
+                "The capability gives access to a file, not a Register".to_string(),
+            )),
+        }
+    }
+
+    /// The address of the Register recording the capability.
+    pub fn record(&self) -> RegisterAddress {
+        self.record
+    }
+
+    /// The access the capability gives, unless it was revoked.
+    pub async fn access(&self, client: &Client) -> Result<Access> {
+        let access = match self.resolve(client).await? {
+            CapabilityTarget::Register {
+                writer: Some(_), ..
+            } => Access::Write,
+            _ => Access::Read,
+        };
+        Ok(access)
+    }
+
+    /// Serialises the capability to share it.
+    pub fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(rmp_serde::to_vec(self)?))
+    }
+
+    /// Deserialises a capability shared with `to_hex`.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = hex::decode(hex)
+            .map_err(|err| Error::InvalidCapability(format!("Invalid hex: {err}")))?;
+        Ok(rmp_serde::from_slice(&bytes)?)
+    }
+
+    // Reads the target from the latest entries of the Register, a revocation overriding any
+    // concurrent grant.
+    fn target_in(&self, register: &ClientRegister) -> Result<CapabilityTarget> {
+        let mut target = None;
+        for (_, entry) in register.read() {
+            match open(&self.key, &entry)? {
+                CapabilityRecord::Revoked => return Err(Error::CapabilityRevoked(self.record)),
+                CapabilityRecord::Granted(granted) => target = Some(granted),
+            }
+        }
+        target.ok_or_else(|| {
+            Error::InvalidCapability(format!("Nothing is recorded at {:?}", self.record))
+        })
+    }
+}
+
+fn seal(key: &PublicKey, record: &CapabilityRecord) -> Result<Vec<u8>> {
+    Ok(key.encrypt(rmp_serde::to_vec(record)?).to_bytes())
+}
+
+fn open(key: &SecretKey, entry: &[u8]) -> Result<CapabilityRecord> {
+    let invalid = || Error::InvalidCapability("The record can't be decrypted".to_string());
+    let cipher = Ciphertext::from_bytes(entry).map_err(|_| invalid())?;
+    let bytes = key.decrypt(&cipher).ok_or_else(invalid)?;
+    Ok(rmp_serde::from_slice(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uploader::tests::setup::build_unconnected_client;
+    use eyre::Result;
+    use tempfile::tempdir;
+
+    #[test]
+    fn capabilities_resolve_until_revoked() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        let mut register = ClientRegister::create(client, XorName::random(&mut rand::thread_rng()));
+        let key = SecretKey::random();
+        let capability = Capability {
+            record: *register.address(),
+            key: SerdeSecret(key.clone()),
+        };
+        assert!(matches!(
+            capability.target_in(&register),
+            Err(crate::Error::InvalidCapability(_))
+        ));
+
+        let target_address =
+            RegisterAddress::new(XorName::random(&mut rand::thread_rng()), key.public_key());
+        let target = CapabilityTarget::Register {
+            address: target_address,
+            writer: Some(SerdeSecret(SecretKey::random())),
+        };
+        let _ = register.write(&seal(
+            &key.public_key(),
+            &CapabilityRecord::Granted(target),
+        )?)?;
+
+        // the shared capability resolves to the target.
+        let shared = Capability::from_hex(&capability.to_hex()?)?;
+        match shared.target_in(&register)? {
+            CapabilityTarget::Register {
+                address,
+                writer: Some(_),
+            } => assert_eq!(address, target_address),
+            target => panic!("Unexpected target {target:?}"),
+        }
+        // but no other key can read it.
+        let other = Capability {
+            record: *register.address(),
+            key: SerdeSecret(SecretKey::random()),
+        };
+        assert!(other.target_in(&register).is_err());
+
+        let _ = register
+            .write_merging_branches(&seal(&key.public_key(), &CapabilityRecord::Revoked)?)?;
+        assert!(matches!(
+            shared.target_in(&register),
+            Err(crate::Error::CapabilityRevoked(_))
+        ));
+        assert!(Capability::from_hex("not hex").is_err());
+        Ok(())
+    }
+}
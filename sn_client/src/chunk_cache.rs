@@ -0,0 +1,294 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bytes::Bytes;
+use sn_protocol::storage::{Chunk, ChunkAddress};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use xor_name::XorName;
+
+/// An on-disk cache of the chunks fetched from the network, so that files which are accessed
+/// repeatedly are not fetched again every time.
+///
+/// The cache is bounded by the total size of the cached chunks. Once it is full, the least
+/// recently used chunks are evicted. The order of use survives restarts, as it is kept in the
+/// modification times of the cached files.
+///
+/// Where there is no file system, e.g. in the browser, the cache can be kept in memory instead.
+#[derive(Clone, Debug)]
+pub struct ChunkCache {
+    // `None` if the chunks are kept in memory.
+    dir: Option<PathBuf>,
+    max_size: u64,
+    state: Arc<Mutex<CacheState>>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    // The size and the last use of every cached chunk.
+    entries: HashMap<XorName, (u64, u64)>,
+    // The cached chunks, from the least to the most recently used.
+    by_last_use: BTreeMap<u64, XorName>,
+    total_size: u64,
+    next_use: u64,
+    // The content of the cached chunks, for a cache kept in memory.
+    in_memory: HashMap<XorName, Bytes>,
+}
+
+impl ChunkCache {
+    /// Opens the cache stored in `dir`, creating it if needed, and evicts chunks until the cache fits
+    /// within `max_size` bytes.
+    pub fn open(dir: &Path, max_size: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let mut cached = vec![];
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let Some(xorname) = entry.file_name().to_str().and_then(xorname_from_hex) else {
+                continue;
+            };
+            let metadata = entry.metadata()?;
+            let last_use = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            cached.push((last_use, xorname, metadata.len()));
+        }
+        cached.sort();
+
+        let cache = Self {
+            dir: Some(dir.to_path_buf()),
+            max_size,
+            state: Default::default(),
+        };
+        let mut state = cache.lock_state();
+        for (_, xorname, size) in cached {
+            state.touch(xorname, size);
+        }
+        cache.evict(&mut state, 0);
+        drop(state);
+
+        Ok(cache)
+    }
+
+    /// Creates a cache kept in memory, holding up to `max_size` bytes of chunks.
+    pub fn in_memory(max_size: u64) -> Self {
+        Self {
+            dir: None,
+            max_size,
+            state: Default::default(),
+        }
+    }
+
+    /// The total size of the cached chunks.
+    pub fn size(&self) -> u64 {
+        self.lock_state().total_size
+    }
+
+    /// Returns the chunk if it is cached, marking it as the most recently used one.
+    pub fn get(&self, address: &ChunkAddress) -> Option<Chunk> {
+        let xorname = *address.xorname();
+        let mut state = self.lock_state();
+        let (size, _) = *state.entries.get(&xorname)?;
+
+        let Some(path) = self.chunk_path(&xorname) else {
+            let content = state.in_memory.get(&xorname)?.clone();
+            state.touch(xorname, size);
+            return Some(Chunk::new(content));
+        };
+        let content = match fs::read(&path) {
+            Ok(content) => Bytes::from(content),
+            Err(err) => {
+                warn!("Could not read cached chunk {xorname:?}: {err:?}");
+                state.remove(&xorname);
+                return None;
+            }
+        };
+        let chunk = Chunk::new(content);
+        // the address of a chunk is the hash of its content, so a damaged file is never served.
+        if chunk.name() != &xorname {
+            warn!("Cached chunk {xorname:?} is corrupted, removing it");
+            state.remove(&xorname);
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        state.touch(xorname, size);
+        if let Err(err) = File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+        {
+            warn!("Could not record the use of cached chunk {xorname:?}: {err:?}");
+        }
+        Some(chunk)
+    }
+
+    /// Adds the chunk to the cache, evicting the least recently used chunks to make room for it.
+    /// Chunks larger than the whole cache are not cached.
+    pub fn insert(&self, chunk: &Chunk) -> io::Result<()> {
+        let xorname = *chunk.name();
+        let size = chunk.value().len() as u64;
+        if size > self.max_size {
+            return Ok(());
+        }
+
+        let mut state = self.lock_state();
+        if state.entries.contains_key(&xorname) {
+            state.touch(xorname, size);
+            return Ok(());
+        }
+        self.evict(&mut state, size);
+
+        let Some(path) = self.chunk_path(&xorname) else {
+            let _ = state.in_memory.insert(xorname, chunk.value().clone());
+            state.touch(xorname, size);
+            return Ok(());
+        };
+        // write to a temporary file first, so that an interrupted write never leaves a partial chunk behind.
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, chunk.value())?;
+        fs::rename(&tmp_path, &path)?;
+        state.touch(xorname, size);
+        Ok(())
+    }
+
+    // Removes the least recently used chunks until `incoming` more bytes fit in the cache.
+    fn evict(&self, state: &mut CacheState, incoming: u64) {
+        while state.total_size + incoming > self.max_size {
+            let Some((_, xorname)) = state.by_last_use.pop_first() else {
+                break;
+            };
+            if let Some((size, _)) = state.entries.remove(&xorname) {
+                state.total_size -= size;
+            }
+            debug!("Evicting chunk {xorname:?} from the cache");
+            let _ = state.in_memory.remove(&xorname);
+            if let Some(path) = self.chunk_path(&xorname) {
+                if let Err(err) = fs::remove_file(path) {
+                    warn!("Could not remove evicted chunk {xorname:?}: {err:?}");
+                }
+            }
+        }
+    }
+
+    fn chunk_path(&self, xorname: &XorName) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(hex::encode(xorname)))
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl CacheState {
+    // Marks the chunk as the most recently used one, adding it if needed.
+    fn touch(&mut self, xorname: XorName, size: u64) {
+        self.remove(&xorname);
+        let last_use = self.next_use;
+        self.next_use += 1;
+        let _ = self.entries.insert(xorname, (size, last_use));
+        let _ = self.by_last_use.insert(last_use, xorname);
+        self.total_size += size;
+    }
+
+    fn remove(&mut self, xorname: &XorName) {
+        if let Some((size, last_use)) = self.entries.remove(xorname) {
+            let _ = self.by_last_use.remove(&last_use);
+            self.total_size -= size;
+        }
+    }
+}
+
+fn xorname_from_hex(name: &str) -> Option<XorName> {
+    let bytes = hex::decode(name).ok()?;
+    Some(XorName(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use tempfile::tempdir;
+
+    fn chunk(byte: u8, size: usize) -> Chunk {
+        Chunk::new(Bytes::from(vec![byte; size]))
+    }
+
+    #[test]
+    fn least_recently_used_chunks_are_evicted() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let cache = ChunkCache::open(temp_dir.path(), 300)?;
+        let (a, b, c, d) = (chunk(1, 100), chunk(2, 100), chunk(3, 100), chunk(4, 100));
+
+        cache.insert(&a)?;
+        cache.insert(&b)?;
+        cache.insert(&c)?;
+        // using `a` makes `b` the least recently used chunk.
+        assert_eq!(cache.get(a.address()), Some(a.clone()));
+        cache.insert(&d)?;
+
+        assert_eq!(cache.size(), 300);
+        assert_eq!(cache.get(b.address()), None);
+        assert!(!temp_dir.path().join(hex::encode(b.name())).exists());
+        for kept in [&a, &c, &d] {
+            assert_eq!(cache.get(kept.address()).as_ref(), Some(kept));
+        }
+
+        // too large to ever fit.
+        cache.insert(&chunk(5, 301))?;
+        assert_eq!(cache.size(), 300);
+        Ok(())
+    }
+
+    #[test]
+    fn cache_is_reloaded_and_shrunk_on_open() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let (a, b) = (chunk(1, 100), chunk(2, 100));
+        {
+            let cache = ChunkCache::open(temp_dir.path(), 1000)?;
+            cache.insert(&a)?;
+            cache.insert(&b)?;
+        }
+        // a corrupted file is dropped instead of being served.
+        fs::write(temp_dir.path().join(hex::encode(a.name())), b"corrupted")?;
+
+        let cache = ChunkCache::open(temp_dir.path(), 1000)?;
+        assert_eq!(cache.get(b.address()), Some(b.clone()));
+        assert_eq!(cache.get(a.address()), None);
+        assert_eq!(cache.size(), 100);
+
+        let cache = ChunkCache::open(temp_dir.path(), 50)?;
+        assert_eq!(cache.size(), 0);
+        assert_eq!(cache.get(b.address()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn in_memory_cache_evicts_least_recently_used_chunks() -> Result<()> {
+        let cache = ChunkCache::in_memory(200);
+        let (a, b, c) = (chunk(1, 100), chunk(2, 100), chunk(3, 100));
+
+        cache.insert(&a)?;
+        cache.insert(&b)?;
+        assert_eq!(cache.get(a.address()), Some(a.clone()));
+        cache.insert(&c)?;
+
+        assert_eq!(cache.size(), 200);
+        assert_eq!(cache.get(b.address()), None);
+        assert_eq!(cache.get(a.address()), Some(a));
+        assert_eq!(cache.get(c.address()), Some(c));
+        Ok(())
+    }
+}
@@ -8,6 +8,12 @@
 
 mod error;
 mod pac_man;
+mod stream_encryptor;
+mod user_key;
 
 pub(crate) use self::error::{Error, Result};
-pub(crate) use pac_man::{encrypt_large, DataMapLevel};
+pub use pac_man::MAX_PACKED_FILE_SIZE;
+pub(crate) use pac_man::{encrypt_large, pack_data_map, pack_file, DataMapLevel};
+pub(crate) use stream_encryptor::{StreamEncryptor, MIN_STREAMED_SIZE};
+pub use user_key::UserKey;
+pub(crate) use user_key::{FileCipher, UserEncryption};
@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use libp2p::PeerId;
 use self_encryption::MIN_ENCRYPTABLE_BYTES;
 use sn_protocol::PrettyPrintRecordKey;
 use std::io;
@@ -63,9 +64,28 @@ pub enum Error {
         missing_chunks: Vec<XorName>,
     },
 
+    #[error("The file is packed into its data map, which points to no chunks")]
+    PackedFile,
+
+    #[error("The file was encrypted with a user key, which must be provided to read it")]
+    UserKeyRequired,
+
+    #[error("The file was encrypted with another user key")]
+    WrongUserKey,
+
     #[error("Chunk could not be retrieved from the network: {0:?}")]
     ChunkMissing(XorName),
 
+    #[error(
+        "Chunk {address:?} at index {index} of the data map was corrupt, served by {holder:?}"
+    )]
+    CorruptChunk {
+        address: XorName,
+        index: usize,
+        /// The peer the chunk was fetched from, if known.
+        holder: Option<PeerId>,
+    },
+
     #[error("Not all data was chunked, expected {expected}, but we have {chunked}.)")]
     NotAllDataWasChunked {
         /// Number of Chunks expected to be generated
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::Result;
+use super::{Result, UserEncryption};
 use bytes::{BufMut, Bytes, BytesMut};
 use rayon::prelude::*;
 use self_encryption::{DataMap, StreamSelfEncryptor, MAX_CHUNK_SIZE};
@@ -27,8 +27,17 @@ pub(crate) enum DataMapLevel {
     // resulting from chunking up a previous level data map.
     // This happens when that previous level data map was too big to fit in a chunk itself.
     Additional(DataMap),
+    // Holds the data map to the source data, which was encrypted with a user key before being
+    // self-encrypted.
+    UserEncrypted(DataMap, UserEncryption),
+    // Holds the whole content of a file small enough to be packed into its data map, instead of
+    // being self-encrypted into chunks of its own.
+    Packed(Bytes),
 }
 
+/// The size up to which files are packed into their data map.
+pub const MAX_PACKED_FILE_SIZE: usize = 64 * 1024;
+
 #[allow(unused)]
 pub(crate) fn encrypt_from_path(path: &Path, output_dir: &Path) -> Result<(Chunk, Vec<XorName>)> {
     let (data_map, mut encrypted_chunks) = self_encryption::encrypt_from_file(path, output_dir)?;
@@ -49,6 +58,7 @@ pub(crate) fn encrypt_from_path(path: &Path, output_dir: &Path) -> Result<(Chunk
 pub(crate) fn encrypt_large(
     file_path: &Path,
     output_dir: &Path,
+    user_encryption: Option<UserEncryption>,
 ) -> Result<(Chunk, Vec<(XorName, PathBuf)>)> {
     let mut encryptor = StreamSelfEncryptor::encrypt_from_file(
         Box::new(file_path.to_path_buf()),
@@ -76,7 +86,11 @@ pub(crate) fn encrypt_large(
         .collect();
 
     // Pack the datamap into chunks that under the same output folder as well.
-    let (data_map_chunk, additional_chunks) = pack_data_map(data_map)?;
+    let first_level = match user_encryption {
+        Some(user_encryption) => DataMapLevel::UserEncrypted(data_map, user_encryption),
+        None => DataMapLevel::First(data_map),
+    };
+    let (data_map_chunk, additional_chunks) = pack_data_map_level(first_level)?;
     for chunk in additional_chunks.iter() {
         let file_path = output_dir.join(hex::encode(chunk.name()));
         encrypted_chunks.push((*chunk.name(), file_path.to_path_buf()));
@@ -97,9 +111,18 @@ pub(crate) fn to_chunk(chunk_content: Bytes) -> Chunk {
 // In other words: If the chunk content is too big, it will be
 // self encrypted into additional chunks, and now we have a new `DataMap`
 // which points to all of those additional chunks.. and so on.
-fn pack_data_map(data_map: DataMap) -> Result<(Chunk, Vec<Chunk>)> {
+/// Packs the content of a small file into its data map chunk.
+pub(crate) fn pack_file(content: Bytes) -> Result<Chunk> {
+    Ok(to_chunk(wrap_data_map(&DataMapLevel::Packed(content))?))
+}
+
+pub(crate) fn pack_data_map(data_map: DataMap) -> Result<(Chunk, Vec<Chunk>)> {
+    pack_data_map_level(DataMapLevel::First(data_map))
+}
+
+fn pack_data_map_level(first_level: DataMapLevel) -> Result<(Chunk, Vec<Chunk>)> {
     let mut chunks = vec![];
-    let mut chunk_content = wrap_data_map(&DataMapLevel::First(data_map))?;
+    let mut chunk_content = wrap_data_map(&first_level)?;
 
     let (data_map_chunk, additional_chunks) = loop {
         let chunk = to_chunk(chunk_content);
@@ -0,0 +1,202 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Result;
+use aes::{
+    cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit},
+    Aes128,
+};
+use brotli::enc::BrotliEncoderParams;
+use bytes::Bytes;
+use self_encryption::{ChunkInfo, DataMap, COMPRESSION_QUALITY, MAX_CHUNK_SIZE};
+use sn_protocol::storage::Chunk;
+use std::io::Cursor;
+use xor_name::{XorName, XOR_NAME_LEN};
+
+type Aes128CbcEnc = cbc::Encryptor<Aes128>;
+
+const KEY_SIZE: usize = 16;
+const IV_SIZE: usize = 16;
+const PAD_SIZE: usize = (XOR_NAME_LEN * 3) - KEY_SIZE - IV_SIZE;
+
+/// The size from which self-encryption cuts the source into chunks of `MAX_CHUNK_SIZE`, the last
+/// one holding the remainder. Smaller sources are cut in three, which needs their size upfront.
+pub(crate) const MIN_STREAMED_SIZE: usize = 3 * MAX_CHUNK_SIZE;
+
+/// Self-encrypts a source fed one chunk at a time, without knowing its size upfront.
+///
+/// The chunks must all be `MAX_CHUNK_SIZE` long but the last, and the source at least
+/// `MIN_STREAMED_SIZE` long. The result is the same as `self_encryption::encrypt` on the whole
+/// source.
+///
+/// A chunk is encrypted with keys derived from the hashes of the two source chunks before it, so
+/// each chunk can be encrypted once it is pushed. The first two chunks are keyed with the last
+/// ones instead, and are held until the source is finished.
+#[derive(Default)]
+pub(crate) struct StreamEncryptor {
+    src_hashes: Vec<XorName>,
+    first_chunks: Vec<Bytes>,
+    chunk_infos: Vec<ChunkInfo>,
+}
+
+/// A source chunk along with what's needed to encrypt it, so the encryption can be run apart
+/// from the encryptor, e.g. on a blocking thread.
+pub(crate) struct EncryptionJob {
+    index: usize,
+    content: Bytes,
+    src_hash: XorName,
+    n_1_src_hash: XorName,
+    n_2_src_hash: XorName,
+}
+
+impl StreamEncryptor {
+    /// Takes the next chunk of the source, returning the job encrypting it if it can already be.
+    pub(crate) fn push(&mut self, content: Bytes) -> Option<EncryptionJob> {
+        let index = self.src_hashes.len();
+        let src_hash = XorName::from_content(&content);
+        self.src_hashes.push(src_hash);
+        if index < 2 {
+            self.first_chunks.push(content);
+            return None;
+        }
+
+        Some(EncryptionJob {
+            index,
+            content,
+            src_hash,
+            n_1_src_hash: self.src_hashes[index - 1],
+            n_2_src_hash: self.src_hashes[index - 2],
+        })
+    }
+
+    /// Returns the jobs encrypting the first two chunks, once the whole source has been pushed.
+    pub(crate) fn finish(&mut self) -> Vec<EncryptionJob> {
+        let last = self.src_hashes.len().saturating_sub(1);
+        let first_chunks = std::mem::take(&mut self.first_chunks);
+        first_chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, content)| {
+                let (n_1, n_2) = if index == 0 {
+                    (last, last - 1)
+                } else {
+                    (0, last)
+                };
+                EncryptionJob {
+                    index,
+                    content,
+                    src_hash: self.src_hashes[index],
+                    n_1_src_hash: self.src_hashes[n_1],
+                    n_2_src_hash: self.src_hashes[n_2],
+                }
+            })
+            .collect()
+    }
+
+    /// Records the info of a chunk encrypted by one of the jobs, for the data map.
+    pub(crate) fn add_chunk_info(&mut self, chunk_info: ChunkInfo) {
+        self.chunk_infos.push(chunk_info);
+    }
+
+    /// The data map of the source, once all the jobs have been run.
+    pub(crate) fn data_map(self) -> DataMap {
+        DataMap::new(self.chunk_infos)
+    }
+}
+
+impl EncryptionJob {
+    /// Compresses and encrypts the chunk, the way `self_encryption` does.
+    pub(crate) fn run(self) -> Result<(Chunk, ChunkInfo)> {
+        let mut pad = [0u8; PAD_SIZE];
+        let mut key = [0u8; KEY_SIZE];
+        let mut iv = [0u8; IV_SIZE];
+        for (pad_el, element) in pad
+            .iter_mut()
+            .zip(self.src_hash.iter().chain(self.n_2_src_hash.iter()))
+        {
+            *pad_el = *element;
+        }
+        for (key_el, element) in key
+            .iter_mut()
+            .chain(iv.iter_mut())
+            .zip(self.n_1_src_hash.iter())
+        {
+            *key_el = *element;
+        }
+
+        let mut compressed = vec![];
+        let enc_params = BrotliEncoderParams {
+            quality: COMPRESSION_QUALITY,
+            ..Default::default()
+        };
+        let _size = brotli::BrotliCompress(
+            &mut Cursor::new(self.content.as_ref()),
+            &mut compressed,
+            &enc_params,
+        )
+        .map_err(|_| self_encryption::Error::Compression)?;
+        let encrypted =
+            Aes128CbcEnc::new(&key.into(), &iv.into()).encrypt_padded_vec_mut::<Pkcs7>(&compressed);
+        let content: Vec<_> = encrypted
+            .iter()
+            .zip(pad.iter().cycle())
+            .map(|(&a, &b)| a ^ b)
+            .collect();
+
+        let chunk = Chunk::new(Bytes::from(content));
+        let chunk_info = ChunkInfo {
+            index: self.index,
+            dst_hash: *chunk.name(),
+            src_hash: self.src_hash,
+            src_size: self.content.len(),
+        };
+        Ok((chunk, chunk_info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use rand::RngCore;
+
+    #[test]
+    fn streamed_source_encrypts_like_the_whole_source() -> Result<()> {
+        for size in [
+            MIN_STREAMED_SIZE,
+            MIN_STREAMED_SIZE + 1234,
+            5 * MAX_CHUNK_SIZE,
+        ] {
+            let mut data = vec![0u8; size];
+            rand::thread_rng().fill_bytes(&mut data);
+            let data = Bytes::from(data);
+
+            let mut encryptor = StreamEncryptor::default();
+            let mut jobs = vec![];
+            for start in (0..size).step_by(MAX_CHUNK_SIZE) {
+                let end = usize::min(start + MAX_CHUNK_SIZE, size);
+                jobs.extend(encryptor.push(data.slice(start..end)));
+            }
+            jobs.extend(encryptor.finish());
+            let mut chunks = vec![];
+            for job in jobs {
+                let (chunk, chunk_info) = job.run()?;
+                chunks.push(chunk);
+                encryptor.add_chunk_info(chunk_info);
+            }
+
+            let (expected_data_map, expected_chunks) = self_encryption::encrypt(data)?;
+            assert!(encryptor.data_map() == expected_data_map);
+            assert_eq!(chunks.len(), expected_chunks.len());
+            for expected in expected_chunks {
+                assert!(chunks.iter().any(|chunk| chunk.value == expected.content));
+            }
+        }
+        Ok(())
+    }
+}
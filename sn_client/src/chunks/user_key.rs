@@ -0,0 +1,197 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Error, Result};
+use aes::Aes256;
+use ctr::{
+    cipher::{KeyIvInit, StreamCipher, StreamCipherSeek},
+    Ctr128BE,
+};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sn_transfers::MainSecretKey;
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+use xor_name::XorName;
+
+const KEY_SIZE: usize = 32;
+const SALT_SIZE: usize = 32;
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// A secret key private files can be encrypted with before being self-encrypted.
+///
+/// Self-encryption alone lets anyone holding the data map of a file read it. The content of a file
+/// uploaded with a `UserKey` is encrypted with a key derived from it first, so the file stays
+/// unreadable to whoever gets hold of its data map without the `UserKey`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UserKey([u8; KEY_SIZE]);
+
+impl UserKey {
+    /// Derives the key from a secret with enough entropy, e.g. random bytes or a long passphrase.
+    pub fn from_secret(secret: &[u8]) -> Self {
+        Self::derive(secret, b"safe-user-key-from-secret")
+    }
+
+    /// Derives the key from the secret key of a wallet, so that nothing else has to be kept.
+    pub fn from_wallet_key(key: &MainSecretKey) -> Self {
+        Self::derive(&key.secret_key().to_bytes(), b"safe-user-key-from-wallet")
+    }
+
+    fn derive(secret: &[u8], info: &[u8]) -> Self {
+        let mut key = [0; KEY_SIZE];
+        Hkdf::<Sha256>::new(None, secret)
+            .expand(info, &mut key)
+            .expect("32 bytes is a valid HKDF output length");
+        Self(key)
+    }
+
+    /// Picks the salt a new file is encrypted with.
+    pub(crate) fn new_file_encryption(&self) -> UserEncryption {
+        let mut salt = [0; SALT_SIZE];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let (_, key_id) = self.file_key(&salt);
+        UserEncryption { salt, key_id }
+    }
+
+    /// Returns the cipher of the file, checking it was encrypted with this key.
+    pub(crate) fn file_cipher(&self, encryption: &UserEncryption) -> Result<FileCipher> {
+        let (key, key_id) = self.file_key(&encryption.salt);
+        if key_id != encryption.key_id {
+            return Err(Error::WrongUserKey);
+        }
+        Ok(FileCipher(Ctr128BE::<Aes256>::new(
+            &key.into(),
+            &[0; 16].into(),
+        )))
+    }
+
+    // Every file has its own key, so the counter can always start from zero.
+    fn file_key(&self, salt: &[u8; SALT_SIZE]) -> ([u8; KEY_SIZE], XorName) {
+        let hkdf = Hkdf::<Sha256>::new(Some(salt), &self.0);
+        let mut key = [0; KEY_SIZE];
+        let mut check = [0; KEY_SIZE];
+        hkdf.expand(b"file-key", &mut key)
+            .and_then(|_| hkdf.expand(b"file-key-id", &mut check))
+            .expect("32 bytes is a valid HKDF output length");
+        (key, XorName::from_content(&check))
+    }
+}
+
+impl fmt::Debug for UserKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UserKey(..)")
+    }
+}
+
+/// How the content of a file was encrypted with a `UserKey`. It is held in the data map of the
+/// file, and reveals nothing of the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct UserEncryption {
+    salt: [u8; SALT_SIZE],
+    // Tells a wrong key apart from corrupted content.
+    key_id: XorName,
+}
+
+/// The stream cipher of a file, which can encrypt or decrypt any range of it.
+pub(crate) struct FileCipher(Ctr128BE<Aes256>);
+
+impl FileCipher {
+    /// Encrypts or decrypts the bytes found at `position` in the file.
+    pub(crate) fn apply(&mut self, position: u64, bytes: &mut [u8]) {
+        self.0.seek(position);
+        self.0.apply_keystream(bytes);
+    }
+
+    /// Encrypts or decrypts a whole file, writing the result to `output`, or in place if it is
+    /// the same path.
+    pub(crate) fn apply_to_file(&mut self, input: &Path, output: &Path) -> Result<()> {
+        let mut reader = File::open(input)?;
+        let mut writer = if input == output {
+            OpenOptions::new().write(true).open(output)?
+        } else {
+            File::create(output)?
+        };
+        let mut buffer = vec![0; BUFFER_SIZE];
+        let mut position = 0;
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            self.apply(position, &mut buffer[..read]);
+            let _ = writer.seek(SeekFrom::Start(position))?;
+            writer.write_all(&buffer[..read])?;
+            position += read as u64;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use tempfile::tempdir;
+
+    #[test]
+    fn any_range_is_decrypted_with_the_same_key_only() -> Result<()> {
+        let key = UserKey::from_secret(b"correct horse battery staple");
+        let encryption = key.new_file_encryption();
+        assert_ne!(encryption, key.new_file_encryption());
+
+        let data: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let mut encrypted = data.clone();
+        key.file_cipher(&encryption)?.apply(0, &mut encrypted);
+        assert_ne!(encrypted, data);
+
+        let mut cipher =
+            UserKey::from_secret(b"correct horse battery staple").file_cipher(&encryption)?;
+        let mut range = encrypted[70_000..70_100].to_vec();
+        cipher.apply(70_000, &mut range);
+        assert_eq!(range, data[70_000..70_100]);
+
+        assert!(matches!(
+            UserKey::from_secret(b"wrong").file_cipher(&encryption),
+            Err(Error::WrongUserKey)
+        ));
+        let wallet_key = MainSecretKey::random();
+        assert!(UserKey::from_wallet_key(&wallet_key)
+            .file_cipher(&UserKey::from_wallet_key(&wallet_key).new_file_encryption())
+            .is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn files_are_encrypted_and_decrypted_in_place() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("file");
+        let encrypted_path = temp_dir.path().join("encrypted");
+        let data: Vec<u8> = (0..3 * BUFFER_SIZE + 7).map(|i| (i % 13) as u8).collect();
+        std::fs::write(&path, &data)?;
+
+        let key = UserKey::from_secret(b"secret");
+        let encryption = key.new_file_encryption();
+        key.file_cipher(&encryption)?
+            .apply_to_file(&path, &encrypted_path)?;
+        let mut expected = data.clone();
+        key.file_cipher(&encryption)?.apply(0, &mut expected);
+        assert_eq!(std::fs::read(&encrypted_path)?, expected);
+
+        key.file_cipher(&encryption)?
+            .apply_to_file(&encrypted_path, &encrypted_path)?;
+        assert_eq!(std::fs::read(&encrypted_path)?, data);
+        Ok(())
+    }
+}
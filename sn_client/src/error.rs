@@ -8,11 +8,13 @@
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 
-use crate::UploadSummary;
+use crate::{ReadQuorum, TimeoutOperation, UploadSummary};
 
 use super::ClientEvent;
-use sn_protocol::NetworkAddress;
-use sn_registers::{Entry, EntryHash};
+use libp2p::PeerId;
+use sn_protocol::{storage::ChunkAddress, NetworkAddress};
+use sn_registers::{Entry, EntryHash, RegisterAddress};
+use sn_transfers::NanoTokens;
 use std::collections::BTreeSet;
 use thiserror::Error;
 use tokio::time::Duration;
@@ -49,6 +51,99 @@ pub enum Error {
     #[error("Decrypting a Folder's item failed: {0}")]
     FolderEntryDecryption(EntryHash),
 
+    #[error("Invalid path in a files container: {0}")]
+    InvalidContainerPath(String),
+
+    #[error("No file or directory at {0} in the files container")]
+    ContainerEntryNotFound(String),
+
+    #[error("Invalid capability: {0}")]
+    InvalidCapability(String),
+
+    #[error("The capability recorded at {0:?} was revoked")]
+    CapabilityRevoked(RegisterAddress),
+
+    #[error("Chunk {address:?} served by {holder:?} doesn't match its address")]
+    CorruptChunk {
+        address: ChunkAddress,
+        /// The peer the chunk was fetched from, if known.
+        holder: Option<PeerId>,
+    },
+
+    #[error("The files container at {0:?} holds an invalid entry")]
+    InvalidContainerHead(RegisterAddress),
+
+    #[error("The Chunk {0:?} an oversized Register entry was spilled into is invalid")]
+    InvalidRegisterOverflow(XorName),
+
+    #[error(
+        "The Register at {0:?} holds oversized entries whose Chunks need to be paid with `sync`"
+    )]
+    RegisterOverflowNotPaid(RegisterAddress),
+
+    #[error("The value doesn't match the schema of the Register: {0}")]
+    TypedValueRejected(String),
+
+    #[error("The entry {0:?} of the Register is not a valid value: {1}")]
+    InvalidTypedEntry(EntryHash, String),
+
+    #[error("The log entry of {0} bytes is bigger than a segment can hold: {1}")]
+    LogEntryTooBig(usize, usize),
+
+    #[error("No entry {0} in the log")]
+    NoSuchLogEntry(u64),
+
+    #[error("The Chunk {0:?} of a log segment is invalid")]
+    InvalidLogSegment(XorName),
+
+    #[error("The entry {0:?} of the Register of a log is not a valid head")]
+    InvalidLogHead(EntryHash),
+
+    #[error("Invalid safe URL: {0}")]
+    InvalidSafeUrl(String),
+
+    #[error("The name {0} is already registered")]
+    NameAlreadyRegistered(String),
+
+    #[error("The name {0} is not registered")]
+    NameNotRegistered(String),
+
+    #[error("No subname is mapped at {0}")]
+    NoSuchSubname(String),
+
+    #[error("No such version of the name's map: {0}")]
+    NoSuchNameVersion(String),
+
+    #[error("Too many Pointers followed resolving {0}")]
+    TooManyPointerHops(String),
+
+    #[error("The entry {0:?} of the Register of a name is not a valid map")]
+    InvalidNameMap(EntryHash),
+
+    #[error("No network profile named {0}")]
+    NetworkProfileNotFound(String),
+
+    #[error("Invalid network profile: {0}")]
+    InvalidNetworkProfile(String),
+
+    #[error("The network key of the profile {0} differs from the one already in use")]
+    NetworkKeyMismatch(String),
+
+    #[error("{0:?} is not a read quorum, expected first, majority or all")]
+    InvalidReadQuorum(String),
+
+    #[error("The holders of the record returned diverging copies, failing the {0} read quorum")]
+    ReadQuorumNotMet(ReadQuorum),
+
+    #[error("The holders of {0:?} returned diverging copies, none returned by more of them than the others")]
+    NoAgreedCopy(NetworkAddress),
+
+    #[error("{0:?} did not complete in {1:?}")]
+    OperationTimeout(TimeoutOperation, Duration),
+
+    #[error("No operation {0} in the outbox")]
+    OutboxOperationNotFound(u64),
+
     #[error("SelfEncryption Error {0}.")]
     SelfEncryptionIO(#[from] self_encryption::Error),
 
@@ -90,6 +185,12 @@ pub enum Error {
     #[error("Total price exceed possible token amount")]
     TotalPriceTooHigh,
 
+    #[error("The upload would spend {required}, over its budget of {budget}")]
+    UploadBudgetExceeded {
+        budget: NanoTokens,
+        required: NanoTokens,
+    },
+
     #[error("Logic error: NonZeroUsize was initialised as zero")]
     NonZeroUsizeWasInitialisedAsZero,
 
@@ -6,7 +6,13 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use serde::Serialize;
+use crate::RetryEvent;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::ChunkAddress;
+use sn_registers::RegisterAddress;
+use sn_transfers::NanoTokens;
+use std::path::PathBuf;
 use tokio::sync::broadcast::{self, error::RecvError};
 
 // Channel where events will be broadcasted by the client.
@@ -53,6 +59,42 @@ pub enum ClientEvent {
     /// No network activity has been received for a given duration
     /// we should error out
     InactiveClient(tokio::time::Duration),
+    /// Progress was made storing data on the network.
+    Progress(ProgressEvent),
+    /// A message was published on a pub/sub topic subscribed to.
+    PubSubMessage {
+        topic: String,
+        #[debug(skip)]
+        msg: Bytes,
+    },
+}
+
+/// The progress of the operations storing data on the network, to render progress bars from.
+///
+/// Unlike the other events, these can be deserialized, to be forwarded to other processes, e.g. a GUI.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ProgressEvent {
+    /// A chunk has been uploaded.
+    ChunkUploaded { address: ChunkAddress },
+    /// A chunk was found to be stored already, and was not paid for.
+    ChunkAlreadyStored { address: ChunkAddress },
+    /// A Register has been uploaded, or its changes pushed to the network.
+    RegisterUploaded { address: RegisterAddress },
+    /// A payment for storing a batch of data has been made.
+    PaymentMade {
+        storage_cost: NanoTokens,
+        royalty_fees: NanoTokens,
+        new_balance: NanoTokens,
+    },
+    /// An operation failed, and is about to be retried.
+    Retry(RetryEvent),
+    /// An uploaded chunk could not be verified to be stored, and is uploaded again.
+    VerificationFailed { address: ChunkAddress },
+    /// All the chunks of a file have been uploaded.
+    FileCompleted {
+        path: PathBuf,
+        data_map: ChunkAddress,
+    },
 }
 
 /// Receiver Channel where users of the public API can listen to events broadcasted by the client.
@@ -0,0 +1,143 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{bandwidth::Direction, error::Result, Client, Error, WalletClient};
+use bytes::Bytes;
+use libp2p::kad::{Quorum, Record};
+use sn_networking::{GetRecordCfg, NetworkError, PutRecordCfg, VerificationKind};
+use sn_protocol::{
+    error::Error as ProtocolError,
+    storage::{
+        try_deserialize_record, try_serialize_record, ExpiringData, ExpiringDataAddress,
+        RecordHeader, RecordKind, RetryStrategy,
+    },
+    NetworkAddress,
+};
+use sn_transfers::NanoTokens;
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime},
+};
+
+impl Client {
+    /// Store `data` on the network for `ttl`, after which the nodes drop it. The storage is paid
+    /// with the wallet.
+    ///
+    /// Returns the ExpiringData along with the total cost paid for it.
+    pub async fn store_expiring_data(
+        &self,
+        data: Bytes,
+        ttl: Duration,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(ExpiringData, NanoTokens)> {
+        let expiring_data = ExpiringData::new(data, ttl)?;
+
+        let net_addr = expiring_data.network_address();
+        let payment_result = wallet_client
+            .pay_for_storage(std::iter::once(net_addr.clone()))
+            .await?;
+        let cost = payment_result
+            .storage_cost
+            .checked_add(payment_result.royalty_fees)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        if let Err(err) = wallet_client.store_local_wallet() {
+            warn!("Failed to store wallet with cached payment proofs: {err:?}");
+        }
+        let (payment, payee) = wallet_client.get_recent_payment_for_addr(&net_addr)?;
+
+        let key = net_addr.to_record_key();
+        let record = Record {
+            key: key.clone(),
+            value: try_serialize_record(
+                &(payment, &expiring_data),
+                RecordKind::ExpiringDataWithPayment,
+            )?
+            .to_vec(),
+            publisher: None,
+            expires: None,
+        };
+
+        let (record_to_verify, expected_holders) = if verify_store {
+            let expected_holders: HashSet<_> = self
+                .network
+                .get_closest_peers(&net_addr, true)
+                .await?
+                .iter()
+                .cloned()
+                .collect();
+            (
+                Some(Record {
+                    key,
+                    value: try_serialize_record(&expiring_data, RecordKind::ExpiringData)?.to_vec(),
+                    publisher: None,
+                    expires: None,
+                }),
+                expected_holders,
+            )
+        } else {
+            (None, Default::default())
+        };
+
+        let verification_cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: Some(RetryStrategy::Quick),
+            target_record: record_to_verify,
+            expected_holders,
+        };
+        let put_cfg = PutRecordCfg {
+            put_quorum: Quorum::All,
+            retry_strategy: Some(RetryStrategy::Balanced),
+            use_put_record_to: Some(vec![payee]),
+            verification: Some((VerificationKind::Network, verification_cfg)),
+        };
+        self.throttle(Direction::Upload, record.value.len()).await;
+        self.network.put_record(record, &put_cfg).await?;
+        Ok((expiring_data, cost))
+    }
+
+    /// Retrieve the ExpiringData at the address from the network, which can't be once it
+    /// has expired.
+    pub async fn get_expiring_data(&self, address: ExpiringDataAddress) -> Result<ExpiringData> {
+        let key = NetworkAddress::from_expiring_data_address(address).to_record_key();
+        let get_cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: Some(RetryStrategy::Quick),
+            target_record: None,
+            expected_holders: Default::default(),
+        };
+
+        let record = match self.network.get_record_from_network(key, &get_cfg).await {
+            Ok(record) => record,
+            Err(err) => {
+                warn!("Failed to get ExpiringData at {address:?} from the network: {err:?}");
+                return Err(ProtocolError::ExpiringDataNotFound(Box::new(address)).into());
+            }
+        };
+        self.throttle(Direction::Download, record.value.len()).await;
+        expiring_data_from_record(&record, &address)
+    }
+}
+
+/// Deserialise the ExpiringData from the record, checking it's the one at the address and that
+/// it hasn't expired yet.
+fn expiring_data_from_record(
+    record: &Record,
+    address: &ExpiringDataAddress,
+) -> Result<ExpiringData> {
+    let header = RecordHeader::from_record(record)?;
+    if !matches!(header.kind, RecordKind::ExpiringData) {
+        return Err(NetworkError::RecordKindMismatch(RecordKind::ExpiringData).into());
+    }
+    let expiring_data: ExpiringData = try_deserialize_record(record)?;
+    if expiring_data.address() != address {
+        return Err(ProtocolError::ExpiringDataNotFound(Box::new(*address)).into());
+    }
+    expiring_data.verify(SystemTime::now())?;
+    Ok(expiring_data)
+}
@@ -7,10 +7,15 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 pub(crate) mod download;
+pub(crate) mod estimate;
+mod stream;
 
 use crate::{
-    acc_packet::load_account_wallet_or_create_with_mnemonic, chunks::Error as ChunksError,
-    error::Result, wallet::StoragePaymentResult, Client, Error, WalletClient,
+    acc_packet::load_account_wallet_or_create_with_mnemonic,
+    chunks::{pack_file, Error as ChunksError, UserEncryption, UserKey, MAX_PACKED_FILE_SIZE},
+    error::Result,
+    wallet::StoragePaymentResult,
+    Client, Error, WalletClient,
 };
 use bytes::Bytes;
 use self_encryption::{self, MIN_ENCRYPTABLE_BYTES};
@@ -73,20 +78,69 @@ impl FilesApi {
 
     /// Tries to chunk the file, returning `(head_address, data_map_chunk, file_size, chunk_names)`
     /// and writes encrypted chunks to disk.
+    ///
+    /// If `include_data_map_in_chunks` is set, the data map is public: its chunk is uploaded with
+    /// the others, and anyone given the head address can download the file. Otherwise it is
+    /// private: it is kept by the uploader, who downloads the file, or shares it, with the
+    /// `data_map_chunk`.
+    ///
+    /// Files of up to `MAX_PACKED_FILE_SIZE` bytes are packed into their data map instead of being
+    /// self-encrypted, so that they cost a single chunk when public, and nothing when private.
     pub fn chunk_file(
         file_path: &Path,
         chunk_dir: &Path,
         include_data_map_in_chunks: bool,
+    ) -> ChunkFileResult {
+        Self::chunk_file_inner(file_path, chunk_dir, include_data_map_in_chunks, None)
+    }
+
+    /// Chunks the file as `chunk_file` does, encrypting its content with `user_key` before
+    /// self-encrypting it, so that it can't be read with the data map alone.
+    ///
+    /// The file is read with a `FilesDownload` set with the same key.
+    pub fn chunk_file_with_user_key(
+        file_path: &Path,
+        chunk_dir: &Path,
+        include_data_map_in_chunks: bool,
+        user_key: &UserKey,
+    ) -> ChunkFileResult {
+        Self::chunk_file_inner(
+            file_path,
+            chunk_dir,
+            include_data_map_in_chunks,
+            Some(user_key),
+        )
+    }
+
+    fn chunk_file_inner(
+        file_path: &Path,
+        chunk_dir: &Path,
+        include_data_map_in_chunks: bool,
+        user_key: Option<&UserKey>,
     ) -> ChunkFileResult {
         let file = File::open(file_path)?;
         let metadata = file.metadata()?;
         let file_size = metadata.len();
 
         let (head_address, data_map_chunk, mut chunks_paths) =
-            if file_size < MIN_ENCRYPTABLE_BYTES as u64 {
+            if user_key.is_none() && file_size <= MAX_PACKED_FILE_SIZE as u64 {
+                let data_map_chunk = pack_file(Bytes::from(fs::read(file_path)?))?;
+                (*data_map_chunk.name(), data_map_chunk, vec![])
+            } else if file_size < MIN_ENCRYPTABLE_BYTES as u64 {
                 Err(ChunksError::FileTooSmall)?
+            } else if let Some(user_key) = user_key {
+                let user_encryption = user_key.new_file_encryption();
+                // the encrypted copy is self-encrypted, then dropped.
+                let encrypted_dir = tempfile::tempdir_in(chunk_dir)?;
+                let encrypted_path = encrypted_dir.path().join("user_encrypted");
+                user_key
+                    .file_cipher(&user_encryption)?
+                    .apply_to_file(file_path, &encrypted_path)?;
+                let (data_map_chunk, chunks) =
+                    encrypt_large(&encrypted_path, chunk_dir, Some(user_encryption))?;
+                (*data_map_chunk.name(), data_map_chunk, chunks)
             } else {
-                let (data_map_chunk, chunks) = encrypt_large(file_path, chunk_dir)?;
+                let (data_map_chunk, chunks) = encrypt_large(file_path, chunk_dir, None)?;
                 (*data_map_chunk.name(), data_map_chunk, chunks)
             };
 
@@ -190,6 +244,14 @@ impl FilesApi {
 /// Does not store anything to the network.
 ///
 /// Returns data map as a chunk, and the resulting chunks
-fn encrypt_large(file_path: &Path, output_dir: &Path) -> Result<(Chunk, Vec<(XorName, PathBuf)>)> {
-    Ok(crate::chunks::encrypt_large(file_path, output_dir)?)
+fn encrypt_large(
+    file_path: &Path,
+    output_dir: &Path,
+    user_encryption: Option<UserEncryption>,
+) -> Result<(Chunk, Vec<(XorName, PathBuf)>)> {
+    Ok(crate::chunks::encrypt_large(
+        file_path,
+        output_dir,
+        user_encryption,
+    )?)
 }
@@ -7,11 +7,11 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    chunks::{DataMapLevel, Error as ChunksError},
+    chunks::{DataMapLevel, Error as ChunksError, FileCipher, UserKey},
     error::{Error as ClientError, Result},
     Client, FilesApi, BATCH_SIZE,
 };
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures::StreamExt;
 use itertools::Itertools;
 use self_encryption::{decrypt_full_set, DataMap, EncryptedChunk, StreamSelfDecryptor};
@@ -19,7 +19,10 @@ use sn_networking::target_arch::Instant;
 use sn_protocol::storage::{Chunk, ChunkAddress, RetryStrategy};
 
 use std::{collections::HashMap, fs, path::PathBuf};
-use tokio::sync::mpsc::{self};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::mpsc::{self},
+};
 use xor_name::XorName;
 
 /// The events emitted from the download process.
@@ -37,6 +40,16 @@ pub enum FilesDownloadEvent {
     Error,
 }
 
+// What the head chunk of a file unpacks to.
+#[allow(clippy::large_enum_variant)]
+enum UnpackedFile {
+    // The content is self-encrypted into the chunks of the data map, and encrypted with a user key
+    // first if a cipher is given.
+    Chunked(DataMap, Option<FileCipher>),
+    // The content is small enough to be packed into the data map itself.
+    Packed(Bytes),
+}
+
 // Internally used to differentiate between the various ways that the downloaded chunks are returned.
 enum DownloadReturnType {
     EncryptedChunks(Vec<EncryptedChunk>),
@@ -52,6 +65,7 @@ pub struct FilesDownload {
     batch_size: usize,
     show_holders: bool,
     retry_strategy: RetryStrategy,
+    user_key: Option<UserKey>,
     // API
     api: FilesApi,
     // Events
@@ -67,6 +81,7 @@ impl FilesDownload {
             batch_size: BATCH_SIZE,
             show_holders: false,
             retry_strategy: RetryStrategy::Quick,
+            user_key: None,
             api: files_api,
             event_sender: None,
             logged_event_sender_absence: false,
@@ -97,6 +112,14 @@ impl FilesDownload {
         self
     }
 
+    /// Sets the key the files uploaded with `FilesApi::chunk_file_with_user_key` are decrypted with.
+    ///
+    /// By default, no key is set, and such files can't be downloaded.
+    pub fn set_user_key(mut self, user_key: UserKey) -> Self {
+        self.user_key = Some(user_key);
+        self
+    }
+
     /// Returns a receiver for file download events.
     /// This method is optional and the download process can be performed without it.
     pub fn get_events(&mut self) -> mpsc::Receiver<FilesDownloadEvent> {
@@ -152,8 +175,19 @@ impl FilesDownload {
             .await?;
 
         // First try to deserialize a LargeFile, if it works, we go and seek it.
-        // If an error occurs, we consider it to be a SmallFile.
-        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
+        // If an error occurs, we consider it to be a SmallFile, unless it is about the user key.
+        let unpacked = match self.unpack_file(chunk.clone()).await {
+            Err(ClientError::Chunks(
+                err @ (ChunksError::UserKeyRequired | ChunksError::WrongUserKey),
+            )) => return Err(err.into()),
+            Ok(UnpackedFile::Packed(mut bytes)) => {
+                let _ = bytes.split_to(position.min(bytes.len()));
+                bytes.truncate(length);
+                return Ok(bytes);
+            }
+            unpacked => unpacked,
+        };
+        if let Ok(UnpackedFile::Chunked(data_map, mut cipher)) = unpacked {
             let info = self_encryption::seek_info(data_map.file_size(), position, length);
             let range = &info.index_range;
             let all_infos = data_map.infos();
@@ -175,7 +209,7 @@ impl FilesDownload {
                     length,
                 )
                 .map_err(ChunksError::SelfEncryption)?;
-                return Ok(bytes);
+                return Ok(decrypt_bytes(cipher.as_mut(), position as u64, bytes));
             } else {
                 error!("IncorrectDownloadOption: expected to get the encrypted chunks back");
                 return Err(ClientError::IncorrectDownloadOption);
@@ -266,38 +300,37 @@ impl FilesDownload {
         data_map_chunk: Option<Chunk>,
         downloaded_file_path: Option<PathBuf>,
     ) -> Result<Option<Bytes>> {
-        let head_chunk = if let Some(chunk) = data_map_chunk {
-            info!("Downloading via supplied local datamap");
-            chunk
-        } else {
-            match self
-                .api
-                .client
-                .get_chunk(address, self.show_holders, Some(self.retry_strategy))
-                .await
-            {
-                Ok(chunk) => chunk,
-                Err(err) => {
-                    error!("Failed to fetch head chunk {address:?}");
-                    return Err(err);
-                }
-            }
-        };
+        let head_chunk = self.get_head_chunk(address, data_map_chunk).await?;
 
         // first try to deserialize a LargeFile, if it works, we go and seek it
-        match self.unpack_chunk(head_chunk.clone()).await {
-            Ok(data_map) => {
+        match self.unpack_file(head_chunk.clone()).await {
+            Ok(UnpackedFile::Packed(bytes)) => {
+                if let Some(path) = downloaded_file_path {
+                    fs::write(path, bytes)?;
+                    Ok(None)
+                } else {
+                    Ok(Some(bytes))
+                }
+            }
+            Ok(UnpackedFile::Chunked(data_map, mut cipher)) => {
                 // read_all emits
                 match self
-                    .read(data_map, downloaded_file_path, false, false)
+                    .read(data_map, downloaded_file_path.clone(), false, false)
                     .await?
                 {
                     DownloadReturnType::EncryptedChunks(_) => {
                         error!("IncorrectDownloadOption: we should not be getting the encrypted chunks back as it is set to false.");
                         Err(ClientError::IncorrectDownloadOption)
                     }
-                    DownloadReturnType::DecryptedBytes(bytes) => Ok(Some(bytes)),
-                    DownloadReturnType::WrittenToFileSystem => Ok(None),
+                    DownloadReturnType::DecryptedBytes(bytes) => {
+                        Ok(Some(decrypt_bytes(cipher.as_mut(), 0, bytes)))
+                    }
+                    DownloadReturnType::WrittenToFileSystem => {
+                        if let (Some(mut cipher), Some(path)) = (cipher, downloaded_file_path) {
+                            cipher.apply_to_file(&path, &path)?;
+                        }
+                        Ok(None)
+                    }
                 }
             }
             Err(ClientError::Chunks(ChunksError::Deserialisation(_))) => {
@@ -330,6 +363,127 @@ impl FilesDownload {
         }
     }
 
+    /// Download a file from the network, writing its decrypted content to `writer` in order.
+    /// If the data_map_chunk is not provided, the DataMap is fetched from the network using the provided address.
+    ///
+    /// At most `batch_size` chunks are fetched or held ahead of the writer, so a slow writer
+    /// slows the download down instead of letting the chunks pile up in memory.
+    pub async fn download_to_writer<W: AsyncWrite + Unpin>(
+        &mut self,
+        address: ChunkAddress,
+        data_map_chunk: Option<Chunk>,
+        writer: &mut W,
+    ) -> Result<()> {
+        // clean up the trackers/stats
+        self.logged_event_sender_absence = false;
+
+        let result = self
+            .download_to_writer_inner(address, data_map_chunk, writer)
+            .await;
+
+        // send an event indicating that the download process completed with an error
+        if result.is_err() {
+            self.send_event(FilesDownloadEvent::Error).await?;
+        }
+
+        // drop the sender to close the channel.
+        let sender = self.event_sender.take();
+        drop(sender);
+
+        result
+    }
+
+    async fn download_to_writer_inner<W: AsyncWrite + Unpin>(
+        &mut self,
+        address: ChunkAddress,
+        data_map_chunk: Option<Chunk>,
+        writer: &mut W,
+    ) -> Result<()> {
+        let head_chunk = self.get_head_chunk(address, data_map_chunk).await?;
+
+        let (data_map, mut cipher) = match self.unpack_file(head_chunk.clone()).await {
+            Ok(UnpackedFile::Chunked(data_map, cipher)) => (data_map, cipher),
+            Ok(UnpackedFile::Packed(bytes)) => {
+                writer.write_all(&bytes).await?;
+                writer.flush().await?;
+                return Ok(());
+            }
+            Err(ClientError::Chunks(ChunksError::Deserialisation(_))) => {
+                warn!("Consider head chunk {address:?} as an SmallFile");
+                self.send_event(FilesDownloadEvent::ChunksCount(1)).await?;
+                self.send_event(FilesDownloadEvent::Downloaded(address))
+                    .await?;
+                writer.write_all(head_chunk.value()).await?;
+                writer.flush().await?;
+                return Ok(());
+            }
+            Err(err) => {
+                error!("Encounter error when unpack head_chunk {address:?} : {err:?}");
+                return Err(err);
+            }
+        };
+
+        self.send_event(FilesDownloadEvent::ChunksCount(data_map.infos().len()))
+            .await?;
+        let now = Instant::now();
+
+        let client_clone = self.api.client.clone();
+        let show_holders = self.show_holders;
+        let retry_strategy = self.retry_strategy;
+        // `buffered` yields the chunks in the order of the datamap, and stops fetching
+        // new ones while the writer is busy with the ones already fetched.
+        let mut stream = futures::stream::iter(data_map.infos())
+            .map(|chunk_info| {
+                Self::get_chunk(
+                    client_clone.clone(),
+                    chunk_info.dst_hash,
+                    chunk_info.index,
+                    show_holders,
+                    retry_strategy,
+                )
+            })
+            .buffered(self.batch_size);
+
+        let mut position = 0;
+        while let Some(result) = stream.next().await {
+            let (chunk_address, index, encrypted_chunk) = result?;
+            self.send_event(FilesDownloadEvent::Downloaded(chunk_address))
+                .await?;
+
+            // each chunk can be decrypted on its own, as the datamap holds the hashes of its neighbours
+            let bytes =
+                self_encryption::decrypt_range(&data_map, &[encrypted_chunk], 0, usize::MAX)
+                    .map_err(ChunksError::SelfEncryption)?;
+            let length = bytes.len();
+            let bytes = decrypt_bytes(cipher.as_mut(), position, bytes);
+            position += length as u64;
+            debug!("Writing {length} decrypted bytes of chunk {index:?}");
+            writer.write_all(&bytes).await?;
+        }
+        writer.flush().await?;
+
+        let elapsed = now.elapsed();
+        info!("Client downloaded file to writer in {elapsed:?}");
+        Ok(())
+    }
+
+    /// Returns the supplied datamap chunk, or fetches the head chunk from the network.
+    async fn get_head_chunk(
+        &self,
+        address: ChunkAddress,
+        data_map_chunk: Option<Chunk>,
+    ) -> Result<Chunk> {
+        if let Some(chunk) = data_map_chunk {
+            info!("Downloading via supplied local datamap");
+            return Ok(chunk);
+        }
+        self.api
+            .client
+            .get_chunk(address, self.show_holders, Some(self.retry_strategy))
+            .await
+            .inspect_err(|_| error!("Failed to fetch head chunk {address:?}"))
+    }
+
     /// The internal logic to download the provided chunks inside the datamap.
     /// If the decrypted_file_path is provided, we return DownloadReturnType::WrittenToFileSystem
     /// If return_encrypted_chunks is true, we return DownloadReturnType::EncryptedChunks
@@ -474,11 +628,29 @@ impl FilesDownload {
     /// Extracts a file DataMapLevel from a chunk.
     /// If the DataMapLevel is not the first level mapping directly to the user's contents,
     /// the process repeats itself until it obtains the first level DataMapLevel.
-    pub async fn unpack_chunk(&mut self, mut chunk: Chunk) -> Result<DataMap> {
+    pub async fn unpack_chunk(&mut self, chunk: Chunk) -> Result<DataMap> {
+        match self.unpack_file(chunk).await? {
+            UnpackedFile::Chunked(data_map, _) => Ok(data_map),
+            UnpackedFile::Packed(_) => Err(ChunksError::PackedFile.into()),
+        }
+    }
+
+    /// Extracts a file DataMap from a chunk, along with the cipher the content of the file is to
+    /// be decrypted with if it was encrypted with a user key, or the content itself if it was
+    /// packed into the data map.
+    async fn unpack_file(&mut self, mut chunk: Chunk) -> Result<UnpackedFile> {
         loop {
             match rmp_serde::from_slice(chunk.value()).map_err(ChunksError::Deserialisation)? {
                 DataMapLevel::First(data_map) => {
-                    return Ok(data_map);
+                    return Ok(UnpackedFile::Chunked(data_map, None));
+                }
+                DataMapLevel::UserEncrypted(data_map, user_encryption) => {
+                    let user_key = self.user_key.as_ref().ok_or(ChunksError::UserKeyRequired)?;
+                    let cipher = user_key.file_cipher(&user_encryption)?;
+                    return Ok(UnpackedFile::Chunked(data_map, Some(cipher)));
+                }
+                DataMapLevel::Packed(bytes) => {
+                    return Ok(UnpackedFile::Packed(bytes));
                 }
                 DataMapLevel::Additional(data_map) => {
                     if let DownloadReturnType::DecryptedBytes(serialized_chunk) =
@@ -522,9 +694,21 @@ impl FilesDownload {
                 Some(retry_strategy),
             )
             .await
-            .map_err(|err| {
-                error!("Chunk missing {address:?} with {err:?}",);
-                ChunksError::ChunkMissing(address)
+            .map_err(|err| match err {
+                // the data map expects the chunk at this index to hash to its address, so a
+                // corrupt chunk fails the download as soon as it comes in.
+                ClientError::CorruptChunk { holder, .. } => {
+                    error!("Chunk {address:?} at index {index} is corrupt, served by {holder:?}");
+                    ChunksError::CorruptChunk {
+                        address,
+                        index,
+                        holder,
+                    }
+                }
+                err => {
+                    error!("Chunk missing {address:?} with {err:?}",);
+                    ChunksError::ChunkMissing(address)
+                }
             })?;
         let encrypted_chunk = EncryptedChunk {
             index,
@@ -533,3 +717,137 @@ impl FilesDownload {
         Ok((chunk.address, index, encrypted_chunk))
     }
 }
+
+// Decrypts the bytes found at `position` in a file encrypted with a user key.
+fn decrypt_bytes(cipher: Option<&mut FileCipher>, position: u64, bytes: Bytes) -> Bytes {
+    match cipher {
+        Some(cipher) => {
+            let mut bytes = BytesMut::from(&bytes[..]);
+            cipher.apply(position, &mut bytes);
+            bytes.freeze()
+        }
+        None => bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{uploader::tests::setup::build_unconnected_client, ChunkCache};
+    use eyre::Result;
+    use rand::RngCore;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn user_encrypted_files_need_the_user_key() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut data = vec![0u8; 3 * self_encryption::MAX_CHUNK_SIZE + 1234];
+        rand::thread_rng().fill_bytes(&mut data);
+        let file_path = temp_dir.path().join("file");
+        fs::write(&file_path, &data)?;
+        let chunk_dir = temp_dir.path().join("chunks");
+        fs::create_dir_all(&chunk_dir)?;
+
+        let user_key = UserKey::from_secret(b"secret");
+        let (head_address, _, _, chunks_paths) =
+            FilesApi::chunk_file_with_user_key(&file_path, &chunk_dir, true, &user_key)?;
+
+        // the chunks are served from the cache, so nothing reaches the network.
+        let cache = ChunkCache::in_memory(u64::MAX);
+        for (_, path) in chunks_paths {
+            let chunk = Chunk::new(Bytes::from(fs::read(path)?));
+            cache.insert(&chunk)?;
+        }
+        let mut client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        client.set_chunk_cache(Some(cache));
+        let files_api = FilesApi::new(client, temp_dir.path().to_path_buf());
+
+        let bytes = FilesDownload::new(files_api.clone())
+            .set_user_key(user_key.clone())
+            .download_file(head_address, None)
+            .await?;
+        assert_eq!(bytes, data);
+        let range = FilesDownload::new(files_api.clone())
+            .set_user_key(user_key.clone())
+            .download_from(head_address, 1_500_000, 1000)
+            .await?;
+        assert_eq!(range, data[1_500_000..1_501_000]);
+        let downloaded_path = temp_dir.path().join("downloaded");
+        FilesDownload::new(files_api.clone())
+            .set_user_key(user_key.clone())
+            .download_file_to_path(head_address, None, downloaded_path.clone())
+            .await?;
+        assert_eq!(fs::read(downloaded_path)?, data);
+        let mut written = vec![];
+        FilesDownload::new(files_api.clone())
+            .set_user_key(user_key)
+            .download_to_writer(head_address, None, &mut written)
+            .await?;
+        assert_eq!(written, data);
+
+        assert!(matches!(
+            FilesDownload::new(files_api.clone())
+                .download_file(head_address, None)
+                .await,
+            Err(ClientError::Chunks(ChunksError::UserKeyRequired))
+        ));
+        assert!(matches!(
+            FilesDownload::new(files_api)
+                .set_user_key(UserKey::from_secret(b"wrong"))
+                .download_from(head_address, 0, 1000)
+                .await,
+            Err(ClientError::Chunks(ChunksError::WrongUserKey))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn small_files_are_packed_into_their_data_map() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let data = b"a small file".to_vec();
+        let file_path = temp_dir.path().join("file");
+        fs::write(&file_path, &data)?;
+        let chunk_dir = temp_dir.path().join("chunks");
+        fs::create_dir_all(&chunk_dir)?;
+
+        // a private data map is kept by the uploader, so there is nothing to upload.
+        let (_, private_data_map, size, chunks_paths) =
+            FilesApi::chunk_file(&file_path, &chunk_dir, false)?;
+        assert_eq!(size, data.len() as u64);
+        assert!(chunks_paths.is_empty());
+        // a public one is the only chunk.
+        let (head_address, public_data_map, _, chunks_paths) =
+            FilesApi::chunk_file(&file_path, &chunk_dir, true)?;
+        assert_eq!(chunks_paths.len(), 1);
+        assert_eq!(chunks_paths[0].0, *head_address.xorname());
+
+        let cache = ChunkCache::in_memory(u64::MAX);
+        cache.insert(&public_data_map)?;
+        let mut client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        client.set_chunk_cache(Some(cache));
+        let files_api = FilesApi::new(client, temp_dir.path().to_path_buf());
+
+        let bytes = FilesDownload::new(files_api.clone())
+            .download_file(head_address, Some(private_data_map))
+            .await?;
+        assert_eq!(bytes, data);
+        let range = FilesDownload::new(files_api.clone())
+            .download_from(head_address, 2, 5)
+            .await?;
+        assert_eq!(range, data[2..7]);
+        let mut written = vec![];
+        FilesDownload::new(files_api.clone())
+            .download_to_writer(head_address, None, &mut written)
+            .await?;
+        assert_eq!(written, data);
+
+        // empty files can be stored too.
+        fs::write(&file_path, b"")?;
+        let (_, empty_data_map, _, _) = FilesApi::chunk_file(&file_path, &chunk_dir, false)?;
+        let bytes = FilesDownload::new(files_api)
+            .download_file(head_address, Some(empty_data_map))
+            .await?;
+        assert!(bytes.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{chunks::Error as ChunksError, error::Result, Error, FilesApi, BATCH_SIZE};
+use futures::{stream, StreamExt, TryStreamExt};
+use sn_protocol::{storage::ChunkAddress, NetworkAddress};
+use sn_transfers::{calculate_royalties_fee, NanoTokens};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+use tempfile::tempdir;
+use walkdir::WalkDir;
+use xor_name::XorName;
+
+/// The estimated cost of uploading a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileCostEstimate {
+    pub storage_cost: NanoTokens,
+    pub royalty_fees: NanoTokens,
+    /// The number of chunks of the file.
+    pub chunks: usize,
+    /// The number of chunks of the file which are already stored on the network, and are free.
+    pub already_stored_chunks: usize,
+}
+
+/// The estimated cost of uploading a set of files, from the current store cost quotes.
+///
+/// Chunks shared by several files are only paid once: they count towards the cost of every file
+/// holding them, but only once towards the total.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CostEstimate {
+    /// The total storage cost of all the files.
+    pub storage_cost: NanoTokens,
+    /// The total network royalties of all the files.
+    pub royalty_fees: NanoTokens,
+    /// The estimate of every file, by path.
+    pub files: BTreeMap<PathBuf, FileCostEstimate>,
+    /// The files which can't be uploaded, as they are too small to be self-encrypted.
+    pub skipped_files: Vec<PathBuf>,
+}
+
+impl CostEstimate {
+    /// The total amount which would be paid, storage cost and royalties included.
+    pub fn total(&self) -> NanoTokens {
+        NanoTokens::from(
+            self.storage_cost
+                .as_nano()
+                .saturating_add(self.royalty_fees.as_nano()),
+        )
+    }
+}
+
+impl FilesApi {
+    /// Estimates the cost of uploading the files at `paths`, directories included, without paying
+    /// for anything.
+    ///
+    /// The files are chunked locally, then a store cost quote is fetched for each chunk. If
+    /// `make_data_public` is set, the data map chunks of the files are counted too, as they get
+    /// uploaded along with the files.
+    pub async fn estimate_cost(
+        &self,
+        paths: &[PathBuf],
+        make_data_public: bool,
+    ) -> Result<CostEstimate> {
+        let chunks_dir = tempdir()?;
+        let mut files = vec![];
+        let mut skipped_files = vec![];
+        for path in paths {
+            for entry in WalkDir::new(path) {
+                let entry = entry.map_err(std::io::Error::from)?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+
+                let file_chunks_dir = chunks_dir.path().join(files.len().to_string());
+                fs::create_dir_all(&file_chunks_dir)?;
+                match Self::chunk_file(entry.path(), &file_chunks_dir, make_data_public) {
+                    Ok((_, _, _, chunks)) => {
+                        let names = chunks.into_iter().map(|(name, _)| name).collect();
+                        files.push((entry.path().to_path_buf(), names));
+                    }
+                    Err(Error::Chunks(ChunksError::FileTooSmall)) => {
+                        skipped_files.push(entry.path().to_path_buf());
+                    }
+                    Err(err) => return Err(err),
+                }
+                // the chunks are not needed any more, only their names.
+                fs::remove_dir_all(&file_chunks_dir)?;
+            }
+        }
+
+        let unique_chunks: BTreeSet<XorName> = files
+            .iter()
+            .flat_map(|(_, names): &(_, Vec<XorName>)| names.iter().copied())
+            .collect();
+        debug!(
+            "Estimating the cost of {} files, holding {} chunks",
+            files.len(),
+            unique_chunks.len()
+        );
+        let quotes: BTreeMap<XorName, NanoTokens> = stream::iter(unique_chunks)
+            .map(|name| async move {
+                let address = NetworkAddress::from_chunk_address(ChunkAddress::new(name));
+                let (_, _, quote) = self
+                    .client
+                    .network
+                    .get_store_costs_from_network(address, vec![])
+                    .await?;
+                Ok::<_, Error>((name, quote.cost))
+            })
+            .buffer_unordered(BATCH_SIZE)
+            .try_collect()
+            .await?;
+
+        let mut estimate = estimate_from_quotes(files, &quotes);
+        estimate.skipped_files = skipped_files;
+        Ok(estimate)
+    }
+}
+
+// Sums the quotes of the chunks of every file, and of all the chunks.
+fn estimate_from_quotes(
+    files: Vec<(PathBuf, Vec<XorName>)>,
+    quotes: &BTreeMap<XorName, NanoTokens>,
+) -> CostEstimate {
+    let cost_of = |name: &XorName| quotes.get(name).copied().unwrap_or_else(NanoTokens::zero);
+    let add = |sum: NanoTokens, cost: NanoTokens| {
+        NanoTokens::from(sum.as_nano().saturating_add(cost.as_nano()))
+    };
+
+    let mut estimate = CostEstimate {
+        storage_cost: NanoTokens::zero(),
+        royalty_fees: NanoTokens::zero(),
+        files: BTreeMap::new(),
+        skipped_files: vec![],
+    };
+    let mut counted = BTreeSet::new();
+    for (path, names) in files {
+        let mut file_estimate = FileCostEstimate {
+            storage_cost: NanoTokens::zero(),
+            royalty_fees: NanoTokens::zero(),
+            chunks: names.len(),
+            already_stored_chunks: 0,
+        };
+        for name in names {
+            let cost = cost_of(&name);
+            if cost.is_zero() {
+                file_estimate.already_stored_chunks += 1;
+            }
+            file_estimate.storage_cost = add(file_estimate.storage_cost, cost);
+            file_estimate.royalty_fees =
+                add(file_estimate.royalty_fees, calculate_royalties_fee(cost));
+            if counted.insert(name) {
+                estimate.storage_cost = add(estimate.storage_cost, cost);
+                estimate.royalty_fees = add(estimate.royalty_fees, calculate_royalties_fee(cost));
+            }
+        }
+        let _ = estimate.files.insert(path, file_estimate);
+    }
+    estimate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_chunks_are_only_counted_once_in_the_total() {
+        let (a, b, shared, stored) = (
+            XorName([1; 32]),
+            XorName([2; 32]),
+            XorName([3; 32]),
+            XorName([4; 32]),
+        );
+        let quotes = BTreeMap::from([
+            (a, NanoTokens::from(850)),
+            (b, NanoTokens::from(1700)),
+            (shared, NanoTokens::from(8500)),
+            (stored, NanoTokens::zero()),
+        ]);
+        let files = vec![
+            (PathBuf::from("one"), vec![a, shared, stored]),
+            (PathBuf::from("two"), vec![b, shared]),
+        ];
+
+        let estimate = estimate_from_quotes(files, &quotes);
+        assert_eq!(
+            estimate.files[&PathBuf::from("one")],
+            FileCostEstimate {
+                storage_cost: NanoTokens::from(9350),
+                royalty_fees: NanoTokens::from(1650),
+                chunks: 3,
+                already_stored_chunks: 1,
+            }
+        );
+        assert_eq!(
+            estimate.files[&PathBuf::from("two")].storage_cost,
+            NanoTokens::from(10200)
+        );
+        assert_eq!(estimate.storage_cost, NanoTokens::from(11050));
+        assert_eq!(estimate.royalty_fees, NanoTokens::from(1950));
+        assert_eq!(estimate.total(), NanoTokens::from(13000));
+    }
+}
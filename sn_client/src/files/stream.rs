@@ -0,0 +1,261 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    chunks::{
+        pack_data_map, pack_file, Error as ChunksError, StreamEncryptor, MAX_PACKED_FILE_SIZE,
+        MIN_STREAMED_SIZE,
+    },
+    error::Result,
+    Error, FilesApi, StoragePaymentResult, BATCH_SIZE,
+};
+use bytes::{Bytes, BytesMut};
+use futures::future::try_join_all;
+use self_encryption::{DataMap, MAX_CHUNK_SIZE};
+use sn_protocol::storage::{Chunk, ChunkAddress};
+use sn_transfers::NanoTokens;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    sync::mpsc,
+    task::spawn_blocking,
+};
+
+impl FilesApi {
+    /// Self-encrypts the data read from `reader` and uploads the resulting chunks, paying for
+    /// them in batches of `BATCH_SIZE` as they are produced. Returns the address of the
+    /// data map chunk, from which the data can be downloaded, along with what was paid.
+    ///
+    /// The address of every chunk is sent to `chunk_sender` once it has been stored.
+    ///
+    /// Each chunk is encrypted on a blocking thread as soon as it has been read, apart from the
+    /// first two, which are keyed with the last ones and so are held until the end. Only those
+    /// and one batch of encrypted chunks are ever held in memory. Sources smaller than three
+    /// chunks are read whole, and those of up to `MAX_PACKED_FILE_SIZE` bytes are packed into
+    /// the data map chunk instead.
+    pub async fn upload_from_reader<R: AsyncRead + Unpin>(
+        &self,
+        mut reader: R,
+        verify_store: bool,
+        chunk_sender: Option<mpsc::Sender<ChunkAddress>>,
+    ) -> Result<(ChunkAddress, StoragePaymentResult)> {
+        let mut paid = StoragePaymentResult {
+            storage_cost: NanoTokens::zero(),
+            royalty_fees: NanoTokens::zero(),
+            skipped_chunks: vec![],
+        };
+        let mut first_blocks = Vec::with_capacity(3);
+        let mut read = 0;
+        while read < MIN_STREAMED_SIZE {
+            let block = read_block(&mut reader).await?;
+            if block.is_empty() {
+                break;
+            }
+            read += block.len();
+            first_blocks.push(block);
+        }
+
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let data_map = if read < MIN_STREAMED_SIZE {
+            let content = first_blocks.concat();
+            if read <= MAX_PACKED_FILE_SIZE {
+                debug!("Packing {read} bytes into their data map");
+                let data_map_chunk = pack_file(Bytes::from(content))?;
+                let head_address = *data_map_chunk.address();
+                batch.push(data_map_chunk);
+                self.upload_stream_batch(&mut batch, &mut paid, verify_store, &chunk_sender)
+                    .await?;
+                return Ok((head_address, paid));
+            }
+
+            debug!("Self-encrypting {read} bytes read whole");
+            let (data_map, encrypted_chunks) =
+                spawn_blocking(move || self_encryption::encrypt(Bytes::from(content)))
+                    .await?
+                    .map_err(ChunksError::from)?;
+            for encrypted_chunk in encrypted_chunks {
+                self.push_to_stream_batch(
+                    &mut batch,
+                    &mut paid,
+                    Chunk::new(encrypted_chunk.content),
+                    verify_store,
+                    &chunk_sender,
+                )
+                .await?;
+            }
+            data_map
+        } else {
+            debug!("Streaming upload of a source of at least {read} bytes");
+            let mut encryptor = StreamEncryptor::default();
+            let mut blocks = first_blocks.into_iter();
+            loop {
+                let block = match blocks.next() {
+                    Some(block) => block,
+                    None => read_block(&mut reader).await?,
+                };
+                if block.is_empty() {
+                    break;
+                }
+                if let Some(job) = encryptor.push(block) {
+                    let (chunk, chunk_info) = spawn_blocking(move || job.run()).await??;
+                    encryptor.add_chunk_info(chunk_info);
+                    self.push_to_stream_batch(
+                        &mut batch,
+                        &mut paid,
+                        chunk,
+                        verify_store,
+                        &chunk_sender,
+                    )
+                    .await?;
+                }
+            }
+            for job in encryptor.finish() {
+                let (chunk, chunk_info) = spawn_blocking(move || job.run()).await??;
+                encryptor.add_chunk_info(chunk_info);
+                self.push_to_stream_batch(
+                    &mut batch,
+                    &mut paid,
+                    chunk,
+                    verify_store,
+                    &chunk_sender,
+                )
+                .await?;
+            }
+            encryptor.data_map()
+        };
+        self.upload_stream_batch(&mut batch, &mut paid, verify_store, &chunk_sender)
+            .await?;
+
+        let head_address = self
+            .upload_stream_data_map(data_map, &mut batch, &mut paid, verify_store, &chunk_sender)
+            .await?;
+        Ok((head_address, paid))
+    }
+
+    /// Uploads the data map, its additional levels first as the head chunk refers to them.
+    /// Returns the address of the head chunk.
+    async fn upload_stream_data_map(
+        &self,
+        data_map: DataMap,
+        batch: &mut Vec<Chunk>,
+        paid: &mut StoragePaymentResult,
+        verify_store: bool,
+        chunk_sender: &Option<mpsc::Sender<ChunkAddress>>,
+    ) -> Result<ChunkAddress> {
+        let (data_map_chunk, additional_chunks) = pack_data_map(data_map)?;
+        for chunk in additional_chunks {
+            self.push_to_stream_batch(batch, paid, chunk, verify_store, chunk_sender)
+                .await?;
+        }
+        self.upload_stream_batch(batch, paid, verify_store, chunk_sender)
+            .await?;
+        let head_address = *data_map_chunk.address();
+        batch.push(data_map_chunk);
+        self.upload_stream_batch(batch, paid, verify_store, chunk_sender)
+            .await?;
+
+        Ok(head_address)
+    }
+
+    /// Adds the chunk to the batch, uploading the batch once it is full.
+    async fn push_to_stream_batch(
+        &self,
+        batch: &mut Vec<Chunk>,
+        paid: &mut StoragePaymentResult,
+        chunk: Chunk,
+        verify_store: bool,
+        chunk_sender: &Option<mpsc::Sender<ChunkAddress>>,
+    ) -> Result<()> {
+        batch.push(chunk);
+        if batch.len() >= BATCH_SIZE {
+            self.upload_stream_batch(batch, paid, verify_store, chunk_sender)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Pays for and uploads the chunks of the batch, leaving it empty, adding the payment to `paid`.
+    async fn upload_stream_batch(
+        &self,
+        batch: &mut Vec<Chunk>,
+        paid: &mut StoragePaymentResult,
+        verify_store: bool,
+        chunk_sender: &Option<mpsc::Sender<ChunkAddress>>,
+    ) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let chunks = std::mem::take(batch);
+        let payment = self
+            .pay_for_chunks(chunks.iter().map(|chunk| *chunk.name()).collect())
+            .await?;
+        paid.storage_cost = paid
+            .storage_cost
+            .checked_add(payment.storage_cost)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        paid.royalty_fees = paid
+            .royalty_fees
+            .checked_add(payment.royalty_fees)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        paid.skipped_chunks.extend(payment.skipped_chunks);
+
+        let addresses: Vec<_> = chunks.iter().map(|chunk| *chunk.address()).collect();
+        let _ = try_join_all(
+            chunks
+                .into_iter()
+                .map(|chunk| self.get_local_payment_and_upload_chunk(chunk, verify_store, None)),
+        )
+        .await?;
+
+        if let Some(sender) = chunk_sender {
+            for address in addresses {
+                if let Err(err) = sender.send(address).await {
+                    warn!("Could not send the address of the uploaded chunk: {err:?}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the next `MAX_CHUNK_SIZE` bytes from `reader`, or what's left of them before the end.
+async fn read_block<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Bytes> {
+    let mut block = BytesMut::zeroed(MAX_CHUNK_SIZE);
+    let mut filled = 0;
+    while filled < MAX_CHUNK_SIZE {
+        let read = reader.read(&mut block[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    block.truncate(filled);
+    Ok(block.freeze())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn blocks_are_read_whole_but_the_last() -> Result<()> {
+        // a pipe hands out at most 1000 bytes per read
+        let (mut reader, mut writer) = tokio::io::duplex(1000);
+        let _handle = tokio::spawn(async move {
+            let data = vec![7u8; 2 * MAX_CHUNK_SIZE + 1234];
+            writer.write_all(&data).await
+        });
+
+        assert_eq!(read_block(&mut reader).await?.len(), MAX_CHUNK_SIZE);
+        assert_eq!(read_block(&mut reader).await?.len(), MAX_CHUNK_SIZE);
+        assert_eq!(read_block(&mut reader).await?.len(), 1234);
+        assert!(read_block(&mut reader).await?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,722 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+mod download;
+mod fs;
+mod refresh;
+mod sync;
+
+pub use fs::{ContainerFs, FsAttr, FsKind};
+pub use refresh::RefreshReport;
+pub use sync::SyncReport;
+
+use super::{error::Result, Client, ClientRegister};
+use crate::{chunks::pack_data_map, Error, FilesApi, FilesDownload};
+use bytes::Bytes;
+use self_encryption::MAX_CHUNK_SIZE;
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::{Chunk, ChunkAddress, RegisterAddress};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    time::SystemTime,
+};
+use xor_name::{XorName, XOR_NAME_LEN};
+
+/// A file stored in a `FilesContainer`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// The address the file can be downloaded from, i.e. the address of its data map chunk.
+    pub data_map: ChunkAddress,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// When the file was last modified, if known.
+    pub modified: Option<SystemTime>,
+    /// The POSIX mode bits of the file, if they were recorded.
+    #[serde(default)]
+    pub mode: Option<u32>,
+}
+
+/// An item listed in a directory of a `FilesContainer`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContainerItem {
+    File(FileEntry),
+    Directory,
+    /// A symbolic link, with its target.
+    Symlink(String),
+}
+
+// The content of a container.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct ContainerTree {
+    entries: BTreeMap<String, FileEntry>,
+    // The earlier versions of the files, from the oldest to the latest one.
+    history: BTreeMap<String, Vec<FileEntry>>,
+    // The symbolic links, with their targets.
+    #[serde(default)]
+    symlinks: BTreeMap<String, String>,
+    // The symbolic links removed or repointed, with their former targets, for the merges of
+    // branches to tell them from the links added on the other branches.
+    #[serde(default)]
+    removed_symlinks: BTreeSet<(String, String)>,
+}
+
+// What the head chunk of a container holds, the Register pointing at the latest head chunk.
+#[derive(Serialize, Deserialize)]
+enum ContainerHead {
+    // The tree itself, when it is small enough to fit in the head chunk.
+    Inline(ContainerTree),
+    // The address of the data map chunk of the self-encrypted tree.
+    SelfEncrypted(ChunkAddress),
+}
+
+/// A directory tree of files stored on the network.
+///
+/// The container maps `/` separated paths to the data maps of the files, or to the targets of
+/// symbolic links. Directories exist as long as they hold a file or a link. The tree is stored in a chunk, whose address is written in a Register, so the
+/// container keeps the same address when it is modified.
+///
+/// When a file is replaced or removed, its previous version is kept in the history of its path, from
+/// which it can still be downloaded, until the history is pruned.
+#[derive(Clone)]
+pub struct FilesContainer {
+    client: Client,
+    files_api: FilesApi,
+    register: ClientRegister,
+    tree: ContainerTree,
+    modified: bool,
+    preserve_metadata: bool,
+}
+
+impl FilesContainer {
+    /// Creates a new empty container (locally), at a random address.
+    pub fn new(client: Client, wallet_dir: &Path) -> Self {
+        let mut rng = rand::thread_rng();
+        let register = ClientRegister::create(client.clone(), XorName::random(&mut rng));
+        Self {
+            files_api: FilesApi::new(client.clone(), wallet_dir.to_path_buf()),
+            client,
+            register,
+            tree: ContainerTree::default(),
+            // an empty container still has to be stored
+            modified: true,
+            preserve_metadata: false,
+        }
+    }
+
+    /// Downloads a copy of the container from the network.
+    ///
+    /// If the Register has several branches, because the container was modified concurrently, the trees
+    /// are merged, and the histories of all the branches kept. A file or a link replaced or removed
+    /// on a branch is replaced or removed in the merged tree, unless another branch modified it
+    /// too, in which case the entry of the later branch is kept. A file whose history was pruned
+    /// since it was removed may come back, the removal being told from its history.
+    pub async fn retrieve(
+        client: Client,
+        wallet_dir: &Path,
+        address: RegisterAddress,
+    ) -> Result<Self> {
+        let register = ClientRegister::retrieve(client.clone(), address).await?;
+        let mut container = Self {
+            files_api: FilesApi::new(client.clone(), wallet_dir.to_path_buf()),
+            client,
+            register,
+            tree: ContainerTree::default(),
+            modified: false,
+            preserve_metadata: false,
+        };
+
+        let heads = container.register.read();
+        let mut trees = Vec::with_capacity(heads.len());
+        for (_, head) in heads.iter() {
+            let head_xorname =
+                xorname_from_entry(head).ok_or(Error::InvalidContainerHead(address))?;
+            trees.push(container.fetch_tree(head_xorname).await?);
+        }
+        container.tree = merge_trees(trees);
+        container.modified = heads.len() > 1;
+        Ok(container)
+    }
+
+    /// Returns the address of the container on the network.
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Returns true if the container has local changes that have not been synced yet.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Sets whether syncing a folder records, and downloading it restores, the POSIX permissions of
+    /// the files and the symbolic links. Modification times are always recorded.
+    /// Disabled by default.
+    pub fn set_preserve_metadata(&mut self, preserve_metadata: bool) {
+        self.preserve_metadata = preserve_metadata;
+    }
+
+    /// Adds a file at the given path (locally), returning the file it replaced, if any.
+    pub fn add_file(&mut self, path: &str, file: FileEntry) -> Result<Option<FileEntry>> {
+        let path = normalise_path(path)?;
+        self.check_leaf_path(&path)?;
+        if self.tree.symlinks.contains_key(&path) {
+            return Err(Error::InvalidContainerPath(format!("{path} is a symlink")));
+        }
+
+        self.modified = true;
+        let replaced = self.tree.entries.insert(path.clone(), file.clone());
+        if let Some(previous) = &replaced {
+            if previous.data_map != file.data_map {
+                self.record_version(path, previous.clone());
+            }
+        }
+        Ok(replaced)
+    }
+
+    /// Removes the file at the given path (locally).
+    pub fn remove_file(&mut self, path: &str) -> Result<FileEntry> {
+        let path = normalise_path(path)?;
+        let removed = self
+            .tree
+            .entries
+            .remove(&path)
+            .ok_or_else(|| Error::ContainerEntryNotFound(path.clone()))?;
+        self.record_version(path, removed.clone());
+        self.modified = true;
+        Ok(removed)
+    }
+
+    /// Adds a symbolic link to `target` at the given path (locally), returning the target it
+    /// replaced, if any.
+    pub fn add_symlink(&mut self, path: &str, target: &str) -> Result<Option<String>> {
+        let path = normalise_path(path)?;
+        self.check_leaf_path(&path)?;
+        if self.tree.entries.contains_key(&path) {
+            return Err(Error::InvalidContainerPath(format!("{path} is a file")));
+        }
+
+        self.modified = true;
+        let _ = self
+            .tree
+            .removed_symlinks
+            .remove(&(path.clone(), target.to_string()));
+        let replaced = self.tree.symlinks.insert(path.clone(), target.to_string());
+        if let Some(previous) = &replaced {
+            if previous != target {
+                let _ = self.tree.removed_symlinks.insert((path, previous.clone()));
+            }
+        }
+        Ok(replaced)
+    }
+
+    /// Removes the symbolic link at the given path (locally), returning its target.
+    pub fn remove_symlink(&mut self, path: &str) -> Result<String> {
+        let path = normalise_path(path)?;
+        let target = self
+            .tree
+            .symlinks
+            .remove(&path)
+            .ok_or_else(|| Error::ContainerEntryNotFound(path.clone()))?;
+        let _ = self.tree.removed_symlinks.insert((path, target.clone()));
+        self.modified = true;
+        Ok(target)
+    }
+
+    /// Removes a directory and all the files and links under it (locally), returning the removed files.
+    pub fn remove_dir(&mut self, path: &str) -> Result<BTreeMap<String, FileEntry>> {
+        let path = normalise_path(path)?;
+        let prefix = format!("{path}/");
+        let removed: BTreeMap<_, _> = self
+            .tree
+            .entries
+            .iter()
+            .filter(|(file_path, _)| file_path.starts_with(&prefix))
+            .map(|(file_path, file)| (file_path.clone(), file.clone()))
+            .collect();
+        let removed_symlinks: Vec<_> = self
+            .tree
+            .symlinks
+            .iter()
+            .filter(|(link_path, _)| link_path.starts_with(&prefix))
+            .map(|(link_path, target)| (link_path.clone(), target.clone()))
+            .collect();
+        if removed.is_empty() && removed_symlinks.is_empty() {
+            return Err(Error::ContainerEntryNotFound(path));
+        }
+
+        for removed_symlink in removed_symlinks {
+            let _ = self.tree.symlinks.remove(&removed_symlink.0);
+            let _ = self.tree.removed_symlinks.insert(removed_symlink);
+        }
+
+        self.tree
+            .entries
+            .retain(|file_path, _| !removed.contains_key(file_path));
+        for (file_path, file) in &removed {
+            self.record_version(file_path.clone(), file.clone());
+        }
+        self.modified = true;
+        Ok(removed)
+    }
+
+    /// Returns the file at the given path.
+    pub fn get(&self, path: &str) -> Option<&FileEntry> {
+        normalise_path(path)
+            .ok()
+            .and_then(|path| self.tree.entries.get(&path))
+    }
+
+    /// Returns all the files of the container, by path.
+    pub fn files(&self) -> &BTreeMap<String, FileEntry> {
+        &self.tree.entries
+    }
+
+    /// Returns the targets of all the symbolic links of the container, by path.
+    pub fn symlinks(&self) -> &BTreeMap<String, String> {
+        &self.tree.symlinks
+    }
+
+    /// Lists the files, links and directories directly under the given directory, by name.
+    /// Use an empty path to list the root of the container.
+    pub fn list(&self, dir: &str) -> Result<BTreeMap<String, ContainerItem>> {
+        let prefix = if dir.trim_matches('/').is_empty() {
+            String::new()
+        } else {
+            format!("{}/", normalise_path(dir)?)
+        };
+
+        let files = self
+            .tree
+            .entries
+            .range(prefix.clone()..)
+            .map(|(path, file)| (path, ContainerItem::File(file.clone())));
+        let symlinks = self
+            .tree
+            .symlinks
+            .range(prefix.clone()..)
+            .map(|(path, target)| (path, ContainerItem::Symlink(target.clone())));
+
+        let mut listing = BTreeMap::new();
+        for (path, item) in files.chain(symlinks) {
+            let Some(relative_path) = path.strip_prefix(&prefix) else {
+                continue;
+            };
+            match relative_path.split_once('/') {
+                Some((dir_name, _)) => {
+                    let _ = listing.insert(dir_name.to_string(), ContainerItem::Directory);
+                }
+                None => {
+                    let _ = listing.insert(relative_path.to_string(), item);
+                }
+            }
+        }
+
+        if listing.is_empty() && !prefix.is_empty() {
+            return Err(Error::ContainerEntryNotFound(dir.to_string()));
+        }
+        Ok(listing)
+    }
+
+    /// Returns the earlier versions of the file at the given path, from the oldest to the latest one.
+    /// This includes the last version of a removed file. Each version can be downloaded from its
+    /// data map address, as any other file.
+    pub fn versions(&self, path: &str) -> &[FileEntry] {
+        normalise_path(path)
+            .ok()
+            .and_then(|path| self.tree.history.get(&path))
+            .map_or(&[], |versions| versions.as_slice())
+    }
+
+    /// Drops the earlier versions of every file but the `keep` latest ones (locally), returning the
+    /// number of versions dropped.
+    pub fn prune_history(&mut self, keep: usize) -> usize {
+        let paths: Vec<_> = self.tree.history.keys().cloned().collect();
+        paths
+            .into_iter()
+            .map(|path| self.prune_versions(&path, keep))
+            .sum()
+    }
+
+    /// Drops the earlier versions of the file at the given path but the `keep` latest ones (locally),
+    /// returning the number of versions dropped.
+    pub fn prune_file_history(&mut self, path: &str, keep: usize) -> Result<usize> {
+        let path = normalise_path(path)?;
+        if !self.tree.history.contains_key(&path) {
+            return Err(Error::ContainerEntryNotFound(path));
+        }
+        Ok(self.prune_versions(&path, keep))
+    }
+
+    /// Stores the container on the network, paying for the new tree and for the Register if needed.
+    /// The files themselves are expected to be uploaded already.
+    pub async fn sync(&mut self, verify_store: bool) -> Result<()> {
+        if !self.modified {
+            return Ok(());
+        }
+
+        let head_bytes = Bytes::from(rmp_serde::to_vec(&ContainerHead::Inline(
+            self.tree.clone(),
+        ))?);
+        let mut chunks = vec![];
+        let head_chunk = if head_bytes.len() <= MAX_CHUNK_SIZE {
+            Chunk::new(head_bytes)
+        } else {
+            // too large to fit in a chunk, so the tree is self-encrypted like any other file.
+            let tree_bytes = Bytes::from(rmp_serde::to_vec(&self.tree)?);
+            let (data_map, encrypted_chunks) = self_encryption::encrypt(tree_bytes)?;
+            let (data_map_chunk, additional_chunks) = pack_data_map(data_map)?;
+            let data_map_address = *data_map_chunk.address();
+            chunks.extend(
+                encrypted_chunks
+                    .into_iter()
+                    .map(|chunk| Chunk::new(chunk.content)),
+            );
+            chunks.extend(additional_chunks);
+            chunks.push(data_map_chunk);
+            Chunk::new(Bytes::from(rmp_serde::to_vec(
+                &ContainerHead::SelfEncrypted(data_map_address),
+            )?))
+        };
+        let head_xorname = *head_chunk.name();
+        chunks.push(head_chunk);
+
+        debug!(
+            "Syncing container {:?} with {} chunks",
+            self.address(),
+            chunks.len()
+        );
+        let _ = self
+            .files_api
+            .pay_for_chunks(chunks.iter().map(|chunk| *chunk.name()).collect())
+            .await?;
+        for chunk in chunks {
+            self.files_api
+                .get_local_payment_and_upload_chunk(chunk, verify_store, None)
+                .await?;
+        }
+
+        // writing atop all the current entries merges any concurrent branches.
+        let _ = self.register.write_merging_branches(&head_xorname)?;
+        let mut wallet_client = self.files_api.wallet()?;
+        let _ = self
+            .register
+            .sync(&mut wallet_client, verify_store, None)
+            .await?;
+
+        self.modified = false;
+        Ok(())
+    }
+
+    // Adds a version to the history of a path, unless it is already the latest one.
+    fn record_version(&mut self, path: String, file: FileEntry) {
+        let versions = self.tree.history.entry(path).or_default();
+        if versions.last() != Some(&file) {
+            versions.push(file);
+        }
+    }
+
+    // Drops the oldest versions of a path, so that at most `keep` remain.
+    fn prune_versions(&mut self, path: &str, keep: usize) -> usize {
+        let Some(versions) = self.tree.history.get_mut(path) else {
+            return 0;
+        };
+        let pruned = versions.len().saturating_sub(keep);
+        let _ = versions.drain(..pruned);
+        if versions.is_empty() {
+            let _ = self.tree.history.remove(path);
+        }
+        if pruned > 0 {
+            self.modified = true;
+        }
+        pruned
+    }
+
+    // Checks that a file or a link can be stored at the given path, i.e. that it is not a directory,
+    // nor under a file or a link.
+    fn check_leaf_path(&self, path: &str) -> Result<()> {
+        if self.is_directory(path) {
+            return Err(Error::InvalidContainerPath(format!(
+                "{path} is a directory"
+            )));
+        }
+        if let Some(leaf_ancestor) = ancestors(path).find(|dir| {
+            self.tree.entries.contains_key(*dir) || self.tree.symlinks.contains_key(*dir)
+        }) {
+            return Err(Error::InvalidContainerPath(format!(
+                "{leaf_ancestor} is not a directory"
+            )));
+        }
+        Ok(())
+    }
+
+    // Returns true if some file or link is stored under the given path.
+    fn is_directory(&self, path: &str) -> bool {
+        let prefix = format!("{path}/");
+        let first_file = self.tree.entries.range(prefix.clone()..).next();
+        let first_symlink = self.tree.symlinks.range(prefix.clone()..).next();
+        first_file
+            .map(|(path, _)| path)
+            .into_iter()
+            .chain(first_symlink.map(|(path, _)| path))
+            .any(|stored_path| stored_path.starts_with(&prefix))
+    }
+
+    // Fetches the tree stored under the given head chunk.
+    async fn fetch_tree(&self, head_xorname: XorName) -> Result<ContainerTree> {
+        let head_chunk = self
+            .client
+            .get_chunk(ChunkAddress::new(head_xorname), false, None)
+            .await?;
+        match rmp_serde::from_slice(head_chunk.value())? {
+            ContainerHead::Inline(tree) => Ok(tree),
+            ContainerHead::SelfEncrypted(data_map_address) => {
+                let tree_bytes = FilesDownload::new(self.files_api.clone())
+                    .download_file(data_map_address, None)
+                    .await?;
+                Ok(rmp_serde::from_slice(&tree_bytes)?)
+            }
+        }
+    }
+}
+
+// Merges the trees of the branches of a container, see `FilesContainer::retrieve`.
+fn merge_trees(trees: Vec<ContainerTree>) -> ContainerTree {
+    // whether a branch other than the one at `branch` replaced or removed the file
+    let file_superseded = |branch: usize, path: &String, file: &FileEntry| {
+        trees.iter().enumerate().any(|(other, tree)| {
+            other != branch
+                && tree.entries.get(path) != Some(file)
+                && tree
+                    .history
+                    .get(path)
+                    .is_some_and(|versions| versions.contains(file))
+        })
+    };
+    let symlink_superseded = |branch: usize, link: &(String, String)| {
+        trees.iter().enumerate().any(|(other, tree)| {
+            other != branch
+                && tree.symlinks.get(&link.0) != Some(&link.1)
+                && tree.removed_symlinks.contains(link)
+        })
+    };
+
+    let mut merged = ContainerTree::default();
+    for (branch, tree) in trees.iter().enumerate() {
+        for (path, file) in &tree.entries {
+            if !file_superseded(branch, path, file) {
+                let _ = merged.entries.insert(path.clone(), file.clone());
+            }
+        }
+        for (path, target) in &tree.symlinks {
+            if !symlink_superseded(branch, &(path.clone(), target.clone())) {
+                let _ = merged.symlinks.insert(path.clone(), target.clone());
+            }
+        }
+        for (path, versions) in &tree.history {
+            let merged_versions = merged.history.entry(path.clone()).or_default();
+            for version in versions {
+                if !merged_versions.contains(version) {
+                    merged_versions.push(version.clone());
+                }
+            }
+        }
+        merged
+            .removed_symlinks
+            .extend(tree.removed_symlinks.iter().cloned());
+    }
+    merged
+        .removed_symlinks
+        .retain(|(path, target)| merged.symlinks.get(path) != Some(target));
+    merged
+}
+
+// Turns a path into its canonical form in a container: relative, `/` separated, without
+// empty, `.` or `..` components.
+fn normalise_path(path: &str) -> Result<String> {
+    let components: Vec<_> = path
+        .split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect();
+    if components.is_empty() || components.contains(&"..") {
+        return Err(Error::InvalidContainerPath(path.to_string()));
+    }
+    Ok(components.join("/"))
+}
+
+// The parent directories of a normalised path, from the closest one.
+fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+    path.rmatch_indices('/')
+        .map(move |(index, _)| &path[..index])
+}
+
+// Helper to convert a Register entry into a XorName
+fn xorname_from_entry(entry: &[u8]) -> Option<XorName> {
+    let xorname: [u8; XOR_NAME_LEN] = entry.try_into().ok()?;
+    Some(XorName(xorname))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uploader::tests::setup::build_unconnected_client;
+    use eyre::Result;
+    use tempfile::tempdir;
+
+    fn file(size: u64) -> FileEntry {
+        FileEntry {
+            data_map: ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+            size,
+            modified: None,
+            mode: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn files_are_added_listed_and_removed() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        let mut container = FilesContainer::new(client, temp_dir.path());
+
+        let (readme, photo, song) = (file(10), file(20), file(30));
+        assert_eq!(container.add_file("README.md", readme.clone())?, None);
+        assert_eq!(container.add_file("/photos/./a.jpg", photo.clone())?, None);
+        assert_eq!(
+            container.add_file("music\\rock\\b.mp3", song.clone())?,
+            None
+        );
+        assert_eq!(container.get("photos/a.jpg"), Some(&photo));
+
+        assert_eq!(
+            container.list("")?,
+            BTreeMap::from([
+                ("README.md".to_string(), ContainerItem::File(readme.clone())),
+                ("music".to_string(), ContainerItem::Directory),
+                ("photos".to_string(), ContainerItem::Directory),
+            ])
+        );
+        assert_eq!(
+            container.list("music")?,
+            BTreeMap::from([("rock".to_string(), ContainerItem::Directory)])
+        );
+        assert!(container.list("videos").is_err());
+
+        // a file and a directory can't share a path.
+        assert!(container.add_file("photos", file(1)).is_err());
+        assert!(container.add_file("README.md/c.txt", file(1)).is_err());
+        assert!(container.add_file("../escape", file(1)).is_err());
+
+        // replacing a file returns the previous one.
+        let new_photo = file(21);
+        assert_eq!(
+            container.add_file("photos/a.jpg", new_photo.clone())?,
+            Some(photo)
+        );
+
+        assert_eq!(container.remove_file("README.md")?, readme);
+        assert!(container.remove_file("README.md").is_err());
+        assert_eq!(container.remove_dir("music")?.len(), 1);
+        assert_eq!(
+            container.files(),
+            &BTreeMap::from([("photos/a.jpg".to_string(), new_photo)])
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replaced_and_removed_files_are_kept_in_history() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        let mut container = FilesContainer::new(client, temp_dir.path());
+
+        let versions = [file(1), file(2), file(3)];
+        for version in &versions {
+            let _ = container.add_file("notes.txt", version.clone())?;
+        }
+        // re-adding the same data is not a new version.
+        let _ = container.add_file("notes.txt", versions[2].clone())?;
+        assert_eq!(container.versions("notes.txt"), &versions[..2]);
+        assert!(container.versions("other.txt").is_empty());
+
+        let _ = container.remove_file("notes.txt")?;
+        assert_eq!(container.versions("/notes.txt"), &versions[..]);
+
+        assert_eq!(container.prune_file_history("notes.txt", 2)?, 1);
+        assert_eq!(container.versions("notes.txt"), &versions[1..]);
+        assert!(container.prune_file_history("other.txt", 0).is_err());
+
+        let _ = container.add_file("docs/a.txt", file(4))?;
+        let _ = container.add_file("docs/a.txt", file(5))?;
+        assert_eq!(container.prune_history(0), 3);
+        assert!(container.versions("notes.txt").is_empty());
+        assert!(container.versions("docs/a.txt").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn removals_and_replacements_win_over_the_unmodified_branches() {
+        let (kept, removed, replaced, replacement) = (file(1), file(2), file(3), file(4));
+        let mut base = ContainerTree::default();
+        for (path, file) in [
+            ("kept", &kept),
+            ("removed", &removed),
+            ("replaced", &replaced),
+        ] {
+            let _ = base.entries.insert(path.to_string(), file.clone());
+        }
+        let _ = base.symlinks.insert("link".to_string(), "kept".to_string());
+
+        // one branch removes and replaces files, and removes the link, the other adds a file
+        let mut modified = base.clone();
+        let _ = modified.entries.remove("removed");
+        let _ = modified
+            .history
+            .insert("removed".to_string(), vec![removed.clone()]);
+        let _ = modified
+            .entries
+            .insert("replaced".to_string(), replacement.clone());
+        let _ = modified
+            .history
+            .insert("replaced".to_string(), vec![replaced.clone()]);
+        let _ = modified.symlinks.remove("link");
+        let _ = modified
+            .removed_symlinks
+            .insert(("link".to_string(), "kept".to_string()));
+        let mut added = base.clone();
+        let new_file = file(5);
+        let _ = added.entries.insert("new".to_string(), new_file.clone());
+
+        let expected = BTreeMap::from([
+            ("kept".to_string(), kept),
+            ("new".to_string(), new_file),
+            ("replaced".to_string(), replacement),
+        ]);
+        for trees in [
+            vec![modified.clone(), added.clone()],
+            vec![added.clone(), modified.clone()],
+        ] {
+            let merged = merge_trees(trees);
+            assert_eq!(merged.entries, expected);
+            assert!(merged.symlinks.is_empty());
+            assert_eq!(merged.history.len(), 2);
+        }
+
+        // a file modified on both branches is kept as modified by the later one
+        let mut other_replacement = added;
+        let _ = other_replacement
+            .entries
+            .insert("replaced".to_string(), file(6));
+        let _ = other_replacement
+            .history
+            .insert("replaced".to_string(), vec![replaced]);
+        let merged = merge_trees(vec![modified, other_replacement.clone()]);
+        assert_eq!(
+            merged.entries.get("replaced"),
+            other_replacement.entries.get("replaced")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{FileEntry, FilesContainer};
+use crate::{error::Result, FilesDownload};
+use std::{
+    fs::{self, File},
+    io,
+    path::Path,
+};
+
+impl FilesContainer {
+    /// Downloads all the files of the container into `local_dir`, restoring their modification
+    /// times. If the container preserves metadata, the permissions of the files are restored too,
+    /// as well as the symbolic links (on Unix only).
+    pub async fn download_folder(&self, local_dir: &Path) -> Result<()> {
+        let mut files_download = FilesDownload::new(self.files_api.clone());
+        for (path, file) in self.files() {
+            let local_path = local_dir.join(path);
+            if let Some(parent) = local_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            debug!("Downloading {path} of container {:?}", self.address());
+            files_download
+                .download_file_to_path(file.data_map, None, local_path.clone())
+                .await?;
+            restore_metadata(&local_path, file, self.preserve_metadata)?;
+        }
+
+        if self.preserve_metadata {
+            for (path, target) in self.symlinks() {
+                let local_path = local_dir.join(path);
+                if let Some(parent) = local_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                create_symlink(target, &local_path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Sets the modification time, then the permissions, as these may make the file read-only.
+fn restore_metadata(path: &Path, file: &FileEntry, restore_mode: bool) -> io::Result<()> {
+    if let Some(modified) = file.modified {
+        File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(modified)?;
+    }
+    #[cfg(unix)]
+    if let Some(mode) = file.mode.filter(|_| restore_mode) {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = restore_mode;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &str, path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path).is_ok() {
+        fs::remove_file(path)?;
+    }
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(target: &str, path: &Path) -> io::Result<()> {
+    warn!("Symbolic links are only restored on Unix, not creating {path:?} to {target}");
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use sn_protocol::storage::ChunkAddress;
+    use std::{
+        os::unix::fs::PermissionsExt,
+        time::{Duration, SystemTime},
+    };
+    use tempfile::tempdir;
+    use xor_name::XorName;
+
+    #[test]
+    fn metadata_and_symlinks_are_restored() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("script.sh");
+        fs::write(&path, b"echo")?;
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let file = FileEntry {
+            data_map: ChunkAddress::new(XorName::default()),
+            size: 4,
+            modified: Some(modified),
+            mode: Some(0o750),
+        };
+
+        restore_metadata(&path, &file, false)?;
+        assert_eq!(fs::metadata(&path)?.modified()?, modified);
+        assert_ne!(fs::metadata(&path)?.permissions().mode() & 0o7777, 0o750);
+        restore_metadata(&path, &file, true)?;
+        assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o7777, 0o750);
+
+        let link = temp_dir.path().join("link");
+        create_symlink("script.sh", &link)?;
+        // an existing link is replaced.
+        create_symlink("script.sh", &link)?;
+        assert_eq!(fs::read_link(&link)?, Path::new("script.sh"));
+        assert_eq!(fs::read(&link)?, b"echo");
+        Ok(())
+    }
+}
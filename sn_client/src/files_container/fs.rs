@@ -0,0 +1,274 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{ContainerItem, FilesContainer};
+use crate::{error::Result, Error, FilesDownload};
+use bytes::Bytes;
+use std::{collections::HashMap, time::SystemTime};
+
+/// The kind of an entry of a `ContainerFs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FsKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+/// The attributes of an entry of a `ContainerFs`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FsAttr {
+    pub inode: u64,
+    pub kind: FsKind,
+    /// The size of a file, or the length of the target of a link.
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// The POSIX mode bits of a file, if they were recorded.
+    pub mode: Option<u32>,
+}
+
+/// A read-only view of a `FilesContainer` as a file system, its entries being addressed by inode,
+/// as FUSE does, so that it can be mounted.
+///
+/// The content of the files is fetched on demand, by range, going through the chunk cache of the
+/// client if it has one.
+pub struct ContainerFs {
+    container: FilesContainer,
+    // The path of every inode handed out, the root being the empty path.
+    paths: Vec<String>,
+    inodes: HashMap<String, u64>,
+}
+
+impl ContainerFs {
+    /// The inode of the root directory.
+    pub const ROOT_INODE: u64 = 1;
+
+    pub fn new(container: FilesContainer) -> Self {
+        Self {
+            container,
+            paths: vec![String::new()],
+            inodes: HashMap::from([(String::new(), Self::ROOT_INODE)]),
+        }
+    }
+
+    /// The container being viewed.
+    pub fn container(&self) -> &FilesContainer {
+        &self.container
+    }
+
+    /// Looks up the entry called `name` in the directory `parent`.
+    pub fn lookup(&mut self, parent: u64, name: &str) -> Result<FsAttr> {
+        let parent_path = self.path(parent)?.to_string();
+        if self.kind(&parent_path)? != FsKind::Directory {
+            return Err(Error::InvalidContainerPath(format!(
+                "{parent_path} is not a directory"
+            )));
+        }
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            return Err(Error::InvalidContainerPath(name.to_string()));
+        }
+        let path = join(&parent_path, name);
+        let _ = self.kind(&path)?;
+        let inode = self.inode(path);
+        self.getattr(inode)
+    }
+
+    /// Returns the attributes of the entry.
+    pub fn getattr(&self, inode: u64) -> Result<FsAttr> {
+        let path = self.path(inode)?;
+        let attr = match self.kind(path)? {
+            FsKind::File => {
+                let file = self
+                    .container
+                    .get(path)
+                    .ok_or_else(|| Error::ContainerEntryNotFound(path.to_string()))?;
+                FsAttr {
+                    inode,
+                    kind: FsKind::File,
+                    size: file.size,
+                    modified: file.modified,
+                    mode: file.mode,
+                }
+            }
+            FsKind::Symlink => FsAttr {
+                inode,
+                kind: FsKind::Symlink,
+                size: self.readlink(inode)?.len() as u64,
+                modified: None,
+                mode: None,
+            },
+            FsKind::Directory => FsAttr {
+                inode,
+                kind: FsKind::Directory,
+                size: 0,
+                modified: None,
+                mode: None,
+            },
+        };
+        Ok(attr)
+    }
+
+    /// Lists the entries of the directory, by name.
+    pub fn readdir(&mut self, inode: u64) -> Result<Vec<(String, FsAttr)>> {
+        let path = self.path(inode)?.to_string();
+        let listing = self.container.list(&path)?;
+        let mut entries = Vec::with_capacity(listing.len());
+        for (name, item) in listing {
+            let child = self.inode(join(&path, &name));
+            let attr = match item {
+                ContainerItem::File(file) => FsAttr {
+                    inode: child,
+                    kind: FsKind::File,
+                    size: file.size,
+                    modified: file.modified,
+                    mode: file.mode,
+                },
+                ContainerItem::Directory => FsAttr {
+                    inode: child,
+                    kind: FsKind::Directory,
+                    size: 0,
+                    modified: None,
+                    mode: None,
+                },
+                ContainerItem::Symlink(target) => FsAttr {
+                    inode: child,
+                    kind: FsKind::Symlink,
+                    size: target.len() as u64,
+                    modified: None,
+                    mode: None,
+                },
+            };
+            entries.push((name, attr));
+        }
+        Ok(entries)
+    }
+
+    /// Returns the target of the symbolic link.
+    pub fn readlink(&self, inode: u64) -> Result<String> {
+        let path = self.path(inode)?;
+        self.container
+            .symlinks()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::InvalidContainerPath(format!("{path} is not a symlink")))
+    }
+
+    /// Reads up to `size` bytes of the file from `offset`, fetching only the chunks holding them.
+    pub async fn read(&self, inode: u64, offset: u64, size: usize) -> Result<Bytes> {
+        let path = self.path(inode)?;
+        let file = self
+            .container
+            .get(path)
+            .ok_or_else(|| Error::InvalidContainerPath(format!("{path} is not a file")))?;
+        if offset >= file.size || size == 0 {
+            return Ok(Bytes::new());
+        }
+        let length = size.min(usize::try_from(file.size - offset).unwrap_or(usize::MAX));
+        let offset = usize::try_from(offset)
+            .map_err(|_| Error::InvalidContainerPath(format!("Invalid offset in {path}")))?;
+        trace!("Reading {length} bytes of {path} from {offset}");
+        FilesDownload::new(self.container.files_api.clone())
+            .download_from(file.data_map, offset, length)
+            .await
+    }
+
+    fn path(&self, inode: u64) -> Result<&str> {
+        usize::try_from(inode)
+            .ok()
+            .and_then(|inode| inode.checked_sub(1))
+            .and_then(|index| self.paths.get(index))
+            .map(String::as_str)
+            .ok_or_else(|| Error::ContainerEntryNotFound(format!("inode {inode}")))
+    }
+
+    // Returns the inode of the path, handing out a new one the first time.
+    fn inode(&mut self, path: String) -> u64 {
+        if let Some(inode) = self.inodes.get(&path) {
+            return *inode;
+        }
+        self.paths.push(path.clone());
+        let inode = self.paths.len() as u64;
+        let _ = self.inodes.insert(path, inode);
+        inode
+    }
+
+    fn kind(&self, path: &str) -> Result<FsKind> {
+        if path.is_empty() {
+            Ok(FsKind::Directory)
+        } else if self.container.files().contains_key(path) {
+            Ok(FsKind::File)
+        } else if self.container.symlinks().contains_key(path) {
+            Ok(FsKind::Symlink)
+        } else if self.container.is_directory(path) {
+            Ok(FsKind::Directory)
+        } else {
+            Err(Error::ContainerEntryNotFound(path.to_string()))
+        }
+    }
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{uploader::tests::setup::build_unconnected_client, FileEntry};
+    use eyre::Result;
+    use sn_protocol::storage::ChunkAddress;
+    use tempfile::tempdir;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    async fn entries_are_looked_up_and_listed_by_inode() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        let mut container = FilesContainer::new(client, temp_dir.path());
+        let photo = FileEntry {
+            data_map: ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+            size: 42,
+            modified: None,
+            mode: Some(0o644),
+        };
+        let _ = container.add_file("photos/2024/a.jpg", photo)?;
+        let _ = container.add_symlink("latest", "photos/2024/a.jpg")?;
+        let mut fs = ContainerFs::new(container);
+
+        let root = fs.readdir(ContainerFs::ROOT_INODE)?;
+        let names: Vec<_> = root.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["latest", "photos"]);
+        assert_eq!(root[0].1.kind, FsKind::Symlink);
+        assert_eq!(fs.readlink(root[0].1.inode)?, "photos/2024/a.jpg");
+
+        let photos = fs.lookup(ContainerFs::ROOT_INODE, "photos")?;
+        assert_eq!(photos, root[1].1);
+        let year = fs.lookup(photos.inode, "2024")?;
+        assert_eq!(year.kind, FsKind::Directory);
+        let file = fs.lookup(year.inode, "a.jpg")?;
+        assert_eq!(
+            (file.kind, file.size, file.mode),
+            (FsKind::File, 42, Some(0o644))
+        );
+        // the same entry keeps its inode.
+        assert_eq!(fs.readdir(year.inode)?[0].1, file);
+        assert_eq!(fs.getattr(file.inode)?, file);
+
+        assert!(fs.lookup(ContainerFs::ROOT_INODE, "missing").is_err());
+        assert!(fs.lookup(file.inode, "child").is_err());
+        assert!(fs.lookup(photos.inode, "..").is_err());
+        assert!(fs.readdir(file.inode).is_err());
+        assert!(fs.getattr(1000).is_err());
+        // reading past the end does not reach the network.
+        assert!(fs.read(file.inode, 42, 10).await?.is_empty());
+        Ok(())
+    }
+}
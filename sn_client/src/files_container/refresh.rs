@@ -0,0 +1,190 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{xorname_from_entry, ContainerHead, FilesContainer};
+use crate::{
+    chunks::{DataMapLevel, Error as ChunksError},
+    error::Result,
+    Uploader,
+};
+use self_encryption::{decrypt_full_set, EncryptedChunk};
+use sn_protocol::{storage::ChunkAddress, NetworkAddress};
+use sn_transfers::NanoTokens;
+use std::collections::{BTreeMap, BTreeSet};
+use xor_name::XorName;
+
+/// The outcome of refreshing the chunks of a `FilesContainer`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefreshReport {
+    /// The number of chunks checked.
+    pub checked: usize,
+    /// The chunks held by fewer nodes than wanted, along with the number of nodes which proved
+    /// they hold them.
+    pub under_replicated: BTreeMap<ChunkAddress, usize>,
+    /// The under-replicated chunks which were paid for and pushed again.
+    pub republished: BTreeSet<ChunkAddress>,
+    /// The under-replicated chunks held by the node they would be paid to, which are left for it
+    /// to replicate, as it doesn't take payment for them.
+    pub left_to_replication: BTreeSet<ChunkAddress>,
+    /// The chunks which could be retrieved neither from the network nor from the chunk cache,
+    /// so can't be pushed again.
+    pub lost: BTreeSet<ChunkAddress>,
+    pub storage_cost: NanoTokens,
+    pub royalty_fees: NanoTokens,
+}
+
+impl Default for RefreshReport {
+    fn default() -> Self {
+        Self {
+            checked: 0,
+            under_replicated: BTreeMap::new(),
+            republished: BTreeSet::new(),
+            left_to_replication: BTreeSet::new(),
+            lost: BTreeSet::new(),
+            storage_cost: NanoTokens::zero(),
+            royalty_fees: NanoTokens::zero(),
+        }
+    }
+}
+
+impl FilesContainer {
+    /// Checks how many nodes hold each chunk of the container, i.e. of its tree and of every
+    /// version of its files, and pushes the ones held by fewer than `min_holders` nodes again.
+    ///
+    /// The holders have to prove they hold the exact content of a chunk to be counted. Pushing a
+    /// chunk is only paid for if the node quoting it doesn't hold it already, the nodes holding it
+    /// then replicating it to the others.
+    pub async fn refresh(&self, min_holders: usize, verify_store: bool) -> Result<RefreshReport> {
+        let mut report = RefreshReport::default();
+        let mut to_republish = vec![];
+        for xorname in self.chunk_names(&mut report).await? {
+            let address = ChunkAddress::new(xorname);
+            report.checked += 1;
+            let chunk = match self.client.get_chunk(address, false, None).await {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    warn!("Chunk {address:?} could not be retrieved to be refreshed: {err:?}");
+                    let _ = report.lost.insert(address);
+                    continue;
+                }
+            };
+            let holders = self.client.count_chunk_holders(&chunk).await?;
+            if holders < min_holders {
+                debug!("Chunk {address:?} is only held by {holders} nodes");
+                let _ = report.under_replicated.insert(address, holders);
+                to_republish.push(chunk);
+            }
+        }
+        if to_republish.is_empty() {
+            return Ok(report);
+        }
+
+        let mut uploader = Uploader::new(
+            self.files_api.client.clone(),
+            self.files_api.wallet_dir.clone(),
+        );
+        uploader.set_verify_store(verify_store);
+        // the chunks are known to be stored already, just not by enough nodes.
+        uploader.set_check_existing_chunks(false);
+        uploader.insert_chunks(to_republish);
+        let summary = uploader.start_upload().await?;
+
+        report.storage_cost = summary.storage_cost;
+        report.royalty_fees = summary.royalty_fees;
+        for address in report.under_replicated.keys() {
+            if summary
+                .uploaded_addresses
+                .contains(&NetworkAddress::from_chunk_address(*address))
+            {
+                let _ = report.republished.insert(*address);
+            } else {
+                let _ = report.left_to_replication.insert(*address);
+            }
+        }
+        Ok(report)
+    }
+
+    // The names of all the chunks of the container, recording the files whose data map is lost.
+    async fn chunk_names(&self, report: &mut RefreshReport) -> Result<BTreeSet<XorName>> {
+        let mut names = BTreeSet::new();
+        let mut data_maps: BTreeSet<_> = self
+            .tree
+            .entries
+            .values()
+            .chain(self.tree.history.values().flatten())
+            .map(|file| file.data_map)
+            .collect();
+
+        for (_, head) in self.register.read() {
+            let Some(head_xorname) = xorname_from_entry(&head) else {
+                continue;
+            };
+            let _ = names.insert(head_xorname);
+            let head_address = ChunkAddress::new(head_xorname);
+            match self.client.get_chunk(head_address, false, None).await {
+                Ok(head_chunk) => {
+                    if let ContainerHead::SelfEncrypted(data_map) =
+                        rmp_serde::from_slice(head_chunk.value())?
+                    {
+                        let _ = data_maps.insert(data_map);
+                    }
+                }
+                Err(err) => {
+                    warn!("Head chunk {head_address:?} could not be retrieved: {err:?}");
+                    let _ = report.lost.insert(head_address);
+                }
+            }
+        }
+
+        for data_map in data_maps {
+            match self.file_chunk_names(data_map).await {
+                Ok(file_names) => names.extend(file_names),
+                Err(err) => {
+                    warn!("The chunks of the file at {data_map:?} could not be listed: {err:?}");
+                    let _ = report.lost.insert(data_map);
+                }
+            }
+        }
+        // the lost chunks are already accounted for.
+        names.retain(|name| !report.lost.contains(&ChunkAddress::new(*name)));
+        Ok(names)
+    }
+
+    // The names of the chunks of a file, its data map chunks included.
+    async fn file_chunk_names(&self, data_map: ChunkAddress) -> Result<Vec<XorName>> {
+        let mut names = vec![*data_map.xorname()];
+        let mut chunk = self.client.get_chunk(data_map, false, None).await?;
+        loop {
+            match rmp_serde::from_slice(chunk.value()).map_err(ChunksError::Deserialisation)? {
+                DataMapLevel::First(data_map) | DataMapLevel::UserEncrypted(data_map, _) => {
+                    names.extend(data_map.infos().iter().map(|info| info.dst_hash));
+                    return Ok(names);
+                }
+                DataMapLevel::Packed(_) => return Ok(names),
+                DataMapLevel::Additional(data_map) => {
+                    // the next level is self-encrypted into the chunks of this one.
+                    let mut encrypted_chunks = vec![];
+                    for info in data_map.infos() {
+                        names.push(info.dst_hash);
+                        let level_chunk = self
+                            .client
+                            .get_chunk(ChunkAddress::new(info.dst_hash), false, None)
+                            .await?;
+                        encrypted_chunks.push(EncryptedChunk {
+                            index: info.index,
+                            content: level_chunk.value,
+                        });
+                    }
+                    let bytes = decrypt_full_set(&data_map, &encrypted_chunks)
+                        .map_err(ChunksError::SelfEncryption)?;
+                    chunk = rmp_serde::from_slice(&bytes).map_err(ChunksError::Deserialisation)?;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{FileEntry, FilesContainer};
+use crate::{
+    chunks::Error as ChunksError, error::Result, Error, FilesApi, ProgressEvent, Uploader,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tempfile::tempdir;
+use walkdir::WalkDir;
+
+/// The changes made to a `FilesContainer` to mirror a local folder.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Paths of the files which were not in the container.
+    pub added: Vec<String>,
+    /// Paths of the files whose size or modification time changed.
+    pub updated: Vec<String>,
+    /// Paths of the files which no longer exist locally.
+    pub removed: Vec<String>,
+    /// Paths of the files left out, as they are too small to be self-encrypted.
+    pub skipped: Vec<String>,
+    /// The number of files left as they were.
+    pub unchanged: usize,
+    /// The number of chunks uploaded.
+    pub uploaded_chunks: usize,
+    /// The number of chunks found already stored on the network.
+    pub already_stored_chunks: usize,
+}
+
+impl SyncReport {
+    /// Returns true if the container already mirrored the folder.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+// A local file which has to be uploaded.
+struct LocalFile {
+    path: PathBuf,
+    size: u64,
+    modified: Option<SystemTime>,
+    mode: Option<u32>,
+}
+
+// What has to change in a container to mirror a folder.
+#[derive(Default)]
+struct FolderDiff {
+    report: SyncReport,
+    // The files to upload and the links to store, by container path.
+    files: Vec<(String, LocalFile)>,
+    symlinks: Vec<(String, String)>,
+}
+
+impl FilesContainer {
+    /// Compares the container with the local folder, without changing anything.
+    ///
+    /// A file is considered unchanged when its size and modification time match the ones recorded
+    /// in the container, like `rsync` does by default. Symbolic links are only synced, and
+    /// permissions only compared, if the container preserves metadata.
+    pub fn folder_changes(&self, local_dir: &Path) -> Result<SyncReport> {
+        Ok(self.diff_folder(local_dir)?.report)
+    }
+
+    /// Makes the container mirror the local folder: new and changed files are uploaded, files
+    /// which no longer exist locally are removed, then the container itself is synced.
+    /// Only the files which changed since the last sync are read and uploaded.
+    pub async fn sync_folder(
+        &mut self,
+        local_dir: &Path,
+        verify_store: bool,
+    ) -> Result<SyncReport> {
+        let FolderDiff {
+            mut report,
+            files: to_upload,
+            symlinks,
+        } = self.diff_folder(local_dir)?;
+        info!(
+            "Syncing {local_dir:?} to container {:?}: {} added, {} updated, {} removed",
+            self.address(),
+            report.added.len(),
+            report.updated.len(),
+            report.removed.len()
+        );
+
+        let chunks_dir = tempdir()?;
+        let mut uploader = Uploader::new(
+            self.files_api.client.clone(),
+            self.files_api.wallet_dir.clone(),
+        );
+        uploader.set_verify_store(verify_store);
+        let mut uploaded = BTreeMap::new();
+        for (container_path, file) in to_upload {
+            let file_chunks_dir = chunks_dir.path().join(uploaded.len().to_string());
+            std::fs::create_dir_all(&file_chunks_dir)?;
+            match FilesApi::chunk_file(&file.path, &file_chunks_dir, true) {
+                Ok((data_map, _, size, chunks)) => {
+                    uploader.insert_chunk_paths(chunks);
+                    let entry = FileEntry {
+                        data_map,
+                        size,
+                        modified: file.modified,
+                        mode: file.mode,
+                    };
+                    let _ = uploaded.insert(container_path, (file.path, entry));
+                }
+                Err(Error::Chunks(ChunksError::FileTooSmall)) => {
+                    debug!("Skipping {:?} as it is too small to be uploaded", file.path);
+                    report.added.retain(|path| path != &container_path);
+                    report.updated.retain(|path| path != &container_path);
+                    report.skipped.push(container_path);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if !uploaded.is_empty() {
+            let summary = uploader.start_upload().await?;
+            report.uploaded_chunks = summary.uploaded_count;
+            report.already_stored_chunks = summary.skipped_count;
+            for (path, entry) in uploaded.values() {
+                self.files_api
+                    .client
+                    .report_progress(ProgressEvent::FileCompleted {
+                        path: path.clone(),
+                        data_map: entry.data_map,
+                    });
+            }
+        }
+
+        for path in &report.removed {
+            self.remove_leaf(path)?;
+        }
+        // a file may have replaced a link, or the other way round.
+        for (path, (_, entry)) in uploaded {
+            if self.symlinks().contains_key(&path) {
+                let _ = self.remove_symlink(&path)?;
+            }
+            let _ = self.add_file(&path, entry)?;
+        }
+        for (path, target) in symlinks {
+            if self.files().contains_key(&path) {
+                let _ = self.remove_file(&path)?;
+            }
+            let _ = self.add_symlink(&path, &target)?;
+        }
+        self.sync(verify_store).await?;
+
+        Ok(report)
+    }
+
+    // Returns the changes, along with the local files and links which have to be stored.
+    fn diff_folder(&self, local_dir: &Path) -> Result<FolderDiff> {
+        let mut diff = FolderDiff::default();
+        let mut local_paths = BTreeSet::new();
+
+        for entry in WalkDir::new(local_dir).sort_by_file_name() {
+            let entry = entry.map_err(std::io::Error::from)?;
+            let is_symlink = entry.file_type().is_symlink();
+            if !(entry.file_type().is_file() || is_symlink && self.preserve_metadata) {
+                continue;
+            }
+            let relative_path = entry
+                .path()
+                .strip_prefix(local_dir)
+                .map_err(|_| Error::InvalidContainerPath(format!("{:?}", entry.path())))?;
+            let container_path = relative_path
+                .components()
+                .map(|component| component.as_os_str().to_str())
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| Error::InvalidContainerPath(format!("{relative_path:?}")))?
+                .join("/");
+            let _ = local_paths.insert(container_path.clone());
+
+            if is_symlink {
+                let target = std::fs::read_link(entry.path())?;
+                let target = target
+                    .to_str()
+                    .ok_or_else(|| Error::InvalidContainerPath(format!("{target:?}")))?
+                    .to_string();
+                match self.symlinks().get(&container_path) {
+                    Some(stored) if stored == &target => diff.report.unchanged += 1,
+                    _ => {
+                        diff.report_change(self, &container_path);
+                        diff.symlinks.push((container_path, target));
+                    }
+                }
+                continue;
+            }
+
+            let metadata = entry.metadata().map_err(std::io::Error::from)?;
+            let local_file = LocalFile {
+                path: entry.path().to_path_buf(),
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+                mode: self
+                    .preserve_metadata
+                    .then(|| file_mode(&metadata))
+                    .flatten(),
+            };
+            match self.get(&container_path) {
+                Some(stored)
+                    if stored.size == local_file.size
+                        && stored.modified.is_some()
+                        && stored.modified == local_file.modified
+                        && (!self.preserve_metadata || stored.mode == local_file.mode) =>
+                {
+                    diff.report.unchanged += 1;
+                }
+                _ => {
+                    diff.report_change(self, &container_path);
+                    diff.files.push((container_path, local_file));
+                }
+            }
+        }
+
+        let stored_symlinks = self.symlinks().keys().filter(|_| self.preserve_metadata);
+        diff.report.removed = self
+            .files()
+            .keys()
+            .chain(stored_symlinks)
+            .filter(|path| !local_paths.contains(*path))
+            .cloned()
+            .collect();
+        diff.report.removed.sort();
+
+        Ok(diff)
+    }
+
+    // Removes the file or the link at the given path.
+    fn remove_leaf(&mut self, path: &str) -> Result<()> {
+        if self.symlinks().contains_key(path) {
+            let _ = self.remove_symlink(path)?;
+        } else {
+            let _ = self.remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl FolderDiff {
+    // Records a path as added or updated, depending on whether the container holds something there.
+    fn report_change(&mut self, container: &FilesContainer, path: &str) {
+        if container.get(path).is_some() || container.symlinks().contains_key(path) {
+            self.report.updated.push(path.to_string());
+        } else {
+            self.report.added.push(path.to_string());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &std::fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &std::fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uploader::tests::setup::build_unconnected_client;
+    use eyre::Result;
+    use sn_protocol::storage::ChunkAddress;
+    use std::fs;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    async fn only_new_changed_and_deleted_files_are_reported() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let local_dir = temp_dir.path().join("folder");
+        fs::create_dir_all(local_dir.join("docs"))?;
+        fs::write(local_dir.join("unchanged.txt"), b"unchanged")?;
+        fs::write(local_dir.join("docs/changed.txt"), b"changed")?;
+        fs::write(local_dir.join("docs/new.txt"), b"new")?;
+
+        let client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        let mut container = FilesContainer::new(client, temp_dir.path());
+        let stored = |path: &Path, size| -> Result<FileEntry> {
+            Ok(FileEntry {
+                data_map: ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+                size,
+                modified: Some(fs::metadata(path)?.modified()?),
+                mode: None,
+            })
+        };
+        let _ = container.add_file(
+            "unchanged.txt",
+            stored(&local_dir.join("unchanged.txt"), 9)?,
+        )?;
+        // same modification time, but a different size.
+        let _ = container.add_file(
+            "docs/changed.txt",
+            stored(&local_dir.join("docs/changed.txt"), 3)?,
+        )?;
+        let _ = container.add_file("deleted.txt", stored(&local_dir.join("unchanged.txt"), 9)?)?;
+
+        let report = container.folder_changes(&local_dir)?;
+        assert_eq!(report.added, vec!["docs/new.txt".to_string()]);
+        assert_eq!(report.updated, vec!["docs/changed.txt".to_string()]);
+        assert_eq!(report.removed, vec!["deleted.txt".to_string()]);
+        assert_eq!(report.unchanged, 1);
+        assert!(!report.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn symlinks_and_permissions_are_only_synced_when_preserved() -> Result<()> {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let temp_dir = tempdir()?;
+        let local_dir = temp_dir.path().join("folder");
+        fs::create_dir_all(&local_dir)?;
+        let script = local_dir.join("script.sh");
+        fs::write(&script, b"echo")?;
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o750))?;
+        symlink("script.sh", local_dir.join("link"))?;
+
+        let client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        let mut container = FilesContainer::new(client, temp_dir.path());
+        let _ = container.add_file(
+            "script.sh",
+            FileEntry {
+                data_map: ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+                size: 4,
+                modified: Some(fs::metadata(&script)?.modified()?),
+                mode: None,
+            },
+        )?;
+
+        let report = container.folder_changes(&local_dir)?;
+        assert!(report.is_empty());
+        assert_eq!(report.unchanged, 1);
+
+        container.set_preserve_metadata(true);
+        let diff = container.diff_folder(&local_dir)?;
+        assert_eq!(diff.report.added, vec!["link".to_string()]);
+        assert_eq!(diff.report.updated, vec!["script.sh".to_string()]);
+        assert_eq!(
+            diff.symlinks,
+            vec![("link".to_string(), "script.sh".to_string())]
+        );
+        assert_eq!(diff.files[0].1.mode, Some(0o750));
+
+        let _ = container.add_symlink("link", "script.sh")?;
+        fs::remove_file(local_dir.join("link"))?;
+        assert_eq!(
+            container.folder_changes(&local_dir)?.removed,
+            vec!["link".to_string()]
+        );
+        Ok(())
+    }
+}
@@ -41,16 +41,37 @@ extern crate tracing;
 
 pub mod acc_packet;
 pub mod api;
+mod append_log;
 mod audit;
+mod bandwidth;
+mod capability;
+mod chunk_cache;
 mod chunks;
 mod error;
 mod event;
+mod expiring_data;
 mod faucet;
 mod files;
+mod files_container;
 mod folders;
+#[cfg(feature = "open-metrics")]
+mod metrics;
+mod nrs;
+mod outbox;
+mod payer;
+mod pointer;
+mod profile;
+mod quorum;
 mod register;
+mod retry;
+mod scratchpad;
+mod timeouts;
+mod typed_register;
 mod uploader;
 mod wallet;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+mod watch;
 
 /// Test utils
 #[cfg(feature = "test-utils")]
@@ -66,93 +87,58 @@ pub use sn_transfers as transfers;
 
 const MAX_CONCURRENT_TASKS: usize = 4096;
 
+#[cfg(feature = "open-metrics")]
+pub use self::metrics::ClientMetrics;
 pub use self::{
+    append_log::AppendLog,
     audit::{DagError, SpendDag, SpendDagGet, SpendFault},
+    bandwidth::BandwidthLimits,
+    capability::{Access, Capability, CapabilityTarget},
+    chunk_cache::ChunkCache,
+    chunks::{UserKey, MAX_PACKED_FILE_SIZE},
     error::Error,
-    event::{ClientEvent, ClientEventsBroadcaster, ClientEventsReceiver},
+    event::{ClientEvent, ClientEventsBroadcaster, ClientEventsReceiver, ProgressEvent},
     faucet::fund_faucet_from_genesis_wallet,
     files::{
         download::{FilesDownload, FilesDownloadEvent},
+        estimate::{CostEstimate, FileCostEstimate},
         FilesApi, BATCH_SIZE,
     },
+    files_container::{
+        ContainerFs, ContainerItem, FileEntry, FilesContainer, FsAttr, FsKind, RefreshReport,
+        SyncReport,
+    },
     folders::{FolderEntry, FoldersApi, Metadata},
+    nrs::{NameResolution, NameResolver, SafeUrl},
+    outbox::{Conflict, FlushReport, OperationId, Outbox, OutboxOperation, Resolution},
+    payer::{Payer, PaymentCostMap, PaymentReceipt},
+    profile::{NetworkProfile, NetworkProfiles, NETWORK_PROFILES_FILE, NETWORK_PROFILE_ENV},
+    quorum::{CrossCheckedRead, QuorumRead, ReadQuorum},
     register::ClientRegister,
-    uploader::{UploadCfg, UploadEvent, UploadSummary, Uploader},
+    retry::{RetryClassifier, RetryEvent, RetryObserver, RetryOperation, RetryPolicy},
+    timeouts::TimeoutOperation,
+    typed_register::{EntrySchema, TypedRegister},
+    uploader::{
+        UploadCfg, UploadEvent, UploadManifest, UploadSummary, Uploader, VerificationReport,
+    },
     wallet::{broadcast_signed_spends, send, StoragePaymentResult, WalletClient},
+    watch::RegisterWatch,
 };
 pub(crate) use error::Result;
 
 use sn_networking::Network;
 use std::sync::Arc;
 
-#[cfg(target_arch = "wasm32")]
-use console_error_panic_hook;
-#[cfg(target_arch = "wasm32")]
-use wasm_bindgen::prelude::*;
-#[cfg(target_arch = "wasm32")]
-use web_sys::console;
-
-// This is like the `main` function, except for JavaScript.
-#[cfg(target_arch = "wasm32")]
-#[wasm_bindgen(start)]
-pub async fn main_js() -> std::result::Result<(), JsValue> {
-    // This provides better error messages in debug mode.
-    // It's disabled in release mode so it doesn't bloat up the file size.
-    // #[cfg(debug_assertions)]
-    console_error_panic_hook::set_once();
-
-    console::log_1(&JsValue::from_str("Hello safe world!"));
-
-    // Tracing
-    // TODO: dont log _everything_
-    // right now it logs all libp2p entirely.
-    tracing_wasm::set_as_global_default();
-
-    Ok(())
-}
-
-/// A quick client that only takes some peers to connect to
-#[wasm_bindgen]
-#[cfg(target_arch = "wasm32")]
-pub async fn get_data(peer: &str, data_address: &str) -> std::result::Result<(), JsError> {
-    let bytes = hex::decode(&data_address).expect("Input address is not a hex string");
-    let xor_name = xor_name::XorName(
-        bytes
-            .try_into()
-            .expect("Failed to parse XorName from hex string"),
-    );
-
-    use sn_protocol::storage::ChunkAddress;
-    console::log_1(&JsValue::from_str(peer));
-
-    let the_peer = sn_peers_acquisition::parse_peer_addr(peer)?;
-
-    console::log_1(&JsValue::from_str(&format!(
-        "Provided Peer was {the_peer:?}"
-    )));
-
-    // TODO: We need to tidy this up, the client loops forever in the browser, and eventually crashes
-    // it does _do things_ but errors surface, and even after getting data, it continues...
-    let client = Client::quick_start(Some(vec![the_peer]))
-        .await
-        .map_err(|e| JsError::new(&format!("Client could not start: {e:?}")))?;
-
-    console::log_1(&JsValue::from_str("Client started {chunk:?}"));
-
-    let chunk = client
-        .get_chunk(ChunkAddress::new(xor_name), false, None)
-        .await
-        .map_err(|e| JsError::new(&format!("Client get data failed: {e:?}")))?;
-
-    console::log_1(&JsValue::from_str(&format!("Data found {chunk:?}")));
-
-    Ok(())
-}
-
 /// Client API implementation to store and get data.
 #[derive(Clone)]
 pub struct Client {
     network: Network,
     events_broadcaster: ClientEventsBroadcaster,
     signer: Arc<bls::SecretKey>,
+    chunk_cache: Option<ChunkCache>,
+    retry_policies: retry::RetryPolicies,
+    timeouts: timeouts::OperationTimeouts,
+    bandwidth: bandwidth::BandwidthLimiter,
+    #[cfg(feature = "open-metrics")]
+    metrics: ClientMetrics,
 }
@@ -0,0 +1,189 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{ProgressEvent, RetryOperation};
+use prometheus_client::{
+    encoding::{EncodeLabelSet, EncodeLabelValue},
+    metrics::{
+        counter::Counter,
+        family::Family,
+        histogram::{exponential_buckets, Histogram},
+    },
+    registry::{Registry, Unit},
+};
+use std::time::Duration;
+
+/// The metrics recorded by a client, shared by all its clones.
+///
+/// They can be pulled into the registry of the application with `register`, or served by the
+/// client itself with `Client::serve_metrics`.
+#[derive(Clone, Debug)]
+pub struct ClientMetrics {
+    chunks_uploaded: Counter,
+    chunks_downloaded: Counter,
+    retries: Family<RetryLabels, Counter>,
+    verification_failures: Counter,
+    payments: Histogram,
+    request_latency: Family<RequestLabels, Histogram, fn() -> Histogram>,
+}
+
+/// The requests made to the network whose latency is recorded.
+#[derive(EncodeLabelValue, Hash, Clone, Copy, Eq, PartialEq, Debug)]
+pub(crate) enum RequestKind {
+    PutChunk,
+    GetChunk,
+    PutRegister,
+    GetRegister,
+    PutSpend,
+}
+
+#[derive(EncodeLabelSet, Hash, Clone, Eq, PartialEq, Debug)]
+struct RetryLabels {
+    operation: RetryOperation,
+}
+
+#[derive(EncodeLabelSet, Hash, Clone, Eq, PartialEq, Debug)]
+struct RequestLabels {
+    request: RequestKind,
+}
+
+impl Default for ClientMetrics {
+    fn default() -> Self {
+        Self {
+            chunks_uploaded: Counter::default(),
+            chunks_downloaded: Counter::default(),
+            retries: Family::default(),
+            verification_failures: Counter::default(),
+            // from 1000 nanos to about 1 token.
+            payments: Histogram::new(exponential_buckets(1_000.0, 10.0, 7)),
+            // from 10ms to about 40s.
+            request_latency: Family::new_with_constructor(|| {
+                Histogram::new(exponential_buckets(0.01, 2.0, 13))
+            }),
+        }
+    }
+}
+
+impl ClientMetrics {
+    /// Registers the metrics in the registry, under the `sn_client` prefix.
+    pub fn register(&self, registry: &mut Registry) {
+        let sub_registry = registry.sub_registry_with_prefix("sn_client");
+        sub_registry.register(
+            "chunks_uploaded",
+            "Number of chunks stored on the network",
+            self.chunks_uploaded.clone(),
+        );
+        sub_registry.register(
+            "chunks_downloaded",
+            "Number of chunks fetched from the network",
+            self.chunks_downloaded.clone(),
+        );
+        sub_registry.register(
+            "retries",
+            "Number of times an operation was retried",
+            self.retries.clone(),
+        );
+        sub_registry.register(
+            "verification_failures",
+            "Number of uploaded chunks which could not be verified to be stored",
+            self.verification_failures.clone(),
+        );
+        sub_registry.register_with_unit(
+            "payments",
+            "The amounts paid for storing data, royalties included",
+            Unit::Other("Nano".to_string()),
+            self.payments.clone(),
+        );
+        sub_registry.register_with_unit(
+            "request_latency",
+            "The time taken by the requests to the network",
+            Unit::Seconds,
+            self.request_latency.clone(),
+        );
+    }
+
+    pub(crate) fn record_request(&self, request: RequestKind, latency: Duration) {
+        self.request_latency
+            .get_or_create(&RequestLabels { request })
+            .observe(latency.as_secs_f64());
+        match request {
+            RequestKind::PutChunk => {
+                let _ = self.chunks_uploaded.inc();
+            }
+            RequestKind::GetChunk => {
+                let _ = self.chunks_downloaded.inc();
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn record_retry(&self, operation: RetryOperation) {
+        let _ = self.retries.get_or_create(&RetryLabels { operation }).inc();
+    }
+
+    pub(crate) fn record_progress(&self, event: &ProgressEvent) {
+        match event {
+            ProgressEvent::PaymentMade {
+                storage_cost,
+                royalty_fees,
+                ..
+            } => {
+                let amount = storage_cost
+                    .as_nano()
+                    .saturating_add(royalty_fees.as_nano());
+                self.payments.observe(amount as f64);
+            }
+            ProgressEvent::VerificationFailed { .. } => {
+                let _ = self.verification_failures.inc();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus_client::encoding::text::encode;
+    use sn_protocol::storage::ChunkAddress;
+    use sn_transfers::NanoTokens;
+    use xor_name::XorName;
+
+    #[test]
+    fn recorded_metrics_are_encoded_from_the_registry() -> eyre::Result<()> {
+        let metrics = ClientMetrics::default();
+        let mut registry = Registry::default();
+        metrics.clone().register(&mut registry);
+
+        metrics.record_request(RequestKind::PutChunk, Duration::from_millis(50));
+        metrics.record_request(RequestKind::GetRegister, Duration::from_secs(1));
+        metrics.record_retry(RetryOperation::StoragePayment);
+        metrics.record_progress(&ProgressEvent::PaymentMade {
+            storage_cost: NanoTokens::from(3_000),
+            royalty_fees: NanoTokens::from(450),
+            new_balance: NanoTokens::zero(),
+        });
+        metrics.record_progress(&ProgressEvent::VerificationFailed {
+            address: ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+        });
+
+        let mut encoded = String::new();
+        encode(&mut encoded, &registry)?;
+        for line in [
+            "sn_client_chunks_uploaded_total 1",
+            "sn_client_chunks_downloaded_total 0",
+            "sn_client_retries_total{operation=\"StoragePayment\"} 1",
+            "sn_client_verification_failures_total 1",
+            "sn_client_payments_Nano_sum 3450.0",
+            "sn_client_request_latency_seconds_count{request=\"GetRegister\"} 1",
+        ] {
+            assert!(encoded.contains(line), "{line} missing from {encoded}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, Client, ClientRegister, Error, WalletClient};
+use bls::PublicKey;
+use serde::{Deserialize, Serialize};
+use sn_protocol::NetworkAddress;
+use sn_registers::{AuthoredEntry, EntryHash, Permissions, RegisterAddress};
+use sn_transfers::NanoTokens;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+use xor_name::XorName;
+
+/// The scheme of the URLs resolved by the naming system.
+const SAFE_URL_SCHEME: &str = "safe://";
+/// The query parameter selecting a version of a name's map.
+const VERSION_PARAM: &str = "v=";
+/// The length a name or a subname can be at most.
+const MAX_NAME_LEN: usize = 63;
+/// The meta of the registry Register, combined with the key of its owner.
+const NRS_REGISTRY_META: &[u8] = b"sn_nrs_registry";
+/// Prefixes the name in the meta of the map Register of a name.
+const NRS_MAP_META: &[u8] = b"sn_nrs_map";
+/// How many Pointers are followed at most when resolving a name, so cycles are bounded.
+const MAX_POINTER_HOPS: usize = 8;
+
+/// A URL of the naming system, `safe://<name>[/<subname>...][?v=<version>]`.
+///
+/// The name is the one registered, and the subnames, if any, select one of the targets
+/// the owner of the name mapped under it, e.g. `safe://alice/blog`. The version selects
+/// the state of the name's map at which to resolve it, the latest one being used if unset.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SafeUrl {
+    name: String,
+    subnames: Vec<String>,
+    version: Option<u64>,
+}
+
+impl SafeUrl {
+    /// Parse a `safe://` URL.
+    pub fn parse(url: &str) -> Result<Self> {
+        let invalid = || Error::InvalidSafeUrl(url.to_string());
+        let rest = url.strip_prefix(SAFE_URL_SCHEME).ok_or_else(invalid)?;
+        let (path, version) = match rest.split_once('?') {
+            Some((path, query)) => {
+                let version = query
+                    .strip_prefix(VERSION_PARAM)
+                    .and_then(|version| version.parse().ok())
+                    .ok_or_else(invalid)?;
+                (path, Some(version))
+            }
+            None => (rest, None),
+        };
+
+        let mut labels = path.trim_end_matches('/').split('/');
+        let name = labels.next().unwrap_or_default().to_string();
+        let subnames: Vec<String> = labels.map(str::to_string).collect();
+        if !is_valid_label(&name) || !subnames.iter().all(|subname| is_valid_label(subname)) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name,
+            subnames,
+            version,
+        })
+    }
+
+    /// The name registered.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The subnames under the name, outermost first.
+    pub fn subnames(&self) -> &[String] {
+        &self.subnames
+    }
+
+    /// The version of the name's map to resolve the URL at, the latest one if `None`.
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+
+    /// Return the URL at another version of the name's map.
+    pub fn at_version(mut self, version: Option<u64>) -> Self {
+        self.version = version;
+        self
+    }
+
+    // The key of the target in the name's map, empty for the name's own target.
+    fn subname_key(&self) -> String {
+        self.subnames.join("/")
+    }
+}
+
+impl FromStr for SafeUrl {
+    type Err = Error;
+
+    fn from_str(url: &str) -> Result<Self> {
+        Self::parse(url)
+    }
+}
+
+impl fmt::Display for SafeUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{SAFE_URL_SCHEME}{}", self.name)?;
+        for subname in &self.subnames {
+            write!(f, "/{subname}")?;
+        }
+        if let Some(version) = self.version {
+            write!(f, "?{VERSION_PARAM}{version}")?;
+        }
+        Ok(())
+    }
+}
+
+/// What a URL resolved to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameResolution {
+    /// The version of the name's map the URL was resolved at.
+    pub version: u64,
+    /// The address the URL resolved to, after following the Pointers it was mapped to, if any.
+    pub target: NetworkAddress,
+}
+
+/// A claim of a name, written by its owner to the registry.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct NameClaim {
+    name: String,
+    /// The Register holding the versions of the name's map, owned by the claimer.
+    map: RegisterAddress,
+}
+
+/// A version of the map of a name, written to its Register atop the previous ones.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct NameMap {
+    version: u64,
+    /// The targets by subname, the name's own target being under the empty one.
+    targets: BTreeMap<String, NetworkAddress>,
+}
+
+/// Resolves human-readable `safe://` URLs to network addresses, and registers names.
+///
+/// Names are claimed first-come, first-served in a registry, a Register anyone can write to,
+/// identified by the key of its owner: each claim is signed by the claimer, and when a name
+/// was claimed more than once, the earliest claim in the registry's history wins.
+///
+/// A claim links the name to a Register owned by the claimer, holding the versions of the
+/// name's map from subnames to targets. Each update writes a new version, so that URLs can be
+/// resolved at any past version. Targets can be any network address; when they're Pointers,
+/// they are followed, so that a name stays stable while the content behind it moves.
+#[derive(Clone)]
+pub struct NameResolver {
+    client: Client,
+    registry: RegisterAddress,
+}
+
+impl NameResolver {
+    /// A resolver of the names in the registry owned by `registry_owner`.
+    pub fn new(client: Client, registry_owner: PublicKey) -> Self {
+        let registry =
+            RegisterAddress::new(XorName::from_content(NRS_REGISTRY_META), registry_owner);
+        Self { client, registry }
+    }
+
+    /// Create a new registry owned by the client's signer, returning its resolver
+    /// along with the cost paid for it.
+    pub async fn create_registry(
+        client: Client,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(Self, NanoTokens)> {
+        let resolver = Self::new(client.clone(), client.signer_pk());
+        let (_, storage_cost, royalties) = ClientRegister::create_online(
+            client,
+            resolver.registry.meta(),
+            wallet_client,
+            verify_store,
+            Permissions::new_anyone_can_write(),
+        )
+        .await?;
+        let cost = storage_cost
+            .checked_add(royalties)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        Ok((resolver, cost))
+    }
+
+    /// The address of the registry names are claimed in.
+    pub fn registry(&self) -> &RegisterAddress {
+        &self.registry
+    }
+
+    /// Register a name owned by the client's signer, resolving to `target`, returning the
+    /// cost paid for it.
+    pub async fn register(
+        &self,
+        name: &str,
+        target: NetworkAddress,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<NanoTokens> {
+        let url = SafeUrl::parse(&format!("{SAFE_URL_SCHEME}{name}"))?;
+        let mut registry = ClientRegister::retrieve(self.client.clone(), self.registry).await?;
+        if find_claim(&registry, url.name()).await?.is_some() {
+            return Err(Error::NameAlreadyRegistered(url.name().to_string()));
+        }
+
+        // store the first version of the map before claiming the name
+        let map_meta = XorName::from_content_parts(&[NRS_MAP_META, url.name().as_bytes()]);
+        let mut map = ClientRegister::create(self.client.clone(), map_meta);
+        let first = NameMap {
+            version: 0,
+            targets: BTreeMap::from([(String::new(), target)]),
+        };
+        let _ = map.write_merging_branches(&rmp_serde::to_vec(&first)?)?;
+        let (map_cost, map_royalties) = map.sync(wallet_client, verify_store, None).await?;
+
+        let claim = NameClaim {
+            name: url.name().to_string(),
+            map: *map.address(),
+        };
+        let entry = AuthoredEntry::new(
+            self.registry,
+            rmp_serde::to_vec(&claim)?,
+            self.client.signer(),
+        )?;
+        let tips = registry.read().into_iter().map(|(hash, _)| hash).collect();
+        let _ = registry.write_authored_atop(&entry, &tips)?;
+        let (claim_cost, claim_royalties) =
+            registry.sync(wallet_client, verify_store, None).await?;
+
+        // someone else may have claimed the name concurrently, and earlier
+        if find_claim(&registry, url.name()).await? != Some(claim) {
+            return Err(Error::NameAlreadyRegistered(url.name().to_string()));
+        }
+
+        [map_royalties, claim_cost, claim_royalties]
+            .into_iter()
+            .try_fold(map_cost, |total, cost| total.checked_add(cost))
+            .ok_or(Error::TotalPriceTooHigh)
+    }
+
+    /// Map the URL's subnames, or the name itself if it has none, to `target` in a new version
+    /// of the name's map, returning that version. Only the owner of the name can.
+    pub async fn set_target(
+        &self,
+        url: &SafeUrl,
+        target: NetworkAddress,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<u64> {
+        self.update_map(url, Some(target), wallet_client, verify_store)
+            .await
+    }
+
+    /// Unmap the URL's subnames in a new version of the name's map, returning that version.
+    /// Only the owner of the name can.
+    pub async fn remove_subname(
+        &self,
+        url: &SafeUrl,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<u64> {
+        if url.subnames().is_empty() {
+            return Err(Error::InvalidSafeUrl(url.to_string()));
+        }
+        self.update_map(url, None, wallet_client, verify_store)
+            .await
+    }
+
+    /// Resolve the URL to the address it's mapped to, at the version it selects.
+    pub async fn resolve(&self, url: &SafeUrl) -> Result<NameResolution> {
+        let map_register = self.map_register(url).await?;
+        let (_, map) = match url.version() {
+            Some(version) => map_at_version(&map_register, version).await?,
+            None => latest_map(&map_register).await?,
+        }
+        .ok_or_else(|| Error::NoSuchNameVersion(url.to_string()))?;
+
+        let mut target = map
+            .targets
+            .get(&url.subname_key())
+            .cloned()
+            .ok_or_else(|| Error::NoSuchSubname(url.to_string()))?;
+        for _ in 0..MAX_POINTER_HOPS {
+            let NetworkAddress::PointerAddress(pointer) = target else {
+                return Ok(NameResolution {
+                    version: map.version,
+                    target,
+                });
+            };
+            target = self.client.get_pointer(pointer).await?.target().clone();
+        }
+        Err(Error::TooManyPointerHops(url.to_string()))
+    }
+
+    /// List the subnames mapped under the name of the URL, at the version it selects,
+    /// along with their targets.
+    pub async fn subnames(&self, url: &SafeUrl) -> Result<BTreeMap<String, NetworkAddress>> {
+        let map_register = self.map_register(url).await?;
+        let (_, map) = match url.version() {
+            Some(version) => map_at_version(&map_register, version).await?,
+            None => latest_map(&map_register).await?,
+        }
+        .ok_or_else(|| Error::NoSuchNameVersion(url.to_string()))?;
+        Ok(map
+            .targets
+            .into_iter()
+            .filter(|(subname, _)| !subname.is_empty())
+            .collect())
+    }
+
+    // Write a new version of the map of the URL's name, mapping or unmapping its subnames.
+    async fn update_map(
+        &self,
+        url: &SafeUrl,
+        target: Option<NetworkAddress>,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<u64> {
+        let mut map_register = self.map_register(url).await?;
+        let mut map = latest_map(&map_register)
+            .await?
+            .map(|(_, map)| map)
+            .unwrap_or_default();
+
+        let key = url.subname_key();
+        let changed = match target {
+            Some(target) => map.targets.insert(key, target.clone()) != Some(target),
+            None => map.targets.remove(&key).is_some(),
+        };
+        if !changed {
+            return Ok(map.version);
+        }
+
+        map.version += 1;
+        let _ = map_register.write_merging_branches(&rmp_serde::to_vec(&map)?)?;
+        let _ = map_register.sync(wallet_client, verify_store, None).await?;
+        Ok(map.version)
+    }
+
+    // Retrieve the Register of the map of the URL's name.
+    async fn map_register(&self, url: &SafeUrl) -> Result<ClientRegister> {
+        let registry = ClientRegister::retrieve(self.client.clone(), self.registry).await?;
+        let claim = find_claim(&registry, url.name())
+            .await?
+            .ok_or_else(|| Error::NameNotRegistered(url.name().to_string()))?;
+        ClientRegister::retrieve(self.client.clone(), claim.map).await
+    }
+}
+
+// Whether the label is a valid name or subname: lowercase alphanumerics and inner hyphens.
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= MAX_NAME_LEN
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+// The earliest valid claim of the name in the registry's history, if any.
+async fn find_claim(registry: &ClientRegister, name: &str) -> Result<Option<NameClaim>> {
+    for (hash, _) in registry.tips_history() {
+        let authored = match registry.get_authored(hash).await {
+            Ok(Some(authored)) => authored,
+            Ok(None) => continue,
+            Err(err) => {
+                warn!("Ignoring invalid entry {hash:?} of the names registry: {err:?}");
+                continue;
+            }
+        };
+        let Ok(claim) = rmp_serde::from_slice::<NameClaim>(authored.content()) else {
+            warn!("Ignoring invalid claim {hash:?} in the names registry");
+            continue;
+        };
+        // a name can only be linked to a map owned by its claimer
+        if claim.name == name && claim.map.owner() == authored.author() {
+            return Ok(Some(claim));
+        }
+    }
+    Ok(None)
+}
+
+// Decode a version of a name's map.
+async fn get_map(map_register: &ClientRegister, hash: EntryHash) -> Result<NameMap> {
+    let payload = map_register.get_payload(hash).await?;
+    rmp_serde::from_slice(&payload).map_err(|_| Error::InvalidNameMap(hash))
+}
+
+// The latest version of a name's map, the greatest hash breaking the ties between branches.
+async fn latest_map(map_register: &ClientRegister) -> Result<Option<(EntryHash, NameMap)>> {
+    let mut latest: Option<(EntryHash, NameMap)> = None;
+    for (hash, _) in map_register.read() {
+        let map = get_map(map_register, hash).await?;
+        if latest.as_ref().is_none_or(|(latest_hash, latest)| {
+            (map.version, hash) > (latest.version, *latest_hash)
+        }) {
+            latest = Some((hash, map));
+        }
+    }
+    Ok(latest)
+}
+
+// The given version of a name's map, the greatest hash breaking the ties between branches.
+async fn map_at_version(
+    map_register: &ClientRegister,
+    version: u64,
+) -> Result<Option<(EntryHash, NameMap)>> {
+    let mut found: Option<(EntryHash, NameMap)> = None;
+    for (hash, _) in map_register.tips_history() {
+        let map = get_map(map_register, hash).await?;
+        if map.version == version
+            && found
+                .as_ref()
+                .is_none_or(|(found_hash, _)| hash > *found_hash)
+        {
+            found = Some((hash, map));
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_urls_are_parsed_and_displayed() -> Result<()> {
+        let url = SafeUrl::parse("safe://alice/blog")?;
+        assert_eq!(url.name(), "alice");
+        assert_eq!(url.subnames(), ["blog".to_string()]);
+        assert_eq!(url.version(), None);
+        assert_eq!(url.to_string(), "safe://alice/blog");
+
+        let url: SafeUrl = "safe://alice/blog/2024?v=3".parse()?;
+        assert_eq!(url.subname_key(), "blog/2024");
+        assert_eq!(url.version(), Some(3));
+        assert_eq!(url.to_string(), "safe://alice/blog/2024?v=3");
+        assert_eq!(url.at_version(None).to_string(), "safe://alice/blog/2024");
+
+        let url = SafeUrl::parse("safe://alice/")?;
+        assert!(url.subnames().is_empty());
+        assert_eq!(url.subname_key(), "");
+
+        for invalid in [
+            "alice/blog",
+            "http://alice",
+            "safe://",
+            "safe://Alice",
+            "safe://alice//blog",
+            "safe://-alice",
+            "safe://alice?v=latest",
+            "safe://alice?version=1",
+        ] {
+            assert!(
+                matches!(SafeUrl::parse(invalid), Err(Error::InvalidSafeUrl(_))),
+                "{invalid} should be invalid"
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, Client, ClientEvent, ClientRegister, Error, FilesApi, Uploader};
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::ChunkAddress;
+use sn_registers::{Entry, EntryHash, RegisterAddress};
+use sn_transfers::{HotWallet, MainPubkey, NanoTokens, Transfer};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{sync::broadcast::error::RecvError, task::JoinHandle};
+use xor_name::XorName;
+
+/// The name of the directory holding the outbox, in the root directory of the client.
+const OUTBOX_DIR_NAME: &str = "outbox";
+const OPERATION_EXTENSION: &str = "op";
+/// How often a flush interrupted by a network error is retried, if no reconnection is noticed.
+const FLUSH_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The id of an operation queued in an `Outbox`, the operations being flushed in the order of their ids.
+pub type OperationId = u64;
+
+/// An operation queued in an `Outbox`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum OutboxOperation {
+    /// Uploads a file, which was chunked when it was queued.
+    UploadFile {
+        path: PathBuf,
+        data_map: ChunkAddress,
+        chunks: Vec<(XorName, PathBuf)>,
+    },
+    /// Writes an entry to a Register, on top of the entries it held when the write was queued.
+    WriteRegister {
+        address: RegisterAddress,
+        entry: Entry,
+        base: BTreeSet<EntryHash>,
+    },
+    /// Sends tokens from the wallet.
+    Send { amount: NanoTokens, to: MainPubkey },
+}
+
+/// Why a queued operation could not be flushed as it was. It is kept in the outbox until it is
+/// resolved.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Conflict {
+    /// The Register was written to by someone else since the write was queued.
+    RegisterChanged { latest: BTreeSet<EntryHash> },
+    /// The wallet no longer holds enough tokens for the transfer.
+    InsufficientBalance { balance: NanoTokens },
+}
+
+/// How to resolve a conflicting operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// Drops the operation.
+    Discard,
+    /// Applies the operation on the next flush regardless: the write is merged with the entries
+    /// written since, or the transfer is attempted anyway.
+    Apply,
+}
+
+/// The outcome of flushing an `Outbox`.
+#[derive(Debug, Default)]
+pub struct FlushReport {
+    /// The operations which were applied, and removed from the outbox.
+    pub flushed: Vec<OperationId>,
+    /// The transfers made by the queued sends, to be handed to their recipients.
+    pub transfers: Vec<(OperationId, Transfer)>,
+    /// The operations found to conflict, which are kept in the outbox until resolved.
+    pub conflicts: Vec<(OperationId, Conflict)>,
+    /// The operation the flush stopped at, with the error, e.g. as the network is unreachable.
+    /// It is kept in the outbox, along with the operations after it.
+    pub interrupted: Option<(OperationId, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueuedOperation {
+    operation: OutboxOperation,
+    conflict: Option<Conflict>,
+    // Set once a conflict is resolved with `Resolution::Apply`.
+    force: bool,
+}
+
+/// A queue of the operations to apply to the network, which can be filled while disconnected.
+///
+/// The operations are persisted in the root directory of the client, and applied in order by
+/// `flush` once the network can be reached, or automatically by `flush_when_connected`. An
+/// operation which no longer applies to the current state of the network, e.g. a Register
+/// written to by someone else in the meantime, is reported as a conflict instead of being applied.
+#[derive(Clone, Debug)]
+pub struct Outbox {
+    dir: PathBuf,
+}
+
+impl Outbox {
+    /// Opens the outbox of the client with the given root directory, creating it if needed.
+    pub fn open(root_dir: &Path) -> Result<Self> {
+        let dir = root_dir.join(OUTBOX_DIR_NAME);
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Queues the upload of a file, chunking it right away, so that it can change or be removed
+    /// before the outbox is flushed. Returns the address of its data map along with the id.
+    pub fn queue_file(&self, path: &Path) -> Result<(OperationId, ChunkAddress)> {
+        let id = self.next_id()?;
+        let chunk_dir = self.chunk_dir(id);
+        fs::create_dir_all(&chunk_dir)?;
+        let (data_map, _, _, chunks) = FilesApi::chunk_file(path, &chunk_dir, true)?;
+        self.queue_with_id(
+            id,
+            OutboxOperation::UploadFile {
+                path: path.to_path_buf(),
+                data_map,
+                chunks,
+            },
+        )?;
+        Ok((id, data_map))
+    }
+
+    /// Queues the write of an entry to the Register, on top of the entries of the given replica,
+    /// which the write is checked against when the outbox is flushed.
+    pub fn queue_register_write(
+        &self,
+        register: &ClientRegister,
+        entry: &[u8],
+    ) -> Result<OperationId> {
+        let base = register.read().into_iter().map(|(hash, _)| hash).collect();
+        self.queue(OutboxOperation::WriteRegister {
+            address: *register.address(),
+            entry: entry.to_vec(),
+            base,
+        })
+    }
+
+    /// Queues a transfer of tokens from the wallet.
+    pub fn queue_send(&self, amount: NanoTokens, to: MainPubkey) -> Result<OperationId> {
+        if amount.is_zero() {
+            return Err(Error::AmountIsZero);
+        }
+        self.queue(OutboxOperation::Send { amount, to })
+    }
+
+    /// Returns the queued operations, in order, with the conflict found for each one, if any.
+    pub fn pending(&self) -> Result<Vec<(OperationId, OutboxOperation, Option<Conflict>)>> {
+        Ok(self
+            .load_all()?
+            .into_iter()
+            .map(|(id, queued)| (id, queued.operation, queued.conflict))
+            .collect())
+    }
+
+    /// Resolves a conflicting operation.
+    pub fn resolve(&self, id: OperationId, resolution: Resolution) -> Result<()> {
+        let mut queued = self.load(id)?;
+        match resolution {
+            Resolution::Discard => self.remove(id),
+            Resolution::Apply => {
+                queued.conflict = None;
+                queued.force = true;
+                self.store(id, &queued)
+            }
+        }
+    }
+
+    /// Applies the queued operations in order, paying with and sending from the wallet in
+    /// `wallet_dir`.
+    ///
+    /// The operations found to conflict are skipped, and the flush stops at the first error, the
+    /// remaining operations being kept for the next flush.
+    pub async fn flush(
+        &self,
+        client: &Client,
+        wallet_dir: &Path,
+        verify_store: bool,
+    ) -> Result<FlushReport> {
+        let mut report = FlushReport::default();
+        for (id, mut queued) in self.load_all()? {
+            if queued.conflict.is_some() {
+                continue;
+            }
+            match self.apply(client, wallet_dir, verify_store, &queued).await {
+                Ok(Applied::Done(transfer)) => {
+                    debug!("Flushed operation {id} of the outbox");
+                    self.remove(id)?;
+                    report.flushed.push(id);
+                    if let Some(transfer) = transfer {
+                        report.transfers.push((id, transfer));
+                    }
+                }
+                Ok(Applied::Conflict(conflict)) => {
+                    warn!("Operation {id} of the outbox conflicts: {conflict:?}");
+                    queued.conflict = Some(conflict.clone());
+                    self.store(id, &queued)?;
+                    report.conflicts.push((id, conflict));
+                }
+                Err(err) => {
+                    warn!("Flushing the outbox stopped at operation {id}: {err:?}");
+                    report.interrupted = Some((id, err.to_string()));
+                    break;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Flushes the outbox in the background whenever the client reconnects to the network, and
+    /// regularly while operations are pending, until the client is dropped.
+    pub fn flush_when_connected(
+        self,
+        client: Client,
+        wallet_dir: PathBuf,
+        verify_store: bool,
+    ) -> JoinHandle<()> {
+        let mut events = client.events_channel();
+        tokio::spawn(async move {
+            loop {
+                match self.flush(&client, &wallet_dir, verify_store).await {
+                    Ok(report) => {
+                        if !report.flushed.is_empty() || !report.conflicts.is_empty() {
+                            info!(
+                                "Flushed {} operations of the outbox, {} conflicting",
+                                report.flushed.len(),
+                                report.conflicts.len()
+                            );
+                        }
+                    }
+                    Err(err) => error!("Could not flush the outbox: {err:?}"),
+                }
+
+                // new operations may be queued at any time, through another handle of the outbox.
+                let reconnected = async {
+                    loop {
+                        match events.recv().await {
+                            Ok(ClientEvent::ConnectedToNetwork) => return true,
+                            Err(RecvError::Closed) => return false,
+                            _ => {}
+                        }
+                    }
+                };
+                tokio::select! {
+                    keep_going = reconnected => if !keep_going {
+                        debug!("The client was dropped, the outbox is no longer flushed");
+                        break;
+                    },
+                    _ = tokio::time::sleep(FLUSH_RETRY_INTERVAL) => {}
+                }
+            }
+        })
+    }
+
+    async fn apply(
+        &self,
+        client: &Client,
+        wallet_dir: &Path,
+        verify_store: bool,
+        queued: &QueuedOperation,
+    ) -> Result<Applied> {
+        match &queued.operation {
+            OutboxOperation::UploadFile { chunks, .. } => {
+                let mut uploader = Uploader::new(client.clone(), wallet_dir.to_path_buf());
+                uploader.set_verify_store(verify_store);
+                uploader.insert_chunk_paths(chunks.clone());
+                let _ = uploader.start_upload().await?;
+                Ok(Applied::Done(None))
+            }
+            OutboxOperation::WriteRegister {
+                address,
+                entry,
+                base,
+            } => {
+                let mut register = client.get_register(*address).await?;
+                let latest = register.read().into_iter().map(|(hash, _)| hash).collect();
+                if let Some(conflict) = register_conflict(base, latest, queued.force) {
+                    return Ok(Applied::Conflict(conflict));
+                }
+                register
+                    .write_merging_branches_online(entry, verify_store)
+                    .await?;
+                Ok(Applied::Done(None))
+            }
+            OutboxOperation::Send { amount, to } => {
+                let wallet = HotWallet::load_from(wallet_dir)?;
+                let balance = wallet.balance();
+                if balance < *amount && !queued.force {
+                    return Ok(Applied::Conflict(Conflict::InsufficientBalance { balance }));
+                }
+                let cash_note = crate::send(wallet, *amount, *to, client, verify_store).await?;
+                let transfer = Transfer::transfer_from_cash_note(&cash_note)?;
+                Ok(Applied::Done(Some(transfer)))
+            }
+        }
+    }
+
+    fn queue(&self, operation: OutboxOperation) -> Result<OperationId> {
+        let id = self.next_id()?;
+        self.queue_with_id(id, operation)?;
+        Ok(id)
+    }
+
+    fn queue_with_id(&self, id: OperationId, operation: OutboxOperation) -> Result<()> {
+        info!("Queuing operation {id} in the outbox: {operation:?}");
+        self.store(
+            id,
+            &QueuedOperation {
+                operation,
+                conflict: None,
+                force: false,
+            },
+        )
+    }
+
+    fn next_id(&self) -> Result<OperationId> {
+        Ok(self.ids()?.last().map_or(0, |id| id + 1))
+    }
+
+    fn ids(&self) -> Result<Vec<OperationId>> {
+        let mut ids = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(OPERATION_EXTENSION) {
+                continue;
+            }
+            if let Some(id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+            {
+                ids.push(id);
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn load_all(&self) -> Result<Vec<(OperationId, QueuedOperation)>> {
+        self.ids()?
+            .into_iter()
+            .map(|id| Ok((id, self.load(id)?)))
+            .collect()
+    }
+
+    fn load(&self, id: OperationId) -> Result<QueuedOperation> {
+        let bytes =
+            fs::read(self.operation_path(id)).map_err(|_| Error::OutboxOperationNotFound(id))?;
+        Ok(rmp_serde::from_slice(&bytes)?)
+    }
+
+    fn store(&self, id: OperationId, queued: &QueuedOperation) -> Result<()> {
+        fs::write(self.operation_path(id), rmp_serde::to_vec(queued)?)?;
+        Ok(())
+    }
+
+    fn remove(&self, id: OperationId) -> Result<()> {
+        fs::remove_file(self.operation_path(id)).map_err(|_| Error::OutboxOperationNotFound(id))?;
+        let chunk_dir = self.chunk_dir(id);
+        if chunk_dir.exists() {
+            fs::remove_dir_all(chunk_dir)?;
+        }
+        Ok(())
+    }
+
+    fn operation_path(&self, id: OperationId) -> PathBuf {
+        self.dir.join(format!("{id}.{OPERATION_EXTENSION}"))
+    }
+
+    fn chunk_dir(&self, id: OperationId) -> PathBuf {
+        self.dir.join(format!("{id}-chunks"))
+    }
+}
+
+enum Applied {
+    Done(Option<Transfer>),
+    Conflict(Conflict),
+}
+
+// A write conflicts if the Register was written to since it was queued, unless it is forced.
+fn register_conflict(
+    base: &BTreeSet<EntryHash>,
+    latest: BTreeSet<EntryHash>,
+    force: bool,
+) -> Option<Conflict> {
+    if force || *base == latest {
+        None
+    } else {
+        Some(Conflict::RegisterChanged { latest })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uploader::tests::setup::build_unconnected_client;
+    use bls::SecretKey;
+    use eyre::Result;
+    use tempfile::tempdir;
+
+    #[test]
+    fn operations_are_queued_and_resolved_across_restarts() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let client = build_unconnected_client(temp_dir.path().to_path_buf())?;
+        let outbox = Outbox::open(temp_dir.path())?;
+
+        let file_path = temp_dir.path().join("file");
+        fs::write(&file_path, vec![7; 100 * 1024])?;
+        let (upload, _) = outbox.queue_file(&file_path)?;
+        let mut register = ClientRegister::create(client, XorName::random(&mut rand::thread_rng()));
+        let _ = register.write(b"seen offline")?;
+        let write = outbox.queue_register_write(&register, b"written offline")?;
+        let to = MainPubkey::new(SecretKey::random().public_key());
+        let send = outbox.queue_send(NanoTokens::from(10), to)?;
+        assert!(outbox.queue_send(NanoTokens::zero(), to).is_err());
+        // the file can go away once it is chunked.
+        fs::remove_file(&file_path)?;
+
+        let outbox = Outbox::open(temp_dir.path())?;
+        let pending = outbox.pending()?;
+        let ids: Vec<_> = pending.iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(ids, vec![upload, write, send]);
+        match &pending[1].1 {
+            OutboxOperation::WriteRegister { base, .. } => assert_eq!(base.len(), 1),
+            operation => panic!("Unexpected operation {operation:?}"),
+        }
+
+        outbox.resolve(upload, Resolution::Discard)?;
+        assert!(!outbox.chunk_dir(upload).exists());
+        outbox.resolve(send, Resolution::Apply)?;
+        assert!(outbox.load(send)?.force);
+        assert_eq!(outbox.pending()?.len(), 2);
+        assert!(matches!(
+            outbox.resolve(upload, Resolution::Apply),
+            Err(Error::OutboxOperationNotFound(_))
+        ));
+        // ids are not reused while operations are pending.
+        assert_eq!(outbox.next_id()?, send + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn writes_conflict_with_concurrent_ones_unless_forced() {
+        let seen = EntryHash::default();
+        let concurrent = EntryHash([1; 32]);
+        assert_eq!(
+            register_conflict(&BTreeSet::from([seen]), BTreeSet::from([seen]), false),
+            None
+        );
+        assert_eq!(
+            register_conflict(&BTreeSet::from([seen]), BTreeSet::from([concurrent]), false),
+            Some(Conflict::RegisterChanged {
+                latest: BTreeSet::from([concurrent])
+            })
+        );
+        assert_eq!(
+            register_conflict(&BTreeSet::from([seen]), BTreeSet::from([concurrent]), true),
+            None
+        );
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::WalletClient;
+use async_trait::async_trait;
+use sn_transfers::{MainPubkey, NanoTokens, PaymentDetails, PaymentQuote, WalletResult};
+use std::collections::BTreeMap;
+use xor_name::XorName;
+
+/// The quotes to pay for, by the name of the record: the payee's key, its quote and its PeerId
+/// as bytes.
+pub type PaymentCostMap = BTreeMap<XorName, (MainPubkey, PaymentQuote, Vec<u8>)>;
+
+/// What a `Payer` paid for a batch of records.
+#[derive(Clone)]
+pub struct PaymentReceipt {
+    pub storage_cost: NanoTokens,
+    pub royalty_fees: NanoTokens,
+    /// The payment of each record, which is sent to its payee along with it. The uploader caches
+    /// them in the wallet dir of the upload, so an interrupted upload can be resumed without
+    /// paying again.
+    pub payments: BTreeMap<XorName, PaymentDetails>,
+}
+
+/// Pays for the storage of the records being uploaded.
+///
+/// The local `HotWallet` pays by default, through its `WalletClient`, but the uploads can be funded
+/// from elsewhere, e.g. a remote wallet service or a third party paying on the user's behalf.
+#[async_trait]
+pub trait Payer: Send + Sync {
+    /// Pay the quotes of the records, making sure the payments have reached the network before
+    /// returning them when `verify_store` is set.
+    ///
+    /// `WalletError::Transfer(TransferError::NotEnoughBalance(..))` is not retried, the upload
+    /// erroring out instead.
+    async fn pay_for_records(
+        &mut self,
+        cost_map: &PaymentCostMap,
+        verify_store: bool,
+    ) -> WalletResult<PaymentReceipt>;
+
+    /// Returns the balance left to pay with, or zero if the payer doesn't disclose it.
+    fn balance(&self) -> NanoTokens;
+}
+
+#[async_trait]
+impl Payer for WalletClient {
+    async fn pay_for_records(
+        &mut self,
+        cost_map: &PaymentCostMap,
+        verify_store: bool,
+    ) -> WalletResult<PaymentReceipt> {
+        let _ = self.resend_pending_transaction_blocking_loop().await;
+        let (storage_cost, royalty_fees) =
+            WalletClient::pay_for_records(self, cost_map, verify_store).await?;
+
+        // the wallet cached the payments in its own wallet dir
+        let api = self.mut_wallet().api();
+        let payments = cost_map
+            .keys()
+            .map(|xorname| Ok((*xorname, api.get_recent_payment(xorname)?)))
+            .collect::<WalletResult<_>>()?;
+        Ok(PaymentReceipt {
+            storage_cost,
+            royalty_fees,
+            payments,
+        })
+    }
+
+    fn balance(&self) -> NanoTokens {
+        WalletClient::balance(self)
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{bandwidth::Direction, error::Result, Client, Error, WalletClient};
+use libp2p::{
+    kad::{Quorum, Record},
+    PeerId,
+};
+use sn_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
+use sn_protocol::{
+    error::Error as ProtocolError,
+    storage::{
+        try_deserialize_record, try_serialize_record, Pointer, PointerAddress, RecordHeader,
+        RecordKind, RetryStrategy,
+    },
+    NetworkAddress,
+};
+use sn_transfers::{NanoTokens, Payment};
+use std::collections::HashSet;
+use xor_name::XorName;
+
+impl Client {
+    /// Create a Pointer owned by the client's signer, at the address derived from `meta`,
+    /// pointing at `target`. The storage is paid with the wallet.
+    ///
+    /// Returns the Pointer along with the total cost paid for it.
+    pub async fn create_pointer(
+        &self,
+        meta: XorName,
+        target: NetworkAddress,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(Pointer, NanoTokens)> {
+        let address = PointerAddress::new(meta, self.signer_pk());
+        let pointer = Pointer::new(address, 0, target, self.signer())?;
+
+        let net_addr = pointer.network_address();
+        let payment_result = wallet_client
+            .pay_for_storage(std::iter::once(net_addr.clone()))
+            .await?;
+        let cost = payment_result
+            .storage_cost
+            .checked_add(payment_result.royalty_fees)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        if let Err(err) = wallet_client.store_local_wallet() {
+            warn!("Failed to store wallet with cached payment proofs: {err:?}");
+        }
+        let (payment, payee) = wallet_client.get_recent_payment_for_addr(&net_addr)?;
+
+        self.put_pointer(&pointer, Some((payment, payee)), verify_store)
+            .await?;
+        Ok((pointer, cost))
+    }
+
+    /// Repoint the Pointer at the address to `target`, signing a new version of it with the
+    /// client's signer, which must be its owner.
+    pub async fn update_pointer(
+        &self,
+        address: PointerAddress,
+        target: NetworkAddress,
+        verify_store: bool,
+    ) -> Result<Pointer> {
+        let current = self.get_pointer(address).await?;
+        let pointer = Pointer::new(address, current.counter() + 1, target, self.signer())?;
+        self.put_pointer(&pointer, None, verify_store).await?;
+        Ok(pointer)
+    }
+
+    /// Retrieve the newest version of the Pointer at the address from the network.
+    pub async fn get_pointer(&self, address: PointerAddress) -> Result<Pointer> {
+        let key = NetworkAddress::from_pointer_address(address).to_record_key();
+        let get_cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: Some(RetryStrategy::Quick),
+            target_record: None,
+            expected_holders: Default::default(),
+        };
+
+        let records = match self.network.get_record_from_network(key, &get_cfg).await {
+            Ok(record) => {
+                self.throttle(Direction::Download, record.value.len()).await;
+                vec![record]
+            }
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { result_map })) => {
+                // holders may not have all got the latest version yet
+                result_map
+                    .into_values()
+                    .map(|(record, _holders)| record)
+                    .collect()
+            }
+            Err(err) => {
+                warn!("Failed to get Pointer at {address:?} from the network: {err:?}");
+                return Err(ProtocolError::PointerNotFound(Box::new(address)).into());
+            }
+        };
+
+        let mut newest: Option<Pointer> = None;
+        for record in records {
+            let pointer = match pointer_from_record(&record, &address) {
+                Ok(pointer) => pointer,
+                Err(err) => {
+                    warn!("Ignoring invalid Pointer record at {address:?}: {err:?}");
+                    continue;
+                }
+            };
+            if newest.as_ref().is_none_or(|n| pointer.supersedes(n)) {
+                newest = Some(pointer);
+            }
+        }
+        newest.ok_or_else(|| ProtocolError::PointerNotFound(Box::new(address)).into())
+    }
+
+    /// Store the Pointer on the network, sending it along with the payment to the payee when
+    /// it's being created.
+    async fn put_pointer(
+        &self,
+        pointer: &Pointer,
+        payment: Option<(Payment, PeerId)>,
+        verify_store: bool,
+    ) -> Result<()> {
+        let network_address = pointer.network_address();
+        let key = network_address.to_record_key();
+        let (value, payee) = match payment {
+            Some((payment, payee)) => (
+                try_serialize_record(&(payment, pointer), RecordKind::PointerWithPayment)?,
+                Some(vec![payee]),
+            ),
+            None => (try_serialize_record(pointer, RecordKind::Pointer)?, None),
+        };
+        let record = Record {
+            key: key.clone(),
+            value: value.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+
+        let (record_to_verify, expected_holders) = if verify_store {
+            let expected_holders: HashSet<_> = self
+                .network
+                .get_closest_peers(&network_address, true)
+                .await?
+                .iter()
+                .cloned()
+                .collect();
+            (
+                Some(Record {
+                    key,
+                    value: try_serialize_record(pointer, RecordKind::Pointer)?.to_vec(),
+                    publisher: None,
+                    expires: None,
+                }),
+                expected_holders,
+            )
+        } else {
+            (None, Default::default())
+        };
+
+        let verification_cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: Some(RetryStrategy::Quick),
+            target_record: record_to_verify,
+            expected_holders,
+        };
+        let put_cfg = PutRecordCfg {
+            put_quorum: Quorum::All,
+            retry_strategy: Some(RetryStrategy::Balanced),
+            use_put_record_to: payee,
+            verification: Some((VerificationKind::Network, verification_cfg)),
+        };
+        self.throttle(Direction::Upload, record.value.len()).await;
+        self.network.put_record(record, &put_cfg).await?;
+        Ok(())
+    }
+}
+
+/// Deserialise the Pointer from the record, checking it's the one at the address and was signed
+/// by its owner.
+fn pointer_from_record(record: &Record, address: &PointerAddress) -> Result<Pointer> {
+    let header = RecordHeader::from_record(record)?;
+    if !matches!(header.kind, RecordKind::Pointer) {
+        return Err(NetworkError::RecordKindMismatch(RecordKind::Pointer).into());
+    }
+    let pointer: Pointer = try_deserialize_record(record)?;
+    if pointer.address() != address {
+        return Err(ProtocolError::PointerNotFound(Box::new(*address)).into());
+    }
+    pointer.verify()?;
+    Ok(pointer)
+}
@@ -0,0 +1,271 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, Client, ClientEventsBroadcaster, Error};
+use bls::SecretKey;
+use libp2p::Multiaddr;
+use serde::{Deserialize, Serialize};
+use sn_transfers::{MainPubkey, GENESIS_PK};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+/// The environment variable naming the profile to use, when none is given explicitly.
+pub const NETWORK_PROFILE_ENV: &str = "SAFE_NETWORK_PROFILE";
+/// File storing the network profiles, in the client data dir.
+pub const NETWORK_PROFILES_FILE: &str = "network_profiles";
+
+// The environment variable the genesis key of the network is read from.
+const GENESIS_PK_ENV: &str = "GENESIS_PK";
+
+/// A named configuration of the network to connect to, e.g. a local, test or main network.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    pub name: String,
+    /// The multiaddrs of the peers to bootstrap from, which can be left empty with local
+    /// discovery.
+    pub peers: Vec<String>,
+    /// The genesis key of the network, hex or bech32m encoded, which tells networks apart.
+    /// The key built in the client is used if `None`.
+    pub network_key: Option<String>,
+    /// The dir the client key, the wallet and any other data kept for the network are stored in.
+    pub root_dir: PathBuf,
+}
+
+impl NetworkProfile {
+    /// Creates a profile without peers, using the network key built in the client.
+    /// The name is made of alphanumerics, `-` and `_`.
+    pub fn new(name: &str, root_dir: PathBuf) -> Result<Self> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::InvalidNetworkProfile(format!(
+                "{name:?} is not a valid profile name"
+            )));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            peers: vec![],
+            network_key: None,
+            root_dir,
+        })
+    }
+
+    /// Returns the peers to bootstrap from.
+    pub fn bootstrap_peers(&self) -> Result<Vec<Multiaddr>> {
+        self.peers
+            .iter()
+            .map(|peer| {
+                peer.parse().map_err(|err| {
+                    Error::InvalidNetworkProfile(format!(
+                        "{}: invalid peer {peer:?}: {err}",
+                        self.name
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the genesis key of the network, if the profile sets one.
+    pub fn network_key(&self) -> Result<Option<MainPubkey>> {
+        self.network_key
+            .as_ref()
+            .map(|key| {
+                MainPubkey::from_str(key).map_err(|err| {
+                    Error::InvalidNetworkProfile(format!(
+                        "{}: invalid network key: {err}",
+                        self.name
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    /// Makes the process use the network key of the profile.
+    ///
+    /// The key is only read once per process, so this has to be called before anything uses it,
+    /// e.g. at start up. Errors out if the process already uses another key.
+    pub fn apply_network_key(&self) -> Result<()> {
+        let Some(key) = self.network_key()? else {
+            return Ok(());
+        };
+        std::env::set_var(GENESIS_PK_ENV, key.to_hex());
+        if *GENESIS_PK != key {
+            return Err(Error::NetworkKeyMismatch(self.name.clone()));
+        }
+        Ok(())
+    }
+
+    /// Connects a client to the network of the profile, applying its network key first.
+    pub async fn connect(
+        &self,
+        signer: SecretKey,
+        connection_timeout: Option<Duration>,
+        client_events_broadcaster: Option<ClientEventsBroadcaster>,
+    ) -> Result<Client> {
+        self.apply_network_key()?;
+        let peers = self.bootstrap_peers()?;
+        // without peers, they are discovered locally.
+        let peers = (!peers.is_empty()).then_some(peers);
+        Client::new(signer, peers, connection_timeout, client_events_broadcaster).await
+    }
+}
+
+// What the profiles file holds.
+#[derive(Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    default: Option<String>,
+    profiles: BTreeMap<String, NetworkProfile>,
+}
+
+/// The network profiles of the client, stored in its data dir, so several networks can be used
+/// from the same machine, each with its own peers, network key and data.
+pub struct NetworkProfiles {
+    path: PathBuf,
+    default: Option<String>,
+    profiles: BTreeMap<String, NetworkProfile>,
+}
+
+impl NetworkProfiles {
+    /// Loads the profiles stored in the dir, which has none if no profile was saved yet.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(NETWORK_PROFILES_FILE);
+        let file: ProfilesFile = if path.exists() {
+            rmp_serde::from_slice(&std::fs::read(&path)?)?
+        } else {
+            ProfilesFile::default()
+        };
+        Ok(Self {
+            path,
+            default: file.default,
+            profiles: file.profiles,
+        })
+    }
+
+    /// Stores the profiles in the dir they were loaded from.
+    pub fn save(&self) -> Result<()> {
+        let file = ProfilesFile {
+            default: self.default.clone(),
+            profiles: self.profiles.clone(),
+        };
+        std::fs::write(&self.path, rmp_serde::to_vec(&file)?)?;
+        Ok(())
+    }
+
+    /// Adds a profile, returning the one of the same name it replaced, if any.
+    pub fn add(&mut self, profile: NetworkProfile) -> Option<NetworkProfile> {
+        self.profiles.insert(profile.name.clone(), profile)
+    }
+
+    /// Removes a profile, which is no longer the default one if it was.
+    pub fn remove(&mut self, name: &str) -> Result<NetworkProfile> {
+        let profile = self
+            .profiles
+            .remove(name)
+            .ok_or_else(|| Error::NetworkProfileNotFound(name.to_string()))?;
+        if self.default.as_deref() == Some(name) {
+            self.default = None;
+        }
+        Ok(profile)
+    }
+
+    /// Returns the profile of that name.
+    pub fn get(&self, name: &str) -> Option<&NetworkProfile> {
+        self.profiles.get(name)
+    }
+
+    /// Returns all the profiles, by name.
+    pub fn profiles(&self) -> impl Iterator<Item = &NetworkProfile> {
+        self.profiles.values()
+    }
+
+    /// Returns the profile used when none is selected, if any.
+    pub fn default_profile(&self) -> Option<&NetworkProfile> {
+        self.default
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+    }
+
+    /// Sets the profile used when none is selected, or unsets it.
+    pub fn set_default(&mut self, name: Option<&str>) -> Result<()> {
+        if let Some(name) = name {
+            if !self.profiles.contains_key(name) {
+                return Err(Error::NetworkProfileNotFound(name.to_string()));
+            }
+        }
+        self.default = name.map(str::to_string);
+        Ok(())
+    }
+
+    /// Returns the profile to use: the one named, else the one named by the
+    /// `SAFE_NETWORK_PROFILE` environment variable, else the default one, if any.
+    pub fn select(&self, name: Option<&str>) -> Result<Option<&NetworkProfile>> {
+        let name = match name {
+            Some(name) => Some(name.to_string()),
+            None => std::env::var(NETWORK_PROFILE_ENV)
+                .ok()
+                .filter(|name| !name.is_empty()),
+        };
+        match name {
+            Some(name) => self
+                .profiles
+                .get(&name)
+                .map(Some)
+                .ok_or(Error::NetworkProfileNotFound(name)),
+            None => Ok(self.default_profile()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn profiles_are_stored_and_selected() -> Result<()> {
+        let dir = tempdir()?;
+        let mut local = NetworkProfile::new("local", dir.path().join("local"))?;
+        local.peers = vec!["/ip4/127.0.0.1/udp/12000/quic-v1".to_string()];
+        let mut test = NetworkProfile::new("test-net", dir.path().join("test"))?;
+        let network_key = MainPubkey::new(SecretKey::random().public_key());
+        test.network_key = Some(network_key.to_address());
+        assert!(NetworkProfile::new("main net", dir.path().to_path_buf()).is_err());
+
+        let mut profiles = NetworkProfiles::load(dir.path())?;
+        assert!(profiles.add(local.clone()).is_none());
+        assert!(profiles.add(test.clone()).is_none());
+        profiles.set_default(Some("local"))?;
+        assert!(profiles.set_default(Some("main")).is_err());
+        profiles.save()?;
+
+        let mut profiles = NetworkProfiles::load(dir.path())?;
+        assert_eq!(profiles.profiles().count(), 2);
+        assert_eq!(profiles.default_profile(), Some(&local));
+        assert_eq!(profiles.select(Some("test-net"))?, Some(&test));
+        assert!(profiles.select(Some("main")).is_err());
+        assert_eq!(local.bootstrap_peers()?.len(), 1);
+        assert_eq!(test.network_key()?, Some(network_key));
+
+        // the environment variable takes over the default profile
+        std::env::set_var(NETWORK_PROFILE_ENV, "test-net");
+        let selected = profiles.select(None)?.cloned();
+        std::env::remove_var(NETWORK_PROFILE_ENV);
+        assert_eq!(selected, Some(test));
+        assert_eq!(profiles.select(None)?, Some(&local));
+
+        let _ = profiles.remove("local")?;
+        assert_eq!(profiles.select(None)?, None);
+        Ok(())
+    }
+}
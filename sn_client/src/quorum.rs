@@ -0,0 +1,272 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{bandwidth::Direction, error::Result, Client, Error};
+use libp2p::{
+    kad::{Quorum, Record, RecordKey},
+    PeerId,
+};
+use serde::{Deserialize, Serialize};
+use sn_networking::{get_quorum_value, HolderCopies, NodeIssue};
+use sn_protocol::{
+    storage::{try_deserialize_record, Chunk, ChunkAddress, RecordHeader, RecordKind},
+    NetworkAddress,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+};
+use xor_name::XorName;
+
+/// How many of the nodes holding a record have to return the same copy of it for a GET to
+/// succeed, trading latency against the assurance the copy is the one stored by the network.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReadQuorum {
+    /// The first copy returned is used, which is the quickest.
+    First,
+    /// A majority of the closest nodes have to return the same copy.
+    #[default]
+    Majority,
+    /// All the closest nodes have to return the same copy, which fails if any of them lacks the
+    /// record or holds another version of it.
+    All,
+}
+
+impl ReadQuorum {
+    /// The number of matching copies the quorum requires.
+    pub fn copies(&self) -> usize {
+        get_quorum_value(&self.kad_quorum())
+    }
+
+    pub(crate) fn kad_quorum(&self) -> Quorum {
+        match self {
+            ReadQuorum::First => Quorum::One,
+            ReadQuorum::Majority => Quorum::Majority,
+            ReadQuorum::All => Quorum::All,
+        }
+    }
+}
+
+impl FromStr for ReadQuorum {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "first" => Ok(ReadQuorum::First),
+            "majority" => Ok(ReadQuorum::Majority),
+            "all" => Ok(ReadQuorum::All),
+            _ => Err(Error::InvalidReadQuorum(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ReadQuorum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// A value read from the network, along with the quorum it was read with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuorumRead<T> {
+    pub value: T,
+    pub quorum: ReadQuorum,
+    /// The number of nodes which returned that same copy, at least.
+    pub matching_copies: usize,
+}
+
+impl<T> QuorumRead<T> {
+    pub(crate) fn new(value: T, quorum: ReadQuorum) -> Self {
+        Self {
+            value,
+            quorum,
+            matching_copies: quorum.copies(),
+        }
+    }
+}
+
+/// A value read from all the closest holders of a record, along with how their copies compared.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrossCheckedRead<T> {
+    pub value: T,
+    /// The holders which returned the copy the value was read from.
+    pub agreeing: BTreeSet<PeerId>,
+    /// The holders which returned another copy, along with the hash of it.
+    pub divergent: BTreeMap<PeerId, XorName>,
+    /// The holders which returned no copy.
+    pub missing: BTreeSet<PeerId>,
+}
+
+impl Client {
+    /// Get a record from each of its closest holders and compare their copies, the one returned
+    /// by the most holders being used.
+    ///
+    /// The holders returning another copy are reported as bad nodes, so this is meant for the
+    /// records which don't change once stored. The read fails if no copy is returned by more
+    /// holders than any other one.
+    pub async fn get_record_cross_checked(
+        &self,
+        address: NetworkAddress,
+    ) -> Result<CrossCheckedRead<Vec<u8>>> {
+        self.cross_check(address, |_| true).await
+    }
+
+    /// Get a chunk from each of its closest holders and compare their copies, the holders
+    /// returning a copy other than the chunk at the address being reported as bad nodes.
+    pub async fn get_chunk_cross_checked(
+        &self,
+        address: ChunkAddress,
+    ) -> Result<CrossCheckedRead<Chunk>> {
+        let read = self
+            .cross_check(NetworkAddress::from_chunk_address(address), |value| {
+                chunk_from_value(value).is_some_and(|chunk| chunk.address == address)
+            })
+            .await?;
+        let chunk = chunk_from_value(&read.value).ok_or(Error::CorruptChunk {
+            address,
+            holder: None,
+        })?;
+        Ok(CrossCheckedRead {
+            value: chunk,
+            agreeing: read.agreeing,
+            divergent: read.divergent,
+            missing: read.missing,
+        })
+    }
+
+    async fn cross_check(
+        &self,
+        address: NetworkAddress,
+        is_valid: impl Fn(&[u8]) -> bool,
+    ) -> Result<CrossCheckedRead<Vec<u8>>> {
+        info!("Cross-checking the copies of {address:?}");
+        let copies = self.network.get_record_from_holders(&address).await?;
+        let read = compare_copies(&address, copies, is_valid)?;
+        for peer in read.divergent.keys() {
+            warn!("{peer:?} returned a divergent copy of {address:?}");
+            self.network
+                .record_node_issues(*peer, NodeIssue::DivergentRecord);
+        }
+        self.throttle(Direction::Download, read.value.len()).await;
+        Ok(read)
+    }
+}
+
+// Picks the valid copy returned by the most holders, which has to be returned by more holders
+// than any other valid copy, as it can't be told which ones diverge otherwise.
+fn compare_copies(
+    address: &NetworkAddress,
+    copies: HolderCopies,
+    is_valid: impl Fn(&[u8]) -> bool,
+) -> Result<CrossCheckedRead<Vec<u8>>> {
+    let HolderCopies { copies, missing } = copies;
+    let mut valid: Vec<_> = copies
+        .iter()
+        .filter(|(_, (value, _))| is_valid(value))
+        .map(|(hash, (_, holders))| (holders.len(), *hash))
+        .collect();
+    valid.sort_unstable_by(|a, b| b.cmp(a));
+    let agreed = match valid.as_slice() {
+        [(most, hash), (next, _), ..] if most > next => Some(*hash),
+        [(_, hash)] => Some(*hash),
+        _ => None,
+    };
+    let Some(agreed) = agreed else {
+        warn!(
+            "The holders of {address:?} returned {} copies, none agreed on",
+            copies.len()
+        );
+        return Err(Error::NoAgreedCopy(address.clone()));
+    };
+
+    let mut read = None;
+    let mut divergent = BTreeMap::new();
+    for (hash, (value, holders)) in copies {
+        if hash == agreed {
+            read = Some((value, holders));
+        } else {
+            divergent.extend(holders.into_iter().map(|peer| (peer, hash)));
+        }
+    }
+    let (value, agreeing) = read.ok_or_else(|| Error::NoAgreedCopy(address.clone()))?;
+    Ok(CrossCheckedRead {
+        value,
+        agreeing,
+        divergent,
+        missing,
+    })
+}
+
+fn chunk_from_value(value: &[u8]) -> Option<Chunk> {
+    let record = Record::new(RecordKey::new(&[]), value.to_vec());
+    match RecordHeader::from_record(&record).ok()?.kind {
+        RecordKind::Chunk => try_deserialize_record(&record).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::CLOSE_GROUP_SIZE;
+
+    #[test]
+    fn quorums_require_more_copies_as_they_get_stricter() -> eyre::Result<()> {
+        assert_eq!(ReadQuorum::First.copies(), 1);
+        assert!(ReadQuorum::Majority.copies() > 1);
+        assert_eq!(ReadQuorum::All.copies(), CLOSE_GROUP_SIZE);
+
+        for quorum in [ReadQuorum::First, ReadQuorum::Majority, ReadQuorum::All] {
+            assert_eq!(quorum.to_string().parse::<ReadQuorum>()?, quorum);
+        }
+        assert!("some".parse::<ReadQuorum>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn divergent_holders_are_told_apart_from_the_agreeing_ones() -> eyre::Result<()> {
+        let address = NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::default()));
+        let peers: Vec<_> = (0..5).map(|_| PeerId::random()).collect();
+        let copy = |value: &[u8], holders: &[PeerId]| {
+            (
+                XorName::from_content(value),
+                (value.to_vec(), holders.iter().cloned().collect()),
+            )
+        };
+
+        let copies = HolderCopies {
+            copies: BTreeMap::from([copy(b"good", &peers[..3]), copy(b"bad", &peers[3..4])]),
+            missing: BTreeSet::from([peers[4]]),
+        };
+        let read = compare_copies(&address, copies.clone(), |_| true)?;
+        assert_eq!(read.value, b"good".to_vec());
+        assert_eq!(read.agreeing.len(), 3);
+        assert_eq!(
+            read.divergent,
+            BTreeMap::from([(peers[3], XorName::from_content(b"bad"))])
+        );
+        assert_eq!(read.missing, BTreeSet::from([peers[4]]));
+
+        // the copy returned by the most holders is only used if it's a valid one.
+        let read = compare_copies(&address, copies, |value| value == b"bad")?;
+        assert_eq!(read.value, b"bad".to_vec());
+        assert_eq!(read.divergent.len(), 3);
+
+        // it can't be told which holders diverge on a tie.
+        let copies = HolderCopies {
+            copies: BTreeMap::from([copy(b"one", &peers[..2]), copy(b"two", &peers[2..4])]),
+            missing: BTreeSet::new(),
+        };
+        assert!(matches!(
+            compare_copies(&address, copies, |_| true),
+            Err(Error::NoAgreedCopy(_))
+        ));
+        Ok(())
+    }
+}
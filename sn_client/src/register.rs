@@ -6,24 +6,40 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{wallet::StoragePaymentResult, Client, Error, Result, WalletClient};
+#[cfg(feature = "open-metrics")]
+use crate::metrics::RequestKind;
+use crate::{
+    bandwidth::Direction, wallet::StoragePaymentResult, Client, Error, Result, TimeoutOperation,
+    WalletClient,
+};
 use bls::PublicKey;
-use crdts::merkle_reg::MerkleReg;
+use bytes::{BufMut, BytesMut};
 use libp2p::{
     kad::{Quorum, Record},
     PeerId,
 };
+use self_encryption::MAX_CHUNK_SIZE;
 use sn_networking::{GetRecordCfg, PutRecordCfg, VerificationKind};
 use sn_protocol::{
     error::Error as ProtocolError,
     messages::RegisterCmd,
-    storage::{try_serialize_record, RecordKind, RetryStrategy},
+    storage::{try_serialize_record, Chunk, ChunkAddress, RecordKind, RetryStrategy},
     NetworkAddress,
 };
-use sn_registers::{Entry, EntryHash, Permissions, Register, RegisterAddress, SignedRegister};
+use sn_registers::{
+    AuthoredEntry, ConflictPolicy, Entry, EntryHash, MerkleDag, Permissions, PermissionsUpdate,
+    Register, RegisterAddress, SignedRegister, MAX_REG_ENTRY_SIZE,
+};
 use sn_transfers::{NanoTokens, Payment};
-use std::collections::{BTreeSet, HashSet, LinkedList};
-use xor_name::XorName;
+use std::collections::{BTreeMap, BTreeSet, HashSet, LinkedList};
+use xor_name::{XorName, XOR_NAME_LEN};
+
+// Prefix of the entries holding the address of the first of the linked Chunks
+// an oversized entry was spilled into.
+const OVERFLOW_ENTRY_MARK: &[u8] = b"\0sn_register_overflow\0";
+
+// Room left for the payload on an overflow Chunk, after the flag and address of the next Chunk.
+const OVERFLOW_SEGMENT_SIZE: usize = MAX_CHUNK_SIZE - 1 - XOR_NAME_LEN;
 
 /// Cached operations made to an offline Register instance are applied locally only,
 /// and accumulated until the user explicitly calls 'sync'. The user can
@@ -34,6 +50,9 @@ pub struct ClientRegister {
     client: Client,
     pub(crate) register: Register,
     pub ops: LinkedList<RegisterCmd>, // Cached operations.
+    // Chunks oversized entries were spilled into, till they are uploaded on `sync`.
+    #[debug(skip)]
+    overflow: BTreeMap<XorName, Chunk>,
 }
 
 impl ClientRegister {
@@ -45,6 +64,7 @@ impl ClientRegister {
             client,
             register,
             ops: LinkedList::new(),
+            overflow: BTreeMap::new(),
         }
     }
 
@@ -99,6 +119,7 @@ impl ClientRegister {
             client,
             register,
             ops: LinkedList::new(),
+            overflow: BTreeMap::new(),
         }
     }
 
@@ -164,6 +185,7 @@ impl ClientRegister {
             client,
             register,
             ops: LinkedList::new(),
+            overflow: BTreeMap::new(),
         })
     }
 
@@ -336,6 +358,72 @@ impl ClientRegister {
         self.register.read()
     }
 
+    /// Read a single current entry, resolving the branches of the Register with the policy,
+    /// e.g. `ConflictPolicy::OwnerPriority`. Returns `None` if the Register is empty.
+    pub fn resolve(&self, policy: &ConflictPolicy) -> Option<(EntryHash, Entry)> {
+        self.register.resolve(policy)
+    }
+
+    /// Read the last values, or values when there are branches, fetching the values of the
+    /// entries which were spilled into Chunks.
+    ///
+    /// Return type: [BTreeSet]<([EntryHash], [Vec]<u8>)>
+    pub async fn read_payloads(&self) -> Result<BTreeSet<(EntryHash, Vec<u8>)>> {
+        let mut payloads = BTreeSet::new();
+        for (hash, entry) in self.register.read() {
+            let _ = payloads.insert((hash, self.fetch_payload(entry).await?));
+        }
+        Ok(payloads)
+    }
+
+    /// Return the value of the entry corresponding to the provided 'hash', fetching it
+    /// from the Chunks it was spilled into if needed.
+    pub async fn get_payload(&self, hash: EntryHash) -> Result<Vec<u8>> {
+        let entry = self.register.get(hash)?.clone();
+        self.fetch_payload(entry).await
+    }
+
+    /// Return the entry corresponding to the provided 'hash' along with its author, having
+    /// checked the author's signature, or `None` if the entry isn't an authored one.
+    /// See `write_authored_atop`.
+    pub async fn get_authored(&self, hash: EntryHash) -> Result<Option<AuthoredEntry>> {
+        let payload = self.get_payload(hash).await?;
+        let Some(authored) = AuthoredEntry::from_entry(&payload)? else {
+            return Ok(None);
+        };
+        authored.verify(self.address())?;
+        Ok(Some(authored))
+    }
+
+    /// Return the entries written after the entry at `from` up to the one at `to` included,
+    /// each after the ones it descends from. Sync the Register first to walk the history known
+    /// to the network.
+    ///
+    /// Return type: [Vec]<([EntryHash], [Entry])>
+    pub fn entries_between(
+        &self,
+        from: EntryHash,
+        to: EntryHash,
+    ) -> Result<Vec<(EntryHash, Entry)>> {
+        Ok(self.register.entries_between(from, to)?)
+    }
+
+    /// Return every entry after the entries it descends from, along with the branch tips of the
+    /// Register holding that entry and the ones before it.
+    /// Concurrent entries are ordered by hash, so that all the replicas agree on the history,
+    /// which is thus not the order they were written in.
+    ///
+    /// Return type: [Vec]<([EntryHash], [BTreeSet]<[EntryHash]>)>
+    pub fn tips_history(&self) -> Vec<(EntryHash, BTreeSet<EntryHash>)> {
+        self.register.tips_history()
+    }
+
+    /// Return a copy of the Register holding only the entry at `hash` and the ones it descends
+    /// from, e.g. to replay how the entries of an app converged.
+    pub fn state_at(&self, hash: EntryHash) -> Result<Register> {
+        Ok(self.register.state_at(hash)?)
+    }
+
     /// Write a new value onto the Register atop latest value.
     /// It returns an error if it finds branches in the content/entries; if it is
     /// required to merge/resolve the branches, invoke the `write_merging_branches` API.
@@ -410,6 +498,10 @@ impl ClientRegister {
     /// Note you can use `write_merging_branches` API instead if you
     /// want to write atop all exiting branches/entries.
     ///
+    /// Values bigger than a Register entry can hold are spilled into linked Chunks, the entry
+    /// only holding the address of the first one. The Chunks are paid for and uploaded on `sync`,
+    /// and `read_payloads` or `get_payload` return the values whole.
+    ///
     /// # Arguments
     /// * 'entry' - u8 (i.e .as_bytes)
     /// * 'children' - [BTreeSet]<[EntryHash]>
@@ -440,9 +532,15 @@ impl ClientRegister {
         let public_key = self.client.signer_pk();
         self.register.check_user_permissions(public_key)?;
 
-        let (entry_hash, op) = self
-            .register
-            .write(entry.into(), children, self.client.signer())?;
+        // spill the entries too big for the Register into Chunks, which are uploaded on `sync`
+        let (entry, overflow) = if entry.len() > MAX_REG_ENTRY_SIZE {
+            spill_into_chunks(entry)
+        } else {
+            (entry.into(), vec![])
+        };
+        let (entry_hash, op) = self.register.write(entry, children, self.client.signer())?;
+        self.overflow
+            .extend(overflow.into_iter().map(|chunk| (*chunk.name(), chunk)));
         let cmd = RegisterCmd::Edit(op);
 
         self.ops.push_front(cmd);
@@ -450,6 +548,102 @@ impl ClientRegister {
         Ok(entry_hash)
     }
 
+    /// Write an entry signed by its author onto the Register, atop the given entries.
+    ///
+    /// The author doesn't need to be allowed to write to the Register: whoever is writes the
+    /// entry on the author's behalf, and the readers check who authored it with `get_authored`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use sn_client::{Client, ClientRegister, Error};
+    /// # use bls::SecretKey;
+    /// # use sn_registers::AuthoredEntry;
+    /// # use std::collections::BTreeSet;
+    /// # use xor_name::XorName;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(),Error>{
+    /// let mut rng = rand::thread_rng();
+    /// let client = Client::new(SecretKey::random(), None, None, None).await?;
+    /// let mut register = ClientRegister::create(client, XorName::random(&mut rng));
+    /// let author = SecretKey::random();
+    /// let entry = AuthoredEntry::new(*register.address(), b"Register entry".to_vec(), &author)?;
+    /// let hash = register.write_authored_atop(&entry, &BTreeSet::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_authored_atop(
+        &mut self,
+        entry: &AuthoredEntry,
+        children: &BTreeSet<EntryHash>,
+    ) -> Result<EntryHash> {
+        entry.verify(self.address())?;
+        self.write_atop(&entry.to_entry()?, children)
+    }
+
+    /// Grant permissions to write to the Register to more users, e.g. the collaborators of an app,
+    /// without sharing the owner key with them. Only the owner of the Register can.
+    ///
+    /// The permissions are added to the ones the Register was created with, and can't be revoked.
+    ///
+    /// # Arguments
+    /// * 'permissions' - [Permissions]
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use sn_client::{Client, ClientRegister, Error};
+    /// # use bls::SecretKey;
+    /// # use sn_registers::Permissions;
+    /// # use xor_name::XorName;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(),Error>{
+    /// let mut rng = rand::thread_rng();
+    /// let client = Client::new(SecretKey::random(), None, None, None).await?;
+    /// let collaborator = SecretKey::random().public_key();
+    /// let mut register = ClientRegister::create(client, XorName::random(&mut rng));
+    /// register.grant(Permissions::new_with([collaborator]))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn grant(&mut self, permissions: Permissions) -> Result<()> {
+        let grant = PermissionsUpdate::new(*self.address(), permissions, self.client.signer())?;
+        self.register.grant(grant.clone())?;
+        self.ops.push_front(RegisterCmd::Grant(grant));
+        Ok(())
+    }
+
+    /// Compact the history of the Register up to the entry at `cutoff`, so that the clients
+    /// fetching it skip the ops of the entries the cutoff entry descends from.
+    /// Only the owner of the Register can.
+    ///
+    /// The entries written atop the compacted ones, other than the cutoff entry, are dropped,
+    /// so the cutoff is usually the single latest entry of the Register.
+    ///
+    /// # Arguments
+    /// * 'cutoff' - [EntryHash]
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use sn_client::{Client, ClientRegister, Error};
+    /// # use bls::SecretKey;
+    /// # use xor_name::XorName;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(),Error>{
+    /// let mut rng = rand::thread_rng();
+    /// let client = Client::new(SecretKey::random(), None, None, None).await?;
+    /// let mut register = ClientRegister::create(client, XorName::random(&mut rng));
+    /// register.write(b"first entry")?;
+    /// let latest = register.write(b"latest entry")?;
+    /// register.compact(latest)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compact(&mut self, cutoff: EntryHash) -> Result<()> {
+        let snapshot = self.register.snapshot_at(cutoff, self.client.signer())?;
+        self.register.compact(snapshot.clone())?;
+        self.ops.push_front(RegisterCmd::Snapshot(snapshot));
+        Ok(())
+    }
+
     // ********* Online methods  *********
 
     /// Sync this Register with the replicas on the network.
@@ -552,6 +746,16 @@ impl ClientRegister {
             }
         };
         self.register.merge(&remote_replica)?;
+
+        let (overflow_cost, overflow_royalties) =
+            self.upload_overflow(wallet_client, verify_store).await?;
+        storage_cost = storage_cost
+            .checked_add(overflow_cost)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        royalties_fees = royalties_fees
+            .checked_add(overflow_royalties)
+            .ok_or(Error::TotalPriceTooHigh)?;
+
         self.push(verify_store).await?;
 
         Ok((storage_cost, royalties_fees))
@@ -580,6 +784,11 @@ impl ClientRegister {
     /// # }
     /// ```
     pub async fn push(&mut self, verify_store: bool) -> Result<()> {
+        // the entries spilled into Chunks can't be read till their Chunks are paid for
+        if !self.overflow.is_empty() {
+            return Err(Error::RegisterOverflowNotPaid(*self.address()));
+        }
+
         let ops_len = self.ops.len();
         if ops_len > 0 {
             let address = *self.address();
@@ -715,14 +924,45 @@ impl ClientRegister {
         self.push(verify_store).await
     }
 
-    /// Access the underlying MerkleReg (e.g. for access to history)
+    /// Grant permissions to write to the Register to more users, and push them to the network.
+    /// See `grant` for details.
+    ///
+    /// # Arguments
+    /// * 'permissions' - [Permissions]
+    /// * 'verify_store' - Boolean
+    pub async fn grant_online(
+        &mut self,
+        permissions: Permissions,
+        verify_store: bool,
+    ) -> Result<()> {
+        self.grant(permissions)?;
+        self.push(verify_store).await
+    }
+
+    /// Compact the history of the Register up to the entry at `cutoff`, and push the snapshot
+    /// to the network. See `compact` for details.
+    ///
+    /// # Arguments
+    /// * 'cutoff' - [EntryHash]
+    /// * 'verify_store' - Boolean
+    pub async fn compact_online(&mut self, cutoff: EntryHash, verify_store: bool) -> Result<()> {
+        self.compact(cutoff)?;
+        self.push(verify_store).await
+    }
+
+    /// Access the underlying MerkleDag (e.g. for access to history)
     /// NOTE: This API is unstable and may be removed in the future
-    pub fn merkle_reg(&self) -> &MerkleReg<Entry> {
+    pub fn merkle_reg(&self) -> &MerkleDag {
         self.register.merkle_reg()
     }
 
     // ********* Private helpers  *********
 
+    // The client the Register is synced with.
+    pub(crate) fn client(&self) -> &Client {
+        &self.client
+    }
+
     // Make a storage payment for the provided network address
     async fn make_payment(
         &self,
@@ -758,6 +998,90 @@ impl ClientRegister {
         Ok(payment_result)
     }
 
+    // Pay for and upload the Chunks oversized entries were spilled into,
+    // returning the storage cost and royalties paid.
+    async fn upload_overflow(
+        &mut self,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(NanoTokens, NanoTokens)> {
+        if self.overflow.is_empty() {
+            return Ok((NanoTokens::zero(), NanoTokens::zero()));
+        }
+
+        debug!(
+            "Uploading {} overflow Chunks of Register at {:?}",
+            self.overflow.len(),
+            self.address()
+        );
+        let payment_result = wallet_client
+            .pay_for_storage(
+                self.overflow
+                    .keys()
+                    .map(|name| NetworkAddress::ChunkAddress(ChunkAddress::new(*name))),
+            )
+            .await?;
+        wallet_client.store_local_wallet()?;
+
+        while let Some((name, chunk)) = self.overflow.pop_first() {
+            let chunk_addr = chunk.network_address();
+            let result = match wallet_client.get_recent_payment_for_addr(&chunk_addr) {
+                Ok((payment, payee)) => {
+                    self.client
+                        .store_chunk(chunk.clone(), payee, payment, verify_store, None)
+                        .await
+                }
+                Err(err) => Err(err.into()),
+            };
+            if let Err(err) = result {
+                warn!("Failed to upload overflow Chunk {name:?}: {err:?}");
+                // We keep the chunk for next sync to retry
+                let _ = self.overflow.insert(name, chunk);
+                return Err(err);
+            }
+            wallet_client.remove_payment_for_addr(&chunk_addr)?;
+        }
+
+        Ok((payment_result.storage_cost, payment_result.royalty_fees))
+    }
+
+    // Return the value of the entry, following the linked Chunks it was spilled into, if any.
+    async fn fetch_payload(&self, entry: Entry) -> Result<Vec<u8>> {
+        let Some(head) = overflow_head(&entry) else {
+            return Ok(entry);
+        };
+
+        let mut payload = Vec::new();
+        let mut next = Some(head);
+        while let Some(name) = next {
+            let chunk = match self.overflow.get(&name) {
+                Some(chunk) => chunk.clone(),
+                None => {
+                    self.client
+                        .get_chunk(ChunkAddress::new(name), false, None)
+                        .await?
+                }
+            };
+            let value = chunk.value();
+            let segment = match value.split_first() {
+                Some((0, segment)) => {
+                    next = None;
+                    segment
+                }
+                Some((1, rest)) if rest.len() >= XOR_NAME_LEN => {
+                    let (link, segment) = rest.split_at(XOR_NAME_LEN);
+                    let mut xorname = [0; XOR_NAME_LEN];
+                    xorname.copy_from_slice(link);
+                    next = Some(XorName(xorname));
+                    segment
+                }
+                _ => return Err(Error::InvalidRegisterOverflow(name)),
+            };
+            payload.extend_from_slice(segment);
+        }
+        Ok(payload)
+    }
+
     /// Publish a `Register` command on the network.
     /// If `verify_store` is true, it will verify the Register was stored on the network.
     /// Optionally contains the Payment and the PeerId that we paid to.
@@ -791,6 +1115,16 @@ impl ClientRegister {
                 reg.add_op(op)?;
                 reg
             }
+            RegisterCmd::Grant(grant) => {
+                let mut reg = network_reg?;
+                reg.add_grant(grant)?;
+                reg
+            }
+            RegisterCmd::Snapshot(snapshot) => {
+                let mut reg = network_reg?;
+                reg.add_snapshot(snapshot)?;
+                reg
+            }
         };
 
         let network_address = NetworkAddress::from_register_address(*register.address());
@@ -855,7 +1189,21 @@ impl ClientRegister {
         };
 
         // Register edits might exist, so we cannot be sure that just because we get a record back that this should fail
-        Ok(client.network.put_record(record, &put_cfg).await?)
+        client.throttle(Direction::Upload, record.value.len()).await;
+        #[cfg(feature = "open-metrics")]
+        let started = sn_networking::Instant::now();
+        client
+            .timeouts
+            .run(
+                TimeoutOperation::Register,
+                client.network.put_record(record, &put_cfg),
+            )
+            .await??;
+        #[cfg(feature = "open-metrics")]
+        client
+            .metrics
+            .record_request(RequestKind::PutRegister, started.elapsed());
+        Ok(())
     }
 
     /// Retrieve a `Register` from the Network.
@@ -871,3 +1219,83 @@ impl ClientRegister {
         Ok(reg.register()?)
     }
 }
+
+// Split a value too big for a Register entry into Chunks, each starting with a flag telling
+// whether it is followed by the address of the next one, before its share of the value.
+// Returns the entry to write in place of the value, along with the Chunks.
+fn spill_into_chunks(value: &[u8]) -> (Entry, Vec<Chunk>) {
+    let mut chunks = Vec::new();
+    let mut next: Option<XorName> = None;
+    for segment in value.chunks(OVERFLOW_SEGMENT_SIZE).rev() {
+        let mut bytes = BytesMut::with_capacity(1 + XOR_NAME_LEN + segment.len());
+        match next {
+            Some(name) => {
+                bytes.put_u8(1);
+                bytes.put(&name.0[..]);
+            }
+            None => bytes.put_u8(0),
+        }
+        bytes.put(segment);
+        let chunk = Chunk::new(bytes.freeze());
+        next = Some(*chunk.name());
+        chunks.push(chunk);
+    }
+
+    let mut entry = OVERFLOW_ENTRY_MARK.to_vec();
+    if let Some(head) = next {
+        entry.extend_from_slice(&head.0);
+    }
+    (entry, chunks)
+}
+
+// The address of the first Chunk the value of the entry was spilled into, if it was.
+fn overflow_head(entry: &Entry) -> Option<XorName> {
+    let link = entry.strip_prefix(OVERFLOW_ENTRY_MARK)?;
+    let mut xorname = [0; XOR_NAME_LEN];
+    if link.len() != XOR_NAME_LEN {
+        return None;
+    }
+    xorname.copy_from_slice(link);
+    Some(XorName(xorname))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_values_are_spilled_into_linked_chunks() {
+        let value: Vec<u8> = (0..2 * OVERFLOW_SEGMENT_SIZE + 10)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let (entry, chunks) = spill_into_chunks(&value);
+        assert!(entry.len() <= MAX_REG_ENTRY_SIZE);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.value().len() <= MAX_CHUNK_SIZE));
+
+        // follow the links from the head to rebuild the value
+        let chunks: BTreeMap<XorName, Chunk> = chunks
+            .into_iter()
+            .map(|chunk| (*chunk.name(), chunk))
+            .collect();
+        let mut rebuilt = Vec::new();
+        let mut next = overflow_head(&entry);
+        while let Some(name) = next {
+            let bytes = chunks[&name].value();
+            if bytes[0] == 0 {
+                next = None;
+                rebuilt.extend_from_slice(&bytes[1..]);
+            } else {
+                let mut xorname = [0; XOR_NAME_LEN];
+                xorname.copy_from_slice(&bytes[1..1 + XOR_NAME_LEN]);
+                next = Some(XorName(xorname));
+                rebuilt.extend_from_slice(&bytes[1 + XOR_NAME_LEN..]);
+            }
+        }
+        assert_eq!(rebuilt, value);
+
+        assert_eq!(overflow_head(&b"small entry".to_vec()), None);
+    }
+}
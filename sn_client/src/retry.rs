@@ -0,0 +1,347 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{ClientEvent, ClientEventsBroadcaster, ProgressEvent};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error as StdError, sync::Arc, time::Duration};
+
+/// Decides whether an error is worth retrying, overriding the default classification of an operation.
+pub type RetryClassifier = Arc<dyn Fn(&dyn StdError) -> bool + Send + Sync>;
+
+/// Called before every retry of an operation.
+pub type RetryObserver = Arc<dyn Fn(&RetryEvent) + Send + Sync>;
+
+/// The operations of the client which are retried on failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "open-metrics",
+    derive(prometheus_client::encoding::EncodeLabelValue)
+)]
+pub enum RetryOperation {
+    /// Paying for the storage of data.
+    StoragePayment,
+    /// Resending the transactions which were not confirmed by the network.
+    PendingTransactions,
+    /// Storing a new Register until it is verified to be stored.
+    RegisterStore,
+}
+
+impl RetryOperation {
+    /// The policy used for the operation, unless another one is set on the `Client`.
+    pub fn default_policy(&self) -> RetryPolicy {
+        match self {
+            RetryOperation::StoragePayment => RetryPolicy {
+                max_attempts: 20,
+                initial_delay: Duration::from_millis(500),
+                max_delay: Duration::from_secs(60),
+                multiplier: 1.5,
+                jitter: 0.5,
+                classifier: None,
+            },
+            RetryOperation::PendingTransactions => RetryPolicy {
+                max_attempts: 12,
+                initial_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(1),
+                multiplier: 1.0,
+                jitter: 0.0,
+                classifier: None,
+            },
+            RetryOperation::RegisterStore => RetryPolicy {
+                max_attempts: 10,
+                initial_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(30),
+                multiplier: 2.0,
+                jitter: 0.2,
+                classifier: None,
+            },
+        }
+    }
+}
+
+/// How an operation is retried: how many times, how long to wait between the attempts, and which
+/// errors are worth retrying.
+///
+/// The delay before the n-th retry is `initial_delay * multiplier^(n - 1)`, capped at `max_delay`,
+/// then randomised by up to `jitter` times itself in either direction, so that clients failing at
+/// the same time do not retry in lockstep.
+#[derive(Clone, custom_debug::Debug)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: usize,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    /// Between 0 and 1.
+    pub jitter: f64,
+    #[debug(skip)]
+    pub classifier: Option<RetryClassifier>,
+}
+
+impl RetryPolicy {
+    /// A policy which never retries.
+    pub fn no_retry() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            multiplier: 1.0,
+            jitter: 0.0,
+            classifier: None,
+        }
+    }
+
+    /// Sets the function deciding which errors are retried.
+    pub fn with_classifier(
+        mut self,
+        classifier: impl Fn(&dyn StdError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// The delay before the given retry, starting at 1, for a random factor between -1 and 1.
+    fn delay(&self, retry: usize, random_factor: f64) -> Duration {
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let backoff = self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        let capped = backoff.min(self.max_delay.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * random_factor;
+        Duration::from_secs_f64((capped * (1.0 + jitter)).max(0.0))
+    }
+}
+
+/// A retry about to happen.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RetryEvent {
+    pub operation: RetryOperation,
+    /// The number of the attempt which failed, starting at 1.
+    pub attempt: usize,
+    /// How long the client waits before the next attempt.
+    pub delay: Duration,
+    /// Why the last attempt failed.
+    pub reason: String,
+}
+
+/// The retry policies of a `Client`, by operation.
+#[derive(Clone, Default, custom_debug::Debug)]
+pub(crate) struct RetryPolicies {
+    policies: BTreeMap<RetryOperation, RetryPolicy>,
+    #[debug(skip)]
+    observer: Option<RetryObserver>,
+}
+
+impl RetryPolicies {
+    pub(crate) fn set_policy(&mut self, operation: RetryOperation, policy: RetryPolicy) {
+        let _ = self.policies.insert(operation, policy);
+    }
+
+    pub(crate) fn set_observer(&mut self, observer: Option<RetryObserver>) {
+        self.observer = observer;
+    }
+
+    pub(crate) fn policy(&self, operation: RetryOperation) -> RetryPolicy {
+        self.policies
+            .get(&operation)
+            .cloned()
+            .unwrap_or_else(|| operation.default_policy())
+    }
+
+    /// Starts tracking the attempts of an operation.
+    pub(crate) fn start(&self, operation: RetryOperation) -> Retries {
+        Retries {
+            operation,
+            policy: self.policy(operation),
+            observer: self.observer.clone(),
+            events_broadcaster: None,
+            #[cfg(feature = "open-metrics")]
+            metrics: None,
+            attempt: 1,
+        }
+    }
+}
+
+/// The attempts made at an operation.
+pub(crate) struct Retries {
+    operation: RetryOperation,
+    policy: RetryPolicy,
+    observer: Option<RetryObserver>,
+    events_broadcaster: Option<ClientEventsBroadcaster>,
+    #[cfg(feature = "open-metrics")]
+    metrics: Option<crate::ClientMetrics>,
+    attempt: usize,
+}
+
+impl Retries {
+    /// Broadcasts the retries as `ProgressEvent::Retry`.
+    pub(crate) fn with_events_broadcaster(mut self, broadcaster: ClientEventsBroadcaster) -> Self {
+        self.events_broadcaster = Some(broadcaster);
+        self
+    }
+
+    /// Counts the retries in the metrics of the client.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn with_metrics(mut self, metrics: crate::ClientMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Records the failure of the current attempt, returning how long to wait before the next one,
+    /// or `None` if the error is not worth retrying or no attempts are left.
+    /// `retriable` is the default classification of the error, used unless the policy has its own.
+    pub(crate) fn on_failure(&mut self, error: &dyn StdError, retriable: bool) -> Option<Duration> {
+        let retriable = match &self.policy.classifier {
+            Some(classifier) => classifier(error),
+            None => retriable,
+        };
+        if !retriable {
+            debug!(
+                "{:?} failed with a non retriable error: {error}",
+                self.operation
+            );
+            return None;
+        }
+        self.next_delay(error.to_string())
+    }
+
+    /// Records the failure of the current attempt, returning how long to wait before the next one,
+    /// or `None` if no attempts are left.
+    pub(crate) fn next_delay(&mut self, reason: String) -> Option<Duration> {
+        if self.attempt >= self.policy.max_attempts {
+            warn!(
+                "{:?} failed after {} attempts: {reason}",
+                self.operation, self.attempt
+            );
+            return None;
+        }
+
+        let delay = self
+            .policy
+            .delay(self.attempt, rand::thread_rng().gen_range(-1.0..=1.0));
+        let event = RetryEvent {
+            operation: self.operation,
+            attempt: self.attempt,
+            delay,
+            reason,
+        };
+        debug!("Retrying {:?}: {event:?}", self.operation);
+        if let Some(observer) = &self.observer {
+            observer(&event);
+        }
+        if let Some(broadcaster) = &self.events_broadcaster {
+            broadcaster.broadcast(ClientEvent::Progress(ProgressEvent::Retry(event)));
+        }
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_retry(self.operation);
+        }
+        self.attempt += 1;
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io, sync::Mutex};
+
+    #[test]
+    fn delays_back_off_exponentially_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.5,
+            classifier: None,
+        };
+        let delays: Vec<_> = (1..=5)
+            .map(|retry| policy.delay(retry, 0.0).as_secs())
+            .collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+
+        assert_eq!(policy.delay(2, 1.0), Duration::from_secs(3));
+        assert_eq!(policy.delay(2, -1.0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retries_stop_after_max_attempts_or_on_non_retriable_errors() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut policies = RetryPolicies::default();
+        let recorded = Arc::clone(&events);
+        policies.set_observer(Some(Arc::new(move |event: &RetryEvent| {
+            if let Ok(mut events) = recorded.lock() {
+                events.push(event.attempt);
+            }
+        })));
+        policies.set_policy(
+            RetryOperation::StoragePayment,
+            RetryPolicy {
+                max_attempts: 3,
+                ..RetryPolicy::no_retry()
+            },
+        );
+
+        let error = io::Error::new(io::ErrorKind::TimedOut, "timed out");
+        let mut retries = policies.start(RetryOperation::StoragePayment);
+        assert_eq!(retries.on_failure(&error, false), None);
+        assert_eq!(retries.on_failure(&error, true), Some(Duration::ZERO));
+        assert_eq!(retries.on_failure(&error, true), Some(Duration::ZERO));
+        assert_eq!(retries.on_failure(&error, true), None);
+        assert_eq!(
+            events.lock().map(|events| events.clone()).ok(),
+            Some(vec![1, 2])
+        );
+
+        // a classifier overrides the default classification.
+        policies.set_policy(
+            RetryOperation::StoragePayment,
+            RetryPolicy {
+                max_attempts: 3,
+                ..RetryPolicy::no_retry()
+            }
+            .with_classifier(|error| error.to_string().contains("timed out")),
+        );
+        let mut retries = policies.start(RetryOperation::StoragePayment);
+        assert!(retries.on_failure(&error, false).is_some());
+        let other_error = io::Error::other("invalid");
+        assert!(retries.on_failure(&other_error, true).is_none());
+
+        assert_eq!(
+            policies
+                .start(RetryOperation::RegisterStore)
+                .policy
+                .max_attempts,
+            RetryOperation::RegisterStore.default_policy().max_attempts
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_are_broadcast_as_progress_events() -> eyre::Result<()> {
+        let broadcaster = ClientEventsBroadcaster::default();
+        let mut receiver = broadcaster.subscribe();
+        let mut retries = RetryPolicies::default()
+            .start(RetryOperation::PendingTransactions)
+            .with_events_broadcaster(broadcaster);
+
+        let error = io::Error::other("not confirmed");
+        assert!(retries.on_failure(&error, true).is_some());
+
+        let event = match receiver.recv().await? {
+            ClientEvent::Progress(ProgressEvent::Retry(event)) => event,
+            other => eyre::bail!("Unexpected event {other:?}"),
+        };
+        assert_eq!(event.operation, RetryOperation::PendingTransactions);
+        assert_eq!(event.attempt, 1);
+
+        // progress events can be forwarded to other processes.
+        let progress = ProgressEvent::Retry(event);
+        let bytes = rmp_serde::to_vec(&progress)?;
+        assert_eq!(rmp_serde::from_slice::<ProgressEvent>(&bytes)?, progress);
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{bandwidth::Direction, error::Result, Client, Error, WalletClient};
+use bytes::Bytes;
+use libp2p::{
+    kad::{Quorum, Record},
+    PeerId,
+};
+use sn_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
+use sn_protocol::{
+    error::Error as ProtocolError,
+    storage::{
+        try_deserialize_record, try_serialize_record, RecordHeader, RecordKind, RetryStrategy,
+        Scratchpad, ScratchpadAddress,
+    },
+    NetworkAddress,
+};
+use sn_transfers::{NanoTokens, Payment};
+use std::collections::HashSet;
+use xor_name::XorName;
+
+impl Client {
+    /// Create a Scratchpad owned by the client's signer, at the address derived from `meta`,
+    /// holding `data`. The storage is paid with the wallet.
+    ///
+    /// Returns the Scratchpad along with the total cost paid for it.
+    pub async fn create_scratchpad(
+        &self,
+        meta: XorName,
+        data: Bytes,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+    ) -> Result<(Scratchpad, NanoTokens)> {
+        let address = ScratchpadAddress::new(meta, self.signer_pk());
+        let scratchpad = Scratchpad::new(address, 0, data, self.signer())?;
+
+        let net_addr = scratchpad.network_address();
+        let payment_result = wallet_client
+            .pay_for_storage(std::iter::once(net_addr.clone()))
+            .await?;
+        let cost = payment_result
+            .storage_cost
+            .checked_add(payment_result.royalty_fees)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        if let Err(err) = wallet_client.store_local_wallet() {
+            warn!("Failed to store wallet with cached payment proofs: {err:?}");
+        }
+        let (payment, payee) = wallet_client.get_recent_payment_for_addr(&net_addr)?;
+
+        self.put_scratchpad(&scratchpad, Some((payment, payee)), verify_store)
+            .await?;
+        Ok((scratchpad, cost))
+    }
+
+    /// Overwrite the data of the Scratchpad at the address, signing a new version of it with the
+    /// client's signer, which must be its owner.
+    pub async fn update_scratchpad(
+        &self,
+        address: ScratchpadAddress,
+        data: Bytes,
+        verify_store: bool,
+    ) -> Result<Scratchpad> {
+        let current = self.get_scratchpad(address).await?;
+        let scratchpad = Scratchpad::new(address, current.counter() + 1, data, self.signer())?;
+        self.put_scratchpad(&scratchpad, None, verify_store).await?;
+        Ok(scratchpad)
+    }
+
+    /// Clear the data of the Scratchpad at the address. Records can't be removed from the
+    /// network, so the Scratchpad is overwritten with no data, and can be written to again.
+    pub async fn clear_scratchpad(
+        &self,
+        address: ScratchpadAddress,
+        verify_store: bool,
+    ) -> Result<Scratchpad> {
+        self.update_scratchpad(address, Bytes::new(), verify_store)
+            .await
+    }
+
+    /// Retrieve the newest version of the Scratchpad at the address from the network.
+    pub async fn get_scratchpad(&self, address: ScratchpadAddress) -> Result<Scratchpad> {
+        let key = NetworkAddress::from_scratchpad_address(address).to_record_key();
+        let get_cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: Some(RetryStrategy::Quick),
+            target_record: None,
+            expected_holders: Default::default(),
+        };
+
+        let records = match self.network.get_record_from_network(key, &get_cfg).await {
+            Ok(record) => {
+                self.throttle(Direction::Download, record.value.len()).await;
+                vec![record]
+            }
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { result_map })) => {
+                // holders may not have all got the latest version yet
+                result_map
+                    .into_values()
+                    .map(|(record, _holders)| record)
+                    .collect()
+            }
+            Err(err) => {
+                warn!("Failed to get Scratchpad at {address:?} from the network: {err:?}");
+                return Err(ProtocolError::ScratchpadNotFound(Box::new(address)).into());
+            }
+        };
+
+        let mut newest: Option<Scratchpad> = None;
+        for record in records {
+            let scratchpad = match scratchpad_from_record(&record, &address) {
+                Ok(scratchpad) => scratchpad,
+                Err(err) => {
+                    warn!("Ignoring invalid Scratchpad record at {address:?}: {err:?}");
+                    continue;
+                }
+            };
+            if newest.as_ref().is_none_or(|n| scratchpad.supersedes(n)) {
+                newest = Some(scratchpad);
+            }
+        }
+        newest.ok_or_else(|| ProtocolError::ScratchpadNotFound(Box::new(address)).into())
+    }
+
+    /// Store the Scratchpad on the network, sending it along with the payment to the payee when
+    /// it's being created.
+    async fn put_scratchpad(
+        &self,
+        scratchpad: &Scratchpad,
+        payment: Option<(Payment, PeerId)>,
+        verify_store: bool,
+    ) -> Result<()> {
+        let network_address = scratchpad.network_address();
+        let key = network_address.to_record_key();
+        let (value, payee) = match payment {
+            Some((payment, payee)) => (
+                try_serialize_record(&(payment, scratchpad), RecordKind::ScratchpadWithPayment)?,
+                Some(vec![payee]),
+            ),
+            None => (
+                try_serialize_record(scratchpad, RecordKind::Scratchpad)?,
+                None,
+            ),
+        };
+        let record = Record {
+            key: key.clone(),
+            value: value.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+
+        let (record_to_verify, expected_holders) = if verify_store {
+            let expected_holders: HashSet<_> = self
+                .network
+                .get_closest_peers(&network_address, true)
+                .await?
+                .iter()
+                .cloned()
+                .collect();
+            (
+                Some(Record {
+                    key,
+                    value: try_serialize_record(scratchpad, RecordKind::Scratchpad)?.to_vec(),
+                    publisher: None,
+                    expires: None,
+                }),
+                expected_holders,
+            )
+        } else {
+            (None, Default::default())
+        };
+
+        let verification_cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: Some(RetryStrategy::Quick),
+            target_record: record_to_verify,
+            expected_holders,
+        };
+        let put_cfg = PutRecordCfg {
+            put_quorum: Quorum::All,
+            retry_strategy: Some(RetryStrategy::Balanced),
+            use_put_record_to: payee,
+            verification: Some((VerificationKind::Network, verification_cfg)),
+        };
+        self.throttle(Direction::Upload, record.value.len()).await;
+        self.network.put_record(record, &put_cfg).await?;
+        Ok(())
+    }
+}
+
+/// Deserialise the Scratchpad from the record, checking it's the one at the address and was signed
+/// by its owner.
+fn scratchpad_from_record(record: &Record, address: &ScratchpadAddress) -> Result<Scratchpad> {
+    let header = RecordHeader::from_record(record)?;
+    if !matches!(header.kind, RecordKind::Scratchpad) {
+        return Err(NetworkError::RecordKindMismatch(RecordKind::Scratchpad).into());
+    }
+    let scratchpad: Scratchpad = try_deserialize_record(record)?;
+    if scratchpad.address() != address {
+        return Err(ProtocolError::ScratchpadNotFound(Box::new(*address)).into());
+    }
+    scratchpad.verify()?;
+    Ok(scratchpad)
+}
@@ -10,6 +10,8 @@ use crate::{
     acc_packet::{create_faucet_account_and_wallet, load_account_wallet_or_create_with_mnemonic},
     send, Client, WalletClient,
 };
+use libp2p::identity::Keypair;
+use sn_networking::NetworkBuilder;
 use sn_peers_acquisition::parse_peer_addr;
 use sn_protocol::{storage::Chunk, NetworkAddress};
 use sn_transfers::{HotWallet, NanoTokens};
@@ -18,7 +20,10 @@ use bls::SecretKey;
 use bytes::Bytes;
 use eyre::{bail, Result};
 use rand::distributions::{Distribution, Standard};
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tokio::{
     sync::Mutex,
     time::{Duration, Instant},
@@ -53,6 +58,24 @@ pub async fn get_new_client(owner_sk: SecretKey) -> Result<Client> {
     Ok(client)
 }
 
+/// Get a new Client which is not connected to any network, for testing with the chunks served
+/// from its chunk cache. It must be built within a tokio runtime.
+pub fn get_unconnected_client(root_dir: PathBuf) -> Result<Client> {
+    let network_builder = NetworkBuilder::new(Keypair::generate_ed25519(), true, root_dir);
+    let (network, ..) = network_builder.build_client()?;
+    Ok(Client {
+        network,
+        events_broadcaster: Default::default(),
+        signer: Arc::new(SecretKey::random()),
+        chunk_cache: None,
+        retry_policies: Default::default(),
+        timeouts: Default::default(),
+        bandwidth: Default::default(),
+        #[cfg(feature = "open-metrics")]
+        metrics: Default::default(),
+    })
+}
+
 /// Generate a Chunk with random bytes
 pub fn random_file_chunk() -> Chunk {
     let mut rng = rand::thread_rng();
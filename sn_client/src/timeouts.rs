@@ -0,0 +1,111 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sn_networking::target_arch::timeout;
+use std::{collections::BTreeMap, future::Future, time::Duration};
+
+/// The network operations of the client which are given up on after a deadline of their own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TimeoutOperation {
+    /// Fetching a Chunk from the network, its retries included.
+    ChunkGet,
+    /// Storing a Chunk, along with the verification it was stored if asked for.
+    ChunkPut,
+    /// Fetching a spend, e.g. to verify a transfer.
+    SpendQuery,
+    /// Fetching or storing a Register.
+    Register,
+}
+
+impl TimeoutOperation {
+    /// The timeout of the operation, unless another one is set on the `Client`.
+    ///
+    /// They leave room for the slowest `RetryStrategy` the operation is run with.
+    pub fn default_timeout(&self) -> Duration {
+        match self {
+            TimeoutOperation::ChunkGet => Duration::from_secs(240),
+            TimeoutOperation::ChunkPut => Duration::from_secs(300),
+            TimeoutOperation::SpendQuery => Duration::from_secs(600),
+            TimeoutOperation::Register => Duration::from_secs(300),
+        }
+    }
+}
+
+/// The timeouts of a `Client`, by operation.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OperationTimeouts {
+    timeouts: BTreeMap<TimeoutOperation, Duration>,
+}
+
+impl OperationTimeouts {
+    pub(crate) fn set_timeout(&mut self, operation: TimeoutOperation, duration: Duration) {
+        let _ = self.timeouts.insert(operation, duration);
+    }
+
+    pub(crate) fn timeout(&self, operation: TimeoutOperation) -> Duration {
+        self.timeouts
+            .get(&operation)
+            .copied()
+            .unwrap_or_else(|| operation.default_timeout())
+    }
+
+    /// Runs the operation, erroring out with `Error::OperationTimeout` if it doesn't complete in
+    /// time.
+    pub(crate) async fn run<F: Future>(
+        &self,
+        operation: TimeoutOperation,
+        future: F,
+    ) -> Result<F::Output> {
+        let duration = self.timeout(operation);
+        timeout(duration, future).await.map_err(|_| {
+            warn!("{operation:?} timed out after {duration:?}");
+            Error::OperationTimeout(operation, duration)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_networking::target_arch::sleep;
+
+    #[tokio::test]
+    async fn operations_time_out_on_their_own_deadline() -> eyre::Result<()> {
+        let mut timeouts = OperationTimeouts::default();
+        assert!(
+            timeouts.timeout(TimeoutOperation::SpendQuery)
+                > timeouts.timeout(TimeoutOperation::ChunkGet)
+        );
+
+        timeouts.set_timeout(TimeoutOperation::ChunkGet, Duration::from_millis(10));
+        assert_eq!(
+            timeouts.timeout(TimeoutOperation::ChunkGet),
+            Duration::from_millis(10)
+        );
+        assert!(matches!(
+            timeouts
+                .run(TimeoutOperation::ChunkGet, sleep(Duration::from_secs(1)))
+                .await,
+            Err(Error::OperationTimeout(TimeoutOperation::ChunkGet, _))
+        ));
+
+        // the other operations keep their own deadline.
+        assert_eq!(
+            timeouts
+                .run(TimeoutOperation::SpendQuery, async {
+                    sleep(Duration::from_millis(50)).await;
+                    42
+                })
+                .await?,
+            42
+        );
+        Ok(())
+    }
+}
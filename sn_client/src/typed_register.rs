@@ -0,0 +1,203 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, ClientRegister, Error, WalletClient};
+use libp2p::PeerId;
+use serde::{de::DeserializeOwned, Serialize};
+use sn_registers::{Entry, EntryHash, RegisterAddress};
+use sn_transfers::{NanoTokens, Payment};
+use std::{collections::BTreeSet, marker::PhantomData, sync::Arc};
+
+/// Checks a value before it is written to, or merged into, a `TypedRegister`,
+/// returning the reason it is rejected for if it is.
+pub type EntrySchema<T> = Arc<dyn Fn(&T) -> std::result::Result<(), String> + Send + Sync>;
+
+/// A Register whose entries are values of type `T`, serialised with MessagePack.
+///
+/// The values can optionally be validated against a schema: the values written which don't
+/// match it are rejected, as are the entries written by others when syncing, before they are
+/// merged into the local replica.
+#[derive(Clone)]
+pub struct TypedRegister<T> {
+    register: ClientRegister,
+    schema: Option<EntrySchema<T>>,
+    value_type: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> TypedRegister<T> {
+    /// Hold values of type `T` in the Register.
+    pub fn new(register: ClientRegister) -> Self {
+        Self {
+            register,
+            schema: None,
+            value_type: PhantomData,
+        }
+    }
+
+    /// Validate the values written to and merged into the Register with the schema.
+    pub fn with_schema(mut self, schema: EntrySchema<T>) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Return the address of the Register.
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Access the untyped Register.
+    pub fn register(&self) -> &ClientRegister {
+        &self.register
+    }
+
+    /// Return the untyped Register.
+    pub fn into_register(self) -> ClientRegister {
+        self.register
+    }
+
+    /// Read the last values, or values when there are branches.
+    pub async fn read(&self) -> Result<Vec<(EntryHash, T)>> {
+        self.register
+            .read_payloads()
+            .await?
+            .into_iter()
+            .map(|(hash, payload)| Ok((hash, decode_entry(hash, &payload)?)))
+            .collect()
+    }
+
+    /// Return the value of the entry corresponding to the provided 'hash'.
+    pub async fn get(&self, hash: EntryHash) -> Result<T> {
+        let payload = self.register.get_payload(hash).await?;
+        decode_entry(hash, &payload)
+    }
+
+    /// Write a value onto the Register atop the latest value, see `ClientRegister::write`.
+    pub fn write(&mut self, value: &T) -> Result<EntryHash> {
+        let entry = encode_value(self.schema.as_ref(), value)?;
+        self.register.write(&entry)
+    }
+
+    /// Write a value onto the Register merging all its branches,
+    /// see `ClientRegister::write_merging_branches`.
+    pub fn write_merging_branches(&mut self, value: &T) -> Result<EntryHash> {
+        let entry = encode_value(self.schema.as_ref(), value)?;
+        self.register.write_merging_branches(&entry)
+    }
+
+    /// Write a value onto the Register atop the given entries, see `ClientRegister::write_atop`.
+    pub fn write_atop(&mut self, value: &T, children: &BTreeSet<EntryHash>) -> Result<EntryHash> {
+        let entry = encode_value(self.schema.as_ref(), value)?;
+        self.register.write_atop(&entry, children)
+    }
+
+    /// Sync the Register with its replicas on the network, see `ClientRegister::sync`.
+    ///
+    /// When the Register has a schema, the entries of the replica on the network which are
+    /// unknown locally are validated first, and nothing is merged if one of them is invalid.
+    pub async fn sync(
+        &mut self,
+        wallet_client: &mut WalletClient,
+        verify_store: bool,
+        payment_info: Option<(Payment, PeerId)>,
+    ) -> Result<(NanoTokens, NanoTokens)> {
+        if self.schema.is_some() {
+            self.validate_network_replica().await?;
+        }
+        self.register
+            .sync(wallet_client, verify_store, payment_info)
+            .await
+    }
+
+    // Check the entries of the replica on the network we don't have against the schema.
+    async fn validate_network_replica(&self) -> Result<()> {
+        let client = self.register.client().clone();
+        let replica = match ClientRegister::retrieve(client, *self.address()).await {
+            Ok(replica) => replica,
+            Err(err) => {
+                // the Register may not have been stored yet, `sync` finds out
+                debug!(
+                    "Could not retrieve Register {:?} to validate it: {err:?}",
+                    self.address()
+                );
+                return Ok(());
+            }
+        };
+
+        for (hash, _) in replica.register.tips_history() {
+            if self.register.register.get(hash).is_ok() {
+                continue;
+            }
+            let payload = replica.get_payload(hash).await?;
+            let value = decode_entry::<T>(hash, &payload)?;
+            if let Some(schema) = &self.schema {
+                schema(&value).map_err(|reason| Error::InvalidTypedEntry(hash, reason))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Validate the value against the schema, if any, and serialise it into an entry.
+fn encode_value<T: Serialize>(schema: Option<&EntrySchema<T>>, value: &T) -> Result<Entry> {
+    if let Some(schema) = schema {
+        schema(value).map_err(Error::TypedValueRejected)?;
+    }
+    Ok(rmp_serde::to_vec(value)?)
+}
+
+// Deserialise the value of an entry.
+fn decode_entry<T: DeserializeOwned>(hash: EntryHash, payload: &[u8]) -> Result<T> {
+    rmp_serde::from_slice(payload).map_err(|err| Error::InvalidTypedEntry(hash, err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Message {
+        author: String,
+        text: String,
+    }
+
+    #[test]
+    fn values_are_validated_and_round_trip() -> Result<()> {
+        let schema: EntrySchema<Message> = Arc::new(|message: &Message| {
+            if message.text.is_empty() {
+                Err("empty message".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        let message = Message {
+            author: "alice".to_string(),
+            text: "hello".to_string(),
+        };
+
+        let entry = encode_value(Some(&schema), &message)?;
+        let hash = EntryHash::default();
+        assert_eq!(decode_entry::<Message>(hash, &entry)?, message);
+
+        let empty = Message {
+            author: "alice".to_string(),
+            text: String::new(),
+        };
+        assert!(matches!(
+            encode_value(Some(&schema), &empty),
+            Err(Error::TypedValueRejected(reason)) if reason == "empty message"
+        ));
+        assert!(encode_value(None, &empty).is_ok());
+
+        assert!(matches!(
+            decode_entry::<Message>(hash, b"not a message"),
+            Err(Error::InvalidTypedEntry(invalid, _)) if invalid == hash
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, FilesApi};
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::ChunkAddress;
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use xor_name::XorName;
+
+/// The name of the directory holding the manifests of the uploads, in the root directory of the client.
+const MANIFESTS_DIR_NAME: &str = "upload_manifests";
+const MANIFEST_FILE_NAME: &str = "manifest";
+/// Holds the names of the chunks stored so far, appended one after the other as they are stored.
+const COMPLETED_FILE_NAME: &str = "completed";
+const CHUNKS_DIR_NAME: &str = "chunks";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkedFile {
+    // The size and modification time of the file when it was chunked, to tell if it changed since.
+    size: u64,
+    modified: Option<SystemTime>,
+    data_map: ChunkAddress,
+    chunks: Vec<(XorName, PathBuf)>,
+}
+
+/// The state of the upload of a file, persisted in the root directory of the client, so that an
+/// interrupted upload can be resumed where it stopped, including by another process.
+///
+/// The file is chunked once, along with its data map, and the chunks stored by the network are
+/// recorded as they are, by the `Uploader` the manifest is inserted into. Resuming the upload only
+/// uploads the chunks which weren't recorded, the payments already made for them being reused.
+#[derive(Debug)]
+pub struct UploadManifest {
+    dir: PathBuf,
+    file: ChunkedFile,
+    completed: BTreeSet<XorName>,
+}
+
+impl UploadManifest {
+    /// Loads the manifest of the upload of the file, or chunks the file and creates its manifest
+    /// if it was never uploaded, or was changed since.
+    pub fn chunk_or_resume(root_dir: &Path, path: &Path) -> Result<Self> {
+        let path = path.canonicalize()?;
+        let metadata = fs::metadata(&path)?;
+        let (size, modified) = (metadata.len(), metadata.modified().ok());
+        let key = XorName::from_content(path.to_string_lossy().as_bytes());
+        let dir = root_dir.join(MANIFESTS_DIR_NAME).join(hex::encode(key));
+
+        match fs::read(dir.join(MANIFEST_FILE_NAME)) {
+            Ok(bytes) => {
+                let file: ChunkedFile = rmp_serde::from_slice(&bytes)?;
+                if file.size == size
+                    && file.modified == modified
+                    && file
+                        .chunks
+                        .iter()
+                        .all(|(_, chunk_path)| chunk_path.is_file())
+                {
+                    let completed = read_completed(&dir)?;
+                    info!(
+                        "Resuming the upload of {path:?}, {}/{} chunks already stored",
+                        completed.len(),
+                        file.chunks.len()
+                    );
+                    return Ok(Self {
+                        dir,
+                        file,
+                        completed,
+                    });
+                }
+                info!("{path:?} changed since its upload was interrupted, chunking it again");
+                fs::remove_dir_all(&dir)?;
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let chunks_dir = dir.join(CHUNKS_DIR_NAME);
+        fs::create_dir_all(&chunks_dir)?;
+        let (data_map, _, _, chunks) = FilesApi::chunk_file(&path, &chunks_dir, true)?;
+        let file = ChunkedFile {
+            size,
+            modified,
+            data_map,
+            chunks,
+        };
+        // written last, so that a manifest is only found once the file is fully chunked.
+        let tmp_path = dir.join(format!("{MANIFEST_FILE_NAME}.tmp"));
+        fs::write(&tmp_path, rmp_serde::to_vec(&file)?)?;
+        fs::rename(&tmp_path, dir.join(MANIFEST_FILE_NAME))?;
+        debug!("Chunked {path:?} into {} chunks", file.chunks.len());
+
+        Ok(Self {
+            dir,
+            file,
+            completed: BTreeSet::new(),
+        })
+    }
+
+    /// The address of the data map of the file.
+    pub fn data_map(&self) -> ChunkAddress {
+        self.file.data_map
+    }
+
+    /// All the chunks of the file, the data map included.
+    pub fn chunks(&self) -> &[(XorName, PathBuf)] {
+        &self.file.chunks
+    }
+
+    /// The chunks which haven't been stored yet.
+    pub fn pending_chunks(&self) -> Vec<(XorName, PathBuf)> {
+        self.file
+            .chunks
+            .iter()
+            .filter(|(xorname, _)| !self.completed.contains(xorname))
+            .cloned()
+            .collect()
+    }
+
+    /// Whether all the chunks of the file have been stored.
+    pub fn is_complete(&self) -> bool {
+        self.file
+            .chunks
+            .iter()
+            .all(|(xorname, _)| self.completed.contains(xorname))
+    }
+
+    /// Records the chunk as stored, if it is one of the chunks of the file.
+    pub fn mark_completed(&mut self, xorname: XorName) -> Result<()> {
+        if self.completed.contains(&xorname)
+            || !self.file.chunks.iter().any(|(name, _)| *name == xorname)
+        {
+            return Ok(());
+        }
+        let mut completed_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(COMPLETED_FILE_NAME))?;
+        completed_file.write_all(&xorname.0)?;
+        let _ = self.completed.insert(xorname);
+        Ok(())
+    }
+
+    /// Removes the manifest and the chunks of the file, e.g. once it's uploaded.
+    pub fn remove(self) -> Result<()> {
+        fs::remove_dir_all(&self.dir)?;
+        Ok(())
+    }
+}
+
+// A partly written name, when the process was stopped while writing it, is ignored.
+fn read_completed(dir: &Path) -> Result<BTreeSet<XorName>> {
+    let bytes = match fs::read(dir.join(COMPLETED_FILE_NAME)) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(bytes
+        .chunks_exact(xor_name::XOR_NAME_LEN)
+        .filter_map(|name| name.try_into().ok().map(XorName))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use rand::Rng;
+    use tempfile::tempdir;
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        rand::thread_rng().fill(&mut bytes[..]);
+        bytes
+    }
+
+    #[test]
+    fn an_interrupted_upload_is_resumed_from_its_manifest() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let root_dir = temp_dir.path().join("client");
+        let file_path = temp_dir.path().join("file");
+        fs::write(&file_path, random_bytes(5 * 1024 * 1024))?;
+
+        let mut manifest = UploadManifest::chunk_or_resume(&root_dir, &file_path)?;
+        let chunks = manifest.chunks().to_vec();
+        assert!(chunks.len() > 2);
+        manifest.mark_completed(chunks[0].0)?;
+        manifest.mark_completed(chunks[1].0)?;
+        // not a chunk of the file
+        manifest.mark_completed(XorName::random(&mut rand::thread_rng()))?;
+        let data_map = manifest.data_map();
+        drop(manifest);
+
+        let manifest = UploadManifest::chunk_or_resume(&root_dir, &file_path)?;
+        assert_eq!(manifest.data_map(), data_map);
+        assert_eq!(manifest.pending_chunks(), chunks[2..].to_vec());
+        assert!(!manifest.is_complete());
+
+        // a changed file is chunked again
+        fs::write(&file_path, random_bytes(5 * 1024 * 1024))?;
+        let manifest = UploadManifest::chunk_or_resume(&root_dir, &file_path)?;
+        assert_ne!(manifest.data_map(), data_map);
+        assert_eq!(manifest.pending_chunks().len(), manifest.chunks().len());
+
+        manifest.remove()?;
+        assert_eq!(fs::read_dir(root_dir.join(MANIFESTS_DIR_NAME))?.count(), 0);
+        Ok(())
+    }
+}
@@ -6,12 +6,19 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod manifest;
+mod rate_limit;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 mod upload;
 
-use self::upload::{start_upload, InnerUploader, MAX_REPAYMENTS_PER_FAILED_ITEM};
-use crate::{Client, ClientRegister, Error, Result, BATCH_SIZE};
+pub use self::manifest::UploadManifest;
+
+use self::{
+    rate_limit::UploadRateLimiter,
+    upload::{start_upload, InnerUploader, MAX_REPAYMENTS_PER_FAILED_ITEM},
+};
+use crate::{Client, ClientRegister, Error, Payer, Result, BATCH_SIZE};
 use itertools::Either;
 use sn_networking::PayeeQuote;
 use sn_protocol::{
@@ -37,6 +44,19 @@ pub struct UploadCfg {
     pub retry_strategy: RetryStrategy,
     pub max_repayments_for_failed_data: usize, // we want people to specify an explicit limit here.
     pub collect_registers: bool,
+    /// The maximum number of items being uploaded at the same time. Falls back to `batch_size`.
+    pub max_concurrent_uploads: Option<usize>,
+    /// The maximum number of item uploads started per second.
+    pub max_uploads_per_sec: Option<u32>,
+    /// The maximum amount the upload may spend, storage cost and royalties included.
+    pub max_spend: Option<NanoTokens>,
+    /// The fraction of the uploaded chunks, between 0 and 1, to verify once all the items are uploaded.
+    /// No verification pass is made if `None`.
+    pub verification_sample: Option<f64>,
+    /// Check whether each chunk is already stored by the network before paying for it.
+    pub check_existing_chunks: bool,
+    /// Quote all the items first and pay for them in a single wallet transaction, rather than in one per batch.
+    pub single_payment: bool,
 }
 
 impl Default for UploadCfg {
@@ -48,6 +68,28 @@ impl Default for UploadCfg {
             retry_strategy: RetryStrategy::Balanced,
             max_repayments_for_failed_data: MAX_REPAYMENTS_PER_FAILED_ITEM,
             collect_registers: false,
+            max_concurrent_uploads: None,
+            max_uploads_per_sec: None,
+            max_spend: None,
+            verification_sample: None,
+            check_existing_chunks: true,
+            single_payment: false,
+        }
+    }
+}
+
+impl UploadCfg {
+    /// The maximum number of items being uploaded at the same time.
+    pub fn max_concurrent_uploads(&self) -> usize {
+        self.max_concurrent_uploads.unwrap_or(self.batch_size)
+    }
+
+    /// The maximum number of items paid for in a single wallet transaction.
+    pub fn payment_batch_size(&self) -> usize {
+        if self.single_payment {
+            usize::MAX
+        } else {
+            self.batch_size
         }
     }
 }
@@ -62,6 +104,11 @@ pub struct UploadSummary {
     pub uploaded_registers: BTreeMap<RegisterAddress, ClientRegister>,
     pub uploaded_count: usize,
     pub skipped_count: usize,
+    /// The result of the verification pass, if one was made.
+    pub verification: Option<VerificationReport>,
+    /// What would have been paid for the chunks which were found to be stored already, storage cost and
+    /// royalties included.
+    pub deduplication_savings: NanoTokens,
 }
 
 impl UploadSummary {
@@ -87,11 +134,33 @@ impl UploadSummary {
             uploaded_registers: self.uploaded_registers,
             uploaded_count: self.uploaded_count + other.uploaded_count,
             skipped_count: self.skipped_count + other.skipped_count,
+            verification: match (self.verification, other.verification) {
+                (Some(mut report), Some(other)) => {
+                    report.verified_count += other.verified_count;
+                    report.missing_chunks.extend(other.missing_chunks);
+                    Some(report)
+                }
+                (report, other) => report.or(other),
+            },
+            deduplication_savings: self
+                .deduplication_savings
+                .checked_add(other.deduplication_savings)
+                .ok_or(Error::NumericOverflow)?,
         };
         Ok(summary)
     }
 }
 
+/// The result of the verification pass made after an upload.
+#[derive(Debug, Clone, Default)]
+pub struct VerificationReport {
+    /// The number of chunks found to be stored by the network.
+    pub verified_count: usize,
+    /// The chunks which could not be retrieved from their holders, or whose holders did not agree on their content.
+    /// These were paid for and uploaded again.
+    pub missing_chunks: BTreeSet<ChunkAddress>,
+}
+
 #[derive(Debug, Clone)]
 /// The events emitted from the upload process.
 pub enum UploadEvent {
@@ -232,6 +301,88 @@ impl Uploader {
             .set_collect_registers(collect_registers);
     }
 
+    /// Sets the maximum number of items that are uploaded at the same time, independently of the batch size
+    /// used to get the store costs and make the payments.
+    ///
+    /// By default, this option is set to the batch size.
+    pub fn set_max_concurrent_uploads(&mut self, max_concurrent_uploads: usize) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .set_max_concurrent_uploads(max_concurrent_uploads);
+    }
+
+    /// Sets the maximum number of item uploads started per second, to avoid flooding the network with requests.
+    ///
+    /// By default, there is no limit.
+    pub fn set_max_uploads_per_sec(&mut self, max_uploads_per_sec: Option<u32>) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .set_max_uploads_per_sec(max_uploads_per_sec);
+    }
+
+    /// Sets whether to check if each chunk is already stored by the network before paying for it.
+    /// The nodes quoting a chunk only report it as stored if they hold it themselves, so a chunk held by other nodes
+    /// would be paid for and uploaded again. The savings are recorded in `UploadSummary::deduplication_savings`.
+    ///
+    /// By default, this is set to true.
+    pub fn set_check_existing_chunks(&mut self, check_existing_chunks: bool) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .set_check_existing_chunks(check_existing_chunks);
+    }
+
+    /// Sets the fraction of the uploaded chunks, between 0 and 1, to verify once all the items are uploaded.
+    /// The holders of each chunk in the sample are asked for a proof of its content, and any chunk which fails to be
+    /// verified is paid for and uploaded again. The outcome is recorded in `UploadSummary::verification`.
+    ///
+    /// By default, no verification pass is made.
+    pub fn set_verification_sample(&mut self, verification_sample: Option<f64>) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .set_verification_sample(verification_sample);
+    }
+
+    /// Sets the maximum amount the upload may spend, storage cost and royalties included.
+    /// The upload errors out with `Error::UploadBudgetExceeded` before making a payment which would
+    /// exceed it, e.g. if the store costs rise during the upload. The items paid for so far are still
+    /// uploaded if the upload is resumed.
+    ///
+    /// By default, there is no limit.
+    pub fn set_max_spend(&mut self, max_spend: Option<NanoTokens>) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .set_max_spend(max_spend);
+    }
+
+    /// Sets whether to quote all the items first and pay for them in a single wallet transaction.
+    /// This makes a single spend for a whole directory, instead of one per batch, at the cost of holding every
+    /// quote until the payment, so the quotes of a very large upload may expire before it is made.
+    ///
+    /// By default, this is set to false.
+    pub fn set_single_payment(&mut self, single_payment: bool) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .set_single_payment(single_payment);
+    }
+
+    /// Sets who pays for the storage of the items, e.g. a remote wallet service or a third party paying on the
+    /// user's behalf. The payments it makes are cached in the wallet dir of the root dir, along with the ones of
+    /// the local wallet, so an interrupted upload can be resumed without paying again.
+    ///
+    /// By default, the wallet in the root dir pays.
+    pub fn set_payer(&mut self, payer: Box<dyn Payer>) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .set_payer(payer);
+    }
+
     /// Returns a receiver for UploadEvent.
     /// This method is optional and the upload process can be performed without it.
     pub fn get_event_receiver(&mut self) -> mpsc::Receiver<UploadEvent> {
@@ -249,6 +400,15 @@ impl Uploader {
             .insert_chunk_paths(chunks);
     }
 
+    /// Insert the chunks of a file which weren't stored yet according to its manifest, which
+    /// then records them as they are stored. The manifest is removed once the file is uploaded.
+    pub fn insert_manifest(&mut self, manifest: UploadManifest) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .insert_manifest(manifest);
+    }
+
     /// Insert a list of chunks to upload to upload.
     pub fn insert_chunks(&mut self, chunks: impl IntoIterator<Item = Chunk>) {
         self.inner
@@ -296,6 +456,7 @@ trait UploaderInterface: Send + Sync {
         address: NetworkAddress,
         get_store_cost_strategy: GetStoreCostStrategy,
         max_repayments_for_failed_data: usize,
+        check_existing_chunk: bool,
         task_result_sender: mpsc::Sender<TaskResult>,
     );
 
@@ -305,6 +466,7 @@ trait UploaderInterface: Send + Sync {
         make_payment_sender: mpsc::Sender<Option<(UploadItem, Box<PayeeQuote>)>>,
     );
 
+    #[allow(clippy::too_many_arguments)]
     fn submit_upload_item_task(
         &mut self,
         upload_item: UploadItem,
@@ -312,6 +474,14 @@ trait UploaderInterface: Send + Sync {
         wallet_api: WalletApi,
         verify_store: bool,
         retry_strategy: RetryStrategy,
+        rate_limiter: UploadRateLimiter,
+        task_result_sender: mpsc::Sender<TaskResult>,
+    );
+
+    fn submit_verify_chunk_task(
+        &mut self,
+        upload_item: UploadItem,
+        client: Client,
         task_result_sender: mpsc::Sender<TaskResult>,
     );
 }
@@ -346,6 +516,34 @@ impl InnerUploader {
         self.cfg.collect_registers = collect_registers;
     }
 
+    pub(super) fn set_max_concurrent_uploads(&mut self, max_concurrent_uploads: usize) {
+        self.cfg.max_concurrent_uploads = Some(max_concurrent_uploads);
+    }
+
+    pub(super) fn set_max_uploads_per_sec(&mut self, max_uploads_per_sec: Option<u32>) {
+        self.cfg.max_uploads_per_sec = max_uploads_per_sec;
+    }
+
+    pub(super) fn set_max_spend(&mut self, max_spend: Option<NanoTokens>) {
+        self.cfg.max_spend = max_spend;
+    }
+
+    pub(super) fn set_check_existing_chunks(&mut self, check_existing_chunks: bool) {
+        self.cfg.check_existing_chunks = check_existing_chunks;
+    }
+
+    pub(super) fn set_single_payment(&mut self, single_payment: bool) {
+        self.cfg.single_payment = single_payment;
+    }
+
+    pub(super) fn set_payer(&mut self, payer: Box<dyn Payer>) {
+        self.payer = Some(payer);
+    }
+
+    pub(super) fn set_verification_sample(&mut self, verification_sample: Option<f64>) {
+        self.cfg.verification_sample = verification_sample;
+    }
+
     pub(super) fn get_event_receiver(&mut self) -> mpsc::Receiver<UploadEvent> {
         let (tx, rx) = mpsc::channel(100);
         self.event_sender = Some(tx);
@@ -366,6 +564,11 @@ impl InnerUploader {
             }));
     }
 
+    pub(super) fn insert_manifest(&mut self, manifest: UploadManifest) {
+        self.insert_chunk_paths(manifest.pending_chunks());
+        self.manifests.push(manifest);
+    }
+
     pub(super) fn insert_chunks(&mut self, chunks: impl IntoIterator<Item = Chunk>) {
         self.all_upload_items
             .extend(chunks.into_iter().map(|chunk| {
@@ -436,6 +639,11 @@ enum TaskResult {
         get_store_cost_strategy: GetStoreCostStrategy,
         max_repayments_reached: bool,
     },
+    /// The chunk was quoted, but is already stored by the network.
+    ChunkAlreadyStored {
+        xorname: XorName,
+        saved_cost: NanoTokens,
+    },
     MakePaymentsOk {
         paid_xornames: Vec<XorName>,
         storage_cost: NanoTokens,
@@ -450,6 +658,8 @@ enum TaskResult {
     UploadErr {
         xorname: XorName,
     },
+    VerifyChunkOk(XorName),
+    VerifyChunkErr(XorName),
 }
 
 #[derive(Debug, Clone)]
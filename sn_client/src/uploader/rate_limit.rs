@@ -0,0 +1,94 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_networking::target_arch::{sleep, Instant};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Caps the rate at which the upload tasks start uploading items.
+///
+/// Each task reserves the next free slot before uploading its item, and waits until that slot
+/// is reached. The limit is an average over time, there are no bursts above it.
+///
+/// The bandwidth isn't limited here, but by the client the items are uploaded with, see
+/// `Client::set_bandwidth_limits`.
+#[derive(Debug, Clone)]
+pub(super) struct UploadRateLimiter {
+    // The minimum interval between the start of two uploads.
+    upload_interval: Option<Duration>,
+    // When the next upload is allowed to start.
+    next_slot: Arc<Mutex<Option<Instant>>>,
+}
+
+impl UploadRateLimiter {
+    pub(super) fn new(max_uploads_per_sec: Option<u32>) -> Self {
+        Self {
+            upload_interval: max_uploads_per_sec
+                .filter(|rate| *rate > 0)
+                .map(|rate| Duration::from_secs(1) / rate),
+            next_slot: Default::default(),
+        }
+    }
+
+    /// Waits until uploading another item stays within the limit.
+    pub(super) async fn acquire(&self) {
+        let delay = self.reserve(Instant::now());
+        if !delay.is_zero() {
+            trace!("Delaying an upload by {delay:?} to stay within the rate limit");
+            sleep(delay).await;
+        }
+    }
+
+    /// Reserves the slot for an item, returning how long to wait from `now` for it.
+    fn reserve(&self, now: Instant) -> Duration {
+        let Some(interval) = self.upload_interval else {
+            return Duration::ZERO;
+        };
+
+        let mut next_slot = match self.next_slot.lock() {
+            Ok(next_slot) => next_slot,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let slot = next_slot.map_or(now, |next| next.max(now));
+        *next_slot = Some(slot + interval);
+        slot.saturating_duration_since(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uploads_are_spread_to_stay_within_the_limits() {
+        let now = Instant::now();
+
+        let unlimited = UploadRateLimiter::new(None);
+        for _ in 0..10 {
+            assert_eq!(unlimited.reserve(now), Duration::ZERO);
+        }
+
+        let per_sec = UploadRateLimiter::new(Some(4));
+        let delays: Vec<_> = (0..4).map(|_| per_sec.reserve(now)).collect();
+        assert_eq!(
+            delays,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(250),
+                Duration::from_millis(500),
+                Duration::from_millis(750)
+            ]
+        );
+        assert_eq!(
+            per_sec.reserve(now + Duration::from_secs(10)),
+            Duration::ZERO
+        );
+    }
+}
@@ -6,20 +6,29 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-mod setup;
+pub(crate) mod setup;
 
 use crate::{
-    uploader::tests::setup::{
-        get_dummy_chunk_paths, get_dummy_registers, get_inner_uploader, start_uploading_with_steps,
-        TestSteps,
+    uploader::{
+        tests::setup::{
+            get_dummy_chunk_paths, get_dummy_registers, get_inner_uploader, insert_dummy_payment,
+            start_uploading_with_steps, TestPayer, TestSteps,
+        },
+        TaskResult, UploadItem,
     },
-    Error as ClientError, UploadEvent,
+    Error as ClientError, UploadEvent, UploadManifest,
 };
 use assert_matches::assert_matches;
 use eyre::Result;
+use itertools::Either;
+use libp2p::PeerId;
+use rand::Rng;
 use sn_logging::LogBuilder;
+use sn_protocol::storage::ChunkAddress;
+use sn_transfers::{MainSecretKey, NanoTokens, PaymentQuote};
 use std::collections::VecDeque;
 use tempfile::tempdir;
+use tokio::sync::mpsc;
 
 // ===== HAPPY PATH =======
 
@@ -144,6 +153,109 @@ async fn register_should_be_paid_and_uploaded_if_it_does_not_exists() -> Result<
     Ok(())
 }
 
+/// 5. Chunk: if a payment for the chunk is still valid, then upload it without paying again.
+#[tokio::test]
+async fn chunk_with_an_unexpired_payment_should_be_uploaded_without_paying() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, task_result_rx) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+    let chunk_paths = get_dummy_chunk_paths(1, temp_dir.path().to_path_buf());
+    insert_dummy_payment(&inner_uploader.wallet_api, chunk_paths[0].0, false)?;
+    inner_uploader.insert_chunk_paths(chunk_paths);
+
+    // the path to test
+    let steps = vec![TestSteps::UploadItemOk];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    let _stats = upload_handle.await??;
+    let events = events_handle.await?;
+
+    assert_eq!(events.len(), 1);
+    assert_matches!(events[0], UploadEvent::ChunkUploaded(..));
+    Ok(())
+}
+
+/// 6. Chunk: if the payment for the chunk has expired, then get store cost and pay again.
+#[tokio::test]
+async fn chunk_with_an_expired_payment_should_be_paid_for_again() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, task_result_rx) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+    let chunk_paths = get_dummy_chunk_paths(1, temp_dir.path().to_path_buf());
+    insert_dummy_payment(&inner_uploader.wallet_api, chunk_paths[0].0, true)?;
+    inner_uploader.insert_chunk_paths(chunk_paths);
+
+    // the path to test
+    let steps = vec![
+        TestSteps::GetStoreCostOk {
+            trigger_zero_cost: false,
+            assert_select_different_payee: false,
+        },
+        TestSteps::MakePaymentOk,
+        TestSteps::UploadItemOk,
+    ];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    let _stats = upload_handle.await??;
+    let events = events_handle.await?;
+
+    assert_eq!(events.len(), 2);
+    assert_matches!(events[0], UploadEvent::PaymentMade { .. });
+    assert_matches!(events[1], UploadEvent::ChunkUploaded(..));
+    Ok(())
+}
+
+/// 7. Chunk: only the chunks of a manifest which weren't stored yet should be uploaded, and the manifest should be
+/// removed once they are.
+#[tokio::test]
+async fn manifest_chunks_already_stored_should_not_be_uploaded_again() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, task_result_rx) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+    let file_path = temp_dir.path().join("file");
+    let mut content = vec![0; 5 * 1024 * 1024];
+    rand::thread_rng().fill(&mut content[..]);
+    std::fs::write(&file_path, content)?;
+    let mut manifest = UploadManifest::chunk_or_resume(temp_dir.path(), &file_path)?;
+    let chunks = manifest.chunks().to_vec();
+    let (pending, stored) = chunks.split_last().expect("the file to have chunks");
+    for (xorname, _) in stored {
+        manifest.mark_completed(*xorname)?;
+    }
+    insert_dummy_payment(&inner_uploader.wallet_api, pending.0, false)?;
+    inner_uploader.insert_manifest(manifest);
+
+    // the path to test
+    let steps = vec![TestSteps::UploadItemOk];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    let _stats = upload_handle.await??;
+    let events = events_handle.await?;
+
+    assert_eq!(events.len(), 1);
+    assert_matches!(events[0], UploadEvent::ChunkUploaded(address) if *address.xorname() == pending.0);
+    assert_eq!(
+        std::fs::read_dir(temp_dir.path().join("upload_manifests"))?.count(),
+        0
+    );
+    Ok(())
+}
+
 // ===== REPAYMENTS ======
 
 /// 1. Chunks: if upload task fails > threshold, then get store cost should be triggered with SelectDifferentStrategy
@@ -457,3 +569,251 @@ async fn maximum_repayment_error_should_be_triggered_during_get_store_cost() ->
     assert_matches!(events[1], UploadEvent::PaymentMade { .. });
     Ok(())
 }
+
+/// 7. Chunk: A payment over the budget of the upload should result in Error::UploadBudgetExceeded
+#[tokio::test]
+async fn chunk_should_error_out_if_its_payment_exceeds_the_budget() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, task_result_rx) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+    inner_uploader.set_max_spend(Some(NanoTokens::from(5)));
+    inner_uploader.insert_chunk_paths(get_dummy_chunk_paths(1, temp_dir.path().to_path_buf()));
+
+    // the path to test
+    let steps = vec![TestSteps::GetStoreCostOk {
+        trigger_zero_cost: false,
+        assert_select_different_payee: false,
+    }];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    // a store cost of 10, plus 1 of royalties.
+    assert_matches!(
+        upload_handle.await?,
+        Err(ClientError::UploadBudgetExceeded { budget, required })
+            if budget == NanoTokens::from(5) && required == NanoTokens::from(11)
+    );
+    let events = events_handle.await?;
+    assert_eq!(events.len(), 0);
+    Ok(())
+}
+
+/// 8. Chunk: The verification pass should verify a sample of the uploaded chunks.
+#[tokio::test]
+async fn sampled_chunks_should_be_verified_after_upload() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, task_result_rx) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+    inner_uploader.set_verification_sample(Some(0.5));
+    inner_uploader.insert_chunk_paths(get_dummy_chunk_paths(1, temp_dir.path().to_path_buf()));
+
+    // the path to test. The sample is rounded up, to verify at least one chunk.
+    let steps = vec![
+        TestSteps::GetStoreCostOk {
+            trigger_zero_cost: false,
+            assert_select_different_payee: false,
+        },
+        TestSteps::MakePaymentOk,
+        TestSteps::UploadItemOk,
+        TestSteps::VerifyChunkOk,
+    ];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    let summary = upload_handle.await??;
+    let events = events_handle.await?;
+
+    let report = summary
+        .verification
+        .expect("the verification pass should be made");
+    assert_eq!(report.verified_count, 1);
+    assert!(report.missing_chunks.is_empty());
+
+    assert_eq!(events.len(), 2);
+    Ok(())
+}
+
+/// 9. Chunk: A chunk which fails the verification pass should be paid for and uploaded again.
+#[tokio::test]
+async fn chunk_that_fails_verification_should_be_uploaded_again() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, task_result_rx) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+    inner_uploader.set_verification_sample(Some(1.0));
+    inner_uploader.insert_chunk_paths(get_dummy_chunk_paths(1, temp_dir.path().to_path_buf()));
+
+    // the path to test
+    let steps = vec![
+        TestSteps::GetStoreCostOk {
+            trigger_zero_cost: false,
+            assert_select_different_payee: false,
+        },
+        TestSteps::MakePaymentOk,
+        TestSteps::UploadItemOk,
+        // the chunk is missing, so it is uploaded again, and not verified a second time.
+        TestSteps::VerifyChunkErr,
+        TestSteps::GetStoreCostOk {
+            trigger_zero_cost: false,
+            assert_select_different_payee: false,
+        },
+        TestSteps::MakePaymentOk,
+        TestSteps::UploadItemOk,
+    ];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    let summary = upload_handle.await??;
+    let events = events_handle.await?;
+
+    assert_eq!(summary.uploaded_count, 1);
+    let report = summary
+        .verification
+        .expect("the verification pass should be made");
+    assert_eq!(report.verified_count, 0);
+    assert_eq!(report.missing_chunks.len(), 1);
+
+    assert_eq!(events.len(), 4);
+    assert_matches!(events[0], UploadEvent::PaymentMade { .. });
+    assert_matches!(events[1], UploadEvent::ChunkUploaded(..));
+    assert_matches!(events[2], UploadEvent::PaymentMade { .. });
+    assert_matches!(events[3], UploadEvent::ChunkUploaded(..));
+    Ok(())
+}
+
+/// 10. Chunk: A chunk found to be stored already should not be paid for, and the savings should be reported.
+#[tokio::test]
+async fn chunk_that_is_already_stored_should_not_be_paid_for() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, task_result_rx) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+    inner_uploader.insert_chunk_paths(get_dummy_chunk_paths(1, temp_dir.path().to_path_buf()));
+
+    // the path to test
+    let steps = vec![TestSteps::GetStoreCostChunkAlreadyStored];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    let summary = upload_handle.await??;
+    let events = events_handle.await?;
+
+    assert_eq!(summary.skipped_count, 1);
+    assert_eq!(summary.storage_cost, NanoTokens::zero());
+    assert_eq!(summary.deduplication_savings, NanoTokens::from(11));
+
+    assert_eq!(events.len(), 1);
+    assert_matches!(events[0], UploadEvent::ChunkAlreadyExistsInNetwork(_));
+    Ok(())
+}
+
+/// 11. Chunk: With a single payment, all the chunks should be quoted first and paid for in one transaction.
+#[tokio::test]
+async fn chunks_should_be_paid_for_in_a_single_payment() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, task_result_rx) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+    inner_uploader.set_single_payment(true);
+    inner_uploader.insert_chunk_paths(get_dummy_chunk_paths(3, temp_dir.path().to_path_buf()));
+
+    // the path to test
+    let quote = TestSteps::GetStoreCostOk {
+        trigger_zero_cost: false,
+        assert_select_different_payee: false,
+    };
+    let steps = vec![
+        quote.clone(),
+        quote.clone(),
+        quote,
+        TestSteps::MakePaymentOk,
+        TestSteps::UploadItemOk,
+        TestSteps::UploadItemOk,
+        TestSteps::UploadItemOk,
+    ];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    let summary = upload_handle.await??;
+    let events = events_handle.await?;
+
+    assert_eq!(summary.uploaded_count, 3);
+    assert_eq!(summary.storage_cost, NanoTokens::from(30));
+
+    assert_eq!(events.len(), 4);
+    assert_matches!(events[0], UploadEvent::PaymentMade { .. });
+    assert!(events[1..]
+        .iter()
+        .all(|event| matches!(event, UploadEvent::ChunkUploaded(_))));
+    Ok(())
+}
+
+/// 12. Payment: the payments of a payer other than the local wallet should be cached for the items to be uploaded.
+#[tokio::test]
+async fn payments_made_by_an_external_payer_should_be_cached() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader", true);
+    let temp_dir = tempdir()?;
+    let (mut inner_uploader, _) = get_inner_uploader(temp_dir.path().to_path_buf())?;
+    inner_uploader.set_payer(Box::new(TestPayer {
+        balance: NanoTokens::from(100),
+    }));
+
+    let (make_payment_sender, make_payment_receiver) = mpsc::channel(1);
+    let (task_result_sender, mut task_result_receiver) = mpsc::channel(1);
+    inner_uploader.start_make_payment_processing_loop(
+        make_payment_receiver,
+        task_result_sender,
+        2,
+    )?;
+    assert_eq!(inner_uploader.upload_final_balance, NanoTokens::from(100));
+
+    let chunk_paths = get_dummy_chunk_paths(2, temp_dir.path().to_path_buf());
+    for (xorname, path) in chunk_paths.iter().cloned() {
+        let item = UploadItem::Chunk {
+            address: ChunkAddress::new(xorname),
+            chunk: Either::Right(path),
+        };
+        let quote = Box::new((
+            PeerId::random(),
+            MainSecretKey::random().main_pubkey(),
+            PaymentQuote::test_dummy(xorname, NanoTokens::from(10)),
+        ));
+        make_payment_sender.send(Some((item, quote))).await?;
+    }
+
+    assert_matches!(
+        task_result_receiver.recv().await,
+        Some(TaskResult::MakePaymentsOk {
+            paid_xornames,
+            storage_cost,
+            new_balance,
+            ..
+        }) if paid_xornames.len() == 2
+            && storage_cost == NanoTokens::from(20)
+            && new_balance == NanoTokens::from(80)
+    );
+    for (xorname, _) in chunk_paths {
+        assert_eq!(
+            inner_uploader.wallet_api.get_all_payments(&xorname)?.len(),
+            1
+        );
+    }
+    Ok(())
+}
@@ -9,12 +9,13 @@
 use crate::{
     uploader::{
         upload::{start_upload, InnerUploader},
-        GetStoreCostStrategy, TaskResult, UploadItem, UploaderInterface,
+        GetStoreCostStrategy, TaskResult, UploadItem, UploadRateLimiter, UploaderInterface,
     },
     ClientRegister, UploadEvent,
 };
-use crate::{Client, Result as ClientResult, UploadSummary};
+use crate::{Client, Payer, PaymentCostMap, PaymentReceipt, Result as ClientResult, UploadSummary};
 use assert_matches::assert_matches;
+use async_trait::async_trait;
 use bls::SecretKey;
 use eyre::Result;
 use libp2p::PeerId;
@@ -23,11 +24,15 @@ use rand::thread_rng;
 use sn_networking::{NetworkBuilder, PayeeQuote};
 use sn_protocol::{storage::RetryStrategy, NetworkAddress};
 use sn_registers::{Register, RegisterAddress};
-use sn_transfers::{MainSecretKey, NanoTokens, PaymentQuote, WalletApi};
+use sn_transfers::{
+    MainPubkey, MainSecretKey, NanoTokens, PaymentDetails, PaymentQuote, Transfer, WalletApi,
+    WalletResult, QUOTE_EXPIRATION_SECS,
+};
 use std::{
     collections::{BTreeMap, VecDeque},
     path::PathBuf,
     sync::Arc,
+    time::{Duration, SystemTime},
 };
 use tokio::{runtime::Handle, sync::mpsc, task::JoinHandle};
 use xor_name::XorName;
@@ -141,6 +146,7 @@ impl UploaderInterface for TestUploader {
         _address: NetworkAddress,
         get_store_cost_strategy: GetStoreCostStrategy,
         max_repayments_for_failed_data: usize,
+        check_existing_chunk: bool,
         _task_result_sender: mpsc::Sender<TaskResult>,
     ) {
         let step = self
@@ -207,6 +213,18 @@ impl UploaderInterface for TestUploader {
                         .expect("Failed to send task result");
                 });
             }
+            TestSteps::GetStoreCostChunkAlreadyStored => {
+                assert!(check_existing_chunk);
+                handle.spawn(async move {
+                    task_result_sender
+                        .send(TaskResult::ChunkAlreadyStored {
+                            xorname,
+                            saved_cost: NanoTokens::from(11),
+                        })
+                        .await
+                        .expect("Failed to send task result");
+                });
+            }
             TestSteps::GetStoreCostErr {
                 assert_select_different_payee,
             } => {
@@ -240,6 +258,18 @@ impl UploaderInterface for TestUploader {
         to_send: Option<(UploadItem, Box<PayeeQuote>)>,
         _make_payment_sender: mpsc::Sender<Option<(UploadItem, Box<PayeeQuote>)>>,
     ) {
+        if let Some((upload_item, quote)) = &to_send {
+            self.make_payment_collector
+                .push((upload_item.xorname(), quote.clone()));
+        }
+        // gotta collect batch size before sending task result.
+        let make_payment = self.make_payment_collector.len() >= self.batch_size
+            || (to_send.is_none() && !self.make_payment_collector.is_empty());
+        if !make_payment {
+            info!("TEST: spawn_make_payment called, collecting the item until the batch is full");
+            return;
+        }
+
         let step = self
             .test_steps
             .pop_front()
@@ -247,15 +277,12 @@ impl UploaderInterface for TestUploader {
         let handle = Handle::current();
         let task_result_sender = self.task_result_sender.clone();
         match &to_send {
-            Some((upload_item, quote)) => {
+            Some((upload_item, _)) => {
                 let xorname = upload_item.xorname();
                 println!("spawn_make_payment called for: {xorname:?}. Step to execute: {step:?}");
                 info!(
                     "TEST: spawn_make_payment called for: {xorname:?}. Step to execute: {step:?}"
                 );
-
-                self.make_payment_collector
-                    .push((upload_item.xorname(), quote.clone()));
             }
             None => {
                 println!(
@@ -265,16 +292,7 @@ impl UploaderInterface for TestUploader {
             }
         }
 
-        // gotta collect batch size before sending task result.
-        let _make_payment = self.make_payment_collector.len() >= self.batch_size
-            || (to_send.is_none() && !self.make_payment_collector.is_empty());
-
         match step {
-            // TestSteps::MakePaymentJustCollectItem => {
-            //     // The test expected for us to just collect item, but if the logic wants us to make payment, then it as
-            //     // error
-            //     assert!(!make_payment);
-            // }
             TestSteps::MakePaymentOk => {
                 let paid_xornames = std::mem::take(&mut self.make_payment_collector)
                     .into_iter()
@@ -285,15 +303,15 @@ impl UploaderInterface for TestUploader {
                     let entry = self.payments_made_per_xorname.entry(*xorname).or_insert(0);
                     *entry += 1;
                 }
-                let batch_size = self.batch_size;
+                let paid_count = paid_xornames.len() as u64;
 
                 handle.spawn(async move {
                     task_result_sender
                         .send(TaskResult::MakePaymentsOk {
                             paid_xornames,
-                            storage_cost: NanoTokens::from(batch_size as u64 * 10),
-                            royalty_fees: NanoTokens::from(batch_size as u64 * 3),
-                            new_balance: NanoTokens::from(batch_size as u64 * 1000),
+                            storage_cost: NanoTokens::from(paid_count * 10),
+                            royalty_fees: NanoTokens::from(paid_count * 3),
+                            new_balance: NanoTokens::from(paid_count * 1000),
                         })
                         .await
                         .expect("Failed to send task result");
@@ -323,6 +341,7 @@ impl UploaderInterface for TestUploader {
         _wallet_api: WalletApi,
         _verify_store: bool,
         _retry_strategy: RetryStrategy,
+        _rate_limiter: UploadRateLimiter,
         _task_result_sender: mpsc::Sender<TaskResult>,
     ) {
         let xorname = upload_item.xorname();
@@ -355,6 +374,35 @@ impl UploaderInterface for TestUploader {
             con => panic!("Test failed: Expected UploadItem step. Got: {con:?}"),
         }
     }
+
+    fn submit_verify_chunk_task(
+        &mut self,
+        upload_item: UploadItem,
+        _client: Client,
+        _task_result_sender: mpsc::Sender<TaskResult>,
+    ) {
+        let xorname = upload_item.xorname();
+        let step = self
+            .test_steps
+            .pop_front()
+            .expect("TestSteps are empty. Expected a VerifyChunk step.");
+        let handle = Handle::current();
+        let task_result_sender = self.task_result_sender.clone();
+
+        println!("spawn_verify_chunk called for: {xorname:?}. Step to execute: {step:?}");
+        info!("TEST: spawn_verify_chunk called for: {xorname:?}. Step to execute: {step:?}");
+        let task_result = match step {
+            TestSteps::VerifyChunkOk => TaskResult::VerifyChunkOk(xorname),
+            TestSteps::VerifyChunkErr => TaskResult::VerifyChunkErr(xorname),
+            con => panic!("Test failed: Expected VerifyChunk step. Got: {con:?}"),
+        };
+        handle.spawn(async move {
+            task_result_sender
+                .send(task_result)
+                .await
+                .expect("Failed to send task result");
+        });
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -370,11 +418,14 @@ pub enum TestSteps {
     GetStoreCostErr {
         assert_select_different_payee: bool,
     },
-    // MakePaymentJustCollectItem,
+    // The chunk is quoted, but found to be already stored.
+    GetStoreCostChunkAlreadyStored,
     MakePaymentOk,
     MakePaymentErr,
     UploadItemOk,
     UploadItemErr,
+    VerifyChunkOk,
+    VerifyChunkErr,
 }
 
 pub fn get_inner_uploader(root_dir: PathBuf) -> Result<(InnerUploader, mpsc::Sender<TaskResult>)> {
@@ -397,7 +448,7 @@ pub fn start_uploading_with_steps(
     JoinHandle<ClientResult<UploadSummary>>,
     JoinHandle<Vec<UploadEvent>>,
 ) {
-    let batch_size = inner_uploader.cfg.batch_size;
+    let batch_size = inner_uploader.cfg.payment_batch_size();
     let mut upload_event_rx = inner_uploader.get_event_receiver();
 
     let upload_handle = tokio::spawn(start_upload(Box::new(TestUploader {
@@ -431,6 +482,12 @@ pub fn build_unconnected_client(root_dir: PathBuf) -> Result<Client> {
         network,
         events_broadcaster: Default::default(),
         signer: Arc::new(SecretKey::random()),
+        chunk_cache: None,
+        retry_policies: Default::default(),
+        timeouts: Default::default(),
+        bandwidth: Default::default(),
+        #[cfg(feature = "open-metrics")]
+        metrics: Default::default(),
     };
     Ok(client)
 }
@@ -445,6 +502,71 @@ pub fn get_dummy_chunk_paths(num: usize, temp_dir: PathBuf) -> Vec<(XorName, Pat
     chunks
 }
 
+// Stores a payment for the xorname in the wallet dir, as an interrupted upload would have left it.
+pub fn insert_dummy_payment(wallet_api: &WalletApi, xorname: XorName, expired: bool) -> Result<()> {
+    let mut quote = PaymentQuote::test_dummy(xorname, NanoTokens::from(10));
+    if expired {
+        quote.timestamp = SystemTime::now() - Duration::from_secs(QUOTE_EXPIRATION_SECS + 1);
+    }
+    let payment = dummy_payment(
+        MainSecretKey::random().main_pubkey(),
+        PeerId::random().to_bytes(),
+        quote,
+    );
+    wallet_api.insert_payment_transaction(xorname, payment)?;
+    Ok(())
+}
+
+fn dummy_payment(
+    recipient: MainPubkey,
+    peer_id_bytes: Vec<u8>,
+    quote: PaymentQuote,
+) -> PaymentDetails {
+    PaymentDetails {
+        recipient,
+        peer_id_bytes,
+        transfer: (Transfer::Encrypted(vec![]), quote.cost),
+        royalties: (Transfer::NetworkRoyalties(vec![]), NanoTokens::zero()),
+        quote,
+    }
+}
+
+// A payer funding the uploads from elsewhere than the local wallet, without touching the network.
+pub struct TestPayer {
+    pub balance: NanoTokens,
+}
+
+#[async_trait]
+impl Payer for TestPayer {
+    async fn pay_for_records(
+        &mut self,
+        cost_map: &PaymentCostMap,
+        _verify_store: bool,
+    ) -> WalletResult<PaymentReceipt> {
+        let storage_cost = cost_map
+            .values()
+            .map(|(_, quote, _)| quote.cost.as_nano())
+            .sum();
+        self.balance = NanoTokens::from(self.balance.as_nano() - storage_cost);
+        let payments = cost_map
+            .iter()
+            .map(|(xorname, (recipient, quote, peer_id_bytes))| {
+                let payment = dummy_payment(*recipient, peer_id_bytes.clone(), quote.clone());
+                (*xorname, payment)
+            })
+            .collect();
+        Ok(PaymentReceipt {
+            storage_cost: NanoTokens::from(storage_cost),
+            royalty_fees: NanoTokens::zero(),
+            payments,
+        })
+    }
+
+    fn balance(&self) -> NanoTokens {
+        self.balance
+    }
+}
+
 pub fn get_dummy_registers(num: usize, client: Client) -> Vec<ClientRegister> {
     let mut rng = thread_rng();
     let mut registers = Vec::with_capacity(num);
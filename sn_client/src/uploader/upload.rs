@@ -7,25 +7,27 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    GetStoreCostStrategy, TaskResult, UploadCfg, UploadEvent, UploadItem, UploadSummary,
-    UploaderInterface,
+    GetStoreCostStrategy, TaskResult, UploadCfg, UploadEvent, UploadItem, UploadManifest,
+    UploadRateLimiter, UploadSummary, UploaderInterface, VerificationReport,
 };
 use crate::{
     acc_packet::load_account_wallet_or_create_with_mnemonic,
     transfers::{TransferError, WalletError},
-    Client, ClientRegister, Error as ClientError, Result, Uploader, WalletClient,
+    Client, ClientRegister, Error as ClientError, Payer, PaymentReceipt, ProgressEvent, Result,
+    Uploader, WalletClient,
 };
 use bytes::Bytes;
 use itertools::Either;
-use libp2p::PeerId;
-use sn_networking::PayeeQuote;
+use libp2p::{kad::Quorum, PeerId};
+use rand::{seq::SliceRandom, thread_rng};
+use sn_networking::{GetRecordCfg, PayeeQuote};
 use sn_protocol::{
     messages::RegisterCmd,
-    storage::{Chunk, RetryStrategy},
+    storage::{try_deserialize_record, Chunk, ChunkAddress, RetryStrategy},
     NetworkAddress,
 };
 use sn_registers::{Register, RegisterAddress};
-use sn_transfers::{NanoTokens, WalletApi};
+use sn_transfers::{calculate_royalties_fee, NanoTokens, PaymentDetails, WalletApi};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     path::{Path, PathBuf},
@@ -78,25 +80,28 @@ pub(super) async fn start_upload(
             mpsc::channel(uploader.cfg.batch_size * 6 + 1)
         };
     let (make_payment_sender, make_payment_receiver) = mpsc::channel(uploader.cfg.batch_size);
+    let rate_limiter = UploadRateLimiter::new(uploader.cfg.max_uploads_per_sec);
 
     uploader.start_make_payment_processing_loop(
         make_payment_receiver,
         task_result_sender.clone(),
-        uploader.cfg.batch_size,
+        uploader.cfg.payment_batch_size(),
     )?;
 
-    // chunks can be pushed to pending_get_store_cost directly
-    uploader.pending_to_get_store_cost = uploader
-        .all_upload_items
-        .iter()
-        .filter_map(|(xorname, item)| {
-            if let UploadItem::Chunk { .. } = item {
-                Some((*xorname, GetStoreCostStrategy::Cheapest))
+    // chunks can be pushed to pending_get_store_cost directly, unless an interrupted upload already paid for them.
+    // The payments are persisted in the wallet dir, so an unexpired one can be used to resume the upload right away.
+    for (xorname, item) in uploader.all_upload_items.iter() {
+        if let UploadItem::Chunk { .. } = item {
+            if uploader.has_unexpired_payment(xorname) {
+                debug!("Resuming the upload of {xorname:?} with its existing payment");
+                uploader.pending_to_upload.push(*xorname);
             } else {
-                None
+                uploader
+                    .pending_to_get_store_cost
+                    .push((*xorname, GetStoreCostStrategy::Cheapest));
             }
-        })
-        .collect();
+        }
+    }
 
     // registers have to be verified + merged with remote replica, so we have to fetch it first.
     uploader.pending_to_get_register = uploader
@@ -112,14 +117,21 @@ pub(super) async fn start_upload(
         .collect();
 
     loop {
-        // Break if we have uploaded all the items.
+        // Break if we have uploaded and verified all the items.
         // The loop also breaks if we fail to get_store_cost / make payment / upload for n consecutive times.
-        if uploader.all_upload_items.is_empty() {
+        if uploader.all_upload_items.is_empty()
+            && uploader.pending_to_verify.is_empty()
+            && uploader.on_going_verifications.is_empty()
+            && !uploader.start_verification()
+        {
             debug!("Upload items are empty, exiting main upload loop.");
-            // To avoid empty final_balance when all items are skipped.
-            uploader.upload_final_balance =
-                InnerUploader::load_wallet_client(uploader.client.clone(), &uploader.root_dir)?
-                    .balance();
+            for manifest in std::mem::take(&mut uploader.manifests) {
+                if manifest.is_complete() {
+                    if let Err(err) = manifest.remove() {
+                        warn!("Failed to remove the manifest of a completed upload: {err:?}");
+                    }
+                }
+            }
             #[cfg(test)]
             trace!("UPLOADER STATE: finished uploading all items {uploader:?}");
             let summary = UploadSummary {
@@ -130,6 +142,8 @@ pub(super) async fn start_upload(
                 uploaded_count: uploader.uploaded_count,
                 skipped_count: uploader.skipped_count,
                 uploaded_registers: uploader.uploaded_registers,
+                verification: uploader.verification_report,
+                deduplication_savings: uploader.deduplication_savings,
             };
 
             if !uploader.max_repayments_reached.is_empty() {
@@ -186,7 +200,7 @@ pub(super) async fn start_upload(
         // try to get store cost for an item if pending_to_pay needs items & if we have enough buffer.
         while !uploader.pending_to_get_store_cost.is_empty()
             && uploader.on_going_get_cost.len() < uploader.cfg.batch_size
-            && uploader.pending_to_pay.len() < uploader.cfg.batch_size
+            && uploader.pending_to_pay.len() < uploader.cfg.payment_batch_size()
         {
             let (xorname, address, get_store_cost_strategy) =
                 uploader.pop_item_for_get_store_cost()?;
@@ -200,13 +214,15 @@ pub(super) async fn start_upload(
                 address,
                 get_store_cost_strategy,
                 uploader.cfg.max_repayments_for_failed_data,
+                uploader.cfg.check_existing_chunks,
                 task_result_sender.clone(),
             );
         }
 
         // try to make payment for an item if pending_to_upload needs items & if we have enough buffer.
+        // With a single payment, every quoted item is held by the payment loop until all of them are quoted.
         while !uploader.pending_to_pay.is_empty()
-            && uploader.on_going_payments.len() < uploader.cfg.batch_size
+            && uploader.on_going_payments.len() < uploader.cfg.payment_batch_size()
             && uploader.pending_to_upload.len() < uploader.cfg.batch_size
         {
             let (upload_item, quote) = uploader.pop_item_for_make_payment()?;
@@ -214,7 +230,16 @@ pub(super) async fn start_upload(
                 "Conditions met for making payments. {:?} {quote:?}",
                 upload_item.xorname()
             );
+            let payment = quote
+                .2
+                .cost
+                .checked_add(calculate_royalties_fee(quote.2.cost))
+                .ok_or(ClientError::TotalPriceTooHigh)?;
+            uploader.check_budget(payment)?;
             let _ = uploader.on_going_payments.insert(upload_item.xorname());
+            let _ = uploader
+                .on_going_payments_cost
+                .insert(upload_item.xorname(), payment);
 
             interface
                 .submit_make_payment_task(Some((upload_item, quote)), make_payment_sender.clone());
@@ -222,7 +247,7 @@ pub(super) async fn start_upload(
 
         // try to upload if we have enough buffer to upload.
         while !uploader.pending_to_upload.is_empty()
-            && uploader.on_going_uploads.len() < uploader.cfg.batch_size
+            && uploader.on_going_uploads.len() < uploader.cfg.max_concurrent_uploads()
         {
             #[cfg(test)]
             trace!("UPLOADER STATE: upload_item : {uploader:?}");
@@ -236,6 +261,23 @@ pub(super) async fn start_upload(
                 uploader.wallet_api.clone(),
                 uploader.cfg.verify_store,
                 uploader.cfg.retry_strategy,
+                rate_limiter.clone(),
+                task_result_sender.clone(),
+            );
+        }
+
+        // try to verify the uploaded chunks, once all the items are uploaded.
+        while !uploader.pending_to_verify.is_empty()
+            && uploader.on_going_verifications.len() < uploader.cfg.batch_size
+        {
+            let upload_item = uploader.pop_item_for_verification()?;
+            trace!("Conditions met for verifying {:?}", upload_item.xorname());
+            let _ = uploader
+                .on_going_verifications
+                .insert(upload_item.xorname());
+            interface.submit_verify_chunk_task(
+                upload_item,
+                uploader.client.clone(),
                 task_result_sender.clone(),
             );
         }
@@ -245,7 +287,7 @@ pub(super) async fn start_upload(
         if uploader.pending_to_get_store_cost.is_empty()
             && uploader.on_going_get_cost.is_empty()
             && !uploader.on_going_payments.is_empty()
-            && uploader.on_going_payments.len() < uploader.cfg.batch_size
+            && uploader.on_going_payments.len() < uploader.cfg.payment_batch_size()
         {
             #[cfg(test)]
             trace!("UPLOADER STATE: make_payment (forced): {uploader:?}");
@@ -361,6 +403,28 @@ pub(super) async fn start_upload(
                     }
                 }
             }
+            TaskResult::ChunkAlreadyStored {
+                xorname,
+                saved_cost,
+            } => {
+                let _ = uploader.on_going_get_cost.remove(&xorname);
+                uploader.get_store_cost_errors = 0;
+                trace!("{xorname:?} is already stored by the network, saving {saved_cost:?}");
+
+                let removed_item = uploader
+                    .all_upload_items
+                    .remove(&xorname)
+                    .ok_or(ClientError::UploadableItemNotFound(xorname))?;
+                let _ = uploader.uploaded_addresses.insert(removed_item.address());
+                uploader.skipped_count += 1;
+                uploader.deduplication_savings = uploader
+                    .deduplication_savings
+                    .checked_add(saved_cost)
+                    .ok_or(ClientError::TotalPriceTooHigh)?;
+                if let UploadItem::Chunk { address, .. } = removed_item {
+                    uploader.emit_upload_event(UploadEvent::ChunkAlreadyExistsInNetwork(address));
+                }
+            }
             TaskResult::GetStoreCostErr {
                 xorname,
                 get_store_cost_strategy,
@@ -396,6 +460,7 @@ pub(super) async fn start_upload(
                 paid_xornames.len(), InnerUploader::hash_of_xornames(paid_xornames.iter()));
                 for xorname in paid_xornames.iter() {
                     let _ = uploader.on_going_payments.remove(xorname);
+                    let _ = uploader.on_going_payments_cost.remove(xorname);
                 }
                 uploader.pending_to_upload.extend(paid_xornames);
                 uploader.make_payments_errors = 0;
@@ -435,6 +500,7 @@ pub(super) async fn start_upload(
 
                 for (xorname, quote) in failed_xornames {
                     let _ = uploader.on_going_payments.remove(&xorname);
+                    let _ = uploader.on_going_payments_cost.remove(&xorname);
                     uploader.pending_to_pay.push((xorname, quote));
                 }
                 uploader.make_payments_errors += 1;
@@ -458,6 +524,16 @@ pub(super) async fn start_upload(
                     .ok_or(ClientError::UploadableItemNotFound(xorname))?;
                 let _ = uploader.uploaded_addresses.insert(removed_item.address());
 
+                // keep the chunks around to be verified, unless they are re-uploaded by the verification pass.
+                if uploader.cfg.verification_sample.is_some()
+                    && uploader.verification_report.is_none()
+                    && matches!(removed_item, UploadItem::Chunk { .. })
+                {
+                    let _ = uploader
+                        .uploaded_chunks
+                        .insert(xorname, removed_item.clone());
+                }
+
                 match removed_item {
                     UploadItem::Chunk { address, .. } => {
                         uploader.emit_upload_event(UploadEvent::ChunkUploaded(address));
@@ -494,6 +570,37 @@ pub(super) async fn start_upload(
                     uploader.pending_to_upload.push(xorname);
                 }
             }
+            TaskResult::VerifyChunkOk(xorname) => {
+                let _ = uploader.on_going_verifications.remove(&xorname);
+                let _ = uploader.uploaded_chunks.remove(&xorname);
+                trace!("VerifyChunkOk for {xorname:?}");
+                if let Some(report) = uploader.verification_report.as_mut() {
+                    report.verified_count += 1;
+                }
+            }
+            TaskResult::VerifyChunkErr(xorname) => {
+                let _ = uploader.on_going_verifications.remove(&xorname);
+                warn!("VerifyChunkErr for {xorname:?}, uploading it again");
+                let item = uploader
+                    .uploaded_chunks
+                    .remove(&xorname)
+                    .ok_or(ClientError::UploadableItemNotFound(xorname))?;
+                if let Some(report) = uploader.verification_report.as_mut() {
+                    let _ = report.missing_chunks.insert(ChunkAddress::new(xorname));
+                }
+                uploader
+                    .client
+                    .report_progress(ProgressEvent::VerificationFailed {
+                        address: ChunkAddress::new(xorname),
+                    });
+
+                // it is counted again once it is re-uploaded.
+                uploader.uploaded_count = uploader.uploaded_count.saturating_sub(1);
+                let _ = uploader.all_upload_items.insert(xorname, item);
+                uploader
+                    .pending_to_get_store_cost
+                    .push((xorname, GetStoreCostStrategy::Cheapest));
+            }
         }
     }
 }
@@ -513,12 +620,19 @@ impl UploaderInterface for Uploader {
         address: NetworkAddress,
         get_store_cost_strategy: GetStoreCostStrategy,
         max_repayments_for_failed_data: usize,
+        check_existing_chunk: bool,
         task_result_sender: mpsc::Sender<TaskResult>,
     ) {
         trace!("Spawning get_store_cost for {xorname:?}");
         let _handle = tokio::spawn(async move {
+            let chunk_address = match &address {
+                NetworkAddress::ChunkAddress(chunk_address) if check_existing_chunk => {
+                    Some(*chunk_address)
+                }
+                _ => None,
+            };
             let task_result = match InnerUploader::get_store_cost(
-                client,
+                client.clone(),
                 wallet_api,
                 xorname,
                 address,
@@ -529,9 +643,27 @@ impl UploaderInterface for Uploader {
             {
                 Ok(quote) => {
                     debug!("StoreCosts retrieved for {xorname:?} quote: {quote:?}");
-                    TaskResult::GetStoreCostOk {
-                        xorname,
-                        quote: Box::new(quote),
+                    let already_stored = match chunk_address {
+                        Some(chunk_address) if !quote.2.cost.is_zero() => {
+                            InnerUploader::is_chunk_stored(&client, chunk_address).await
+                        }
+                        _ => false,
+                    };
+                    if already_stored {
+                        let saved_cost = quote
+                            .2
+                            .cost
+                            .checked_add(calculate_royalties_fee(quote.2.cost))
+                            .unwrap_or(quote.2.cost);
+                        TaskResult::ChunkAlreadyStored {
+                            xorname,
+                            saved_cost,
+                        }
+                    } else {
+                        TaskResult::GetStoreCostOk {
+                            xorname,
+                            quote: Box::new(quote),
+                        }
                     }
                 }
                 Err(err) => {
@@ -621,6 +753,7 @@ impl UploaderInterface for Uploader {
         wallet_api: WalletApi,
         verify_store: bool,
         retry_strategy: RetryStrategy,
+        rate_limiter: UploadRateLimiter,
         task_result_sender: mpsc::Sender<TaskResult>,
     ) {
         trace!("Spawning upload item task for {:?}", upload_item.xorname());
@@ -633,6 +766,7 @@ impl UploaderInterface for Uploader {
                 upload_item,
                 verify_store,
                 retry_strategy,
+                rate_limiter,
             )
             .await;
 
@@ -649,6 +783,27 @@ impl UploaderInterface for Uploader {
             };
         });
     }
+
+    fn submit_verify_chunk_task(
+        &mut self,
+        upload_item: UploadItem,
+        client: Client,
+        task_result_sender: mpsc::Sender<TaskResult>,
+    ) {
+        trace!("Spawning verify chunk task for {:?}", upload_item.xorname());
+
+        let _handle = tokio::spawn(async move {
+            let xorname = upload_item.xorname();
+            let task_result = match InnerUploader::verify_chunk(client, upload_item).await {
+                Ok(()) => TaskResult::VerifyChunkOk(xorname),
+                Err(err) => {
+                    warn!("Encountered error {err:?} while verifying chunk {xorname:?}");
+                    TaskResult::VerifyChunkErr(xorname)
+                }
+            };
+            let _ = task_result_sender.send(task_result).await;
+        });
+    }
 }
 
 /// `Uploader` provides functionality for uploading both Chunks and Registers with support for retries and queuing.
@@ -662,6 +817,11 @@ pub(super) struct InnerUploader {
     #[debug(skip)]
     pub(super) wallet_api: WalletApi,
     pub(super) root_dir: PathBuf,
+    // pays for the items, the wallet in the root dir being loaded if not set.
+    #[debug(skip)]
+    pub(super) payer: Option<Box<dyn Payer>>,
+    // the manifests of the files being uploaded, recording their chunks as they are stored.
+    pub(super) manifests: Vec<UploadManifest>,
 
     // states
     pub(super) all_upload_items: HashMap<XorName, UploadItem>,
@@ -676,7 +836,17 @@ pub(super) struct InnerUploader {
     pub(super) on_going_push_register: BTreeSet<XorName>,
     pub(super) on_going_get_cost: BTreeSet<XorName>,
     pub(super) on_going_payments: BTreeSet<XorName>,
+    // what the on going payments will spend, storage cost and royalties included.
+    pub(super) on_going_payments_cost: BTreeMap<XorName, NanoTokens>,
     pub(super) on_going_uploads: BTreeSet<XorName>,
+    pub(super) on_going_verifications: BTreeSet<XorName>,
+
+    // verification pass
+    // the uploaded chunks which may be verified once all the items are uploaded.
+    pub(super) uploaded_chunks: HashMap<XorName, UploadItem>,
+    pub(super) pending_to_verify: Vec<XorName>,
+    // set once the verification pass has started.
+    pub(super) verification_report: Option<VerificationReport>,
 
     // error trackers
     pub(super) n_errors_during_uploads: BTreeMap<XorName, usize>,
@@ -688,6 +858,7 @@ pub(super) struct InnerUploader {
     pub(super) upload_storage_cost: NanoTokens,
     pub(super) upload_royalty_fees: NanoTokens,
     pub(super) upload_final_balance: NanoTokens,
+    pub(super) deduplication_savings: NanoTokens,
     pub(super) max_repayments_reached: BTreeSet<XorName>,
     pub(super) uploaded_addresses: BTreeSet<NetworkAddress>,
     pub(super) uploaded_registers: BTreeMap<RegisterAddress, ClientRegister>,
@@ -712,6 +883,8 @@ impl InnerUploader {
             client,
             wallet_api: WalletApi::new_from_root_dir(&root_dir),
             root_dir,
+            payer: None,
+            manifests: Default::default(),
 
             all_upload_items: Default::default(),
             pending_to_get_register: Default::default(),
@@ -724,7 +897,13 @@ impl InnerUploader {
             on_going_push_register: Default::default(),
             on_going_get_cost: Default::default(),
             on_going_payments: Default::default(),
+            on_going_payments_cost: Default::default(),
             on_going_uploads: Default::default(),
+            on_going_verifications: Default::default(),
+
+            uploaded_chunks: Default::default(),
+            pending_to_verify: Default::default(),
+            verification_report: None,
 
             n_errors_during_uploads: Default::default(),
             push_register_errors: Default::default(),
@@ -735,6 +914,7 @@ impl InnerUploader {
             upload_storage_cost: NanoTokens::zero(),
             upload_royalty_fees: NanoTokens::zero(),
             upload_final_balance: NanoTokens::zero(),
+            deduplication_savings: NanoTokens::zero(),
             uploaded_addresses: Default::default(),
             uploaded_registers: Default::default(),
             uploaded_count: Default::default(),
@@ -805,18 +985,41 @@ impl InnerUploader {
         }
     }
 
+    fn pop_item_for_verification(&mut self) -> Result<UploadItem> {
+        if let Some(name) = self.pending_to_verify.pop() {
+            let upload_item = self
+                .uploaded_chunks
+                .get(&name)
+                .cloned()
+                .ok_or(ClientError::UploadableItemNotFound(name))?;
+            Ok(upload_item)
+        } else {
+            // the caller will be making sure this does not happen.
+            Err(ClientError::UploadStateTrackerIsEmpty)
+        }
+    }
+
     // ====== Processing Loop ======
 
     // This is spawned as a long running task to prevent us from reading the wallet files
     // each time we have to make a payment.
-    fn start_make_payment_processing_loop(
-        &self,
+    pub(super) fn start_make_payment_processing_loop(
+        &mut self,
         mut make_payment_receiver: mpsc::Receiver<Option<(UploadItem, Box<PayeeQuote>)>>,
         task_result_sender: mpsc::Sender<TaskResult>,
         batch_size: usize,
     ) -> Result<()> {
-        let mut wallet_client = Self::load_wallet_client(self.client.clone(), &self.root_dir)?;
+        let mut payer = match self.payer.take() {
+            Some(payer) => payer,
+            None => Box::new(Self::load_wallet_client(
+                self.client.clone(),
+                &self.root_dir,
+            )?),
+        };
+        // To avoid empty final_balance when all items are skipped.
+        self.upload_final_balance = payer.balance();
 
+        let wallet_api = self.wallet_api.clone();
         let verify_store = self.cfg.verify_store;
         let _handle = tokio::spawn(async move {
             debug!("Spawning the long running make payment processing loop.");
@@ -860,15 +1063,15 @@ impl InnerUploader {
                         got_a_previous_force_payment = false;
                     }
 
-                    let _ = wallet_client
-                        .resend_pending_transaction_blocking_loop()
-                        .await;
-
                     let mut terminate_process = false;
 
-                    let result = match wallet_client.pay_for_records(&cost_map, verify_store).await
-                    {
-                        Ok((storage_cost, royalty_fees)) => {
+                    let result = match payer.pay_for_records(&cost_map, verify_store).await {
+                        Ok(PaymentReceipt {
+                            storage_cost,
+                            royalty_fees,
+                            payments,
+                        }) => {
+                            Self::cache_payments(&wallet_api, payments);
                             let paid_xornames = std::mem::take(&mut current_batch);
                             let paid_xornames = paid_xornames
                                 .into_iter()
@@ -883,7 +1086,7 @@ impl InnerUploader {
                                 paid_xornames,
                                 storage_cost,
                                 royalty_fees,
-                                new_balance: wallet_client.balance(),
+                                new_balance: payer.balance(),
                             }
                         }
                         Err(err) => {
@@ -996,12 +1199,40 @@ impl InnerUploader {
         Ok(quote)
     }
 
+    /// Returns true if a majority of the holders of the chunk return it, in which case it does not have to be paid for.
+    async fn is_chunk_stored(client: &Client, address: ChunkAddress) -> bool {
+        let key = NetworkAddress::from_chunk_address(address).to_record_key();
+        // a majority makes sure we don't skip a chunk held by a single node, which could soon be lost.
+        let get_cfg = GetRecordCfg {
+            get_quorum: Quorum::Majority,
+            retry_strategy: None,
+            target_record: None,
+            expected_holders: Default::default(),
+        };
+        let record = match client.network.get_record_from_network(key, &get_cfg).await {
+            Ok(record) => record,
+            Err(err) => {
+                trace!("Chunk {address:?} is not stored by the network: {err:?}");
+                return false;
+            }
+        };
+        // chunks are content addressed, so the record is the chunk if its content hashes to the address.
+        match try_deserialize_record::<Chunk>(&record) {
+            Ok(chunk) => chunk.name() == address.xorname(),
+            Err(err) => {
+                warn!("The record at {address:?} is not a valid chunk: {err:?}");
+                false
+            }
+        }
+    }
+
     async fn upload_item(
         client: Client,
         wallet_api: WalletApi,
         upload_item: UploadItem,
         verify_store: bool,
         retry_strategy: RetryStrategy,
+        rate_limiter: UploadRateLimiter,
     ) -> Result<()> {
         let xorname = upload_item.xorname();
 
@@ -1014,13 +1245,8 @@ impl InnerUploader {
 
         match upload_item {
             UploadItem::Chunk { address: _, chunk } => {
-                let chunk = match chunk {
-                    Either::Left(chunk) => chunk,
-                    Either::Right(path) => {
-                        let bytes = std::fs::read(path)?;
-                        Chunk::new(Bytes::from(bytes))
-                    }
-                };
+                let chunk = Self::load_chunk(chunk)?;
+                rate_limiter.acquire().await;
 
                 trace!("Client upload started for chunk: {xorname:?}");
                 client
@@ -1029,7 +1255,9 @@ impl InnerUploader {
                 trace!("Client upload completed for chunk: {xorname:?}");
             }
             UploadItem::Register { address: _, reg } => {
-                let signature = client.sign(reg.register.bytes()?);
+                let bytes = reg.register.bytes()?;
+                rate_limiter.acquire().await;
+                let signature = client.sign(bytes);
                 trace!("Client upload started for register: {xorname:?}");
 
                 ClientRegister::publish_register(
@@ -1051,9 +1279,127 @@ impl InnerUploader {
         Ok(())
     }
 
+    async fn verify_chunk(client: Client, upload_item: UploadItem) -> Result<()> {
+        let chunk = if let UploadItem::Chunk { chunk, .. } = upload_item {
+            Self::load_chunk(chunk)?
+        } else {
+            return Err(ClientError::InvalidUploadItemFound);
+        };
+        // the holders have to prove they hold the exact content of the chunk.
+        client.verify_chunk_stored(&chunk).await
+    }
+
     // ====== Misc ======
 
+    fn load_chunk(chunk: Either<Chunk, PathBuf>) -> Result<Chunk> {
+        match chunk {
+            Either::Left(chunk) => Ok(chunk),
+            Either::Right(path) => {
+                let bytes = std::fs::read(path)?;
+                Ok(Chunk::new(Bytes::from(bytes)))
+            }
+        }
+    }
+
+    /// Starts the verification pass if it is enabled and was not made yet, sampling the uploaded chunks to verify.
+    /// Returns true if there are chunks to verify.
+    fn start_verification(&mut self) -> bool {
+        let Some(sample) = self.cfg.verification_sample else {
+            return false;
+        };
+        if self.verification_report.is_some() {
+            return false;
+        }
+        self.verification_report = Some(VerificationReport::default());
+
+        let names = self.uploaded_chunks.keys().copied().collect::<Vec<_>>();
+        let amount = (names.len() as f64 * sample.clamp(0.0, 1.0)).ceil() as usize;
+        self.pending_to_verify = names
+            .choose_multiple(&mut thread_rng(), amount)
+            .copied()
+            .collect();
+        // the chunks which were not sampled are not needed any more.
+        let sampled = self.pending_to_verify.iter().collect::<BTreeSet<_>>();
+        self.uploaded_chunks
+            .retain(|name, _| sampled.contains(name));
+        info!(
+            "Verifying {} of the {} uploaded chunks",
+            self.pending_to_verify.len(),
+            names.len()
+        );
+        !self.pending_to_verify.is_empty()
+    }
+
+    /// Returns true if a payment for the item was made earlier, and its quote can still be used to upload it.
+    fn has_unexpired_payment(&self, xorname: &XorName) -> bool {
+        match self.wallet_api.get_recent_payment(xorname) {
+            Ok(payment) => !payment.quote.has_expired(),
+            Err(_) => false,
+        }
+    }
+
+    // Errors out if making a payment of `next_payment` would exceed the budget of the upload.
+    fn check_budget(&self, next_payment: NanoTokens) -> Result<()> {
+        let Some(budget) = self.cfg.max_spend else {
+            return Ok(());
+        };
+        let required = [
+            self.upload_storage_cost,
+            self.upload_royalty_fees,
+            next_payment,
+        ]
+        .into_iter()
+        .chain(self.on_going_payments_cost.values().copied())
+        .fold(0u64, |sum, amount| sum.saturating_add(amount.as_nano()));
+
+        if required > budget.as_nano() {
+            error!("The upload would spend {required} nanos, over its budget of {budget}");
+            return Err(ClientError::UploadBudgetExceeded {
+                budget,
+                required: NanoTokens::from(required),
+            });
+        }
+        Ok(())
+    }
+
     fn emit_upload_event(&mut self, event: UploadEvent) {
+        if let UploadEvent::ChunkUploaded(address)
+        | UploadEvent::ChunkAlreadyExistsInNetwork(address) = &event
+        {
+            for manifest in self.manifests.iter_mut() {
+                if let Err(err) = manifest.mark_completed(*address.xorname()) {
+                    warn!("Failed to record {address:?} as stored in its upload manifest: {err:?}");
+                }
+            }
+        }
+
+        let progress = match &event {
+            UploadEvent::ChunkUploaded(address) => {
+                Some(ProgressEvent::ChunkUploaded { address: *address })
+            }
+            UploadEvent::ChunkAlreadyExistsInNetwork(address) => {
+                Some(ProgressEvent::ChunkAlreadyStored { address: *address })
+            }
+            UploadEvent::RegisterUploaded(reg) | UploadEvent::RegisterUpdated(reg) => {
+                Some(ProgressEvent::RegisterUploaded {
+                    address: *reg.address(),
+                })
+            }
+            UploadEvent::PaymentMade {
+                storage_cost,
+                royalty_fees,
+                new_balance,
+            } => Some(ProgressEvent::PaymentMade {
+                storage_cost: *storage_cost,
+                royalty_fees: *royalty_fees,
+                new_balance: *new_balance,
+            }),
+            UploadEvent::Error => None,
+        };
+        if let Some(progress) = progress {
+            self.client.report_progress(progress);
+        }
+
         if let Some(sender) = self.event_sender.as_ref() {
             let sender_clone = sender.clone();
             let _handle = tokio::spawn(async move {
@@ -1078,6 +1424,22 @@ impl InnerUploader {
         payments_made > max_repayments_allowed
     }
 
+    // Cache the payments made by the payer in the wallet dir, for the items to be uploaded along with them.
+    fn cache_payments(wallet_api: &WalletApi, payments: BTreeMap<XorName, PaymentDetails>) {
+        for (xorname, payment) in payments {
+            // a wallet in the same wallet dir has cached it already
+            if wallet_api
+                .get_recent_payment(&xorname)
+                .is_ok_and(|cached| cached.quote == payment.quote)
+            {
+                continue;
+            }
+            if let Err(err) = wallet_api.insert_payment_transaction(xorname, payment) {
+                error!("Failed to cache the payment for {xorname:?}: {err:?}");
+            }
+        }
+    }
+
     /// Create a new WalletClient for a given root directory.
     fn load_wallet_client(client: Client, root_dir: &Path) -> Result<WalletClient> {
         let wallet = load_account_wallet_or_create_with_mnemonic(root_dir, None)?;
@@ -8,8 +8,7 @@
 
 use crate::Error;
 
-use super::{error::Result, Client};
-use backoff::{backoff::Backoff, ExponentialBackoff};
+use super::{error::Result, Client, RetryOperation};
 use futures::{future::join_all, TryFutureExt};
 use libp2p::PeerId;
 use sn_networking::target_arch::Instant;
@@ -17,7 +16,8 @@ use sn_networking::{GetRecordError, PayeeQuote};
 use sn_protocol::NetworkAddress;
 use sn_transfers::{
     CashNote, DerivationIndex, HotWallet, MainPubkey, NanoTokens, Payment, PaymentQuote,
-    SignedSpend, SpendAddress, Transaction, Transfer, UniquePubkey, WalletError, WalletResult,
+    SignedSpend, SpendAddress, SweepReport, Transaction, Transfer, UniquePubkey, WalletError,
+    WalletResult,
 };
 use std::{
     collections::{BTreeMap, BTreeSet},
@@ -29,8 +29,6 @@ use tokio::{
 };
 use xor_name::XorName;
 
-const MAX_RESEND_PENDING_TX_ATTEMPTS: usize = 10;
-
 /// A wallet client can be used to send and receive tokens to and from other wallets.
 pub struct WalletClient {
     client: Client,
@@ -338,6 +336,48 @@ impl WalletClient {
         }
     }
 
+    /// Spends all the available cash_notes of the wallet to the given key, e.g. a cold wallet,
+    /// and sends the spends to the network. See `HotWallet::local_sweep_to` for how the
+    /// transactions are created.
+    ///
+    /// Pending transactions are resent first. The returned report holds the cash_notes created
+    /// for the cold key, which need to be passed on to its owner.
+    pub async fn sweep_to(
+        &mut self,
+        cold_pubkey: MainPubkey,
+        verify_store: bool,
+    ) -> WalletResult<SweepReport> {
+        self.resend_pending_transaction_until_success(verify_store)
+            .await?;
+
+        let report = self.wallet.local_sweep_to(cold_pubkey)?;
+        if let Some(err) = &report.error {
+            warn!(
+                "Sweep stopped after {}/{} cash_notes: {err:?}",
+                report.swept_cash_notes, report.total_cash_notes
+            );
+        }
+
+        // send to network
+        if let Err(error) = self
+            .client
+            .send_spends(
+                self.wallet.unconfirmed_spend_requests().iter(),
+                verify_store,
+            )
+            .await
+        {
+            return Err(WalletError::CouldNotSendMoney(format!(
+                "The sweep was not successfully registered in the network: {error:?}"
+            )));
+        } else {
+            // clear unconfirmed txs
+            self.wallet.clear_confirmed_spend_requests();
+        }
+
+        Ok(report)
+    }
+
     /// Send signed spends to another wallet.
     /// Can optionally verify if the store has been successful.
     /// Verification will be attempted via GET request through a Spend on the network.
@@ -464,27 +504,26 @@ impl WalletClient {
     ) -> WalletResult<StoragePaymentResult> {
         let verify_store = true;
         let c: Vec<_> = content_addrs.collect();
-        // Using default ExponentialBackoff doesn't make sense,
-        // as it will just fail after the first payment failure.
-        let mut backoff = ExponentialBackoff::default();
-        let mut last_err = "No retries".to_string();
+        let mut retries = self.client.start_retries(RetryOperation::StoragePayment);
 
-        while let Some(delay) = backoff.next_backoff() {
+        loop {
             trace!("Paying for storage (w/backoff retries) for: {:?}", c);
             match self
                 .pay_for_storage_once(c.clone().into_iter(), verify_store)
                 .await
             {
                 Ok(payment_result) => return Ok(payment_result),
-                Err(WalletError::CouldNotSendMoney(err)) => {
+                Err(err) => {
                     warn!("Attempt to pay for data failed: {err:?}");
-                    last_err = err;
-                    sleep(delay).await;
+                    // only failing to reach the network is worth retrying by default.
+                    let retriable = matches!(err, WalletError::CouldNotSendMoney(_));
+                    match retries.on_failure(&err, retriable) {
+                        Some(delay) => sleep(delay).await,
+                        None => return Err(err),
+                    }
                 }
-                Err(err) => return Err(err),
             }
         }
-        Err(WalletError::CouldNotSendMoney(last_err))
     }
 
     /// Existing chunks will have the store cost set to Zero.
@@ -837,19 +876,19 @@ impl WalletClient {
     ) -> WalletResult<()> {
         let mut did_error = false;
         // Wallet shall be all clear to progress forward.
-        let mut attempts = 0;
+        let mut retries = self
+            .client
+            .start_retries(RetryOperation::PendingTransactions);
         while self.wallet.unconfirmed_spend_requests_exist() {
-            info!("Pre-Unconfirmed transactions exist, sending again after 1 second...");
-            sleep(Duration::from_secs(1)).await;
-            self.resend_pending_transactions(verify_store).await;
-
-            if attempts > MAX_RESEND_PENDING_TX_ATTEMPTS {
+            let Some(delay) = retries.next_delay("unconfirmed transactions exist".to_string())
+            else {
                 // save the error state, but break out of the loop so we can save
                 did_error = true;
                 break;
-            }
-
-            attempts += 1;
+            };
+            info!("Pre-Unconfirmed transactions exist, sending again after {delay:?}...");
+            sleep(delay).await;
+            self.resend_pending_transactions(verify_store).await;
         }
 
         if did_error {
@@ -941,11 +980,21 @@ impl Client {
         verify_store: bool,
     ) -> WalletResult<()> {
         let mut tasks = Vec::new();
+        let mut submission_tokens = BTreeSet::new();
 
         // send spends to the network in parralel
         for spend_request in spend_requests {
+            // the same spend may be given more than once, e.g. when resending pending spends
+            let submission_token = spend_request.submission_token();
+            if !submission_tokens.insert(submission_token) {
+                debug!(
+                    "Skipping spend request already being sent: {:?} ({submission_token})",
+                    spend_request.unique_pubkey()
+                );
+                continue;
+            }
             debug!(
-                "sending spend request to the network: {:?}: {spend_request:#?}",
+                "sending spend request to the network: {:?} ({submission_token}): {spend_request:#?}",
                 spend_request.unique_pubkey()
             );
 
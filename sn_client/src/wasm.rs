@@ -0,0 +1,129 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! The API exposed to JavaScript, so that web apps can fetch public data and verify transfers
+//! directly from the browser, without going through a gateway server.
+
+use crate::{ChunkCache, Client, FilesApi, FilesDownload};
+use js_sys::{Promise, Uint8Array};
+use sn_protocol::storage::ChunkAddress;
+use sn_transfers::{MainSecretKey, NanoTokens, Transfer};
+use std::path::PathBuf;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::console;
+
+/// The maximum size of the chunks cached in memory by a browser client.
+const CHUNK_CACHE_SIZE: u64 = 64 * 1024 * 1024;
+
+// This is like the `main` function, except for JavaScript.
+#[wasm_bindgen(start)]
+pub async fn main_js() -> std::result::Result<(), JsValue> {
+    // This provides better error messages in debug mode.
+    // It's disabled in release mode so it doesn't bloat up the file size.
+    // #[cfg(debug_assertions)]
+    console_error_panic_hook::set_once();
+
+    console::log_1(&JsValue::from_str("Hello safe world!"));
+
+    // Tracing
+    // TODO: dont log _everything_
+    // right now it logs all libp2p entirely.
+    tracing_wasm::set_as_global_default();
+
+    Ok(())
+}
+
+/// A client running in the browser.
+///
+/// There is no file system to rely on, so the fetched chunks are cached in memory, and the data is
+/// returned to JavaScript instead of being written to disk.
+#[wasm_bindgen]
+pub struct WasmClient {
+    client: Client,
+}
+
+#[wasm_bindgen]
+impl WasmClient {
+    /// Connects to the network through the given peers. They have to be reachable from the
+    /// browser, i.e. listen on a websocket address.
+    pub async fn connect(peers: Vec<String>) -> std::result::Result<WasmClient, JsError> {
+        let peers = peers
+            .iter()
+            .map(|peer| sn_peers_acquisition::parse_peer_addr(peer))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        console::log_1(&JsValue::from_str(&format!("Connecting through {peers:?}")));
+
+        let mut client = Client::quick_start(Some(peers)).await?;
+        client.set_chunk_cache(Some(ChunkCache::in_memory(CHUNK_CACHE_SIZE)));
+        Ok(Self { client })
+    }
+
+    /// Fetches the public data stored at the given hex address, e.g. a file whose data map was
+    /// made public on upload. Resolves to a `Uint8Array`.
+    // The futures given to JavaScript can't borrow `self`, hence the promises.
+    #[wasm_bindgen(js_name = getData)]
+    pub fn get_data(&self, address: String) -> Promise {
+        let client = self.client.clone();
+        future_to_promise(async move {
+            let bytes = get_data(client, &address).await?;
+            Ok(Uint8Array::from(bytes.as_slice()).into())
+        })
+    }
+
+    /// Verifies a hex encoded transfer against the network. Resolves to the amount received, in
+    /// nanos. The secret key is the hex encoded key the transfer was sent to, and never leaves the
+    /// browser.
+    #[wasm_bindgen(js_name = verifyTransfer)]
+    pub fn verify_transfer(&self, transfer: String, secret_key: String) -> Promise {
+        let client = self.client.clone();
+        future_to_promise(async move {
+            let received = verify_transfer(client, &transfer, &secret_key).await?;
+            Ok(JsValue::from(received.as_nano()))
+        })
+    }
+}
+
+async fn get_data(client: Client, address: &str) -> std::result::Result<Vec<u8>, JsError> {
+    let address = parse_chunk_address(address)?;
+    // nothing is paid for, so the files api is never given a wallet.
+    let files_api = FilesApi::new(client, PathBuf::new());
+    let bytes = FilesDownload::new(files_api)
+        .download_file(address, None)
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+async fn verify_transfer(
+    client: Client,
+    transfer: &str,
+    secret_key: &str,
+) -> std::result::Result<NanoTokens, JsError> {
+    let transfer = Transfer::from_hex(transfer)?;
+    let secret_key = MainSecretKey::new(bls::SecretKey::from_hex(secret_key)?);
+    let redemptions = transfer.cashnote_redemptions(&secret_key)?;
+    let cash_notes = client
+        .verify_cash_notes_redemptions(secret_key.main_pubkey(), &redemptions)
+        .await?;
+
+    let mut received = NanoTokens::zero();
+    for cash_note in cash_notes {
+        received = received
+            .checked_add(cash_note.value()?)
+            .ok_or_else(|| JsError::new("The amount received overflows"))?;
+    }
+    Ok(received)
+}
+
+fn parse_chunk_address(address: &str) -> std::result::Result<ChunkAddress, JsError> {
+    let bytes = hex::decode(address)?;
+    let xorname = bytes
+        .try_into()
+        .map_err(|_| JsError::new("The address is not 32 bytes long"))?;
+    Ok(ChunkAddress::new(xor_name::XorName(xorname)))
+}
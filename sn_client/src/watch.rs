@@ -0,0 +1,174 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{error::Result, Client};
+use futures::Stream;
+use libp2p::{kad::Record, PeerId};
+use sn_protocol::{
+    messages::{Query, QueryResponse, Request, Response},
+    storage::try_deserialize_record,
+    NetworkAddress,
+};
+use sn_registers::{Entry, EntryHash, Register, RegisterAddress, SignedRegister};
+use std::{
+    collections::BTreeSet,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+/// How many new entries are buffered till the watcher reads them.
+const WATCH_CHANNEL_SIZE: usize = 100;
+/// How long to wait before watching again when none of the holders could be watched.
+const WATCH_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A stream of the new entries of a Register, as they are stored on the network.
+///
+/// The entries are yielded each after the ones it descends from, and the Register stops being
+/// watched once dropped.
+pub struct RegisterWatch {
+    address: RegisterAddress,
+    receiver: mpsc::Receiver<(EntryHash, Entry)>,
+}
+
+impl RegisterWatch {
+    /// The address of the watched Register.
+    pub fn address(&self) -> &RegisterAddress {
+        &self.address
+    }
+}
+
+impl Stream for RegisterWatch {
+    type Item = (EntryHash, Entry);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Client {
+    /// Watch the Register at the address, returning a stream of the entries written to it from
+    /// now on.
+    ///
+    /// The holders of the Register respond to a watch as soon as it is edited, instead of the
+    /// client polling it, so that apps like chats get the new entries with a low latency.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use sn_client::{Client, Error};
+    /// use sn_registers::RegisterAddress;
+    /// use futures::StreamExt;
+    /// # use bls::SecretKey;
+    /// # use xor_name::XorName;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(),Error>{
+    /// # let mut rng = rand::thread_rng();
+    /// let client = Client::new(SecretKey::random(), None, None, None).await?;
+    /// let address = RegisterAddress::new(XorName::random(&mut rng), client.signer_pk());
+    /// let mut watch = client.watch_register(address).await?;
+    /// while let Some((hash, entry)) = watch.next().await {
+    ///     println!("New entry {hash:?}: {entry:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn watch_register(&self, address: RegisterAddress) -> Result<RegisterWatch> {
+        let signed_register = self
+            .get_signed_register_from_network(address, false)
+            .await?;
+        signed_register.verify_with_address(address)?;
+        let register = signed_register.register()?;
+
+        let (sender, receiver) = mpsc::channel(WATCH_CHANNEL_SIZE);
+        let client = self.clone();
+        let _handle = tokio::spawn(async move {
+            client.run_register_watch(register, sender).await;
+            debug!("Stopped watching Register at {address:?}");
+        });
+
+        Ok(RegisterWatch { address, receiver })
+    }
+
+    // Watch the holders of the Register one after the other, merging the replicas they respond
+    // with and sending the entries we didn't know of, till the receiver is dropped.
+    async fn run_register_watch(
+        &self,
+        mut register: Register,
+        sender: mpsc::Sender<(EntryHash, Entry)>,
+    ) {
+        let address = *register.address();
+        let key = NetworkAddress::from_register_address(address);
+        let mut seen: BTreeSet<EntryHash> = register
+            .tips_history()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        let mut holders: Vec<PeerId> = Vec::new();
+
+        while !sender.is_closed() {
+            if holders.is_empty() {
+                match self.network.get_closest_peers(&key, true).await {
+                    Ok(peers) => holders = peers,
+                    Err(err) => warn!("Could not find the holders of Register {address:?}: {err}"),
+                }
+            }
+            let Some(holder) = holders.first().copied() else {
+                tokio::time::sleep(WATCH_RETRY_INTERVAL).await;
+                continue;
+            };
+
+            let tips = register.read().into_iter().map(|(hash, _)| hash).collect();
+            let request = Request::Query(Query::WatchRegister {
+                key: key.clone(),
+                tips,
+            });
+            let replica = match self.network.send_request(request, holder).await {
+                Ok(Response::Query(QueryResponse::WatchRegister(Ok((_, bytes))))) => {
+                    let record = Record::new(key.to_record_key(), bytes.to_vec());
+                    try_deserialize_record::<SignedRegister>(&record)
+                        .map_err(|err| err.to_string())
+                        .and_then(|replica| {
+                            replica
+                                .verify_with_address(address)
+                                .and_then(|_| replica.register())
+                                .map_err(|err| err.to_string())
+                        })
+                }
+                Ok(response) => Err(format!("{response:?}")),
+                Err(err) => Err(err.to_string()),
+            };
+            let replica = match replica {
+                Ok(replica) => replica,
+                Err(err) => {
+                    // watch the next holder, looking the holders up again once all failed
+                    warn!("Could not watch Register {address:?} at {holder:?}: {err}");
+                    let _ = holders.remove(0);
+                    continue;
+                }
+            };
+
+            if let Err(err) = register.merge(&replica) {
+                warn!("Could not merge the replica of Register {address:?} from {holder:?}: {err}");
+                let _ = holders.remove(0);
+                continue;
+            }
+            for (hash, _) in register.tips_history() {
+                if !seen.insert(hash) {
+                    continue;
+                }
+                let Ok(entry) = register.get(hash) else {
+                    continue;
+                };
+                if sender.send((hash, entry.clone())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
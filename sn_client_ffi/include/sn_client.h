@@ -0,0 +1,76 @@
+/*
+ * Copyright 2024 MaidSafe.net limited.
+ *
+ * This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+ * Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+ * under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+ * KIND, either express or implied. Please review the Licences for the specific language governing
+ * permissions and limitations relating to use of the SAFE Network Software.
+ */
+
+/* The C API of the SAFE Network client, implemented in `sn_client_ffi/src/c_api.rs`. */
+
+#ifndef SN_CLIENT_H
+#define SN_CLIENT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The outcome of a call. The message of the last error of the calling thread is returned by
+ * `sn_last_error_message`. A call which panicked returns `SN_PANIC`, after which the client it
+ * was made with should not be used anymore. */
+typedef enum SnError {
+    SN_OK = 0,
+    SN_INVALID_ARGUMENT = 1,
+    SN_NETWORK = 2,
+    SN_WALLET = 3,
+    SN_TRANSFER = 4,
+    SN_PANIC = 5,
+} SnError;
+
+/* Bytes owned by the library, to be freed with `sn_buffer_free`. */
+typedef struct SnBuffer {
+    uint8_t *data;
+    size_t len;
+} SnBuffer;
+
+/* A client connected to the network. */
+typedef struct SnClient SnClient;
+
+/* A nul terminated string, or an empty buffer if no error occurred on the calling thread. */
+SnBuffer sn_last_error_message(void);
+
+void sn_buffer_free(SnBuffer buffer);
+
+/* `peers` is a comma separated list of multiaddrs. The client is freed with `sn_client_free`. */
+SnError sn_client_connect(const char *peers, SnClient **out);
+
+void sn_client_free(SnClient *client);
+
+/* Pays for the chunk from the wallet in `wallet_dir`, and writes its 32 bytes address. */
+SnError sn_client_put_chunk(const SnClient *client, const uint8_t *data, size_t len,
+                            const char *wallet_dir, uint8_t *out_address);
+
+/* `address` is 32 bytes long. */
+SnError sn_client_get_chunk(const SnClient *client, const uint8_t *address, SnBuffer *out);
+
+/* `amount` is in nanos, `to` is a bech32m or hex encoded address. The transfer is a nul
+ * terminated hex string, to be given to the recipient. */
+SnError sn_wallet_send(const SnClient *client, const char *wallet_dir, uint64_t amount,
+                       const char *to, SnBuffer *out_transfer);
+
+/* Deposits the hex encoded transfer, writing the new balance in nanos. */
+SnError sn_wallet_receive(const SnClient *client, const char *wallet_dir, const char *transfer,
+                          uint64_t *out_balance);
+
+SnError sn_wallet_balance(const char *wallet_dir, uint64_t *out_balance);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SN_CLIENT_H */
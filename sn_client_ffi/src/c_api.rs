@@ -0,0 +1,435 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A C API over the client and the wallet, for the languages uniffi does not cover, e.g. C++, Go
+//! through cgo, or Node through N-API. It is declared in `include/sn_client.h`.
+//!
+//! The client is an opaque handle, owning the tokio runtime its calls are blocked on. Every
+//! function returns an `SnError`, the message of the last error of the calling thread being
+//! available from `sn_last_error_message`. No panic unwinds into the caller: a call panicking
+//! returns `SnError::Panic`. The bytes returned by the library are `SnBuffer`s, to
+//! be freed with `sn_buffer_free`.
+
+use sn_client::{
+    acc_packet::load_account_wallet_or_create_with_mnemonic,
+    protocol::storage::{Chunk, ChunkAddress},
+    send,
+    transfers::{MainPubkey, NanoTokens, Transfer},
+    Client, Uploader,
+};
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::PathBuf,
+    ptr, slice,
+    str::FromStr,
+};
+use tokio::runtime::Runtime;
+
+/// The outcome of a call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnError {
+    Ok = 0,
+    /// A null pointer, a string which is not UTF-8, or a malformed address, key or transfer.
+    InvalidArgument = 1,
+    /// The network could not be reached, or failed the request.
+    Network = 2,
+    /// The wallet could not be loaded, or does not hold enough tokens.
+    Wallet = 3,
+    /// A transfer could not be made, or redeemed.
+    Transfer = 4,
+    /// The library panicked. The client the call was made with should not be used anymore.
+    Panic = 5,
+}
+
+/// Bytes owned by the library, to be freed with `sn_buffer_free`.
+#[repr(C)]
+pub struct SnBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl SnBuffer {
+    fn empty() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let bytes = bytes.into_boxed_slice();
+        let len = bytes.len();
+        let data = Box::into_raw(bytes) as *mut u8;
+        Self { data, len }
+    }
+}
+
+/// A client connected to the network.
+// The client is dropped before the runtime its tasks run on.
+pub struct SnClient {
+    client: Client,
+    runtime: Runtime,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Records the message of the error for `sn_last_error_message`, returning its code.
+fn fail(code: SnError, message: impl ToString) -> SnError {
+    let message = message.to_string();
+    error!("FFI call failed with {code:?}: {message}");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    code
+}
+
+// Runs the call, turning its error, or its panic, into a code.
+fn call(f: impl FnOnce() -> Result<(), (SnError, String)>) -> SnError {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => SnError::Ok,
+        Ok(Err((code, message))) => fail(code, message),
+        Err(payload) => fail(SnError::Panic, panic_message(payload.as_ref())),
+    }
+}
+
+// Runs a call returning nothing, a panic only being recorded for `sn_last_error_message`.
+fn call_infallible(f: impl FnOnce()) {
+    if let Err(payload) = catch_unwind(AssertUnwindSafe(f)) {
+        let _ = fail(SnError::Panic, panic_message(payload.as_ref()));
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("panicked: {message}")
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("panicked: {message}")
+    } else {
+        "panicked".to_string()
+    }
+}
+
+fn invalid_argument(message: impl ToString) -> (SnError, String) {
+    (SnError::InvalidArgument, message.to_string())
+}
+
+/// # Safety
+/// `s` must be null, or a valid nul terminated string.
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, (SnError, String)> {
+    if s.is_null() {
+        return Err(invalid_argument(format!("{name} is null")));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| invalid_argument(format!("{name} is not UTF-8")))
+}
+
+/// # Safety
+/// `client` must be null, or a handle returned by `sn_client_connect` and not freed yet.
+unsafe fn client_arg<'a>(client: *const SnClient) -> Result<&'a SnClient, (SnError, String)> {
+    client
+        .as_ref()
+        .ok_or_else(|| invalid_argument("client is null"))
+}
+
+/// The message of the last error returned on the calling thread, as a nul terminated string, or
+/// an empty buffer if there is none.
+#[no_mangle]
+pub extern "C" fn sn_last_error_message() -> SnBuffer {
+    catch_unwind(|| {
+        LAST_ERROR.with(|last| match last.borrow().as_ref() {
+            Some(message) => {
+                let mut bytes = message.clone().into_bytes();
+                bytes.push(0);
+                SnBuffer::from_vec(bytes)
+            }
+            None => SnBuffer::empty(),
+        })
+    })
+    .unwrap_or_else(|_| SnBuffer::empty())
+}
+
+/// Frees bytes returned by the library.
+///
+/// # Safety
+/// `buffer` must have been returned by the library, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sn_buffer_free(buffer: SnBuffer) {
+    call_infallible(|| {
+        if !buffer.data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                buffer.data,
+                buffer.len,
+            )));
+        }
+    })
+}
+
+/// Connects to the network through the comma separated multiaddrs of `peers`, setting `out` to
+/// the handle of the client, to be freed with `sn_client_free`.
+///
+/// # Safety
+/// `peers` must be a valid nul terminated string, and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sn_client_connect(
+    peers: *const c_char,
+    out: *mut *mut SnClient,
+) -> SnError {
+    call(|| {
+        let peers = str_arg(peers, "peers")?
+            .split(',')
+            .map(|peer| sn_peers_acquisition::parse_peer_addr(peer.trim()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| invalid_argument(format!("{err:?}")))?;
+        let out = out
+            .as_mut()
+            .ok_or_else(|| invalid_argument("out is null"))?;
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| (SnError::Network, err.to_string()))?;
+        let client = runtime
+            .block_on(Client::new(
+                bls::SecretKey::random(),
+                Some(peers),
+                None,
+                None,
+            ))
+            .map_err(|err| (SnError::Network, err.to_string()))?;
+        *out = Box::into_raw(Box::new(SnClient { runtime, client }));
+        Ok(())
+    })
+}
+
+/// Disconnects the client, and frees its handle.
+///
+/// # Safety
+/// `client` must be null, or a handle returned by `sn_client_connect` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sn_client_free(client: *mut SnClient) {
+    call_infallible(|| {
+        if !client.is_null() {
+            drop(Box::from_raw(client));
+        }
+    })
+}
+
+/// Stores `len` bytes as a chunk, paying for it from the wallet in `wallet_dir`, and writes the
+/// 32 bytes address of the chunk to `out_address`.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes, `wallet_dir` must be a valid nul terminated string,
+/// and `out_address` must point to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sn_client_put_chunk(
+    client: *const SnClient,
+    data: *const u8,
+    len: usize,
+    wallet_dir: *const c_char,
+    out_address: *mut u8,
+) -> SnError {
+    call(|| {
+        let client = client_arg(client)?;
+        if data.is_null() || out_address.is_null() {
+            return Err(invalid_argument("data or out_address is null"));
+        }
+        let wallet_dir = PathBuf::from(str_arg(wallet_dir, "wallet_dir")?);
+
+        let chunk = Chunk::new(slice::from_raw_parts(data, len).to_vec().into());
+        let name = *chunk.name();
+        let mut uploader = Uploader::new(client.client.clone(), wallet_dir);
+        uploader.insert_chunks([chunk]);
+        let _summary = client
+            .runtime
+            .block_on(uploader.start_upload())
+            .map_err(|err| (SnError::Network, err.to_string()))?;
+
+        ptr::copy_nonoverlapping(name.0.as_ptr(), out_address, name.0.len());
+        Ok(())
+    })
+}
+
+/// Fetches the chunk at the 32 bytes `address`, setting `out` to its content.
+///
+/// # Safety
+/// `address` must point to 32 readable bytes, and `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sn_client_get_chunk(
+    client: *const SnClient,
+    address: *const u8,
+    out: *mut SnBuffer,
+) -> SnError {
+    call(|| {
+        let client = client_arg(client)?;
+        if address.is_null() {
+            return Err(invalid_argument("address is null"));
+        }
+        let out = out
+            .as_mut()
+            .ok_or_else(|| invalid_argument("out is null"))?;
+
+        let mut name = [0; 32];
+        ptr::copy_nonoverlapping(address, name.as_mut_ptr(), name.len());
+        let chunk = client
+            .runtime
+            .block_on(client.client.get_chunk(
+                ChunkAddress::new(xor_name::XorName(name)),
+                false,
+                None,
+            ))
+            .map_err(|err| (SnError::Network, err.to_string()))?;
+        *out = SnBuffer::from_vec(chunk.value().to_vec());
+        Ok(())
+    })
+}
+
+/// Sends `amount` nanos from the wallet in `wallet_dir` to the bech32m or hex encoded address `to`,
+/// setting `out_transfer` to the hex encoded transfer, as a nul terminated string, to be given to
+/// the recipient.
+///
+/// # Safety
+/// `wallet_dir` and `to` must be valid nul terminated strings, and `out_transfer` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sn_wallet_send(
+    client: *const SnClient,
+    wallet_dir: *const c_char,
+    amount: u64,
+    to: *const c_char,
+    out_transfer: *mut SnBuffer,
+) -> SnError {
+    call(|| {
+        let client = client_arg(client)?;
+        let wallet_dir = PathBuf::from(str_arg(wallet_dir, "wallet_dir")?);
+        let to = MainPubkey::from_str(str_arg(to, "to")?).map_err(invalid_argument)?;
+        let out_transfer = out_transfer
+            .as_mut()
+            .ok_or_else(|| invalid_argument("out_transfer is null"))?;
+
+        let wallet = load_account_wallet_or_create_with_mnemonic(&wallet_dir, None)
+            .map_err(|err| (SnError::Wallet, err.to_string()))?;
+        let cash_note = client
+            .runtime
+            .block_on(send(
+                wallet,
+                NanoTokens::from(amount),
+                to,
+                &client.client,
+                true,
+            ))
+            .map_err(|err| (SnError::Transfer, err.to_string()))?;
+        let mut transfer = Transfer::transfer_from_cash_note(&cash_note)
+            .and_then(|transfer| transfer.to_hex())
+            .map_err(|err| (SnError::Transfer, err.to_string()))?
+            .into_bytes();
+        transfer.push(0);
+        *out_transfer = SnBuffer::from_vec(transfer);
+        Ok(())
+    })
+}
+
+/// Verifies the hex encoded `transfer` with the network, and deposits it into the wallet in
+/// `wallet_dir`, setting `out_balance` to the new balance of the wallet, in nanos.
+///
+/// # Safety
+/// `wallet_dir` and `transfer` must be valid nul terminated strings, and `out_balance` a valid
+/// pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sn_wallet_receive(
+    client: *const SnClient,
+    wallet_dir: *const c_char,
+    transfer: *const c_char,
+    out_balance: *mut u64,
+) -> SnError {
+    call(|| {
+        let client = client_arg(client)?;
+        let wallet_dir = PathBuf::from(str_arg(wallet_dir, "wallet_dir")?);
+        let transfer =
+            Transfer::from_hex(str_arg(transfer, "transfer")?).map_err(invalid_argument)?;
+        let out_balance = out_balance
+            .as_mut()
+            .ok_or_else(|| invalid_argument("out_balance is null"))?;
+
+        let mut wallet = load_account_wallet_or_create_with_mnemonic(&wallet_dir, None)
+            .map_err(|err| (SnError::Wallet, err.to_string()))?;
+        let cash_notes = client
+            .runtime
+            .block_on(client.client.receive(&transfer, &wallet))
+            .map_err(|err| (SnError::Transfer, err.to_string()))?;
+        wallet
+            .deposit_and_store_to_disk(&cash_notes)
+            .map_err(|err| (SnError::Wallet, err.to_string()))?;
+        *out_balance = wallet.balance().as_nano();
+        Ok(())
+    })
+}
+
+/// Sets `out_balance` to the balance of the wallet in `wallet_dir`, in nanos.
+///
+/// # Safety
+/// `wallet_dir` must be a valid nul terminated string, and `out_balance` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sn_wallet_balance(
+    wallet_dir: *const c_char,
+    out_balance: *mut u64,
+) -> SnError {
+    call(|| {
+        let wallet_dir = PathBuf::from(str_arg(wallet_dir, "wallet_dir")?);
+        let out_balance = out_balance
+            .as_mut()
+            .ok_or_else(|| invalid_argument("out_balance is null"))?;
+        let wallet = load_account_wallet_or_create_with_mnemonic(&wallet_dir, None)
+            .map_err(|err| (SnError::Wallet, err.to_string()))?;
+        *out_balance = wallet.balance().as_nano();
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn errors_are_reported_with_their_message() {
+        let peers = CString::new("not a multiaddr").expect("no nul byte");
+        let mut client = ptr::null_mut();
+        let code = unsafe { sn_client_connect(peers.as_ptr(), &mut client) };
+        assert_eq!(code, SnError::InvalidArgument);
+        assert!(client.is_null());
+
+        let message = sn_last_error_message();
+        assert!(!message.data.is_null());
+        let text = unsafe { CStr::from_ptr(message.data as *const c_char) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { sn_buffer_free(message) };
+        assert!(!text.is_empty());
+
+        let mut buffer = SnBuffer::empty();
+        let code = unsafe { sn_client_get_chunk(ptr::null(), [0; 32].as_ptr(), &mut buffer) };
+        assert_eq!(code, SnError::InvalidArgument);
+        assert!(buffer.data.is_null());
+    }
+
+    #[test]
+    fn panics_are_returned_as_an_error() {
+        let code = call(|| panic!("boom"));
+        assert_eq!(code, SnError::Panic);
+
+        let message = sn_last_error_message();
+        let text = unsafe { CStr::from_ptr(message.data as *const c_char) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { sn_buffer_free(message) };
+        assert_eq!(text, "panicked: boom");
+    }
+}
@@ -0,0 +1,280 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Kotlin and Swift bindings for the core client and wallet APIs, generated with
+//! [uniffi](https://mozilla.github.io/uniffi-rs/), so that mobile wallet apps can embed the client
+//! instead of shelling out to the CLI.
+//!
+//! The async functions are exposed as `suspend` functions in Kotlin and `async` ones in Swift, and
+//! run on a tokio runtime owned by this library. Progress is reported through the
+//! `ProgressListener` callback interface, implemented by the app.
+//!
+//! The other languages can use the C API of the `c_api` module instead.
+
+#[macro_use]
+extern crate tracing;
+
+// Raw pointers are the only way across a C API.
+#[allow(unsafe_code)]
+pub mod c_api;
+
+use sn_client::{
+    acc_packet::load_account_wallet_or_create_with_mnemonic,
+    protocol::storage::ChunkAddress,
+    send,
+    transfers::{HotWallet, MainPubkey, NanoTokens, Transfer},
+    Client, ClientEvent, FilesApi, FilesDownload, ProgressEvent, Uploader,
+};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+uniffi::setup_scaffolding!();
+
+/// The errors returned to the apps. Only their messages cross the language boundary.
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum SafeError {
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Client error: {0}")]
+    Client(#[from] sn_client::Error),
+    #[error("Wallet error: {0}")]
+    Wallet(#[from] sn_client::transfers::WalletError),
+    #[error("Transfer error: {0}")]
+    Transfer(#[from] sn_client::transfers::TransferError),
+}
+
+/// The progress made storing data on the network, see `sn_client::ProgressEvent`.
+/// Addresses are hex encoded, and amounts are in nanos.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum Progress {
+    ChunkUploaded {
+        address: String,
+    },
+    ChunkAlreadyStored {
+        address: String,
+    },
+    RegisterUploaded {
+        address: String,
+    },
+    PaymentMade {
+        storage_cost: u64,
+        royalty_fees: u64,
+        new_balance: u64,
+    },
+    Retry {
+        operation: String,
+        attempt: u64,
+        delay_ms: u64,
+        reason: String,
+    },
+    VerificationFailed {
+        address: String,
+    },
+    FileCompleted {
+        path: String,
+        data_map: String,
+    },
+}
+
+impl From<ProgressEvent> for Progress {
+    fn from(event: ProgressEvent) -> Self {
+        match event {
+            ProgressEvent::ChunkUploaded { address } => Self::ChunkUploaded {
+                address: address.to_hex(),
+            },
+            ProgressEvent::ChunkAlreadyStored { address } => Self::ChunkAlreadyStored {
+                address: address.to_hex(),
+            },
+            ProgressEvent::RegisterUploaded { address } => Self::RegisterUploaded {
+                address: address.to_hex(),
+            },
+            ProgressEvent::PaymentMade {
+                storage_cost,
+                royalty_fees,
+                new_balance,
+            } => Self::PaymentMade {
+                storage_cost: storage_cost.as_nano(),
+                royalty_fees: royalty_fees.as_nano(),
+                new_balance: new_balance.as_nano(),
+            },
+            ProgressEvent::Retry(event) => Self::Retry {
+                operation: format!("{:?}", event.operation),
+                attempt: event.attempt as u64,
+                delay_ms: u64::try_from(event.delay.as_millis()).unwrap_or(u64::MAX),
+                reason: event.reason,
+            },
+            ProgressEvent::VerificationFailed { address } => Self::VerificationFailed {
+                address: address.to_hex(),
+            },
+            ProgressEvent::FileCompleted { path, data_map } => Self::FileCompleted {
+                path: path.display().to_string(),
+                data_map: data_map.to_hex(),
+            },
+        }
+    }
+}
+
+/// Implemented by the apps to be told about the progress of the client.
+/// It is called from a background thread.
+#[uniffi::export(callback_interface)]
+pub trait ProgressListener: Send + Sync {
+    fn on_progress(&self, progress: Progress);
+}
+
+/// Connects to the network through the given peers, e.g. `/ip4/1.2.3.4/udp/1234/quic-v1/p2p/<peer id>`.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn connect(peers: Vec<String>) -> Result<Arc<SafeClient>, SafeError> {
+    let peers = peers
+        .iter()
+        .map(|peer| sn_peers_acquisition::parse_peer_addr(peer))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| SafeError::InvalidInput(format!("{err:?}")))?;
+    info!("Connecting to the network through {peers:?}");
+    let client = Client::new(bls::SecretKey::random(), Some(peers), None, None).await?;
+    Ok(Arc::new(SafeClient { client }))
+}
+
+/// A client connected to the network.
+#[derive(uniffi::Object)]
+pub struct SafeClient {
+    client: Client,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl SafeClient {
+    /// Calls the listener with the progress made by the client, until the client is dropped.
+    // async so that it is run on the tokio runtime, where the listening task is spawned.
+    #[allow(clippy::unused_async)]
+    pub async fn subscribe_progress(&self, listener: Box<dyn ProgressListener>) {
+        let mut events = self.client.events_channel();
+        let _handle = tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(ClientEvent::Progress(event)) => listener.on_progress(event.into()),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("The progress listener missed {skipped} events");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Fetches the public data stored at the given hex address.
+    pub async fn get_data(&self, address: String) -> Result<Vec<u8>, SafeError> {
+        let address = parse_chunk_address(&address)?;
+        // nothing is paid for, so the files api is never given a wallet.
+        let files_api = FilesApi::new(self.client.clone(), PathBuf::new());
+        let bytes = FilesDownload::new(files_api)
+            .download_file(address, None)
+            .await?;
+        Ok(bytes.to_vec())
+    }
+
+    /// Uploads the file at the given path, paying from the wallet in `wallet_dir`.
+    /// Returns the hex address of the data map of the file, which is uploaded along with it.
+    pub async fn upload_file(&self, path: String, wallet_dir: String) -> Result<String, SafeError> {
+        // each upload has its own chunks dir, removed when dropped.
+        let chunks_dir =
+            tempfile::tempdir().map_err(|err| SafeError::Client(sn_client::Error::from(err)))?;
+
+        let (data_map, _, _, chunks) =
+            FilesApi::chunk_file(Path::new(&path), chunks_dir.path(), true)?;
+        let mut uploader = Uploader::new(self.client.clone(), PathBuf::from(wallet_dir));
+        uploader.insert_chunk_paths(chunks);
+        let _summary = uploader.start_upload().await?;
+        Ok(data_map.to_hex())
+    }
+}
+
+/// Opens the wallet stored in `root_dir`, creating it if needed.
+#[uniffi::export]
+pub fn open_wallet(root_dir: String) -> Result<Arc<SafeWallet>, SafeError> {
+    let root_dir = PathBuf::from(root_dir);
+    let _wallet = load_account_wallet_or_create_with_mnemonic(&root_dir, None)?;
+    Ok(Arc::new(SafeWallet { root_dir }))
+}
+
+/// A wallet stored on the device.
+///
+/// The wallet is loaded from disk by every call, so that it is never out of date with the changes
+/// made by another process.
+#[derive(uniffi::Object)]
+pub struct SafeWallet {
+    root_dir: PathBuf,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl SafeWallet {
+    /// The bech32m address to send tokens to, e.g. `safe1...`.
+    pub fn address(&self) -> Result<String, SafeError> {
+        Ok(self.load()?.address().to_address())
+    }
+
+    /// The balance of the wallet, in nanos.
+    pub fn balance(&self) -> Result<u64, SafeError> {
+        Ok(self.load()?.balance().as_nano())
+    }
+
+    /// Verifies the hex encoded transfer with the network, and deposits it.
+    /// Returns the new balance of the wallet, in nanos.
+    pub async fn receive(
+        &self,
+        client: Arc<SafeClient>,
+        transfer: String,
+    ) -> Result<u64, SafeError> {
+        let transfer = Transfer::from_hex(&transfer)?;
+        let mut wallet = self.load()?;
+        let cash_notes = client.client.receive(&transfer, &wallet).await?;
+        wallet.deposit_and_store_to_disk(&cash_notes)?;
+        Ok(wallet.balance().as_nano())
+    }
+
+    /// Sends `amount` nanos to the address `to`, either bech32m or hex encoded.
+    /// Returns the hex encoded transfer, to be given to the recipient.
+    pub async fn send(
+        &self,
+        client: Arc<SafeClient>,
+        amount: u64,
+        to: String,
+    ) -> Result<String, SafeError> {
+        let to = MainPubkey::from_str(&to)?;
+        let cash_note = send(
+            self.load()?,
+            NanoTokens::from(amount),
+            to,
+            &client.client,
+            true,
+        )
+        .await?;
+        Ok(Transfer::transfer_from_cash_note(&cash_note)?.to_hex()?)
+    }
+}
+
+impl SafeWallet {
+    fn load(&self) -> Result<HotWallet, SafeError> {
+        Ok(load_account_wallet_or_create_with_mnemonic(
+            &self.root_dir,
+            None,
+        )?)
+    }
+}
+
+fn parse_chunk_address(address: &str) -> Result<ChunkAddress, SafeError> {
+    let xorname = hex::decode(address)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| SafeError::InvalidInput(format!("Invalid address: {address}")))?;
+    Ok(ChunkAddress::new(xor_name::XorName(xorname)))
+}
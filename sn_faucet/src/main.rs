@@ -181,7 +181,7 @@ enum SubCmd {
         /// This shall be the number of nanos to send.
         #[clap(name = "amount")]
         amount: String,
-        /// This must be a hex-encoded `MainPubkey`.
+        /// The address of the recipient, or its hex-encoded `MainPubkey`.
         #[clap(name = "to")]
         to: String,
     },
@@ -234,8 +234,8 @@ async fn claim_genesis(client: &Client, mut wallet: HotWallet) -> Result<()> {
 
 /// returns the hex-encoded transfer
 async fn send_tokens(client: &Client, from: HotWallet, amount: &str, to: &str) -> Result<String> {
-    let to = MainPubkey::from_hex(to)?;
     use std::str::FromStr;
+    let to = MainPubkey::from_str(to)?;
     let amount = NanoTokens::from_str(amount)?;
     if amount.as_nano() == 0 {
         println!("Invalid format or zero amount passed in. Nothing sent.");
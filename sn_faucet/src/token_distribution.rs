@@ -96,7 +96,7 @@ impl MaidClaim {
             return Err(eyre!("Claim public key does not match address"));
         }
         // check wallet is a valid bls pubkey
-        if MainPubkey::from_hex(&self.wallet).is_err() {
+        if MainPubkey::from_str(&self.wallet).is_err() {
             return Err(eyre!("Invalid bls public key"));
         };
         // if all the checks are ok, it's valid
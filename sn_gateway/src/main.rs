@@ -0,0 +1,182 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#[macro_use]
+extern crate tracing;
+
+mod s3;
+mod server;
+
+use bls::SecretKey;
+use clap::Parser;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use sn_client::{transfers::bls_secret_from_hex, Client};
+use sn_logging::{Level, LogBuilder, LogOutputDest};
+use sn_peers_acquisition::PeersArgs;
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+const CLIENT_KEY: &str = "client_key";
+
+/// An HTTP gateway to the network, so that web services can store and fetch data, and use a
+/// wallet, without linking the client.
+///
+/// Every request must carry the API token, as `Authorization: Bearer <token>`.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Opt {
+    /// The address to listen on.
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// The token the requests must be authenticated with.
+    ///
+    /// It is also the secret key the requests to the S3 API are signed with.
+    #[clap(long, env = "SAFE_GATEWAY_TOKEN", hide_env_values = true)]
+    api_token: String,
+
+    /// The address to serve the S3 compatible API on, where every bucket is a files container.
+    /// It is not served unless set.
+    #[clap(long)]
+    s3_listen: Option<SocketAddr>,
+
+    /// The access key id the requests to the S3 API are signed with.
+    #[clap(long, default_value = "safe")]
+    s3_access_key: String,
+
+    /// The directory holding the key and the wallet of the gateway.
+    ///
+    /// Defaults to the `safe/gateway` directory in the platform specific data directory.
+    #[clap(long)]
+    root_dir: Option<PathBuf>,
+
+    /// Specify the logging output destination.
+    ///
+    /// Valid values are "stdout", "data-dir", or a custom path.
+    #[clap(long, value_parser = parse_log_output, default_value = "data-dir")]
+    log_output_dest: LogOutputDest,
+
+    #[command(flatten)]
+    peers: PeersArgs,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    color_eyre::install()?;
+    let opt = Opt::parse();
+    if opt.api_token.is_empty() {
+        bail!("The API token can't be empty");
+    }
+
+    let logging_targets = vec![
+        ("safe_gateway".to_string(), Level::TRACE),
+        ("sn_gateway".to_string(), Level::TRACE),
+        ("sn_client".to_string(), Level::DEBUG),
+        ("sn_networking".to_string(), Level::INFO),
+        ("sn_build_info".to_string(), Level::TRACE),
+        ("sn_peers_acquisition".to_string(), Level::TRACE),
+    ];
+    let mut log_builder = LogBuilder::new(logging_targets);
+    log_builder.output_dest(opt.log_output_dest);
+    let _log_handles = log_builder.initialize()?;
+
+    debug!(
+        "safe-gateway built with git version: {}",
+        sn_build_info::git_info()
+    );
+
+    let root_dir = match opt.root_dir {
+        Some(root_dir) => root_dir,
+        None => get_gateway_data_dir()?,
+    };
+    let secret_key = get_gateway_secret_key(&root_dir)?;
+
+    let bootstrap_peers = opt.peers.get_peers().await?;
+    let bootstrap_peers = if bootstrap_peers.is_empty() {
+        // empty vec is returned if `local-discovery` flag is provided
+        None
+    } else {
+        Some(bootstrap_peers)
+    };
+    println!("Connecting to the network...");
+    let client = Client::new(secret_key, bootstrap_peers, None, None).await?;
+
+    let gateway = server::Gateway::new(client, root_dir);
+    let s3_server = async {
+        if let Some(s3_listen) = opt.s3_listen {
+            println!("Serving the S3 API on http://{s3_listen}");
+            info!("Serving the S3 API on {s3_listen}");
+            let credentials = s3::S3Credentials {
+                access_key: opt.s3_access_key.clone(),
+                secret_key: opt.api_token.clone(),
+            };
+            s3::run(gateway.clone(), credentials, s3_listen)
+                .await
+                .wrap_err("Failed to serve the S3 API")?;
+        }
+        Ok::<_, color_eyre::Report>(())
+    };
+    let server = async {
+        server::run(gateway.clone(), opt.api_token.clone(), opt.listen).await;
+        Ok(())
+    };
+
+    println!("Listening on http://{}", opt.listen);
+    info!("Listening on {}", opt.listen);
+    // the gateway stops if either server fails.
+    let _ = tokio::try_join!(server, s3_server)?;
+    Ok(())
+}
+
+// The registers written through the gateway are owned by this key, so it is kept across restarts.
+fn get_gateway_secret_key(root_dir: &Path) -> Result<SecretKey> {
+    std::fs::create_dir_all(root_dir)?;
+    let key_path = root_dir.join(CLIENT_KEY);
+    let secret_key = if key_path.is_file() {
+        info!("Gateway key found. Loading from file...");
+        bls_secret_from_hex(std::fs::read(key_path)?)?
+    } else {
+        info!("No key found. Generating a new gateway key...");
+        let secret_key = SecretKey::random();
+        let mut options = OpenOptions::new();
+        let _ = options.write(true).create_new(true);
+        // On Unix systems, make sure only the current user can read/write.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            let _ = options.mode(0o600);
+        }
+        options
+            .open(key_path)?
+            .write_all(hex::encode(secret_key.to_bytes()).as_bytes())?;
+        secret_key
+    };
+    Ok(secret_key)
+}
+
+fn get_gateway_data_dir() -> Result<PathBuf> {
+    let mut data_dir = dirs_next::data_dir().expect("Data directory is obtainable");
+    data_dir.push("safe");
+    data_dir.push("gateway");
+    std::fs::create_dir_all(&data_dir)?;
+    Ok(data_dir)
+}
+
+fn parse_log_output(val: &str) -> Result<LogOutputDest> {
+    match val {
+        "stdout" => Ok(LogOutputDest::Stdout),
+        "data-dir" => Ok(LogOutputDest::Path(get_gateway_data_dir()?.join("logs"))),
+        // The path should be a directory, but we can't use something like `is_dir` to check
+        // because the path doesn't need to exist. We can create it for the user.
+        value => Ok(LogOutputDest::Path(PathBuf::from(value))),
+    }
+}
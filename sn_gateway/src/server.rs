@@ -0,0 +1,513 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! The HTTP endpoints of the gateway:
+//!
+//! - `POST /files`, with the content of a file as body, uploads it and makes it public, returning
+//!   the hex address of its data map.
+//! - `GET /files/{address}` fetches a public file.
+//! - `POST /registers`, with `{"name": ...}` as body, creates a register owned by the gateway.
+//! - `GET /registers/{address}` returns the hex encoded current entries of a register.
+//! - `POST /registers/{address}`, with an entry as body, writes it to a register.
+//! - `GET /wallet/balance` returns the balance of the wallet of the gateway, in nanos.
+//! - `POST /wallet/send`, with `{"to": ..., "amount": ...}` as body, returns the hex encoded transfer.
+//!
+//! Storage is paid from the wallet of the gateway.
+
+use bytes::{Buf, Bytes};
+use futures::{future, stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sn_client::{
+    acc_packet::load_account_wallet_or_create_with_mnemonic,
+    protocol::storage::ChunkAddress,
+    registers::{Permissions, RegisterAddress},
+    send,
+    transfers::{MainPubkey, NanoTokens, Transfer},
+    Client, FilesApi, FilesDownload, StoragePaymentResult, WalletClient,
+};
+use std::{convert::Infallible, io, net::SocketAddr, path::PathBuf, str::FromStr, sync::Arc};
+use tokio::{
+    io::{AsyncRead, AsyncWriteExt},
+    sync::Mutex,
+};
+use tokio_util::io::{ReaderStream, StreamReader};
+use warp::{
+    http::StatusCode,
+    hyper::Body,
+    reply::{self, Response},
+    Filter, Rejection, Reply,
+};
+use xor_name::XorName;
+
+/// The maximum size of a file uploaded through the gateway.
+const MAX_UPLOAD_SIZE: u64 = 1024 * 1024 * 1024;
+/// The maximum size of a register entry.
+const MAX_ENTRY_SIZE: u64 = 1024;
+/// How much of a downloaded file is buffered ahead of the response body.
+const DOWNLOAD_BUFFER_SIZE: usize = 1024 * 1024;
+/// How much of a range of a file is fetched at a time.
+const RANGE_WINDOW_SIZE: u64 = 8 * 1024 * 1024;
+
+/// The state shared by the handlers.
+#[derive(Clone)]
+pub(crate) struct Gateway {
+    pub(crate) client: Client,
+    pub(crate) root_dir: PathBuf,
+    // The wallet is only spent from by one request at a time.
+    pub(crate) wallet_lock: Arc<Mutex<()>>,
+}
+
+impl Gateway {
+    pub(crate) fn new(client: Client, root_dir: PathBuf) -> Self {
+        Self {
+            client,
+            root_dir,
+            wallet_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Uploads the content read from `reader` as a public file, chunking and paying for it as it
+    /// is read, returning the address of its data map and what was paid for it.
+    /// The caller is expected to hold the wallet lock.
+    pub(crate) async fn upload_from_reader<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+    ) -> Result<(ChunkAddress, StoragePaymentResult), sn_client::Error> {
+        let files_api = FilesApi::new(self.client.clone(), self.root_dir.clone());
+        let (head_address, paid) = files_api.upload_from_reader(reader, true, None).await?;
+        info!("Uploaded file {head_address:?}, for {}", paid.storage_cost);
+        Ok((head_address, paid))
+    }
+
+    /// Returns a body streaming the content of a public file, or of the `(start, length)` range
+    /// of it, as it's fetched. The fetching starts before returning, so that a file which can't
+    /// be fetched is an error rather than an empty body; a later failure aborts the body.
+    pub(crate) async fn download_body(
+        &self,
+        address: ChunkAddress,
+        range: Option<(u64, u64)>,
+    ) -> Result<Body, sn_client::Error> {
+        let (mut writer, reader) = tokio::io::duplex(DOWNLOAD_BUFFER_SIZE);
+        let files_api = FilesApi::new(self.client.clone(), self.root_dir.clone());
+        let download = tokio::spawn(async move {
+            let mut download = FilesDownload::new(files_api);
+            let Some((mut position, length)) = range else {
+                return download
+                    .download_to_writer(address, None, &mut writer)
+                    .await;
+            };
+            let end = position.saturating_add(length);
+            while position < end {
+                let window = (end - position).min(RANGE_WINDOW_SIZE);
+                let bytes = download
+                    .download_from(address, position as usize, window as usize)
+                    .await?;
+                if bytes.is_empty() {
+                    break;
+                }
+                writer.write_all(&bytes).await?;
+                position += bytes.len() as u64;
+            }
+            Ok(())
+        });
+
+        let mut body = ReaderStream::new(reader);
+        let first = match body.next().await {
+            Some(first) => first,
+            None => {
+                download.await??;
+                return Ok(Body::empty());
+            }
+        };
+        let outcome = stream::once(async move {
+            match download.await {
+                Ok(Ok(())) => None,
+                Ok(Err(err)) => Some(Err(io::Error::new(io::ErrorKind::Other, err))),
+                Err(err) => Some(Err(io::Error::new(io::ErrorKind::Other, err))),
+            }
+        })
+        .filter_map(future::ready);
+        Ok(Body::wrap_stream(
+            stream::once(future::ready(first))
+                .chain(body)
+                .chain(outcome),
+        ))
+    }
+}
+
+/// Reads a request body, failing once more than `max_size` bytes were read.
+pub(crate) fn body_reader(
+    body: impl Stream<Item = Result<impl Buf, warp::Error>> + Send + Unpin,
+    max_size: u64,
+) -> impl AsyncRead + Send + Unpin {
+    let mut size = 0u64;
+    StreamReader::new(body.map(move |buf| {
+        let mut buf = buf.map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        size += buf.remaining() as u64;
+        if size > max_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The body is larger than {max_size} bytes"),
+            ));
+        }
+        Ok(buf.copy_to_bytes(buf.remaining()))
+    }))
+}
+
+/// An error returned to the caller, with its status code.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl ToString) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.to_string(),
+        }
+    }
+
+    fn internal(message: impl ToString) -> Self {
+        let message = message.to_string();
+        error!("Gateway request failed: {message}");
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message,
+        }
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+#[derive(Deserialize)]
+struct CreateRegisterRequest {
+    name: String,
+    /// Whether anyone can write to the register, not only the gateway.
+    #[serde(default)]
+    public: bool,
+}
+
+#[derive(Deserialize)]
+struct SendRequest {
+    to: String,
+    /// In nanos.
+    amount: u64,
+}
+
+#[derive(Serialize)]
+struct RegisterEntries {
+    address: String,
+    entries: Vec<String>,
+}
+
+/// Serves the gateway until the process is stopped.
+pub(crate) async fn run(gateway: Gateway, api_token: String, addr: SocketAddr) {
+    warp::serve(routes(gateway, api_token)).run(addr).await;
+    debug!("Server closed");
+}
+
+fn routes(
+    gateway: Gateway,
+    api_token: String,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone {
+    let with_gateway = warp::any().map(move || gateway.clone());
+
+    let upload_file = warp::post()
+        .and(warp::path!("files"))
+        .and(warp::body::content_length_limit(MAX_UPLOAD_SIZE))
+        .and(warp::body::stream())
+        .and(with_gateway.clone())
+        .then(|body, gateway| respond(upload_file(gateway, body)));
+    let get_file = warp::get()
+        .and(warp::path!("files" / String))
+        .and(with_gateway.clone())
+        .then(|address, gateway| respond(get_file(gateway, address)));
+    let create_register = warp::post()
+        .and(warp::path!("registers"))
+        .and(warp::body::json())
+        .and(with_gateway.clone())
+        .then(|request, gateway| respond(create_register(gateway, request)));
+    let read_register = warp::get()
+        .and(warp::path!("registers" / String))
+        .and(with_gateway.clone())
+        .then(|address, gateway| respond(read_register(gateway, address)));
+    let write_register = warp::post()
+        .and(warp::path!("registers" / String))
+        .and(warp::body::content_length_limit(MAX_ENTRY_SIZE))
+        .and(warp::body::bytes())
+        .and(with_gateway.clone())
+        .then(|address, entry, gateway| respond(write_register(gateway, address, entry)));
+    let balance = warp::get()
+        .and(warp::path!("wallet" / "balance"))
+        .and(with_gateway.clone())
+        .then(|gateway| respond(balance(gateway)));
+    let send_tokens = warp::post()
+        .and(warp::path!("wallet" / "send"))
+        .and(warp::body::json())
+        .and(with_gateway)
+        .then(|request, gateway| respond(send_tokens(gateway, request)));
+
+    authenticated(api_token)
+        .and(
+            upload_file
+                .or(get_file)
+                .unify()
+                .or(create_register)
+                .unify()
+                .or(read_register)
+                .unify()
+                .or(write_register)
+                .unify()
+                .or(balance)
+                .unify()
+                .or(send_tokens)
+                .unify(),
+        )
+        .recover(handle_rejection)
+        .unify()
+}
+
+/// Rejects the requests without the API token.
+fn authenticated(api_token: String) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let authorized = header
+                .as_deref()
+                .and_then(|header| header.strip_prefix("Bearer "))
+                .is_some_and(|token| tokens_match(token, &api_token));
+            async move {
+                if authorized {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+// Compares the tokens in constant time, not to leak how much of a guessed token is right.
+fn tokens_match(token: &str, api_token: &str) -> bool {
+    token.len() == api_token.len()
+        && token
+            .bytes()
+            .zip(api_token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+async fn handle_rejection(rejection: Rejection) -> std::result::Result<Response, Infallible> {
+    let (status, message) = if rejection.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "Missing or invalid API token")
+    } else if rejection.is_not_found() {
+        (StatusCode::NOT_FOUND, "Not found")
+    } else if rejection.find::<warp::reject::PayloadTooLarge>().is_some() {
+        (StatusCode::PAYLOAD_TOO_LARGE, "Payload too large")
+    } else if rejection.find::<warp::reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
+    } else {
+        (StatusCode::BAD_REQUEST, "Invalid request")
+    };
+    Ok(error_reply(status, message))
+}
+
+async fn respond<T: Reply>(result: impl std::future::Future<Output = ApiResult<T>>) -> Response {
+    match result.await {
+        Ok(reply) => reply.into_response(),
+        Err(err) => error_reply(err.status, &err.message),
+    }
+}
+
+fn error_reply(status: StatusCode, message: &str) -> Response {
+    reply::with_status(reply::json(&json!({ "error": message })), status).into_response()
+}
+
+async fn upload_file(
+    gateway: Gateway,
+    body: impl Stream<Item = Result<impl Buf, warp::Error>> + Send + Unpin,
+) -> ApiResult<impl Reply> {
+    let _wallet = gateway.wallet_lock.lock().await;
+    let (head_address, paid) = gateway
+        .upload_from_reader(body_reader(body, MAX_UPLOAD_SIZE))
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(reply::json(&json!({
+        "address": head_address.to_hex(),
+        "storage_cost": paid.storage_cost.as_nano(),
+        "royalty_fees": paid.royalty_fees.as_nano(),
+    })))
+}
+
+async fn get_file(gateway: Gateway, address: String) -> ApiResult<impl Reply> {
+    let address = parse_chunk_address(&address)?;
+    let body = gateway
+        .download_body(address, None)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(reply::with_header(
+        Response::new(body),
+        "content-type",
+        "application/octet-stream",
+    ))
+}
+
+async fn create_register(
+    gateway: Gateway,
+    request: CreateRegisterRequest,
+) -> ApiResult<impl Reply> {
+    let permissions = if request.public {
+        Permissions::new_anyone_can_write()
+    } else {
+        Permissions::default()
+    };
+
+    let _wallet = gateway.wallet_lock.lock().await;
+    let wallet = load_account_wallet_or_create_with_mnemonic(&gateway.root_dir, None)
+        .map_err(ApiError::internal)?;
+    let mut wallet_client = WalletClient::new(gateway.client.clone(), wallet);
+    let (register, storage_cost, royalty_fees) = gateway
+        .client
+        .create_and_pay_for_register(
+            XorName::from_content(request.name.as_bytes()),
+            &mut wallet_client,
+            true,
+            permissions,
+        )
+        .await
+        .map_err(ApiError::internal)?;
+
+    Ok(reply::with_status(
+        reply::json(&json!({
+            "address": register.address().to_hex(),
+            "storage_cost": storage_cost.as_nano(),
+            "royalty_fees": royalty_fees.as_nano(),
+        })),
+        StatusCode::CREATED,
+    ))
+}
+
+async fn read_register(gateway: Gateway, address: String) -> ApiResult<impl Reply> {
+    let address = RegisterAddress::from_hex(&address).map_err(ApiError::bad_request)?;
+    let register = gateway
+        .client
+        .get_register(address)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(reply::json(&RegisterEntries {
+        address: address.to_hex(),
+        entries: register
+            .read()
+            .into_iter()
+            .map(|(_, entry)| hex::encode(entry))
+            .collect(),
+    }))
+}
+
+async fn write_register(gateway: Gateway, address: String, entry: Bytes) -> ApiResult<impl Reply> {
+    let address = RegisterAddress::from_hex(&address).map_err(ApiError::bad_request)?;
+    let mut register = gateway
+        .client
+        .get_register(address)
+        .await
+        .map_err(ApiError::internal)?;
+    register
+        .write_merging_branches_online(&entry, true)
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(reply::json(&json!({ "address": address.to_hex() })))
+}
+
+#[allow(clippy::unused_async)]
+async fn balance(gateway: Gateway) -> ApiResult<impl Reply> {
+    let wallet = load_account_wallet_or_create_with_mnemonic(&gateway.root_dir, None)
+        .map_err(ApiError::internal)?;
+    Ok(reply::json(&json!({
+        "address": wallet.address().to_address(),
+        "balance": wallet.balance().as_nano(),
+    })))
+}
+
+async fn send_tokens(gateway: Gateway, request: SendRequest) -> ApiResult<impl Reply> {
+    let to = MainPubkey::from_str(&request.to).map_err(ApiError::bad_request)?;
+    if request.amount == 0 {
+        return Err(ApiError::bad_request("The amount can't be zero"));
+    }
+
+    let _wallet = gateway.wallet_lock.lock().await;
+    let wallet = load_account_wallet_or_create_with_mnemonic(&gateway.root_dir, None)
+        .map_err(ApiError::internal)?;
+    let cash_note = send(
+        wallet,
+        NanoTokens::from(request.amount),
+        to,
+        &gateway.client,
+        true,
+    )
+    .await
+    .map_err(ApiError::internal)?;
+    let transfer = Transfer::transfer_from_cash_note(&cash_note)
+        .and_then(|transfer| transfer.to_hex())
+        .map_err(ApiError::internal)?;
+    Ok(reply::json(&json!({ "transfer": transfer })))
+}
+
+fn parse_chunk_address(address: &str) -> ApiResult<ChunkAddress> {
+    let xorname = hex::decode(address)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| ApiError::bad_request(format!("Invalid address: {address}")))?;
+    Ok(ChunkAddress::new(XorName(xorname)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn requests_need_the_api_token() {
+        let filter = authenticated("secret".to_string()).map(|| "ok");
+
+        let response = warp::test::request()
+            .header("authorization", "Bearer secret")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for header in ["Bearer other", "Bearer secre", "secret"] {
+            let rejected = warp::test::request()
+                .header("authorization", header)
+                .filter(&filter)
+                .await;
+            assert!(rejected.is_err_and(|rejection| rejection.find::<Unauthorized>().is_some()));
+        }
+        assert!(warp::test::request().filter(&filter).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn the_body_is_read_up_to_its_max_size() {
+        use tokio::io::AsyncReadExt;
+
+        let body = || stream::iter([Ok::<_, warp::Error>(&b"1234"[..]), Ok(&b"5678"[..])]);
+
+        let mut read = vec![];
+        assert!(body_reader(body(), 8).read_to_end(&mut read).await.is_ok());
+        assert_eq!(read, b"12345678");
+
+        let result = body_reader(body(), 7).read_to_end(&mut vec![]).await;
+        assert!(result.is_err_and(|err| err.kind() == io::ErrorKind::InvalidData));
+    }
+}
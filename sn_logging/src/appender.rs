@@ -18,24 +18,44 @@ use std::{
     io,
     io::Write,
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
 
+/// When the log files are rotated, and which of the rotated ones are kept.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct LogRotation {
+    /// The size a log file is rotated at, in bytes.
+    pub(crate) max_file_size: Option<usize>,
+    /// How often a log file is rotated, whatever its size.
+    pub(crate) interval: Option<Duration>,
+    /// How long the rotated files are kept for, in place of a max number of files.
+    pub(crate) max_age: Option<Duration>,
+    /// The number of rotated files kept uncompressed.
+    pub(crate) max_uncompressed_files: Option<usize>,
+    /// The number of compressed files kept.
+    pub(crate) max_compressed_files: Option<usize>,
+}
+
 /// max_bytes:
 /// - the maximum size a log can grow to until it is rotated.
 ///
+/// interval:
+/// - how often the log is rotated, whatever its size.
+///
 /// uncompressed_files:
 /// - number of files to keep uncompressed.
 /// - should be lesser than `max_files` to enable compression of excess files.
 ///
-/// max_files:
-/// - maximum number of files to keep.
+/// file_limit:
+/// - the maximum number of files to keep, or how long to keep them for.
 /// - older files are deleted.
 pub(super) fn file_rotater(
     dir: &PathBuf,
     max_bytes: usize,
+    interval: Option<Duration>,
     uncompressed_files: usize,
-    max_files: usize,
+    file_limit: FileLimit,
 ) -> (NonBlocking, WorkerGuard) {
     let binary_name = env::current_exe()
         .map(|path| {
@@ -49,9 +69,10 @@ pub(super) fn file_rotater(
     let file_appender = FileRotateAppender::make_rotate_appender(
         dir,
         format!("{binary_name}.log"),
-        AppendTimestamp::default(FileLimit::MaxFiles(max_files)),
+        AppendTimestamp::default(file_limit),
         ContentLimit::BytesSurpassed(max_bytes),
         Compression::OnRotate(uncompressed_files),
+        interval,
     );
 
     // configure how tracing non-blocking works: https://tracing.rs/tracing_appender/non_blocking/struct.nonblockingbuilder#method.default
@@ -68,13 +89,15 @@ pub(super) fn file_rotater(
 /// `FileRotateAppender` is a `tracing_appender` with extra logrotate features:
 ///  - most recent logfile name re-used to support following (e.g. 'tail -f=logfile')
 ///  - numbered rotation (logfile.1, logfile.2 etc)
-///  - limit logfile by size, lines or time
+///  - limit logfile by size, lines or time, and by time on top of the size
 ///  - limit maximum number of logfiles
 ///  - optional compression of rotated logfiles
 //
 // The above functionality is provided using crate file_rotation
 pub(super) struct FileRotateAppender {
     writer: FileRotate<AppendTimestamp>,
+    interval: Option<Duration>,
+    rotated_at: Instant,
 }
 
 impl FileRotateAppender {
@@ -85,6 +108,7 @@ impl FileRotateAppender {
         file_limit: AppendTimestamp,
         max_log_size: ContentLimit,
         compression: Compression,
+        interval: Option<Duration>,
     ) -> Self {
         let log_directory = directory.as_ref();
         let log_filename_prefix = file_name_prefix.as_ref();
@@ -98,12 +122,23 @@ impl FileRotateAppender {
             None,
         );
 
-        Self { writer }
+        Self {
+            writer,
+            interval,
+            rotated_at: Instant::now(),
+        }
     }
 }
 
 impl Write for FileRotateAppender {
+    // Each write is a whole log line, so the file is rotated in between lines.
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(interval) = self.interval {
+            if self.rotated_at.elapsed() >= interval {
+                self.writer.rotate()?;
+                self.rotated_at = Instant::now();
+            }
+        }
         self.writer.write(buf)
     }
 
@@ -119,3 +154,31 @@ impl Debug for FileRotateAppender {
         f.debug_struct("FileRotateAppender").finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_are_rotated_at_the_interval_whatever_their_size() -> color_eyre::Result<()> {
+        let dir = env::temp_dir().join(format!("sn_logging_rotation_{}", std::process::id()));
+        let mut appender = FileRotateAppender::make_rotate_appender(
+            &dir,
+            "test.log",
+            AppendTimestamp::default(FileLimit::MaxFiles(10)),
+            ContentLimit::BytesSurpassed(1024 * 1024),
+            Compression::None,
+            Some(Duration::from_millis(100)),
+        );
+
+        appender.write_all(b"first\n")?;
+        std::thread::sleep(Duration::from_millis(150));
+        appender.write_all(b"second\n")?;
+        appender.flush()?;
+
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 2);
+        assert_eq!(std::fs::read_to_string(dir.join("test.log"))?, "second\n");
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -7,20 +7,25 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    appender,
+    appender::{self, LogRotation},
     error::{Error, Result},
     LogFormat, LogOutputDest,
 };
+use file_rotate::suffix::FileLimit;
+use serde_json::{Map, Value};
 use std::collections::BTreeMap;
 use tracing_appender::non_blocking::WorkerGuard;
-use tracing_core::{Event, Level, Subscriber};
+use tracing_core::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
 use tracing_subscriber::{
     filter::Targets,
     fmt::{
         self as tracing_fmt,
-        format::Writer,
+        format::{JsonFields, Writer},
         time::{FormatTime, SystemTime},
-        FmtContext, FormatEvent, FormatFields,
+        FmtContext, FormatEvent, FormatFields, FormattedFields, MakeWriter,
     },
     layer::Filter,
     registry::LookupSpan,
@@ -90,6 +95,121 @@ where
     }
 }
 
+/// The span fields the events are correlated by, e.g. the id of the message being handled, or the
+/// key of the record being stored.
+const CORRELATION_FIELDS: [&str; 2] = ["msg_id", "key"];
+
+/// Formats the events as flat JSON objects, one per line, for log shippers to ingest them as they
+/// are. The fields of the spans the event is in and of the event itself are top level keys, along
+/// with the `correlation_id` of the innermost span which has one.
+///
+/// The span fields are read as formatted by `JsonFields`.
+#[derive(Default)]
+pub(crate) struct JsonLogFormatter;
+
+impl<S, N> FormatEvent<S, N> for JsonLogFormatter
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        let mut object = Map::new();
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+        let _ = object.insert("timestamp".to_string(), timestamp.into());
+        let _ = object.insert(
+            "level".to_string(),
+            event.metadata().level().as_str().into(),
+        );
+        let _ = object.insert("target".to_string(), event.metadata().target().into());
+
+        let mut span_names = vec![];
+        let mut correlation_id = None;
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                span_names.push(span.name());
+                let extensions = span.extensions();
+                let Some(fields) = extensions.get::<FormattedFields<N>>() else {
+                    continue;
+                };
+                if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(fields) {
+                    for (name, value) in fields {
+                        if CORRELATION_FIELDS.contains(&name.as_str()) {
+                            correlation_id = Some(value.clone());
+                        }
+                        let _ = object.insert(name, value);
+                    }
+                }
+            }
+        }
+        if !span_names.is_empty() {
+            let _ = object.insert("span".to_string(), span_names.join("/").into());
+        }
+        if let Some(correlation_id) = correlation_id {
+            let _ = object.insert("correlation_id".to_string(), correlation_id);
+        }
+        event.record(&mut JsonVisitor(&mut object));
+
+        let line = serde_json::to_string(&object).map_err(|_| std::fmt::Error)?;
+        writeln!(writer, "{line}")
+    }
+}
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        let _ = self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        let _ = self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        let _ = self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        let _ = self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let _ = self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let _ = self
+            .0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+// The layer formatting the events in the format, to the writer.
+fn format_layer<W>(format: LogFormat, writer: W) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Json => tracing_fmt::layer()
+            .fmt_fields(JsonFields::new())
+            .event_format(JsonLogFormatter)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Default => tracing_fmt::layer()
+            .with_ansi(false)
+            .with_target(false)
+            .event_format(LogFormatter)
+            .with_writer(writer)
+            .boxed(),
+    }
+}
+
 /// The different Subscribers composed into a list of layers
 #[derive(Default)]
 pub(crate) struct TracingLayers {
@@ -103,8 +223,7 @@ impl TracingLayers {
         default_logging_targets: Vec<(String, Level)>,
         output_dest: &LogOutputDest,
         format: LogFormat,
-        max_uncompressed_log_files: Option<usize>,
-        max_compressed_log_files: Option<usize>,
+        rotation: LogRotation,
         print_updates_to_stdout: bool,
     ) -> Result<ReloadHandle> {
         let layer = match output_dest {
@@ -112,18 +231,9 @@ impl TracingLayers {
                 if print_updates_to_stdout {
                     println!("Logging to stdout");
                 }
-                tracing_fmt::layer()
-                    .with_ansi(false)
-                    .with_target(false)
-                    .event_format(LogFormatter)
-                    .boxed()
+                format_layer(format, std::io::stdout)
             }
-            LogOutputDest::Stderr => tracing_fmt::layer()
-                .with_ansi(false)
-                .with_target(false)
-                .event_format(LogFormatter)
-                .with_writer(std::io::stderr)
-                .boxed(),
+            LogOutputDest::Stderr => format_layer(format, std::io::stderr),
             LogOutputDest::Path(path) => {
                 std::fs::create_dir_all(path)?;
                 if print_updates_to_stdout {
@@ -131,35 +241,33 @@ impl TracingLayers {
                 }
 
                 // the number of normal files
-                let max_uncompressed_log_files =
-                    max_uncompressed_log_files.unwrap_or(MAX_UNCOMPRESSED_LOG_FILES);
+                let max_uncompressed_log_files = rotation
+                    .max_uncompressed_files
+                    .unwrap_or(MAX_UNCOMPRESSED_LOG_FILES);
                 // the total number of files; should be greater than uncompressed
-                let max_log_files = if let Some(max_compressed_log_files) = max_compressed_log_files
-                {
-                    max_compressed_log_files + max_uncompressed_log_files
-                } else {
-                    std::cmp::max(max_uncompressed_log_files, MAX_LOG_FILES)
+                let max_log_files =
+                    if let Some(max_compressed_log_files) = rotation.max_compressed_files {
+                        max_compressed_log_files + max_uncompressed_log_files
+                    } else {
+                        std::cmp::max(max_uncompressed_log_files, MAX_LOG_FILES)
+                    };
+                let file_limit = match rotation.max_age {
+                    Some(max_age) => FileLimit::Age(
+                        chrono::Duration::from_std(max_age)
+                            .unwrap_or(chrono::Duration::max_value()),
+                    ),
+                    None => FileLimit::MaxFiles(max_log_files),
                 };
                 let (file_rotation, worker_guard) = appender::file_rotater(
                     path,
-                    MAX_LOG_SIZE,
+                    rotation.max_file_size.unwrap_or(MAX_LOG_SIZE),
+                    rotation.interval,
                     max_uncompressed_log_files,
-                    max_log_files,
+                    file_limit,
                 );
                 self.log_appender_guard = Some(worker_guard);
 
-                match format {
-                    LogFormat::Json => tracing_fmt::layer()
-                        .json()
-                        .flatten_event(true)
-                        .with_writer(file_rotation)
-                        .boxed(),
-                    LogFormat::Default => tracing_fmt::layer()
-                        .with_ansi(false)
-                        .with_writer(file_rotation)
-                        .event_format(LogFormatter)
-                        .boxed(),
-                }
+                format_layer(format, file_rotation)
             }
         };
         let targets = match std::env::var("SN_LOG") {
@@ -328,3 +436,60 @@ fn get_log_level_from_str(log_level: &str) -> Result<Level> {
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|_| std::io::Error::other("poisoned"))?
+                .write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_events_are_flat_and_correlated_by_their_span() -> color_eyre::Result<()> {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber =
+            Registry::default().with(format_layer(LogFormat::Json, move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handle_request", msg_id = "ab12", peer = "some peer");
+            let _entered = span.enter();
+            tracing::info!(event = "PeerAddedToRoutingTable", count = 3, "Added a peer");
+        });
+
+        let logs = String::from_utf8(
+            buffer
+                .0
+                .lock()
+                .map_err(|_| color_eyre::eyre::eyre!("poisoned"))?
+                .clone(),
+        )?;
+        let line: Value = serde_json::from_str(logs.trim())?;
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["event"], "PeerAddedToRoutingTable");
+        assert_eq!(line["message"], "Added a peer");
+        assert_eq!(line["count"], 3);
+        assert_eq!(line["span"], "handle_request");
+        assert_eq!(line["peer"], "some peer");
+        assert_eq!(line["correlation_id"], "ab12");
+        Ok(())
+    }
+}
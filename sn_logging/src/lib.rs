@@ -13,9 +13,10 @@ mod layers;
 pub mod metrics;
 
 use crate::error::Result;
+use appender::LogRotation;
 use layers::TracingLayers;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 use tracing::info;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_core::dispatcher::DefaultGuard;
@@ -124,8 +125,7 @@ pub struct LogBuilder {
     default_logging_targets: Vec<(String, Level)>,
     output_dest: LogOutputDest,
     format: LogFormat,
-    max_uncompressed_log_files: Option<usize>,
-    max_compressed_log_files: Option<usize>,
+    rotation: LogRotation,
     /// Setting this would print the sn_logging related updates to stdout.
     print_updates_to_stdout: bool,
 }
@@ -140,8 +140,7 @@ impl LogBuilder {
             default_logging_targets,
             output_dest: LogOutputDest::Stderr,
             format: LogFormat::Default,
-            max_uncompressed_log_files: None,
-            max_compressed_log_files: None,
+            rotation: LogRotation::default(),
             print_updates_to_stdout: true,
         }
     }
@@ -152,18 +151,36 @@ impl LogBuilder {
     }
 
     /// Set the logging format
+    ///
+    /// In `LogFormat::Json`, each event is a JSON object on a line of its own, with the fields of
+    /// the spans it's in, and the `correlation_id` of the innermost of them which has one.
     pub fn format(&mut self, format: LogFormat) {
         self.format = format
     }
 
     /// The max number of uncompressed log files to store
     pub fn max_uncompressed_log_files(&mut self, files: usize) {
-        self.max_uncompressed_log_files = Some(files);
+        self.rotation.max_uncompressed_files = Some(files);
     }
 
     /// The max number of compressed files to store
     pub fn max_compressed_log_files(&mut self, files: usize) {
-        self.max_compressed_log_files = Some(files);
+        self.rotation.max_compressed_files = Some(files);
+    }
+
+    /// The size a log file is rotated at, in bytes, 20 MiB by default
+    pub fn max_log_file_size(&mut self, bytes: usize) {
+        self.rotation.max_file_size = Some(bytes);
+    }
+
+    /// Rotate the log file at this interval on top of its size, e.g. daily
+    pub fn log_rotation_interval(&mut self, interval: Duration) {
+        self.rotation.interval = Some(interval);
+    }
+
+    /// Keep the log files for this long, in place of the max number of files to store
+    pub fn max_log_file_age(&mut self, age: Duration) {
+        self.rotation.max_age = Some(age);
     }
 
     /// Setting this to false would prevent sn_logging from printing things to stdout.
@@ -182,8 +199,7 @@ impl LogBuilder {
             self.default_logging_targets.clone(),
             &self.output_dest,
             self.format,
-            self.max_uncompressed_log_files,
-            self.max_compressed_log_files,
+            self.rotation,
             self.print_updates_to_stdout,
         )?;
 
@@ -279,7 +295,13 @@ impl LogBuilder {
         let mut layers = TracingLayers::default();
 
         let _reload_handle = layers
-            .fmt_layer(vec![], &output_dest, LogFormat::Default, None, None, false)
+            .fmt_layer(
+                vec![],
+                &output_dest,
+                LogFormat::Default,
+                LogRotation::default(),
+                false,
+            )
             .expect("Failed to get TracingLayers");
         layers
     }
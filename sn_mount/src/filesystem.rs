@@ -0,0 +1,237 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bytes::Bytes;
+use fuser::{
+    FileAttr, FileType, Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry, Request,
+};
+use libc::{c_int, EINVAL, EIO, ENOENT, ENOTDIR};
+use nix::unistd::{getgid, getuid};
+use sn_client::{ContainerFs, Error, FsAttr, FsKind};
+use std::{
+    ffi::OsStr,
+    time::{Duration, UNIX_EPOCH},
+};
+use tokio::runtime::Handle;
+
+// The container can't change under the mount, so the kernel can cache the entries for a while.
+const TTL: Duration = Duration::from_secs(60);
+const BLOCK_SIZE: u32 = 512;
+
+/// Serves a `ContainerFs` through FUSE, blocking on the runtime to fetch the content of the files.
+pub(crate) struct MountedContainer {
+    fs: ContainerFs,
+    runtime: Handle,
+    uid: u32,
+    gid: u32,
+}
+
+impl MountedContainer {
+    pub(crate) fn new(fs: ContainerFs, runtime: Handle) -> Self {
+        let (uid, gid) = (getuid().as_raw(), getgid().as_raw());
+        Self {
+            fs,
+            runtime,
+            uid,
+            gid,
+        }
+    }
+
+    fn file_attr(&self, attr: &FsAttr) -> FileAttr {
+        let (kind, perm, nlink) = match attr.kind {
+            FsKind::File => (FileType::RegularFile, 0o444, 1),
+            FsKind::Directory => (FileType::Directory, 0o555, 2),
+            FsKind::Symlink => (FileType::Symlink, 0o777, 1),
+        };
+        // the write bits are dropped, as the mount is read-only.
+        let perm = attr.mode.map_or(perm, |mode| (mode & 0o555) as u16);
+        let modified = attr.modified.unwrap_or(UNIX_EPOCH);
+        FileAttr {
+            ino: attr.inode,
+            size: attr.size,
+            blocks: attr.size.div_ceil(u64::from(BLOCK_SIZE)),
+            atime: modified,
+            mtime: modified,
+            ctime: modified,
+            crtime: modified,
+            kind,
+            perm,
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        }
+    }
+
+    // Reads up to `size` bytes of the file from `offset`, failing with the errno to reply with.
+    fn read_bytes(&self, ino: u64, offset: i64, size: u32) -> Result<Bytes, c_int> {
+        let offset = u64::try_from(offset).map_err(|_| EINVAL)?;
+        self.runtime
+            .block_on(self.fs.read(ino, offset, size as usize))
+            .map_err(|err| {
+                error!("Failed to read {size} bytes of inode {ino} from {offset}: {err}");
+                errno(&err)
+            })
+    }
+}
+
+impl Filesystem for MountedContainer {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let Some(name) = name.to_str() else {
+            return reply.error(ENOENT);
+        };
+        match self.fs.lookup(parent, name) {
+            Ok(attr) => reply.entry(&TTL, &self.file_attr(&attr), 0),
+            Err(err) => reply.error(errno(&err)),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+        match self.fs.getattr(ino) {
+            Ok(attr) => reply.attr(&TTL, &self.file_attr(&attr)),
+            Err(err) => reply.error(errno(&err)),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        match self.fs.readlink(ino) {
+            Ok(target) => reply.data(target.as_bytes()),
+            Err(err) => reply.error(errno(&err)),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        match self.read_bytes(ino, offset, size) {
+            Ok(bytes) => reply.data(&bytes),
+            Err(errno) => reply.error(errno),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let entries = match self.fs.readdir(ino) {
+            Ok(entries) => entries,
+            Err(err) => return reply.error(errno(&err)),
+        };
+        // the kernel resolves `..` itself, whatever inode it is given.
+        let dots = [
+            (ino, FileType::Directory, ".".to_string()),
+            (ino, FileType::Directory, "..".to_string()),
+        ];
+        let entries = dots.into_iter().chain(
+            entries
+                .into_iter()
+                .map(|(name, attr)| (attr.inode, self.file_attr(&attr).kind, name)),
+        );
+        // the offset of an entry is the one to resume from after it.
+        for (index, (inode, kind, name)) in entries.enumerate().skip(offset as usize) {
+            if reply.add(inode, (index + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok()
+    }
+}
+
+fn errno(err: &Error) -> c_int {
+    match err {
+        Error::ContainerEntryNotFound(_) => ENOENT,
+        Error::InvalidContainerPath(_) => ENOTDIR,
+        _ => EIO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color_eyre::Result;
+    use sn_client::{
+        protocol::storage::Chunk, test_utils::get_unconnected_client, ChunkCache, FileEntry,
+        FilesApi, FilesContainer,
+    };
+    use std::fs;
+    use tempfile::tempdir;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn files_are_read_by_range() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let file_path = temp_dir.path().join("file");
+        fs::write(&file_path, &data)?;
+        let chunk_dir = temp_dir.path().join("chunks");
+        fs::create_dir_all(&chunk_dir)?;
+        let (head_address, _, size, chunks_paths) =
+            FilesApi::chunk_file(&file_path, &chunk_dir, true)?;
+
+        // the chunks are served from the cache, so nothing reaches the network.
+        let cache = ChunkCache::in_memory(u64::MAX);
+        for (_, path) in chunks_paths {
+            cache.insert(&Chunk::new(Bytes::from(fs::read(path)?)))?;
+        }
+        let runtime = Runtime::new()?;
+        let mut client = {
+            let _guard = runtime.enter();
+            get_unconnected_client(temp_dir.path().to_path_buf())?
+        };
+        client.set_chunk_cache(Some(cache));
+        let mut container = FilesContainer::new(client, temp_dir.path());
+        let file = FileEntry {
+            data_map: head_address,
+            size,
+            modified: None,
+            mode: None,
+        };
+        let _ = container.add_file("dir/file", file)?;
+        let mut fs = ContainerFs::new(container);
+        let dir = fs.lookup(ContainerFs::ROOT_INODE, "dir")?;
+        let file = fs.lookup(dir.inode, "file")?;
+        let mounted = MountedContainer::new(fs, runtime.handle().clone());
+
+        let attr = mounted.file_attr(&file);
+        assert_eq!((attr.kind, attr.size), (FileType::RegularFile, size));
+        // a range across chunks.
+        let offset = 1024 * 1024 - 10;
+        assert_eq!(
+            mounted.read_bytes(file.inode, offset as i64, 4096),
+            Ok(Bytes::copy_from_slice(&data[offset..offset + 4096]))
+        );
+        // the last read of the file is cut short at its end.
+        let offset = data.len() - 100;
+        assert_eq!(
+            mounted.read_bytes(file.inode, offset as i64, 4096),
+            Ok(Bytes::copy_from_slice(&data[offset..]))
+        );
+        assert_eq!(
+            mounted.read_bytes(file.inode, data.len() as i64, 4096),
+            Ok(Bytes::new())
+        );
+        assert_eq!(mounted.read_bytes(file.inode, -1, 4096), Err(EINVAL));
+        assert_eq!(mounted.read_bytes(dir.inode, 0, 4096), Err(ENOTDIR));
+        assert_eq!(mounted.read_bytes(1000, 0, 4096), Err(ENOENT));
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Mounts files containers as read-only FUSE filesystems, for the `safe-mount` binary and the
+//! `safe mount` command.
+
+#[macro_use]
+extern crate tracing;
+
+mod filesystem;
+
+use filesystem::MountedContainer;
+use fuser::MountOption;
+use sn_client::{
+    protocol::storage::RegisterAddress, ChunkCache, Client, ContainerFs, Error, FilesContainer,
+};
+use std::{io, path::Path};
+use tokio::runtime::Handle;
+
+/// Retrieves the files container at the address, to be mounted.
+///
+/// The chunks fetched by the client are then kept in `cache_dir`, up to `cache_size` bytes.
+pub async fn retrieve_container(
+    mut client: Client,
+    data_dir: &Path,
+    address: RegisterAddress,
+    cache_dir: &Path,
+    cache_size: u64,
+) -> Result<ContainerFs, Error> {
+    client.set_chunk_cache(Some(ChunkCache::open(cache_dir, cache_size)?));
+    let container = FilesContainer::retrieve(client, data_dir, address).await?;
+    Ok(ContainerFs::new(container))
+}
+
+/// Mounts the container on the mountpoint, returning once it is unmounted.
+///
+/// The FUSE callbacks are synchronous, so they block on the runtime of the handle to fetch the
+/// content of the files. This is thus to be called out of the runtime, e.g. with `spawn_blocking`.
+pub fn mount(fs: ContainerFs, runtime: Handle, mountpoint: &Path) -> io::Result<()> {
+    info!("Mounting the container on {mountpoint:?}");
+    let options = [
+        MountOption::RO,
+        MountOption::FSName("safe".to_string()),
+        MountOption::DefaultPermissions,
+    ];
+    fuser::mount2(MountedContainer::new(fs, runtime), mountpoint, &options)
+}
@@ -0,0 +1,118 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bls::SecretKey;
+use clap::Parser;
+use color_eyre::eyre::{eyre, Result};
+use sn_client::{protocol::storage::RegisterAddress, Client};
+use sn_logging::{Level, LogBuilder, LogOutputDest};
+use sn_peers_acquisition::PeersArgs;
+use std::path::PathBuf;
+
+/// Mounts a files container as a read-only filesystem.
+///
+/// The content of the files is fetched from the network as it is read, and the chunks are kept in
+/// a local cache. The filesystem is unmounted with `fusermount -u <mountpoint>`, or `umount` on
+/// macOS.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Opt {
+    /// The hex encoded address of the container.
+    address: String,
+
+    /// The directory to mount the container on.
+    mountpoint: PathBuf,
+
+    /// The directory the fetched chunks are cached in.
+    ///
+    /// Defaults to the `safe/mount/chunk_cache` directory in the platform specific data directory.
+    #[clap(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// The maximum size of the chunk cache, in bytes.
+    #[clap(long, default_value_t = 1024 * 1024 * 1024)]
+    cache_size: u64,
+
+    /// Specify the logging output destination.
+    ///
+    /// Valid values are "stdout", "data-dir", or a custom path.
+    #[clap(long, value_parser = parse_log_output, default_value = "data-dir")]
+    log_output_dest: LogOutputDest,
+
+    #[command(flatten)]
+    peers: PeersArgs,
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let opt = Opt::parse();
+
+    let logging_targets = vec![
+        ("safe_mount".to_string(), Level::TRACE),
+        ("sn_mount".to_string(), Level::TRACE),
+        ("sn_client".to_string(), Level::DEBUG),
+        ("sn_networking".to_string(), Level::INFO),
+        ("sn_peers_acquisition".to_string(), Level::TRACE),
+    ];
+    let mut log_builder = LogBuilder::new(logging_targets);
+    log_builder.output_dest(opt.log_output_dest);
+    let _log_handles = log_builder.initialize()?;
+
+    let address = RegisterAddress::from_hex(&opt.address)
+        .map_err(|err| eyre!("Invalid container address {}: {err}", opt.address))?;
+    let data_dir = get_mount_data_dir()?;
+    let cache_dir = opt
+        .cache_dir
+        .unwrap_or_else(|| data_dir.join("chunk_cache"));
+
+    // The FUSE callbacks are synchronous, so the runtime is handed over to block on the reads.
+    let runtime = tokio::runtime::Runtime::new()?;
+    let fs = runtime.block_on(async {
+        let bootstrap_peers = opt.peers.get_peers().await?;
+        let bootstrap_peers = if bootstrap_peers.is_empty() {
+            // empty vec is returned if `local-discovery` flag is provided
+            None
+        } else {
+            Some(bootstrap_peers)
+        };
+        println!("Connecting to the network...");
+        // Reading public data doesn't need a particular key.
+        let client = Client::new(SecretKey::random(), bootstrap_peers, None, None).await?;
+
+        println!("Retrieving the container at {address:?}...");
+        let fs =
+            sn_mount::retrieve_container(client, &data_dir, address, &cache_dir, opt.cache_size)
+                .await?;
+        Ok::<_, color_eyre::Report>(fs)
+    })?;
+
+    println!(
+        "Mounting the container on {}. Unmount it to exit.",
+        opt.mountpoint.display()
+    );
+    sn_mount::mount(fs, runtime.handle().clone(), &opt.mountpoint)?;
+    Ok(())
+}
+
+fn get_mount_data_dir() -> Result<PathBuf> {
+    let mut data_dir = dirs_next::data_dir().expect("Data directory is obtainable");
+    data_dir.push("safe");
+    data_dir.push("mount");
+    std::fs::create_dir_all(&data_dir)?;
+    Ok(data_dir)
+}
+
+fn parse_log_output(val: &str) -> Result<LogOutputDest> {
+    match val {
+        "stdout" => Ok(LogOutputDest::Stdout),
+        "data-dir" => Ok(LogOutputDest::Path(get_mount_data_dir()?.join("logs"))),
+        // The path should be a directory, but we can't use something like `is_dir` to check
+        // because the path doesn't need to exist. We can create it for the user.
+        value => Ok(LogOutputDest::Path(PathBuf::from(value))),
+    }
+}
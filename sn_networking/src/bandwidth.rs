@@ -0,0 +1,490 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use futures::{ready, AsyncRead, AsyncWrite};
+use libp2p::{
+    core::muxing::{StreamMuxer, StreamMuxerBox, StreamMuxerEvent, SubstreamBox},
+    PeerId,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    task::{Context, Poll},
+};
+
+/// The protocol the bytes of the substreams whose protocol couldn't be told are accounted to.
+pub const UNKNOWN_PROTOCOL: &str = "unknown";
+
+/// The most peers the bandwidth is kept for, past which the ones with the least traffic are
+/// dropped.
+const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// The most bytes a protocol negotiation is read out of, the substreams going past it being
+/// accounted to `UNKNOWN_PROTOCOL`.
+const MAX_NEGOTIATION_LEN: usize = 1024;
+
+const MULTISTREAM_HEADER: &[u8] = b"/multistream/1.0.0\n";
+const NOT_AVAILABLE: &[u8] = b"na\n";
+
+/// The bytes sent to and received from a peer, or over a protocol.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bandwidth {
+    /// The bytes sent.
+    pub sent: u64,
+    /// The bytes received.
+    pub received: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl Counters {
+    fn add(&self, bandwidth: Bandwidth) {
+        let _ = self.sent.fetch_add(bandwidth.sent, Ordering::Relaxed);
+        let _ = self
+            .received
+            .fetch_add(bandwidth.received, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Bandwidth {
+        Bandwidth {
+            sent: self.sent.load(Ordering::Relaxed),
+            received: self.received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tracked {
+    peers: HashMap<PeerId, HashMap<String, Arc<Counters>>>,
+    // Kept apart from the peers, for the totals not to go down as the peers are dropped.
+    protocols: HashMap<String, Arc<Counters>>,
+}
+
+/// Accounts the bytes sent and received over the connections, per peer and per protocol, for the
+/// asymmetric or abusive peers to be told, and the replication volumes checked.
+///
+/// The muxer of each connection is wrapped, its substreams counting the bytes going through
+/// them. The protocol of a substream is read out of its multistream-select negotiation, as the
+/// one its listener agreed on.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BandwidthAccounting(Arc<Mutex<Tracked>>);
+
+impl BandwidthAccounting {
+    /// Wraps the muxer of a connection to the peer, for the bytes of its substreams to be
+    /// accounted to the peer.
+    pub(crate) fn wrap(&self, peer_id: PeerId, muxer: StreamMuxerBox) -> StreamMuxerBox {
+        StreamMuxerBox::new(CountingMuxer {
+            inner: muxer,
+            peer_id,
+            accounting: self.clone(),
+        })
+    }
+
+    /// The bytes sent to and received from each of the peers, per protocol.
+    pub(crate) fn per_peer(&self) -> BTreeMap<PeerId, BTreeMap<String, Bandwidth>> {
+        self.lock()
+            .peers
+            .iter()
+            .map(|(peer_id, protocols)| {
+                let protocols = protocols
+                    .iter()
+                    .map(|(protocol, counters)| (protocol.clone(), counters.get()))
+                    .collect();
+                (*peer_id, protocols)
+            })
+            .collect()
+    }
+
+    /// The bytes sent and received over each of the protocols, all the peers together.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn per_protocol(&self) -> BTreeMap<String, Bandwidth> {
+        self.lock()
+            .protocols
+            .iter()
+            .map(|(protocol, counters)| (protocol.clone(), counters.get()))
+            .collect()
+    }
+
+    fn counters(&self, peer_id: PeerId, protocol: &str) -> StreamCounters {
+        let mut tracked = self.lock();
+        if !tracked.peers.contains_key(&peer_id) && tracked.peers.len() >= MAX_TRACKED_PEERS {
+            let least_used = tracked
+                .peers
+                .iter()
+                .min_by_key(|(_, protocols)| {
+                    protocols
+                        .values()
+                        .map(|counters| {
+                            let bandwidth = counters.get();
+                            bandwidth.sent + bandwidth.received
+                        })
+                        .sum::<u64>()
+                })
+                .map(|(peer_id, _)| *peer_id);
+            if let Some(least_used) = least_used {
+                let _ = tracked.peers.remove(&least_used);
+            }
+        }
+        let peer = Arc::clone(
+            tracked
+                .peers
+                .entry(peer_id)
+                .or_default()
+                .entry(protocol.to_string())
+                .or_default(),
+        );
+        let protocol = Arc::clone(tracked.protocols.entry(protocol.to_string()).or_default());
+        StreamCounters { peer, protocol }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Tracked> {
+        match self.0.lock() {
+            Ok(tracked) => tracked,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+struct CountingMuxer {
+    inner: StreamMuxerBox,
+    peer_id: PeerId,
+    accounting: BandwidthAccounting,
+}
+
+impl CountingMuxer {
+    fn substream(&self, inner: SubstreamBox, inbound: bool) -> CountingStream {
+        CountingStream {
+            inner,
+            peer_id: self.peer_id,
+            accounting: self.accounting.clone(),
+            negotiation: Some(Negotiation::new(inbound)),
+            pending: Bandwidth::default(),
+            counters: None,
+        }
+    }
+}
+
+impl StreamMuxer for CountingMuxer {
+    type Substream = CountingStream;
+    type Error = io::Error;
+
+    fn poll_inbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let inner = ready!(Pin::new(&mut this.inner).poll_inbound(cx))?;
+        Poll::Ready(Ok(this.substream(inner, true)))
+    }
+
+    fn poll_outbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let inner = ready!(Pin::new(&mut this.inner).poll_outbound(cx))?;
+        Poll::Ready(Ok(this.substream(inner, false)))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll(cx)
+    }
+}
+
+struct StreamCounters {
+    peer: Arc<Counters>,
+    protocol: Arc<Counters>,
+}
+
+struct CountingStream {
+    inner: SubstreamBox,
+    peer_id: PeerId,
+    accounting: BandwidthAccounting,
+    negotiation: Option<Negotiation>,
+    // The bytes sent and received before the protocol is agreed on.
+    pending: Bandwidth,
+    counters: Option<StreamCounters>,
+}
+
+impl CountingStream {
+    fn count(&mut self, sent: bool, bytes: &[u8]) {
+        let bandwidth = if sent {
+            Bandwidth {
+                sent: bytes.len() as u64,
+                received: 0,
+            }
+        } else {
+            Bandwidth {
+                sent: 0,
+                received: bytes.len() as u64,
+            }
+        };
+        if let Some(counters) = &self.counters {
+            counters.peer.add(bandwidth);
+            counters.protocol.add(bandwidth);
+            return;
+        }
+        self.pending.sent += bandwidth.sent;
+        self.pending.received += bandwidth.received;
+        if let Some(protocol) = self
+            .negotiation
+            .as_mut()
+            .and_then(|negotiation| negotiation.feed(sent, bytes))
+        {
+            self.agreed_on(&protocol);
+        }
+    }
+
+    fn agreed_on(&mut self, protocol: &str) {
+        let counters = self.accounting.counters(self.peer_id, protocol);
+        counters.peer.add(self.pending);
+        counters.protocol.add(self.pending);
+        self.pending = Bandwidth::default();
+        self.negotiation = None;
+        self.counters = Some(counters);
+    }
+}
+
+impl Drop for CountingStream {
+    fn drop(&mut self) {
+        if self.counters.is_none() && self.pending != Bandwidth::default() {
+            self.agreed_on(UNKNOWN_PROTOCOL);
+        }
+    }
+}
+
+impl AsyncRead for CountingStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let read = ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.count(false, &buf[..read]);
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl AsyncWrite for CountingStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let written = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.count(true, &buf[..written]);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+/// The multistream-select negotiation of a substream, which the protocol it's agreed on is read
+/// out of.
+#[derive(Debug)]
+struct Negotiation {
+    /// Whether the substream was opened by the peer, i.e. the node is its listener.
+    inbound: bool,
+    listener: Messages,
+    dialer: Messages,
+    proposals: Vec<Vec<u8>>,
+}
+
+impl Negotiation {
+    fn new(inbound: bool) -> Self {
+        Self {
+            inbound,
+            listener: Messages::default(),
+            dialer: Messages::default(),
+            proposals: Vec::new(),
+        }
+    }
+
+    /// Reads the bytes sent or received, returning the protocol once it's agreed on.
+    fn feed(&mut self, sent: bool, bytes: &[u8]) -> Option<String> {
+        if sent != self.inbound {
+            // Only the protocols the node proposed are kept, for a peer not to make up its own.
+            if !self.inbound {
+                if let Some(proposals) = self.dialer.feed(bytes) {
+                    self.proposals.extend(proposals);
+                }
+            }
+            return None;
+        }
+
+        let Some(messages) = self.listener.feed(bytes) else {
+            return Some(UNKNOWN_PROTOCOL.to_string());
+        };
+        let agreed = messages
+            .into_iter()
+            .find(|msg| msg != MULTISTREAM_HEADER && msg != NOT_AVAILABLE)?;
+        // The node only agrees on its own protocols, and the peer is to agree on a proposed one.
+        if self.inbound || self.proposals.contains(&agreed) {
+            let protocol = agreed.strip_suffix(b"\n").unwrap_or(&agreed);
+            Some(String::from_utf8_lossy(protocol).into_owned())
+        } else {
+            Some(UNKNOWN_PROTOCOL.to_string())
+        }
+    }
+}
+
+/// The length prefixed messages sent one way on a substream while its protocol is negotiated.
+#[derive(Debug, Default)]
+struct Messages {
+    buf: Vec<u8>,
+    parsed: usize,
+}
+
+impl Messages {
+    /// Reads the bytes, returning the messages they complete, or `None` once past the most bytes
+    /// a negotiation takes.
+    fn feed(&mut self, bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let room = MAX_NEGOTIATION_LEN.saturating_sub(self.buf.len());
+        self.buf.extend_from_slice(&bytes[..bytes.len().min(room)]);
+
+        let mut messages = Vec::new();
+        while let Some((len, prefix_len)) = decode_varint(&self.buf[self.parsed..]) {
+            let start = self.parsed + prefix_len;
+            let Some(msg) = self.buf.get(start..start + len) else {
+                break;
+            };
+            messages.push(msg.to_vec());
+            self.parsed = start + len;
+        }
+        if messages.is_empty() && self.buf.len() >= MAX_NEGOTIATION_LEN {
+            return None;
+        }
+        Some(messages)
+    }
+}
+
+// Decodes the unsigned varint at the start of the bytes, returning it and its length.
+fn decode_varint(bytes: &[u8]) -> Option<(usize, usize)> {
+    let mut value = 0;
+    for (i, byte) in bytes.iter().take(3).enumerate() {
+        value |= usize::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(msg: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![msg.len() as u8];
+        bytes.extend_from_slice(msg);
+        bytes
+    }
+
+    #[test]
+    fn substreams_are_accounted_to_the_protocol_agreed_on() {
+        let kad = b"/safe/kad/1.0.0\n";
+        let meshsub = b"/meshsub/1.1.0\n";
+
+        // The node listens, answering the proposal of the peer, then data follows.
+        let mut inbound = Negotiation::new(true);
+        assert_eq!(inbound.feed(false, &message(MULTISTREAM_HEADER)), None);
+        assert_eq!(inbound.feed(false, &message(kad)), None);
+        assert_eq!(inbound.feed(true, &message(MULTISTREAM_HEADER)), None);
+        assert_eq!(
+            inbound.feed(true, &[message(kad), b"data".to_vec()].concat()),
+            Some("/safe/kad/1.0.0".to_string())
+        );
+
+        // The node dials, the peer turning down its first proposal.
+        let mut outbound = Negotiation::new(false);
+        let proposals = [
+            message(MULTISTREAM_HEADER),
+            message(b"/meshsub/1.2.0\n"),
+            message(meshsub),
+        ]
+        .concat();
+        assert_eq!(outbound.feed(true, &proposals), None);
+        assert_eq!(
+            outbound.feed(
+                false,
+                &[message(MULTISTREAM_HEADER), message(NOT_AVAILABLE)].concat()
+            ),
+            None
+        );
+        assert_eq!(
+            outbound.feed(false, &message(meshsub)),
+            Some("/meshsub/1.1.0".to_string())
+        );
+
+        // A peer can't make a protocol up, nor go on negotiating forever.
+        let mut outbound = Negotiation::new(false);
+        assert_eq!(outbound.feed(true, &message(kad)), None);
+        assert_eq!(
+            outbound.feed(false, &message(b"/made/up\n")),
+            Some(UNKNOWN_PROTOCOL.to_string())
+        );
+        let mut inbound = Negotiation::new(true);
+        assert_eq!(
+            inbound.feed(true, &[0xff; MAX_NEGOTIATION_LEN]),
+            Some(UNKNOWN_PROTOCOL.to_string())
+        );
+    }
+
+    #[test]
+    fn the_least_used_peers_are_dropped_past_the_most_tracked() {
+        let accounting = BandwidthAccounting::default();
+        let busy = PeerId::random();
+        accounting
+            .counters(busy, "/safe/kad/1.0.0")
+            .peer
+            .add(Bandwidth {
+                sent: 10,
+                received: 20,
+            });
+        for _ in 1..MAX_TRACKED_PEERS {
+            let _ = accounting.counters(PeerId::random(), "/safe/kad/1.0.0");
+        }
+        let newcomer = PeerId::random();
+        let _ = accounting.counters(newcomer, "/safe/kad/1.0.0");
+
+        let per_peer = accounting.per_peer();
+        assert_eq!(per_peer.len(), MAX_TRACKED_PEERS);
+        assert!(per_peer.contains_key(&newcomer));
+        assert_eq!(
+            per_peer[&busy]["/safe/kad/1.0.0"],
+            Bandwidth {
+                sent: 10,
+                received: 20
+            }
+        );
+    }
+}
@@ -28,7 +28,7 @@ const LAST_PEER_ADDED_TIME_LIMIT: Duration = Duration::from_secs(180);
 const LAST_BOOTSTRAP_TRIGGERED_TIME_LIMIT: Duration = Duration::from_secs(30);
 
 /// The bootstrap interval to use if we haven't added any new peers in a while.
-const NO_PEER_ADDED_SLOWDOWN_INTERVAL_MAX_S: u64 = 600;
+pub(crate) const NO_PEER_ADDED_SLOWDOWN_INTERVAL_MAX: Duration = Duration::from_secs(600);
 
 impl SwarmDriver {
     /// This functions triggers network discovery based on when the last peer was added to the RT and the number of
@@ -72,20 +72,31 @@ impl SwarmDriver {
 
 /// Tracks and helps with the continuous kad::bootstrapping process
 pub(crate) struct ContinuousBootstrap {
+    /// The interval bootstrapping starts at, and is stepped up by.
+    interval: Duration,
+    /// The most the interval is slowed down to.
+    max_interval: Duration,
     initial_bootstrap_done: bool,
     last_peer_added_instant: Instant,
     last_bootstrap_triggered: Option<Instant>,
 }
 
 impl ContinuousBootstrap {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(interval: Duration, max_interval: Duration) -> Self {
         Self {
+            interval,
+            max_interval,
             initial_bootstrap_done: false,
             last_peer_added_instant: Instant::now(),
             last_bootstrap_triggered: None,
         }
     }
 
+    /// The interval bootstrapping starts at.
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
     /// The Kademlia Bootstrap request has been sent successfully.
     pub(crate) fn initiated(&mut self) {
         self.last_bootstrap_triggered = Some(Instant::now());
@@ -125,11 +136,8 @@ impl ContinuousBootstrap {
         if self.last_peer_added_instant.elapsed() > LAST_PEER_ADDED_TIME_LIMIT && peers_in_rt != 0 {
             // To avoid a heart beat like cpu usage due to the 1K candidates generation,
             // randomize the interval within certain range
-            let no_peer_added_slowdown_interval: u64 = OsRng.gen_range(
-                NO_PEER_ADDED_SLOWDOWN_INTERVAL_MAX_S / 2..NO_PEER_ADDED_SLOWDOWN_INTERVAL_MAX_S,
-            );
             let no_peer_added_slowdown_interval_duration =
-                Duration::from_secs(no_peer_added_slowdown_interval);
+                OsRng.gen_range(self.max_interval / 2..=self.max_interval);
             info!(
                     "It has been {LAST_PEER_ADDED_TIME_LIMIT:?} since we last added a peer to RT. Slowing down the continuous bootstrapping process. Old interval: {current_interval:?}, New interval: {no_peer_added_slowdown_interval_duration:?}"
                 );
@@ -139,10 +147,10 @@ impl ContinuousBootstrap {
             return (should_bootstrap, Some(new_interval));
         }
 
-        // increment bootstrap_interval in steps of BOOTSTRAP_INTERVAL every BOOTSTRAP_CONNECTED_PEERS_STEP
+        // increment bootstrap_interval in steps of the initial interval every BOOTSTRAP_CONNECTED_PEERS_STEP
         let step = peers_in_rt / BOOTSTRAP_CONNECTED_PEERS_STEP;
         let step = std::cmp::max(1, step);
-        let new_interval = BOOTSTRAP_INTERVAL * step;
+        let new_interval = self.interval * step;
         let new_interval = if new_interval > current_interval {
             info!("More peers have been added to our RT!. Slowing down the continuous bootstrapping process. Old interval: {current_interval:?}, New interval: {new_interval:?}");
             let mut interval = interval(new_interval);
@@ -11,9 +11,13 @@ use crate::{
     error::{NetworkError, Result},
     event::TerminateNodeReason,
     log_markers::Marker,
-    multiaddr_pop_p2p, GetRecordCfg, GetRecordError, MsgResponder, NetworkEvent, CLOSE_GROUP_SIZE,
-    REPLICATION_PEERS_COUNT,
+    multiaddr_pop_p2p,
+    peer_access::PeerAccess,
+    peer_scores::SHUN_THRESHOLD,
+    record_store::DiskUsage,
+    GetRecordCfg, GetRecordError, MsgResponder, NetworkEvent, CLOSE_GROUP_SIZE,
 };
+use bytes::Bytes;
 use libp2p::{
     kad::{
         store::{Error as StoreError, RecordStore},
@@ -22,7 +26,7 @@ use libp2p::{
     Multiaddr, PeerId,
 };
 use sn_protocol::{
-    messages::{Cmd, Request, Response},
+    messages::{Cmd, MsgEnvelope, MsgId, Request, Response},
     storage::{RecordHeader, RecordKind, RecordType},
     NetworkAddress, PrettyPrintRecordKey,
 };
@@ -30,7 +34,7 @@ use sn_transfers::{NanoTokens, PaymentQuote, QuotingMetrics};
 use std::{
     collections::{BTreeMap, HashMap},
     fmt::Debug,
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 use tokio::sync::oneshot;
 use xor_name::XorName;
@@ -54,6 +58,12 @@ pub enum NodeIssue {
     BadQuoting,
     /// Peer failed to pass the chunk proof verification
     FailedChunkProofCheck,
+    /// Returned a copy of a record which differs from the one returned by the other holders
+    DivergentRecord,
+    /// Served a record which failed the validation
+    InvalidRecord,
+    /// Failed to prove it still holds a record it is responsible for
+    FailedStorageChallenge,
 }
 
 /// Commands to send to the Swarm
@@ -93,8 +103,26 @@ pub enum LocalSwarmCmd {
         key: RecordKey,
         sender: oneshot::Sender<(NanoTokens, QuotingMetrics)>,
     },
-    /// Notify the node received a payment.
-    PaymentReceived,
+    /// Notify the node received a payment for a record.
+    PaymentReceived {
+        key: RecordKey,
+    },
+    /// Get the disk taken by the records of the node.
+    GetDiskUsage {
+        sender: oneshot::Sender<DiskUsage>,
+    },
+    /// Ask the peers closest to each of the records of the node to fetch it, before the node leaves
+    /// the network. The number of records handed over is sent back.
+    HandoverRecords {
+        sender: oneshot::Sender<usize>,
+    },
+    /// Notify the closest peers that the node is leaving the network.
+    AnnounceDeparture,
+    /// Notify the closest peers that the node is moving to a new key.
+    AnnounceKeyRotation {
+        new_public_key: Vec<u8>,
+        signature: Vec<u8>,
+    },
     /// Put record to the local RecordStore
     PutLocalRecord {
         record: Record,
@@ -104,6 +132,10 @@ pub enum LocalSwarmCmd {
     RemoveFailedLocalRecord {
         key: RecordKey,
     },
+    /// Remove a corrupted record from the RecordStore, keeping its bytes aside
+    QuarantineLocalRecord {
+        key: RecordKey,
+    },
     /// Add a local record to the RecordStore's HashSet of stored records
     /// This should be done after the record has been stored to disk
     AddLocalRecordAsStored {
@@ -187,6 +219,23 @@ pub enum NetworkSwarmCmd {
         sender: oneshot::Sender<Result<()>>,
         quorum: Quorum,
     },
+
+    /// Subscribe to a pub/sub topic
+    SubscribeToTopic {
+        topic: String,
+        sender: oneshot::Sender<Result<()>>,
+    },
+    /// Unsubscribe from a pub/sub topic
+    UnsubscribeFromTopic {
+        topic: String,
+        sender: oneshot::Sender<Result<()>>,
+    },
+    /// Publish a message on a pub/sub topic
+    PublishOnTopic {
+        topic: String,
+        msg: Bytes,
+        sender: oneshot::Sender<Result<()>>,
+    },
 }
 
 /// Debug impl for LocalSwarmCmd to avoid printing full Record, instead only RecodKey
@@ -201,6 +250,13 @@ impl Debug for LocalSwarmCmd {
                     PrettyPrintRecordKey::from(&record.key)
                 )
             }
+            LocalSwarmCmd::QuarantineLocalRecord { key } => {
+                write!(
+                    f,
+                    "LocalSwarmCmd::QuarantineLocalRecord {{ key: {:?} }}",
+                    PrettyPrintRecordKey::from(key)
+                )
+            }
             LocalSwarmCmd::RemoveFailedLocalRecord { key } => {
                 write!(
                     f,
@@ -228,8 +284,24 @@ impl Debug for LocalSwarmCmd {
             LocalSwarmCmd::GetLocalStoreCost { .. } => {
                 write!(f, "LocalSwarmCmd::GetLocalStoreCost")
             }
-            LocalSwarmCmd::PaymentReceived => {
-                write!(f, "LocalSwarmCmd::PaymentReceived")
+            LocalSwarmCmd::PaymentReceived { key } => {
+                write!(
+                    f,
+                    "LocalSwarmCmd::PaymentReceived {{ key: {:?} }}",
+                    PrettyPrintRecordKey::from(key)
+                )
+            }
+            LocalSwarmCmd::GetDiskUsage { .. } => {
+                write!(f, "LocalSwarmCmd::GetDiskUsage")
+            }
+            LocalSwarmCmd::HandoverRecords { .. } => {
+                write!(f, "LocalSwarmCmd::HandoverRecords")
+            }
+            LocalSwarmCmd::AnnounceDeparture => {
+                write!(f, "LocalSwarmCmd::AnnounceDeparture")
+            }
+            LocalSwarmCmd::AnnounceKeyRotation { .. } => {
+                write!(f, "LocalSwarmCmd::AnnounceKeyRotation")
             }
             LocalSwarmCmd::GetLocalRecord { key, .. } => {
                 write!(
@@ -325,6 +397,25 @@ impl Debug for NetworkSwarmCmd {
             NetworkSwarmCmd::SendResponse { resp, .. } => {
                 write!(f, "NetworkSwarmCmd::SendResponse resp: {resp:?}")
             }
+            NetworkSwarmCmd::SubscribeToTopic { topic, .. } => {
+                write!(
+                    f,
+                    "NetworkSwarmCmd::SubscribeToTopic {{ topic: {topic:?} }}"
+                )
+            }
+            NetworkSwarmCmd::UnsubscribeFromTopic { topic, .. } => {
+                write!(
+                    f,
+                    "NetworkSwarmCmd::UnsubscribeFromTopic {{ topic: {topic:?} }}"
+                )
+            }
+            NetworkSwarmCmd::PublishOnTopic { topic, msg, .. } => {
+                write!(
+                    f,
+                    "NetworkSwarmCmd::PublishOnTopic {{ topic: {topic:?}, msg: {} bytes }}",
+                    msg.len()
+                )
+            }
             NetworkSwarmCmd::SendRequest { req, peer, .. } => {
                 write!(
                     f,
@@ -464,6 +555,18 @@ impl SwarmDriver {
                     Err(e) => sender.send(Err(e.into())),
                 };
             }
+            NetworkSwarmCmd::SubscribeToTopic { topic, sender } => {
+                cmd_string = "SubscribeToTopic";
+                let _ = sender.send(self.subscribe_to_topic(&topic));
+            }
+            NetworkSwarmCmd::UnsubscribeFromTopic { topic, sender } => {
+                cmd_string = "UnsubscribeFromTopic";
+                let _ = sender.send(self.unsubscribe_from_topic(&topic));
+            }
+            NetworkSwarmCmd::PublishOnTopic { topic, msg, sender } => {
+                cmd_string = "PublishOnTopic";
+                let _ = sender.send(self.publish_on_topic(&topic, msg));
+            }
             NetworkSwarmCmd::GetClosestPeersToAddressFromNetwork { key, sender } => {
                 cmd_string = "GetClosestPeersToAddressFromNetwork";
                 let query_id = self
@@ -485,10 +588,12 @@ impl SwarmDriver {
                 // If `self` is the recipient, forward the request directly to our upper layer to
                 // be handled.
                 // `self` then handles the request and sends a response back again to itself.
+                let msg_id = MsgId::random();
                 if peer == *self.swarm.local_peer_id() {
                     trace!("Sending query request to self");
                     if let Request::Query(query) = req {
                         self.send_event(NetworkEvent::QueryRequestReceived {
+                            msg_id,
                             query,
                             channel: MsgResponder::FromSelf(sender),
                         });
@@ -498,13 +603,16 @@ impl SwarmDriver {
                         trace!("Replicate cmd to self received, ignoring");
                     }
                 } else {
+                    let span = info_span!("send_request", %msg_id, %peer);
+                    let _entered = span.enter();
                     let request_id = self
                         .swarm
                         .behaviour_mut()
                         .request_response
-                        .send_request(&peer, req);
+                        .send_request(&peer, MsgEnvelope { msg_id, msg: req });
                     trace!("Sending request {request_id:?} to peer {peer:?}");
                     let _ = self.pending_requests.insert(request_id, sender);
+                    self.live_connected_peers.mark_used(&peer);
 
                     trace!("Pending Requests now: {:?}", self.pending_requests.len());
                 }
@@ -528,12 +636,14 @@ impl SwarmDriver {
                             }
                         }
                     }
-                    MsgResponder::FromPeer(channel) => {
+                    MsgResponder::FromPeer(channel, msg_id) => {
                         self.swarm
                             .behaviour_mut()
                             .request_response
-                            .send_response(channel, resp)
-                            .map_err(NetworkError::OutgoingResponseDropped)?;
+                            .send_response(channel, MsgEnvelope { msg_id, msg: resp })
+                            .map_err(|envelope| {
+                                NetworkError::OutgoingResponseDropped(envelope.msg)
+                            })?;
                     }
                 }
             }
@@ -567,13 +677,42 @@ impl SwarmDriver {
 
                 let _res = sender.send((cost, quoting_metrics));
             }
-            LocalSwarmCmd::PaymentReceived => {
+            LocalSwarmCmd::PaymentReceived { key } => {
                 cmd_string = "PaymentReceived";
                 self.swarm
                     .behaviour_mut()
                     .kademlia
                     .store_mut()
-                    .payment_received();
+                    .payment_received(key);
+            }
+            LocalSwarmCmd::GetDiskUsage { sender } => {
+                cmd_string = "GetDiskUsage";
+                let usage = self.swarm.behaviour_mut().kademlia.store_mut().disk_usage();
+                let _ = sender.send(usage);
+            }
+            LocalSwarmCmd::HandoverRecords { sender } => {
+                cmd_string = "HandoverRecords";
+                let handed_over = self.handover_records();
+                let _ = sender.send(handed_over);
+            }
+            LocalSwarmCmd::AnnounceDeparture => {
+                cmd_string = "AnnounceDeparture";
+                let request = Request::Cmd(Cmd::Departure {
+                    peer: NetworkAddress::from_peer(self.self_peer_id),
+                });
+                self.send_to_closest_peers(request);
+            }
+            LocalSwarmCmd::AnnounceKeyRotation {
+                new_public_key,
+                signature,
+            } => {
+                cmd_string = "AnnounceKeyRotation";
+                let request = Request::Cmd(Cmd::KeyRotation {
+                    peer: NetworkAddress::from_peer(self.self_peer_id),
+                    new_public_key,
+                    signature,
+                });
+                self.send_to_closest_peers(request);
             }
             LocalSwarmCmd::GetLocalRecord { key, sender } => {
                 cmd_string = "GetLocalRecord";
@@ -591,16 +730,26 @@ impl SwarmDriver {
                 cmd_string = "PutLocalRecord";
                 let key = record.key.clone();
                 let record_key = PrettyPrintRecordKey::from(&key);
+                let span = info_span!("store_record", key = %record_key);
+                let _entered = span.enter();
 
                 let record_type = match RecordHeader::from_record(&record) {
                     Ok(record_header) => {
                         match record_header.kind {
                             RecordKind::Chunk => RecordType::Chunk,
-                            RecordKind::Spend | RecordKind::Register => {
+                            RecordKind::Spend
+                            | RecordKind::Register
+                            | RecordKind::Pointer
+                            | RecordKind::Scratchpad
+                            | RecordKind::ExpiringData => {
                                 let content_hash = XorName::from_content(&record.value);
                                 RecordType::NonChunk(content_hash)
                             }
-                            RecordKind::ChunkWithPayment | RecordKind::RegisterWithPayment => {
+                            RecordKind::ChunkWithPayment
+                            | RecordKind::RegisterWithPayment
+                            | RecordKind::PointerWithPayment
+                            | RecordKind::ScratchpadWithPayment
+                            | RecordKind::ExpiringDataWithPayment => {
                                 error!("Record {record_key:?} with payment shall not be stored locally.");
                                 return Err(NetworkError::InCorrectRecordHeader);
                             }
@@ -704,6 +853,14 @@ impl SwarmDriver {
                     });
                 }
             }
+            LocalSwarmCmd::QuarantineLocalRecord { key } => {
+                cmd_string = "QuarantineLocalRecord";
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .quarantine(&key);
+            }
             LocalSwarmCmd::RecordStoreHasKey { key, sender } => {
                 cmd_string = "RecordStoreHasKey";
                 let has_key = self
@@ -837,12 +994,49 @@ impl SwarmDriver {
         Ok(())
     }
 
-    fn record_node_issue(&mut self, peer_id: PeerId, issue: NodeIssue) {
+    // Sends the request to the closest peers of the routing table, not awaiting their responses.
+    fn send_to_closest_peers(&mut self, request: Request) {
+        for peer in self.get_closest_k_value_local_peers() {
+            if peer == self.self_peer_id {
+                continue;
+            }
+            self.queue_network_swarm_cmd(NetworkSwarmCmd::SendRequest {
+                req: request.clone(),
+                peer,
+                sender: None,
+            });
+        }
+    }
+
+    /// Carries the issues and the score of a peer over to the key it moved to, then drops its
+    /// former `PeerId` without taking it for churn.
+    pub(crate) fn on_key_rotation(&mut self, old_peer: PeerId, new_peer: PeerId) {
+        if let Some(issues) = self.bad_nodes.remove(&old_peer) {
+            let _ = self.bad_nodes.insert(new_peer, issues);
+        }
+        if let Some(peer_scores) = &mut self.peer_scores {
+            peer_scores.carry_over(old_peer, new_peer);
+            peer_scores.flush();
+        }
+        self.rotated_peers.insert(old_peer, new_peer);
+        if let Some(dead_peer) = self.swarm.behaviour_mut().kademlia.remove_peer(&old_peer) {
+            self.update_on_peer_removal(*dead_peer.node.key.preimage());
+        }
+    }
+
+    pub(crate) fn record_node_issue(&mut self, peer_id: PeerId, issue: NodeIssue) {
         info!("Peer {peer_id:?} is reported as having issue {issue:?}");
+        if let Some(peer_access) = &self.peer_access {
+            if peer_access.peer_access(&peer_id) == PeerAccess::Allowed {
+                debug!("Not holding the issue against {peer_id:?}, allowed by the access list");
+                return;
+            }
+        }
         let (issue_vec, is_bad) = self.bad_nodes.entry(peer_id).or_default();
 
         let mut is_new_bad = false;
         let mut bad_behaviour: String = "".to_string();
+        let mut score = 0.0;
 
         // If being considered as bad already, skip certain operations
         if !(*is_bad) {
@@ -864,6 +1058,10 @@ impl SwarmDriver {
             };
 
             if is_new_issue {
+                if let Some(peer_scores) = &mut self.peer_scores {
+                    score = peer_scores.add_strike(peer_id, &issue, SystemTime::now());
+                    peer_scores.flush();
+                }
                 issue_vec.push((issue, Instant::now()));
             }
 
@@ -883,6 +1081,14 @@ impl SwarmDriver {
                     break;
                 }
             }
+
+            // Or when the issues of all kinds add up to a score over the threshold.
+            if !*is_bad && score >= SHUN_THRESHOLD {
+                *is_bad = true;
+                is_new_bad = true;
+                bad_behaviour = format!("Score of {score:.1}");
+                info!("Peer {peer_id:?} scored {score:.1} from its issues. Consider it as a bad node now.");
+            }
         }
 
         if *is_bad {
@@ -922,10 +1128,29 @@ impl SwarmDriver {
                     bad_behaviour,
                 });
                 self.queue_network_swarm_cmd(NetworkSwarmCmd::SendRequest {
-                    req: request,
+                    req: request.clone(),
                     peer: peer_id,
                     sender: Some(tx),
                 });
+
+                // Share the evidence with the peers close to the bad one, for them to shun it
+                // as well.
+                let close_peers: Vec<_> = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .get_closest_local_peers(&NetworkAddress::from_peer(peer_id).as_kbucket_key())
+                    .map(|key| key.into_preimage())
+                    .filter(|peer| *peer != peer_id)
+                    .take(CLOSE_GROUP_SIZE)
+                    .collect();
+                for peer in close_peers {
+                    self.queue_network_swarm_cmd(NetworkSwarmCmd::SendRequest {
+                        req: request.clone(),
+                        peer,
+                        sender: None,
+                    });
+                }
             }
         }
     }
@@ -946,7 +1171,63 @@ impl SwarmDriver {
         let _ = self.quotes_history.insert(peer_id, quote);
     }
 
+    // Sends each peer the list of the records it is among the closest peers to, which they then
+    // fetch from us as they would replicate them from any other holder.
+    fn handover_records(&mut self) -> usize {
+        let all_records: Vec<_> = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .record_addresses_ref()
+            .values()
+            .cloned()
+            .collect();
+
+        let mut keys_by_peer: HashMap<PeerId, Vec<(NetworkAddress, RecordType)>> = HashMap::new();
+        for (address, record_type) in all_records.iter() {
+            let closest_peers: Vec<_> = self
+                .swarm
+                .behaviour_mut()
+                .kademlia
+                .get_closest_local_peers(&address.as_kbucket_key())
+                .map(|key| key.into_preimage())
+                .take(self.kademlia_params.replication_factor)
+                .collect();
+            for peer in closest_peers {
+                keys_by_peer
+                    .entry(peer)
+                    .or_default()
+                    .push((address.clone(), record_type.clone()));
+            }
+        }
+
+        info!(
+            "Handing {} records over to {} peers",
+            all_records.len(),
+            keys_by_peer.len()
+        );
+        for (peer, keys) in keys_by_peer {
+            self.queue_network_swarm_cmd(NetworkSwarmCmd::SendRequest {
+                req: Request::Cmd(Cmd::Replicate {
+                    holder: NetworkAddress::from_peer(self.self_peer_id),
+                    keys,
+                }),
+                peer,
+                sender: None,
+            });
+        }
+        all_records.len()
+    }
+
     fn try_interval_replication(&mut self) -> Result<()> {
+        // the expired records are dropped before their keys get advertised to the peers
+        self.swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .remove_expired_records();
+
         // get closest peers from buckets, sorted by increasing distance to us
         let our_peer_id = self.self_peer_id.into();
         let closest_k_peers = self
@@ -961,7 +1242,7 @@ impl SwarmDriver {
         let mut replicate_targets = closest_k_peers
             .into_iter()
             // add some leeway to allow for divergent knowledge
-            .take(REPLICATION_PEERS_COUNT)
+            .take(self.kademlia_params.replication_peers_count())
             .collect::<Vec<_>>();
 
         let now = Instant::now();
@@ -0,0 +1,130 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::target_arch::Instant;
+use libp2p::{swarm::ConnectionId, PeerId};
+use std::{collections::BTreeMap, time::Duration};
+
+/// The minimum time a new connection is kept open, even if it is not used.
+const MIN_CONNECTION_LIFETIME: Duration = Duration::from_secs(60);
+
+/// The live connections to other peers, with the time each of them may be closed at.
+///
+/// Every request sent to a peer keeps its connections open for `keep_alive` more, so that the next
+/// requests to the same peer reuse them instead of dialing it again. A connection found to be
+/// unhealthy is evicted right away, for the next request to dial a fresh one.
+#[derive(Debug)]
+pub(crate) struct ConnectionPool {
+    connections: BTreeMap<ConnectionId, (PeerId, Instant)>,
+    keep_alive: Duration,
+}
+
+impl ConnectionPool {
+    pub(crate) fn new(keep_alive: Duration) -> Self {
+        Self {
+            connections: Default::default(),
+            keep_alive,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, connection_id: ConnectionId, peer_id: PeerId) {
+        let expiry = Instant::now() + self.keep_alive.max(MIN_CONNECTION_LIFETIME);
+        let _ = self.connections.insert(connection_id, (peer_id, expiry));
+    }
+
+    pub(crate) fn remove(&mut self, connection_id: &ConnectionId) {
+        let _ = self.connections.remove(connection_id);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Keeps the connections to the peer open for another `keep_alive`, as it was just used.
+    pub(crate) fn mark_used(&mut self, peer_id: &PeerId) {
+        let expiry = Instant::now() + self.keep_alive;
+        for (peer, peer_expiry) in self.connections.values_mut() {
+            if peer == peer_id && *peer_expiry < expiry {
+                *peer_expiry = expiry;
+            }
+        }
+    }
+
+    /// Removes the connections to the peer, which failed to serve a request, returning them to be
+    /// closed.
+    pub(crate) fn evict_peer(&mut self, peer_id: &PeerId) -> Vec<ConnectionId> {
+        let evicted: Vec<_> = self
+            .connections
+            .iter()
+            .filter(|(_, (peer, _))| peer == peer_id)
+            .map(|(connection_id, _)| *connection_id)
+            .collect();
+        for connection_id in evicted.iter() {
+            let _ = self.connections.remove(connection_id);
+        }
+        evicted
+    }
+
+    /// Keeps the connections for which `keep` returns true, passing it whether the connection
+    /// expired.
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&ConnectionId, &PeerId, bool) -> bool) {
+        let now = Instant::now();
+        self.connections.retain(|connection_id, (peer_id, expiry)| {
+            keep(connection_id, peer_id, now >= *expiry)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn used_connections_are_kept_alive_and_unhealthy_ones_evicted() {
+        let mut pool = ConnectionPool::new(Duration::from_secs(300));
+        let (peer, other_peer) = (PeerId::random(), PeerId::random());
+        let (first, second, other) = (
+            ConnectionId::new_unchecked(1),
+            ConnectionId::new_unchecked(2),
+            ConnectionId::new_unchecked(3),
+        );
+        pool.insert(first, peer);
+        pool.insert(second, peer);
+        pool.insert(other, other_peer);
+        pool.mark_used(&peer);
+
+        let mut expired = vec![];
+        pool.retain(|connection_id, _, is_expired| {
+            if is_expired {
+                expired.push(*connection_id);
+            }
+            true
+        });
+        assert!(expired.is_empty());
+
+        let mut evicted = pool.evict_peer(&peer);
+        evicted.sort();
+        assert_eq!(evicted, vec![first, second]);
+        assert_eq!(pool.len(), 1);
+        assert!(pool.evict_peer(&peer).is_empty());
+
+        pool.remove(&other);
+        assert_eq!(pool.len(), 0);
+    }
+
+    #[test]
+    fn idle_connections_expire() {
+        let mut pool = ConnectionPool::new(Duration::ZERO);
+        let _ = pool.connections.insert(
+            ConnectionId::new_unchecked(1),
+            (PeerId::random(), Instant::now()),
+        );
+        pool.retain(|_, _, is_expired| !is_expired);
+        assert_eq!(pool.len(), 0);
+    }
+}
@@ -6,38 +6,61 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "local-discovery")]
+use crate::local_discovery::LocalDiscovery;
 #[cfg(feature = "open-metrics")]
 use crate::metrics::NetworkMetricsRecorder;
 #[cfg(feature = "open-metrics")]
 use crate::metrics_service::run_metrics_server;
+#[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+use crate::transport::WebRtcListener;
+#[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+use crate::transport::{WebSocketListener, WebSocketTls};
 use crate::{
-    bootstrap::{ContinuousBootstrap, BOOTSTRAP_INTERVAL},
+    bandwidth::BandwidthAccounting,
+    bootstrap::ContinuousBootstrap,
     circular_vec::CircularVec,
     cmd::{LocalSwarmCmd, NetworkSwarmCmd},
+    connection_pool::ConnectionPool,
     error::{NetworkError, Result},
     event::{NetworkEvent, NodeEvent},
     external_address::ExternalAddressManager,
+    kad_params::KademliaParams,
+    key_rotation::RotatedPeers,
     log_markers::Marker,
     multiaddr_pop_p2p,
     network_discovery::NetworkDiscovery,
-    record_store::{ClientRecordStore, NodeRecordStore, NodeRecordStoreConfig},
+    network_key::NetworkKey,
+    peer_access::{
+        PeerAccess, PeerAccessControl, PEER_ACCESS_FILENAME, PEER_ACCESS_RELOAD_INTERVAL,
+    },
+    peer_scores::PeerScores,
+    pricing::PricingStrategy,
+    pubsub::{PubSubLimiter, PubSubLimits},
+    rate_limiter::{RequestKind, RequestRateLimiter, RequestRateLimits},
+    record_store::{ClientRecordStore, NodeRecordStore, NodeRecordStoreConfig, PruningPolicy},
     record_store_api::UnifiedRecordStore,
-    relay_manager::RelayManager,
-    replication_fetcher::ReplicationFetcher,
+    record_store_backend::{DiskRecordStoreBackend, RecordStoreBackend},
+    relay_manager::{RelayLimits, RelayManager},
+    replication_fetcher::{ReplicationFetcher, ReplicationLimits},
     target_arch::{interval, spawn, Instant},
     version::{
         IDENTIFY_CLIENT_VERSION_STR, IDENTIFY_NODE_VERSION_STR, IDENTIFY_PROTOCOL_STR,
         REQ_RESPONSE_VERSION_STR,
     },
-    GetRecordError, Network, CLOSE_GROUP_SIZE,
+    GetRecordError, Network,
+};
+use crate::{
+    multiaddr_is_global,
+    transport::{self, is_ipv6, TransportConfig, TransportMode},
+    NodeIssue,
 };
-use crate::{transport, NodeIssue};
 use futures::future::Either;
 use futures::StreamExt;
+use libp2p::core::muxing::StreamMuxerBox;
 #[cfg(feature = "local-discovery")]
 use libp2p::mdns;
 use libp2p::Transport as _;
-use libp2p::{core::muxing::StreamMuxerBox, relay};
 use libp2p::{
     identity::Keypair,
     kad::{self, QueryId, Quorum, Record, RecordKey, K_VALUE},
@@ -45,24 +68,25 @@ use libp2p::{
     request_response::{self, Config as RequestResponseConfig, OutboundRequestId, ProtocolSupport},
     swarm::{
         dial_opts::{DialOpts, PeerCondition},
-        ConnectionId, DialError, NetworkBehaviour, StreamProtocol, Swarm,
+        DialError, NetworkBehaviour, StreamProtocol, Swarm,
     },
     Multiaddr, PeerId,
 };
 #[cfg(feature = "open-metrics")]
 use prometheus_client::{metrics::info::Info, registry::Registry};
 use sn_protocol::{
-    messages::{ChunkProof, Nonce, Request, Response},
-    storage::RetryStrategy,
+    messages::{ChunkProof, MsgEnvelope, Nonce, Request, Response},
+    storage::{RecordKind, RetryStrategy},
     NetworkAddress, PrettyPrintKBucketKey, PrettyPrintRecordKey,
 };
 use sn_transfers::PaymentQuote;
 use std::{
     collections::{btree_map::Entry, BTreeMap, HashMap, HashSet},
     fmt::Debug,
-    net::SocketAddr,
-    num::NonZeroUsize,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::PathBuf,
+    sync::Arc,
+    time::SystemTime,
 };
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::Duration;
@@ -76,6 +100,9 @@ pub(crate) const CLOSET_RECORD_CHECK_INTERVAL: Duration = Duration::from_secs(15
 /// Interval over which we query relay manager to check if we can make any more reservations.
 pub(crate) const RELAY_MANAGER_RESERVATION_INTERVAL: Duration = Duration::from_secs(30);
 
+/// Interval over which the changes to the paid records are written to disk.
+const PAID_RECORDS_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
 /// The ways in which the Get Closest queries are used.
 pub(crate) enum PendingGetClosestType {
     /// The network discovery method is present at the networking layer
@@ -112,9 +139,25 @@ const REQUEST_TIMEOUT_DEFAULT_S: Duration = Duration::from_secs(30);
 // Sets the keep-alive timeout of idle connections.
 const CONNECTION_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// The time a client keeps an idle connection open, to reuse it for its next requests to the same
+/// peer instead of dialing it again.
+const CLIENT_CONNECTION_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(300);
+
 // Inverval of resending identify to connected peers.
 const RESEND_IDENTIFY_INVERVAL: Duration = Duration::from_secs(3600);
 
+// What a pub/sub message takes on top of its data when transmitted: its topic, sequence number,
+// and the signature and key of its publisher.
+const PUBSUB_TRANSMIT_OVERHEAD: usize = 1024;
+
+// The Kademlia and gossipsub protocols of a private network, named after its key. The public
+// network keeps the libp2p ones.
+const KAD_PROTOCOL_STR: &str = "/safe/kad/1.0.0";
+const GOSSIPSUB_PROTOCOL_STR: &str = "/safe/meshsub";
+
+// The time the first AutoNAT probe waits for, for the node to have connected to its peers.
+const AUTONAT_BOOT_DELAY: Duration = Duration::from_secs(60);
+
 const NETWORKING_CHANNEL_SIZE: usize = 10_000;
 
 /// Time before a Kad query times out if no response is received
@@ -197,13 +240,17 @@ pub(super) struct NodeBehaviour {
         libp2p::allow_block_list::Behaviour<libp2p::allow_block_list::BlockedPeers>,
     pub(super) identify: libp2p::identify::Behaviour,
     #[cfg(feature = "local-discovery")]
-    pub(super) mdns: mdns::tokio::Behaviour,
+    pub(super) mdns: libp2p::swarm::behaviour::toggle::Toggle<mdns::tokio::Behaviour>,
     #[cfg(feature = "upnp")]
     pub(super) upnp: libp2p::swarm::behaviour::toggle::Toggle<libp2p::upnp::tokio::Behaviour>,
     pub(super) relay_client: libp2p::relay::client::Behaviour,
-    pub(super) relay_server: libp2p::relay::Behaviour,
+    pub(super) relay_server: libp2p::swarm::behaviour::toggle::Toggle<libp2p::relay::Behaviour>,
+    pub(super) autonat: libp2p::swarm::behaviour::toggle::Toggle<libp2p::autonat::Behaviour>,
+    pub(super) dcutr: libp2p::swarm::behaviour::toggle::Toggle<libp2p::dcutr::Behaviour>,
+    pub(super) gossipsub: libp2p::swarm::behaviour::toggle::Toggle<libp2p::gossipsub::Behaviour>,
     pub(super) kademlia: kad::Behaviour<UnifiedRecordStore>,
-    pub(super) request_response: request_response::cbor::Behaviour<Request, Response>,
+    pub(super) request_response:
+        request_response::cbor::Behaviour<MsgEnvelope<Request>, MsgEnvelope<Response>>,
 }
 
 #[derive(Debug)]
@@ -213,9 +260,31 @@ pub struct NetworkBuilder {
     local: bool,
     root_dir: PathBuf,
     listen_addr: Option<SocketAddr>,
+    dual_stack: bool,
     request_timeout: Option<Duration>,
     concurrency_limit: Option<usize>,
+    connection_keep_alive: Option<Duration>,
     initial_peers: Vec<Multiaddr>,
+    record_store_backend: Option<Arc<dyn RecordStoreBackend>>,
+    compressed_record_kinds: Option<HashSet<RecordKind>>,
+    disk_quota: Option<(u64, PruningPolicy)>,
+    replication_limits: ReplicationLimits,
+    kademlia_params: KademliaParams,
+    peer_access_file: Option<PathBuf>,
+    request_rate_limits: Option<RequestRateLimits>,
+    pricing: Option<Arc<dyn PricingStrategy>>,
+    transport_mode: TransportMode,
+    #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+    websocket: Option<WebSocketListener>,
+    #[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+    webrtc: Option<WebRtcListener>,
+    nat_detection: bool,
+    relay_service: Option<RelayLimits>,
+    pubsub: Option<PubSubLimits>,
+    network_key: Option<NetworkKey>,
+    // `None` until set, the default depending on the network key.
+    #[cfg(feature = "local-discovery")]
+    local_discovery: Option<Option<LocalDiscovery>>,
     #[cfg(feature = "open-metrics")]
     metrics_metadata_registry: Option<Registry>,
     #[cfg(feature = "open-metrics")]
@@ -234,9 +303,30 @@ impl NetworkBuilder {
             local,
             root_dir,
             listen_addr: None,
+            dual_stack: true,
             request_timeout: None,
             concurrency_limit: None,
+            connection_keep_alive: None,
             initial_peers: Default::default(),
+            record_store_backend: None,
+            compressed_record_kinds: None,
+            disk_quota: None,
+            replication_limits: Default::default(),
+            kademlia_params: Default::default(),
+            peer_access_file: None,
+            request_rate_limits: Some(Default::default()),
+            pricing: None,
+            transport_mode: Default::default(),
+            #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+            websocket: None,
+            #[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+            webrtc: None,
+            nat_detection: true,
+            relay_service: Some(Default::default()),
+            pubsub: Some(Default::default()),
+            network_key: None,
+            #[cfg(feature = "local-discovery")]
+            local_discovery: None,
             #[cfg(feature = "open-metrics")]
             metrics_metadata_registry: None,
             #[cfg(feature = "open-metrics")]
@@ -264,10 +354,132 @@ impl NetworkBuilder {
         self.concurrency_limit = Some(concurrency_limit);
     }
 
+    /// Sets how long an idle connection is kept open, to be reused by the next requests to the same peer.
+    /// Defaults to 30 seconds for a node, and 5 minutes for a client.
+    pub fn connection_keep_alive(&mut self, connection_keep_alive: Duration) {
+        self.connection_keep_alive = Some(connection_keep_alive);
+    }
+
     pub fn initial_peers(&mut self, initial_peers: Vec<Multiaddr>) {
         self.initial_peers = initial_peers;
     }
 
+    /// Sets the storage engine the records of a node are kept in.
+    /// Defaults to a `DiskRecordStoreBackend` in the `record_store` dir of the root dir.
+    pub fn record_store_backend(&mut self, backend: Arc<dyn RecordStoreBackend>) {
+        self.record_store_backend = Some(backend);
+    }
+
+    /// Sets the kinds of records a node stores compressed.
+    /// Defaults to the spends and the registers, which compress well.
+    pub fn compressed_record_kinds(&mut self, kinds: HashSet<RecordKind>) {
+        self.compressed_record_kinds = Some(kinds);
+    }
+
+    /// Sets the most disk the records of a node may take, in bytes, and the order they are
+    /// pruned in once it's reached. There is no quota by default.
+    pub fn disk_quota(&mut self, max_disk_usage: u64, pruning_policy: PruningPolicy) {
+        self.disk_quota = Some((max_disk_usage, pruning_policy));
+    }
+
+    /// Sets the caps on the replication traffic of a node. Only the number of concurrent fetches
+    /// is enforced here, the bytes per second being up to the fetching and serving of the records.
+    pub fn replication_limits(&mut self, limits: ReplicationLimits) {
+        self.replication_limits = limits;
+    }
+
+    /// Sets the Kademlia parameters of a node or client, failing if they're not ones the network
+    /// can run with. Defaults to `KademliaParams::default()`, the parameters of the network.
+    pub fn kademlia_params(&mut self, params: KademliaParams) -> Result<()> {
+        params.validate()?;
+        self.kademlia_params = params;
+        Ok(())
+    }
+
+    /// Sets the file the access list of a node is read from, and reloaded from whenever it
+    /// changes. Defaults to the `peer_access` file of the root dir.
+    pub fn peer_access_file(&mut self, path: PathBuf) {
+        self.peer_access_file = Some(path);
+    }
+
+    /// Sets the rates a node serves the requests of each of its clients at, `None` leaving them
+    /// unlimited. Defaults to `RequestRateLimits::default()`.
+    pub fn request_rate_limits(&mut self, limits: Option<RequestRateLimits>) {
+        self.request_rate_limits = limits;
+    }
+
+    /// Sets how a node prices the storing of a record. Defaults to `DefaultPricing`, the pricing
+    /// of the network.
+    pub fn pricing(&mut self, pricing: Arc<dyn PricingStrategy>) {
+        self.pricing = Some(pricing);
+    }
+
+    /// Sets whether a node given an unspecified listen address (`0.0.0.0` or `::`) listens on
+    /// both IPv4 and IPv6, on the same port. Enabled by default, the family the node isn't given
+    /// being skipped when the host lacks it.
+    pub fn dual_stack(&mut self, enable: bool) {
+        self.dual_stack = enable;
+    }
+
+    /// Sets the transports a node or client listens and dials on. Defaults to
+    /// `TransportMode::PreferQuic`, i.e. QUIC and TCP.
+    pub fn transport_mode(&mut self, transport_mode: TransportMode) {
+        self.transport_mode = transport_mode;
+    }
+
+    /// Sets the WebSocket listener of a node, for the clients running in browsers to connect to
+    /// it. A node doesn't listen on WebSocket by default.
+    #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+    pub fn websocket(&mut self, websocket: WebSocketListener) {
+        self.websocket = Some(websocket);
+    }
+
+    /// Sets the WebRTC listener of a node, for the clients running in browsers to connect to it
+    /// without the node needing a certificate of a domain. A node doesn't listen on WebRTC by
+    /// default.
+    #[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+    pub fn webrtc(&mut self, webrtc: WebRtcListener) {
+        self.webrtc = Some(webrtc);
+    }
+
+    /// Sets whether a node probes its peers for whether it's reachable (AutoNAT), turning to
+    /// relays once found to be behind a NAT. Enabled by default, the nodes of a local network and
+    /// the clients never probing. Whether probing or not, the nodes upgrade their relayed
+    /// connections to direct ones where hole punching gets through the NATs (DCUtR).
+    pub fn nat_detection(&mut self, enable: bool) {
+        self.nat_detection = enable;
+    }
+
+    /// Sets the limits a node relays the connections of the nodes behind a NAT within, `None`
+    /// not relaying them. Defaults to `RelayLimits::default()`, the nodes behind a home network
+    /// and the clients never relaying.
+    pub fn relay_service(&mut self, limits: Option<RelayLimits>) {
+        self.relay_service = limits;
+    }
+
+    /// Sets the limits of the messages published on the pub/sub topics, `None` not taking part in
+    /// pub/sub. Defaults to `PubSubLimits::default()`.
+    pub fn pubsub(&mut self, limits: Option<PubSubLimits>) {
+        self.pubsub = limits;
+    }
+
+    /// Sets the key of the private network a node or client is part of, for it to only connect to
+    /// the peers given the same key. Defaults to the key in the file at the
+    /// `SAFE_NETWORK_KEY_FILE` env var, the public network being joined if it's not set.
+    pub fn network_key(&mut self, network_key: NetworkKey) {
+        self.network_key = Some(network_key);
+    }
+
+    /// Sets how the peers on the local network are discovered over mDNS, `None` not discovering
+    /// them. Defaults to `LocalDiscovery::default()`, i.e. on all the interfaces, on the public
+    /// network, and to not discovering them on a private one: the peers of every network are
+    /// announced under the same mDNS service, so those of other networks on the LAN would be
+    /// discovered and dialed too, only to fail the handshake.
+    #[cfg(feature = "local-discovery")]
+    pub fn local_discovery(&mut self, local_discovery: Option<LocalDiscovery>) {
+        self.local_discovery = Some(local_discovery);
+    }
+
     /// Set the Registry that will be served at the `/metadata` endpoint. This Registry should contain only the static
     /// info about the peer. Configure the `metrics_server_port` to enable the metrics server.
     #[cfg(feature = "open-metrics")]
@@ -319,21 +531,16 @@ impl NetworkBuilder {
             .set_publication_interval(None)
             // 1mb packet size
             .set_max_packet_size(MAX_PACKET_SIZE)
-            // How many nodes _should_ store data.
-            .set_replication_factor(
-                NonZeroUsize::new(CLOSE_GROUP_SIZE)
-                    .ok_or_else(|| NetworkError::InvalidCloseGroupSize)?,
-            )
             .set_query_timeout(KAD_QUERY_TIMEOUT_S)
             // Require iterative queries to use disjoint paths for increased resiliency in the presence of potentially adversarial nodes.
             .disjoint_query_paths(true)
-            // Records never expire
-            .set_record_ttl(None)
-            // Emit PUT events for validation prior to insertion into the RecordStore.
-            // This is no longer needed as the record_storage::put now can carry out validation.
-            // .set_record_filtering(KademliaStoreInserts::FilterBoth)
+            // Emit PUT events prior to insertion into the RecordStore, for the PUTs to be
+            // rate limited by their sender. The records are validated by the RecordStore::put.
+            .set_record_filtering(kad::StoreInserts::FilterBoth)
             // Disable provider records publication job
             .set_provider_publication_interval(None);
+        // How many nodes _should_ store data, and how long for.
+        self.kademlia_params.apply_to(&mut kad_cfg)?;
 
         let store_cfg = {
             // Configures the disk_store to store records under the provided path and increase the max record size
@@ -344,15 +551,34 @@ impl NetworkBuilder {
                     source: error,
                 });
             }
-            NodeRecordStoreConfig {
+            let mut store_cfg = NodeRecordStoreConfig {
                 max_value_bytes: MAX_PACKET_SIZE, // TODO, does this need to be _less_ than MAX_PACKET_SIZE
                 storage_dir: storage_dir_path,
                 historic_quote_dir: self.root_dir.clone(),
+                quarantine_dir: self.root_dir.join("quarantine"),
+                journal_dir: Some(self.root_dir.join("record_journal")),
                 ..Default::default()
+            };
+            if let Some(kinds) = self.compressed_record_kinds.clone() {
+                store_cfg.compressed_record_kinds = kinds;
+            }
+            if let Some((max_disk_usage, pruning_policy)) = self.disk_quota {
+                store_cfg.max_disk_usage = Some(max_disk_usage);
+                store_cfg.pruning_policy = pruning_policy;
             }
+            if let Some(pricing) = self.pricing.clone() {
+                store_cfg.pricing = pricing;
+            }
+            store_cfg
         };
 
         let listen_addr = self.listen_addr;
+        let dual_stack = self.dual_stack;
+        let transport_mode = self.transport_mode;
+        #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+        let websocket = self.websocket.clone();
+        #[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+        let webrtc = self.webrtc;
         #[cfg(feature = "upnp")]
         let upnp = self.upnp;
 
@@ -369,23 +595,63 @@ impl NetworkBuilder {
         // Listen on the provided address
         let listen_socket_addr = listen_addr.ok_or(NetworkError::ListenAddressNotProvided)?;
 
-        // Listen on QUIC
-        let addr_quic = Multiaddr::from(listen_socket_addr.ip())
-            .with(Protocol::Udp(listen_socket_addr.port()))
-            .with(Protocol::QuicV1);
-        swarm_driver
-            .listen_on(addr_quic)
-            .expect("Multiaddr should be supported by our configured transports");
+        // Listen on both IPv4 and IPv6 when given an unspecified address. The sockets are bound
+        // IPv6-only, so both families can listen on the same port.
+        let mut listen_ips = vec![listen_socket_addr.ip()];
+        if dual_stack && listen_socket_addr.ip().is_unspecified() {
+            listen_ips.push(match listen_socket_addr.ip() {
+                IpAddr::V4(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                IpAddr::V6(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            });
+        }
+
+        for (index, ip) in listen_ips.into_iter().enumerate() {
+            let mut addrs = Vec::new();
 
-        // Listen on WebSocket
-        #[cfg(any(feature = "websockets", target_arch = "wasm32"))]
-        {
-            let addr_ws = Multiaddr::from(listen_socket_addr.ip())
-                .with(Protocol::Tcp(listen_socket_addr.port()))
-                .with(Protocol::Ws("/".into()));
-            swarm_driver
-                .listen_on(addr_ws)
-                .expect("Multiaddr should be supported by our configured transports");
+            // Listen on QUIC
+            addrs.push(
+                Multiaddr::from(ip)
+                    .with(Protocol::Udp(listen_socket_addr.port()))
+                    .with(Protocol::QuicV1),
+            );
+
+            // Listen on TCP, unless QUIC is required
+            if transport_mode == TransportMode::PreferQuic && cfg!(not(target_arch = "wasm32")) {
+                addrs.push(Multiaddr::from(ip).with(Protocol::Tcp(listen_socket_addr.port())));
+            }
+
+            // Listen on WebSocket, or WSS if given a certificate
+            #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+            if let Some(websocket) = &websocket {
+                addrs.push(
+                    Multiaddr::from(ip)
+                        .with(Protocol::Tcp(websocket.port))
+                        .with(if websocket.tls.is_some() {
+                            Protocol::Wss("/".into())
+                        } else {
+                            Protocol::Ws("/".into())
+                        }),
+                );
+            }
+
+            // Listen on WebRTC, the certhash being appended once listening
+            #[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+            if let Some(webrtc) = &webrtc {
+                addrs.push(
+                    Multiaddr::from(ip)
+                        .with(Protocol::Udp(webrtc.port))
+                        .with(Protocol::WebRTCDirect),
+                );
+            }
+
+            for addr in addrs {
+                if index == 0 {
+                    swarm_driver.listen_on(addr)?;
+                } else if let Err(err) = swarm_driver.listen_on(addr.clone()) {
+                    // The host might not have the other family, which leaves the node single-stack.
+                    warn!("Failed to listen on {addr:?}, the node staying single-stack: {err:?}");
+                }
+            }
         }
 
         Ok((network, events_receiver, swarm_driver))
@@ -402,12 +668,9 @@ impl NetworkBuilder {
             .set_kbucket_inserts(libp2p::kad::BucketInserts::Manual)
             .set_max_packet_size(MAX_PACKET_SIZE)
             // Require iterative queries to use disjoint paths for increased resiliency in the presence of potentially adversarial nodes.
-            .disjoint_query_paths(true)
-            // How many nodes _should_ store data.
-            .set_replication_factor(
-                NonZeroUsize::new(CLOSE_GROUP_SIZE)
-                    .ok_or_else(|| NetworkError::InvalidCloseGroupSize)?,
-            );
+            .disjoint_query_paths(true);
+        // How many nodes _should_ store data.
+        self.kademlia_params.apply_to(&mut kad_cfg)?;
 
         let (network, net_event_recv, driver) = self.build(
             kad_cfg,
@@ -425,7 +688,7 @@ impl NetworkBuilder {
     /// Private helper to create the network components with the provided config and req/res behaviour
     fn build(
         self,
-        kad_cfg: kad::Config,
+        mut kad_cfg: kad::Config,
         record_store_cfg: Option<NodeRecordStoreConfig>,
         is_client: bool,
         req_res_protocol: ProtocolSupport,
@@ -447,11 +710,49 @@ impl NetworkBuilder {
         #[cfg(feature = "open-metrics")]
         let mut metrics_registry = self.metrics_registry.unwrap_or_default();
 
+        let network_key = match self.network_key.clone() {
+            Some(network_key) => Some(network_key),
+            None => NetworkKey::from_env()?,
+        };
+        if let Some(network_key) = &network_key {
+            info!("Joining the private network {}", network_key.network_id());
+        }
+        #[cfg(feature = "local-discovery")]
+        let local_discovery = self
+            .local_discovery
+            .clone()
+            .unwrap_or_else(|| network_key.is_none().then(LocalDiscovery::default));
+        let protocol_str = |protocol: &str| match &network_key {
+            Some(network_key) => network_key.protocol_str(protocol),
+            None => protocol.to_string(),
+        };
+
         // ==== Transport ====
+        let noise_prologue = network_key
+            .as_ref()
+            .map(NetworkKey::handshake_secret)
+            .unwrap_or_default();
+        let transport_config = TransportConfig {
+            mode: self.transport_mode,
+            noise_prologue: noise_prologue.clone(),
+            #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+            websocket_tls: self
+                .websocket
+                .as_ref()
+                .and_then(|websocket| websocket.tls.as_ref())
+                .map(WebSocketTls::load)
+                .transpose()?,
+            #[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+            webrtc_certificate: self
+                .webrtc
+                .map(|webrtc| webrtc.load_certificate(&self.root_dir))
+                .transpose()?,
+        };
         #[cfg(feature = "open-metrics")]
-        let main_transport = transport::build_transport(&self.keypair, &mut metrics_registry);
+        let main_transport =
+            transport::build_transport(&self.keypair, transport_config, &mut metrics_registry);
         #[cfg(not(feature = "open-metrics"))]
-        let main_transport = transport::build_transport(&self.keypair);
+        let main_transport = transport::build_transport(&self.keypair, transport_config);
         let transport = if !self.local {
             debug!("Preventing non-global dials");
             // Wrap upper in a transport that prevents dialing local addresses.
@@ -466,21 +767,31 @@ impl NetworkBuilder {
             .upgrade(libp2p::core::upgrade::Version::V1Lazy)
             .authenticate(
                 libp2p::noise::Config::new(&self.keypair)
-                    .expect("Signing libp2p-noise static DH keypair failed."),
+                    .expect("Signing libp2p-noise static DH keypair failed.")
+                    .with_prologue(noise_prologue),
             )
             .multiplex(libp2p::yamux::Config::default())
             .or_transport(transport);
 
-        let transport = relay_transport
-            .map(|either_output, _| match either_output {
-                Either::Left((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
-                Either::Right((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
-            })
-            .boxed();
+        // The bytes of all the connections, relayed or not, are accounted to their peer.
+        let bandwidth = BandwidthAccounting::default();
+        let transport = {
+            let bandwidth = bandwidth.clone();
+            relay_transport
+                .map(move |either_output, _| {
+                    let (peer_id, muxer) = match either_output {
+                        Either::Left((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
+                        Either::Right((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
+                    };
+                    (peer_id, bandwidth.wrap(peer_id, muxer))
+                })
+                .boxed()
+        };
 
         #[cfg(feature = "open-metrics")]
         let network_metrics = if let Some(port) = self.metrics_server_port {
             let network_metrics = NetworkMetricsRecorder::new(&mut metrics_registry);
+            network_metrics.bandwidth_recorder_task(bandwidth.clone());
             let mut metadata_registry = self.metrics_metadata_registry.unwrap_or_default();
             let network_metadata_sub_registry =
                 metadata_registry.sub_registry_with_prefix("sn_networking");
@@ -495,7 +806,7 @@ impl NetworkBuilder {
                 "The protocol version string that is used to connect to the correct network",
                 Info::new(vec![(
                     "identify_protocol_str".to_string(),
-                    IDENTIFY_PROTOCOL_STR.to_string(),
+                    protocol_str(&IDENTIFY_PROTOCOL_STR),
                 )]),
             );
 
@@ -510,13 +821,12 @@ impl NetworkBuilder {
             let cfg = RequestResponseConfig::default()
                 .with_request_timeout(self.request_timeout.unwrap_or(REQUEST_TIMEOUT_DEFAULT_S));
 
-            info!(
-                "Building request response with {:?}",
-                REQ_RESPONSE_VERSION_STR.as_str()
-            );
+            let req_res_protocol_str = protocol_str(&REQ_RESPONSE_VERSION_STR);
+            info!("Building request response with {req_res_protocol_str:?}");
             request_response::cbor::Behaviour::new(
                 [(
-                    StreamProtocol::new(&REQ_RESPONSE_VERSION_STR),
+                    StreamProtocol::try_from_owned(req_res_protocol_str)
+                        .map_err(|err| NetworkError::BahviourErr(err.to_string()))?,
                     req_res_protocol,
                 )],
                 cfg,
@@ -530,12 +840,22 @@ impl NetworkBuilder {
             mpsc::channel(NETWORKING_CHANNEL_SIZE);
 
         // Kademlia Behaviour
+        if network_key.is_some() {
+            let _ = kad_cfg.set_protocol_names(vec![StreamProtocol::try_from_owned(protocol_str(
+                KAD_PROTOCOL_STR,
+            ))
+            .map_err(|err| NetworkError::BahviourErr(err.to_string()))?]);
+        }
         let kademlia = {
             match record_store_cfg {
                 Some(store_cfg) => {
-                    let node_record_store = NodeRecordStore::with_config(
+                    let backend = self.record_store_backend.clone().unwrap_or_else(|| {
+                        Arc::new(DiskRecordStoreBackend::new(store_cfg.storage_dir.clone()))
+                    });
+                    let node_record_store = NodeRecordStore::with_backend(
                         peer_id,
                         store_cfg,
+                        backend,
                         network_event_sender.clone(),
                         local_swarm_cmd_sender.clone(),
                     );
@@ -543,8 +863,8 @@ impl NetworkBuilder {
                     let mut node_record_store = node_record_store;
                     #[cfg(feature = "open-metrics")]
                     if let Some(metrics) = &network_metrics {
-                        node_record_store = node_record_store
-                            .set_record_count_metric(metrics.records_stored.clone());
+                        node_record_store =
+                            node_record_store.set_metrics(metrics.record_store.clone());
                     }
 
                     let store = UnifiedRecordStore::Node(node_record_store);
@@ -561,23 +881,27 @@ impl NetworkBuilder {
         };
 
         #[cfg(feature = "local-discovery")]
-        let mdns_config = mdns::Config {
-            // lower query interval to speed up peer discovery
-            // this increases traffic, but means we no longer have clients unable to connect
-            // after a few minutes
-            query_interval: Duration::from_secs(5),
-            ..Default::default()
-        };
-
-        #[cfg(feature = "local-discovery")]
-        let mdns = mdns::tokio::Behaviour::new(mdns_config, peer_id)?;
+        let mdns = match &local_discovery {
+            Some(local_discovery) => {
+                info!(
+                    "Discovering the local peers over mDNS on {:?}",
+                    local_discovery.interfaces
+                );
+                Some(mdns::tokio::Behaviour::new(
+                    local_discovery.mdns_config(),
+                    peer_id,
+                )?)
+            }
+            None => None,
+        }
+        .into();
 
         // Identify Behaviour
-        let identify_protocol_str = IDENTIFY_PROTOCOL_STR.to_string();
+        let identify_protocol_str = protocol_str(&IDENTIFY_PROTOCOL_STR);
         info!("Building Identify with identify_protocol_str: {identify_protocol_str:?} and identify_version: {identify_version:?}");
         let identify = {
             let mut cfg =
-                libp2p::identify::Config::new(identify_protocol_str, self.keypair.public())
+                libp2p::identify::Config::new(identify_protocol_str.clone(), self.keypair.public())
                     .with_agent_version(identify_version);
             // Enlength the identify interval from default 5 mins to 1 hour.
             cfg.interval = RESEND_IDENTIFY_INVERVAL;
@@ -593,21 +917,78 @@ impl NetworkBuilder {
         }
         .into(); // Into `Toggle<T>`
 
-        let relay_server = {
-            let relay_server_cfg = relay::Config {
-                max_reservations: 128,             // Amount of peers we are relaying for
-                max_circuits: 1024, // The total amount of relayed connections at any given moment.
-                max_circuits_per_peer: 256, // Amount of relayed connections per peer (both dst and src)
-                circuit_src_rate_limiters: vec![], // No extra rate limiting for now
+        // The nodes serve the probes of their peers, and probe them in turn.
+        let autonat = if !self.local && !is_client && self.nat_detection {
+            debug!("Enabling AutoNAT");
+            let autonat_cfg = libp2p::autonat::Config {
+                boot_delay: AUTONAT_BOOT_DELAY,
+                only_global_ips: true,
                 ..Default::default()
             };
-            libp2p::relay::Behaviour::new(peer_id, relay_server_cfg)
-        };
+            Some(libp2p::autonat::Behaviour::new(peer_id, autonat_cfg))
+        } else {
+            None
+        }
+        .into(); // Into `Toggle<T>`
+
+        // The connections relayed between two nodes are upgraded to direct ones by hole punching
+        // through their NATs, both ends taking part.
+        let dcutr = if !self.local && !is_client {
+            debug!("Enabling the direct connection upgrade of the relayed connections");
+            Some(libp2p::dcutr::Behaviour::new(peer_id))
+        } else {
+            None
+        }
+        .into(); // Into `Toggle<T>`
+
+        // Only the nodes the others can reach relay for them.
+        let relay_server = match self.relay_service {
+            Some(limits) if !is_client && !self.is_behind_home_network => {
+                debug!("Enabling the relay service within {limits:?}");
+                Some(libp2p::relay::Behaviour::new(
+                    peer_id,
+                    limits.server_config(),
+                ))
+            }
+            _ => None,
+        }
+        .into(); // Into `Toggle<T>`
+
+        // The messages are signed by their publisher, and checked against the limits of their
+        // topic before being relayed on.
+        let gossipsub = match &self.pubsub {
+            Some(limits) => {
+                debug!("Enabling pub/sub within {limits:?}");
+                let mut gossipsub_cfg = libp2p::gossipsub::ConfigBuilder::default();
+                let _ = gossipsub_cfg
+                    .validation_mode(libp2p::gossipsub::ValidationMode::Strict)
+                    .validate_messages()
+                    .max_transmit_size(limits.max_message_size() + PUBSUB_TRANSMIT_OVERHEAD);
+                if network_key.is_some() {
+                    let _ = gossipsub_cfg.protocol_id_prefix(protocol_str(GOSSIPSUB_PROTOCOL_STR));
+                }
+                let gossipsub_cfg = gossipsub_cfg
+                    .build()
+                    .map_err(|err| NetworkError::BahviourErr(err.to_string()))?;
+                Some(
+                    libp2p::gossipsub::Behaviour::new(
+                        libp2p::gossipsub::MessageAuthenticity::Signed(self.keypair.clone()),
+                        gossipsub_cfg,
+                    )
+                    .map_err(|err| NetworkError::BahviourErr(err.to_string()))?,
+                )
+            }
+            None => None,
+        }
+        .into(); // Into `Toggle<T>`
 
         let behaviour = NodeBehaviour {
             blocklist: libp2p::allow_block_list::Behaviour::default(),
             relay_client: relay_behaviour,
             relay_server,
+            autonat,
+            dcutr,
+            gossipsub,
             #[cfg(feature = "upnp")]
             upnp,
             request_response,
@@ -617,17 +998,62 @@ impl NetworkBuilder {
             mdns,
         };
 
+        let connection_keep_alive = self.connection_keep_alive.unwrap_or(if is_client {
+            CLIENT_CONNECTION_KEEP_ALIVE_TIMEOUT
+        } else {
+            CONNECTION_KEEP_ALIVE_TIMEOUT
+        });
         #[cfg(not(target_arch = "wasm32"))]
         let swarm_config = libp2p::swarm::Config::with_tokio_executor()
-            .with_idle_connection_timeout(CONNECTION_KEEP_ALIVE_TIMEOUT);
+            .with_idle_connection_timeout(connection_keep_alive);
         #[cfg(target_arch = "wasm32")]
         let swarm_config = libp2p::swarm::Config::with_wasm_executor()
-            .with_idle_connection_timeout(CONNECTION_KEEP_ALIVE_TIMEOUT);
+            .with_idle_connection_timeout(connection_keep_alive);
+
+        let mut swarm = Swarm::new(transport, behaviour, peer_id, swarm_config);
+
+        // Only the nodes keep the scores of the peers across restarts, the clients not being
+        // around for long enough to make use of them.
+        let mut bad_nodes = BadNodes::new();
+        let peer_scores = (!is_client).then(|| PeerScores::load(&self.root_dir));
+        if let Some(peer_scores) = &peer_scores {
+            for peer_id in peer_scores.shunned_peers(SystemTime::now()) {
+                info!("Peer {peer_id:?} is still shunned from before the restart");
+                swarm.behaviour_mut().blocklist.block_peer(peer_id);
+                let _ = bad_nodes.insert(peer_id, (vec![], true));
+            }
+        }
 
-        let swarm = Swarm::new(transport, behaviour, peer_id, swarm_config);
+        // The clients connect to the peers they pick, so only the nodes have an access list.
+        let peer_access = if is_client {
+            None
+        } else {
+            let file_path = self
+                .peer_access_file
+                .unwrap_or_else(|| self.root_dir.join(PEER_ACCESS_FILENAME));
+            let peer_access = PeerAccessControl::load(file_path)?;
+            for peer_id in peer_access.list().blocked_peers() {
+                swarm.behaviour_mut().blocklist.block_peer(*peer_id);
+            }
+            Some(peer_access)
+        };
+        let rate_limiter = if is_client {
+            None
+        } else {
+            self.request_rate_limits.map(RequestRateLimiter::new)
+        };
 
-        let bootstrap = ContinuousBootstrap::new();
-        let replication_fetcher = ReplicationFetcher::new(peer_id, network_event_sender.clone());
+        let bootstrap = ContinuousBootstrap::new(
+            self.kademlia_params.bucket_refresh_interval,
+            self.kademlia_params.max_bucket_refresh_interval,
+        );
+        let mut replication_fetcher =
+            ReplicationFetcher::new(peer_id, network_event_sender.clone());
+        replication_fetcher.set_replication_limits(self.replication_limits);
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &network_metrics {
+            replication_fetcher.set_queue_depth_metric(metrics.replication_queue_depth.clone());
+        }
         let mut relay_manager = RelayManager::new(peer_id);
         if !is_client {
             relay_manager.enable_hole_punching(self.is_behind_home_network);
@@ -662,13 +1088,23 @@ impl NetworkBuilder {
             dialed_peers: CircularVec::new(255),
             network_discovery: NetworkDiscovery::new(&peer_id),
             bootstrap_peers: Default::default(),
-            live_connected_peers: Default::default(),
+            live_connected_peers: ConnectionPool::new(connection_keep_alive),
             handling_statistics: Default::default(),
             handled_times: 0,
             hard_disk_write_error: 0,
-            bad_nodes: Default::default(),
+            bad_nodes,
+            peer_scores,
+            peer_access,
+            rate_limiter,
+            transport_mode: self.transport_mode,
+            identify_protocol_str,
+            pubsub_limiter: PubSubLimiter::new(self.pubsub.unwrap_or_default()),
+            #[cfg(feature = "local-discovery")]
+            local_discovery,
+            rotated_peers: Default::default(),
             quotes_history: Default::default(),
             replication_targets: Default::default(),
+            kademlia_params: self.kademlia_params,
         };
 
         let network = Network::new(
@@ -677,6 +1113,8 @@ impl NetworkBuilder {
             peer_id,
             self.root_dir,
             self.keypair,
+            bandwidth,
+            self.kademlia_params.replication_peers_count(),
         );
 
         Ok((network, network_event_receiver, swarm_driver))
@@ -718,14 +1156,25 @@ pub struct SwarmDriver {
     pub(crate) bootstrap_peers: BTreeMap<Option<u32>, HashSet<PeerId>>,
     // Peers that having live connection to. Any peer got contacted during kad network query
     // will have live connection established. And they may not appear in the RT.
-    pub(crate) live_connected_peers: BTreeMap<ConnectionId, (PeerId, Instant)>,
+    pub(crate) live_connected_peers: ConnectionPool,
     // Record the handling time of the recent 10 for each handling kind.
     handling_statistics: BTreeMap<String, Vec<Duration>>,
     handled_times: usize,
     pub(crate) hard_disk_write_error: usize,
     pub(crate) bad_nodes: BadNodes,
+    pub(crate) peer_scores: Option<PeerScores>,
+    pub(crate) peer_access: Option<PeerAccessControl>,
+    pub(crate) rate_limiter: Option<RequestRateLimiter>,
+    pub(crate) transport_mode: TransportMode,
+    /// The identify protocol of the network, the peers of the other networks being blocked.
+    pub(crate) identify_protocol_str: String,
+    pub(crate) pubsub_limiter: PubSubLimiter,
+    #[cfg(feature = "local-discovery")]
+    pub(crate) local_discovery: Option<LocalDiscovery>,
+    pub(crate) rotated_peers: RotatedPeers,
     pub(crate) quotes_history: BTreeMap<PeerId, PaymentQuote>,
     pub(crate) replication_targets: BTreeMap<PeerId, Instant>,
+    pub(crate) kademlia_params: KademliaParams,
 }
 
 impl SwarmDriver {
@@ -737,9 +1186,11 @@ impl SwarmDriver {
     /// and command receiver messages, ensuring efficient handling of multiple
     /// asynchronous tasks.
     pub async fn run(mut self) {
-        let mut bootstrap_interval = interval(BOOTSTRAP_INTERVAL);
+        let mut bootstrap_interval = interval(self.bootstrap.interval());
         let mut set_farthest_record_interval = interval(CLOSET_RECORD_CHECK_INTERVAL);
         let mut relay_manager_reservation_interval = interval(RELAY_MANAGER_RESERVATION_INTERVAL);
+        let mut peer_access_reload_interval = interval(PEER_ACCESS_RELOAD_INTERVAL);
+        let mut paid_records_flush_interval = interval(PAID_RECORDS_FLUSH_INTERVAL);
 
         loop {
             tokio::select! {
@@ -802,6 +1253,12 @@ impl SwarmDriver {
                     }
                 }
                 _ = relay_manager_reservation_interval.tick() => self.relay_manager.try_connecting_to_relay(&mut self.swarm, &self.bad_nodes),
+                _ = peer_access_reload_interval.tick() => self.reload_peer_access(),
+                _ = paid_records_flush_interval.tick() => {
+                    if !self.is_client {
+                        self.swarm.behaviour_mut().kademlia.store_mut().flush_paid_records();
+                    }
+                }
             }
         }
     }
@@ -810,6 +1267,67 @@ impl SwarmDriver {
     // ---------- Crate helpers -------------------
     // --------------------------------------------
 
+    /// Applies the access list again if its file changed, blocking the peers newly blocked and
+    /// closing the connections it now rejects.
+    fn reload_peer_access(&mut self) {
+        let Some(peer_access) = &mut self.peer_access else {
+            return;
+        };
+        let Some((previous, rejected)) = peer_access.reload_if_changed() else {
+            return;
+        };
+        let blocked = peer_access.list().blocked_peers().clone();
+
+        for peer_id in previous.blocked_peers().difference(&blocked) {
+            // the peers shunned for misbehaving stay blocked
+            if !matches!(self.bad_nodes.get(peer_id), Some((_, true))) {
+                info!("Unblocking {peer_id:?}, no longer blocked by the access list");
+                self.swarm.behaviour_mut().blocklist.unblock_peer(*peer_id);
+            }
+        }
+        for peer_id in blocked.difference(previous.blocked_peers()) {
+            info!("Blocking {peer_id:?}, as per the access list");
+            self.block_rejected_peer(*peer_id);
+        }
+        for connection_id in rejected {
+            let _ = self.swarm.close_connection(connection_id);
+        }
+    }
+
+    /// Blocks a peer rejected by the access list, clearing it out of the routing table.
+    fn block_rejected_peer(&mut self, peer_id: PeerId) {
+        self.swarm.behaviour_mut().blocklist.block_peer(peer_id);
+        if let Some(dead_peer) = self.swarm.behaviour_mut().kademlia.remove_peer(&peer_id) {
+            self.update_on_peer_removal(*dead_peer.node.key.preimage());
+        }
+    }
+
+    /// Returns whether the request of the peer is to be served, spending its cost out of the
+    /// bucket of the peer unless it's a node of the routing table, or allowed by the access list.
+    pub(crate) fn allow_request(&mut self, peer_id: PeerId, kind: RequestKind) -> bool {
+        let Some(rate_limiter) = &mut self.rate_limiter else {
+            return true;
+        };
+        if self
+            .peer_access
+            .as_ref()
+            .is_some_and(|peer_access| peer_access.peer_access(&peer_id) == PeerAccess::Allowed)
+        {
+            return true;
+        }
+        let in_routing_table = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .kbucket(peer_id)
+            .is_some_and(|kbucket| {
+                kbucket
+                    .iter()
+                    .any(|entry| *entry.node.key.preimage() == peer_id)
+            });
+        in_routing_table || rate_limiter.allow(peer_id, kind, Instant::now())
+    }
+
     /// Returns the farthest bucket, close to but probably farther than our responsibilty range.
     /// This simply uses the closest k peers to estimate the farthest address as
     /// `K_VALUE`th peer's bucket.
@@ -965,6 +1483,14 @@ impl SwarmDriver {
         }
     }
 
+    /// Whether IPv6 addresses are dialed before IPv4 ones, which is the case once the node has a
+    /// global IPv6 external address, i.e. its IPv6 connectivity has been confirmed.
+    pub(crate) fn prefers_ipv6(&self) -> bool {
+        self.swarm
+            .external_addresses()
+            .any(|addr| is_ipv6(addr) && multiaddr_is_global(addr))
+    }
+
     /// Listen on the provided address. Also records it within RelayManager
     pub(crate) fn listen_on(&mut self, addr: Multiaddr) -> Result<()> {
         let id = self.swarm.listen_on(addr.clone())?;
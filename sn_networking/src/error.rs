@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use libp2p::{
+    gossipsub::{PublishError, SubscriptionError},
     kad::{self, QueryId, Record},
     request_response::{OutboundFailure, OutboundRequestId},
     swarm::DialError,
@@ -152,6 +153,26 @@ pub enum NetworkError {
         source: std::io::Error,
     },
 
+    // ---------- PubSub Errors
+    #[error("Pub/sub is not enabled")]
+    PubSubDisabled,
+
+    #[error("Message of {size} bytes on topic {topic:?} is over its limit of {max} bytes")]
+    PubSubMessageTooLarge {
+        topic: String,
+        size: usize,
+        max: usize,
+    },
+
+    #[error("Publishing on topic {0:?} over its rate limit")]
+    PubSubRateLimited(String),
+
+    #[error("Gossipsub publish error: {0}")]
+    GossipsubPublishError(#[from] PublishError),
+
+    #[error("Gossipsub subscription error: {0}")]
+    GossipsubSubscriptionError(#[from] SubscriptionError),
+
     // ---------- Internal Network Errors
     #[error("Could not get enough peers ({required}) to satisfy the request, found {found}")]
     NotEnoughPeers { found: usize, required: usize },
@@ -159,6 +180,26 @@ pub enum NetworkError {
     #[error("Close group size must be a non-zero usize")]
     InvalidCloseGroupSize,
 
+    #[error("Invalid Kademlia params: {0}")]
+    InvalidKademliaParams(String),
+
+    #[error("Invalid peer access rule at line {line}: {rule:?}")]
+    InvalidPeerAccessRule { line: usize, rule: String },
+
+    #[error("Invalid network key: {0}")]
+    InvalidNetworkKey(String),
+
+    #[error("Invalid pricing script: {0}")]
+    InvalidPricingScript(String),
+
+    #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+    #[error("Invalid WebSocket TLS certificate or key: {0}")]
+    InvalidWebSocketTls(String),
+
+    #[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+    #[error("Invalid WebRTC certificate: {0}")]
+    InvalidWebRtcCertificate(String),
+
     #[error("Node Listen Address was not provided during construction")]
     ListenAddressNotProvided,
 
@@ -8,12 +8,16 @@
 
 use crate::{
     driver::PendingGetClosestType, get_quorum_value, get_raw_signed_spends_from_record,
-    GetRecordCfg, GetRecordError, NetworkError, Result, SwarmDriver, CLOSE_GROUP_SIZE,
+    rate_limiter::RequestKind, GetRecordCfg, GetRecordError, NetworkError, Result, SwarmDriver,
+    CLOSE_GROUP_SIZE,
 };
 use itertools::Itertools;
-use libp2p::kad::{
-    self, GetClosestPeersError, InboundRequest, PeerRecord, ProgressStep, QueryId, QueryResult,
-    QueryStats, Record, K_VALUE,
+use libp2p::{
+    kad::{
+        self, store::RecordStore, GetClosestPeersError, InboundRequest, PeerRecord, ProgressStep,
+        QueryId, QueryResult, QueryStats, Record, K_VALUE,
+    },
+    PeerId,
 };
 use sn_protocol::{
     storage::{try_serialize_record, RecordKind},
@@ -265,11 +269,25 @@ impl SwarmDriver {
                 }
             }
             kad::Event::InboundRequest {
-                request: InboundRequest::PutRecord { .. },
+                request: InboundRequest::PutRecord { source, record, .. },
             } => {
                 event_string = "kad_event::InboundRequest::PutRecord";
-                // Ignored to reduce logging. When `Record filtering` is enabled,
-                // the `record` variable will contain the content for further validation before put.
+                // With `Record filtering` enabled, the records are only stored once the sender is
+                // let through by the rate limits. The record is validated by the RecordStore::put.
+                if let Some(record) = record {
+                    if self.allow_request(source, RequestKind::of_record(&record)) {
+                        if let Err(err) =
+                            self.swarm.behaviour_mut().kademlia.store_mut().put(record)
+                        {
+                            debug!("Record from {source:?} not stored: {err:?}");
+                        }
+                    } else {
+                        debug!(
+                            "Dropping the record {:?} from rate limited {source:?}",
+                            PrettyPrintRecordKey::from(&record.key)
+                        );
+                    }
+                }
             }
             kad::Event::InboundRequest {
                 request: InboundRequest::FindNode { .. },
@@ -359,6 +377,7 @@ impl SwarmDriver {
         if let Entry::Occupied(mut entry) = self.pending_get_record.entry(query_id) {
             let (_key, _senders, result_map, cfg) = entry.get_mut();
 
+            self.live_connected_peers.mark_used(&peer_id);
             if !cfg.expected_holders.is_empty() {
                 if cfg.expected_holders.remove(&peer_id) {
                     debug!("For record {pretty_key:?} task {query_id:?}, received a copy from an expected holder {peer_id:?}");
@@ -395,7 +414,12 @@ impl SwarmDriver {
                 let (_key, senders, result_map, _) = entry.remove();
 
                 if result_map.len() == 1 {
-                    Self::send_record_after_checking_target(senders, peer_record.record, &cfg)?;
+                    Self::send_record_after_checking_target(
+                        senders,
+                        peer_record.record,
+                        Some(peer_id),
+                        &cfg,
+                    )?;
                 } else {
                     debug!("For record {pretty_key:?} task {query_id:?}, fetch completed with split record");
                     let mut accumulated_spends = BTreeSet::new();
@@ -589,7 +613,13 @@ impl SwarmDriver {
                 // if we have enough responses here, we can return the record
                 if let Some((record, peers)) = result_map.values().next() {
                     if peers.len() >= required_response_count {
-                        Self::send_record_after_checking_target(senders, record.clone(), &cfg)?;
+                        let holder = peers.iter().next().copied();
+                        Self::send_record_after_checking_target(
+                            senders,
+                            record.clone(),
+                            holder,
+                            &cfg,
+                        )?;
                         return Ok(());
                     }
                 }
@@ -609,10 +639,14 @@ impl SwarmDriver {
 
     fn send_record_after_checking_target(
         senders: Vec<oneshot::Sender<std::result::Result<Record, GetRecordError>>>,
-        record: Record,
+        mut record: Record,
+        holder: Option<PeerId>,
         cfg: &GetRecordCfg,
     ) -> Result<()> {
         let res = if cfg.target_record.is_none() || cfg.does_target_match(&record) {
+            // the publisher is not kept by the nodes, so it carries the peer the record was fetched
+            // from instead, for the record to be blamed on it if it turns out to be invalid.
+            record.publisher = holder;
             Ok(record)
         } else {
             Err(GetRecordError::RecordDoesNotMatch(record))
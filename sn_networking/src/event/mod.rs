@@ -11,6 +11,7 @@ mod request_response;
 mod swarm;
 
 use crate::{driver::SwarmDriver, error::Result};
+use bytes::Bytes;
 use core::fmt;
 use custom_debug::Debug as CustomDebug;
 #[cfg(feature = "local-discovery")]
@@ -22,7 +23,7 @@ use libp2p::{
 };
 
 use sn_protocol::{
-    messages::{Query, Request, Response},
+    messages::{MsgEnvelope, MsgId, Query, Request, Response},
     NetworkAddress, PrettyPrintRecordKey,
 };
 use sn_transfers::PaymentQuote;
@@ -37,13 +38,16 @@ use tokio::sync::oneshot;
 pub(super) enum NodeEvent {
     #[cfg(feature = "upnp")]
     Upnp(libp2p::upnp::Event),
-    MsgReceived(libp2p::request_response::Event<Request, Response>),
+    MsgReceived(libp2p::request_response::Event<MsgEnvelope<Request>, MsgEnvelope<Response>>),
     Kademlia(libp2p::kad::Event),
     #[cfg(feature = "local-discovery")]
     Mdns(Box<mdns::Event>),
     Identify(Box<libp2p::identify::Event>),
     RelayClient(Box<libp2p::relay::client::Event>),
     RelayServer(Box<libp2p::relay::Event>),
+    Autonat(Box<libp2p::autonat::Event>),
+    Dcutr(Box<libp2p::dcutr::Event>),
+    Gossipsub(Box<libp2p::gossipsub::Event>),
     Void(void::Void),
 }
 
@@ -54,8 +58,12 @@ impl From<libp2p::upnp::Event> for NodeEvent {
     }
 }
 
-impl From<libp2p::request_response::Event<Request, Response>> for NodeEvent {
-    fn from(event: libp2p::request_response::Event<Request, Response>) -> Self {
+impl From<libp2p::request_response::Event<MsgEnvelope<Request>, MsgEnvelope<Response>>>
+    for NodeEvent
+{
+    fn from(
+        event: libp2p::request_response::Event<MsgEnvelope<Request>, MsgEnvelope<Response>>,
+    ) -> Self {
         NodeEvent::MsgReceived(event)
     }
 }
@@ -89,6 +97,24 @@ impl From<libp2p::relay::Event> for NodeEvent {
     }
 }
 
+impl From<libp2p::autonat::Event> for NodeEvent {
+    fn from(event: libp2p::autonat::Event) -> Self {
+        NodeEvent::Autonat(Box::new(event))
+    }
+}
+
+impl From<libp2p::dcutr::Event> for NodeEvent {
+    fn from(event: libp2p::dcutr::Event) -> Self {
+        NodeEvent::Dcutr(Box::new(event))
+    }
+}
+
+impl From<libp2p::gossipsub::Event> for NodeEvent {
+    fn from(event: libp2p::gossipsub::Event) -> Self {
+        NodeEvent::Gossipsub(Box::new(event))
+    }
+}
+
 impl From<void::Void> for NodeEvent {
     fn from(event: void::Void) -> Self {
         NodeEvent::Void(event)
@@ -100,8 +126,8 @@ impl From<void::Void> for NodeEvent {
 pub enum MsgResponder {
     /// Respond to a request from `self` through a simple one-shot channel.
     FromSelf(Option<oneshot::Sender<Result<Response>>>),
-    /// Respond to a request from a peer in the network.
-    FromPeer(PeerResponseChannel<Response>),
+    /// Respond to a request from a peer in the network, echoing the id of the request.
+    FromPeer(PeerResponseChannel<MsgEnvelope<Response>>, MsgId),
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -109,6 +135,8 @@ pub enum MsgResponder {
 pub enum NetworkEvent {
     /// Incoming `Query` from a peer
     QueryRequestReceived {
+        /// The id of the request carrying the query
+        msg_id: MsgId,
         /// Query
         query: Query,
         /// The channel to send the `Response` through
@@ -145,6 +173,8 @@ pub enum NetworkEvent {
         peer_id: PeerId,
         keys_to_verify: Vec<NetworkAddress>,
     },
+    /// A message published on a pub/sub topic subscribed to
+    PubSubMessage { topic: String, msg: Bytes },
 }
 
 /// Terminate node for the following reason
@@ -212,6 +242,13 @@ impl Debug for NetworkEvent {
                     "NetworkEvent::ChunkProofVerification({peer_id:?} {keys_to_verify:?})"
                 )
             }
+            NetworkEvent::PubSubMessage { topic, msg } => {
+                write!(
+                    f,
+                    "NetworkEvent::PubSubMessage({topic:?}, {} bytes)",
+                    msg.len()
+                )
+            }
         }
     }
 }
@@ -241,6 +278,10 @@ impl SwarmDriver {
             self.peers_in_rt
         );
         self.log_kbuckets(&removed_peer);
+        // A peer which moved to a new key keeps its records, so they're not fetched first.
+        if self.rotated_peers.successor(&removed_peer).is_none() {
+            self.replication_fetcher.on_peer_removed(removed_peer);
+        }
         self.send_event(NetworkEvent::PeerRemoved(removed_peer, self.peers_in_rt));
 
         #[cfg(feature = "open-metrics")]
@@ -7,14 +7,19 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    cmd::NetworkSwarmCmd, log_markers::Marker, sort_peers_by_address, MsgResponder, NetworkError,
-    NetworkEvent, SwarmDriver, CLOSE_GROUP_SIZE,
+    cmd::NetworkSwarmCmd, key_rotation::verify_key_rotation, log_markers::Marker,
+    rate_limiter::RequestKind, sort_peers_by_address, MsgResponder, NetworkError, NetworkEvent,
+    NodeIssue, SwarmDriver, CLOSE_GROUP_SIZE,
 };
 use itertools::Itertools;
-use libp2p::request_response::{self, Message};
+use libp2p::{
+    kad::K_VALUE,
+    request_response::{self, Message, OutboundFailure},
+    PeerId,
+};
 use rand::{rngs::OsRng, thread_rng, Rng};
 use sn_protocol::{
-    messages::{CmdResponse, Request, Response},
+    messages::{CmdResponse, MsgEnvelope, Request, Response},
     storage::RecordType,
     NetworkAddress,
 };
@@ -23,16 +28,22 @@ impl SwarmDriver {
     /// Forwards `Request` to the upper layers using `Sender<NetworkEvent>`. Sends `Response` to the peers
     pub(super) fn handle_req_resp_events(
         &mut self,
-        event: request_response::Event<Request, Response>,
+        event: request_response::Event<MsgEnvelope<Request>, MsgEnvelope<Response>>,
     ) -> Result<(), NetworkError> {
         match event {
             request_response::Event::Message { message, peer } => match message {
                 Message::Request {
-                    request,
+                    request:
+                        MsgEnvelope {
+                            msg_id,
+                            msg: request,
+                        },
                     channel,
                     request_id,
                     ..
                 } => {
+                    let span = info_span!("handle_request", %msg_id, %peer);
+                    let _entered = span.enter();
                     debug!("Received request {request_id:?} from peer {peer:?}, req: {request:?}");
                     // If the request is replication or quote verification,
                     // we can handle it and send the OK response here.
@@ -45,7 +56,7 @@ impl SwarmDriver {
 
                             self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse {
                                 resp: response,
-                                channel: MsgResponder::FromPeer(channel),
+                                channel: MsgResponder::FromPeer(channel, msg_id),
                             });
 
                             self.add_keys_to_replication_fetcher(holder, keys);
@@ -59,7 +70,7 @@ impl SwarmDriver {
                             );
                             self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse {
                                 resp: response,
-                                channel: MsgResponder::FromPeer(channel),
+                                channel: MsgResponder::FromPeer(channel, msg_id),
                             });
 
                             // The keypair is required to verify the quotes,
@@ -85,7 +96,7 @@ impl SwarmDriver {
 
                             self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse {
                                 resp: response,
-                                channel: MsgResponder::FromPeer(channel),
+                                channel: MsgResponder::FromPeer(channel, msg_id),
                             });
 
                             let (Some(detected_by), Some(bad_peer)) =
@@ -103,23 +114,96 @@ impl SwarmDriver {
 
                                 // TODO: shall we terminate self after received such notifications
                                 //       from the majority close_group nodes around us?
+                            } else if detected_by != peer {
+                                warn!("Peer {peer:?} relayed the evidence of {detected_by:?} against {bad_peer:?}, ignoring it.");
+                            } else if self.is_close_to(peer, bad_peer) {
+                                // Counted as a strike only, so that it takes several close peers
+                                // agreeing for the peer to be shunned by us.
+                                info!("Peer {detected_by:?} consider {bad_peer:?} as BAD, due to {bad_behaviour:?}.");
+                                self.record_node_issue(bad_peer, NodeIssue::CloseNodesShunning);
                             } else {
-                                error!("Received a bad_peer notification from {detected_by:?}, targeting {bad_peer:?}, which is not us.");
+                                warn!("Received a bad_peer notification from {detected_by:?}, targeting {bad_peer:?}, while not being close to it.");
+                            }
+                        }
+                        Request::Cmd(sn_protocol::messages::Cmd::Departure { peer: departing }) => {
+                            let response = Response::Cmd(
+                                sn_protocol::messages::CmdResponse::Departure(Ok(())),
+                            );
+                            self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse {
+                                resp: response,
+                                channel: MsgResponder::FromPeer(channel, msg_id),
+                            });
+
+                            // Only the peer itself can announce its departure.
+                            if departing.as_peer_id() != Some(peer) {
+                                warn!("Peer {peer:?} announced the departure of {departing:?}, ignoring it.");
+                                return Ok(());
+                            }
+
+                            info!("Peer {peer:?} is leaving the network, removing it.");
+                            if let Some(dead_peer) =
+                                self.swarm.behaviour_mut().kademlia.remove_peer(&peer)
+                            {
+                                self.update_on_peer_removal(*dead_peer.node.key.preimage());
+                            }
+                        }
+                        Request::Cmd(sn_protocol::messages::Cmd::KeyRotation {
+                            peer: rotating,
+                            new_public_key,
+                            signature,
+                        }) => {
+                            let response = Response::Cmd(
+                                sn_protocol::messages::CmdResponse::KeyRotation(Ok(())),
+                            );
+                            self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse {
+                                resp: response,
+                                channel: MsgResponder::FromPeer(channel, msg_id),
+                            });
+
+                            // Only the peer itself can announce its rotation.
+                            if rotating.as_peer_id() != Some(peer) {
+                                warn!("Peer {peer:?} announced the key rotation of {rotating:?}, ignoring it.");
+                                return Ok(());
+                            }
+                            let Some(new_peer) =
+                                verify_key_rotation(peer, &new_public_key, &signature)
+                            else {
+                                warn!("Peer {peer:?} announced a key rotation not signed by the new key, ignoring it.");
+                                return Ok(());
+                            };
+                            // A shunned peer doesn't get to start over under a new key.
+                            if matches!(self.bad_nodes.get(&peer), Some((_, true))) {
+                                warn!("Shunned peer {peer:?} announced a key rotation to {new_peer:?}, ignoring it.");
+                                return Ok(());
                             }
+
+                            info!("Peer {peer:?} moves to the new key of {new_peer:?}.");
+                            self.on_key_rotation(peer, new_peer);
                         }
                         Request::Query(query) => {
+                            // The channel of a rate limited query is dropped, which the
+                            // requester sees as a failure.
+                            if !self.allow_request(peer, RequestKind::of_query(&query)) {
+                                debug!("Dropping the query {query} from rate limited {peer:?}");
+                                return Ok(());
+                            }
                             self.send_event(NetworkEvent::QueryRequestReceived {
+                                msg_id,
                                 query,
-                                channel: MsgResponder::FromPeer(channel),
+                                channel: MsgResponder::FromPeer(channel, msg_id),
                             })
                         }
                     }
                 }
                 Message::Response {
                     request_id,
-                    response,
+                    response:
+                        MsgEnvelope {
+                            msg_id,
+                            msg: response,
+                        },
                 } => {
-                    debug!("Got response {request_id:?} from peer {peer:?}, res: {response}.");
+                    debug!("Got response {request_id:?} to {msg_id} from peer {peer:?}, res: {response}.");
                     if let Some(sender) = self.pending_requests.remove(&request_id) {
                         // The sender will be provided if the caller (Requester) is awaiting for a response
                         // at the call site.
@@ -130,7 +214,13 @@ impl SwarmDriver {
                                 .send(Ok(response))
                                 .map_err(|_| NetworkError::InternalMsgChannelDropped)?,
                             None => {
-                                if let Response::Cmd(CmdResponse::Replicate(Ok(()))) = response {
+                                if let Response::Cmd(
+                                    CmdResponse::Replicate(Ok(()))
+                                    | CmdResponse::Departure(Ok(()))
+                                    | CmdResponse::KeyRotation(Ok(()))
+                                    | CmdResponse::PeerConsideredAsBad(Ok(())),
+                                ) = response
+                                {
                                     // Nothing to do, response was fine
                                     // This only exists to ensure we dont drop the handle and
                                     // exit early, potentially logging false connection woes
@@ -154,6 +244,20 @@ impl SwarmDriver {
                 error,
                 peer,
             } => {
+                // a client drops the connections which failed, rather than waiting for them to time out, so that its
+                // next request to the peer dials a fresh one.
+                if self.is_client
+                    && matches!(
+                        error,
+                        OutboundFailure::Timeout | OutboundFailure::ConnectionClosed
+                    )
+                {
+                    for connection_id in self.live_connected_peers.evict_peer(&peer) {
+                        debug!("Closing the unhealthy connection {connection_id:?} to {peer:?}");
+                        let _ = self.swarm.close_connection(connection_id);
+                    }
+                    self.record_connection_metrics();
+                }
                 if let Some(sender) = self.pending_requests.remove(&request_id) {
                     match sender {
                         Some(sender) => {
@@ -252,6 +356,17 @@ impl SwarmDriver {
         }
     }
 
+    // Whether the peer is among the closest ones to the target in our routing table, the close
+    // peers being the ones which get to check on the target.
+    fn is_close_to(&mut self, peer: PeerId, target: PeerId) -> bool {
+        self.swarm
+            .behaviour_mut()
+            .kademlia
+            .get_closest_local_peers(&NetworkAddress::from_peer(target).as_kbucket_key())
+            .take(K_VALUE.get())
+            .any(|key| *key.preimage() == peer)
+    }
+
     /// Check among all chunk type records that we have, select those close to the peer,
     /// and randomly pick one as the verification candidate.
     #[allow(clippy::mutable_key_type)]
@@ -8,12 +8,8 @@
 
 use crate::event::TerminateNodeReason;
 use crate::{
-    cmd::LocalSwarmCmd,
-    event::NodeEvent,
-    multiaddr_is_global, multiaddr_strip_p2p,
-    relay_manager::is_a_relayed_peer,
-    target_arch::Instant,
-    version::{IDENTIFY_NODE_VERSION_STR, IDENTIFY_PROTOCOL_STR},
+    cmd::LocalSwarmCmd, event::NodeEvent, multiaddr_is_global, multiaddr_strip_p2p,
+    relay_manager::is_a_relayed_peer, target_arch::Instant, version::IDENTIFY_NODE_VERSION_STR,
     NetworkEvent, Result, SwarmDriver,
 };
 #[cfg(feature = "local-discovery")]
@@ -30,9 +26,31 @@ use libp2p::{
     Multiaddr, PeerId, TransportError,
 };
 use std::collections::HashSet;
-use tokio::time::Duration;
 
 impl SwarmDriver {
+    /// The peers can't dial us back, so we're behind a NAT or a firewall: our direct addresses are
+    /// of no use to them, and we're to be reached through a relay instead, as if we were started
+    /// as behind a home network.
+    fn on_behind_nat(&mut self) {
+        if self.is_behind_home_network {
+            return;
+        }
+        warn!("The peers can't reach us directly, turning to the relays. Consider forwarding the port, or running with `--home-network`");
+        self.is_behind_home_network = true;
+        self.relay_manager.enable_hole_punching(true);
+
+        let direct_addrs: Vec<_> = self
+            .swarm
+            .external_addresses()
+            .filter(|addr| !addr.iter().any(|p| matches!(p, Protocol::P2pCircuit)))
+            .cloned()
+            .collect();
+        for addr in direct_addrs {
+            info!("Removing the unreachable external address {addr:?}");
+            self.swarm.remove_external_address(&addr);
+        }
+    }
+
     /// Handle `SwarmEvents`
     pub(crate) fn handle_swarm_events(&mut self, event: SwarmEvent<NodeEvent>) -> Result<()> {
         // This does not record all the events. `SwarmEvent::Behaviour(_)` are skipped. Hence `.record()` has to be
@@ -87,6 +105,42 @@ impl SwarmDriver {
                 }
             }
 
+            SwarmEvent::Behaviour(NodeEvent::Autonat(event)) => {
+                event_string = "autonat_event";
+                if let libp2p::autonat::Event::StatusChanged { old, new } = *event {
+                    info!("NAT status changed from {old:?} to {new:?}");
+                    if new == libp2p::autonat::NatStatus::Private {
+                        self.on_behind_nat();
+                    }
+                } else {
+                    trace!(?event, "AutoNAT event");
+                }
+            }
+
+            SwarmEvent::Behaviour(NodeEvent::Dcutr(event)) => {
+                #[cfg(feature = "open-metrics")]
+                if let Some(metrics) = &self.network_metrics {
+                    metrics.record(&(*event));
+                }
+
+                event_string = "dcutr_event";
+                match event.result {
+                    Ok(connection_id) => info!(
+                        "Upgraded the relayed connection with {:?} to the direct connection {connection_id:?}",
+                        event.remote_peer_id
+                    ),
+                    Err(err) => debug!(
+                        "Could not upgrade the relayed connection with {:?} to a direct one: {err:?}",
+                        event.remote_peer_id
+                    ),
+                }
+            }
+
+            SwarmEvent::Behaviour(NodeEvent::Gossipsub(event)) => {
+                event_string = "gossipsub";
+                self.on_gossipsub_event(*event);
+            }
+
             SwarmEvent::Behaviour(NodeEvent::RelayServer(event)) => {
                 #[cfg(feature = "open-metrics")]
                 if let Some(metrics) = &self.network_metrics {
@@ -123,11 +177,11 @@ impl SwarmDriver {
                     libp2p::identify::Event::Received { peer_id, info } => {
                         debug!(%peer_id, ?info, "identify: received info");
 
-                        if info.protocol_version != IDENTIFY_PROTOCOL_STR.to_string() {
-                            warn!(?info.protocol_version, "identify: {peer_id:?} does not have the same protocol. Our IDENTIFY_PROTOCOL_STR: {:?}", IDENTIFY_PROTOCOL_STR.as_str());
+                        if info.protocol_version != self.identify_protocol_str {
+                            warn!(?info.protocol_version, "identify: {peer_id:?} does not have the same protocol. Our IDENTIFY_PROTOCOL_STR: {:?}", self.identify_protocol_str);
 
                             self.send_event(NetworkEvent::PeerWithUnsupportedProtocol {
-                                our_protocol: IDENTIFY_PROTOCOL_STR.to_string(),
+                                our_protocol: self.identify_protocol_str.clone(),
                                 their_protocol: info.protocol_version,
                             });
                             // Block the peer from any further communication.
@@ -165,6 +219,9 @@ impl SwarmDriver {
                                 .map(|addr| multiaddr_strip_p2p(&addr))
                                 .collect(),
                         };
+                        // Leave out the addresses we've no transport for, e.g. the TCP ones when QUIC
+                        // is required.
+                        addrs.retain(|addr| self.transport_mode.can_dial(addr));
 
                         let has_relayed = is_a_relayed_peer(&addrs);
 
@@ -228,10 +285,13 @@ impl SwarmDriver {
                             }
 
                             info!(%peer_id, ?addrs, "received identify info from undialed peer for not full kbucket {ilog2:?}, dial back to confirm external accessible");
+                            let mut dial_addrs: Vec<_> = addrs.iter().cloned().collect();
+                            self.transport_mode
+                                .sort_addrs(&mut dial_addrs, self.prefers_ipv6());
                             if let Err(err) = self.swarm.dial(
                                 DialOpts::peer_id(peer_id)
                                     .condition(PeerCondition::NotDialing)
-                                    .addresses(addrs.iter().cloned().collect())
+                                    .addresses(dial_addrs)
                                     .build(),
                             ) {
                                 warn!(%peer_id, ?addrs, "dialing error: {err:?}");
@@ -285,6 +345,14 @@ impl SwarmDriver {
                     mdns::Event::Discovered(list) => {
                         if self.local {
                             for (peer_id, addr) in list {
+                                if !self
+                                    .local_discovery
+                                    .as_ref()
+                                    .is_some_and(|discovery| discovery.is_on_interfaces(&addr))
+                                {
+                                    debug!(%addr, "mDNS node discovered outside of our interfaces, not dialing");
+                                    continue;
+                                }
                                 // The multiaddr does not contain the peer ID, so add it.
                                 let addr = addr.with(Protocol::P2p(peer_id));
 
@@ -360,10 +428,23 @@ impl SwarmDriver {
                 event_string = "ConnectionEstablished";
                 debug!(%peer_id, num_established, ?concurrent_dial_errors, "ConnectionEstablished ({connection_id:?}) in {established_in:?}: {}", endpoint_str(&endpoint));
 
-                let _ = self.live_connected_peers.insert(
-                    connection_id,
-                    (peer_id, Instant::now() + Duration::from_secs(60)),
-                );
+                if let Some(peer_access) = &mut self.peer_access {
+                    if !peer_access.on_connection_established(
+                        connection_id,
+                        peer_id,
+                        endpoint.get_remote_address(),
+                    ) {
+                        let _ = self.swarm.close_connection(connection_id);
+                        if let Some(dead_peer) =
+                            self.swarm.behaviour_mut().kademlia.remove_peer(&peer_id)
+                        {
+                            self.update_on_peer_removal(*dead_peer.node.key.preimage());
+                        }
+                        return Ok(());
+                    }
+                }
+
+                self.live_connected_peers.insert(connection_id, peer_id);
                 self.record_connection_metrics();
 
                 if endpoint.is_dialer() {
@@ -379,7 +460,10 @@ impl SwarmDriver {
             } => {
                 event_string = "ConnectionClosed";
                 debug!(%peer_id, ?connection_id, ?cause, num_established, "ConnectionClosed: {}", endpoint_str(&endpoint));
-                let _ = self.live_connected_peers.remove(&connection_id);
+                self.live_connected_peers.remove(&connection_id);
+                if let Some(peer_access) = &mut self.peer_access {
+                    peer_access.on_connection_closed(&connection_id);
+                }
                 self.record_connection_metrics();
             }
             SwarmEvent::OutgoingConnectionError {
@@ -389,7 +473,7 @@ impl SwarmDriver {
             } => {
                 event_string = "OutgoingConnErr";
                 warn!("OutgoingConnectionError to {failed_peer_id:?} on {connection_id:?} - {error:?}");
-                let _ = self.live_connected_peers.remove(&connection_id);
+                self.live_connected_peers.remove(&connection_id);
                 self.record_connection_metrics();
 
                 // we need to decide if this was a critical error and the peer should be removed from the routing table
@@ -510,7 +594,7 @@ impl SwarmDriver {
             } => {
                 event_string = "Incoming ConnErr";
                 error!("IncomingConnectionError from local_addr:?{local_addr:?}, send_back_addr {send_back_addr:?} on {connection_id:?} with error {error:?}");
-                let _ = self.live_connected_peers.remove(&connection_id);
+                self.live_connected_peers.remove(&connection_id);
                 self.record_connection_metrics();
             }
             SwarmEvent::Dialing {
@@ -593,10 +677,10 @@ impl SwarmDriver {
     // Optionally force remove all the connections for a provided peer.
     fn remove_outdated_connections(&mut self) {
         let mut removed_conns = 0;
-        self.live_connected_peers.retain(|connection_id, (peer_id, timeout_time)| {
+        self.live_connected_peers.retain(|connection_id, peer_id, expired| {
 
             // skip if timeout isn't reached yet
-            if Instant::now() < *timeout_time {
+            if !expired {
                 return true; // retain peer
             }
 
@@ -642,7 +726,7 @@ impl SwarmDriver {
     }
 
     /// Record the metrics on update of connection state.
-    fn record_connection_metrics(&self) {
+    pub(crate) fn record_connection_metrics(&self) {
         #[cfg(feature = "open-metrics")]
         if let Some(metrics) = &self.network_metrics {
             metrics
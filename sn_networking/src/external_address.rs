@@ -24,7 +24,11 @@ const MAX_CANDIDATES: usize = 50;
 pub struct ExternalAddressManager {
     /// All the external addresses of the node
     address_states: Vec<ExternalAddressState>,
-    current_ip_address: Option<IpAddr>,
+    /// The current IPv4 address of the node, if any
+    current_ipv4_address: Option<IpAddr>,
+    /// The current IPv6 address of the node, if any. Kept apart from the IPv4 one so that a dual-stack node
+    /// doesn't see its addresses of the other family as an IP change.
+    current_ipv6_address: Option<IpAddr>,
     /// The peer id of the node
     peer_id: PeerId,
 }
@@ -33,7 +37,8 @@ impl ExternalAddressManager {
     pub fn new(peer_id: PeerId) -> Self {
         Self {
             address_states: Vec::new(),
-            current_ip_address: None,
+            current_ipv4_address: None,
+            current_ipv6_address: None,
             peer_id,
         }
     }
@@ -66,7 +71,8 @@ impl ExternalAddressManager {
             return;
         }
 
-        let Some(address) = self.craft_external_address(&address) else {
+        // The TCP ports observed by our peers are ephemeral ones, only the QUIC ones are ours.
+        let Some(address) = self.craft_external_address(&address, false) else {
             debug!("Address is ill formed, not added to manager: {address:?}");
             return;
         };
@@ -86,7 +92,12 @@ impl ExternalAddressManager {
                 } => {
                     if *num_reports >= MAX_REPORTS_BEFORE_CONFIRMATION {
                         // if the IP address of our confirmed address is the same as the new address, then add it
-                        let confirmed = if let Some(current_ip_address) = self.current_ip_address {
+                        let confirmed = if let Some(current_ip_address) =
+                            Self::current_ip_address_of(
+                                self.current_ipv4_address,
+                                self.current_ipv6_address,
+                                ip_address,
+                            ) {
                             current_ip_address == *ip_address
                         } else {
                             true
@@ -118,7 +129,8 @@ impl ExternalAddressManager {
         }
         // check if we need to update to new ip.
         // TODO: Need to observe this
-        if let Some(current_ip_address) = self.current_ip_address {
+        // Only the addresses of the same family as the current one are compared to it.
+        if self.current_ipv4_address.is_some() || self.current_ipv6_address.is_some() {
             let mut new_ip_map = HashMap::new();
 
             for state in &self.address_states {
@@ -128,6 +140,9 @@ impl ExternalAddressManager {
                     ..
                 } = state
                 {
+                    let Some(current_ip_address) = self.current_ip_address(ip_address) else {
+                        continue;
+                    };
                     if current_ip_address != *ip_address
                         && *num_reports >= MAX_REPORTS_BEFORE_CONFIRMATION
                     {
@@ -184,7 +199,7 @@ impl ExternalAddressManager {
     ) {
         // only add our global addresses
         let address = if multiaddr_is_global(&listen_addr) {
-            let Some(address) = self.craft_external_address(&listen_addr) else {
+            let Some(address) = self.craft_external_address(&listen_addr, true) else {
                 error!("Listen address is ill formed, not added to manager: {listen_addr:?}");
                 return;
             };
@@ -197,7 +212,7 @@ impl ExternalAddressManager {
             return;
         };
 
-        if let Some(current_ip_address) = self.current_ip_address {
+        if let Some(current_ip_address) = self.current_ip_address(&ip_address) {
             if current_ip_address != ip_address {
                 // add as candidate with MAX_REPORTS to be confirmed inside switch_to_new_ip
                 self.address_states.push(ExternalAddressState::Candidate {
@@ -245,13 +260,36 @@ impl ExternalAddressManager {
         swarm.add_external_address(address);
     }
 
-    /// Switch to a new IP address. The old external addresses are removed and the new ones are added.
-    /// The new IP address is set as the current IP address.
+    /// The current IP address of the same family as the given one.
+    fn current_ip_address(&self, ip_address: &IpAddr) -> Option<IpAddr> {
+        Self::current_ip_address_of(
+            self.current_ipv4_address,
+            self.current_ipv6_address,
+            ip_address,
+        )
+    }
+
+    fn current_ip_address_of(
+        current_ipv4_address: Option<IpAddr>,
+        current_ipv6_address: Option<IpAddr>,
+        ip_address: &IpAddr,
+    ) -> Option<IpAddr> {
+        match ip_address {
+            IpAddr::V4(_) => current_ipv4_address,
+            IpAddr::V6(_) => current_ipv6_address,
+        }
+    }
+
+    /// Switch to a new IP address. The old external addresses of the same family are removed and the new ones
+    /// are added. The new IP address is set as the current IP address of its family.
     fn switch_to_new_ip(&mut self, new_ip: IpAddr, swarm: &mut Swarm<NodeBehaviour>) {
         info!("Switching to new IpAddr: {new_ip}");
-        self.current_ip_address = Some(new_ip);
+        match new_ip {
+            IpAddr::V4(_) => self.current_ipv4_address = Some(new_ip),
+            IpAddr::V6(_) => self.current_ipv6_address = Some(new_ip),
+        }
 
-        // remove all the old confirmed addresses with different ip
+        // remove all the old confirmed addresses of the same family with different ip
         let mut removed_addresses = Vec::new();
         for state in &mut self.address_states {
             if let ExternalAddressState::Confirmed {
@@ -260,7 +298,7 @@ impl ExternalAddressManager {
                 ..
             } = state
             {
-                if *ip_address != new_ip {
+                if *ip_address != new_ip && ip_address.is_ipv6() == new_ip.is_ipv6() {
                     removed_addresses.push(address.clone());
                     swarm.remove_external_address(address);
                 }
@@ -268,8 +306,10 @@ impl ExternalAddressManager {
         }
         info!("Removed addresses due to change of IP: {removed_addresses:?}");
 
-        self.address_states
-            .retain(|state| !matches!(state, ExternalAddressState::Confirmed { .. }));
+        self.address_states.retain(|state| {
+            !matches!(state, ExternalAddressState::Confirmed { ip_address, .. }
+                if ip_address.is_ipv6() == new_ip.is_ipv6())
+        });
 
         // add the new confirmed addresses with new ip
         for state in &mut self.address_states {
@@ -293,19 +333,44 @@ impl ExternalAddressManager {
         Self::print_swarm_state(swarm);
     }
 
-    /// Craft a proper address to avoid any ill formed addresses
-    fn craft_external_address(&self, given_address: &Multiaddr) -> Option<Multiaddr> {
+    /// Craft a proper address to avoid any ill formed addresses, a QUIC one, or a TCP one if
+    /// `allow_tcp` is set.
+    fn craft_external_address(
+        &self,
+        given_address: &Multiaddr,
+        allow_tcp: bool,
+    ) -> Option<Multiaddr> {
         let mut output_address = Multiaddr::empty();
 
         let ip = given_address
             .iter()
-            .find(|protocol| matches!(protocol, Protocol::Ip4(_)))?;
+            .find(|protocol| matches!(protocol, Protocol::Ip4(_) | Protocol::Ip6(_)))?;
         output_address.push(ip);
-        let port = given_address
+        // The WebRTC addresses aren't QUIC ones, and are only for the browsers to dial.
+        if given_address
+            .iter()
+            .any(|protocol| matches!(protocol, Protocol::WebRTCDirect))
+        {
+            return None;
+        }
+        if let Some(port) = given_address
             .iter()
-            .find(|protocol| matches!(protocol, Protocol::Udp(_)))?;
-        output_address.push(port);
-        output_address.push(Protocol::QuicV1);
+            .find(|protocol| matches!(protocol, Protocol::Udp(_)))
+        {
+            output_address.push(port);
+            output_address.push(Protocol::QuicV1);
+        } else {
+            // The WebSocket addresses aren't plain TCP ones.
+            let is_plain_tcp = allow_tcp
+                && !given_address
+                    .iter()
+                    .any(|protocol| matches!(protocol, Protocol::Ws(_) | Protocol::Wss(_)));
+            let port = given_address
+                .iter()
+                .find(|protocol| matches!(protocol, Protocol::Tcp(_)))
+                .filter(|_| is_plain_tcp)?;
+            output_address.push(port);
+        }
 
         output_address.push(Protocol::P2p(self.peer_id));
         Some(output_address)
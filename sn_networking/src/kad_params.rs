@@ -0,0 +1,154 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    bootstrap::{BOOTSTRAP_INTERVAL, NO_PEER_ADDED_SLOWDOWN_INTERVAL_MAX},
+    error::{NetworkError, Result},
+    CLOSE_GROUP_SIZE, REPLICATION_LEEWAY,
+};
+use libp2p::kad::{self, ALPHA_VALUE, K_VALUE};
+use std::{num::NonZeroUsize, time::Duration};
+
+/// The Kademlia parameters of a node or client, for the private networks and the experiments to
+/// tune the DHT without patching its constants. All the peers of a network are to be given the
+/// same ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KademliaParams {
+    /// How many of the closest peers a record is stored with, and the closest peers queries
+    /// return. Between the close group size and the bucket size (`K_VALUE`).
+    pub replication_factor: usize,
+    /// How many peers a query asks at once (alpha). Between 1 and the bucket size.
+    pub parallelism: usize,
+    /// How long Kademlia holds the records it puts valid for, `None` for ever. It doesn't apply to
+    /// the records the nodes store, chunks, spends and registers being paid for to be kept: only
+    /// the records of an expiring kind are removed, once their data expired.
+    pub record_ttl: Option<Duration>,
+    /// How often the routing table is refreshed, by querying the peers closest to random
+    /// addresses in its buckets, while peers are being added to it. Stepped up as it fills.
+    pub bucket_refresh_interval: Duration,
+    /// The most the refreshes are slowed down to once no peer has been added for a while.
+    pub max_bucket_refresh_interval: Duration,
+}
+
+impl Default for KademliaParams {
+    fn default() -> Self {
+        Self {
+            replication_factor: CLOSE_GROUP_SIZE,
+            parallelism: ALPHA_VALUE.get(),
+            record_ttl: None,
+            bucket_refresh_interval: BOOTSTRAP_INTERVAL,
+            max_bucket_refresh_interval: NO_PEER_ADDED_SLOWDOWN_INTERVAL_MAX,
+        }
+    }
+}
+
+impl KademliaParams {
+    /// Checks the parameters are ones the network can run with.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| Err(NetworkError::InvalidKademliaParams(reason));
+        if !(CLOSE_GROUP_SIZE..=K_VALUE.get()).contains(&self.replication_factor) {
+            return invalid(format!(
+                "the replication factor {} is not between the close group size {CLOSE_GROUP_SIZE} and {K_VALUE}",
+                self.replication_factor
+            ));
+        }
+        if !(1..=K_VALUE.get()).contains(&self.parallelism) {
+            return invalid(format!(
+                "the parallelism {} is not between 1 and {K_VALUE}",
+                self.parallelism
+            ));
+        }
+        if self.record_ttl == Some(Duration::ZERO) {
+            return invalid("the record TTL is zero".to_string());
+        }
+        if self.bucket_refresh_interval.is_zero() {
+            return invalid("the bucket refresh interval is zero".to_string());
+        }
+        if self.max_bucket_refresh_interval < self.bucket_refresh_interval {
+            return invalid(format!(
+                "the max bucket refresh interval {:?} is below the bucket refresh interval {:?}",
+                self.max_bucket_refresh_interval, self.bucket_refresh_interval
+            ));
+        }
+        Ok(())
+    }
+
+    /// The count of peers close to a record that it's replicated to, and accepted from: the
+    /// replication factor, with some leeway.
+    pub fn replication_peers_count(&self) -> usize {
+        self.replication_factor + REPLICATION_LEEWAY
+    }
+
+    pub(crate) fn apply_to(&self, kad_cfg: &mut kad::Config) -> Result<()> {
+        self.validate()?;
+        let non_zero = |value| {
+            NonZeroUsize::new(value).ok_or_else(|| {
+                NetworkError::InvalidKademliaParams(format!("{self:?} has a zero value"))
+            })
+        };
+        let _ = kad_cfg
+            .set_replication_factor(non_zero(self.replication_factor)?)
+            .set_parallelism(non_zero(self.parallelism)?)
+            .set_record_ttl(self.record_ttl);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_outside_of_what_the_network_runs_with_are_rejected() {
+        assert!(KademliaParams::default().validate().is_ok());
+        assert_eq!(
+            KademliaParams::default().replication_peers_count(),
+            crate::REPLICATION_PEERS_COUNT
+        );
+        assert!(KademliaParams {
+            replication_factor: K_VALUE.get(),
+            parallelism: 1,
+            record_ttl: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        }
+        .validate()
+        .is_ok());
+
+        let invalid = [
+            KademliaParams {
+                replication_factor: CLOSE_GROUP_SIZE - 1,
+                ..Default::default()
+            },
+            KademliaParams {
+                replication_factor: K_VALUE.get() + 1,
+                ..Default::default()
+            },
+            KademliaParams {
+                parallelism: 0,
+                ..Default::default()
+            },
+            KademliaParams {
+                record_ttl: Some(Duration::ZERO),
+                ..Default::default()
+            },
+            KademliaParams {
+                bucket_refresh_interval: Duration::ZERO,
+                ..Default::default()
+            },
+            KademliaParams {
+                bucket_refresh_interval: Duration::from_secs(60),
+                max_bucket_refresh_interval: Duration::from_secs(30),
+                ..Default::default()
+            },
+        ];
+        for params in invalid {
+            assert!(params.validate().is_err(), "{params:?}");
+            assert!(params.apply_to(&mut kad::Config::default()).is_err());
+        }
+    }
+}
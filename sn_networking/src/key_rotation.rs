@@ -0,0 +1,99 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, target_arch::Instant};
+use libp2p::{identity::Keypair, identity::PublicKey, PeerId};
+use std::{collections::HashMap, time::Duration};
+
+/// How long the peers which rotated their key are remembered, for them going away not to be
+/// taken for churn.
+const ROTATION_WINDOW: Duration = Duration::from_secs(3600);
+
+// Prefixed to what the new key signs, for the signature not to be replayed as another message.
+const KEY_ROTATION_DOMAIN: &[u8] = b"safenode key rotation";
+
+fn rotation_payload(old_peer: &PeerId) -> Vec<u8> {
+    [KEY_ROTATION_DOMAIN, &old_peer.to_bytes()].concat()
+}
+
+/// Signs the move of the peer to the new key, returning the encoded new public key and its
+/// signature of the move.
+pub(crate) fn sign_key_rotation(
+    old_peer: PeerId,
+    new_keypair: &Keypair,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let signature = new_keypair.sign(&rotation_payload(&old_peer))?;
+    Ok((new_keypair.public().encode_protobuf(), signature))
+}
+
+/// Returns the `PeerId` the peer moved to, if the new key did sign the move.
+pub(crate) fn verify_key_rotation(
+    old_peer: PeerId,
+    new_public_key: &[u8],
+    signature: &[u8],
+) -> Option<PeerId> {
+    let new_public_key = PublicKey::try_decode_protobuf(new_public_key).ok()?;
+    new_public_key
+        .verify(&rotation_payload(&old_peer), signature)
+        .then(|| new_public_key.to_peer_id())
+}
+
+/// The peers which recently moved to a new key, by their former `PeerId`.
+#[derive(Debug, Default)]
+pub(crate) struct RotatedPeers {
+    rotations: HashMap<PeerId, (PeerId, Instant)>,
+}
+
+impl RotatedPeers {
+    pub(crate) fn insert(&mut self, old_peer: PeerId, new_peer: PeerId) {
+        let now = Instant::now();
+        self.rotations
+            .retain(|_, (_, rotated_at)| now.duration_since(*rotated_at) < ROTATION_WINDOW);
+        let _ = self.rotations.insert(old_peer, (new_peer, now));
+    }
+
+    /// The `PeerId` the peer moved to, if it rotated its key lately.
+    pub(crate) fn successor(&self, old_peer: &PeerId) -> Option<PeerId> {
+        self.rotations
+            .get(old_peer)
+            .filter(|(_, rotated_at)| rotated_at.elapsed() < ROTATION_WINDOW)
+            .map(|(new_peer, _)| *new_peer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_new_key_can_sign_a_rotation() -> eyre::Result<()> {
+        let old_peer = PeerId::random();
+        let new_keypair = Keypair::generate_ed25519();
+        let new_peer = new_keypair.public().to_peer_id();
+
+        let (public_key, signature) = sign_key_rotation(old_peer, &new_keypair)?;
+        assert_eq!(
+            verify_key_rotation(old_peer, &public_key, &signature),
+            Some(new_peer)
+        );
+        // the signature doesn't hold for another peer
+        assert_eq!(
+            verify_key_rotation(PeerId::random(), &public_key, &signature),
+            None
+        );
+        // nor for another key
+        let other_key = Keypair::generate_ed25519().public().encode_protobuf();
+        assert_eq!(verify_key_rotation(old_peer, &other_key, &signature), None);
+
+        let mut rotated_peers = RotatedPeers::default();
+        rotated_peers.insert(old_peer, new_peer);
+        assert_eq!(rotated_peers.successor(&old_peer), Some(new_peer));
+        assert_eq!(rotated_peers.successor(&new_peer), None);
+        Ok(())
+    }
+}
@@ -9,21 +9,35 @@
 #[macro_use]
 extern crate tracing;
 
+mod bandwidth;
 mod bootstrap;
 mod circular_vec;
 mod cmd;
+mod connection_pool;
 mod driver;
 mod error;
 mod event;
 mod external_address;
+mod kad_params;
+mod key_rotation;
+#[cfg(feature = "local-discovery")]
+mod local_discovery;
 mod log_markers;
 #[cfg(feature = "open-metrics")]
 mod metrics;
 #[cfg(feature = "open-metrics")]
 mod metrics_service;
 mod network_discovery;
+mod network_key;
+mod peer_access;
+mod peer_scores;
+mod pricing;
+mod pubsub;
+mod rate_limiter;
+mod record_journal;
 mod record_store;
 mod record_store_api;
+mod record_store_backend;
 mod relay_manager;
 mod replication_fetcher;
 mod spends;
@@ -36,19 +50,46 @@ use cmd::LocalSwarmCmd;
 // re-export arch dependent deps for use in the crate, or above
 pub use target_arch::{interval, sleep, spawn, Instant, Interval};
 
+#[cfg(feature = "open-metrics")]
+pub use metrics_service::run_metrics_server;
+
 pub use self::{
+    bandwidth::{Bandwidth, UNKNOWN_PROTOCOL},
     cmd::{NodeIssue, SwarmLocalState},
     driver::{
         GetRecordCfg, NetworkBuilder, PutRecordCfg, SwarmDriver, VerificationKind, MAX_PACKET_SIZE,
     },
     error::{GetRecordError, NetworkError},
     event::{MsgResponder, NetworkEvent},
-    record_store::{calculate_cost_for_records, NodeRecordStore},
+    kad_params::KademliaParams,
+    network_key::{NetworkKey, NETWORK_KEY_FILE_ENV},
+    peer_access::PeerAccessList,
+    pricing::{
+        DefaultPricing, FlatPricing, PricingStrategy, ScriptedPricing, UtilizationCurvePricing,
+    },
+    pubsub::{PubSubLimits, TopicLimits},
+    rate_limiter::RequestRateLimits,
+    record_store::{calculate_cost_for_records, DiskUsage, NodeRecordStore, PruningPolicy},
+    record_store_backend::{
+        migrate_records, DiskRecordStoreBackend, RecordStoreBackend, RecordStoreStats,
+    },
+    relay_manager::RelayLimits,
+    replication_fetcher::ReplicationLimits,
     transfers::{get_raw_signed_spends_from_record, get_signed_spend_from_record},
+    transport::TransportMode,
 };
-
-use self::{cmd::NetworkSwarmCmd, error::Result};
+#[cfg(feature = "local-discovery")]
+pub use local_discovery::LocalDiscovery;
+#[cfg(feature = "rocksdb")]
+pub use record_store_backend::RocksDbRecordStoreBackend;
+#[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+pub use transport::WebRtcListener;
+#[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+pub use transport::{WebSocketListener, WebSocketTls};
+
+use self::{bandwidth::BandwidthAccounting, cmd::NetworkSwarmCmd, error::Result};
 use backoff::{Error as BackoffError, ExponentialBackoff};
+use bytes::Bytes;
 use futures::future::select_all;
 use libp2p::{
     identity::Keypair,
@@ -76,13 +117,19 @@ use tokio::sync::{
     oneshot,
 };
 use tokio::time::Duration;
+use xor_name::XorName;
 
 /// The type of quote for a selected payee.
 pub type PayeeQuote = (PeerId, MainPubkey, PaymentQuote);
 
 /// The count of peers that will be considered as close to a record target,
-/// that a replication of the record shall be sent/accepted to/by the peer.
-pub const REPLICATION_PEERS_COUNT: usize = CLOSE_GROUP_SIZE + 2;
+/// that a replication of the record shall be sent/accepted to/by the peer,
+/// with the default replication factor. See `KademliaParams::replication_peers_count`.
+pub const REPLICATION_PEERS_COUNT: usize = CLOSE_GROUP_SIZE + REPLICATION_LEEWAY;
+
+/// The peers past the replication factor a record is replicated to, to allow for divergent
+/// knowledge of the closest peers.
+const REPLICATION_LEEWAY: usize = 2;
 
 /// Majority of a given group (i.e. > 1/2).
 #[inline]
@@ -163,15 +210,19 @@ struct NetworkInner {
     peer_id: PeerId,
     root_dir_path: PathBuf,
     keypair: Keypair,
+    bandwidth: BandwidthAccounting,
+    replication_peers_count: usize,
 }
 
 impl Network {
-    pub fn new(
+    pub(crate) fn new(
         network_swarm_cmd_sender: mpsc::Sender<NetworkSwarmCmd>,
         local_swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
         peer_id: PeerId,
         root_dir_path: PathBuf,
         keypair: Keypair,
+        bandwidth: BandwidthAccounting,
+        replication_peers_count: usize,
     ) -> Self {
         Self {
             inner: Arc::new(NetworkInner {
@@ -180,6 +231,8 @@ impl Network {
                 peer_id,
                 root_dir_path,
                 keypair,
+                bandwidth,
+                replication_peers_count,
             }),
         }
     }
@@ -194,11 +247,23 @@ impl Network {
         &self.inner.keypair
     }
 
+    /// Returns the count of peers close to a record that it's replicated to, set by the
+    /// replication factor of the Kademlia params.
+    pub fn replication_peers_count(&self) -> usize {
+        self.inner.replication_peers_count
+    }
+
     /// Returns the root directory path of the instance.
     pub fn root_dir_path(&self) -> &PathBuf {
         &self.inner.root_dir_path
     }
 
+    /// Returns the bytes sent to and received from each of the peers, per protocol, since the
+    /// start. Only the most active peers are kept once there are too many of them.
+    pub fn bandwidth_per_peer(&self) -> BTreeMap<PeerId, BTreeMap<String, Bandwidth>> {
+        self.inner.bandwidth.per_peer()
+    }
+
     /// Get the sender to send a `NetworkSwarmCmd` to the underlying `Swarm`.
     pub(crate) fn network_swarm_cmd_sender(&self) -> &mpsc::Sender<NetworkSwarmCmd> {
         &self.inner.network_swarm_cmd_sender
@@ -266,6 +331,20 @@ impl Network {
             .map_err(|_e| NetworkError::InternalMsgChannelDropped)
     }
 
+    /// Returns the CLOSE_GROUP_SIZE peers closest to the address in our local Routing Table,
+    /// which doesn't contain our own PeerId.
+    pub async fn get_close_group_local_peers(&self, key: &NetworkAddress) -> Result<Vec<PeerId>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetCloseGroupLocalPeers {
+            key: key.clone(),
+            sender,
+        });
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
     /// Get the Chunk existence proof from the close nodes to the provided chunk address.
     pub async fn verify_chunk_existence(
         &self,
@@ -298,33 +377,9 @@ impl Network {
                 "Getting ChunkProof for {pretty_key:?}. Attempts: {retry_attempts:?}/{total_attempts:?}",
             );
 
-            let request = Request::Query(Query::GetChunkExistenceProof {
-                key: chunk_address.clone(),
-                nonce,
-            });
-            let responses = self
-                .send_and_get_responses(&close_nodes, &request, true)
+            let n_verified = self
+                .count_chunk_proofs(&close_nodes, &chunk_address, nonce, &expected_proof)
                 .await;
-            let n_verified = responses
-                .into_iter()
-                .filter_map(|(peer, resp)| {
-                    if let Ok(Response::Query(QueryResponse::GetChunkExistenceProof(Ok(proof)))) =
-                        resp
-                    {
-                        if expected_proof.verify(&proof) {
-                            debug!("Got a valid ChunkProof from {peer:?}");
-                            Some(())
-                        } else {
-                            warn!("Failed to verify the ChunkProof from {peer:?}. The chunk might have been tampered?");
-                            None
-                        }
-                    } else {
-                        debug!("Did not get a valid response for the ChunkProof from {peer:?}");
-                        None
-                    }
-                })
-                .count();
-            debug!("Got {n_verified} verified chunk existence proofs for chunk_address {chunk_address:?}");
 
             if n_verified >= expected_n_verified {
                 return Ok(());
@@ -344,6 +399,88 @@ impl Network {
         ))
     }
 
+    /// Get the number of close nodes to the provided chunk address which prove they hold the chunk.
+    pub async fn count_chunk_holders(
+        &self,
+        chunk_address: NetworkAddress,
+        nonce: Nonce,
+        expected_proof: ChunkProof,
+    ) -> Result<usize> {
+        let close_nodes = self.get_closest_peers(&chunk_address, true).await?;
+        Ok(self
+            .count_chunk_proofs(&close_nodes, &chunk_address, nonce, &expected_proof)
+            .await)
+    }
+
+    // Ask the nodes for the proof they hold the chunk, returning how many proved it.
+    async fn count_chunk_proofs(
+        &self,
+        nodes: &[PeerId],
+        chunk_address: &NetworkAddress,
+        nonce: Nonce,
+        expected_proof: &ChunkProof,
+    ) -> usize {
+        let request = Request::Query(Query::GetChunkExistenceProof {
+            key: chunk_address.clone(),
+            nonce,
+        });
+        let responses = self.send_and_get_responses(nodes, &request, true).await;
+        let n_verified = responses
+            .into_iter()
+            .filter_map(|(peer, resp)| {
+                if let Ok(Response::Query(QueryResponse::GetChunkExistenceProof(Ok(proof)))) = resp
+                {
+                    if expected_proof.verify(&proof) {
+                        debug!("Got a valid ChunkProof from {peer:?}");
+                        Some(())
+                    } else {
+                        warn!("Failed to verify the ChunkProof from {peer:?}. The chunk might have been tampered?");
+                        None
+                    }
+                } else {
+                    debug!("Did not get a valid response for the ChunkProof from {peer:?}");
+                    None
+                }
+            })
+            .count();
+        debug!(
+            "Got {n_verified} verified chunk existence proofs for chunk_address {chunk_address:?}"
+        );
+        n_verified
+    }
+
+    /// Get the copy of a record held by each of the closest peers to its address, for the copies to
+    /// be compared.
+    pub async fn get_record_from_holders(&self, address: &NetworkAddress) -> Result<HolderCopies> {
+        let holders = self.get_closest_peers(address, true).await?;
+        let request = Request::Query(Query::GetReplicatedRecord {
+            requester: NetworkAddress::from_peer(self.peer_id()),
+            key: address.clone(),
+        });
+        let mut copies = HolderCopies::default();
+        for (peer, resp) in self.send_and_get_responses(&holders, &request, true).await {
+            match resp {
+                Ok(Response::Query(QueryResponse::GetReplicatedRecord(Ok((_, value))))) => {
+                    let (_, peers) = copies
+                        .copies
+                        .entry(XorName::from_content(&value))
+                        .or_insert_with(|| (value.to_vec(), BTreeSet::new()));
+                    let _ = peers.insert(peer);
+                }
+                other => {
+                    debug!("{peer:?} returned no copy of {address:?}: {other:?}");
+                    let _ = copies.missing.insert(peer);
+                }
+            }
+        }
+        debug!(
+            "Got {} distinct copies of {address:?}, {} holders returned none",
+            copies.copies.len(),
+            copies.missing.len()
+        );
+        Ok(copies)
+    }
+
     /// Get the store costs from the majority of the closest peers to the provided RecordKey.
     /// Record already exists will have a cost of zero to be returned.
     ///
@@ -541,9 +678,50 @@ impl Network {
             .map_err(|_e| NetworkError::InternalMsgChannelDropped)
     }
 
-    /// Notify the node receicced a payment.
-    pub fn notify_payment_received(&self) {
-        self.send_local_swarm_cmd(LocalSwarmCmd::PaymentReceived);
+    /// Notify the node receicced a payment for the record.
+    pub fn notify_payment_received(&self, key: RecordKey) {
+        self.send_local_swarm_cmd(LocalSwarmCmd::PaymentReceived { key });
+    }
+
+    /// Returns the disk taken by the records of the node, along with its quota.
+    pub async fn get_disk_usage(&self) -> Result<DiskUsage> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetDiskUsage { sender });
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Asks the closest peers to each of the records of the node to fetch it from us, for them
+    /// not to be lost when the node leaves the network. Returns the number of records handed over.
+    ///
+    /// The peers fetch the records in the background, so the node has to keep running a while.
+    pub async fn handover_records(&self) -> Result<usize> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::HandoverRecords { sender });
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Notifies the closest peers that the node is leaving the network, for them to drop it from
+    /// their routing table at once.
+    pub fn announce_departure(&self) {
+        self.send_local_swarm_cmd(LocalSwarmCmd::AnnounceDeparture)
+    }
+
+    /// Notifies the closest peers that the node is moving to the new key, for them to carry its
+    /// standing over to the new `PeerId` and not take the node going away for churn.
+    pub fn announce_key_rotation(&self, new_keypair: &Keypair) -> Result<()> {
+        let (new_public_key, signature) =
+            key_rotation::sign_key_rotation(self.peer_id(), new_keypair)?;
+        self.send_local_swarm_cmd(LocalSwarmCmd::AnnounceKeyRotation {
+            new_public_key,
+            signature,
+        });
+        Ok(())
     }
 
     /// Get `Record` from the local RecordStore
@@ -783,6 +961,29 @@ impl Network {
         self.send_network_swarm_cmd(NetworkSwarmCmd::SendResponse { resp, channel })
     }
 
+    /// Subscribe to the pub/sub topic, the messages published on it being delivered as
+    /// `NetworkEvent::PubSubMessage`s.
+    pub async fn subscribe_to_topic(&self, topic: String) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_network_swarm_cmd(NetworkSwarmCmd::SubscribeToTopic { topic, sender });
+        receiver.await?
+    }
+
+    /// Unsubscribe from the pub/sub topic.
+    pub async fn unsubscribe_from_topic(&self, topic: String) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_network_swarm_cmd(NetworkSwarmCmd::UnsubscribeFromTopic { topic, sender });
+        receiver.await?
+    }
+
+    /// Publish the message on the pub/sub topic, to the peers subscribed to it. Errors out if the
+    /// message is over the size or the rate limit of the topic.
+    pub async fn publish_on_topic(&self, topic: String, msg: Bytes) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_network_swarm_cmd(NetworkSwarmCmd::PublishOnTopic { topic, msg, sender });
+        receiver.await?
+    }
+
     /// Return a `SwarmLocalState` with some information obtained from swarm's local state.
     pub async fn get_swarm_local_state(&self) -> Result<SwarmLocalState> {
         let (sender, receiver) = oneshot::channel();
@@ -803,6 +1004,11 @@ impl Network {
         self.send_local_swarm_cmd(LocalSwarmCmd::QuoteVerification { quotes });
     }
 
+    /// Remove a corrupted record from the local RecordStore, for it to be fetched again.
+    pub fn quarantine_local_record(&self, key: RecordKey) {
+        self.send_local_swarm_cmd(LocalSwarmCmd::QuarantineLocalRecord { key });
+    }
+
     pub fn trigger_unrelevant_record_cleanup(&self) {
         self.send_local_swarm_cmd(LocalSwarmCmd::TriggerUnrelevantRecordCleanup)
     }
@@ -931,6 +1137,15 @@ fn get_fees_from_store_cost_responses(
     Ok((payee_id, payee.1, payee.2))
 }
 
+/// The copies of a record returned by its holders, as fetched by `Network::get_record_from_holders`.
+#[derive(Clone, Debug, Default)]
+pub struct HolderCopies {
+    /// The copies by the hash of their content, along with the holders which returned them.
+    pub copies: BTreeMap<XorName, (Vec<u8>, BTreeSet<PeerId>)>,
+    /// The holders which returned no copy.
+    pub missing: BTreeSet<PeerId>,
+}
+
 /// Get the value of the provided Quorum
 pub fn get_quorum_value(quorum: &Quorum) -> usize {
     match quorum {
@@ -941,7 +1156,7 @@ pub fn get_quorum_value(quorum: &Quorum) -> usize {
     }
 }
 
-/// Verifies if `Multiaddr` contains IPv4 address that is not global.
+/// Verifies if `Multiaddr` contains an IPv4 or IPv6 address that is not global.
 /// This is used to filter out unroutable addresses from the Kademlia routing table.
 pub fn multiaddr_is_global(multiaddr: &Multiaddr) -> bool {
     !multiaddr.iter().any(|addr| match addr {
@@ -955,6 +1170,17 @@ pub fn multiaddr_is_global(multiaddr: &Multiaddr) -> bool {
                 | ip.is_documentation()
                 | ip.is_broadcast()
         }
+        Protocol::Ip6(ip) => {
+            // Based on the nightly `Ipv6Addr::is_global`, only using what is available in stable.
+            // Unique local `fc00::/7`, unicast link-local `fe80::/10` and documentation `2001:db8::/32`.
+            let segments = ip.segments();
+            ip.is_unspecified()
+                | ip.is_loopback()
+                | ip.is_multicast()
+                | ((segments[0] & 0xfe00) == 0xfc00)
+                | ((segments[0] & 0xffc0) == 0xfe80)
+                | (segments[0] == 0x2001 && segments[1] == 0xdb8)
+        }
         _ => false,
     })
 }
@@ -1112,6 +1338,33 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_multiaddr_is_global_for_both_ip_families() -> eyre::Result<()> {
+        for addr in [
+            "/ip4/198.52.100.1/udp/1200/quic-v1",
+            "/ip6/2a01:4f8::1/udp/1200/quic-v1",
+        ] {
+            assert!(
+                multiaddr_is_global(&addr.parse()?),
+                "{addr} should be global"
+            );
+        }
+        for addr in [
+            "/ip4/192.168.1.1/udp/1200/quic-v1",
+            "/ip6/::/udp/1200/quic-v1",
+            "/ip6/::1/udp/1200/quic-v1",
+            "/ip6/fd00::1/udp/1200/quic-v1",
+            "/ip6/fe80::1/tcp/1200",
+            "/ip6/2001:db8::1/tcp/1200",
+        ] {
+            assert!(
+                !multiaddr_is_global(&addr.parse()?),
+                "{addr} shouldn't be global"
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_network_sign_verify() -> eyre::Result<()> {
         let (network, _, _) =
@@ -0,0 +1,95 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::multiaddr_get_ip;
+use ipnet::IpNet;
+use libp2p::{mdns, Multiaddr};
+use std::time::Duration;
+
+/// The default interval the local network is queried at. Lower than the libp2p one to speed up
+/// the discovery, which increases the traffic, but means the clients aren't left unable to
+/// connect for a few minutes.
+const DEFAULT_QUERY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How a node or client discovers the peers on its local network over mDNS, without being given
+/// their addresses, e.g. for a local testnet or a LAN deployment.
+///
+/// libp2p announces all the peers under the `_p2p._udp.local` service, which can't be changed, so
+/// the nodes of the other networks on the LAN are discovered as well. With a `NetworkKey`, their
+/// connections don't outlive the handshake or the identify exchange, the key being part of both,
+/// and the local discovery is off unless asked for, not to dial them in vain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalDiscovery {
+    /// The networks of the interfaces to discover the peers on, the peers found at an address
+    /// outside of them being left out. All the interfaces if empty. Only IPv6 is queried over if
+    /// all of them are IPv6 ones.
+    pub interfaces: Vec<IpNet>,
+    /// How often the local network is queried.
+    pub query_interval: Duration,
+}
+
+impl Default for LocalDiscovery {
+    fn default() -> Self {
+        Self {
+            interfaces: Vec::new(),
+            query_interval: DEFAULT_QUERY_INTERVAL,
+        }
+    }
+}
+
+impl LocalDiscovery {
+    pub(crate) fn mdns_config(&self) -> mdns::Config {
+        let enable_ipv6 =
+            !self.interfaces.is_empty() && self.interfaces.iter().all(|net| net.addr().is_ipv6());
+        mdns::Config {
+            query_interval: self.query_interval,
+            enable_ipv6,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the discovered address is on one of the interfaces discovered on.
+    pub(crate) fn is_on_interfaces(&self, addr: &Multiaddr) -> bool {
+        if self.interfaces.is_empty() {
+            return true;
+        }
+        multiaddr_get_ip(addr).is_some_and(|ip| self.interfaces.iter().any(|net| net.contains(&ip)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovered_addresses_are_kept_to_the_given_interfaces() -> eyre::Result<()> {
+        let on_lan: Multiaddr = "/ip4/192.168.1.20/udp/1200/quic-v1".parse()?;
+        let on_docker: Multiaddr = "/ip4/172.17.0.2/udp/1200/quic-v1".parse()?;
+
+        let all = LocalDiscovery::default();
+        assert!(all.is_on_interfaces(&on_lan));
+        assert!(all.is_on_interfaces(&on_docker));
+        assert!(!all.mdns_config().enable_ipv6);
+
+        let lan = LocalDiscovery {
+            interfaces: vec!["192.168.1.0/24".parse()?],
+            ..Default::default()
+        };
+        assert!(lan.is_on_interfaces(&on_lan));
+        assert!(!lan.is_on_interfaces(&on_docker));
+        assert!(!lan.mdns_config().enable_ipv6);
+
+        let ipv6 = LocalDiscovery {
+            interfaces: vec!["fd00::/8".parse()?],
+            ..Default::default()
+        };
+        assert!(ipv6.mdns_config().enable_ipv6);
+        assert!(!ipv6.is_on_interfaces(&on_lan));
+        Ok(())
+    }
+}
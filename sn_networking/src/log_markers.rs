@@ -38,6 +38,7 @@ impl<'a> Marker<'a> {
     pub fn log(&self) {
         // Down the line, if some logs are noisier than others, we can
         // match the type and log a different level.
-        info!("{self:?}");
+        // The name of the variant is logged as the `event` field, for it to be matched on as is.
+        info!(event = %self, "{self:?}");
     }
 }
@@ -0,0 +1,45 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::bandwidth::BandwidthAccounting;
+use prometheus_client::{
+    encoding::{EncodeLabelSet, EncodeLabelValue},
+    metrics::{counter::Counter, family::Family},
+};
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct BandwidthLabels {
+    protocol: String,
+    direction: Direction,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum Direction {
+    Sent,
+    Received,
+}
+
+/// Brings the counters of the bytes of each protocol up to the ones accounted. The peers are left
+/// out of the labels, there being too many of them, and are only given over the node RPC.
+pub(crate) fn record_protocol_bandwidth(
+    protocol_bandwidth: &Family<BandwidthLabels, Counter>,
+    accounting: &BandwidthAccounting,
+) {
+    for (protocol, bandwidth) in accounting.per_protocol() {
+        for (direction, bytes) in [
+            (Direction::Sent, bandwidth.sent),
+            (Direction::Received, bandwidth.received),
+        ] {
+            let counter = protocol_bandwidth.get_or_create(&BandwidthLabels {
+                protocol: protocol.clone(),
+                direction,
+            });
+            let _ = counter.inc_by(bytes.saturating_sub(counter.get()));
+        }
+    }
+}
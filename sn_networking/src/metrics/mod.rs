@@ -6,21 +6,23 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{log_markers::Marker, target_arch::sleep};
+use crate::{bandwidth::BandwidthAccounting, log_markers::Marker, target_arch::sleep};
 use libp2p::metrics::{Metrics as Libp2pMetrics, Recorder};
-#[cfg(feature = "upnp")]
-use prometheus_client::metrics::family::Family;
 use prometheus_client::{
-    metrics::{counter::Counter, gauge::Gauge},
+    metrics::{counter::Counter, family::Family, gauge::Gauge},
     registry::{Registry, Unit},
 };
 use sysinfo::{Pid, ProcessRefreshKind, System};
 use tokio::time::Duration;
 
+mod bandwidth;
+mod record_store;
 // Implementation to record `libp2p::upnp::Event` metrics
 #[cfg(feature = "upnp")]
 mod upnp;
 
+pub(crate) use record_store::RecordStoreMetrics;
+
 const UPDATE_INTERVAL: Duration = Duration::from_secs(15);
 const TO_MB: u64 = 1_000_000;
 
@@ -38,7 +40,9 @@ pub(crate) struct NetworkMetricsRecorder {
     pub(crate) estimated_network_size: Gauge,
     pub(crate) open_connections: Gauge,
     pub(crate) peers_in_routing_table: Gauge,
-    pub(crate) records_stored: Gauge,
+    pub(crate) record_store: RecordStoreMetrics,
+    pub(crate) replication_queue_depth: Gauge,
+    protocol_bandwidth: Family<bandwidth::BandwidthLabels, Counter>,
 
     // store cost
     store_cost: Gauge,
@@ -61,11 +65,21 @@ impl NetworkMetricsRecorder {
         let libp2p_metrics = Libp2pMetrics::new(registry);
         let sub_registry = registry.sub_registry_with_prefix("sn_networking");
 
-        let records_stored = Gauge::default();
+        let record_store = RecordStoreMetrics::new(sub_registry);
+
+        let replication_queue_depth = Gauge::default();
         sub_registry.register(
-            "records_stored",
-            "The number of records stored locally",
-            records_stored.clone(),
+            "replication_queue_depth",
+            "The number of records waiting to be fetched, or being fetched, for replication",
+            replication_queue_depth.clone(),
+        );
+
+        let protocol_bandwidth = Family::default();
+        sub_registry.register_with_unit(
+            "protocol_bandwidth",
+            "The bytes sent and received over each of the protocols",
+            Unit::Bytes,
+            protocol_bandwidth.clone(),
         );
 
         let connected_peers = Gauge::default();
@@ -173,7 +187,9 @@ impl NetworkMetricsRecorder {
             #[cfg(feature = "upnp")]
             upnp_events,
 
-            records_stored,
+            record_store,
+            replication_queue_depth,
+            protocol_bandwidth,
             estimated_network_size,
             connected_peers,
             open_connections,
@@ -224,6 +240,17 @@ impl NetworkMetricsRecorder {
         });
     }
 
+    // Updates registry with the bytes accounted to each protocol
+    pub(crate) fn bandwidth_recorder_task(&self, accounting: BandwidthAccounting) {
+        let protocol_bandwidth = self.protocol_bandwidth.clone();
+        tokio::spawn(async move {
+            loop {
+                bandwidth::record_protocol_bandwidth(&protocol_bandwidth, &accounting);
+                sleep(UPDATE_INTERVAL).await;
+            }
+        });
+    }
+
     // Records the metric
     pub(crate) fn record_from_marker(&self, log_marker: Marker) {
         match log_marker {
@@ -265,6 +292,12 @@ impl Recorder<libp2p::relay::Event> for NetworkMetricsRecorder {
     }
 }
 
+impl Recorder<libp2p::dcutr::Event> for NetworkMetricsRecorder {
+    fn record(&self, event: &libp2p::dcutr::Event) {
+        self.libp2p_metrics.record(event)
+    }
+}
+
 impl Recorder<libp2p::identify::Event> for NetworkMetricsRecorder {
     fn record(&self, event: &libp2p::identify::Event) {
         self.libp2p_metrics.record(event)
@@ -0,0 +1,143 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use prometheus_client::{
+    encoding::{EncodeLabelSet, EncodeLabelValue},
+    metrics::{
+        counter::Counter,
+        family::Family,
+        gauge::Gauge,
+        histogram::{exponential_buckets, Histogram},
+    },
+    registry::{Registry, Unit},
+};
+use sn_protocol::storage::RecordKind;
+use std::time::Duration;
+
+/// The metrics of the `NodeRecordStore`.
+#[derive(Clone, Debug)]
+pub(crate) struct RecordStoreMetrics {
+    records_stored: Gauge,
+    records_stored_by_kind: Family<RecordKindLabels, Gauge>,
+    stored_bytes: Gauge,
+    record_gets: Counter,
+    record_get_latency: Histogram,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RecordKindLabels {
+    kind: StoredRecordKind,
+}
+
+// The kinds of records once stored, which are stripped from their payment.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum StoredRecordKind {
+    Chunk,
+    Spend,
+    Register,
+    Pointer,
+    Scratchpad,
+    ExpiringData,
+}
+
+impl From<RecordKind> for StoredRecordKind {
+    fn from(kind: RecordKind) -> Self {
+        match kind {
+            RecordKind::Chunk | RecordKind::ChunkWithPayment => StoredRecordKind::Chunk,
+            RecordKind::Spend => StoredRecordKind::Spend,
+            RecordKind::Register | RecordKind::RegisterWithPayment => StoredRecordKind::Register,
+            RecordKind::Pointer | RecordKind::PointerWithPayment => StoredRecordKind::Pointer,
+            RecordKind::Scratchpad | RecordKind::ScratchpadWithPayment => {
+                StoredRecordKind::Scratchpad
+            }
+            RecordKind::ExpiringData | RecordKind::ExpiringDataWithPayment => {
+                StoredRecordKind::ExpiringData
+            }
+        }
+    }
+}
+
+impl RecordStoreMetrics {
+    pub(crate) fn new(sub_registry: &mut Registry) -> Self {
+        let records_stored = Gauge::default();
+        sub_registry.register(
+            "records_stored",
+            "The number of records stored locally",
+            records_stored.clone(),
+        );
+
+        let records_stored_by_kind = Family::default();
+        sub_registry.register(
+            "records_stored_by_kind",
+            "The number of records stored locally, by kind",
+            records_stored_by_kind.clone(),
+        );
+
+        let stored_bytes = Gauge::default();
+        sub_registry.register_with_unit(
+            "stored",
+            "The space taken by the records stored locally, counted uncompressed",
+            Unit::Bytes,
+            stored_bytes.clone(),
+        );
+
+        let record_gets = Counter::default();
+        sub_registry.register(
+            "record_gets",
+            "Number of records read from the store to be served",
+            record_gets.clone(),
+        );
+
+        let record_get_latency = Histogram::new(exponential_buckets(0.0005, 2.0, 14));
+        sub_registry.register_with_unit(
+            "record_get_latency",
+            "The time taken to read a record from the store",
+            Unit::Seconds,
+            record_get_latency.clone(),
+        );
+
+        Self {
+            records_stored,
+            records_stored_by_kind,
+            stored_bytes,
+            record_gets,
+            record_get_latency,
+        }
+    }
+
+    pub(crate) fn set_records_stored(&self, records: usize) {
+        let _ = self.records_stored.set(records as i64);
+    }
+
+    pub(crate) fn record_added(&self, kind: Option<RecordKind>) {
+        if let Some(kind) = kind {
+            let _ = self
+                .records_stored_by_kind
+                .get_or_create(&RecordKindLabels { kind: kind.into() })
+                .inc();
+        }
+    }
+
+    pub(crate) fn record_removed(&self, kind: Option<RecordKind>) {
+        if let Some(kind) = kind {
+            let _ = self
+                .records_stored_by_kind
+                .get_or_create(&RecordKindLabels { kind: kind.into() })
+                .dec();
+        }
+    }
+
+    pub(crate) fn set_stored_bytes(&self, bytes: u64) {
+        let _ = self.stored_bytes.set(bytes as i64);
+    }
+
+    pub(crate) fn record_get(&self, elapsed: Duration) {
+        let _ = self.record_gets.inc();
+        self.record_get_latency.observe(elapsed.as_secs_f64());
+    }
+}
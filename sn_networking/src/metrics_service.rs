@@ -18,11 +18,8 @@ use std::{
 
 const METRICS_CONTENT_TYPE: &str = "application/openmetrics-text;charset=utf-8;version=1.0.0";
 
-pub(crate) fn run_metrics_server(
-    metrics_registry: Registry,
-    metadata_registry: Registry,
-    port: u16,
-) {
+/// Serves the metrics and the metadata of the registries on `localhost:<port>`.
+pub fn run_metrics_server(metrics_registry: Registry, metadata_registry: Registry, port: u16) {
     // todo: containers don't work with localhost.
     let addr = ([127, 0, 0, 1], port).into();
 
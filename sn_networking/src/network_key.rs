@@ -0,0 +1,167 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{NetworkError, Result};
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tiny_keccak::{Hasher, Sha3};
+
+/// The env var the network key file of a node or client is read from, when it's not given one.
+pub const NETWORK_KEY_FILE_ENV: &str = "SAFE_NETWORK_KEY_FILE";
+
+/// The header of a network key file, the format being the one of the libp2p `swarm.key` files.
+const KEY_FILE_HEADER: &str = "/key/swarm/psk/1.0.0/\n/base16/\n";
+
+const KEY_LEN: usize = 32;
+
+/// The key of a private network, shared by all its nodes and clients, for them not to connect to
+/// the nodes of the other networks by mistake, e.g. a testnet and the main network on the same
+/// hosts or bootstrapped off the same peers.
+///
+/// The protocols of the network are named after the key, so the peers without it don't speak
+/// them, and the noise handshakes (TCP, WebSocket and relayed connections) are bound to a secret
+/// derived from it, so they fail with the peers without it. The QUIC and WebRTC handshakes can't
+/// be, the connections to the peers of the other networks being closed once they're identified
+/// instead.
+#[derive(Clone, PartialEq, Eq)]
+pub struct NetworkKey([u8; KEY_LEN]);
+
+impl NetworkKey {
+    /// Generates a new random key, for a new network.
+    pub fn generate() -> Self {
+        Self(rand::random())
+    }
+
+    /// Reads the key from a network key file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        content.parse()
+    }
+
+    /// Reads the key from the file at the `SAFE_NETWORK_KEY_FILE` env var, if it's set.
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(NETWORK_KEY_FILE_ENV) {
+            Ok(path) => Self::from_file(&PathBuf::from(path)).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Writes the key to a new network key file, for it to be handed to the nodes and clients of
+    /// the network. An existing file isn't overwritten, the key of a running network being lost
+    /// otherwise.
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let mut options = OpenOptions::new();
+        let _ = options.write(true).create_new(true);
+        // On Unix systems, make sure only the current user can read/write.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            let _ = options.mode(0o600);
+        }
+        options.open(path)?.write_all(self.to_string().as_bytes())?;
+        Ok(())
+    }
+
+    /// The id of the network, which tells the networks apart without giving their key away.
+    pub fn network_id(&self) -> String {
+        hex::encode(&self.derive(b"safe-network-id")[..8])
+    }
+
+    /// The secret the noise handshakes are bound to.
+    pub(crate) fn handshake_secret(&self) -> Vec<u8> {
+        self.derive(b"safe-network-handshake").to_vec()
+    }
+
+    /// The name of the protocol within the network.
+    pub(crate) fn protocol_str(&self, protocol: &str) -> String {
+        format!("{protocol}/{}", self.network_id())
+    }
+
+    fn derive(&self, context: &[u8]) -> [u8; 32] {
+        let mut sha3 = Sha3::v256();
+        sha3.update(context);
+        sha3.update(&self.0);
+        let mut hash = [0; 32];
+        sha3.finalize(&mut hash);
+        hash
+    }
+}
+
+impl std::str::FromStr for NetworkKey {
+    type Err = NetworkError;
+
+    fn from_str(content: &str) -> Result<Self> {
+        let hex_key = content
+            .strip_prefix(KEY_FILE_HEADER)
+            .ok_or_else(|| NetworkError::InvalidNetworkKey("unknown key format".to_string()))?;
+        let bytes = hex::decode(hex_key.trim())
+            .map_err(|err| NetworkError::InvalidNetworkKey(err.to_string()))?;
+        let key = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            NetworkError::InvalidNetworkKey(format!(
+                "the key is {} bytes, not {KEY_LEN}",
+                bytes.len()
+            ))
+        })?;
+        Ok(Self(key))
+    }
+}
+
+impl fmt::Display for NetworkKey {
+    /// Writes the content of the network key file.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{KEY_FILE_HEADER}{}", hex::encode(self.0))
+    }
+}
+
+// The key is left out of the logs.
+impl fmt::Debug for NetworkKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NetworkKey")
+            .field(&self.network_id())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_written_key_is_read_back_and_tells_the_networks_apart() -> eyre::Result<()> {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("network.key");
+
+        let key = NetworkKey::generate();
+        key.write_to_file(&path)?;
+        assert_eq!(NetworkKey::from_file(&path)?, key);
+        // the key of a network isn't overwritten
+        assert!(NetworkKey::generate().write_to_file(&path).is_err());
+
+        let other = NetworkKey::generate();
+        assert_ne!(key.network_id(), other.network_id());
+        assert_ne!(key.handshake_secret(), other.handshake_secret());
+        assert_ne!(
+            key.protocol_str("/safe/kad"),
+            other.protocol_str("/safe/kad")
+        );
+        assert!(!format!("{key:?}").contains(&hex::encode(key.0)));
+
+        assert!("/key/swarm/psk/1.0.0/\n/base16/\nabcd"
+            .parse::<NetworkKey>()
+            .is_err());
+        assert!(hex::encode(key.0).parse::<NetworkKey>().is_err());
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{NetworkError, Result};
+use ipnet::IpNet;
+use libp2p::{multiaddr::Protocol, swarm::ConnectionId, Multiaddr, PeerId};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    net::IpAddr,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+/// The file the access list of a node is read from by default, in its root dir.
+pub(crate) const PEER_ACCESS_FILENAME: &str = "peer_access";
+
+/// Interval between the checks of the access list file for changes.
+pub(crate) const PEER_ACCESS_RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The peers a node always accepts or always rejects, by `PeerId` or by IP range.
+///
+/// It is read from a file with a rule per line, `#` starting a comment:
+/// ```text
+/// block 12D3KooWRBhwfeP2Y4TCx1SM6s9rUoHhR5STiGwxBhgFRcw3UERE
+/// block 203.0.113.0/24
+/// allow 10.0.0.0/8
+/// default reject
+/// ```
+/// `default reject` has the node reject all the peers not allowed, for a private deployment. A
+/// peer both allowed and blocked is rejected.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PeerAccessList {
+    allowed_peers: BTreeSet<PeerId>,
+    blocked_peers: BTreeSet<PeerId>,
+    allowed_ips: Vec<IpNet>,
+    blocked_ips: Vec<IpNet>,
+    reject_by_default: bool,
+}
+
+/// Whether a peer is let in by the access list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PeerAccess {
+    /// The peer is always accepted, and never shunned.
+    Allowed,
+    /// The peer is always rejected.
+    Blocked,
+    /// The peer is accepted unless it misbehaves.
+    Unlisted,
+}
+
+impl PeerAccessList {
+    /// Parses the rules of an access list, failing on the first invalid one.
+    pub fn parse(rules: &str) -> Result<Self> {
+        let mut list = Self::default();
+        for (index, line) in rules.lines().enumerate() {
+            let rule = line.split('#').next().unwrap_or_default().trim();
+            if rule.is_empty() {
+                continue;
+            }
+            let invalid = || NetworkError::InvalidPeerAccessRule {
+                line: index + 1,
+                rule: rule.to_string(),
+            };
+            let (action, target) = rule.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let target = target.trim();
+            match action {
+                "default" => match target {
+                    "reject" => list.reject_by_default = true,
+                    "accept" => list.reject_by_default = false,
+                    _ => return Err(invalid()),
+                },
+                "allow" | "block" => {
+                    let (peers, ips) = if action == "allow" {
+                        (&mut list.allowed_peers, &mut list.allowed_ips)
+                    } else {
+                        (&mut list.blocked_peers, &mut list.blocked_ips)
+                    };
+                    if let Ok(peer_id) = PeerId::from_str(target) {
+                        let _ = peers.insert(peer_id);
+                    } else if let Ok(range) = IpNet::from_str(target) {
+                        ips.push(range);
+                    } else if let Ok(ip) = IpAddr::from_str(target) {
+                        ips.push(IpNet::from(ip));
+                    } else {
+                        return Err(invalid());
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(list)
+    }
+
+    /// The peers blocked by `PeerId`.
+    pub(crate) fn blocked_peers(&self) -> &BTreeSet<PeerId> {
+        &self.blocked_peers
+    }
+
+    /// Returns whether the peer, connected from the IP if known, is let in.
+    pub(crate) fn access(&self, peer_id: &PeerId, ip: Option<IpAddr>) -> PeerAccess {
+        let matches = |peers: &BTreeSet<PeerId>, ips: &[IpNet]| {
+            peers.contains(peer_id)
+                || ip.is_some_and(|ip| ips.iter().any(|range| range.contains(&ip)))
+        };
+        if matches(&self.blocked_peers, &self.blocked_ips) {
+            PeerAccess::Blocked
+        } else if matches(&self.allowed_peers, &self.allowed_ips) {
+            PeerAccess::Allowed
+        } else if self.reject_by_default {
+            PeerAccess::Blocked
+        } else {
+            PeerAccess::Unlisted
+        }
+    }
+}
+
+/// Applies the access list read from a file to the connections of a node, reloading it whenever
+/// the file changes.
+#[derive(Debug)]
+pub(crate) struct PeerAccessControl {
+    file_path: PathBuf,
+    modified: Option<SystemTime>,
+    list: PeerAccessList,
+    /// The live connections, with the IP the peer connected from if it connected directly.
+    connections: BTreeMap<ConnectionId, (PeerId, Option<IpAddr>)>,
+}
+
+impl PeerAccessControl {
+    /// Reads the access list from the file, an absent file being an empty list.
+    pub(crate) fn load(file_path: PathBuf) -> Result<Self> {
+        let (list, modified) = read_list(&file_path)?;
+        if list != PeerAccessList::default() {
+            info!("Loaded the peer access list from {file_path:?}: {list:?}");
+        }
+        Ok(Self {
+            file_path,
+            modified,
+            list,
+            connections: BTreeMap::new(),
+        })
+    }
+
+    pub(crate) fn list(&self) -> &PeerAccessList {
+        &self.list
+    }
+
+    /// Returns whether the peer is let in, from any of the IPs it's connected from.
+    pub(crate) fn peer_access(&self, peer_id: &PeerId) -> PeerAccess {
+        let mut ips = self
+            .connections
+            .values()
+            .filter(|(peer, _)| peer == peer_id)
+            .map(|(_, ip)| *ip)
+            .peekable();
+        if ips.peek().is_none() {
+            return self.list.access(peer_id, None);
+        }
+        ips.map(|ip| self.list.access(peer_id, ip))
+            .min_by_key(|access| match access {
+                PeerAccess::Blocked => 0,
+                PeerAccess::Allowed => 1,
+                PeerAccess::Unlisted => 2,
+            })
+            .unwrap_or(PeerAccess::Unlisted)
+    }
+
+    /// Tracks a new connection, returning false if it's to be closed.
+    pub(crate) fn on_connection_established(
+        &mut self,
+        connection_id: ConnectionId,
+        peer_id: PeerId,
+        remote_addr: &Multiaddr,
+    ) -> bool {
+        let ip = direct_ip(remote_addr);
+        if self.list.access(&peer_id, ip) == PeerAccess::Blocked {
+            info!("Rejecting the connection of {peer_id:?} from {remote_addr:?}, as per the access list");
+            return false;
+        }
+        let _ = self.connections.insert(connection_id, (peer_id, ip));
+        true
+    }
+
+    pub(crate) fn on_connection_closed(&mut self, connection_id: &ConnectionId) {
+        let _ = self.connections.remove(connection_id);
+    }
+
+    /// Reads the file again if it changed, returning the previous list and the live connections
+    /// the new one rejects. A file that became invalid is ignored, keeping the previous list.
+    pub(crate) fn reload_if_changed(&mut self) -> Option<(PeerAccessList, Vec<ConnectionId>)> {
+        let modified = fs::metadata(&self.file_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified == self.modified {
+            return None;
+        }
+        let list = match read_list(&self.file_path) {
+            Ok((list, _)) => list,
+            Err(err) => {
+                error!(
+                    "Keeping the previous peer access list, as {:?} is invalid: {err}",
+                    self.file_path
+                );
+                self.modified = modified;
+                return None;
+            }
+        };
+        info!(
+            "Reloaded the peer access list from {:?}: {list:?}",
+            self.file_path
+        );
+        self.modified = modified;
+        let previous = std::mem::replace(&mut self.list, list);
+
+        let rejected: Vec<_> = self
+            .connections
+            .iter()
+            .filter(|(_, (peer_id, ip))| self.list.access(peer_id, *ip) == PeerAccess::Blocked)
+            .map(|(connection_id, _)| *connection_id)
+            .collect();
+        for connection_id in &rejected {
+            let _ = self.connections.remove(connection_id);
+        }
+        Some((previous, rejected))
+    }
+}
+
+fn read_list(file_path: &PathBuf) -> Result<(PeerAccessList, Option<SystemTime>)> {
+    match fs::read_to_string(file_path) {
+        Ok(rules) => {
+            let modified = fs::metadata(file_path)?.modified().ok();
+            Ok((PeerAccessList::parse(&rules)?, modified))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Ok((PeerAccessList::default(), None))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+// The IP the peer connected from, unless it connected through a relay, whose IP it'd be.
+fn direct_ip(addr: &Multiaddr) -> Option<IpAddr> {
+    if addr.iter().any(|protocol| protocol == Protocol::P2pCircuit) {
+        return None;
+    }
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_are_let_in_as_per_the_access_list() -> eyre::Result<()> {
+        let (allowed, blocked, other) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let rules = format!(
+            "# a comment\nallow {allowed}\nblock {blocked}\nblock 203.0.113.0/24 # abusive\nallow 10.0.0.0/8\nallow 192.0.2.1\n"
+        );
+        let list = PeerAccessList::parse(&rules)?;
+        let ip = |ip: &str| IpAddr::from_str(ip).ok();
+
+        assert_eq!(list.access(&allowed, None), PeerAccess::Allowed);
+        assert_eq!(list.access(&blocked, ip("10.0.0.1")), PeerAccess::Blocked);
+        assert_eq!(list.access(&other, None), PeerAccess::Unlisted);
+        assert_eq!(list.access(&other, ip("203.0.113.7")), PeerAccess::Blocked);
+        assert_eq!(list.access(&other, ip("10.1.2.3")), PeerAccess::Allowed);
+        assert_eq!(list.access(&other, ip("192.0.2.1")), PeerAccess::Allowed);
+        // the allowed peers are rejected from a blocked range
+        assert_eq!(
+            list.access(&allowed, ip("203.0.113.7")),
+            PeerAccess::Blocked
+        );
+
+        let private = PeerAccessList::parse(&format!("{rules}default reject"))?;
+        assert_eq!(private.access(&other, None), PeerAccess::Blocked);
+        assert_eq!(private.access(&allowed, None), PeerAccess::Allowed);
+
+        assert!(matches!(
+            PeerAccessList::parse("allow 10.0.0.0/8\nblock nobody"),
+            Err(NetworkError::InvalidPeerAccessRule { line: 2, .. })
+        ));
+
+        let relayed: Multiaddr =
+            format!("/ip4/198.51.100.1/udp/1/quic-v1/p2p/{other}/p2p-circuit").parse()?;
+        assert_eq!(direct_ip(&relayed), None);
+        assert_eq!(
+            direct_ip(&"/ip4/10.1.2.3/udp/1/quic-v1".parse()?),
+            ip("10.1.2.3")
+        );
+        Ok(())
+    }
+}
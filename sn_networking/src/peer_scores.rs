@@ -0,0 +1,173 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{target_arch::spawn, NodeIssue};
+use libp2p::PeerId;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+const PEER_SCORES_FILENAME: &str = "peer_scores";
+
+/// The score a peer is shunned at.
+pub(crate) const SHUN_THRESHOLD: f64 = 15.0;
+
+// The time it takes for the score of a peer to halve, so that a peer which misbehaved once in a
+// while is not shunned for it in the long run.
+const SCORE_HALF_LIFE: Duration = Duration::from_secs(3600);
+
+// The scores decayed below this are forgotten.
+const MIN_SCORE: f64 = 0.1;
+
+/// The misbehaviour scores of the peers, which add up the strikes of the issues recorded against
+/// them and decay over time. They are kept across restarts, so that a shunned peer can't have
+/// its score reset by waiting for us to restart.
+#[derive(Debug)]
+pub(crate) struct PeerScores {
+    file_path: PathBuf,
+    scores: BTreeMap<PeerId, (f64, SystemTime)>,
+}
+
+impl PeerScores {
+    /// Restores the scores stored in the dir, if any.
+    pub(crate) fn load(dir: &Path) -> Self {
+        let file_path = dir.join(PEER_SCORES_FILENAME);
+        let scores = match fs::read(&file_path) {
+            Ok(bytes) => match rmp_serde::from_slice::<Vec<(Vec<u8>, f64, SystemTime)>>(&bytes) {
+                Ok(entries) => entries
+                    .into_iter()
+                    .filter_map(|(peer, score, updated)| {
+                        Some((PeerId::from_bytes(&peer).ok()?, (score, updated)))
+                    })
+                    .collect(),
+                Err(err) => {
+                    warn!("Failed to deserialize the peer scores from {file_path:?}: {err:?}");
+                    BTreeMap::new()
+                }
+            },
+            Err(_) => BTreeMap::new(),
+        };
+        let mut peer_scores = Self { file_path, scores };
+        peer_scores.prune(SystemTime::now());
+        info!("Restored the scores of {} peers", peer_scores.scores.len());
+        peer_scores
+    }
+
+    /// The peers scoring at the shun threshold or over.
+    pub(crate) fn shunned_peers(&self, now: SystemTime) -> Vec<PeerId> {
+        self.scores
+            .iter()
+            .filter(|(_, (score, updated))| decayed(*score, *updated, now) >= SHUN_THRESHOLD)
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    /// Adds the strike of the issue to the score of the peer, returning the new score.
+    pub(crate) fn add_strike(&mut self, peer: PeerId, issue: &NodeIssue, now: SystemTime) -> f64 {
+        self.prune(now);
+        let (score, updated) = self.scores.entry(peer).or_insert((0.0, now));
+        *score = decayed(*score, *updated, now) + strike(issue);
+        *updated = now;
+        *score
+    }
+
+    /// Moves the score of a peer over to the `PeerId` it rotated its key to.
+    pub(crate) fn carry_over(&mut self, old_peer: PeerId, new_peer: PeerId) {
+        if let Some(entry) = self.scores.remove(&old_peer) {
+            let _ = self.scores.insert(new_peer, entry);
+        }
+    }
+
+    /// Writes the scores to disk, off the calling thread.
+    pub(crate) fn flush(&self) {
+        let file_path = self.file_path.clone();
+        let entries = self.entries();
+        let _handle = spawn(async move {
+            if let Err(err) = save(&file_path, &entries) {
+                warn!("Failed to write the peer scores to {file_path:?}: {err:?}");
+            }
+        });
+    }
+
+    fn entries(&self) -> Vec<(Vec<u8>, f64, SystemTime)> {
+        self.scores
+            .iter()
+            .map(|(peer, (score, updated))| (peer.to_bytes(), *score, *updated))
+            .collect()
+    }
+
+    fn prune(&mut self, now: SystemTime) {
+        self.scores
+            .retain(|_, (score, updated)| decayed(*score, *updated, now) >= MIN_SCORE);
+    }
+}
+
+// How much an issue weighs on the score of a peer. The issues which prove the peer misbehaved
+// weigh the most, while the ones which may be down to the network conditions or to our own view
+// of it weigh the least.
+fn strike(issue: &NodeIssue) -> f64 {
+    match issue {
+        NodeIssue::ConnectionIssue => 1.0,
+        NodeIssue::ReplicationFailure => 2.0,
+        NodeIssue::CloseNodesShunning => 3.0,
+        NodeIssue::BadQuoting => 5.0,
+        NodeIssue::FailedChunkProofCheck => 5.0,
+        NodeIssue::DivergentRecord => 5.0,
+        NodeIssue::InvalidRecord => 5.0,
+        NodeIssue::FailedStorageChallenge => 4.0,
+    }
+}
+
+fn decayed(score: f64, updated: SystemTime, now: SystemTime) -> f64 {
+    let elapsed = now.duration_since(updated).unwrap_or_default();
+    score * 0.5f64.powf(elapsed.as_secs_f64() / SCORE_HALF_LIFE.as_secs_f64())
+}
+
+fn save(file_path: &Path, entries: &[(Vec<u8>, f64, SystemTime)]) -> io::Result<()> {
+    let bytes = rmp_serde::to_vec(entries).map_err(io::Error::other)?;
+    fs::write(file_path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_are_shunned_until_their_score_decays() -> eyre::Result<()> {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir)?;
+        let mut peer_scores = PeerScores::load(&dir);
+        let (flaky, bad) = (PeerId::random(), PeerId::random());
+        let now = SystemTime::now();
+
+        for _ in 0..5 {
+            let _ = peer_scores.add_strike(flaky, &NodeIssue::ConnectionIssue, now);
+        }
+        for _ in 0..2 {
+            let _ = peer_scores.add_strike(bad, &NodeIssue::InvalidRecord, now);
+        }
+        assert!(peer_scores.shunned_peers(now).is_empty());
+        assert_eq!(
+            peer_scores.add_strike(bad, &NodeIssue::FailedChunkProofCheck, now),
+            15.0
+        );
+        assert_eq!(peer_scores.shunned_peers(now), vec![bad]);
+
+        // the scores survive a restart.
+        save(&peer_scores.file_path, &peer_scores.entries())?;
+        let peer_scores = PeerScores::load(&dir);
+        assert_eq!(peer_scores.shunned_peers(now), vec![bad]);
+
+        // and the misbehaviour is forgiven once it's long past.
+        assert!(peer_scores.shunned_peers(now + SCORE_HALF_LIFE).is_empty());
+        Ok(())
+    }
+}
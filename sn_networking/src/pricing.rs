@@ -0,0 +1,352 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    error::{NetworkError, Result},
+    record_store::calculate_cost_for_records,
+    CLOSE_GROUP_SIZE,
+};
+use sn_transfers::{QuotingMetrics, TOTAL_SUPPLY};
+use std::fmt::Debug;
+
+/// The most a record may cost to store, for a payment to the close group not to exceed the
+/// supply.
+const MAX_STORE_COST: u64 = TOTAL_SUPPLY / CLOSE_GROUP_SIZE as u64;
+
+/// How a node prices the storing of a record, from the state it's in.
+///
+/// The nodes check the quotes of their peers against their own strategy, flagging those which
+/// don't match, so the nodes of a network are to use the same strategy.
+pub trait PricingStrategy: Debug + Send + Sync {
+    /// The cost of storing a record, in nanos, for a node in the state of the metrics.
+    fn store_cost(&self, quoting_metrics: &QuotingMetrics) -> u64;
+}
+
+/// The pricing of the network: linear in the records stored up to 60% of the max records, then
+/// exponential, made cheaper by the payments received and the time the node has been around.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultPricing;
+
+impl PricingStrategy for DefaultPricing {
+    fn store_cost(&self, quoting_metrics: &QuotingMetrics) -> u64 {
+        calculate_cost_for_records(quoting_metrics)
+    }
+}
+
+/// The same cost for any record, whatever the state of the node.
+#[derive(Clone, Copy, Debug)]
+pub struct FlatPricing {
+    pub cost: u64,
+}
+
+impl PricingStrategy for FlatPricing {
+    fn store_cost(&self, _quoting_metrics: &QuotingMetrics) -> u64 {
+        self.cost.min(MAX_STORE_COST)
+    }
+}
+
+/// A cost growing with the share of the max records stored, from `min_cost` when empty to
+/// `max_cost` when full, as the share to the power of `exponent`.
+#[derive(Clone, Copy, Debug)]
+pub struct UtilizationCurvePricing {
+    pub min_cost: u64,
+    pub max_cost: u64,
+    pub exponent: f64,
+}
+
+impl PricingStrategy for UtilizationCurvePricing {
+    fn store_cost(&self, quoting_metrics: &QuotingMetrics) -> u64 {
+        let utilization =
+            quoting_metrics.close_records_stored as f64 / quoting_metrics.max_records.max(1) as f64;
+        let range = self.max_cost.saturating_sub(self.min_cost) as f64;
+        capped(self.min_cost as f64 + range * utilization.clamp(0.0, 1.0).powf(self.exponent))
+    }
+}
+
+/// A cost worked out by a formula of the operator, e.g.
+/// ```text
+/// 10 * records * 1.02 ^ max(0, records - 0.6 * max_records) / max(1, live_days)
+/// ```
+///
+/// The formula is made of numbers, `+ - * / ^`, parentheses, `min(a, b)`, `max(a, b)`, and the
+/// metrics of the node: `records` stored close to it, `max_records`, `payments` received,
+/// `live_time` in seconds and `live_days`. The cost is rounded down, and capped to the supply.
+#[derive(Clone, Debug)]
+pub struct ScriptedPricing {
+    formula: Expr,
+}
+
+impl ScriptedPricing {
+    /// Parses the formula, failing if it's invalid.
+    pub fn parse(formula: &str) -> Result<Self> {
+        let tokens = tokenize(formula)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expr()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(NetworkError::InvalidPricingScript(format!(
+                "unexpected {token:?}"
+            )));
+        }
+        Ok(Self { formula: expr })
+    }
+}
+
+impl PricingStrategy for ScriptedPricing {
+    fn store_cost(&self, quoting_metrics: &QuotingMetrics) -> u64 {
+        capped(self.formula.eval(quoting_metrics))
+    }
+}
+
+fn capped(cost: f64) -> u64 {
+    if cost.is_nan() || cost <= 0.0 {
+        0
+    } else if cost >= MAX_STORE_COST as f64 {
+        MAX_STORE_COST
+    } else {
+        cost as u64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(f64),
+    Metric(Metric),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Min(Box<Expr>, Box<Expr>),
+    Max(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Metric {
+    Records,
+    MaxRecords,
+    Payments,
+    LiveTime,
+    LiveDays,
+}
+
+impl Expr {
+    fn eval(&self, metrics: &QuotingMetrics) -> f64 {
+        match self {
+            Expr::Number(number) => *number,
+            Expr::Metric(metric) => match metric {
+                Metric::Records => metrics.close_records_stored as f64,
+                Metric::MaxRecords => metrics.max_records as f64,
+                Metric::Payments => metrics.received_payment_count as f64,
+                Metric::LiveTime => metrics.live_time as f64,
+                Metric::LiveDays => (metrics.live_time / (24 * 3600)) as f64,
+            },
+            Expr::Neg(expr) => -expr.eval(metrics),
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(metrics), rhs.eval(metrics));
+                match op {
+                    '+' => lhs + rhs,
+                    '-' => lhs - rhs,
+                    '*' => lhs * rhs,
+                    '/' => lhs / rhs,
+                    _ => lhs.powf(rhs),
+                }
+            }
+            Expr::Min(lhs, rhs) => lhs.eval(metrics).min(rhs.eval(metrics)),
+            Expr::Max(lhs, rhs) => lhs.eval(metrics).max(rhs.eval(metrics)),
+        }
+    }
+}
+
+fn tokenize(formula: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = formula.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            let _ = chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                number.push(c);
+                let _ = chars.next();
+            }
+            let number = number.parse().map_err(|_| {
+                NetworkError::InvalidPricingScript(format!("invalid number {number:?}"))
+            })?;
+            tokens.push(Token::Number(number));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+            {
+                ident.push(c);
+                let _ = chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else if "+-*/^(),".contains(c) {
+            tokens.push(Token::Op(c));
+            let _ = chars.next();
+        } else {
+            return Err(NetworkError::InvalidPricingScript(format!(
+                "unexpected {c:?}"
+            )));
+        }
+    }
+    Ok(tokens)
+}
+
+// A recursive descent parser of the formula, `^` binding the tightest, and to the right.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.tokens.get(self.pos) == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: char) -> Result<()> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(NetworkError::InvalidPricingScript(format!(
+                "expected {op:?}"
+            )))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut expr = self.term()?;
+        while let Some(op) = ['+', '-'].into_iter().find(|op| self.eat(*op)) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut expr = self.factor()?;
+        while let Some(op) = ['*', '/'].into_iter().find(|op| self.eat(*op)) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.factor()?)));
+        }
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.factor()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::Op('(')) => {
+                let expr = self.expr()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "min" | "max" => {
+                    self.expect('(')?;
+                    let lhs = Box::new(self.expr()?);
+                    self.expect(',')?;
+                    let rhs = Box::new(self.expr()?);
+                    self.expect(')')?;
+                    Ok(if ident == "min" {
+                        Expr::Min(lhs, rhs)
+                    } else {
+                        Expr::Max(lhs, rhs)
+                    })
+                }
+                "records" => Ok(Expr::Metric(Metric::Records)),
+                "max_records" => Ok(Expr::Metric(Metric::MaxRecords)),
+                "payments" => Ok(Expr::Metric(Metric::Payments)),
+                "live_time" => Ok(Expr::Metric(Metric::LiveTime)),
+                "live_days" => Ok(Expr::Metric(Metric::LiveDays)),
+                _ => Err(NetworkError::InvalidPricingScript(format!(
+                    "unknown {ident:?}"
+                ))),
+            },
+            token => Err(NetworkError::InvalidPricingScript(format!(
+                "unexpected {token:?}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_priced_as_per_the_strategy() -> eyre::Result<()> {
+        let metrics = QuotingMetrics {
+            close_records_stored: 1024,
+            max_records: 4096,
+            received_payment_count: 10,
+            live_time: 3 * 24 * 3600,
+        };
+        assert_eq!(
+            DefaultPricing.store_cost(&metrics),
+            calculate_cost_for_records(&metrics)
+        );
+        assert_eq!(FlatPricing { cost: 42 }.store_cost(&metrics), 42);
+
+        let curve = UtilizationCurvePricing {
+            min_cost: 100,
+            max_cost: 1_100,
+            exponent: 2.0,
+        };
+        assert_eq!(curve.store_cost(&metrics), 162);
+        let full = QuotingMetrics {
+            close_records_stored: 5000,
+            ..metrics.clone()
+        };
+        assert_eq!(curve.store_cost(&full), 1_100);
+
+        let scripted =
+            ScriptedPricing::parse("10 * records * 2 ^ -1 / max(1, live_days) + min(payments, 5)")?;
+        assert_eq!(scripted.store_cost(&metrics), 1711);
+        assert_eq!(ScriptedPricing::parse("-records")?.store_cost(&metrics), 0);
+        assert_eq!(
+            ScriptedPricing::parse("10 ^ 30")?.store_cost(&metrics),
+            MAX_STORE_COST
+        );
+        for invalid in ["records +", "unknown * 2", "max(1)", "(records", "2 # 3"] {
+            assert!(
+                matches!(
+                    ScriptedPricing::parse(invalid),
+                    Err(NetworkError::InvalidPricingScript(_))
+                ),
+                "{invalid:?} parsed"
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    driver::SwarmDriver,
+    error::{NetworkError, Result},
+    event::NetworkEvent,
+    target_arch::Instant,
+};
+use bytes::Bytes;
+use libp2p::{gossipsub, PeerId};
+use std::collections::HashMap;
+
+/// The most topic senders the buckets are kept for, past which the full ones are dropped.
+const MAX_TRACKED_SENDERS: usize = 10_000;
+
+/// The limits of the messages published on a topic, for the applications to broadcast lightweight
+/// messages (presence, typing notifications, cache invalidations) without flooding the network.
+///
+/// The rate is a token bucket per sender: each message spends a token out of the bucket of its
+/// sender, which is refilled over time. The messages over the limits aren't published, nor
+/// relayed when received.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TopicLimits {
+    /// The largest message, in bytes.
+    pub max_message_size: usize,
+    /// The most messages a sender can publish at once, i.e. the size of its bucket.
+    pub burst: u32,
+    /// The messages a sender is given back per second, i.e. the steady rate it can publish at.
+    pub per_sec: u32,
+}
+
+impl Default for TopicLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: 4 * 1024,
+            burst: 20,
+            per_sec: 5,
+        }
+    }
+}
+
+/// The limits of the pub/sub topics, the ones not given limits of their own being held to the
+/// `default` ones.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PubSubLimits {
+    /// The limits of the topics not in `topics`.
+    pub default: TopicLimits,
+    /// The limits of the given topics.
+    pub topics: HashMap<String, TopicLimits>,
+}
+
+impl PubSubLimits {
+    /// The limits the topic is held to.
+    pub fn of_topic(&self, topic: &str) -> TopicLimits {
+        self.topics.get(topic).copied().unwrap_or(self.default)
+    }
+
+    /// The largest message on any of the topics, which the transmitted ones are kept under.
+    pub(crate) fn max_message_size(&self) -> usize {
+        self.topics
+            .values()
+            .map(|limits| limits.max_message_size)
+            .fold(self.default.max_message_size, usize::max)
+    }
+}
+
+/// Why a message isn't published, or relayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PubSubRejection {
+    TooLarge { size: usize, max: usize },
+    RateLimited,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Keeps the messages of each sender on each topic within the limits of the topic.
+#[derive(Debug)]
+pub(crate) struct PubSubLimiter {
+    limits: PubSubLimits,
+    buckets: HashMap<(String, PeerId), TokenBucket>,
+}
+
+impl PubSubLimiter {
+    pub(crate) fn new(limits: PubSubLimits) -> Self {
+        Self {
+            limits,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Checks the message of the sender is within the limits of the topic, spending a token out of
+    /// the bucket of the sender if it is.
+    pub(crate) fn check(
+        &mut self,
+        topic: &str,
+        sender: PeerId,
+        size: usize,
+        now: Instant,
+    ) -> std::result::Result<(), PubSubRejection> {
+        let limits = self.limits.of_topic(topic);
+        if size > limits.max_message_size {
+            return Err(PubSubRejection::TooLarge {
+                size,
+                max: limits.max_message_size,
+            });
+        }
+
+        if self.buckets.len() >= MAX_TRACKED_SENDERS {
+            let all_limits = &self.limits;
+            self.buckets.retain(|(topic, _), bucket| {
+                let limits = all_limits.of_topic(topic);
+                refill(bucket, &limits, now) < f64::from(limits.burst)
+            });
+        }
+        let bucket = self
+            .buckets
+            .entry((topic.to_string(), sender))
+            .or_insert_with(|| TokenBucket {
+                tokens: f64::from(limits.burst),
+                refilled_at: now,
+            });
+        if refill(bucket, &limits, now) < 1.0 {
+            return Err(PubSubRejection::RateLimited);
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+impl SwarmDriver {
+    fn gossipsub(&mut self) -> Result<&mut gossipsub::Behaviour> {
+        self.swarm
+            .behaviour_mut()
+            .gossipsub
+            .as_mut()
+            .ok_or(NetworkError::PubSubDisabled)
+    }
+
+    pub(crate) fn subscribe_to_topic(&mut self, topic: &str) -> Result<()> {
+        let topic = gossipsub::IdentTopic::new(topic);
+        if self.gossipsub()?.subscribe(&topic)? {
+            info!("Subscribed to pub/sub topic {topic}");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn unsubscribe_from_topic(&mut self, topic: &str) -> Result<()> {
+        let topic = gossipsub::IdentTopic::new(topic);
+        if self.gossipsub()?.unsubscribe(&topic)? {
+            info!("Unsubscribed from pub/sub topic {topic}");
+        }
+        Ok(())
+    }
+
+    /// Publishes the message on the topic, if within the limits of the topic.
+    pub(crate) fn publish_on_topic(&mut self, topic: &str, msg: Bytes) -> Result<()> {
+        let _ = self.gossipsub()?;
+        self.pubsub_limiter
+            .check(topic, self.self_peer_id, msg.len(), Instant::now())
+            .map_err(|rejection| match rejection {
+                PubSubRejection::TooLarge { size, max } => NetworkError::PubSubMessageTooLarge {
+                    topic: topic.to_string(),
+                    size,
+                    max,
+                },
+                PubSubRejection::RateLimited => NetworkError::PubSubRateLimited(topic.to_string()),
+            })?;
+        let _ = self
+            .gossipsub()?
+            .publish(gossipsub::IdentTopic::new(topic), msg)?;
+        Ok(())
+    }
+
+    /// Delivers the messages within the limits of their topic, which are relayed on, and drops the
+    /// others.
+    pub(crate) fn on_gossipsub_event(&mut self, event: gossipsub::Event) {
+        match event {
+            gossipsub::Event::Message {
+                propagation_source,
+                message_id,
+                message,
+            } => {
+                let topic = message.topic.into_string();
+                let sender = message.source.unwrap_or(propagation_source);
+                let acceptance = match self.pubsub_limiter.check(
+                    &topic,
+                    sender,
+                    message.data.len(),
+                    Instant::now(),
+                ) {
+                    Ok(()) => gossipsub::MessageAcceptance::Accept,
+                    // The oversized messages are against the protocol, the peers relaying
+                    // them being penalised, unlike the ones over the rate.
+                    Err(PubSubRejection::TooLarge { size, max }) => {
+                        warn!("Dropping a message of {size} bytes from {sender:?} on topic {topic:?}, over {max} bytes");
+                        gossipsub::MessageAcceptance::Reject
+                    }
+                    Err(PubSubRejection::RateLimited) => {
+                        debug!("Dropping a message from {sender:?} on topic {topic:?}, over the rate limit");
+                        gossipsub::MessageAcceptance::Ignore
+                    }
+                };
+                let accepted = matches!(acceptance, gossipsub::MessageAcceptance::Accept);
+                if let Ok(gossipsub) = self.gossipsub() {
+                    let _ = gossipsub.report_message_validation_result(
+                        &message_id,
+                        &propagation_source,
+                        acceptance,
+                    );
+                }
+                if accepted {
+                    self.send_event(NetworkEvent::PubSubMessage {
+                        topic,
+                        msg: Bytes::from(message.data),
+                    });
+                }
+            }
+            other => trace!("Gossipsub event: {other:?}"),
+        }
+    }
+}
+
+// Refills the bucket for the time since it last was, returning its tokens.
+fn refill(bucket: &mut TokenBucket, limits: &TopicLimits, now: Instant) -> f64 {
+    let elapsed = now.duration_since(bucket.refilled_at);
+    bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * f64::from(limits.per_sec))
+        .min(f64::from(limits.burst));
+    bucket.refilled_at = now;
+    bucket.tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn messages_are_kept_within_the_limits_of_their_topic() {
+        let chat = TopicLimits {
+            max_message_size: 100,
+            burst: 2,
+            per_sec: 1,
+        };
+        let mut limiter = PubSubLimiter::new(PubSubLimits {
+            default: TopicLimits::default(),
+            topics: HashMap::from([("chat".to_string(), chat)]),
+        });
+        let (sender, other) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        assert_eq!(
+            limiter.check("chat", sender, 101, now),
+            Err(PubSubRejection::TooLarge {
+                size: 101,
+                max: 100
+            })
+        );
+        assert!(limiter.check("presence", sender, 101, now).is_ok());
+
+        assert!(limiter.check("chat", sender, 10, now).is_ok());
+        assert!(limiter.check("chat", sender, 10, now).is_ok());
+        assert_eq!(
+            limiter.check("chat", sender, 10, now),
+            Err(PubSubRejection::RateLimited)
+        );
+        // the other senders, and the other topics, have buckets of their own
+        assert!(limiter.check("chat", other, 10, now).is_ok());
+        assert!(limiter.check("presence", sender, 10, now).is_ok());
+
+        let later = now + Duration::from_secs(1);
+        assert!(limiter.check("chat", sender, 10, later).is_ok());
+        assert!(limiter.check("chat", sender, 10, later).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::target_arch::Instant;
+use libp2p::{kad::Record, PeerId};
+use sn_protocol::{
+    messages::Query,
+    storage::{RecordHeader, RecordKind},
+    NetworkAddress,
+};
+use std::collections::{BTreeSet, HashMap};
+
+/// The most peers the buckets are kept for, past which the least recently refilled is dropped.
+const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// The rates a node serves the requests of each of its clients at, as token buckets: a client
+/// spends the cost of each of its requests out of its bucket, which is refilled over time, and
+/// its requests are dropped while the bucket is short.
+///
+/// The peers in the routing table, which are the nodes the network relies on, and the ones
+/// allowed by the access list aren't limited.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RequestRateLimits {
+    /// The most tokens a client can spend at once, i.e. the size of its bucket.
+    pub burst: u32,
+    /// The tokens a client is given back per second, i.e. the steady rate it's served at.
+    pub per_sec: u32,
+    /// The cost of a query for a record, or its proofs.
+    pub get_cost: u32,
+    /// The cost of storing a record, or of getting a quote for it.
+    pub put_cost: u32,
+    /// The cost of storing a spend, or of querying one, which the node has to check against the
+    /// spends it holds.
+    pub spend_cost: u32,
+}
+
+impl Default for RequestRateLimits {
+    fn default() -> Self {
+        Self {
+            burst: 1000,
+            per_sec: 100,
+            get_cost: 1,
+            put_cost: 5,
+            spend_cost: 10,
+        }
+    }
+}
+
+/// The kind of a request, which is what it costs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+    Get,
+    Put,
+    Spend,
+}
+
+impl RequestKind {
+    pub(crate) fn of_query(query: &Query) -> Self {
+        if matches!(query.dst(), NetworkAddress::SpendAddress(_)) {
+            Self::Spend
+        } else if matches!(query, Query::GetStoreCost(_)) {
+            Self::Put
+        } else {
+            Self::Get
+        }
+    }
+
+    pub(crate) fn of_record(record: &Record) -> Self {
+        match RecordHeader::from_record(record).map(|header| header.kind) {
+            Ok(RecordKind::Spend) => Self::Spend,
+            _ => Self::Put,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+    /// Whether its last request was dropped, for the start of a flood to be logged only.
+    limited: bool,
+}
+
+/// Keeps the token buckets of the clients of a node.
+#[derive(Debug)]
+pub(crate) struct RequestRateLimiter {
+    limits: RequestRateLimits,
+    buckets: HashMap<PeerId, TokenBucket>,
+    /// The peers of the buckets by the time they were last refilled, oldest first.
+    refills: BTreeSet<(Instant, PeerId)>,
+}
+
+impl RequestRateLimiter {
+    pub(crate) fn new(limits: RequestRateLimits) -> Self {
+        Self {
+            limits,
+            buckets: HashMap::new(),
+            refills: BTreeSet::new(),
+        }
+    }
+
+    /// Spends the cost of the request out of the bucket of the peer, returning false if it's
+    /// short, for the request to be dropped.
+    pub(crate) fn allow(&mut self, peer: PeerId, kind: RequestKind, now: Instant) -> bool {
+        let limits = self.limits;
+        let cost = f64::from(match kind {
+            RequestKind::Get => limits.get_cost,
+            RequestKind::Put => limits.put_cost,
+            RequestKind::Spend => limits.spend_cost,
+        });
+        if !self.buckets.contains_key(&peer) && self.buckets.len() >= MAX_TRACKED_PEERS {
+            if let Some((_, oldest)) = self.refills.pop_first() {
+                let _ = self.buckets.remove(&oldest);
+            }
+        }
+
+        let bucket = self.buckets.entry(peer).or_insert_with(|| TokenBucket {
+            tokens: f64::from(limits.burst),
+            refilled_at: now,
+            limited: false,
+        });
+        let _ = self.refills.remove(&(bucket.refilled_at, peer));
+        let tokens = refill(bucket, &limits, now);
+        let _ = self.refills.insert((bucket.refilled_at, peer));
+        if tokens < cost {
+            if !bucket.limited {
+                warn!("Rate limiting the {kind:?} requests of {peer:?}, over {limits:?}");
+                bucket.limited = true;
+            }
+            return false;
+        }
+        bucket.tokens -= cost;
+        bucket.limited = false;
+        true
+    }
+}
+
+// Refills the bucket for the time since it last was, returning its tokens.
+fn refill(bucket: &mut TokenBucket, limits: &RequestRateLimits, now: Instant) -> f64 {
+    let elapsed = now.duration_since(bucket.refilled_at);
+    bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * f64::from(limits.per_sec))
+        .min(f64::from(limits.burst));
+    bucket.refilled_at = now;
+    bucket.tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn requests_are_served_at_the_steady_rate_past_the_burst() {
+        let mut limiter = RequestRateLimiter::new(RequestRateLimits {
+            burst: 10,
+            per_sec: 2,
+            get_cost: 1,
+            put_cost: 5,
+            spend_cost: 10,
+        });
+        let (client, other) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        assert!(limiter.allow(client, RequestKind::Put, now));
+        assert!((0..5).all(|_| limiter.allow(client, RequestKind::Get, now)));
+        assert!(!limiter.allow(client, RequestKind::Get, now));
+        // the other clients have buckets of their own
+        assert!(limiter.allow(other, RequestKind::Spend, now));
+        assert!(!limiter.allow(other, RequestKind::Get, now));
+
+        // refilled at the steady rate, up to the burst
+        let later = now + Duration::from_secs(1);
+        assert!(!limiter.allow(client, RequestKind::Put, later));
+        assert!(limiter.allow(client, RequestKind::Get, later));
+        let much_later = now + Duration::from_secs(60);
+        assert!(limiter.allow(other, RequestKind::Spend, much_later));
+        assert!(!limiter.allow(other, RequestKind::Get, much_later));
+    }
+
+    #[test]
+    fn the_least_recently_refilled_buckets_are_dropped_past_the_most_peers() {
+        let mut limiter = RequestRateLimiter::new(RequestRateLimits {
+            burst: 1,
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let oldest = PeerId::random();
+        assert!(limiter.allow(oldest, RequestKind::Get, now));
+        assert!(!limiter.allow(oldest, RequestKind::Get, now));
+
+        let later = now + Duration::from_millis(1);
+        for _ in 0..MAX_TRACKED_PEERS {
+            let _ = limiter.allow(PeerId::random(), RequestKind::Get, later);
+        }
+        assert_eq!(limiter.buckets.len(), MAX_TRACKED_PEERS);
+        assert_eq!(limiter.refills.len(), MAX_TRACKED_PEERS);
+        // its bucket was dropped, so it starts over with a full one
+        assert!(!limiter.buckets.contains_key(&oldest));
+        assert!(limiter.allow(oldest, RequestKind::Get, later));
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::kad::{Record, RecordKey as Key};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// The suffix of the entries being written, which are only entries once renamed without it.
+const TMP_SUFFIX: &str = "tmp";
+
+/// A record as journaled, uncompressed and unencrypted, as the encryption key of the store is
+/// only valid until the node restarts.
+#[derive(Serialize, Deserialize)]
+struct JournaledRecord {
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+/// The write-ahead journal of the records accepted by the store and not yet written to its
+/// backend, for a node killed mid-write to store them on restart rather than lose them.
+///
+/// Each record is journaled as a file of its own, `<hex key>.<seq>`, which is synced to disk before
+/// the record is accepted and removed once the record is written. An entry is first written
+/// under a temporary name, so a torn entry is never taken for one.
+#[derive(Clone, Debug)]
+pub(crate) struct RecordJournal {
+    dir: PathBuf,
+    next_seq: Arc<AtomicU64>,
+}
+
+/// An entry of the journal, to be completed once its record is written.
+#[derive(Debug)]
+pub(crate) struct JournalEntry {
+    path: PathBuf,
+}
+
+impl JournalEntry {
+    /// Removes the entry, its record being written, or given up on.
+    pub(crate) fn complete(self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            if err.kind() != ErrorKind::NotFound {
+                warn!(
+                    "Failed to remove the journal entry {:?}: {err:?}",
+                    self.path
+                );
+            }
+        }
+    }
+}
+
+impl RecordJournal {
+    /// Opens the journal in the dir, creating it if needed.
+    pub(crate) fn open(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let last_seq = fs::read_dir(&dir)?
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                parse_entry_name(&name.to_string_lossy()).map(|(_, seq)| seq)
+            })
+            .max();
+        Ok(Self {
+            dir,
+            next_seq: Arc::new(AtomicU64::new(last_seq.map_or(0, |seq| seq + 1))),
+        })
+    }
+
+    /// Journals the record, which is on disk once this returns.
+    pub(crate) fn append(&self, record: &Record) -> io::Result<JournalEntry> {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let name = format!("{}.{seq}", hex::encode(record.key.as_ref()));
+        let tmp_path = self.dir.join(format!("{name}.{TMP_SUFFIX}"));
+        let path = self.dir.join(name);
+
+        let bytes = rmp_serde::to_vec(&JournaledRecord {
+            key: record.key.to_vec(),
+            value: record.value.clone(),
+        })
+        .map_err(io::Error::other)?;
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
+        sync_dir(&self.dir);
+        Ok(JournalEntry { path })
+    }
+
+    /// Removes the entries of the record, which is no longer to be stored.
+    pub(crate) fn discard(&self, key: &Key) {
+        let prefix = format!("{}.", hex::encode(key.as_ref()));
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                JournalEntry { path: entry.path() }.complete();
+            }
+        }
+    }
+
+    /// Returns the records journaled and not written, i.e. the ones the node was stopped before
+    /// writing, with the entries to complete once they are. Only the latest entry of a record is
+    /// returned, the torn and the superseded entries being removed.
+    pub(crate) fn pending(&self) -> io::Result<Vec<(JournalEntry, Record)>> {
+        let mut latest: BTreeMap<String, (u64, PathBuf)> = BTreeMap::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let Some((key, seq)) = parse_entry_name(&name) else {
+                warn!("Removing the torn or unknown journal entry {path:?}");
+                JournalEntry { path }.complete();
+                continue;
+            };
+            match latest.get(key) {
+                Some((latest_seq, _)) if *latest_seq > seq => JournalEntry { path }.complete(),
+                _ => {
+                    if let Some((_, superseded)) = latest.insert(key.to_string(), (seq, path)) {
+                        JournalEntry { path: superseded }.complete();
+                    }
+                }
+            }
+        }
+
+        let mut pending: Vec<_> = latest.into_values().collect();
+        pending.sort_by_key(|(seq, _)| *seq);
+        Ok(pending
+            .into_iter()
+            .filter_map(|(_, path)| {
+                let record = read_entry(&path);
+                if record.is_none() {
+                    warn!("Removing the unreadable journal entry {path:?}");
+                }
+                let entry = JournalEntry { path };
+                match record {
+                    Some(record) => Some((entry, record)),
+                    None => {
+                        entry.complete();
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+}
+
+// Parses `<hex key>.<seq>`, the temporary entries being left out.
+fn parse_entry_name(name: &str) -> Option<(&str, u64)> {
+    let (key, seq) = name.rsplit_once('.')?;
+    let seq = seq.parse().ok()?;
+    hex::decode(key).ok().map(|_| (key, seq))
+}
+
+fn read_entry(path: &Path) -> Option<Record> {
+    let bytes = fs::read(path).ok()?;
+    let journaled: JournaledRecord = rmp_serde::from_slice(&bytes).ok()?;
+    Some(Record::new(Key::from(journaled.key), journaled.value))
+}
+
+// Syncs the renaming of an entry to disk, which is only needed on Unix.
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Err(err) = File::open(dir).and_then(|dir| dir.sync_all()) {
+        warn!("Failed to sync the journal dir {dir:?}: {err:?}");
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_journaled_before_a_crash_are_pending_on_restart() -> eyre::Result<()> {
+        let dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let journal = RecordJournal::open(dir.clone())?;
+        let written = Record::new(Key::new(b"written"), vec![1; 10]);
+        let replaced = Record::new(Key::new(b"replaced"), vec![2; 10]);
+        let replacement = Record::new(replaced.key.clone(), vec![3; 10]);
+
+        journal.append(&written)?.complete();
+        let _crashed = journal.append(&replaced)?;
+        let _crashed = journal.append(&replacement)?;
+        // killed while journaling a record, which was then not accepted
+        fs::write(
+            dir.join(format!("{}.9.{TMP_SUFFIX}", hex::encode(b"torn"))),
+            [4; 3],
+        )?;
+        // a discarded record isn't brought back
+        let discarded = Record::new(Key::new(b"discarded"), vec![5; 10]);
+        let _crashed = journal.append(&discarded)?;
+        journal.discard(&discarded.key);
+
+        let journal = RecordJournal::open(dir.clone())?;
+        let pending = journal.pending()?;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].1, replacement);
+        assert_eq!(fs::read_dir(&dir)?.count(), 1);
+
+        // the entries of the restarted journal come after the pending ones
+        let next = journal.append(&written)?;
+        assert!(next
+            .path
+            .ends_with(format!("{}.3", hex::encode(b"written"))));
+
+        for (entry, _) in pending {
+            entry.complete();
+        }
+        next.complete();
+        assert!(journal.pending()?.is_empty());
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -11,13 +11,19 @@ use crate::cmd::LocalSwarmCmd;
 use crate::driver::MAX_PACKET_SIZE;
 use crate::target_arch::{spawn, Instant};
 use crate::{event::NetworkEvent, log_markers::Marker};
-use crate::{send_local_swarm_cmd, CLOSE_GROUP_SIZE};
+use crate::{
+    pricing::{DefaultPricing, PricingStrategy},
+    record_journal::RecordJournal,
+    record_store_backend::{DiskRecordStoreBackend, RecordStoreBackend},
+    send_local_swarm_cmd, CLOSE_GROUP_SIZE,
+};
 use aes_gcm_siv::{
     aead::{Aead, KeyInit, OsRng},
     Aes256GcmSiv, Nonce,
 };
 
-use itertools::Itertools;
+#[cfg(feature = "open-metrics")]
+use crate::metrics::RecordStoreMetrics;
 use libp2p::{
     identity::PeerId,
     kad::{
@@ -25,13 +31,11 @@ use libp2p::{
         KBucketDistance as Distance, KBucketKey, ProviderRecord, Record, RecordKey as Key,
     },
 };
-#[cfg(feature = "open-metrics")]
-use prometheus_client::metrics::gauge::Gauge;
 use rand::RngCore;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use sn_protocol::{
-    storage::{RecordHeader, RecordKind, RecordType},
+    storage::{try_deserialize_record, ExpiringData, RecordHeader, RecordKind, RecordType},
     NetworkAddress, PrettyPrintRecordKey,
 };
 use sn_transfers::{NanoTokens, QuotingMetrics, TOTAL_SUPPLY};
@@ -41,11 +45,11 @@ use std::{
     collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
     time::SystemTime,
     vec,
 };
 use tokio::sync::mpsc;
-use walkdir::{DirEntry, WalkDir};
 use xor_name::XorName;
 
 // A spend record is at the size of 4KB roughly.
@@ -59,9 +63,39 @@ const MAX_RECORDS_COUNT: usize = 4096;
 /// The maximum number of records to cache in memory.
 const MAX_RECORDS_CACHE_SIZE: usize = 100;
 
+/// How many of the stored records are loaded at once when restoring the store on restart.
+const RESTORE_BATCH_SIZE: usize = 1024;
+
 /// File name of the recorded historical quoting metrics.
 const HISTORICAL_QUOTING_METRICS_FILENAME: &str = "historic_quoting_metrics";
 
+/// File name of the keys of the records the node got paid for, next to the historic quoting
+/// metrics.
+const PAID_RECORDS_FILENAME: &str = "paid_records";
+
+/// The size of a stored record, counted uncompressed, which is the most it takes on disk, along
+/// with its kind if it could be parsed.
+type RecordDetails = (u64, Option<RecordKind>);
+
+/// The records restored from an existing store, along with their details and the expiry of the
+/// ones of an expiring kind.
+type RestoredRecords = (
+    HashMap<Key, (NetworkAddress, RecordType)>,
+    HashMap<Key, RecordDetails>,
+    HashMap<Key, SystemTime>,
+);
+
+/// The time a record expires at, if it's of an expiring kind, which its data sets. The other
+/// records are paid for to be kept, and never expire.
+fn expiry_of(record: &Record) -> Option<SystemTime> {
+    match RecordHeader::from_record(record).ok()?.kind {
+        RecordKind::ExpiringData => try_deserialize_record::<ExpiringData>(record)
+            .ok()
+            .map(|expiring_data| expiring_data.expires_at()),
+        _ => None,
+    }
+}
+
 /// A `RecordStore` that stores records on disk.
 pub struct NodeRecordStore {
     /// The identity of the peer owning the store.
@@ -70,8 +104,22 @@ pub struct NodeRecordStore {
     local_address: NetworkAddress,
     /// The configuration of the store.
     config: NodeRecordStoreConfig,
+    /// The storage engine the records are kept in.
+    backend: Arc<dyn RecordStoreBackend>,
+    /// The journal of the records accepted and not yet written to the backend, if any.
+    journal: Option<RecordJournal>,
     /// A set of keys, each corresponding to a data `Record` stored on disk.
     records: HashMap<Key, (NetworkAddress, RecordType)>,
+    /// The details of each record stored, to keep to the disk quota and report the metrics.
+    record_details: HashMap<Key, RecordDetails>,
+    /// The sum of the record sizes.
+    used_bytes: u64,
+    /// The expiry of the records of an expiring kind, which are removed once expired.
+    expiring_records: HashMap<Key, SystemTime>,
+    /// The records the node got paid for, which are pruned last. Persisted, to survive restarts.
+    paid_records: HashSet<Key>,
+    /// Whether the paid records changed since they were last written to disk.
+    paid_records_changed: bool,
     /// FIFO simple cache of records to reduce read times
     records_cache: VecDeque<Record>,
     /// A map from record keys to their indices in the cache
@@ -86,8 +134,8 @@ pub struct NodeRecordStore {
     /// None means accept all records.
     responsible_distance_range: Option<u32>,
     #[cfg(feature = "open-metrics")]
-    /// Used to report the number of records held by the store to the metrics server.
-    record_count_metric: Option<Gauge>,
+    /// Used to report the records held by the store to the metrics server.
+    metrics: Option<RecordStoreMetrics>,
     /// Counting how many times got paid
     received_payment_count: usize,
     /// Encyption cipher for the records, randomly generated at node startup
@@ -107,12 +155,51 @@ pub struct NodeRecordStoreConfig {
     /// The directory where the historic quote to be stored
     /// (normally to be the parent dir of the storage_dir)
     pub historic_quote_dir: PathBuf,
+    /// The directory the corrupted records are moved to, which must be out of the storage_dir.
+    pub quarantine_dir: PathBuf,
+    /// The directory the records are journaled in until they're written, which must be out of
+    /// the storage_dir. The records being written when the node is stopped are lost if `None`.
+    pub journal_dir: Option<PathBuf>,
     /// The maximum number of records.
     pub max_records: usize,
     /// The maximum size of record values, in bytes.
     pub max_value_bytes: usize,
     /// The maximum number of records to cache in memory.
     pub records_cache_size: usize,
+    /// The kinds of records stored compressed, which is only worth it for those which compress
+    /// well, unlike the chunks, which are encrypted.
+    pub compressed_record_kinds: HashSet<RecordKind>,
+    /// The most disk the records may take, in bytes, records being pruned to make room for the
+    /// incoming ones once reached. No limit but `max_records` if `None`.
+    pub max_disk_usage: Option<u64>,
+    /// The order the records are pruned in, when the `max_disk_usage` is reached.
+    pub pruning_policy: PruningPolicy,
+    /// How the storing of a record is priced.
+    pub pricing: Arc<dyn PricingStrategy>,
+}
+
+/// The order a node prunes its records in, when it reaches its disk quota.
+///
+/// Whatever the policy, the records the node got paid for are only pruned once all the others
+/// are, as the ones it got through replication are held by other nodes as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PruningPolicy {
+    /// The records farthest from the node first, which are the ones it is the least responsible
+    /// for.
+    #[default]
+    Farthest,
+    /// The largest records first, so the fewest records are pruned.
+    Largest,
+}
+
+/// The disk the records of a node take, as reported by its status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub records: usize,
+    /// The space taken by the records, counted uncompressed, in bytes.
+    pub used_bytes: u64,
+    /// The disk quota of the node, in bytes, if any.
+    pub max_bytes: Option<u64>,
 }
 
 impl Default for NodeRecordStoreConfig {
@@ -120,14 +207,65 @@ impl Default for NodeRecordStoreConfig {
         let historic_quote_dir = std::env::temp_dir();
         Self {
             storage_dir: historic_quote_dir.clone(),
+            quarantine_dir: historic_quote_dir.join("quarantine"),
+            journal_dir: None,
             historic_quote_dir,
             max_records: MAX_RECORDS_COUNT,
             max_value_bytes: MAX_PACKET_SIZE,
             records_cache_size: MAX_RECORDS_CACHE_SIZE,
+            compressed_record_kinds: HashSet::from([
+                RecordKind::Spend,
+                RecordKind::Register,
+                RecordKind::RegisterWithPayment,
+            ]),
+            max_disk_usage: None,
+            pruning_policy: PruningPolicy::default(),
+            pricing: Arc::new(DefaultPricing),
+        }
+    }
+}
+
+/// Prefixes the records stored compressed. MessagePack, which the record headers are serialized
+/// with, never uses that byte, so it can't start a record stored as is.
+const COMPRESSED_RECORD_FLAG: u8 = 0xc1;
+
+/// Compresses a record value with zstd, unless that doesn't make it any smaller.
+fn compress_value(value: Vec<u8>, key: &Key) -> Vec<u8> {
+    match zstd::encode_all(value.as_slice(), zstd::DEFAULT_COMPRESSION_LEVEL) {
+        Ok(compressed) if compressed.len() + 1 < value.len() => {
+            let mut bytes = Vec::with_capacity(compressed.len() + 1);
+            bytes.push(COMPRESSED_RECORD_FLAG);
+            bytes.extend(compressed);
+            bytes
+        }
+        Ok(_) => value,
+        Err(error) => {
+            warn!(
+                "Failed to compress record {:?}, storing it as is: {error:?}",
+                PrettyPrintRecordKey::from(key)
+            );
+            value
         }
     }
 }
 
+/// Decompresses a stored record value if it was stored compressed.
+fn decompress_stored_value(bytes: Vec<u8>, key: &Key) -> Option<Vec<u8>> {
+    match bytes.split_first() {
+        Some((&COMPRESSED_RECORD_FLAG, compressed)) => match zstd::decode_all(compressed) {
+            Ok(value) => Some(value),
+            Err(error) => {
+                error!(
+                    "Error while decompressing record {:?}: {error:?}",
+                    PrettyPrintRecordKey::from(key)
+                );
+                None
+            }
+        },
+        _ => Some(bytes),
+    }
+}
+
 /// Generate an encryption nonce for a given record key and nonce_starter bytes.
 fn generate_nonce_for_record(nonce_starter: &[u8; 4], key: &Key) -> Nonce {
     let mut nonce_bytes = nonce_starter.to_vec();
@@ -145,74 +283,125 @@ struct HistoricQuotingMetrics {
 }
 
 impl NodeRecordStore {
-    /// If a directory for our node already exists, repopulate the records from the files in the dir
+    /// If the backend already holds records, e.g. from a previous run of the node, repopulate
+    /// the records from them
     fn update_records_from_an_existing_store(
-        config: &NodeRecordStoreConfig,
+        backend: &dyn RecordStoreBackend,
         encryption_details: &(Aes256GcmSiv, [u8; 4]),
-    ) -> HashMap<Key, (NetworkAddress, RecordType)> {
-        let process_entry = |entry: &DirEntry| -> _ {
-            let path = entry.path();
-            if path.is_file() {
-                debug!("Existing record found: {path:?}");
-                // if we've got a file, lets try and read it
-                let filename = match path.file_name().and_then(|n| n.to_str()) {
-                    Some(file_name) => file_name,
-                    None => {
-                        // warn and remove this file as it's not a valid record
-                        warn!(
-                            "Found a file in the storage dir that is not a valid record: {:?}",
-                            path
-                        );
-                        if let Err(e) = fs::remove_file(path) {
-                            warn!(
-                                "Failed to remove invalid record file from storage dir: {:?}",
-                                e
-                            );
-                        }
-                        return None;
-                    }
-                };
-                // get the record key from the filename
-                let key = Self::get_data_from_filename(filename)?;
-                let record = match fs::read(path) {
-                    Ok(bytes) => {
-                        // and the stored record
-                        Self::get_record_from_bytes(bytes, &key, encryption_details)?
-                    }
-                    Err(err) => {
-                        error!("Error while reading file. filename: {filename}, error: {err:?}");
-                        return None;
-                    }
-                };
+    ) -> RestoredRecords {
+        let process_key = |key: &Key| -> _ {
+            let pretty_key = PrettyPrintRecordKey::from(key);
+            let record = match backend.get(key) {
+                Ok(Some(bytes)) => {
+                    // and the stored record
+                    Self::get_record_from_bytes(bytes, key, encryption_details)?
+                }
+                Ok(None) => return None,
+                Err(err) => {
+                    error!("Error while reading record {pretty_key:?}, error: {err:?}");
+                    return None;
+                }
+            };
 
-                let record_type = match RecordHeader::is_record_of_type_chunk(&record) {
-                    Ok(true) => RecordType::Chunk,
-                    Ok(false) => {
-                        let xorname_hash = XorName::from_content(&record.value);
-                        RecordType::NonChunk(xorname_hash)
-                    }
-                    Err(error) => {
-                        warn!("Failed to parse record type from record: {:?}", error);
-                        return None;
-                    }
-                };
+            let record_type = match RecordHeader::is_record_of_type_chunk(&record) {
+                Ok(true) => RecordType::Chunk,
+                Ok(false) => {
+                    let xorname_hash = XorName::from_content(&record.value);
+                    RecordType::NonChunk(xorname_hash)
+                }
+                Err(error) => {
+                    warn!("Failed to parse record type from record: {:?}", error);
+                    return None;
+                }
+            };
 
-                let address = NetworkAddress::from_record_key(&key);
-                info!("Existing record loaded: {path:?}");
-                return Some((key, (address, record_type)));
-            }
-            None
+            let address = NetworkAddress::from_record_key(key);
+            info!("Existing record loaded: {pretty_key:?}");
+            let details = (
+                record.value.len() as u64,
+                RecordHeader::from_record(&record)
+                    .ok()
+                    .map(|header| header.kind),
+            );
+            Some((
+                key.clone(),
+                address,
+                record_type,
+                details,
+                expiry_of(&record),
+            ))
         };
 
         info!("Attempting to repopulate records from existing store...");
-        let records = WalkDir::new(&config.storage_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .collect_vec()
-            .par_iter()
-            .filter_map(process_entry)
-            .collect();
-        records
+        // the keys are read in batches, each batch being loaded in parallel
+        let mut loaded = vec![];
+        let mut keys = Vec::with_capacity(RESTORE_BATCH_SIZE);
+        let mut listed = backend.iterate().peekable();
+        while listed.peek().is_some() {
+            keys.clear();
+            for key in listed.by_ref().take(RESTORE_BATCH_SIZE) {
+                match key {
+                    Ok(key) => keys.push(key),
+                    Err(err) => {
+                        error!("Error while listing the records of the existing store: {err:?}");
+                        return Default::default();
+                    }
+                }
+            }
+            loaded.extend(keys.par_iter().filter_map(process_key).collect::<Vec<_>>());
+        }
+        let mut records = HashMap::with_capacity(loaded.len());
+        let mut record_details = HashMap::with_capacity(loaded.len());
+        let mut expiring_records = HashMap::new();
+        for (key, address, record_type, details, expiry) in loaded {
+            if let Some(expiry) = expiry {
+                let _ = expiring_records.insert(key.clone(), expiry);
+            }
+            let _ = record_details.insert(key.clone(), details);
+            let _ = records.insert(key, (address, record_type));
+        }
+        (records, record_details, expiring_records)
+    }
+
+    /// Writes the records which were accepted and not written when the node was stopped, before
+    /// the store is restored from the backend.
+    fn replay_journal(
+        journal: &RecordJournal,
+        backend: &dyn RecordStoreBackend,
+        config: &NodeRecordStoreConfig,
+        encryption_details: &(Aes256GcmSiv, [u8; 4]),
+    ) {
+        let pending = match journal.pending() {
+            Ok(pending) => pending,
+            Err(err) => {
+                error!("Failed to read the record journal: {err:?}");
+                return;
+            }
+        };
+        if !pending.is_empty() {
+            info!(
+                "Replaying {} records journaled before the node stopped",
+                pending.len()
+            );
+        }
+        for (entry, record) in pending {
+            let pretty_key = PrettyPrintRecordKey::from(&record.key).into_owned();
+            let key = record.key.clone();
+            let compress = RecordHeader::from_record(&record)
+                .is_ok_and(|header| config.compressed_record_kinds.contains(&header.kind));
+            let Some(bytes) =
+                Self::prepare_record_bytes(record, encryption_details.clone(), compress)
+            else {
+                continue;
+            };
+            match backend.put(&key, &bytes) {
+                Ok(()) => {
+                    info!("Replayed the journaled record {pretty_key:?}");
+                    entry.complete();
+                }
+                Err(err) => error!("Failed to replay the journaled record {pretty_key:?}: {err:?}"),
+            }
+        }
     }
 
     /// If quote_metrics file already exists, using the existing parameters.
@@ -228,6 +417,49 @@ impl NodeRecordStore {
         None
     }
 
+    /// Reads the keys of the paid records, keeping those of the records still stored.
+    fn restore_paid_records(
+        historic_quote_dir: &Path,
+        records: &HashMap<Key, (NetworkAddress, RecordType)>,
+    ) -> HashSet<Key> {
+        let file_path = historic_quote_dir.join(PAID_RECORDS_FILENAME);
+        let Ok(file) = fs::File::open(&file_path) else {
+            return HashSet::new();
+        };
+        match rmp_serde::from_read::<_, Vec<Vec<u8>>>(&file) {
+            Ok(keys) => keys
+                .into_iter()
+                .map(Key::from)
+                .filter(|key| records.contains_key(key))
+                .collect(),
+            Err(err) => {
+                warn!("Failed to read the paid records from {file_path:?}: {err:?}");
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Writes the keys of the paid records if they changed since last written, replacing the file
+    /// at once so that it's never found partly written.
+    ///
+    /// This is done periodically and when the store is dropped, rather than on every change.
+    pub(crate) fn flush_paid_records(&mut self) {
+        if !self.paid_records_changed {
+            return;
+        }
+        self.paid_records_changed = false;
+        let file_path = self.config.historic_quote_dir.join(PAID_RECORDS_FILENAME);
+        let tmp_path = file_path.with_extension("tmp");
+        let keys: Vec<_> = self.paid_records.iter().map(|key| key.to_vec()).collect();
+        let result = rmp_serde::to_vec(&keys)
+            .map_err(std::io::Error::other)
+            .and_then(|bytes| fs::write(&tmp_path, bytes))
+            .and_then(|()| fs::rename(&tmp_path, &file_path));
+        if let Err(err) = result {
+            error!("Failed to write the paid records to {file_path:?}: {err:?}");
+        }
+    }
+
     fn flush_historic_quoting_metrics(&self) {
         let file_path = self
             .config
@@ -253,6 +485,25 @@ impl NodeRecordStore {
         config: NodeRecordStoreConfig,
         network_event_sender: mpsc::Sender<NetworkEvent>,
         swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
+    ) -> Self {
+        let backend = Arc::new(DiskRecordStoreBackend::new(config.storage_dir.clone()));
+        Self::with_backend(
+            local_id,
+            config,
+            backend,
+            network_event_sender,
+            swarm_cmd_sender,
+        )
+    }
+
+    /// Creates a new store keeping the records in the given backend, rather than in the files of
+    /// the `storage_dir` of the configuration.
+    pub fn with_backend(
+        local_id: PeerId,
+        config: NodeRecordStoreConfig,
+        backend: Arc<dyn RecordStoreBackend>,
+        network_event_sender: mpsc::Sender<NetworkEvent>,
+        swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
     ) -> Self {
         let key = Aes256GcmSiv::generate_key(&mut OsRng);
         let cipher = Aes256GcmSiv::new(&key);
@@ -274,21 +525,40 @@ impl NodeRecordStore {
             (0, SystemTime::now())
         };
 
-        let records = Self::update_records_from_an_existing_store(&config, &encryption_details);
+        let journal = config.journal_dir.clone().and_then(|dir| {
+            RecordJournal::open(dir.clone())
+                .inspect_err(|err| error!("Failed to open the record journal in {dir:?}: {err:?}"))
+                .ok()
+        });
+        if let Some(journal) = &journal {
+            Self::replay_journal(journal, backend.as_ref(), &config, &encryption_details);
+        }
+
+        let (records, record_details, expiring_records) =
+            Self::update_records_from_an_existing_store(backend.as_ref(), &encryption_details);
+        let used_bytes = record_details.values().map(|(size, _)| size).sum();
+        let paid_records = Self::restore_paid_records(&config.historic_quote_dir, &records);
 
         let cache_size = config.records_cache_size;
         let mut record_store = NodeRecordStore {
             local_key: KBucketKey::from(local_id),
             local_address: NetworkAddress::from_peer(local_id),
             config,
+            backend,
+            journal,
             records,
+            record_details,
+            used_bytes,
+            expiring_records,
+            paid_records,
+            paid_records_changed: false,
             records_cache: VecDeque::with_capacity(cache_size),
             records_cache_map: HashMap::with_capacity(cache_size),
             network_event_sender,
             local_swarm_cmd_sender: swarm_cmd_sender,
             responsible_distance_range: None,
             #[cfg(feature = "open-metrics")]
-            record_count_metric: None,
+            metrics: None,
             received_payment_count,
             encryption_details,
             timestamp,
@@ -302,10 +572,15 @@ impl NodeRecordStore {
         record_store
     }
 
-    /// Set the record_count_metric to report the number of records stored to the metrics server
+    /// Set the metrics to report the records stored to the metrics server
     #[cfg(feature = "open-metrics")]
-    pub fn set_record_count_metric(mut self, metric: Gauge) -> Self {
-        self.record_count_metric = Some(metric);
+    pub(crate) fn set_metrics(mut self, metrics: RecordStoreMetrics) -> Self {
+        metrics.set_records_stored(self.records.len());
+        metrics.set_stored_bytes(self.used_bytes);
+        for (_, kind) in self.record_details.values() {
+            metrics.record_added(*kind);
+        }
+        self.metrics = Some(metrics);
         self
     }
 
@@ -314,22 +589,6 @@ impl NodeRecordStore {
         self.responsible_distance_range
     }
 
-    // Converts a Key into a Hex string.
-    fn generate_filename(key: &Key) -> String {
-        hex::encode(key.as_ref())
-    }
-
-    // Converts a Hex string back into a Key.
-    fn get_data_from_filename(hex_str: &str) -> Option<Key> {
-        match hex::decode(hex_str) {
-            Ok(bytes) => Some(Key::from(bytes)),
-            Err(error) => {
-                error!("Error decoding hex string: {:?}", error);
-                None
-            }
-        }
-    }
-
     /// Upon read perform any data transformations required to return a `Record`.
     fn get_record_from_bytes<'a>(
         bytes: Vec<u8>,
@@ -343,8 +602,9 @@ impl NodeRecordStore {
             expires: None,
         };
 
-        // if we're not encrypting, lets just return the record
+        // if we're not encrypting, lets just decompress the record
         if !cfg!(feature = "encrypt-records") {
+            record.value = decompress_stored_value(record.value, key)?;
             return Some(Cow::Owned(record));
         }
 
@@ -353,7 +613,7 @@ impl NodeRecordStore {
 
         match cipher.decrypt(&nonce, record.value.as_ref()) {
             Ok(value) => {
-                record.value = value;
+                record.value = decompress_stored_value(value, key)?;
                 return Some(Cow::Owned(record));
             }
             Err(error) => {
@@ -366,16 +626,14 @@ impl NodeRecordStore {
     fn read_from_disk<'a>(
         encryption_details: &(Aes256GcmSiv, [u8; 4]),
         key: &Key,
-        storage_dir: &Path,
+        backend: &dyn RecordStoreBackend,
     ) -> Option<Cow<'a, Record>> {
         let start = Instant::now();
-        let filename = Self::generate_filename(key);
-
-        let file_path = storage_dir.join(&filename);
+        let filename = DiskRecordStoreBackend::generate_filename(key);
 
         // we should only be reading if we know the record is written to disk properly
-        match fs::read(file_path) {
-            Ok(bytes) => {
+        match backend.get(key) {
+            Ok(Some(bytes)) => {
                 // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
                 info!(
                     "Retrieved record from disk! filename: {filename} after {:?}",
@@ -384,6 +642,10 @@ impl NodeRecordStore {
 
                 Self::get_record_from_bytes(bytes, key, encryption_details)
             }
+            Ok(None) => {
+                error!("Error while reading file. filename: {filename}, error: not found");
+                None
+            }
             Err(err) => {
                 error!("Error while reading file. filename: {filename}, error: {err:?}");
                 None
@@ -449,11 +711,75 @@ impl NodeRecordStore {
         Ok(())
     }
 
+    /// Prune the records in the store, in the order of the `pruning_policy`, until the incoming
+    /// record fits within the `max_disk_usage`.
+    ///
+    /// Err ValueTooLarge if the record is larger than the quota itself, or MaxRecords if there is
+    /// nothing left to prune.
+    fn prune_to_disk_quota(&mut self, incoming_record_key: &Key, incoming_size: u64) -> Result<()> {
+        let Some(max_disk_usage) = self.config.max_disk_usage else {
+            return Ok(());
+        };
+        if incoming_size > max_disk_usage {
+            return Err(Error::ValueTooLarge);
+        }
+
+        // a record being replaced frees up its own space
+        let replaced_size = self
+            .record_details
+            .get(incoming_record_key)
+            .map_or(0, |(size, _)| *size);
+        while self.used_bytes - replaced_size + incoming_size > max_disk_usage {
+            let Some(key) = self.next_record_to_prune(incoming_record_key) else {
+                return Err(Error::MaxRecords);
+            };
+            info!(
+                "Record {:?} will be pruned to stay within the disk quota of {max_disk_usage} bytes",
+                PrettyPrintRecordKey::from(&key)
+            );
+            self.remove(&key);
+        }
+
+        Ok(())
+    }
+
+    // Picks the record to prune first according to the `pruning_policy`, the unpaid ones first.
+    fn next_record_to_prune(&self, incoming_record_key: &Key) -> Option<Key> {
+        let candidates = self
+            .record_details
+            .iter()
+            .filter(|(key, _)| *key != incoming_record_key);
+        let unpaid = |key: &Key| !self.paid_records.contains(key);
+        let next = match self.config.pruning_policy {
+            PruningPolicy::Farthest => candidates.max_by_key(|(key, _)| {
+                let distance = self
+                    .local_address
+                    .distance(&NetworkAddress::from_record_key(key));
+                (unpaid(key), distance)
+            }),
+            PruningPolicy::Largest => {
+                candidates.max_by_key(|(key, (size, _))| (unpaid(key), *size))
+            }
+        };
+        next.map(|(key, _)| key.clone())
+    }
+
+    /// The disk taken by the records, and the quota of it.
+    pub(crate) fn disk_usage(&self) -> DiskUsage {
+        DiskUsage {
+            records: self.records.len(),
+            used_bytes: self.used_bytes,
+            max_bytes: self.config.max_disk_usage,
+        }
+    }
+
     // When the accumulated record copies exceeds the `expotional pricing point` (max_records * 0.6)
     // those `out of range` records shall be cleaned up.
     // This is to avoid `over-quoting` during restart, when RT is not fully populated,
     // result in mis-calculation of relevant records.
     pub fn cleanup_unrelevant_records(&mut self) {
+        self.remove_expired_records();
+
         let accumulated_records = self.records.len();
         if accumulated_records < 6 * MAX_RECORDS_COUNT / 10 {
             return;
@@ -493,11 +819,59 @@ impl NodeRecordStore {
 }
 
 impl NodeRecordStore {
+    /// Removes the records of an expiring kind which expired.
+    pub(crate) fn remove_expired_records(&mut self) {
+        let now = SystemTime::now();
+        let expired: Vec<_> = self
+            .expiring_records
+            .iter()
+            .filter(|(_, expiry)| **expiry <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        if expired.is_empty() {
+            return;
+        }
+
+        for key in expired.iter() {
+            self.remove(key);
+        }
+        info!("Removed {} expired records", expired.len());
+    }
+
+    fn is_expired(&self, key: &Key) -> bool {
+        self.expiring_records
+            .get(key)
+            .is_some_and(|expiry| *expiry <= SystemTime::now())
+    }
+
     /// Returns `true` if the `Key` is present locally
     pub(crate) fn contains(&self, key: &Key) -> bool {
         self.records.contains_key(key)
     }
 
+    /// Removes a corrupted record from the store, for it to be fetched again, the bytes stored
+    /// for it being moved to the quarantine dir for the operator to look into.
+    pub(crate) fn quarantine(&mut self, key: &Key) {
+        let pretty_key = PrettyPrintRecordKey::from(key);
+        match self.backend.get(key) {
+            Ok(Some(bytes)) => {
+                let quarantine_dir = self.config.quarantine_dir.clone();
+                let filename = DiskRecordStoreBackend::generate_filename(key);
+                let _handle = spawn(async move {
+                    if let Err(err) = fs::create_dir_all(&quarantine_dir)
+                        .and_then(|_| fs::write(quarantine_dir.join(&filename), bytes))
+                    {
+                        error!("Failed to quarantine {filename} in {quarantine_dir:?}: {err:?}");
+                    }
+                });
+            }
+            Ok(None) => warn!("No bytes stored for {pretty_key:?} to quarantine"),
+            Err(err) => warn!("Failed to read {pretty_key:?} to quarantine it: {err:?}"),
+        }
+        warn!("Quarantined record {pretty_key:?}");
+        self.remove(key);
+    }
+
     /// Returns the set of `NetworkAddress::RecordKey` held by the store
     /// Use `record_addresses_ref` to get a borrowed type
     pub(crate) fn record_addresses(&self) -> HashMap<NetworkAddress, RecordType> {
@@ -534,18 +908,26 @@ impl NodeRecordStore {
 
     /// Prepare record bytes for storage
     /// If feats are enabled, this will eg, encrypt the record for storage
+    /// The record is compressed first if asked, as the encrypted bytes don't compress.
     fn prepare_record_bytes(
         record: Record,
         encryption_details: (Aes256GcmSiv, [u8; 4]),
+        compress: bool,
     ) -> Option<Vec<u8>> {
+        let value = if compress {
+            compress_value(record.value, &record.key)
+        } else {
+            record.value
+        };
+
         if !cfg!(feature = "encrypt-records") {
-            return Some(record.value);
+            return Some(value);
         }
 
         let (cipher, nonce_starter) = encryption_details;
         let nonce = generate_nonce_for_record(&nonce_starter, &record.key);
 
-        match cipher.encrypt(&nonce, record.value.as_ref()) {
+        match cipher.encrypt(&nonce, value.as_ref()) {
             Ok(value) => Some(value),
             Err(error) => {
                 warn!(
@@ -598,23 +980,49 @@ impl NodeRecordStore {
             .insert(key.clone(), self.records_cache.len() - 1);
 
         self.prune_records_if_needed(key)?;
+        let size = r.value.len() as u64;
+        self.prune_to_disk_quota(key, size)?;
+        let kind = RecordHeader::from_record(&r).ok().map(|header| header.kind);
+        let replaced = self.record_details.insert(key.clone(), (size, kind));
+        if let Some(expiry) = expiry_of(&r) {
+            let _ = self.expiring_records.insert(key.clone(), expiry);
+        }
+        if let Some((replaced_size, _)) = replaced {
+            self.used_bytes -= replaced_size;
+        }
+        self.used_bytes += size;
 
-        let filename = Self::generate_filename(key);
-        let file_path = self.config.storage_dir.join(&filename);
+        let filename = DiskRecordStoreBackend::generate_filename(key);
+        let backend = Arc::clone(&self.backend);
 
         #[cfg(feature = "open-metrics")]
-        if let Some(metric) = &self.record_count_metric {
-            let _ = metric.set(self.records.len() as i64);
+        if let Some(metrics) = &self.metrics {
+            metrics.set_records_stored(self.records.len());
+            metrics.set_stored_bytes(self.used_bytes);
+            if let Some((_, replaced_kind)) = replaced {
+                metrics.record_removed(replaced_kind);
+            }
+            metrics.record_added(kind);
         }
 
         let encryption_details = self.encryption_details.clone();
+        let compress = kind.is_some_and(|kind| self.config.compressed_record_kinds.contains(&kind));
         let cloned_cmd_sender = self.local_swarm_cmd_sender.clone();
 
+        // journaled before being accepted, for it to be written on restart if the node is
+        // stopped before it is
+        let journal_entry = self.journal.as_ref().and_then(|journal| {
+            journal
+                .append(&r)
+                .inspect_err(|err| error!("Failed to journal the record {record_key:?}: {err:?}"))
+                .ok()
+        });
+
         let record_key2 = record_key.clone();
         spawn(async move {
             let key = r.key.clone();
-            if let Some(bytes) = Self::prepare_record_bytes(r, encryption_details) {
-                let cmd = match fs::write(&file_path, bytes) {
+            if let Some(bytes) = Self::prepare_record_bytes(r, encryption_details, compress) {
+                let cmd = match backend.put(&key, &bytes) {
                     Ok(_) => {
                         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
                         info!("Wrote record {record_key2:?} to disk! filename: {filename}");
@@ -631,6 +1039,10 @@ impl NodeRecordStore {
 
                 send_local_swarm_cmd(cloned_cmd_sender, cmd);
             }
+            // the record is no longer pending, whether it got written or is given up on
+            if let Some(journal_entry) = journal_entry {
+                journal_entry.complete();
+            }
         });
 
         Ok(())
@@ -676,7 +1088,7 @@ impl NodeRecordStore {
         let cost = if self.contains(key) {
             0
         } else {
-            calculate_cost_for_records(&quoting_metrics)
+            self.config.pricing.store_cost(&quoting_metrics)
         };
         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
         info!("Cost is now {cost:?} for quoting_metrics {quoting_metrics:?}");
@@ -684,9 +1096,12 @@ impl NodeRecordStore {
         (NanoTokens::from(cost), quoting_metrics)
     }
 
-    /// Notify the node received a payment.
-    pub(crate) fn payment_received(&mut self) {
+    /// Notify the node received a payment for the record.
+    pub(crate) fn payment_received(&mut self, key: Key) {
         self.received_payment_count = self.received_payment_count.saturating_add(1);
+        if self.paid_records.insert(key) {
+            self.paid_records_changed = true;
+        }
 
         self.flush_historic_quoting_metrics();
     }
@@ -731,6 +1146,12 @@ impl RecordStore for NodeRecordStore {
         // ignored if we don't have the record locally.
         let key = PrettyPrintRecordKey::from(k);
 
+        // The expired records are no longer served, even before they get removed.
+        if self.is_expired(k) {
+            debug!("Record {key:?} has expired");
+            return None;
+        }
+
         let cached_record = self.records_cache.iter().find(|r| r.key == *k);
         // first return from FIFO cache if existing there
         if let Some(record) = cached_record {
@@ -744,7 +1165,14 @@ impl RecordStore for NodeRecordStore {
 
         debug!("GET request for Record key: {key}");
 
-        Self::read_from_disk(&self.encryption_details, k, &self.config.storage_dir)
+        #[cfg(feature = "open-metrics")]
+        let start = Instant::now();
+        let record = Self::read_from_disk(&self.encryption_details, k, self.backend.as_ref());
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_get(start.elapsed());
+        }
+        record
     }
 
     fn put(&mut self, record: Record) -> Result<()> {
@@ -763,7 +1191,11 @@ impl RecordStore for NodeRecordStore {
         match RecordHeader::from_record(&record) {
             Ok(record_header) => {
                 match record_header.kind {
-                    RecordKind::ChunkWithPayment | RecordKind::RegisterWithPayment => {
+                    RecordKind::ChunkWithPayment
+                    | RecordKind::RegisterWithPayment
+                    | RecordKind::PointerWithPayment
+                    | RecordKind::ScratchpadWithPayment
+                    | RecordKind::ExpiringDataWithPayment => {
                         debug!("Record {record_key:?} with payment shall always be processed.");
                     }
                     _ => {
@@ -811,11 +1243,26 @@ impl RecordStore for NodeRecordStore {
 
     fn remove(&mut self, k: &Key) {
         let _ = self.records.remove(k);
+        let _ = self.expiring_records.remove(k);
+        let removed = self.record_details.remove(k);
+        if let Some((size, _)) = removed {
+            self.used_bytes -= size;
+        }
+        if self.paid_records.remove(k) {
+            self.paid_records_changed = true;
+        }
         self.records_cache.retain(|r| r.key != *k);
+        if let Some(journal) = &self.journal {
+            journal.discard(k);
+        }
 
         #[cfg(feature = "open-metrics")]
-        if let Some(metric) = &self.record_count_metric {
-            let _ = metric.set(self.records.len() as i64);
+        if let Some(metrics) = &self.metrics {
+            metrics.set_records_stored(self.records.len());
+            metrics.set_stored_bytes(self.used_bytes);
+            if let Some((_, kind)) = removed {
+                metrics.record_removed(kind);
+            }
         }
 
         if let Some((farthest_record, _)) = self.farthest_record.clone() {
@@ -824,11 +1271,12 @@ impl RecordStore for NodeRecordStore {
             }
         }
 
-        let filename = Self::generate_filename(k);
-        let file_path = self.config.storage_dir.join(&filename);
+        let filename = DiskRecordStoreBackend::generate_filename(k);
+        let backend = Arc::clone(&self.backend);
+        let key = k.clone();
 
         let _handle = spawn(async move {
-            match fs::remove_file(file_path) {
+            match backend.delete(&key) {
                 Ok(_) => {
                     info!("Removed record from disk! filename: {filename}");
                 }
@@ -864,6 +1312,12 @@ impl RecordStore for NodeRecordStore {
     }
 }
 
+impl Drop for NodeRecordStore {
+    fn drop(&mut self) {
+        self.flush_paid_records();
+    }
+}
+
 /// A place holder RecordStore impl for the client that does nothing
 #[derive(Default, Debug)]
 pub struct ClientRecordStore {
@@ -976,6 +1430,7 @@ mod tests {
     use crate::{close_group_majority, sort_peers_by_key, REPLICATION_PEERS_COUNT};
     use bytes::Bytes;
     use eyre::ContextCompat;
+    use itertools::Itertools;
     use libp2p::{core::multihash::Multihash, kad::RecordKey};
     use quickcheck::*;
     use sn_protocol::storage::{try_serialize_record, ChunkAddress};
@@ -1117,6 +1572,221 @@ mod tests {
         assert_eq!(sut, 10);
     }
 
+    #[test]
+    fn records_of_the_compressed_kinds_are_stored_compressed() -> eyre::Result<()> {
+        let key = Key::new(&b"register");
+        let value = try_serialize_record(&vec![7u8; 1024], RecordKind::Register)?.to_vec();
+        let record = Record::new(key.clone(), value.clone());
+        let encryption_details = (
+            Aes256GcmSiv::new(&Aes256GcmSiv::generate_key(&mut OsRng)),
+            [1u8; 4],
+        );
+
+        let compressed =
+            NodeRecordStore::prepare_record_bytes(record.clone(), encryption_details.clone(), true)
+                .context("the record was not prepared")?;
+        let stored_as_is =
+            NodeRecordStore::prepare_record_bytes(record, encryption_details.clone(), false)
+                .context("the record was not prepared")?;
+        assert!(compressed.len() < stored_as_is.len());
+
+        // both are read back, as records stored before compression was enabled still are
+        for bytes in [compressed, stored_as_is] {
+            let read = NodeRecordStore::get_record_from_bytes(bytes, &key, &encryption_details)
+                .context("the record was not read back")?;
+            assert_eq!(read.value, value);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn records_are_pruned_to_the_disk_quota_unpaid_first() -> eyre::Result<()> {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir)?;
+        let store_config = NodeRecordStoreConfig {
+            storage_dir: storage_dir.clone(),
+            historic_quote_dir: storage_dir,
+            max_disk_usage: Some(1000),
+            pruning_policy: PruningPolicy::Largest,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _swarm_cmd_receiver) = mpsc::channel(10);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        );
+
+        let put = |store: &mut NodeRecordStore, name: &[u8], size: usize| {
+            let key = Key::new(&name);
+            store.put_verified(Record::new(key.clone(), vec![1; size]), RecordType::Chunk)?;
+            store.mark_as_stored(key.clone(), RecordType::Chunk);
+            Ok::<_, Error>(key)
+        };
+        let paid = put(&mut store, b"paid", 400)?;
+        store.payment_received(paid.clone());
+        let large = put(&mut store, b"large", 300)?;
+        let small = put(&mut store, b"small", 200)?;
+        assert_eq!(store.disk_usage().used_bytes, 900);
+
+        // the largest unpaid record makes room for the incoming one
+        let medium = put(&mut store, b"medium", 250)?;
+        assert!(!store.contains(&large));
+        assert_eq!(store.disk_usage().used_bytes, 850);
+
+        // as many records are pruned as needed, the paid one being kept
+        let huge = put(&mut store, b"huge", 500)?;
+        assert!(!store.contains(&medium) && !store.contains(&small));
+        assert!(store.contains(&paid) && store.contains(&huge));
+        assert_eq!(
+            store.disk_usage(),
+            DiskUsage {
+                records: 2,
+                used_bytes: 900,
+                max_bytes: Some(1000)
+            }
+        );
+
+        assert!(matches!(
+            put(&mut store, b"too large", 1001),
+            Err(Error::ValueTooLarge)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn paid_records_are_still_pruned_last_after_a_restart() -> eyre::Result<()> {
+        let root_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let storage_dir = root_dir.join("record_store");
+        fs::create_dir_all(&storage_dir)?;
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            historic_quote_dir: root_dir,
+            max_disk_usage: Some(1000),
+            pruning_policy: PruningPolicy::Largest,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, mut swarm_cmd_receiver) = mpsc::channel(10);
+        let open = || {
+            NodeRecordStore::with_config(
+                PeerId::random(),
+                store_config.clone(),
+                network_event_sender.clone(),
+                swarm_cmd_sender.clone(),
+            )
+        };
+        let put = |store: &mut NodeRecordStore, name: &[u8], size: usize| {
+            let key = Key::new(&name);
+            let value = try_serialize_record(&Bytes::from(vec![1; size]), RecordKind::Chunk)?;
+            store.put_verified(Record::new(key.clone(), value.to_vec()), RecordType::Chunk)?;
+            store.mark_as_stored(key.clone(), RecordType::Chunk);
+            Ok::<_, eyre::Report>(key)
+        };
+
+        let mut store = open();
+        let paid = put(&mut store, b"paid", 400)?;
+        store.payment_received(paid.clone());
+        let unpaid = put(&mut store, b"unpaid", 300)?;
+        // wait for the records to be written
+        for _ in 0..2 {
+            let cmd = swarm_cmd_receiver.recv().await;
+            assert!(matches!(
+                cmd,
+                Some(LocalSwarmCmd::AddLocalRecordAsStored { .. })
+            ));
+        }
+        drop(store);
+
+        let mut store = open();
+        assert!(store.contains(&paid) && store.contains(&unpaid));
+        // the paid record is the largest, but the unpaid one makes room for the incoming one
+        let incoming = put(&mut store, b"incoming", 500)?;
+        assert!(!store.contains(&unpaid));
+        assert!(store.contains(&paid) && store.contains(&incoming));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn records_journaled_before_a_crash_are_stored_on_restart() -> eyre::Result<()> {
+        let root_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let storage_dir = root_dir.join("record_store");
+        fs::create_dir_all(&storage_dir)?;
+        let store_config = NodeRecordStoreConfig {
+            storage_dir: storage_dir.clone(),
+            historic_quote_dir: root_dir.clone(),
+            journal_dir: Some(root_dir.join("record_journal")),
+            ..Default::default()
+        };
+        let value = try_serialize_record(&Bytes::from_static(b"paid for"), RecordKind::Chunk)?;
+        let record = Record::new(Key::new(b"accepted"), value.to_vec());
+
+        // the node was killed after accepting the record, halfway through writing it
+        let _crashed = RecordJournal::open(root_dir.join("record_journal"))?.append(&record)?;
+        fs::write(
+            storage_dir.join(DiskRecordStoreBackend::generate_filename(&record.key)),
+            &record.value[..3],
+        )?;
+
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _swarm_cmd_receiver) = mpsc::channel(10);
+        let store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        );
+        assert!(store.contains(&record.key));
+        assert_eq!(
+            store.get(&record.key).map(|stored| stored.value.clone()),
+            Some(record.value)
+        );
+        assert_eq!(fs::read_dir(root_dir.join("record_journal"))?.count(), 0);
+        fs::remove_dir_all(root_dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expired_records_are_not_served_and_get_removed() -> eyre::Result<()> {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir)?;
+        let store_config = NodeRecordStoreConfig {
+            storage_dir: storage_dir.clone(),
+            historic_quote_dir: storage_dir,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _swarm_cmd_receiver) = mpsc::channel(10);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        );
+
+        let mut put = |ttl: Duration| {
+            let expiring_data = ExpiringData::new(Bytes::from_static(b"message"), ttl)?;
+            let key = expiring_data.network_address().to_record_key();
+            let value = try_serialize_record(&expiring_data, RecordKind::ExpiringData)?.to_vec();
+            let record_type = RecordType::NonChunk(XorName::from_content(&value));
+            store.put_verified(Record::new(key.clone(), value), record_type.clone())?;
+            store.mark_as_stored(key.clone(), record_type);
+            Ok::<_, eyre::Report>(key)
+        };
+        let live = put(Duration::from_secs(60))?;
+        let expired = put(Duration::ZERO)?;
+
+        assert!(store.get(&live).is_some());
+        assert!(store.get(&expired).is_none());
+
+        store.remove_expired_records();
+        assert!(store.contains(&live));
+        assert!(!store.contains(&expired));
+        Ok(())
+    }
+
     #[test]
     fn put_get_remove_record() {
         fn prop(r: ArbitraryRecord) {
@@ -1443,7 +2113,7 @@ mod tests {
             swarm_cmd_sender.clone(),
         );
 
-        store.payment_received();
+        store.payment_received(RecordKey::new(&b"paid record"));
 
         // Wait for a while to allow the file written to disk.
         sleep(Duration::from_millis(5000)).await;
@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 #![allow(clippy::mutable_key_type)] // for the Bytes in NetworkAddress
 
-use crate::record_store::{ClientRecordStore, NodeRecordStore};
+use crate::record_store::{ClientRecordStore, DiskUsage, NodeRecordStore};
 use libp2p::kad::{
     store::{RecordStore, Result},
     ProviderRecord, Record, RecordKey,
@@ -122,12 +122,31 @@ impl UnifiedRecordStore {
         }
     }
 
-    pub(crate) fn payment_received(&mut self) {
+    pub(crate) fn payment_received(&mut self, key: RecordKey) {
         match self {
             Self::Client(_) => {
                 warn!("Calling payment_received at Client. This should not happen");
             }
-            Self::Node(store) => store.payment_received(),
+            Self::Node(store) => store.payment_received(key),
+        }
+    }
+
+    pub(crate) fn flush_paid_records(&mut self) {
+        match self {
+            Self::Client(_) => {
+                warn!("Calling flush_paid_records at Client. This should not happen");
+            }
+            Self::Node(store) => store.flush_paid_records(),
+        }
+    }
+
+    pub(crate) fn disk_usage(&self) -> DiskUsage {
+        match self {
+            Self::Client(_) => {
+                warn!("Calling disk_usage at Client. This should not happen");
+                DiskUsage::default()
+            }
+            Self::Node(store) => store.disk_usage(),
         }
     }
 
@@ -170,6 +189,22 @@ impl UnifiedRecordStore {
         };
     }
 
+    pub(crate) fn quarantine(&mut self, k: &RecordKey) {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling quarantine at Client. This should not happen");
+            }
+            Self::Node(store) => store.quarantine(k),
+        }
+    }
+
+    pub(crate) fn remove_expired_records(&mut self) {
+        match self {
+            Self::Client(_store) => {}
+            Self::Node(store) => store.remove_expired_records(),
+        }
+    }
+
     pub(crate) fn cleanup_unrelevant_records(&mut self) {
         match self {
             Self::Client(_store) => {
@@ -0,0 +1,339 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::kad::RecordKey as Key;
+use std::{
+    fmt::Debug,
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+use walkdir::WalkDir;
+
+/// The storage engine the `NodeRecordStore` keeps the records of the node in.
+///
+/// The `NodeRecordStore` keeps track of the stored keys, caches, prunes and encrypts the records,
+/// and the replication only ever goes through it, so a backend only has to store the bytes it's
+/// given by key. Writes and deletes are run off the swarm driver's thread.
+pub trait RecordStoreBackend: Debug + Send + Sync {
+    /// Returns the bytes stored for the key, or `None` if nothing is stored for it.
+    fn get(&self, key: &Key) -> io::Result<Option<Vec<u8>>>;
+
+    /// Stores the bytes for the key, replacing any stored before.
+    fn put(&self, key: &Key, value: &[u8]) -> io::Result<()>;
+
+    /// Deletes the bytes stored for the key, which is not an error if nothing is stored for it.
+    fn delete(&self, key: &Key) -> io::Result<()>;
+
+    /// Returns the keys of everything stored, for the store to be restored on restart.
+    /// The keys are streamed, a node holding too many records for them all to be listed at once.
+    fn iterate(&self) -> Box<dyn Iterator<Item = io::Result<Key>> + '_>;
+
+    fn stats(&self) -> io::Result<RecordStoreStats>;
+}
+
+/// What a `RecordStoreBackend` holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecordStoreStats {
+    pub records: usize,
+    /// The space taken by the records, in bytes.
+    pub used_bytes: u64,
+}
+
+/// The default `RecordStoreBackend`, storing each record in a file of its own, named after the
+/// hex encoded key.
+#[derive(Clone, Debug)]
+pub struct DiskRecordStoreBackend {
+    storage_dir: PathBuf,
+}
+
+impl DiskRecordStoreBackend {
+    pub fn new(storage_dir: PathBuf) -> Self {
+        Self { storage_dir }
+    }
+
+    // Converts a Key into a Hex string.
+    pub(crate) fn generate_filename(key: &Key) -> String {
+        hex::encode(key.as_ref())
+    }
+
+    // Converts a Hex string back into a Key.
+    fn get_data_from_filename(hex_str: &str) -> Option<Key> {
+        match hex::decode(hex_str) {
+            Ok(bytes) => Some(Key::from(bytes)),
+            Err(error) => {
+                error!("Error decoding hex string: {:?}", error);
+                None
+            }
+        }
+    }
+
+    fn files(&self) -> impl Iterator<Item = walkdir::DirEntry> {
+        WalkDir::new(&self.storage_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.path().is_file())
+    }
+}
+
+impl RecordStoreBackend for DiskRecordStoreBackend {
+    fn get(&self, key: &Key) -> io::Result<Option<Vec<u8>>> {
+        let file_path = self.storage_dir.join(Self::generate_filename(key));
+        match fs::read(file_path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn put(&self, key: &Key, value: &[u8]) -> io::Result<()> {
+        fs::write(self.storage_dir.join(Self::generate_filename(key)), value)
+    }
+
+    fn delete(&self, key: &Key) -> io::Result<()> {
+        match fs::remove_file(self.storage_dir.join(Self::generate_filename(key))) {
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    fn iterate(&self) -> Box<dyn Iterator<Item = io::Result<Key>> + '_> {
+        Box::new(self.files().filter_map(|entry| {
+            let path = entry.path();
+            debug!("Existing record found: {path:?}");
+            match path.file_name().and_then(|n| n.to_str()) {
+                Some(filename) => Self::get_data_from_filename(filename).map(Ok),
+                None => {
+                    // warn and remove this file as it's not a valid record
+                    warn!(
+                        "Found a file in the storage dir that is not a valid record: {:?}",
+                        path
+                    );
+                    if let Err(e) = fs::remove_file(path) {
+                        warn!(
+                            "Failed to remove invalid record file from storage dir: {:?}",
+                            e
+                        );
+                    }
+                    None
+                }
+            }
+        }))
+    }
+
+    fn stats(&self) -> io::Result<RecordStoreStats> {
+        let mut stats = RecordStoreStats::default();
+        for entry in self.files() {
+            stats.records += 1;
+            stats.used_bytes += entry.metadata().map_err(io::Error::from)?.len();
+        }
+        Ok(stats)
+    }
+}
+
+/// A `RecordStoreBackend` keeping the records in a RocksDB database, for nodes holding so many
+/// small records that a file per record exhausts the inodes and the fsync throughput.
+#[cfg(feature = "rocksdb")]
+pub struct RocksDbRecordStoreBackend {
+    path: PathBuf,
+    db: rocksdb::DB,
+}
+
+#[cfg(feature = "rocksdb")]
+impl RocksDbRecordStoreBackend {
+    /// Opens the database at the path, creating it if it doesn't exist yet.
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let db = rocksdb::DB::open_default(&path).map_err(io::Error::other)?;
+        Ok(Self { path, db })
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+impl Debug for RocksDbRecordStoreBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RocksDbRecordStoreBackend")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+impl RecordStoreBackend for RocksDbRecordStoreBackend {
+    fn get(&self, key: &Key) -> io::Result<Option<Vec<u8>>> {
+        self.db.get(key.as_ref()).map_err(io::Error::other)
+    }
+
+    fn put(&self, key: &Key, value: &[u8]) -> io::Result<()> {
+        self.db.put(key.as_ref(), value).map_err(io::Error::other)
+    }
+
+    fn delete(&self, key: &Key) -> io::Result<()> {
+        self.db.delete(key.as_ref()).map_err(io::Error::other)
+    }
+
+    fn iterate(&self) -> Box<dyn Iterator<Item = io::Result<Key>> + '_> {
+        Box::new(self.db.iterator(rocksdb::IteratorMode::Start).map(|entry| {
+            entry
+                .map(|(key, _)| Key::from(key.into_vec()))
+                .map_err(io::Error::other)
+        }))
+    }
+
+    // RocksDB only estimates these, which is good enough for the metrics.
+    fn stats(&self) -> io::Result<RecordStoreStats> {
+        let property = |name| {
+            self.db
+                .property_int_value(name)
+                .map(Option::unwrap_or_default)
+                .map_err(io::Error::other)
+        };
+        Ok(RecordStoreStats {
+            records: property(rocksdb::properties::ESTIMATE_NUM_KEYS)? as usize,
+            used_bytes: property(rocksdb::properties::LIVE_SST_FILES_SIZE)?,
+        })
+    }
+}
+
+/// Moves all the records of a backend into another one, e.g. from the files of a
+/// `DiskRecordStoreBackend` into a `RocksDbRecordStoreBackend`, returning how many were moved.
+///
+/// Each record is only deleted from the source once stored in the target, so an interrupted
+/// migration can be run again.
+pub fn migrate_records(
+    from: &dyn RecordStoreBackend,
+    to: &dyn RecordStoreBackend,
+) -> io::Result<usize> {
+    let mut migrated = 0;
+    for key in from.iterate() {
+        let key = key?;
+        let Some(value) = from.get(&key)? else {
+            continue;
+        };
+        to.put(&key, &value)?;
+        from.delete(&key)?;
+        migrated += 1;
+    }
+    info!("Migrated {migrated} records from {from:?} to {to:?}");
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_backend_stores_each_record_in_a_file() -> eyre::Result<()> {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir)?;
+        let backend = DiskRecordStoreBackend::new(storage_dir);
+        let key = Key::new(&b"some record");
+
+        assert_eq!(backend.get(&key)?, None);
+        backend.put(&key, b"value")?;
+        assert_eq!(backend.get(&key)?, Some(b"value".to_vec()));
+        assert_eq!(
+            backend.iterate().collect::<io::Result<Vec<_>>>()?,
+            vec![key.clone()]
+        );
+        assert_eq!(
+            backend.stats()?,
+            RecordStoreStats {
+                records: 1,
+                used_bytes: 5
+            }
+        );
+
+        backend.delete(&key)?;
+        backend.delete(&key)?;
+        assert_eq!(backend.get(&key)?, None);
+        assert_eq!(backend.iterate().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn records_are_moved_from_a_backend_to_another() -> eyre::Result<()> {
+        let root_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let (from_dir, to_dir) = (root_dir.join("from"), root_dir.join("to"));
+        fs::create_dir_all(&from_dir)?;
+        fs::create_dir_all(&to_dir)?;
+        let from = DiskRecordStoreBackend::new(from_dir);
+        let to = DiskRecordStoreBackend::new(to_dir);
+        let keys: Vec<_> = (0..3u8).map(|i| Key::new(&[i])).collect();
+        for key in &keys {
+            from.put(key, key.as_ref())?;
+        }
+
+        assert_eq!(migrate_records(&from, &to)?, 3);
+        assert_eq!(from.iterate().count(), 0);
+        for key in &keys {
+            assert_eq!(to.get(key)?, Some(key.to_vec()));
+        }
+        assert_eq!(migrate_records(&from, &to)?, 0);
+        Ok(())
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn rocksdb_backend_stores_the_records_by_key() -> eyre::Result<()> {
+        let path = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let backend = RocksDbRecordStoreBackend::open(path.clone())?;
+        let keys: Vec<_> = (0..3u8).map(|i| Key::new(&[i])).collect();
+
+        assert_eq!(backend.get(&keys[0])?, None);
+        for key in &keys {
+            backend.put(key, key.as_ref())?;
+        }
+        backend.put(&keys[0], b"replaced")?;
+        assert_eq!(backend.get(&keys[0])?, Some(b"replaced".to_vec()));
+        assert_eq!(backend.get(&keys[1])?, Some(keys[1].to_vec()));
+        // the keys are listed in order
+        assert_eq!(backend.iterate().collect::<io::Result<Vec<_>>>()?, keys);
+
+        backend.delete(&keys[1])?;
+        backend.delete(&keys[1])?;
+        assert_eq!(backend.get(&keys[1])?, None);
+        assert_eq!(
+            backend.iterate().collect::<io::Result<Vec<_>>>()?,
+            vec![keys[0].clone(), keys[2].clone()]
+        );
+
+        // the records are kept over a restart
+        drop(backend);
+        let backend = RocksDbRecordStoreBackend::open(path.clone())?;
+        assert_eq!(backend.get(&keys[2])?, Some(keys[2].to_vec()));
+        drop(backend);
+        fs::remove_dir_all(path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn records_are_moved_from_the_disk_backend_to_rocksdb() -> eyre::Result<()> {
+        let root_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let from_dir = root_dir.join("records");
+        fs::create_dir_all(&from_dir)?;
+        let from = DiskRecordStoreBackend::new(from_dir);
+        let to = RocksDbRecordStoreBackend::open(root_dir.join("rocksdb"))?;
+        let keys: Vec<_> = (0..100u8).map(|i| Key::new(&[i])).collect();
+        for key in &keys {
+            from.put(key, key.as_ref())?;
+        }
+
+        assert_eq!(migrate_records(&from, &to)?, keys.len());
+        assert_eq!(from.iterate().count(), 0);
+        for key in &keys {
+            assert_eq!(to.get(key)?, Some(key.to_vec()));
+        }
+        assert_eq!(to.iterate().count(), keys.len());
+        assert_eq!(migrate_records(&from, &to)?, 0);
+
+        drop(to);
+        fs::remove_dir_all(root_dir)?;
+        Ok(())
+    }
+}
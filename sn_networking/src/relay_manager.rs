@@ -9,14 +9,59 @@
 use crate::driver::{BadNodes, NodeBehaviour};
 use itertools::Itertools;
 use libp2p::{
-    core::transport::ListenerId, multiaddr::Protocol, Multiaddr, PeerId, StreamProtocol, Swarm,
+    core::transport::ListenerId, multiaddr::Protocol, relay, Multiaddr, PeerId, StreamProtocol,
+    Swarm,
 };
 use rand::Rng;
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    time::Duration,
+};
 
 const MAX_CONCURRENT_RELAY_CONNECTIONS: usize = 4;
 const MAX_POTENTIAL_CANDIDATES: usize = 1000;
 
+/// The limits a node relays the connections of the nodes behind a NAT within, as a relay server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RelayLimits {
+    /// The most nodes relayed for at once, i.e. the reservations they hold.
+    pub max_reservations: usize,
+    /// The most connections relayed at once, all nodes together.
+    pub max_circuits: usize,
+    /// The most connections relayed at once to or from a single peer.
+    pub max_circuits_per_peer: usize,
+    /// How long a relayed connection lasts before it's closed.
+    pub max_circuit_duration: Duration,
+    /// The most bytes relayed over a connection, both ways, before it's closed.
+    pub max_circuit_bytes: u64,
+}
+
+impl Default for RelayLimits {
+    fn default() -> Self {
+        Self {
+            max_reservations: 128,
+            max_circuits: 1024,
+            max_circuits_per_peer: 256,
+            max_circuit_duration: Duration::from_secs(2 * 60),
+            max_circuit_bytes: 128 * 1024,
+        }
+    }
+}
+
+impl RelayLimits {
+    pub(crate) fn server_config(&self) -> relay::Config {
+        relay::Config {
+            max_reservations: self.max_reservations,
+            max_circuits: self.max_circuits,
+            max_circuits_per_peer: self.max_circuits_per_peer,
+            max_circuit_duration: self.max_circuit_duration,
+            max_circuit_bytes: self.max_circuit_bytes,
+            circuit_src_rate_limiters: vec![], // No extra rate limiting for now
+            ..Default::default()
+        }
+    }
+}
+
 pub(crate) fn is_a_relayed_peer(addrs: &HashSet<Multiaddr>) -> bool {
     addrs
         .iter()
@@ -227,13 +272,9 @@ impl RelayManager {
         }
     }
 
+    /// Whether the peer serves as a relay, which not all nodes do.
     fn does_it_support_relay_server_protocol(protocols: &Vec<StreamProtocol>) -> bool {
-        for stream_protocol in protocols {
-            if *stream_protocol == "/libp2p/circuit/relay/0.2.0/stop" {
-                return true;
-            }
-        }
-        false
+        protocols.contains(&relay::HOP_PROTOCOL_NAME)
     }
 
     /// The listen addr should be something like /ip4/198.51.100.0/tcp/55555/p2p/QmRelay/p2p-circuit/
@@ -242,7 +283,7 @@ impl RelayManager {
 
         let ip = addr
             .iter()
-            .find(|protocol| matches!(protocol, Protocol::Ip4(_)))?;
+            .find(|protocol| matches!(protocol, Protocol::Ip4(_) | Protocol::Ip6(_)))?;
         output_addr.push(ip);
         let port = addr
             .iter()
@@ -13,6 +13,8 @@ use libp2p::{
     kad::{KBucketDistance as Distance, RecordKey, K_VALUE},
     PeerId,
 };
+#[cfg(feature = "open-metrics")]
+use prometheus_client::metrics::gauge::Gauge;
 use sn_protocol::{storage::RecordType, NetworkAddress, PrettyPrintRecordKey};
 use std::collections::{hash_map::Entry, BTreeSet, HashMap};
 use tokio::{sync::mpsc, time::Duration};
@@ -26,6 +28,14 @@ const MAX_PARALLEL_FETCH: usize = K_VALUE.get();
 // Hence shall give a longer time as allowance.
 const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
 
+// How long the departure of a peer is taken into account to prioritise the fetches, the records
+// it held having lost a copy until the network replicated them.
+const CHURN_WINDOW: Duration = Duration::from_secs(600);
+
+// The size of a full chunk, which the fetches are held back for when the replication traffic is
+// capped in bytes.
+const FULL_CHUNK_SIZE: usize = 1024 * 1024;
+
 // The duration after which a pending entry shall be cleared from the `to_be_fetch` list.
 // This is to avoid holding too many outdated entries when the fetching speed is slow.
 const PENDING_TIMEOUT: Duration = Duration::from_secs(900);
@@ -33,6 +43,29 @@ const PENDING_TIMEOUT: Duration = Duration::from_secs(900);
 // The time the entry will be considered as `time out` and to be cleared.
 type ReplicationTimeout = Instant;
 
+// See `ReplicationFetcher::fetch_priority`.
+type FetchPriority = (usize, std::cmp::Reverse<usize>, bool, Distance);
+
+/// Caps on the replication traffic of a node, for it not to saturate the connection it runs on
+/// while joining the network or absorbing churn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplicationLimits {
+    /// The records fetched from and served to the peers, in bytes per second.
+    /// `None` leaves it unlimited.
+    pub bytes_per_sec: Option<u64>,
+    /// The number of records fetched at the same time.
+    pub concurrent_fetches: usize,
+}
+
+impl Default for ReplicationLimits {
+    fn default() -> Self {
+        Self {
+            bytes_per_sec: None,
+            concurrent_fetches: MAX_PARALLEL_FETCH,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ReplicationFetcher {
     self_peer_id: PeerId,
@@ -47,6 +80,13 @@ pub(crate) struct ReplicationFetcher {
     /// used when the node is full, but we still have "close" data coming in
     /// that is _not_ closer than our farthest max record
     farthest_acceptable_distance: Option<Distance>,
+    max_parallel_fetches: usize,
+    fetch_timeout: Duration,
+    // The peers which recently left the routing table, along with when they did.
+    recent_departures: HashMap<PeerId, Instant>,
+    #[cfg(feature = "open-metrics")]
+    /// Used to report the number of keys to fetch, or being fetched, to the metrics server.
+    queue_depth_metric: Option<Gauge>,
 }
 
 impl ReplicationFetcher {
@@ -59,9 +99,45 @@ impl ReplicationFetcher {
             event_sender,
             distance_range: None,
             farthest_acceptable_distance: None,
+            max_parallel_fetches: MAX_PARALLEL_FETCH,
+            fetch_timeout: FETCH_TIMEOUT,
+            recent_departures: HashMap::new(),
+            #[cfg(feature = "open-metrics")]
+            queue_depth_metric: None,
         }
     }
 
+    /// Set the queue_depth_metric to report the number of keys to fetch to the metrics server
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn set_queue_depth_metric(&mut self, metric: Gauge) {
+        self.queue_depth_metric = Some(metric);
+    }
+
+    /// Set the number of records fetched at the same time, `MAX_PARALLEL_FETCH` by default.
+    ///
+    /// Once the traffic is capped in bytes, the fetches can be held back until all the ongoing
+    /// ones got through, so they are given that much longer before their holder is considered
+    /// failed to fetch from.
+    pub(crate) fn set_replication_limits(&mut self, limits: ReplicationLimits) {
+        self.max_parallel_fetches = limits.concurrent_fetches.max(1);
+        self.fetch_timeout = match limits.bytes_per_sec.filter(|limit| *limit > 0) {
+            Some(limit) => {
+                let backlog = (self.max_parallel_fetches * FULL_CHUNK_SIZE) as f64;
+                FETCH_TIMEOUT + Duration::from_secs_f64(backlog / limit as f64)
+            }
+            None => FETCH_TIMEOUT,
+        };
+    }
+
+    /// Notify the fetcher that a peer left the routing table, for the records it held to be
+    /// fetched first.
+    pub(crate) fn on_peer_removed(&mut self, peer: PeerId) {
+        let now = Instant::now();
+        self.recent_departures
+            .retain(|_, departed_at| now.duration_since(*departed_at) < CHURN_WINDOW);
+        let _ = self.recent_departures.insert(peer, now);
+    }
+
     /// Set the distance range.
     pub(crate) fn set_replication_distance_range(&mut self, distance_range: u32) {
         self.distance_range = Some(distance_range);
@@ -122,7 +198,7 @@ impl ReplicationFetcher {
             if let Entry::Vacant(entry) = self.on_going_fetches.entry(new_data_key.clone()) {
                 let (record_key, _record_type) = new_data_key;
                 keys_to_fetch.push((holder, record_key));
-                let _ = entry.insert((holder, Instant::now() + FETCH_TIMEOUT));
+                let _ = entry.insert((holder, Instant::now() + self.fetch_timeout));
             }
 
             // To avoid later on un-necessary actions.
@@ -246,13 +322,19 @@ impl ReplicationFetcher {
 
     // Returns the set of keys that has to be fetched from the peer/network.
     // Target must not be under-fetching
-    // and no more than MAX_PARALLEL_FETCH fetches to be undertaken at the same time.
+    // and no more than `max_parallel_fetches` fetches to be undertaken at the same time.
     pub(crate) fn next_keys_to_fetch(&mut self) -> Vec<(PeerId, RecordKey)> {
         self.prune_expired_keys_and_slow_nodes();
 
+        // the keys about to be fetched are only moved to the `on_going_fetches`
+        #[cfg(feature = "open-metrics")]
+        if let Some(metric) = &self.queue_depth_metric {
+            let _ = metric.set((self.to_be_fetched.len() + self.on_going_fetches.len()) as i64);
+        }
+
         debug!("Next to fetch....");
 
-        if self.on_going_fetches.len() >= MAX_PARALLEL_FETCH {
+        if self.on_going_fetches.len() >= self.max_parallel_fetches {
             warn!("Replication Fetcher doesn't have free fetch capacity. Currently has {} entries in queue.",
                 self.to_be_fetched.len());
             return vec![];
@@ -266,22 +348,27 @@ impl ReplicationFetcher {
         }
 
         let mut data_to_fetch = vec![];
-        // Sort to_be_fetched by key closeness to our PeerId
-        let mut to_be_fetched_sorted: Vec<_> = self.to_be_fetched.iter_mut().collect();
-
-        let self_address = NetworkAddress::from_peer(self.self_peer_id);
-
-        to_be_fetched_sorted.sort_by(|((a, _, _), _), ((b, _, _), _)| {
-            let a = NetworkAddress::from_record_key(a);
-            let b = NetworkAddress::from_record_key(b);
-            self_address.distance(&a).cmp(&self_address.distance(&b))
-        });
+        let mut holders: HashMap<(&RecordKey, &RecordType), usize> = HashMap::new();
+        for (key, t, holder) in self.to_be_fetched.keys() {
+            if !self.recent_departures.contains_key(holder) {
+                *holders.entry((key, t)).or_default() += 1;
+            }
+        }
+        let mut to_be_fetched_sorted: Vec<_> = self
+            .to_be_fetched
+            .keys()
+            .map(|entry| {
+                let holders = holders.get(&(&entry.0, &entry.1)).copied().unwrap_or(0);
+                (self.fetch_priority(&entry.0, &entry.1, holders), entry)
+            })
+            .collect();
+        to_be_fetched_sorted.sort_by_key(|(priority, _)| *priority);
 
-        for ((key, t, holder), _) in to_be_fetched_sorted {
+        for (_, (key, t, holder)) in to_be_fetched_sorted {
             // Already carried out expiration pruning above.
             // Hence here only need to check whether is ongoing fetching.
             // Also avoid fetching same record from different nodes.
-            if self.on_going_fetches.len() < MAX_PARALLEL_FETCH
+            if self.on_going_fetches.len() < self.max_parallel_fetches
                 && !self
                     .on_going_fetches
                     .contains_key(&(key.clone(), t.clone()))
@@ -289,12 +376,12 @@ impl ReplicationFetcher {
                 data_to_fetch.push((*holder, key.clone(), t.clone()));
                 let _ = self.on_going_fetches.insert(
                     (key.clone(), t.clone()),
-                    (*holder, Instant::now() + FETCH_TIMEOUT),
+                    (*holder, Instant::now() + self.fetch_timeout),
                 );
             }
 
             // break out the loop early if we can do no more now
-            if self.on_going_fetches.len() >= MAX_PARALLEL_FETCH {
+            if self.on_going_fetches.len() >= self.max_parallel_fetches {
                 break;
             }
         }
@@ -326,6 +413,35 @@ impl ReplicationFetcher {
     // The node then considered to be in trouble and:
     //   1, the pending_entries from that node shall be removed from `to_be_fetched` list.
     //   2, firing event up to notify bad_nodes, hence trigger them to be removed from RT.
+    // The order the keys are fetched in, the records most at risk of being lost first:
+    //   1, the ones left with the fewest holders, departed peers not counting,
+    //   2, the ones the most recently departed peers were closer to than us, which likely held them,
+    //   3, the spends and the other mutable records, rather than the chunks,
+    //   4, the ones closest to us.
+    fn fetch_priority(
+        &self,
+        key: &RecordKey,
+        record_type: &RecordType,
+        holders: usize,
+    ) -> FetchPriority {
+        let self_address = NetworkAddress::from_peer(self.self_peer_id);
+        let address = NetworkAddress::from_record_key(key);
+        let distance = self_address.distance(&address);
+
+        let departed_holders = self
+            .recent_departures
+            .keys()
+            .filter(|peer| NetworkAddress::from_peer(**peer).distance(&address) < distance)
+            .count();
+
+        (
+            holders,
+            std::cmp::Reverse(departed_holders),
+            matches!(record_type, RecordType::Chunk),
+            distance,
+        )
+    }
+
     fn prune_expired_keys_and_slow_nodes(&mut self) {
         let mut failed_fetches = vec![];
 
@@ -406,13 +522,82 @@ impl ReplicationFetcher {
 
 #[cfg(test)]
 mod tests {
-    use super::{ReplicationFetcher, FETCH_TIMEOUT, MAX_PARALLEL_FETCH};
+    use super::{ReplicationFetcher, ReplicationLimits, FETCH_TIMEOUT, MAX_PARALLEL_FETCH};
     use eyre::Result;
     use libp2p::{kad::RecordKey, PeerId};
     use sn_protocol::{storage::RecordType, NetworkAddress};
     use std::{collections::HashMap, time::Duration};
     use tokio::{sync::mpsc, time::sleep};
 
+    #[tokio::test]
+    async fn fetches_are_held_to_the_replication_limits() -> Result<()> {
+        let (event_sender, _event_receiver) = mpsc::channel(4);
+        let mut replication_fetcher = ReplicationFetcher::new(PeerId::random(), event_sender);
+        replication_fetcher.set_replication_limits(ReplicationLimits {
+            bytes_per_sec: Some(1024 * 1024),
+            concurrent_fetches: 2,
+        });
+        assert_eq!(
+            replication_fetcher.fetch_timeout,
+            FETCH_TIMEOUT + Duration::from_secs(2)
+        );
+
+        let incoming_keys = (0..5)
+            .map(|_| {
+                let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+                let key = NetworkAddress::from_record_key(&RecordKey::from(random_data));
+                (key, RecordType::Chunk)
+            })
+            .collect();
+        let keys_to_fetch =
+            replication_fetcher.add_keys(PeerId::random(), incoming_keys, &HashMap::new());
+        assert_eq!(keys_to_fetch.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn records_most_at_risk_are_fetched_first() {
+        let self_peer_id = PeerId::random();
+        let (event_sender, _event_receiver) = mpsc::channel(4);
+        let mut replication_fetcher = ReplicationFetcher::new(self_peer_id, event_sender);
+        let random_key = || {
+            let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+            RecordKey::from(random_data)
+        };
+        let spend_type = RecordType::NonChunk(xor_name::XorName::random(&mut rand::thread_rng()));
+        let key = random_key();
+
+        // the fewer holders, the sooner, the spends coming before the chunks.
+        assert!(
+            replication_fetcher.fetch_priority(&key, &RecordType::Chunk, 1)
+                < replication_fetcher.fetch_priority(&random_key(), &RecordType::Chunk, 2)
+        );
+        assert!(
+            replication_fetcher.fetch_priority(&key, &spend_type, 1)
+                < replication_fetcher.fetch_priority(&key, &RecordType::Chunk, 1)
+        );
+
+        // a record a departed peer was closer to than us likely lost a holder.
+        let departed = PeerId::random();
+        let self_address = NetworkAddress::from_peer(self_peer_id);
+        let departed_address = NetworkAddress::from_peer(departed);
+        let (lost_copy, kept_copy) = loop {
+            let (a, b) = (random_key(), random_key());
+            let closer_to_departed = |key: &RecordKey| {
+                let address = NetworkAddress::from_record_key(key);
+                departed_address.distance(&address) < self_address.distance(&address)
+            };
+            if closer_to_departed(&a) && !closer_to_departed(&b) {
+                break (a, b);
+            }
+        };
+        replication_fetcher.on_peer_removed(departed);
+        assert!(
+            replication_fetcher.fetch_priority(&lost_copy, &RecordType::Chunk, 1)
+                < replication_fetcher.fetch_priority(&kept_copy, &RecordType::Chunk, 1)
+        );
+    }
+
     #[tokio::test]
     async fn verify_max_parallel_fetches() -> Result<()> {
         //random peer_id
@@ -15,8 +15,7 @@ use sn_protocol::{
     NetworkAddress, PrettyPrintRecordKey,
 };
 use sn_transfers::{
-    CashNote, CashNoteRedemption, DerivationIndex, HotWallet, MainPubkey, SignedSpend, Transaction,
-    Transfer, UniquePubkey,
+    CashNote, CashNoteRedemption, HotWallet, MainPubkey, SignedSpend, Transaction, Transfer,
 };
 use std::collections::BTreeSet;
 use tokio::task::JoinSet;
@@ -121,6 +120,18 @@ impl Network {
         &self,
         main_pubkey: MainPubkey,
         cashnote_redemptions: &[CashNoteRedemption],
+    ) -> Result<Vec<CashNote>> {
+        self.verify_cash_notes_redemptions_to_any(&[main_pubkey], cashnote_redemptions)
+            .await
+    }
+
+    /// Like `verify_cash_notes_redemptions`, but each CashNoteRedemption may be destined to any of
+    /// the given keys, e.g. to one of the network royalties beneficiaries.
+    /// The returned CashNotes are owned by the key each CashNoteRedemption was destined to.
+    pub async fn verify_cash_notes_redemptions_to_any(
+        &self,
+        main_pubkeys: &[MainPubkey],
+        cashnote_redemptions: &[CashNoteRedemption],
     ) -> Result<Vec<CashNote>> {
         // get the parent transactions
         debug!(
@@ -147,23 +158,22 @@ impl Network {
             parent_spends.iter().map(|s| s.spent_tx()).collect();
 
         // get our outputs from Tx
-        let our_output_unique_pubkeys: Vec<(UniquePubkey, DerivationIndex)> = cashnote_redemptions
-            .iter()
-            .map(|u| {
-                let unique_pubkey = main_pubkey.new_unique_pubkey(&u.derivation_index);
-                (unique_pubkey, u.derivation_index)
-            })
-            .collect();
         let mut our_output_cash_notes = Vec::new();
 
-        for (id, derivation_index) in our_output_unique_pubkeys.into_iter() {
-            let src_tx = parent_txs
+        for u in cashnote_redemptions {
+            let (main_pubkey, id, src_tx) = main_pubkeys
                 .iter()
-                .find(|tx| tx.outputs.iter().any(|o| o.unique_pubkey() == &id))
+                .find_map(|main_pubkey| {
+                    let id = main_pubkey.new_unique_pubkey(&u.derivation_index);
+                    parent_txs
+                        .iter()
+                        .find(|tx| tx.outputs.iter().any(|o| o.unique_pubkey() == &id))
+                        .map(|tx| (*main_pubkey, id, tx.clone()))
+                })
                 .ok_or(NetworkError::InvalidTransfer(
                     "None of the CashNoteRedemptions are destined to our key".to_string(),
-                ))?
-                .clone();
+                ))?;
+            let derivation_index = u.derivation_index;
             let signed_spends: BTreeSet<SignedSpend> = parent_spends
                 .iter()
                 .filter(|s| s.spent_tx_hash() == src_tx.hash())
@@ -3,3 +3,238 @@
 pub(crate) mod mod_impl;
 
 pub(crate) use mod_impl::build_transport;
+
+#[cfg(all(
+    any(feature = "websockets", feature = "webrtc"),
+    not(target_arch = "wasm32")
+))]
+use crate::error::{NetworkError, Result};
+use libp2p::{multiaddr::Protocol, Multiaddr};
+#[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+use std::path::Path;
+#[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+use std::path::PathBuf;
+
+/// The file the certificate of the WebRTC listener is kept in, within the root dir of the node.
+#[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+const WEBRTC_CERTIFICATE_FILENAME: &str = "webrtc_certificate.pem";
+
+/// What the transports of a node or client are built from.
+#[derive(Debug, Default)]
+pub(crate) struct TransportConfig {
+    pub(crate) mode: TransportMode,
+    /// The secret the noise handshakes are bound to, the handshakes with the peers given another
+    /// one failing.
+    pub(crate) noise_prologue: Vec<u8>,
+    /// The TLS config the WebSocket listener serves WSS with.
+    #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+    pub(crate) websocket_tls: Option<libp2p::websocket::tls::Config>,
+    /// The certificate the WebRTC listener serves, if listening on WebRTC.
+    #[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+    pub(crate) webrtc_certificate: Option<libp2p_webrtc::tokio::Certificate>,
+}
+
+/// The transports a node listens and dials on.
+///
+/// QUIC (`/udp/<port>/quic-v1`) sets a connection up in a single round trip, with the security
+/// and the multiplexing built in, and goes through NATs more easily than TCP. TCP
+/// (`/tcp/<port>`, secured with noise and multiplexed with yamux) reaches the peers on the
+/// networks where UDP is blocked or throttled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransportMode {
+    /// Listens on QUIC and TCP, dialing the QUIC address of a peer before its TCP one.
+    #[default]
+    PreferQuic,
+    /// Listens and dials on QUIC only, the peers only reachable over TCP being left out.
+    RequireQuic,
+}
+
+impl TransportMode {
+    /// Whether the address is one the node dials, and adds to its routing table.
+    pub(crate) fn can_dial(&self, addr: &Multiaddr) -> bool {
+        // In a browser, only the WebSocket and WebRTC addresses can be dialed. The WebRTC ones
+        // are only for the browsers, the nodes reaching each other over QUIC or TCP.
+        if cfg!(target_arch = "wasm32") {
+            return is_websocket(addr) || is_webrtc(addr);
+        }
+        if is_webrtc(addr) {
+            return false;
+        }
+        match self {
+            Self::PreferQuic => true,
+            Self::RequireQuic => is_quic(addr),
+        }
+    }
+
+    /// Orders the addresses of a peer in the order they're to be dialed in: QUIC, TCP, then
+    /// WebSocket, the addresses of the preferred IP family coming first within each.
+    pub(crate) fn sort_addrs(&self, addrs: &mut [Multiaddr], prefer_ipv6: bool) {
+        addrs.sort_by_key(|addr| {
+            (
+                !is_quic(addr),
+                is_websocket(addr),
+                is_ipv6(addr) != prefer_ipv6,
+            )
+        });
+    }
+}
+
+/// The WebSocket listener of a node, for the clients running in browsers to connect to it.
+#[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebSocketListener {
+    /// The TCP port to listen on, `0` for one picked by the OS. It's not to be the port the node
+    /// listens on over QUIC and TCP.
+    pub port: u16,
+    /// The certificate to serve WSS with, for the pages loaded over HTTPS to be allowed to
+    /// connect. Plain WS is served if `None`.
+    pub tls: Option<WebSocketTls>,
+}
+
+/// The PEM files of the certificate WSS is served with. The certificate is to be for the domain
+/// name the node is reached at, as in `/dns4/<domain>/tcp/<port>/wss/p2p/<peer id>`.
+#[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebSocketTls {
+    /// The certificate, followed by the intermediate ones.
+    pub cert_chain_file: PathBuf,
+    /// The private key of the certificate, in PKCS#8 or PKCS#1 format.
+    pub private_key_file: PathBuf,
+}
+
+#[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
+impl WebSocketTls {
+    /// Reads the certificate and its key into the TLS config of the WebSocket transport.
+    pub(crate) fn load(&self) -> Result<libp2p::websocket::tls::Config> {
+        use libp2p::websocket::tls;
+
+        let read_pems = |path: &PathBuf| {
+            let bytes = std::fs::read(path).map_err(|err| {
+                NetworkError::InvalidWebSocketTls(format!("failed to read {path:?}: {err}"))
+            })?;
+            pem::parse_many(bytes).map_err(|err| {
+                NetworkError::InvalidWebSocketTls(format!("failed to parse {path:?}: {err}"))
+            })
+        };
+        let certs: Vec<_> = read_pems(&self.cert_chain_file)?
+            .into_iter()
+            .filter(|pem| pem.tag() == "CERTIFICATE")
+            .map(|pem| tls::Certificate::new(pem.into_contents()))
+            .collect();
+        if certs.is_empty() {
+            return Err(NetworkError::InvalidWebSocketTls(format!(
+                "no certificate in {:?}",
+                self.cert_chain_file
+            )));
+        }
+        let key = read_pems(&self.private_key_file)?
+            .into_iter()
+            .find(|pem| matches!(pem.tag(), "PRIVATE KEY" | "RSA PRIVATE KEY"))
+            .ok_or_else(|| {
+                NetworkError::InvalidWebSocketTls(format!(
+                    "no PKCS#8 or PKCS#1 private key in {:?}",
+                    self.private_key_file
+                ))
+            })?;
+        tls::Config::new(tls::PrivateKey::new(key.into_contents()), certs)
+            .map_err(|err| NetworkError::InvalidWebSocketTls(err.to_string()))
+    }
+}
+
+/// The WebRTC listener of a node, for the clients running in browsers to connect to it over
+/// `/udp/<port>/webrtc-direct`, without the node needing a certificate of a domain as WSS does.
+///
+/// The node serves a self-signed certificate, whose hash the browsers learn from the address,
+/// as in `/ip4/<ip>/udp/<port>/webrtc-direct/certhash/<hash>/p2p/<peer id>`. The certificate is
+/// kept in the root dir of the node, so the address survives restarts.
+#[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WebRtcListener {
+    /// The UDP port to listen on, `0` for one picked by the OS. It's not to be the port the node
+    /// listens on over QUIC.
+    pub port: u16,
+}
+
+#[cfg(all(feature = "webrtc", not(target_arch = "wasm32")))]
+impl WebRtcListener {
+    /// Reads the certificate of the listener from the root dir, generating it the first time.
+    pub(crate) fn load_certificate(
+        &self,
+        root_dir: &Path,
+    ) -> Result<libp2p_webrtc::tokio::Certificate> {
+        use libp2p_webrtc::tokio::Certificate;
+
+        let path = root_dir.join(WEBRTC_CERTIFICATE_FILENAME);
+        if path.is_file() {
+            let pem = std::fs::read_to_string(&path)?;
+            return Certificate::from_pem(&pem).map_err(|err| {
+                NetworkError::InvalidWebRtcCertificate(format!("failed to parse {path:?}: {err}"))
+            });
+        }
+
+        info!("Generating the WebRTC certificate into {path:?}");
+        let certificate = Certificate::generate(&mut rand::thread_rng())
+            .map_err(|err| NetworkError::InvalidWebRtcCertificate(err.to_string()))?;
+        std::fs::create_dir_all(root_dir)?;
+        std::fs::write(&path, certificate.serialize_pem())?;
+        Ok(certificate)
+    }
+}
+
+/// Whether the address is a QUIC one, relayed or not.
+pub(crate) fn is_quic(addr: &Multiaddr) -> bool {
+    addr.iter().any(|protocol| protocol == Protocol::QuicV1)
+}
+
+/// Whether the address is an IPv6 one.
+pub(crate) fn is_ipv6(addr: &Multiaddr) -> bool {
+    matches!(addr.iter().next(), Some(Protocol::Ip6(_)))
+}
+
+/// Whether the address is a WebSocket one, secured or not.
+pub(crate) fn is_websocket(addr: &Multiaddr) -> bool {
+    addr.iter()
+        .any(|protocol| matches!(protocol, Protocol::Ws(_) | Protocol::Wss(_)))
+}
+
+/// Whether the address is a WebRTC one.
+pub(crate) fn is_webrtc(addr: &Multiaddr) -> bool {
+    addr.iter()
+        .any(|protocol| matches!(protocol, Protocol::WebRTCDirect))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quic_addresses_are_dialed_first_or_only() -> eyre::Result<()> {
+        let quic: Multiaddr = "/ip4/198.51.100.1/udp/1200/quic-v1".parse()?;
+        let tcp: Multiaddr = "/ip4/198.51.100.1/tcp/1200".parse()?;
+        let relayed: Multiaddr =
+            "/ip4/198.51.100.2/udp/1200/quic-v1/p2p/12D3KooWRi6wF7yxWLuPSNskXc6kQ5cJ6eaymeMbCRdTnMesPgFx/p2p-circuit"
+                .parse()?;
+
+        let ws: Multiaddr = "/ip4/198.51.100.1/tcp/1201/ws".parse()?;
+        let mut addrs = vec![ws.clone(), tcp.clone(), quic.clone()];
+        TransportMode::PreferQuic.sort_addrs(&mut addrs, false);
+        assert_eq!(addrs, vec![quic.clone(), tcp.clone(), ws]);
+
+        let quic_v6: Multiaddr = "/ip6/2001:4860::1/udp/1200/quic-v1".parse()?;
+        let mut addrs = vec![tcp.clone(), quic.clone(), quic_v6.clone()];
+        TransportMode::PreferQuic.sort_addrs(&mut addrs, true);
+        assert_eq!(addrs, vec![quic_v6.clone(), quic.clone(), tcp.clone()]);
+        TransportMode::PreferQuic.sort_addrs(&mut addrs, false);
+        assert_eq!(addrs, vec![quic.clone(), quic_v6, tcp.clone()]);
+        assert!(TransportMode::PreferQuic.can_dial(&tcp));
+
+        assert!(TransportMode::RequireQuic.can_dial(&quic));
+        assert!(TransportMode::RequireQuic.can_dial(&relayed));
+        assert!(!TransportMode::RequireQuic.can_dial(&tcp));
+
+        let webrtc: Multiaddr = "/ip4/198.51.100.1/udp/1202/webrtc-direct".parse()?;
+        assert!(!TransportMode::PreferQuic.can_dial(&webrtc));
+        assert!(!TransportMode::RequireQuic.can_dial(&webrtc));
+        Ok(())
+    }
+}
@@ -1,20 +1,37 @@
-#[cfg(feature = "websockets")]
+use super::{TransportConfig, TransportMode};
 use futures::future::Either;
+#[cfg(feature = "websockets")]
+use libp2p::core::upgrade;
 #[cfg(feature = "open-metrics")]
 use libp2p::metrics::Registry;
-#[cfg(feature = "websockets")]
-use libp2p::{core::upgrade, noise, yamux};
 use libp2p::{
     core::{muxing::StreamMuxerBox, transport},
     identity::Keypair,
-    PeerId, Transport as _,
+    noise, yamux, PeerId, Transport as _,
 };
 
 pub(crate) fn build_transport(
     keypair: &Keypair,
+    config: TransportConfig,
     #[cfg(feature = "open-metrics")] registry: &mut Registry,
 ) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
-    let trans = generate_quic_transport(keypair);
+    let noise_config = || {
+        noise::Config::new(keypair)
+            .expect("Signing libp2p-noise static DH keypair failed.")
+            .with_prologue(config.noise_prologue.clone())
+    };
+    let trans = generate_quic_transport(keypair)
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+    // QUIC is tried first, the TCP transport only taking the `/tcp` addresses.
+    let trans = match config.mode {
+        TransportMode::PreferQuic => trans
+            .or_transport(generate_tcp_transport(noise_config()))
+            .map(|either_output, _| match either_output {
+                Either::Left(output) | Either::Right(output) => output,
+            })
+            .boxed(),
+        TransportMode::RequireQuic => trans.boxed(),
+    };
     #[cfg(feature = "open-metrics")]
     let trans = libp2p::metrics::BandwidthTransport::new(trans, registry);
 
@@ -22,12 +39,13 @@ pub(crate) fn build_transport(
     // Using a closure here due to the complex return type
     let generate_ws_transport = || {
         let tcp = libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default());
-        libp2p::websocket::WsConfig::new(tcp)
-            .upgrade(upgrade::Version::V1)
-            .authenticate(
-                noise::Config::new(keypair)
-                    .expect("Signing libp2p-noise static DH keypair failed."),
-            )
+        let mut ws = libp2p::websocket::WsConfig::new(tcp);
+        // Serving WSS, on the `/wss` listen addrs, dialing WSS being supported either way.
+        if let Some(tls) = config.websocket_tls {
+            let _ = ws.set_tls_config(tls);
+        }
+        ws.upgrade(upgrade::Version::V1)
+            .authenticate(noise_config())
             .multiplex(yamux::Config::default())
     };
 
@@ -42,6 +60,21 @@ pub(crate) fn build_transport(
     #[cfg(not(feature = "websockets"))]
     let trans = trans.map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
 
+    // With a certificate to serve, the WebRTC listener is added, for the browsers.
+    #[cfg(feature = "webrtc")]
+    if let Some(certificate) = config.webrtc_certificate {
+        return trans
+            .or_transport(libp2p_webrtc::tokio::Transport::new(
+                keypair.clone(),
+                certificate,
+            ))
+            .map(|either_output, _| match either_output {
+                Either::Left(output) => output,
+                Either::Right((peer_id, connection)) => (peer_id, StreamMuxerBox::new(connection)),
+            })
+            .boxed();
+    }
+
     trans.boxed()
 }
 
@@ -50,3 +83,14 @@ fn generate_quic_transport(
 ) -> libp2p::quic::GenTransport<libp2p::quic::tokio::Provider> {
     libp2p::quic::tokio::Transport::new(libp2p::quic::Config::new(keypair))
 }
+
+fn generate_tcp_transport(
+    noise_config: noise::Config,
+) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+    libp2p::tcp::tokio::Transport::new(libp2p::tcp::Config::default().nodelay(true))
+        .upgrade(transport::upgrade::Version::V1Lazy)
+        .authenticate(noise_config)
+        .multiplex(yamux::Config::default())
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+        .boxed()
+}
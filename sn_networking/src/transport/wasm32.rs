@@ -1,18 +1,33 @@
 // wasm32 environments typically only support WebSockets (and WebRTC or WebTransport), so no plain UDP or TCP.
 
+use super::TransportConfig;
+use futures::future::Either;
 use libp2p::{
     core::{muxing::StreamMuxerBox, transport, upgrade},
     identity::Keypair,
     noise, websocket_websys, yamux, PeerId, Transport as _,
 };
+use libp2p_webrtc_websys as webrtc_websys;
 
-pub(crate) fn build_transport(keypair: &Keypair) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
-    // We build a single transport here, WebSockets.
+pub(crate) fn build_transport(
+    keypair: &Keypair,
+    config: TransportConfig,
+) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+    // WebSockets, and WebRTC for the nodes without a certificate of a domain, whatever the config.
     websocket_websys::Transport::default()
         .upgrade(upgrade::Version::V1)
         .authenticate(
-            noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed."),
+            noise::Config::new(keypair)
+                .expect("Signing libp2p-noise static DH keypair failed.")
+                .with_prologue(config.noise_prologue),
         )
         .multiplex(yamux::Config::default())
+        .or_transport(webrtc_websys::Transport::new(webrtc_websys::Config::new(
+            keypair,
+        )))
+        .map(|either_output, _| match either_output {
+            Either::Left((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
+            Either::Right((peer_id, connection)) => (peer_id, StreamMuxerBox::new(connection)),
+        })
         .boxed()
 }
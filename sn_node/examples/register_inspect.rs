@@ -6,12 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crdts::merkle_reg::{Hash, MerkleReg, Node};
+use crdts::merkle_reg::{Hash, Node};
 use std::collections::HashMap;
 use std::io;
 
 use sn_client::{acc_packet::load_account_wallet_or_create_with_mnemonic, Client, WalletClient};
-use sn_registers::{Entry, Permissions, RegisterAddress};
+use sn_registers::{Entry, MerkleDag, Permissions, RegisterAddress};
 
 use xor_name::XorName;
 
@@ -128,7 +128,6 @@ async fn main() -> Result<()> {
         println!("Syncing with SAFE...");
         reg_replica.sync(&mut wallet_client, true, None).await?;
         let merkle_reg = reg_replica.merkle_reg();
-        let content = merkle_reg.read();
         println!("synced!");
 
         // Show the Register structure
@@ -139,13 +138,13 @@ async fn main() -> Result<()> {
         // which is hard to determine.
         let mut index: usize = 0;
         let mut node_ordering: HashMap<Hash, usize> = HashMap::new();
-        for (_hash, node) in content.hashes_and_nodes() {
+        for (_hash, node) in merkle_reg.read() {
             index_node_and_descendants(node, &mut index, &mut node_ordering, merkle_reg);
         }
 
         println!("======================");
         println!("Root (Latest) Node(s):");
-        for node in content.nodes() {
+        for (_hash, node) in merkle_reg.read() {
             let _ = print_node(0, node, &node_ordering);
         }
 
@@ -153,7 +152,7 @@ async fn main() -> Result<()> {
         println!("Register Structure:");
         println!("(In general, earlier nodes are more indented)");
         let mut indents = 0;
-        for (_hash, node) in content.hashes_and_nodes() {
+        for (_hash, node) in merkle_reg.read() {
             print_node_and_descendants(&mut indents, node, &node_ordering, merkle_reg);
         }
 
@@ -165,7 +164,7 @@ fn index_node_and_descendants(
     node: &Node<Entry>,
     index: &mut usize,
     node_ordering: &mut HashMap<Hash, usize>,
-    merkle_reg: &MerkleReg<Entry>,
+    merkle_reg: &MerkleDag,
 ) {
     let node_hash = node.hash();
     if node_ordering.get(&node_hash).is_none() {
@@ -186,7 +185,7 @@ fn print_node_and_descendants(
     indents: &mut usize,
     node: &Node<Entry>,
     node_ordering: &HashMap<Hash, usize>,
-    merkle_reg: &MerkleReg<Entry>,
+    merkle_reg: &MerkleDag,
 ) {
     let _ = print_node(*indents, node, node_ordering);
 
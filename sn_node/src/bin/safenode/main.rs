@@ -10,22 +10,32 @@
 extern crate tracing;
 
 mod rpc_service;
+mod upgrade;
 
 use clap::Parser;
 use eyre::{eyre, Result};
-use libp2p::{identity::Keypair, PeerId};
+use libp2p::{identity::Keypair, Multiaddr, PeerId};
 #[cfg(feature = "metrics")]
 use sn_logging::metrics::init_metrics;
 use sn_logging::{Level, LogFormat, LogOutputDest, ReloadHandle};
-use sn_node::{Marker, NodeBuilder, NodeEvent, NodeEventsReceiver};
+#[cfg(feature = "rocksdb")]
+use sn_node::{migrate_records, DiskRecordStoreBackend, RocksDbRecordStoreBackend};
+use sn_node::{
+    DefaultPricing, FlatPricing, KademliaParams, Marker, NetworkKey, NodeBuilder, NodeEvent,
+    NodeEventsReceiver, NodeIdentity, NodeManager, PricingStrategy, PruningPolicy, PubSubLimits,
+    RelayLimits, ReplicationLimits, RequestRateLimits, ResourceRequirements, RewardForwarding,
+    ScriptedPricing, TransportMode, UtilizationCurvePricing,
+};
 use sn_peers_acquisition::PeersArgs;
 use sn_protocol::{node::get_safenode_root_dir, node_rpc::NodeCtrl};
+use sn_transfers::{MainPubkey, NanoTokens};
 use std::{
     env,
     io::Write,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::{Path, PathBuf},
     process::Command,
+    sync::Arc,
     time::Duration,
 };
 use tokio::{
@@ -34,6 +44,10 @@ use tokio::{
     time::sleep,
 };
 use tracing_appender::non_blocking::WorkerGuard;
+use upgrade::Upgrader;
+
+/// How often the combined status of the nodes run with `--nodes` is logged.
+const NODES_STATUS_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone)]
 pub enum LogOutputDestArg {
@@ -62,6 +76,47 @@ pub fn parse_log_output(val: &str) -> Result<LogOutputDestArg> {
     }
 }
 
+pub fn parse_upgrade_public_key(val: &str) -> Result<bls::PublicKey> {
+    bls::PublicKey::from_hex(val).map_err(|err| eyre!("{val:?} is not a valid public key: {err}"))
+}
+
+pub fn parse_pruning_policy(val: &str) -> Result<PruningPolicy> {
+    match val {
+        "farthest" => Ok(PruningPolicy::Farthest),
+        "largest" => Ok(PruningPolicy::Largest),
+        _ => Err(eyre!("{val:?} is not a valid pruning policy")),
+    }
+}
+
+pub fn parse_pricing(val: &str) -> Result<Arc<dyn PricingStrategy>> {
+    let (strategy, params) = val.split_once(':').unwrap_or((val, ""));
+    let invalid = || eyre!("{val:?} is not a valid pricing");
+    let pricing: Arc<dyn PricingStrategy> = match strategy {
+        "default" => Arc::new(DefaultPricing),
+        "flat" => Arc::new(FlatPricing {
+            cost: params.parse().map_err(|_| invalid())?,
+        }),
+        "curve" => {
+            let params: Vec<_> = params.split(',').map(str::trim).collect();
+            let [min_cost, max_cost, exponent] = params.as_slice() else {
+                return Err(invalid());
+            };
+            Arc::new(UtilizationCurvePricing {
+                min_cost: min_cost.parse().map_err(|_| invalid())?,
+                max_cost: max_cost.parse().map_err(|_| invalid())?,
+                exponent: exponent.parse().map_err(|_| invalid())?,
+            })
+        }
+        "script" => {
+            let formula = std::fs::read_to_string(params)
+                .map_err(|err| eyre!("Failed to read the pricing script {params:?}: {err}"))?;
+            Arc::new(ScriptedPricing::parse(formula.trim())?)
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(pricing)
+}
+
 // Please do not remove the blank lines in these doc comments.
 // They are used for inserting line breaks when the help menu is rendered in the UI.
 #[derive(Parser, Debug)]
@@ -70,10 +125,76 @@ struct Opt {
     /// Specify whether the node is operating from a home network and situated behind a NAT without port forwarding
     /// capabilities. Setting this to true, activates hole-punching to facilitate direct connections from other nodes.
     ///
-    /// If this not enabled and you're behind a NAT, the node is terminated.
+    /// If this is not enabled and the peers find the node can't be reached, it turns to the relays
+    /// by itself, unless `--no-nat-detection` is set.
     #[clap(long, default_value_t = false)]
     home_network: bool,
 
+    /// Do not ask the peers whether the node can be reached (AutoNAT).
+    ///
+    /// The node then only turns to the relays if `--home-network` is set, and doesn't answer the
+    /// probes of its peers either.
+    #[clap(long)]
+    no_nat_detection: bool,
+
+    /// Do not relay the connections of the nodes behind a NAT.
+    ///
+    /// By default, a node the others can reach serves as a relay for the nodes behind a home
+    /// network, within the `--relay-*` limits. A node started with `--home-network` never does.
+    #[clap(long)]
+    no_relay_service: bool,
+
+    /// The most nodes relayed for at once.
+    #[clap(long, conflicts_with = "no_relay_service")]
+    relay_max_reservations: Option<usize>,
+
+    /// The most connections relayed at once, all nodes together.
+    #[clap(long, conflicts_with = "no_relay_service")]
+    relay_max_circuits: Option<usize>,
+
+    /// The most connections relayed at once to or from a single peer.
+    #[clap(long, conflicts_with = "no_relay_service")]
+    relay_max_circuits_per_peer: Option<usize>,
+
+    /// How long a relayed connection lasts before it's closed, in seconds.
+    #[clap(long, conflicts_with = "no_relay_service")]
+    relay_max_circuit_duration_secs: Option<u64>,
+
+    /// The most kilobytes relayed over a connection, both ways, before it's closed.
+    #[clap(long, conflicts_with = "no_relay_service")]
+    relay_max_circuit_kb: Option<u64>,
+
+    /// Do not take part in pub/sub, i.e. neither relay the messages published on the topics nor
+    /// publish.
+    #[clap(long)]
+    no_pubsub: bool,
+
+    /// The largest message published on a pub/sub topic, in bytes.
+    #[clap(long, conflicts_with = "no_pubsub")]
+    pubsub_max_message_size: Option<usize>,
+
+    /// The most messages a peer can publish at once on a pub/sub topic.
+    #[clap(long, conflicts_with = "no_pubsub")]
+    pubsub_burst: Option<u32>,
+
+    /// The messages per second a peer can publish on a pub/sub topic past the `--pubsub-burst`.
+    #[clap(long, conflicts_with = "no_pubsub")]
+    pubsub_per_sec: Option<u32>,
+
+    /// The network key file of the private network to join, the node only connecting to the peers
+    /// given the same key. Defaults to the file at the `SAFE_NETWORK_KEY_FILE` env var, the
+    /// public network being joined if it's not set.
+    ///
+    /// The nodes on the local network aren't discovered over mDNS on a private network, unless
+    /// `--local-discovery` is set.
+    #[clap(long)]
+    network_key_file: Option<PathBuf>,
+
+    /// Generate the key of a new private network to this file then exit, for it to be handed to
+    /// the nodes and clients of the network with `--network-key-file` or `SAFE_NETWORK_KEY_FILE`.
+    #[clap(long, conflicts_with = "network_key_file")]
+    generate_network_key: Option<PathBuf>,
+
     /// Try to use UPnP to open a port in the home router and allow incoming connections.
     #[cfg(feature = "upnp")]
     #[clap(long, default_value_t = false)]
@@ -97,6 +218,9 @@ struct Opt {
     ///
     /// Valid values are "default" or "json".
     ///
+    /// In "json", each line is a JSON object, with the `event` name of the log markers and the
+    /// `correlation_id` of the message or record being handled. It applies to stdout as well.
+    ///
     /// If the argument is not used, the default format will be applied.
     #[clap(long, value_parser = LogFormat::parse_from_str, verbatim_doc_comment)]
     log_format: Option<LogFormat>,
@@ -118,6 +242,24 @@ struct Opt {
     #[clap(long = "max_archived_log_files", verbatim_doc_comment)]
     max_compressed_log_files: Option<usize>,
 
+    /// Specify the size in MB a log file is rotated at, 20 by default.
+    ///
+    /// This argument is ignored if `log_output_dest` is set to "stdout"
+    #[clap(long, verbatim_doc_comment)]
+    max_log_file_size_mb: Option<usize>,
+
+    /// Rotate the log file every number of hours, on top of when it reaches its max size.
+    ///
+    /// This argument is ignored if `log_output_dest` is set to "stdout"
+    #[clap(long, verbatim_doc_comment)]
+    log_rotation_interval_hours: Option<u64>,
+
+    /// Keep the log files for this number of days, in place of the max number of archived files.
+    ///
+    /// This argument is ignored if `log_output_dest` is set to "stdout"
+    #[clap(long, verbatim_doc_comment)]
+    log_retention_days: Option<u64>,
+
     /// Specify the node's data directory.
     ///
     /// If not provided, the default location is platform specific:
@@ -128,6 +270,32 @@ struct Opt {
     #[clap(long, verbatim_doc_comment)]
     root_dir: Option<PathBuf>,
 
+    /// Write the identity of the node in the `--root-dir`, its key and its reward key, to this
+    /// file then exit, for it to be imported on another machine with `--import-identity`.
+    #[clap(long, requires = "root_dir", conflicts_with = "import_identity")]
+    export_identity: Option<PathBuf>,
+
+    /// Start the node with the identity exported from another machine with `--export-identity`,
+    /// for it to keep its PeerId and its reward key.
+    ///
+    /// The root dir defaults to the one of the PeerId imported, and must not hold another
+    /// identity.
+    #[clap(long)]
+    import_identity: Option<PathBuf>,
+
+    /// Run this many nodes in the one process, rather than a process per node.
+    ///
+    /// Each node gets its own identity, kept in the `safenode<N>` dir of the `--root-dir` if given,
+    /// and listens on the `--port` plus its index if a port is given. The
+    /// `--max-replication-bandwidth-kib` is then shared by all of them.
+    #[clap(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u16).range(1..),
+        conflicts_with_all = ["export_identity", "import_identity", "rpc", "upgrade_url"]
+    )]
+    nodes: u16,
+
     /// Specify the port to listen on.
     ///
     /// The special value `0` will cause the OS to assign a random port.
@@ -136,10 +304,78 @@ struct Opt {
 
     /// Specify the IP to listen on.
     ///
-    /// The special value `0.0.0.0` binds to all network interfaces available.
+    /// The special value `0.0.0.0` binds to all network interfaces available, as does `::`. Both
+    /// IPv4 and IPv6 are then listened on, unless `--no-dual-stack` is set.
     #[clap(long, default_value_t = IpAddr::V4(Ipv4Addr::UNSPECIFIED))]
     ip: IpAddr,
 
+    /// Only listen on the family of `--ip`, not on both IPv4 and IPv6.
+    #[clap(long)]
+    no_dual_stack: bool,
+
+    /// Listen and dial on QUIC only.
+    ///
+    /// By default, the node listens on QUIC and TCP, on the same `--port`, and dials the QUIC
+    /// address of a peer before its TCP one. With this flag set, the peers only reachable over
+    /// TCP are left out.
+    #[clap(long)]
+    require_quic: bool,
+
+    /// Listen on WebSocket too, on this TCP port, for the clients running in browsers.
+    ///
+    /// It's not to be the `--port`, which the node listens on over TCP as well.
+    #[cfg(feature = "websockets")]
+    #[clap(long)]
+    ws_port: Option<u16>,
+
+    /// Serve WSS rather than WS, with the certificate chain of this PEM file.
+    ///
+    /// The certificate is to be for the domain name the node is reached at.
+    #[cfg(feature = "websockets")]
+    #[clap(long, requires_all = ["ws_port", "wss_key_file"])]
+    wss_cert_file: Option<PathBuf>,
+
+    /// The PEM file of the private key of the `--wss-cert-file`.
+    #[cfg(feature = "websockets")]
+    #[clap(long, requires = "wss_cert_file")]
+    wss_key_file: Option<PathBuf>,
+
+    /// Listen on WebRTC too, on this UDP port, for the clients running in browsers to connect
+    /// without the node needing a certificate of a domain.
+    ///
+    /// It's not to be the `--port`, which the node listens on over QUIC. The self-signed
+    /// certificate served is kept in the root dir, its hash being part of the address.
+    #[cfg(feature = "webrtc")]
+    #[clap(long)]
+    webrtc_port: Option<u16>,
+
+    /// Do not discover the nodes on the local network over mDNS.
+    ///
+    /// They aren't discovered by default on a private network, set with `--network-key-file` or
+    /// `SAFE_NETWORK_KEY_FILE`:
+    /// the nodes of every network are announced under the same mDNS service, so the ones of the
+    /// other networks on the LAN would be dialed too, only to fail the handshake.
+    #[cfg(feature = "local-discovery")]
+    #[clap(long)]
+    no_local_discovery: bool,
+
+    /// Discover the nodes on the local network over mDNS on a private network too, e.g. for a
+    /// local testnet, the nodes of the other networks found being dialed in vain.
+    #[cfg(feature = "local-discovery")]
+    #[clap(long, conflicts_with = "no_local_discovery")]
+    local_discovery: bool,
+
+    /// Only discover the nodes on the local network of this interface, given as its network, e.g.
+    /// `192.168.1.0/24`, which turns the discovery on a private network too.
+    ///
+    /// It can be set several times. The nodes are discovered on all the interfaces by default.
+    #[cfg(feature = "local-discovery")]
+    #[clap(
+        long = "local-discovery-interface",
+        conflicts_with = "no_local_discovery"
+    )]
+    local_discovery_interfaces: Vec<ipnet::IpNet>,
+
     #[command(flatten)]
     peers: PeersArgs,
 
@@ -159,6 +395,178 @@ struct Opt {
     #[clap(long)]
     owner: Option<String>,
 
+    /// The most disk the records may take, in megabytes.
+    ///
+    /// Once reached, records are pruned to make room for the incoming ones, in the order of the
+    /// `--pruning-policy`.
+    #[clap(long)]
+    max_disk_usage_mb: Option<u64>,
+
+    /// The order the records are pruned in once the `--max-disk-usage-mb` is reached.
+    ///
+    /// Valid values are "farthest", for the records farthest from the node first, and "largest".
+    /// The records the node got paid for are always pruned last.
+    #[clap(long, default_value = "farthest", value_parser = parse_pruning_policy)]
+    pruning_policy: PruningPolicy,
+
+    /// How the storing of a record is priced:
+    ///
+    /// default: the pricing of the network
+    /// flat:<nanos>: the same cost for any record
+    /// curve:<min nanos>,<max nanos>,<exponent>: from min to max with the share of the max records stored
+    /// script:<file>: the formula of the file, of the metrics `records`, `max_records`, `payments`,
+    ///   `live_time` and `live_days`, e.g. `10 * records * 1.02 ^ max(0, records - 0.6 * max_records)`
+    ///
+    /// The quotes of the peers are checked against it too, so all the nodes of a network, e.g. a
+    /// private one, are to use the same pricing.
+    #[clap(long, default_value = "default", value_parser = parse_pricing, verbatim_doc_comment)]
+    pricing: Arc<dyn PricingStrategy>,
+
+    /// The most bandwidth the replication may take, in KiB per second, counting the records both
+    /// fetched from and served to the peers.
+    ///
+    /// It is unlimited by default, which can saturate a home connection while the node joins the
+    /// network or absorbs churn.
+    #[clap(long)]
+    max_replication_bandwidth_kib: Option<u64>,
+
+    /// The most records fetched at the same time for the replication.
+    #[clap(long)]
+    max_replication_fetches: Option<usize>,
+
+    /// How many of the closest peers a record is stored with, between the close group size and
+    /// 20. All the nodes of a network are to be given the same Kademlia parameters.
+    #[clap(long)]
+    kad_replication_factor: Option<usize>,
+
+    /// How many peers a Kademlia query asks at once (alpha), between 1 and 20.
+    #[clap(long)]
+    kad_parallelism: Option<usize>,
+
+    /// How long Kademlia holds the records it puts valid for, in seconds, for ever by default.
+    ///
+    /// The records the node stores aren't removed after it: the chunks, spends and registers are
+    /// paid for to be kept, and only the records of an expiring kind are removed, once their data
+    /// expired.
+    #[clap(long)]
+    kad_record_ttl_secs: Option<u64>,
+
+    /// How often the routing table is refreshed while peers are being added to it, in seconds.
+    #[clap(long)]
+    kad_bucket_refresh_secs: Option<u64>,
+
+    /// The most the routing table refreshes are slowed down to once no peer has been added for a
+    /// while, in seconds.
+    #[clap(long)]
+    kad_max_bucket_refresh_secs: Option<u64>,
+
+    /// The memory to keep available for the node, in MiB.
+    ///
+    /// The node refuses to start with less available, and stops taking new records while it has
+    /// less.
+    #[clap(long)]
+    min_available_memory_mb: Option<u64>,
+
+    /// The disk space to keep available in the root dir of the node, in MiB.
+    ///
+    /// The node refuses to start with less available, and stops taking new records while it has
+    /// less.
+    #[clap(long)]
+    min_available_disk_mb: Option<u64>,
+
+    /// The average CPU usage of the machine, in percent, over which the node stops taking new
+    /// records.
+    #[clap(long)]
+    max_cpu_usage_percent: Option<f32>,
+
+    /// Forward the rewards earned by the node to this cold wallet, given as an address or a hex
+    /// encoded key, so they're not kept under the hot reward key of the node.
+    ///
+    /// The transfers for the cold wallet to receive them with are logged to the
+    /// `reward_forwarding.log` file of the root dir.
+    #[clap(long, verbatim_doc_comment)]
+    forward_rewards_to: Option<MainPubkey>,
+
+    /// The reward balance, in tokens, from which it is forwarded to the `--forward-rewards-to`
+    /// wallet.
+    #[clap(long, default_value = "1")]
+    forward_rewards_min: NanoTokens,
+
+    /// How often to check the reward balance to forward, in hours.
+    #[clap(long, default_value_t = 24)]
+    forward_rewards_interval_hours: u64,
+
+    /// The file of the peers to always accept or always reject, with a rule per line:
+    ///
+    /// allow|block <PeerId|IP|CIDR range>
+    /// default reject|accept
+    ///
+    /// `default reject` has the node reject all the peers not allowed, for a private deployment.
+    /// Defaults to the `peer_access` file of the root dir. It's reloaded whenever it changes.
+    #[clap(long, verbatim_doc_comment)]
+    peer_access_file: Option<PathBuf>,
+
+    /// Serve the requests of the clients without limiting their rate.
+    ///
+    /// By default, each client spends the cost of its requests out of a bucket of tokens refilled
+    /// at a steady rate, its requests being dropped while the bucket is short. The nodes of the
+    /// routing table, and the peers allowed by the `--peer-access-file`, are never limited.
+    #[clap(long, verbatim_doc_comment)]
+    no_request_rate_limits: bool,
+
+    /// The most tokens a client can spend at once, i.e. the burst of requests it's served.
+    #[clap(long, conflicts_with = "no_request_rate_limits")]
+    request_rate_burst: Option<u32>,
+
+    /// The tokens a client is given back per second, i.e. the steady rate it's served at.
+    #[clap(long, conflicts_with = "no_request_rate_limits")]
+    request_rate_per_sec: Option<u32>,
+
+    /// The tokens a query for a record, or its proofs, costs.
+    #[clap(long, conflicts_with = "no_request_rate_limits")]
+    request_rate_get_cost: Option<u32>,
+
+    /// The tokens storing a record, or getting a quote for it, costs.
+    #[clap(long, conflicts_with = "no_request_rate_limits")]
+    request_rate_put_cost: Option<u32>,
+
+    /// The tokens storing or querying a spend costs.
+    #[clap(long, conflicts_with = "no_request_rate_limits")]
+    request_rate_spend_cost: Option<u32>,
+
+    /// How long to keep the node running on stop, in seconds, for the closest peers to fetch the
+    /// records it holds before it leaves the network.
+    ///
+    /// Set to 0 to stop without handing the records over.
+    #[clap(long, default_value_t = 10)]
+    handover_period_secs: u64,
+
+    /// Upgrade the node to the releases published at this URL, once signed by the
+    /// `--upgrade-public-key`.
+    ///
+    /// The node checks for a new release every `--upgrade-check-interval-hours`, and whenever asked
+    /// to update over the RPC. A new release replaces the running binary and the node restarts into
+    /// it, keeping its root dir and port, hence its identity and records.
+    ///
+    /// The node doesn't upgrade itself unless set.
+    #[clap(long, requires = "upgrade_public_key", verbatim_doc_comment)]
+    upgrade_url: Option<String>,
+
+    /// The hex encoded BLS public key the releases at the `--upgrade-url` are signed with.
+    #[clap(long, value_parser = parse_upgrade_public_key)]
+    upgrade_public_key: Option<bls::PublicKey>,
+
+    /// How often to check the `--upgrade-url` for a new release, in hours.
+    #[clap(long, default_value_t = 6)]
+    upgrade_check_interval_hours: u64,
+
+    #[cfg(feature = "rocksdb")]
+    /// Store the records in a RocksDB database in the root dir, rather than in a file each.
+    ///
+    /// The records stored in files by a previous run are moved into the database on start.
+    #[clap(long)]
+    rocksdb: bool,
+
     #[cfg(feature = "open-metrics")]
     /// Specify the port for the OpenMetrics server.
     ///
@@ -182,15 +590,46 @@ struct Opt {
 fn main() -> Result<()> {
     color_eyre::install()?;
     let opt = Opt::parse();
-
-    let node_socket_addr = SocketAddr::new(opt.ip, opt.port);
-    let (root_dir, keypair) = get_root_dir_and_keypair(&opt.root_dir)?;
+    let exe_path = upgrade::current_exe()?;
+
+    if let Some(path) = &opt.generate_network_key {
+        let network_key = NetworkKey::generate();
+        network_key.write_to_file(path)?;
+        println!(
+            "Generated the key of the network {} to {path:?}",
+            network_key.network_id()
+        );
+        return Ok(());
+    }
+    if let Some(path) = &opt.export_identity {
+        let root_dir = opt.root_dir.clone().unwrap_or_default();
+        NodeIdentity::load(&root_dir)?.export(path)?;
+        println!("Exported the identity of the node in {root_dir:?} to {path:?}");
+        return Ok(());
+    }
+    let identities = if opt.nodes > 1 {
+        (1..=opt.nodes)
+            .map(|n| {
+                let root_dir = opt
+                    .root_dir
+                    .as_ref()
+                    .map(|dir| dir.join(format!("safenode{n}")));
+                get_root_dir_and_keypair(&root_dir)
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        let root_dir = match &opt.import_identity {
+            Some(path) => Some(import_identity(path, opt.root_dir.clone())?),
+            None => opt.root_dir.clone(),
+        };
+        vec![get_root_dir_and_keypair(&root_dir)?]
+    };
 
     let (log_output_dest, log_reload_handle, _log_appender_guard) =
-        init_logging(&opt, keypair.public().to_peer_id())?;
+        init_logging(&opt, identities[0].1.public().to_peer_id())?;
 
     let rt = Runtime::new()?;
-    let bootstrap_peers = rt.block_on(opt.peers.get_peers())?;
+    let bootstrap_peers = rt.block_on(opt.peers.clone().get_peers())?;
     let msg = format!(
         "Running {} v{}",
         env!("CARGO_BIN_NAME"),
@@ -210,31 +649,38 @@ fn main() -> Result<()> {
     #[cfg(feature = "metrics")]
     rt.spawn(init_metrics(std::process::id()));
     debug!("Node's owner set to: {:?}", opt.owner);
+    let node_builders = identities
+        .into_iter()
+        .zip(0..)
+        .map(|((root_dir, keypair), index)| {
+            node_builder(&opt, keypair, root_dir, bootstrap_peers.clone(), index)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let handover_period = Duration::from_secs(opt.handover_period_secs);
+    let upgrader = opt
+        .upgrade_url
+        .clone()
+        .zip(opt.upgrade_public_key)
+        .map(|(url, public_key)| Upgrader::new(url, public_key, exe_path.clone()));
     let restart_options = rt.block_on(async move {
-        let mut node_builder = NodeBuilder::new(
-            keypair,
-            node_socket_addr,
-            bootstrap_peers,
-            opt.local,
-            root_dir,
-            opt.owner.clone(),
-            #[cfg(feature = "upnp")]
-            opt.upnp,
-        );
-        node_builder.is_behind_home_network = opt.home_network;
-        #[cfg(feature = "open-metrics")]
-        let mut node_builder = node_builder;
-        // if enable flag is provided or only if the port is specified then enable the server by setting Some()
-        #[cfg(feature = "open-metrics")]
-        let metrics_server_port = if opt.enable_metrics_server || opt.metrics_server_port != 0 {
-            Some(opt.metrics_server_port)
-        } else {
-            None
-        };
-        #[cfg(feature = "open-metrics")]
-        node_builder.metrics_server_port(metrics_server_port);
-        let restart_options =
-            run_node(node_builder, opt.rpc, &log_output_dest, log_reload_handle).await?;
+        let mut node_builders = node_builders;
+        if node_builders.len() > 1 {
+            return Err(run_nodes(node_builders, replication_limits(&opt), handover_period).await);
+        }
+        let restart_options = run_node(
+            node_builders.remove(0),
+            opt.rpc,
+            &log_output_dest,
+            log_reload_handle,
+            handover_period,
+            upgrader.map(|upgrader| {
+                (
+                    upgrader,
+                    Duration::from_secs(opt.upgrade_check_interval_hours * 3600),
+                )
+            }),
+        )
+        .await?;
 
         Ok::<_, eyre::Report>(restart_options)
     })?;
@@ -244,13 +690,219 @@ fn main() -> Result<()> {
 
     // we got this far without error, which means (so far) the only thing we should be doing
     // is restarting the node
-    start_new_node_process(restart_options);
+    start_new_node_process(&exe_path, restart_options);
 
     // Command was successful, so we shut down the process
     println!("A new node process has been started successfully.");
     Ok(())
 }
 
+/// Configures the builder of the node at the `index` of the nodes run, from the options.
+fn node_builder(
+    opt: &Opt,
+    keypair: Keypair,
+    root_dir: PathBuf,
+    bootstrap_peers: Vec<Multiaddr>,
+    index: u16,
+) -> Result<NodeBuilder> {
+    let node_socket_addr = SocketAddr::new(opt.ip, offset_port(opt.port, index)?);
+    let mut node_builder = NodeBuilder::new(
+        keypair,
+        node_socket_addr,
+        bootstrap_peers,
+        opt.local,
+        root_dir.clone(),
+        opt.owner.clone(),
+        #[cfg(feature = "upnp")]
+        opt.upnp,
+    );
+    node_builder.is_behind_home_network = opt.home_network;
+    node_builder.dual_stack(!opt.no_dual_stack);
+    node_builder.nat_detection(!opt.no_nat_detection);
+    if opt.no_relay_service {
+        node_builder.relay_service(None);
+    } else {
+        let mut limits = RelayLimits::default();
+        if let Some(max) = opt.relay_max_reservations {
+            limits.max_reservations = max;
+        }
+        if let Some(max) = opt.relay_max_circuits {
+            limits.max_circuits = max;
+        }
+        if let Some(max) = opt.relay_max_circuits_per_peer {
+            limits.max_circuits_per_peer = max;
+        }
+        if let Some(secs) = opt.relay_max_circuit_duration_secs {
+            limits.max_circuit_duration = Duration::from_secs(secs);
+        }
+        if let Some(kb) = opt.relay_max_circuit_kb {
+            limits.max_circuit_bytes = kb * 1024;
+        }
+        node_builder.relay_service(Some(limits));
+    }
+    if let Some(path) = &opt.network_key_file {
+        node_builder.network_key(NetworkKey::from_file(path)?);
+    }
+    if opt.no_pubsub {
+        node_builder.pubsub(None);
+    } else {
+        let mut limits = PubSubLimits::default();
+        if let Some(max) = opt.pubsub_max_message_size {
+            limits.default.max_message_size = max;
+        }
+        if let Some(burst) = opt.pubsub_burst {
+            limits.default.burst = burst;
+        }
+        if let Some(per_sec) = opt.pubsub_per_sec {
+            limits.default.per_sec = per_sec;
+        }
+        node_builder.pubsub(Some(limits));
+    }
+    if let Some(max_disk_usage_mb) = opt.max_disk_usage_mb {
+        node_builder.disk_quota(max_disk_usage_mb * 1024 * 1024, opt.pruning_policy);
+    }
+    node_builder.replication_limits(replication_limits(opt));
+    let mut kademlia_params = KademliaParams::default();
+    if let Some(replication_factor) = opt.kad_replication_factor {
+        kademlia_params.replication_factor = replication_factor;
+    }
+    if let Some(parallelism) = opt.kad_parallelism {
+        kademlia_params.parallelism = parallelism;
+    }
+    if let Some(secs) = opt.kad_record_ttl_secs {
+        kademlia_params.record_ttl = Some(Duration::from_secs(secs));
+    }
+    if let Some(secs) = opt.kad_bucket_refresh_secs {
+        kademlia_params.bucket_refresh_interval = Duration::from_secs(secs);
+    }
+    if let Some(secs) = opt.kad_max_bucket_refresh_secs {
+        kademlia_params.max_bucket_refresh_interval = Duration::from_secs(secs);
+    }
+    node_builder.kademlia_params(kademlia_params)?;
+    let mut resource_requirements = ResourceRequirements::default();
+    if let Some(mb) = opt.min_available_memory_mb {
+        resource_requirements.min_available_memory = mb * 1024 * 1024;
+    }
+    if let Some(mb) = opt.min_available_disk_mb {
+        resource_requirements.min_available_disk = mb * 1024 * 1024;
+    }
+    if let Some(percent) = opt.max_cpu_usage_percent {
+        resource_requirements.max_cpu_usage = percent;
+    }
+    node_builder.resource_requirements(resource_requirements);
+    if let Some(cold_wallet) = opt.forward_rewards_to {
+        node_builder.reward_forwarding(RewardForwarding {
+            cold_wallet,
+            min_amount: opt.forward_rewards_min,
+            interval: Duration::from_secs(opt.forward_rewards_interval_hours * 3600),
+        });
+    }
+    if let Some(path) = opt.peer_access_file.clone() {
+        node_builder.peer_access_file(path);
+    }
+    node_builder.pricing(Arc::clone(&opt.pricing));
+    if opt.require_quic {
+        node_builder.transport_mode(TransportMode::RequireQuic);
+    }
+    #[cfg(feature = "websockets")]
+    if let Some(port) = opt.ws_port {
+        node_builder.websocket(sn_node::WebSocketListener {
+            port,
+            tls: opt.wss_cert_file.clone().zip(opt.wss_key_file.clone()).map(
+                |(cert_chain_file, private_key_file)| sn_node::WebSocketTls {
+                    cert_chain_file,
+                    private_key_file,
+                },
+            ),
+        });
+    }
+    #[cfg(feature = "webrtc")]
+    if let Some(port) = opt.webrtc_port {
+        node_builder.webrtc(sn_node::WebRtcListener { port });
+    }
+    #[cfg(feature = "local-discovery")]
+    if opt.no_local_discovery {
+        node_builder.local_discovery(None);
+    } else if opt.local_discovery || !opt.local_discovery_interfaces.is_empty() {
+        node_builder.local_discovery(Some(sn_node::LocalDiscovery {
+            interfaces: opt.local_discovery_interfaces.clone(),
+            ..Default::default()
+        }));
+    }
+    if opt.no_request_rate_limits {
+        node_builder.request_rate_limits(None);
+    } else {
+        let mut limits = RequestRateLimits::default();
+        if let Some(burst) = opt.request_rate_burst {
+            limits.burst = burst;
+        }
+        if let Some(per_sec) = opt.request_rate_per_sec {
+            limits.per_sec = per_sec;
+        }
+        if let Some(cost) = opt.request_rate_get_cost {
+            limits.get_cost = cost;
+        }
+        if let Some(cost) = opt.request_rate_put_cost {
+            limits.put_cost = cost;
+        }
+        if let Some(cost) = opt.request_rate_spend_cost {
+            limits.spend_cost = cost;
+        }
+        node_builder.request_rate_limits(Some(limits));
+    }
+    #[cfg(feature = "rocksdb")]
+    if opt.rocksdb {
+        node_builder.record_store_backend(open_rocksdb_record_store(&root_dir)?);
+    }
+    // if enable flag is provided or only if the port is specified then enable the server by setting Some()
+    #[cfg(feature = "open-metrics")]
+    let metrics_server_port = if opt.enable_metrics_server || opt.metrics_server_port != 0 {
+        Some(offset_port(opt.metrics_server_port, index)?)
+    } else {
+        None
+    };
+    #[cfg(feature = "open-metrics")]
+    node_builder.metrics_server_port(metrics_server_port);
+    Ok(node_builder)
+}
+
+/// The port of the node at the `index` of the nodes run, the OS assigning them all one if `0`.
+fn offset_port(port: u16, index: u16) -> Result<u16> {
+    if port == 0 {
+        return Ok(0);
+    }
+    port.checked_add(index)
+        .ok_or_else(|| eyre!("Port {port} is too high to run {} nodes from", index + 1))
+}
+
+fn replication_limits(opt: &Opt) -> ReplicationLimits {
+    let mut replication_limits = ReplicationLimits::default();
+    if let Some(kib) = opt.max_replication_bandwidth_kib {
+        replication_limits.bytes_per_sec = Some(kib * 1024);
+    }
+    if let Some(fetches) = opt.max_replication_fetches {
+        replication_limits.concurrent_fetches = fetches;
+    }
+    replication_limits
+}
+
+/// Opens the RocksDB record store in the root dir, moving the records stored in the files of the
+/// `record_store` dir into it first.
+#[cfg(feature = "rocksdb")]
+fn open_rocksdb_record_store(root_dir: &Path) -> Result<std::sync::Arc<RocksDbRecordStoreBackend>> {
+    let backend = RocksDbRecordStoreBackend::open(root_dir.join("record_store_db"))?;
+    let files_dir = root_dir.join("record_store");
+    if files_dir.exists() {
+        let migrated = migrate_records(&DiskRecordStoreBackend::new(files_dir), &backend)?;
+        if migrated > 0 {
+            println!(
+                "Moved {migrated} records from the record_store dir into the RocksDB record store"
+            );
+        }
+    }
+    Ok(std::sync::Arc::new(backend))
+}
+
 /// Start a node with the given configuration.
 /// This function will only return if it receives a Restart NodeCtrl cmd. It optionally contains the node's root dir
 /// and it's listening port if we want to retain_peer_id on restart.
@@ -259,6 +911,8 @@ async fn run_node(
     rpc: Option<SocketAddr>,
     log_output_dest: &str,
     log_reload_handle: ReloadHandle,
+    handover_period: Duration,
+    upgrades: Option<(Upgrader, Duration)>,
 ) -> Result<Option<(PathBuf, u16)>> {
     let started_instant = std::time::Instant::now();
 
@@ -307,13 +961,17 @@ You can check your reward balance by running:
         }
     });
 
+    if let Some((upgrader, interval)) = &upgrades {
+        upgrader.spawn_periodic_upgrades(*interval, ctrl_tx.clone());
+    }
+
     // Start up gRPC interface if enabled by user
     if let Some(addr) = rpc {
         rpc_service::start_rpc_service(
             addr,
             log_output_dest,
             running_node.clone(),
-            ctrl_tx,
+            ctrl_tx.clone(),
             started_instant,
             log_reload_handle,
         );
@@ -346,12 +1004,21 @@ You can check your reward balance by running:
                 info!("{msg}");
                 println!("{msg} Node log path: {log_output_dest}");
                 sleep(delay).await;
+                if !handover_period.is_zero() {
+                    println!("Handing the records over to the closest peers before leaving...");
+                    if let Err(err) = running_node.leave_network(handover_period).await {
+                        warn!("Failed to hand the records over before leaving: {err:?}");
+                    }
+                }
                 return Err(cause);
             }
-            Some(NodeCtrl::Update(_delay)) => {
-                // TODO: implement self-update once safenode app releases are published again
-                println!("No self-update supported yet.");
-            }
+            Some(NodeCtrl::Update(delay)) => match &upgrades {
+                Some((upgrader, _)) => {
+                    info!("Checking for a new release in {delay:?}...");
+                    upgrader.spawn_upgrade(delay, ctrl_tx.clone());
+                }
+                None => println!("Self-update is not enabled, see `--upgrade-url`."),
+            },
             None => {
                 info!("Internal node ctrl cmds channel has been closed, restarting node");
                 break Err(eyre!("Internal node ctrl cmds channel has been closed"));
@@ -360,6 +1027,69 @@ You can check your reward balance by running:
     }
 }
 
+/// Runs the nodes in this process with a `NodeManager`, logging their combined status, until
+/// ctrl-c or a node terminating. Returns the cause they were stopped for.
+async fn run_nodes(
+    node_builders: Vec<NodeBuilder>,
+    replication_limits: ReplicationLimits,
+    handover_period: Duration,
+) -> eyre::Report {
+    info!("Starting {} nodes ...", node_builders.len());
+    let manager = match NodeManager::run(node_builders, replication_limits) {
+        Ok(manager) => manager,
+        Err(err) => return err.into(),
+    };
+
+    // Channel to receive the stop cmds from the events monitoring tasks of the nodes
+    let (ctrl_tx, mut ctrl_rx) = mpsc::channel::<NodeCtrl>(5);
+    let pid = std::process::id();
+    for node in manager.nodes() {
+        println!("Node started, PeerId is {}", node.peer_id());
+        let pid_file = node.root_dir_path().join("safenode.pid");
+        if let Err(err) = std::fs::write(pid_file, pid.to_string().as_bytes()) {
+            return err.into();
+        }
+        monitor_node_events(node.node_events_channel().subscribe(), ctrl_tx.clone());
+    }
+
+    let mut status_interval = tokio::time::interval(NODES_STATUS_INTERVAL);
+    let cause = loop {
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => {
+                if let Err(err) = result {
+                    // I/O error, ignore/print the error, but continue to handle as if ctrl-c was received
+                    warn!("Listening to ctrl-c error: {err}");
+                }
+                break eyre!("Ctrl-C received!");
+            }
+            ctrl = ctrl_rx.recv() => match ctrl {
+                Some(NodeCtrl::Stop { cause, .. }) => break cause,
+                Some(ctrl) => debug!("Ignored node ctrl cmd {ctrl:?}"),
+                None => break eyre!("Internal node ctrl cmds channel has been closed"),
+            },
+            _ = status_interval.tick() => {
+                let summary = manager.summary().await;
+                info!("Status of the nodes: {summary:?}");
+                println!(
+                    "{}/{} nodes running, holding {} records ({} bytes), rewarded {}",
+                    summary.running_nodes,
+                    summary.nodes,
+                    summary.records,
+                    summary.used_bytes,
+                    summary.reward_balance
+                );
+            }
+        }
+    };
+
+    println!("Nodes are stopping: {cause}");
+    if !handover_period.is_zero() {
+        println!("Handing the records over to the closest peers before leaving...");
+        manager.leave_network(handover_period).await;
+    }
+    cause
+}
+
 fn monitor_node_events(mut node_events_rx: NodeEventsReceiver, ctrl_tx: mpsc::Sender<NodeCtrl>) {
     let _handle = tokio::spawn(async move {
         loop {
@@ -432,13 +1162,7 @@ fn init_logging(opt: &Opt, peer_id: PeerId) -> Result<(String, ReloadHandle, Opt
     let (reload_handle, log_appender_guard) = {
         let mut log_builder = sn_logging::LogBuilder::new(logging_targets);
         log_builder.output_dest(output_dest.clone());
-        log_builder.format(opt.log_format.unwrap_or(LogFormat::Default));
-        if let Some(files) = opt.max_uncompressed_log_files {
-            log_builder.max_uncompressed_log_files(files);
-        }
-        if let Some(files) = opt.max_compressed_log_files {
-            log_builder.max_compressed_log_files(files);
-        }
+        configure_log_files(&mut log_builder, opt);
 
         log_builder.initialize()?
     };
@@ -450,13 +1174,7 @@ fn init_logging(opt: &Opt, peer_id: PeerId) -> Result<(String, ReloadHandle, Opt
         let (reload_handle, log_appender_guard) = rt.block_on(async {
             let mut log_builder = sn_logging::LogBuilder::new(logging_targets);
             log_builder.output_dest(output_dest.clone());
-            log_builder.format(opt.log_format.unwrap_or(LogFormat::Default));
-            if let Some(files) = opt.max_uncompressed_log_files {
-                log_builder.max_uncompressed_log_files(files);
-            }
-            if let Some(files) = opt.max_compressed_log_files {
-                log_builder.max_compressed_log_files(files);
-            }
+            configure_log_files(&mut log_builder, opt);
             log_builder.initialize()
         })?;
         (rt, reload_handle, log_appender_guard)
@@ -465,6 +1183,25 @@ fn init_logging(opt: &Opt, peer_id: PeerId) -> Result<(String, ReloadHandle, Opt
     Ok((output_dest.to_string(), reload_handle, log_appender_guard))
 }
 
+fn configure_log_files(log_builder: &mut sn_logging::LogBuilder, opt: &Opt) {
+    log_builder.format(opt.log_format.unwrap_or(LogFormat::Default));
+    if let Some(files) = opt.max_uncompressed_log_files {
+        log_builder.max_uncompressed_log_files(files);
+    }
+    if let Some(files) = opt.max_compressed_log_files {
+        log_builder.max_compressed_log_files(files);
+    }
+    if let Some(size_mb) = opt.max_log_file_size_mb {
+        log_builder.max_log_file_size(size_mb * 1024 * 1024);
+    }
+    if let Some(hours) = opt.log_rotation_interval_hours {
+        log_builder.log_rotation_interval(Duration::from_secs(hours * 3600));
+    }
+    if let Some(days) = opt.log_retention_days {
+        log_builder.max_log_file_age(Duration::from_secs(days * 24 * 3600));
+    }
+}
+
 fn create_secret_key_file(path: impl AsRef<Path>) -> Result<std::fs::File, std::io::Error> {
     let mut opt = std::fs::OpenOptions::new();
     opt.write(true).create_new(true);
@@ -540,41 +1277,51 @@ fn get_root_dir_and_keypair(root_dir: &Option<PathBuf>) -> Result<(PathBuf, Keyp
     }
 }
 
-/// Starts a new process running the binary with the same args as
-/// the current process
-/// Optionally provide the node's root dir and listen port to retain it's PeerId
-fn start_new_node_process(retain_peer_id: Option<(PathBuf, u16)>) {
-    // Retrieve the current executable's path
-    let current_exe = env::current_exe().expect("could not get current executable path");
+/// Installs the identity exported to the file in the root dir, returning the root dir.
+fn import_identity(path: &Path, root_dir: Option<PathBuf>) -> Result<PathBuf> {
+    let identity = NodeIdentity::import(path)?;
+    let root_dir = match root_dir {
+        Some(dir) => dir,
+        None => get_safenode_root_dir(identity.peer_id())?,
+    };
+    identity.install(&root_dir)?;
+    println!(
+        "Imported the identity of {} in {root_dir:?}",
+        identity.peer_id()
+    );
+    Ok(root_dir)
+}
+
+// The identity imported is installed in the root dir on the first start, where it may be rotated
+// since, so it's not imported again on restart.
+fn without_identity_import(args: &[String]) -> Vec<String> {
+    let mut kept = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--import-identity" {
+            let _path = args.next();
+        } else if !arg.starts_with("--import-identity=") {
+            kept.push(arg.clone());
+        }
+    }
+    kept
+}
 
+/// Starts a new process running the binary at `exe_path`, the one the current process was started
+/// from, or the one that replaced it, with the same args as the current process.
+/// Optionally provide the node's root dir and listen port to retain it's PeerId
+fn start_new_node_process(exe_path: &Path, retain_peer_id: Option<(PathBuf, u16)>) {
     // Retrieve the command-line arguments passed to this process
     let args: Vec<String> = env::args().collect();
 
     info!("Original args are: {args:?}");
-    info!("Current exe is: {current_exe:?}");
-
-    // Convert current exe path to string, log an error and return if it fails
-    let current_exe = match current_exe.to_str() {
-        Some(s) => {
-            // remove "(deleted)" string from current exe path
-            if s.contains(" (deleted)") {
-                warn!("The current executable path contains ' (deleted)', which may lead to unexpected behavior. This has been removed from the exe location string");
-                s.replace(" (deleted)", "")
-            } else {
-                s.to_string()
-            }
-        }
-        None => {
-            error!("Failed to convert current executable path to string");
-            return;
-        }
-    };
+    info!("Current exe is: {exe_path:?}");
 
     // Create a new Command instance to run the current executable
-    let mut cmd = Command::new(current_exe);
+    let mut cmd = Command::new(exe_path);
 
     // Set the arguments for the new Command
-    cmd.args(&args[1..]); // Exclude the first argument (binary path)
+    cmd.args(without_identity_import(&args[1..])); // Exclude the first argument (binary path)
 
     if let Some((root_dir, port)) = retain_peer_id {
         cmd.arg("--root-dir");
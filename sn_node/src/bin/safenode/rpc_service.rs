@@ -11,11 +11,13 @@ use sn_logging::ReloadHandle;
 use sn_node::RunningNode;
 use sn_protocol::node_rpc::NodeCtrl;
 use sn_protocol::safenode_proto::{
-    k_buckets_response,
+    bandwidth_response, k_buckets_response,
     safe_node_server::{SafeNode, SafeNodeServer},
-    KBucketsRequest, KBucketsResponse, NetworkInfoRequest, NetworkInfoResponse, NodeEvent,
-    NodeEventsRequest, NodeInfoRequest, NodeInfoResponse, RecordAddressesRequest,
-    RecordAddressesResponse, RestartRequest, RestartResponse, StopRequest, StopResponse,
+    BandwidthRequest, BandwidthResponse, EarningsRequest, EarningsResponse, KBucketsRequest,
+    KBucketsResponse, NetworkInfoRequest, NetworkInfoResponse, NodeEvent, NodeEventsRequest,
+    NodeInfoRequest, NodeInfoResponse, RecordAddressesRequest, RecordAddressesResponse,
+    RecordStatsRequest, RecordStatsResponse, RestartRequest, RestartResponse,
+    RotateIdentityRequest, RotateIdentityResponse, StopRequest, StopResponse,
     UpdateLogLevelRequest, UpdateLogLevelResponse, UpdateRequest, UpdateResponse,
 };
 use std::{
@@ -55,6 +57,17 @@ impl SafeNode for SafeNodeRpcService {
             request.get_ref()
         );
 
+        let disk_usage = self.running_node.get_disk_usage().await.map_err(|err| {
+            Status::new(Code::Internal, format!("Failed to get disk usage: {err}"))
+        })?;
+
+        let wallet_address = self.running_node.get_node_wallet_address().map_err(|err| {
+            Status::new(
+                Code::Internal,
+                format!("Failed to get node wallet address: {err}"),
+            )
+        })?;
+
         let resp = Response::new(NodeInfoResponse {
             peer_id: self.running_node.peer_id().to_bytes(),
             log_dir: self.log_dir.clone(),
@@ -71,6 +84,9 @@ impl SafeNode for SafeNodeRpcService {
                 .get_node_wallet_balance()
                 .expect("Failed to get node wallet balance")
                 .as_nano(),
+            records_used_bytes: disk_usage.used_bytes,
+            records_max_bytes: disk_usage.max_bytes.unwrap_or(0),
+            wallet_address: wallet_address.to_hex(),
         });
 
         Ok(resp)
@@ -164,6 +180,88 @@ impl SafeNode for SafeNodeRpcService {
         Ok(Response::new(RecordAddressesResponse { addresses }))
     }
 
+    async fn record_stats(
+        &self,
+        request: Request<RecordStatsRequest>,
+    ) -> Result<Response<RecordStatsResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let disk_usage = self.running_node.get_disk_usage().await.map_err(|err| {
+            Status::new(Code::Internal, format!("Failed to get disk usage: {err}"))
+        })?;
+
+        Ok(Response::new(RecordStatsResponse {
+            records: disk_usage.records as u64,
+            used_bytes: disk_usage.used_bytes,
+            max_bytes: disk_usage.max_bytes.unwrap_or(0),
+        }))
+    }
+
+    async fn earnings(
+        &self,
+        request: Request<EarningsRequest>,
+    ) -> Result<Response<EarningsResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let earnings = self.running_node.earnings_stats();
+        Ok(Response::new(EarningsResponse {
+            total: earnings.total.as_nano(),
+            payments: earnings.payments,
+            per_day: earnings
+                .per_day
+                .into_iter()
+                .map(|(day, amount)| (day, amount.as_nano()))
+                .collect(),
+            per_record_kind: earnings
+                .per_record_kind
+                .into_iter()
+                .map(|(kind, amount)| (kind, amount.as_nano()))
+                .collect(),
+            per_payer: earnings
+                .per_payer
+                .into_iter()
+                .map(|(payer, amount)| (payer, amount.as_nano()))
+                .collect(),
+        }))
+    }
+
+    async fn bandwidth(
+        &self,
+        request: Request<BandwidthRequest>,
+    ) -> Result<Response<BandwidthResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let bandwidth = self
+            .running_node
+            .bandwidth_per_peer()
+            .into_iter()
+            .flat_map(|(peer_id, protocols)| {
+                protocols.into_iter().map(move |(protocol, bandwidth)| {
+                    bandwidth_response::PeerBandwidth {
+                        peer: peer_id.to_bytes(),
+                        protocol,
+                        sent: bandwidth.sent,
+                        received: bandwidth.received,
+                    }
+                })
+            })
+            .collect();
+
+        Ok(Response::new(BandwidthResponse { bandwidth }))
+    }
+
     async fn k_buckets(
         &self,
         request: Request<KBucketsRequest>,
@@ -242,6 +340,42 @@ impl SafeNode for SafeNodeRpcService {
         }
     }
 
+    async fn rotate_identity(
+        &self,
+        request: Request<RotateIdentityRequest>,
+    ) -> Result<Response<RotateIdentityResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let new_peer_id = self.running_node.rotate_identity().map_err(|err| {
+            Status::new(
+                Code::Internal,
+                format!("Failed to rotate the identity of the node: {err}"),
+            )
+        })?;
+        // the node takes its new key on by restarting with the same root dir
+        let delay = Duration::from_millis(request.get_ref().delay_millis);
+        match self
+            .ctrl_tx
+            .send(NodeCtrl::Restart {
+                delay,
+                retain_peer_id: true,
+            })
+            .await
+        {
+            Ok(()) => Ok(Response::new(RotateIdentityResponse {
+                new_peer_id: new_peer_id.to_bytes(),
+            })),
+            Err(err) => Err(Status::new(
+                Code::Internal,
+                format!("Failed to restart the node with its new identity: {err}"),
+            )),
+        }
+    }
+
     async fn update(
         &self,
         request: Request<UpdateRequest>,
@@ -0,0 +1,224 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use eyre::{eyre, Result};
+use semver::Version;
+use sn_protocol::node_rpc::NodeCtrl;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, Mutex},
+    time::sleep,
+};
+
+/// How long the node waits after installing a new binary before restarting into it.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Upgrades the node to the signed releases published at a URL, which holds:
+///  - `version`: the version of the latest release, e.g. `0.111.0`
+///  - `safenode-<arch>-<os>`: the binary of that release, for each platform
+///  - `safenode-<arch>-<os>.sig`: the BLS signature of the version followed by a new line and the
+///    binary, by the key the releases are published with.
+///
+/// The binary replaces the one running once its signature is verified, the previous one being
+/// kept alongside with an `.old` extension, and the node restarts into it with the same root dir
+/// and port, so it keeps its identity and records.
+#[derive(Clone, Debug)]
+pub(crate) struct Upgrader {
+    url: String,
+    public_key: bls::PublicKey,
+    // The path of the running binary, taken at start, as it's reported to be the previous one
+    // kept with the `.old` extension once a new one is installed on Linux.
+    exe_path: PathBuf,
+    // Held while an upgrade is being tried, so that the periodic check and the RPC requested ones
+    // don't race each other.
+    in_progress: Arc<Mutex<()>>,
+}
+
+impl Upgrader {
+    pub(crate) fn new(url: String, public_key: bls::PublicKey, exe_path: PathBuf) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            public_key,
+            exe_path,
+            in_progress: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Checks for a new release at each interval, restarting the node into it once installed.
+    pub(crate) fn spawn_periodic_upgrades(
+        &self,
+        interval: Duration,
+        ctrl_tx: mpsc::Sender<NodeCtrl>,
+    ) {
+        let upgrader = self.clone();
+        let _handle = tokio::spawn(async move {
+            let mut upgrade_interval = tokio::time::interval(interval);
+            let _ = upgrade_interval.tick().await; // first tick completes immediately
+            loop {
+                let _ = upgrade_interval.tick().await;
+                upgrader.upgrade_and_restart(&ctrl_tx).await;
+            }
+        });
+    }
+
+    /// Checks for a new release after the delay, restarting the node into it once installed.
+    pub(crate) fn spawn_upgrade(&self, delay: Duration, ctrl_tx: mpsc::Sender<NodeCtrl>) {
+        let upgrader = self.clone();
+        let _handle = tokio::spawn(async move {
+            sleep(delay).await;
+            upgrader.upgrade_and_restart(&ctrl_tx).await;
+        });
+    }
+
+    async fn upgrade_and_restart(&self, ctrl_tx: &mpsc::Sender<NodeCtrl>) {
+        let Ok(_guard) = self.in_progress.try_lock() else {
+            debug!("An upgrade is already in progress");
+            return;
+        };
+
+        match self.upgrade().await {
+            Ok(Some(version)) => {
+                println!("Upgraded the node to v{version}, restarting...");
+                info!("Upgraded the node to v{version}, restarting");
+                if let Err(err) = ctrl_tx
+                    .send(NodeCtrl::Restart {
+                        delay: RESTART_DELAY,
+                        retain_peer_id: true,
+                    })
+                    .await
+                {
+                    error!("Failed to send node control msg to safenode bin main thread: {err}");
+                }
+            }
+            Ok(None) => debug!("The node is running the latest release"),
+            Err(err) => error!("Failed to upgrade the node: {err:?}"),
+        }
+    }
+
+    /// Installs the latest release if it's newer than the running one, returning its version.
+    async fn upgrade(&self) -> Result<Option<Version>> {
+        let current = Version::parse(env!("CARGO_PKG_VERSION"))?;
+        let latest = Version::parse(String::from_utf8(self.download("version").await?)?.trim())?;
+        if latest <= current {
+            return Ok(None);
+        }
+
+        let binary_name = format!("safenode-{}-{}", env::consts::ARCH, env::consts::OS);
+        info!("Downloading {binary_name} v{latest} from {}", self.url);
+        let binary = self.download(&binary_name).await?;
+        let signature = self.download(&format!("{binary_name}.sig")).await?;
+        verify_release(&self.public_key, &latest, &binary, &signature)?;
+
+        install(&self.exe_path, &binary)?;
+        Ok(Some(latest))
+    }
+
+    async fn download(&self, file: &str) -> Result<Vec<u8>> {
+        let url = format!("{}/{file}", self.url);
+        let response = reqwest::get(&url).await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// Checks the binary of the release was signed along with its version by the release key.
+fn verify_release(
+    public_key: &bls::PublicKey,
+    version: &Version,
+    binary: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    let signature = bls::Signature::from_bytes(
+        signature
+            .try_into()
+            .map_err(|_| eyre!("The release signature is {} bytes long", signature.len()))?,
+    )?;
+    if public_key.verify(&signature, signed_release_bytes(version, binary)) {
+        Ok(())
+    } else {
+        Err(eyre!("The signature of the v{version} release is invalid"))
+    }
+}
+
+fn signed_release_bytes(version: &Version, binary: &[u8]) -> Vec<u8> {
+    [format!("{version}\n").as_bytes(), binary].concat()
+}
+
+/// Replaces the binary at the path, keeping the previous one with an `.old` extension.
+fn install(exe_path: &Path, binary: &[u8]) -> Result<()> {
+    let new_path = exe_path.with_extension("new");
+    let old_path = exe_path.with_extension("old");
+    fs::write(&new_path, binary)?;
+    fs::set_permissions(&new_path, fs::metadata(exe_path)?.permissions())?;
+
+    // The running binary can be renamed but not overwritten on every platform.
+    fs::rename(exe_path, &old_path)?;
+    if let Err(err) = fs::rename(&new_path, exe_path) {
+        fs::rename(&old_path, exe_path)?;
+        return Err(err.into());
+    }
+    info!("Installed the new binary at {exe_path:?}, the previous one is kept at {old_path:?}");
+    Ok(())
+}
+
+/// The path of the running binary, to be taken before any new binary is installed, and restarted
+/// into.
+pub(crate) fn current_exe() -> Result<PathBuf> {
+    let exe_path = env::current_exe()?;
+    // the path of a binary replaced while running is reported with this suffix on Linux
+    match exe_path.to_str() {
+        Some(path) if path.ends_with(" (deleted)") => {
+            Ok(PathBuf::from(path.trim_end_matches(" (deleted)")))
+        }
+        _ => Ok(exe_path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn releases_are_installed_only_if_signed() -> Result<()> {
+        let release_key = bls::SecretKey::random();
+        let version = Version::new(1, 2, 3);
+        let binary = b"new binary".to_vec();
+        let signature = release_key
+            .sign(signed_release_bytes(&version, &binary))
+            .to_bytes();
+
+        verify_release(&release_key.public_key(), &version, &binary, &signature)?;
+        // the signature doesn't hold for another version, nor another key
+        assert!(verify_release(
+            &release_key.public_key(),
+            &Version::new(1, 2, 4),
+            &binary,
+            &signature
+        )
+        .is_err());
+        assert!(verify_release(
+            &bls::SecretKey::random().public_key(),
+            &version,
+            &binary,
+            &signature
+        )
+        .is_err());
+
+        let dir = tempfile::tempdir()?;
+        let exe_path = dir.path().join("safenode");
+        fs::write(&exe_path, b"old binary")?;
+        install(&exe_path, &binary)?;
+        assert_eq!(fs::read(&exe_path)?, binary);
+        assert_eq!(fs::read(exe_path.with_extension("old"))?, b"old binary");
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::RecordKind;
+use sn_transfers::NanoTokens;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const EARNINGS_FILENAME: &str = "earnings";
+
+/// The days the earnings are kept for.
+const MAX_EARNINGS_DAYS: usize = 366;
+
+/// The most payers the earnings are kept for, the ones who paid the least being dropped first.
+const MAX_EARNINGS_PAYERS: usize = 1000;
+
+/// The payer of the payments whose record had no publisher.
+const UNKNOWN_PAYER: &str = "unknown";
+
+const SECS_PER_DAY: u64 = 24 * 3600;
+
+/// The rewards earned by the node, added up over time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EarningsStats {
+    /// The rewards earned in total.
+    pub total: NanoTokens,
+    /// The number of payments the rewards were earned with.
+    pub payments: u64,
+    /// The rewards earned each day, by the number of days since the unix epoch.
+    pub per_day: BTreeMap<u64, NanoTokens>,
+    /// The rewards earned for each kind of record, e.g. `chunk`.
+    pub per_record_kind: BTreeMap<String, NanoTokens>,
+    /// The rewards earned from each payer, by the `PeerId` the records were published with.
+    pub per_payer: BTreeMap<String, NanoTokens>,
+}
+
+impl Default for EarningsStats {
+    fn default() -> Self {
+        Self {
+            total: NanoTokens::zero(),
+            payments: 0,
+            per_day: BTreeMap::new(),
+            per_record_kind: BTreeMap::new(),
+            per_payer: BTreeMap::new(),
+        }
+    }
+}
+
+impl EarningsStats {
+    fn add(
+        &mut self,
+        amount: NanoTokens,
+        kind: RecordKind,
+        payer: Option<PeerId>,
+        now: SystemTime,
+    ) {
+        let day = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / SECS_PER_DAY;
+        let payer = payer.map_or_else(|| UNKNOWN_PAYER.to_string(), |peer| peer.to_string());
+
+        add_to(&mut self.total, amount);
+        self.payments += 1;
+        add_to(
+            self.per_day.entry(day).or_insert(NanoTokens::zero()),
+            amount,
+        );
+        add_to(
+            self.per_record_kind
+                .entry(record_kind_name(kind).to_string())
+                .or_insert(NanoTokens::zero()),
+            amount,
+        );
+        add_to(
+            self.per_payer.entry(payer).or_insert(NanoTokens::zero()),
+            amount,
+        );
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        while self.per_day.len() > MAX_EARNINGS_DAYS {
+            let _ = self.per_day.pop_first();
+        }
+        if self.per_payer.len() > MAX_EARNINGS_PAYERS {
+            let mut payers: Vec<_> = std::mem::take(&mut self.per_payer).into_iter().collect();
+            payers.sort_by(|(_, a), (_, b)| b.cmp(a));
+            payers.truncate(MAX_EARNINGS_PAYERS);
+            self.per_payer = payers.into_iter().collect();
+        }
+    }
+}
+
+fn add_to(total: &mut NanoTokens, amount: NanoTokens) {
+    *total = total.checked_add(amount).unwrap_or(*total);
+}
+
+/// The name the earnings of the records of the kind are tracked under.
+fn record_kind_name(kind: RecordKind) -> &'static str {
+    match kind {
+        RecordKind::Chunk | RecordKind::ChunkWithPayment => "chunk",
+        RecordKind::Spend => "spend",
+        RecordKind::Register | RecordKind::RegisterWithPayment => "register",
+        RecordKind::Pointer | RecordKind::PointerWithPayment => "pointer",
+        RecordKind::Scratchpad | RecordKind::ScratchpadWithPayment => "scratchpad",
+        RecordKind::ExpiringData | RecordKind::ExpiringDataWithPayment => "expiring_data",
+    }
+}
+
+/// Keeps the earnings stats of the node, on disk in its root dir so they're kept across
+/// restarts.
+#[derive(Clone, Debug)]
+pub(crate) struct EarningsTracker {
+    file_path: PathBuf,
+    stats: Arc<Mutex<EarningsStats>>,
+}
+
+impl EarningsTracker {
+    /// Restores the earnings stored in the dir, if any.
+    pub(crate) fn load(dir: &Path) -> Self {
+        let file_path = dir.join(EARNINGS_FILENAME);
+        let stats = match fs::read(&file_path) {
+            Ok(bytes) => rmp_serde::from_slice(&bytes).unwrap_or_else(|err| {
+                warn!("Failed to deserialize the earnings from {file_path:?}: {err:?}");
+                EarningsStats::default()
+            }),
+            Err(_) => EarningsStats::default(),
+        };
+        Self {
+            file_path,
+            stats: Arc::new(Mutex::new(stats)),
+        }
+    }
+
+    /// Returns the earnings so far.
+    pub(crate) fn stats(&self) -> EarningsStats {
+        self.lock_stats().clone()
+    }
+
+    /// Adds a payment for a record of the kind to the earnings, and writes them to disk.
+    pub(crate) fn record_payment(
+        &self,
+        amount: NanoTokens,
+        kind: RecordKind,
+        payer: Option<PeerId>,
+    ) {
+        let stats = {
+            let mut stats = self.lock_stats();
+            stats.add(amount, kind, payer, SystemTime::now());
+            stats.clone()
+        };
+        if let Err(err) = save(&self.file_path, &stats) {
+            warn!(
+                "Failed to write the earnings to {:?}: {err:?}",
+                self.file_path
+            );
+        }
+    }
+
+    fn lock_stats(&self) -> MutexGuard<'_, EarningsStats> {
+        match self.stats.lock() {
+            Ok(stats) => stats,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+fn save(file_path: &Path, stats: &EarningsStats) -> io::Result<()> {
+    let bytes = rmp_serde::to_vec(stats).map_err(io::Error::other)?;
+    fs::write(file_path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn earnings_are_added_up_and_kept_across_restarts() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let tracker = EarningsTracker::load(dir.path());
+        let payer = PeerId::random();
+
+        tracker.record_payment(
+            NanoTokens::from(10),
+            RecordKind::ChunkWithPayment,
+            Some(payer),
+        );
+        tracker.record_payment(NanoTokens::from(5), RecordKind::RegisterWithPayment, None);
+
+        let stats = EarningsTracker::load(dir.path()).stats();
+        assert_eq!(stats, tracker.stats());
+        assert_eq!(stats.total, NanoTokens::from(15));
+        assert_eq!(stats.payments, 2);
+        assert_eq!(
+            stats.per_day.values().copied().collect::<Vec<_>>(),
+            [NanoTokens::from(15)]
+        );
+        assert_eq!(stats.per_record_kind["chunk"], NanoTokens::from(10));
+        assert_eq!(stats.per_record_kind["register"], NanoTokens::from(5));
+        assert_eq!(stats.per_payer[&payer.to_string()], NanoTokens::from(10));
+        assert_eq!(stats.per_payer[UNKNOWN_PAYER], NanoTokens::from(5));
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_latest_days_are_kept() {
+        let mut stats = EarningsStats::default();
+        for day in 0..MAX_EARNINGS_DAYS as u64 + 2 {
+            let now = UNIX_EPOCH + Duration::from_secs(day * SECS_PER_DAY);
+            stats.add(NanoTokens::from(1), RecordKind::ChunkWithPayment, None, now);
+        }
+        assert_eq!(stats.per_day.len(), MAX_EARNINGS_DAYS);
+        assert_eq!(stats.per_day.keys().next(), Some(&2));
+        assert_eq!(stats.total.as_nano(), MAX_EARNINGS_DAYS as u64 + 2);
+    }
+}
@@ -6,8 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use sn_protocol::{NetworkAddress, PrettyPrintRecordKey};
-use sn_transfers::{NanoTokens, WalletError};
+use crate::SuitabilityReport;
+use libp2p::PeerId;
+use sn_protocol::{storage::SpendAddress, NetworkAddress, PrettyPrintRecordKey};
+use sn_transfers::{MainPubkey, NanoTokens, WalletError};
 use thiserror::Error;
 
 pub(super) type Result<T, E = Error> = std::result::Result<T, E>;
@@ -40,6 +42,15 @@ pub enum Error {
     #[error("Overflow occurred while adding values")]
     NumericOverflow,
 
+    #[error("The node lacks the resources to join the network: {0:?}")]
+    InsufficientResources(Box<SuitabilityReport>),
+
+    #[error("Invalid node identity: {0}")]
+    InvalidIdentity(String),
+
+    #[error("The root dir already holds the identity of {0}")]
+    IdentityConflict(PeerId),
+
     // ---------- Record Errors
     #[error("Record was not stored as no payment supplied: {0:?}")]
     InvalidPutWithoutPayment(PrettyPrintRecordKey<'static>),
@@ -50,6 +61,9 @@ pub enum Error {
     #[error("The Record::key does not match with the key derived from Record::value")]
     RecordKeyMismatch,
 
+    #[error("The reclaimable CashNote at {0:?} was already spent by the other party")]
+    ReclaimableCashNoteAlreadyRedeemed(SpendAddress),
+
     // ---------- Payment Errors
     #[error("The content of the payment quote is invalid")]
     InvalidQuoteContent,
@@ -71,6 +85,13 @@ pub enum Error {
         paid: NanoTokens,
         expected: NanoTokens,
     },
+    /// A network royalties beneficiary was paid less than its share of the royalties
+    #[error("The network royalties paid to {beneficiary:?} are not its share, paid {paid}, expected {expected}")]
+    RoyaltiesShareInsufficientAmount {
+        beneficiary: MainPubkey,
+        paid: NanoTokens,
+        expected: NanoTokens,
+    },
     #[error("A payment we received contains cash notes already confirmed to be spent")]
     ReusedPayment,
 
@@ -79,6 +100,8 @@ pub enum Error {
     FailedToGenerateRewardKey,
 
     // ---------- Miscellaneous Errors
+    #[error("I/O error {0}")]
+    Io(#[from] std::io::Error),
     #[error("Failed to obtain node's current port")]
     FailedToGetNodePort,
     /// The request is invalid or the arguments of the function are invalid
@@ -8,6 +8,7 @@
 
 use crate::error::{Error, Result};
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use sn_protocol::storage::{ChunkAddress, RegisterAddress};
 use sn_transfers::UniquePubkey;
@@ -68,6 +69,14 @@ pub enum NodeEvent {
     ChannelClosed,
     /// Terminates the node
     TerminateNode(String),
+    /// A message was published on a pub/sub topic subscribed to
+    PubSubMessage {
+        /// The topic the message was published on
+        topic: String,
+        /// The message
+        #[debug(skip)]
+        msg: Bytes,
+    },
 }
 
 impl NodeEvent {
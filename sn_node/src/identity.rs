@@ -0,0 +1,220 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    error::{Error, Result},
+    RunningNode,
+};
+use libp2p::{identity::Keypair, PeerId};
+use sn_transfers::{bls_secret_from_hex, HotWallet, MainPubkey, MainSecretKey, WALLET_DIR_NAME};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+/// The file the key of a node is kept in, in its root dir.
+const NODE_SECRET_KEY_FILENAME: &str = "secret-key";
+
+/// The file the reward key of a node is kept in, hex encoded, in its wallet dir.
+const REWARD_KEY_FILENAME: &str = "main_secret_key";
+
+/// The identity of a node: the key it's known by to its peers, and the key it's paid its rewards
+/// to. Moved along with it, the node keeps its standing and its rewards on another machine.
+pub struct NodeIdentity {
+    keypair: Keypair,
+    reward_key: MainSecretKey,
+}
+
+impl NodeIdentity {
+    /// Reads the identity of the node with the root dir.
+    pub fn load(root_dir: &Path) -> Result<Self> {
+        let bytes = fs::read(root_dir.join(NODE_SECRET_KEY_FILENAME))?;
+        let keypair = Keypair::ed25519_from_bytes(bytes)
+            .map_err(|err| Error::InvalidIdentity(err.to_string()))?;
+        let reward_key_path = root_dir.join(WALLET_DIR_NAME).join(REWARD_KEY_FILENAME);
+        let reward_key = match fs::read(&reward_key_path) {
+            Ok(hex) => MainSecretKey::new(
+                bls_secret_from_hex(hex).map_err(|err| Error::InvalidIdentity(err.to_string()))?,
+            ),
+            // the node didn't start yet, its reward key is the one it'd start with
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => reward_key_of(&keypair)?,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            keypair,
+            reward_key,
+        })
+    }
+
+    /// Reads an identity exported with `export`.
+    pub fn import(path: &Path) -> Result<Self> {
+        let exported = fs::read_to_string(path)?;
+        let field = |name: &str| {
+            exported
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+                .map(str::trim)
+                .ok_or_else(|| Error::InvalidIdentity(format!("no {name} in {path:?}")))
+        };
+        let mut peer_key = hex::decode(field("peer_key")?)
+            .map_err(|err| Error::InvalidIdentity(err.to_string()))?;
+        let keypair = Keypair::ed25519_from_bytes(&mut peer_key)
+            .map_err(|err| Error::InvalidIdentity(err.to_string()))?;
+        let reward_key = bls_secret_from_hex(field("reward_key")?)
+            .map_err(|err| Error::InvalidIdentity(err.to_string()))?;
+        Ok(Self {
+            keypair,
+            reward_key: MainSecretKey::new(reward_key),
+        })
+    }
+
+    /// The `PeerId` of the node.
+    pub fn peer_id(&self) -> PeerId {
+        self.keypair.public().to_peer_id()
+    }
+
+    /// The address the node is paid its rewards to.
+    pub fn reward_address(&self) -> MainPubkey {
+        self.reward_key.main_pubkey()
+    }
+
+    /// Writes the identity to the file, readable by the current user only, for it to be imported
+    /// on another machine.
+    pub fn export(&self, path: &Path) -> Result<()> {
+        let peer_key = self
+            .keypair
+            .clone()
+            .try_into_ed25519()
+            .map_err(|err| Error::InvalidIdentity(err.to_string()))?
+            .secret();
+        let exported = format!(
+            "peer_key {}\nreward_key {}\n",
+            hex::encode(peer_key.as_ref()),
+            hex::encode(self.reward_key.to_bytes())
+        );
+        write_secret_file(path, exported.as_bytes())
+    }
+
+    /// Sets the identity up in the root dir, for the node started with it to take it on. Fails if
+    /// the root dir already holds another identity.
+    pub fn install(&self, root_dir: &Path) -> Result<()> {
+        fs::create_dir_all(root_dir)?;
+        if let Ok(existing) = Self::load(root_dir) {
+            if existing.peer_id() != self.peer_id()
+                || existing.reward_address() != self.reward_address()
+            {
+                return Err(Error::IdentityConflict(existing.peer_id()));
+            }
+        }
+        write_keypair(root_dir, &self.keypair)?;
+        let reward_key = MainSecretKey::new(self.reward_key.secret_key().clone());
+        let _ = HotWallet::create_from_key(root_dir, reward_key, None)?;
+        info!(
+            "Installed the identity of {} in {root_dir:?}",
+            self.peer_id()
+        );
+        Ok(())
+    }
+}
+
+impl RunningNode {
+    /// Moves the node to a new key, telling its closest peers ahead so they carry its standing
+    /// over and don't take it for churn. It keeps its reward key, and its current key is kept in
+    /// the root dir as `secret-key.<PeerId>`.
+    ///
+    /// The node goes on with its current key until it's restarted with the same root dir.
+    /// Returns the `PeerId` it'll restart with.
+    pub fn rotate_identity(&self) -> Result<PeerId> {
+        let root_dir = self.network.root_dir_path();
+        let old_peer_id = self.peer_id();
+        let new_keypair = Keypair::generate_ed25519();
+        let new_peer_id = new_keypair.public().to_peer_id();
+
+        let key_path = root_dir.join(NODE_SECRET_KEY_FILENAME);
+        let _ = fs::copy(
+            &key_path,
+            root_dir.join(format!("{NODE_SECRET_KEY_FILENAME}.{old_peer_id}")),
+        )?;
+        write_keypair(root_dir, &new_keypair)?;
+        self.network.announce_key_rotation(&new_keypair)?;
+        info!("Rotated the key of the node from {old_peer_id} to {new_peer_id}, taking effect on restart");
+        Ok(new_peer_id)
+    }
+}
+
+/// The reward key of a node which doesn't have a wallet yet, derived from its key.
+pub(crate) fn reward_key_of(keypair: &Keypair) -> Result<MainSecretKey> {
+    // Using the signature as the seed of generating the reward_key
+    let sig_vec = match keypair.sign(b"generate reward seed") {
+        Ok(sig) => sig,
+        Err(_err) => return Err(Error::FailedToGenerateRewardKey),
+    };
+    let mut rng = sn_transfers::rng::from_vec(&sig_vec);
+    Ok(MainSecretKey::random_from_rng(&mut rng))
+}
+
+fn write_keypair(root_dir: &Path, keypair: &Keypair) -> Result<()> {
+    let secret = keypair
+        .clone()
+        .try_into_ed25519()
+        .map_err(|err| Error::InvalidIdentity(err.to_string()))?
+        .secret();
+    write_secret_file(&root_dir.join(NODE_SECRET_KEY_FILENAME), secret.as_ref())
+}
+
+fn write_secret_file(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    let _ = options.write(true).create(true).truncate(true);
+    // On Unix systems, make sure only the current user can read/write.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        let _ = options.mode(0o600);
+    }
+    options.open(path)?.write_all(bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_exported_identity_is_installed_elsewhere() -> eyre::Result<()> {
+        let (old_machine, new_machine) = (tempfile::tempdir()?, tempfile::tempdir()?);
+        let keypair = Keypair::generate_ed25519();
+        write_keypair(old_machine.path(), &keypair)?;
+        let identity = NodeIdentity::load(old_machine.path())?;
+        assert_eq!(identity.peer_id(), keypair.public().to_peer_id());
+        assert_eq!(
+            identity.reward_address(),
+            reward_key_of(&keypair)?.main_pubkey()
+        );
+
+        let exported = old_machine.path().join("identity");
+        identity.export(&exported)?;
+        let imported = NodeIdentity::import(&exported)?;
+        imported.install(new_machine.path())?;
+
+        let installed = NodeIdentity::load(new_machine.path())?;
+        assert_eq!(installed.peer_id(), identity.peer_id());
+        assert_eq!(installed.reward_address(), identity.reward_address());
+        // installing it again is harmless
+        imported.install(new_machine.path())?;
+
+        // while another identity is refused
+        write_keypair(old_machine.path(), &Keypair::generate_ed25519())?;
+        let other = NodeIdentity::load(old_machine.path())?;
+        assert!(matches!(
+            other.install(new_machine.path()),
+            Err(Error::IdentityConflict(peer_id)) if peer_id == identity.peer_id()
+        ));
+        Ok(())
+    }
+}
@@ -27,32 +27,64 @@
 #[macro_use]
 extern crate tracing;
 
+mod earnings;
 mod error;
 mod event;
+mod identity;
 mod log_markers;
 #[cfg(feature = "open-metrics")]
 mod metrics;
 mod node;
+mod node_manager;
 mod put_validation;
 mod quote;
 mod replication;
+mod replication_throttle;
+mod resources;
+mod reward_forwarding;
+mod scrubbing;
 
 pub use self::{
+    earnings::EarningsStats,
     event::{NodeEvent, NodeEventsChannel, NodeEventsReceiver},
+    identity::NodeIdentity,
     log_markers::Marker,
     node::{NodeBuilder, PERIODIC_REPLICATION_INTERVAL_MAX_S},
+    node_manager::{NodeManager, NodeStatus, NodesSummary},
+    resources::{ResourceRequirements, ResourceShortfall, SuitabilityReport},
+    reward_forwarding::{ForwardedRewards, RewardForwarding},
 };
+#[cfg(feature = "local-discovery")]
+pub use sn_networking::LocalDiscovery;
+#[cfg(feature = "rocksdb")]
+pub use sn_networking::RocksDbRecordStoreBackend;
+#[cfg(feature = "webrtc")]
+pub use sn_networking::WebRtcListener;
+pub use sn_networking::{
+    migrate_records, Bandwidth, DefaultPricing, DiskRecordStoreBackend, DiskUsage, FlatPricing,
+    KademliaParams, NetworkKey, PricingStrategy, PruningPolicy, PubSubLimits, RecordStoreBackend,
+    RecordStoreStats, RelayLimits, ReplicationLimits, RequestRateLimits, ScriptedPricing,
+    TopicLimits, TransportMode, UtilizationCurvePricing,
+};
+#[cfg(feature = "websockets")]
+pub use sn_networking::{WebSocketListener, WebSocketTls};
 
-use crate::error::{Error, Result};
+use crate::{
+    earnings::EarningsTracker,
+    error::{Error, Result},
+    resources::ResourceMonitor,
+};
 
 use libp2p::PeerId;
 use sn_networking::{Network, SwarmLocalState};
 use sn_protocol::{get_port_from_multiaddr, NetworkAddress};
-use sn_transfers::{HotWallet, NanoTokens};
+use sn_transfers::{HotWallet, MainPubkey, NanoTokens};
 use std::{
     collections::{BTreeMap, HashSet},
     path::PathBuf,
+    time::Duration,
 };
+use tokio::time::sleep;
 
 /// Once a node is started and running, the user obtains
 /// a `NodeRunning` object which can be used to interact with it.
@@ -60,6 +92,8 @@ use std::{
 pub struct RunningNode {
     network: Network,
     node_events_channel: NodeEventsChannel,
+    resource_monitor: ResourceMonitor,
+    earnings: EarningsTracker,
 }
 
 impl RunningNode {
@@ -86,6 +120,29 @@ impl RunningNode {
         Ok(wallet.balance())
     }
 
+    /// Returns the address of the wallet the node is paid its rewards to
+    pub fn get_node_wallet_address(&self) -> Result<MainPubkey> {
+        let wallet = HotWallet::load_from(self.network.root_dir_path())?;
+        Ok(wallet.address())
+    }
+
+    /// Returns the rewards earned by the node, per day, per kind of record and per payer.
+    pub fn earnings_stats(&self) -> EarningsStats {
+        self.earnings.stats()
+    }
+
+    /// Returns the bytes sent to and received from each of the peers, per protocol, since the
+    /// node started.
+    pub fn bandwidth_per_peer(&self) -> BTreeMap<PeerId, BTreeMap<String, Bandwidth>> {
+        self.network.bandwidth_per_peer()
+    }
+
+    /// Returns the rewards forwarded to the cold wallet so far, oldest first, each with the
+    /// transfer for the cold wallet to receive them with.
+    pub fn reward_forwarding_log(&self) -> Result<Vec<ForwardedRewards>> {
+        reward_forwarding::read_forwarding_log(self.network.root_dir_path())
+    }
+
     /// Returns a `SwarmLocalState` with some information obtained from swarm's local state.
     pub async fn get_swarm_local_state(&self) -> Result<SwarmLocalState> {
         let state = self.network.get_swarm_local_state().await?;
@@ -108,6 +165,36 @@ impl RunningNode {
         &self.node_events_channel
     }
 
+    /// Returns the disk taken by the records held by the node, along with its quota
+    pub async fn get_disk_usage(&self) -> Result<DiskUsage> {
+        let usage = self.network.get_disk_usage().await?;
+        Ok(usage)
+    }
+
+    /// Returns the resources available to the node, and how they fall short of its requirements
+    /// if they do, in which case the node doesn't take new records.
+    pub fn suitability_report(&self) -> SuitabilityReport {
+        self.resource_monitor.report()
+    }
+
+    /// Hands the records held by the node over to the closest peers to each of them, then
+    /// announces the departure of the node to its closest peers, for it to leave the network
+    /// without its records being lost.
+    ///
+    /// The records are fetched from the node during the `handover_period`, so it has to be long
+    /// enough for the peers to fetch all of them.
+    pub async fn leave_network(&self, handover_period: Duration) -> Result<()> {
+        let handed_over = self.network.handover_records().await?;
+        if handed_over > 0 {
+            info!("Handed {handed_over} records over, waiting {handover_period:?} for them to be fetched");
+            sleep(handover_period).await;
+        }
+        self.network.announce_departure();
+        // give the swarm driver the time to send the notifications out.
+        sleep(Duration::from_secs(1)).await;
+        Ok(())
+    }
+
     /// Returns the list of all the RecordKeys held by the node
     pub async fn get_all_record_addresses(&self) -> Result<HashSet<NetworkAddress>> {
         #[allow(clippy::mutable_key_type)] // for Bytes in NetworkAddress
@@ -121,6 +208,26 @@ impl RunningNode {
         Ok(addresses)
     }
 
+    /// Subscribes to the pub/sub topic, the messages published on it being broadcasted as
+    /// `NodeEvent::PubSubMessage`s. The node relays the messages of the topic to its peers in turn.
+    pub async fn subscribe_to_topic(&self, topic: String) -> Result<()> {
+        self.network.subscribe_to_topic(topic).await?;
+        Ok(())
+    }
+
+    /// Unsubscribes from the pub/sub topic.
+    pub async fn unsubscribe_from_topic(&self, topic: String) -> Result<()> {
+        self.network.unsubscribe_from_topic(topic).await?;
+        Ok(())
+    }
+
+    /// Publishes the message on the pub/sub topic, to the peers subscribed to it. Errors out if
+    /// it's over the size or the rate limit of the topic.
+    pub async fn publish_on_topic(&self, topic: String, msg: bytes::Bytes) -> Result<()> {
+        self.network.publish_on_topic(topic, msg).await?;
+        Ok(())
+    }
+
     /// Returns a map where each key is the ilog2 distance of that Kbucket and each value is a vector of peers in that
     /// bucket.
     pub async fn get_kbuckets(&self) -> Result<BTreeMap<u32, Vec<PeerId>>> {
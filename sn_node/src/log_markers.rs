@@ -43,6 +43,12 @@ pub enum Marker<'a> {
     ValidRegisterRecordPutFromNetwork(&'a PrettyPrintRecordKey<'a>),
     /// Valid non-existing Spend record PUT from the network received and stored
     ValidSpendRecordPutFromNetwork(&'a PrettyPrintRecordKey<'a>),
+    /// Valid newer Pointer record PUT from the network received and stored
+    ValidPointerRecordPutFromNetwork(&'a PrettyPrintRecordKey<'a>),
+    /// Valid newer Scratchpad record PUT from the network received and stored
+    ValidScratchpadRecordPutFromNetwork(&'a PrettyPrintRecordKey<'a>),
+    /// Valid non-existing ExpiringData record PUT from the network received and stored
+    ValidExpiringDataRecordPutFromNetwork(&'a PrettyPrintRecordKey<'a>),
 
     /// Valid paid to us and royalty paid chunk stored
     ValidPaidChunkPutFromClient(&'a PrettyPrintRecordKey<'a>),
@@ -50,6 +56,12 @@ pub enum Marker<'a> {
     ValidPaidRegisterPutFromClient(&'a PrettyPrintRecordKey<'a>),
     /// Valid spend stored
     ValidSpendPutFromClient(&'a PrettyPrintRecordKey<'a>),
+    /// Valid paid to us and royalty paid pointer stored
+    ValidPaidPointerPutFromClient(&'a PrettyPrintRecordKey<'a>),
+    /// Valid paid to us and royalty paid scratchpad stored
+    ValidPaidScratchpadPutFromClient(&'a PrettyPrintRecordKey<'a>),
+    /// Valid paid to us and royalty paid expiring data stored
+    ValidPaidExpiringDataPutFromClient(&'a PrettyPrintRecordKey<'a>),
 
     /// Record rejected
     RecordRejected(&'a PrettyPrintRecordKey<'a>, &'a Error),
@@ -63,7 +75,8 @@ impl<'a> Marker<'a> {
     pub fn log(&self) {
         // Down the line, if some logs are noisier than others, we can
         // match the type and log a different level.
-        info!("{self:?}");
+        // The name of the variant is logged as the `event` field, for it to be matched on as is.
+        info!(event = %self, "{self:?}");
     }
 
     /// Helper to log the FetchingKeysForReplication variant
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::Marker;
+use crate::{replication_throttle::ReplicationTrafficLabels, Marker};
 use prometheus_client::{
     encoding::{EncodeLabelSet, EncodeLabelValue},
     metrics::{
@@ -18,6 +18,8 @@ use prometheus_client::{
     registry::{Registry, Unit},
 };
 use sn_networking::Instant;
+use sn_protocol::storage::RecordKind;
+use sn_transfers::NanoTokens;
 
 #[derive(Clone)]
 /// The shared recorders that are used to record metrics.
@@ -25,10 +27,12 @@ pub(crate) struct NodeMetricsRecorder {
     /// put record
     put_record_ok: Family<PutRecordOk, Counter>,
     put_record_err: Counter,
+    pub(crate) put_record_latency: Histogram,
 
     /// replication
     replication_triggered: Counter,
     replication_keys_to_fetch: Histogram,
+    pub(crate) replication_traffic: Family<ReplicationTrafficLabels, Counter>,
 
     // routing table
     peer_added_to_routing_table: Counter,
@@ -37,6 +41,7 @@ pub(crate) struct NodeMetricsRecorder {
     // wallet
     pub(crate) current_reward_wallet_balance: Gauge,
     pub(crate) total_forwarded_rewards: Gauge,
+    earned_rewards: Family<EarnedRewards, Counter>,
 
     // to track the uptime of the node.
     pub(crate) started_instant: Instant,
@@ -48,11 +53,19 @@ struct PutRecordOk {
     record_type: RecordType,
 }
 
+#[derive(EncodeLabelSet, Hash, Clone, Eq, PartialEq, Debug)]
+struct EarnedRewards {
+    record_type: RecordType,
+}
+
 #[derive(EncodeLabelValue, Hash, Clone, Eq, PartialEq, Debug)]
 enum RecordType {
     Chunk,
     Register,
     Spend,
+    Pointer,
+    Scratchpad,
+    ExpiringData,
 }
 
 impl NodeMetricsRecorder {
@@ -72,6 +85,14 @@ impl NodeMetricsRecorder {
             put_record_err.clone(),
         );
 
+        let put_record_latency = Histogram::new(exponential_buckets(0.01, 2.0, 14));
+        sub_registry.register_with_unit(
+            "put_record_latency",
+            "The time taken to validate and store a record PUT",
+            Unit::Seconds,
+            put_record_latency.clone(),
+        );
+
         let replication_triggered = Counter::default();
         sub_registry.register(
             "replication_triggered",
@@ -87,6 +108,14 @@ impl NodeMetricsRecorder {
             replication_keys_to_fetch.clone(),
         );
 
+        let replication_traffic = Family::default();
+        sub_registry.register_with_unit(
+            "replication_traffic",
+            "The records fetched and served for the replication",
+            Unit::Bytes,
+            replication_traffic.clone(),
+        );
+
         let peer_added_to_routing_table = Counter::default();
         sub_registry.register(
             "peer_added_to_routing_table",
@@ -117,6 +146,14 @@ impl NodeMetricsRecorder {
             total_forwarded_rewards.clone(),
         );
 
+        let earned_rewards = Family::default();
+        sub_registry.register_with_unit(
+            "earned_rewards",
+            "The cumulative number of Nanos earned by the node, per type of record paid for",
+            Unit::Other("Nano".to_string()),
+            earned_rewards.clone(),
+        );
+
         let uptime = Gauge::default();
         sub_registry.register_with_unit(
             "uptime",
@@ -128,17 +165,38 @@ impl NodeMetricsRecorder {
         Self {
             put_record_ok,
             put_record_err,
+            put_record_latency,
             replication_triggered,
             replication_keys_to_fetch,
+            replication_traffic,
             peer_added_to_routing_table,
             peer_removed_from_routing_table,
             current_reward_wallet_balance,
             total_forwarded_rewards,
+            earned_rewards,
             started_instant: Instant::now(),
             uptime,
         }
     }
 
+    // Records the rewards earned for a record of the kind
+    pub(crate) fn record_earnings(&self, kind: RecordKind, amount: NanoTokens) {
+        let record_type = match kind {
+            RecordKind::Chunk | RecordKind::ChunkWithPayment => RecordType::Chunk,
+            RecordKind::Spend => RecordType::Spend,
+            RecordKind::Register | RecordKind::RegisterWithPayment => RecordType::Register,
+            RecordKind::Pointer | RecordKind::PointerWithPayment => RecordType::Pointer,
+            RecordKind::Scratchpad | RecordKind::ScratchpadWithPayment => RecordType::Scratchpad,
+            RecordKind::ExpiringData | RecordKind::ExpiringDataWithPayment => {
+                RecordType::ExpiringData
+            }
+        };
+        let _ = self
+            .earned_rewards
+            .get_or_create(&EarnedRewards { record_type })
+            .inc_by(amount.as_nano());
+    }
+
     // Records the metric
     pub(crate) fn record(&self, log_marker: Marker) {
         match log_marker {
@@ -169,6 +227,33 @@ impl NodeMetricsRecorder {
                     .inc();
             }
 
+            Marker::ValidPointerRecordPutFromNetwork(_) => {
+                let _ = self
+                    .put_record_ok
+                    .get_or_create(&PutRecordOk {
+                        record_type: RecordType::Pointer,
+                    })
+                    .inc();
+            }
+
+            Marker::ValidScratchpadRecordPutFromNetwork(_) => {
+                let _ = self
+                    .put_record_ok
+                    .get_or_create(&PutRecordOk {
+                        record_type: RecordType::Scratchpad,
+                    })
+                    .inc();
+            }
+
+            Marker::ValidExpiringDataRecordPutFromNetwork(_) => {
+                let _ = self
+                    .put_record_ok
+                    .get_or_create(&PutRecordOk {
+                        record_type: RecordType::ExpiringData,
+                    })
+                    .inc();
+            }
+
             Marker::RecordRejected(_, _) => {
                 let _ = self.put_record_err.inc();
             }
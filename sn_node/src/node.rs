@@ -14,25 +14,39 @@ use super::{
 };
 #[cfg(feature = "open-metrics")]
 use crate::metrics::NodeMetricsRecorder;
-use crate::RunningNode;
+use crate::{
+    earnings::EarningsTracker,
+    identity::reward_key_of,
+    replication_throttle::{ReplicationDirection, ReplicationThrottle},
+    resources::{ResourceMonitor, ResourceRequirements},
+    reward_forwarding::RewardForwarding,
+    RunningNode,
+};
 use bytes::Bytes;
 use libp2p::{identity::Keypair, Multiaddr, PeerId};
 #[cfg(feature = "open-metrics")]
 use prometheus_client::metrics::{gauge::Gauge, info::Info};
 #[cfg(feature = "open-metrics")]
 use prometheus_client::registry::Registry;
-use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
 use sn_networking::{
-    close_group_majority, Instant, Network, NetworkBuilder, NetworkError, NetworkEvent, NodeIssue,
-    SwarmDriver,
+    close_group_majority, DefaultPricing, Instant, KademliaParams, Network, NetworkBuilder,
+    NetworkError, NetworkEvent, NetworkKey, NodeIssue, PricingStrategy, PruningPolicy,
+    PubSubLimits, RecordStoreBackend, RelayLimits, ReplicationLimits, RequestRateLimits,
+    SwarmDriver, TransportMode,
 };
 use sn_protocol::{
     error::Error as ProtocolError,
-    messages::{ChunkProof, CmdResponse, Query, QueryResponse, Request, Response},
+    messages::{
+        ChunkProof, CmdResponse, Query, QueryResponse, Request, Response, StorageChallenge,
+    },
+    storage::{try_deserialize_record, RecordKind, RecordType},
     NetworkAddress, PrettyPrintRecordKey, CLOSE_GROUP_SIZE,
 };
-use sn_transfers::{HotWallet, MainPubkey, MainSecretKey, NanoTokens, PAYMENT_FORWARD_PK};
+use sn_registers::{EntryHash, SignedRegister};
+use sn_transfers::{HotWallet, MainPubkey, NanoTokens, PAYMENT_FORWARD_PK};
 use std::{
+    collections::{BTreeSet, HashSet},
     net::SocketAddr,
     path::PathBuf,
     sync::{
@@ -42,16 +56,23 @@ use std::{
     time::Duration,
 };
 use tokio::{
-    sync::mpsc::Receiver,
+    sync::{broadcast::error::RecvError, mpsc::Receiver},
     task::{spawn, JoinHandle},
 };
+use tracing::Instrument;
 
 #[cfg(feature = "reward-forward")]
 use libp2p::kad::{Quorum, Record};
+#[cfg(feature = "local-discovery")]
+use sn_networking::LocalDiscovery;
 #[cfg(feature = "reward-forward")]
 use sn_networking::PutRecordCfg;
+#[cfg(feature = "webrtc")]
+use sn_networking::WebRtcListener;
+#[cfg(feature = "websockets")]
+use sn_networking::WebSocketListener;
 #[cfg(feature = "reward-forward")]
-use sn_protocol::storage::{try_serialize_record, RecordKind, SpendAddress};
+use sn_protocol::storage::{try_serialize_record, SpendAddress};
 
 /// Interval to trigger replication of all records to all peers.
 /// This is the max time it should take. Minimum interval at any node will be half this
@@ -65,6 +86,10 @@ const PERIODIC_BAD_NODE_DETECTION_INTERVAL_MAX_S: u64 = 600;
 /// This is the max time it should take. Minimum interval at any node will be half this
 const PERIODIC_REWARD_FORWARD_INTERVAL_MAX_S: u64 = 450;
 
+/// Interval to challenge a close peer to prove it still holds a chunk we hold as well.
+/// This is the max time it should take. Minimum interval at any node will be half this
+const PERIODIC_STORAGE_CHALLENGE_INTERVAL_MAX_S: u64 = 300;
+
 /// Max number of attempts that chunk proof verification will be carried out against certain target,
 /// before classifying peer as a bad peer.
 const MAX_CHUNK_PROOF_VERIFY_ATTEMPTS: usize = 3;
@@ -76,6 +101,9 @@ const CHUNK_PROOF_VERIFY_RETRY_INTERVAL: Duration = Duration::from_secs(15);
 /// Track the forward balance by storing the balance in a file. This is useful to restore the balance between restarts.
 const FORWARDED_BALANCE_FILE_NAME: &str = "forwarded_balance";
 
+/// How long a Register watch is held when the Register isn't edited, below the request timeout.
+const REGISTER_WATCH_TIMEOUT: Duration = Duration::from_secs(20);
+
 /// Interval to update the nodes uptime metric
 const UPTIME_METRICS_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
 
@@ -95,6 +123,30 @@ pub struct NodeBuilder {
     /// Enable hole punching for nodes connecting from home networks.
     pub is_behind_home_network: bool,
     owner: Option<String>,
+    record_store_backend: Option<Arc<dyn RecordStoreBackend>>,
+    compressed_record_kinds: Option<HashSet<RecordKind>>,
+    disk_quota: Option<(u64, PruningPolicy)>,
+    replication_limits: ReplicationLimits,
+    kademlia_params: KademliaParams,
+    replication_throttle: Option<ReplicationThrottle>,
+    resource_requirements: ResourceRequirements,
+    reward_forwarding: Option<RewardForwarding>,
+    peer_access_file: Option<PathBuf>,
+    request_rate_limits: Option<RequestRateLimits>,
+    pricing: Option<Arc<dyn PricingStrategy>>,
+    transport_mode: TransportMode,
+    #[cfg(feature = "websockets")]
+    websocket: Option<WebSocketListener>,
+    #[cfg(feature = "webrtc")]
+    webrtc: Option<WebRtcListener>,
+    dual_stack: bool,
+    nat_detection: bool,
+    relay_service: Option<RelayLimits>,
+    pubsub: Option<PubSubLimits>,
+    network_key: Option<NetworkKey>,
+    // `None` until set, the default depending on the network key.
+    #[cfg(feature = "local-discovery")]
+    local_discovery: Option<Option<LocalDiscovery>>,
     #[cfg(feature = "upnp")]
     upnp: bool,
 }
@@ -120,11 +172,157 @@ impl NodeBuilder {
             metrics_server_port: None,
             is_behind_home_network: false,
             owner,
+            record_store_backend: None,
+            compressed_record_kinds: None,
+            disk_quota: None,
+            replication_limits: Default::default(),
+            kademlia_params: Default::default(),
+            replication_throttle: None,
+            resource_requirements: Default::default(),
+            reward_forwarding: None,
+            peer_access_file: None,
+            request_rate_limits: Some(Default::default()),
+            pricing: None,
+            transport_mode: Default::default(),
+            #[cfg(feature = "websockets")]
+            websocket: None,
+            #[cfg(feature = "webrtc")]
+            webrtc: None,
+            dual_stack: true,
+            nat_detection: true,
+            relay_service: Some(Default::default()),
+            pubsub: Some(Default::default()),
+            network_key: None,
+            #[cfg(feature = "local-discovery")]
+            local_discovery: None,
             #[cfg(feature = "upnp")]
             upnp,
         }
     }
 
+    /// Set the storage engine the records of the node are kept in, in place of the files of the
+    /// `record_store` dir in the root dir.
+    pub fn record_store_backend(&mut self, backend: Arc<dyn RecordStoreBackend>) {
+        self.record_store_backend = Some(backend);
+    }
+
+    /// Set the kinds of records stored compressed, in place of the spends and the registers.
+    pub fn compressed_record_kinds(&mut self, kinds: HashSet<RecordKind>) {
+        self.compressed_record_kinds = Some(kinds);
+    }
+
+    /// Set the most disk the records may take, in bytes, records being pruned in the order of the
+    /// policy to make room for the incoming ones once it's reached.
+    pub fn disk_quota(&mut self, max_disk_usage: u64, pruning_policy: PruningPolicy) {
+        self.disk_quota = Some((max_disk_usage, pruning_policy));
+    }
+
+    /// Set the caps on the replication traffic, which is unlimited in bytes by default.
+    pub fn replication_limits(&mut self, limits: ReplicationLimits) {
+        self.replication_limits = limits;
+    }
+
+    /// Set the Kademlia parameters, failing if they're not ones the network can run with. All the
+    /// nodes of a network are to be given the same ones.
+    pub fn kademlia_params(&mut self, params: KademliaParams) -> Result<()> {
+        params.validate()?;
+        self.kademlia_params = params;
+        Ok(())
+    }
+
+    /// Set the resources the node needs, which it refuses to join the network without, and stops
+    /// taking new records without while running.
+    pub fn resource_requirements(&mut self, requirements: ResourceRequirements) {
+        self.resource_requirements = requirements;
+    }
+
+    /// Set the cold wallet the rewards earned by the node are forwarded to, which they're kept in
+    /// the reward wallet of the node without.
+    pub fn reward_forwarding(&mut self, forwarding: RewardForwarding) {
+        self.reward_forwarding = Some(forwarding);
+    }
+
+    /// Set the file of the peers the node always accepts or always rejects, in place of the
+    /// `peer_access` file of the root dir. It's reloaded whenever it changes.
+    pub fn peer_access_file(&mut self, path: PathBuf) {
+        self.peer_access_file = Some(path);
+    }
+
+    /// Set the rates the requests of each client are served at, `None` leaving them unlimited.
+    /// The nodes of the routing table are never limited.
+    pub fn request_rate_limits(&mut self, limits: Option<RequestRateLimits>) {
+        self.request_rate_limits = limits;
+    }
+
+    /// Set how the storing of a record is priced, in place of the pricing of the network. The
+    /// quotes of the peers are checked against it as well, so all the nodes of the network are
+    /// to price the same way.
+    pub fn pricing(&mut self, pricing: Arc<dyn PricingStrategy>) {
+        self.pricing = Some(pricing);
+    }
+
+    /// Set the transports the node listens and dials on, QUIC and TCP by default.
+    pub fn transport_mode(&mut self, transport_mode: TransportMode) {
+        self.transport_mode = transport_mode;
+    }
+
+    /// Set the node to listen on WebSocket too, for the clients running in browsers.
+    #[cfg(feature = "websockets")]
+    pub fn websocket(&mut self, websocket: WebSocketListener) {
+        self.websocket = Some(websocket);
+    }
+
+    /// Set the node to listen on WebRTC too, for the clients running in browsers to connect to it
+    /// without a certificate of a domain.
+    #[cfg(feature = "webrtc")]
+    pub fn webrtc(&mut self, webrtc: WebRtcListener) {
+        self.webrtc = Some(webrtc);
+    }
+
+    /// Set whether the node listens on both IPv4 and IPv6 when given an unspecified address
+    /// (`0.0.0.0` or `::`). Enabled by default.
+    pub fn dual_stack(&mut self, enable: bool) {
+        self.dual_stack = enable;
+    }
+
+    /// Set whether the node checks it's reachable by its peers (AutoNAT), turning to the relays
+    /// if not, as if it were behind a home network. Enabled by default.
+    pub fn nat_detection(&mut self, enable: bool) {
+        self.nat_detection = enable;
+    }
+
+    /// Set the limits the node relays the connections of the nodes behind a NAT within, `None`
+    /// not relaying them. A node behind a home network never relays.
+    pub fn relay_service(&mut self, limits: Option<RelayLimits>) {
+        self.relay_service = limits;
+    }
+
+    /// Set the limits of the messages published on the pub/sub topics, `None` not taking part in
+    /// pub/sub, i.e. neither relaying the messages of the others nor publishing.
+    pub fn pubsub(&mut self, limits: Option<PubSubLimits>) {
+        self.pubsub = limits;
+    }
+
+    /// Set the key of the private network the node is part of, for it to only connect to the
+    /// peers given the same key, in place of the one at the `SAFE_NETWORK_KEY_FILE` env var.
+    pub fn network_key(&mut self, network_key: NetworkKey) {
+        self.network_key = Some(network_key);
+    }
+
+    /// Set how the node discovers the nodes on its local network over mDNS, `None` not
+    /// discovering them. Defaults to discovering them on all the interfaces, but on a private
+    /// network, whose nodes aren't told apart from the others over mDNS.
+    #[cfg(feature = "local-discovery")]
+    pub fn local_discovery(&mut self, local_discovery: Option<LocalDiscovery>) {
+        self.local_discovery = Some(local_discovery);
+    }
+
+    /// Set the throttle the replication traffic goes through, in place of one of the node's own,
+    /// for several nodes to share the bandwidth.
+    pub(crate) fn replication_throttle(&mut self, throttle: ReplicationThrottle) {
+        self.replication_throttle = Some(throttle);
+    }
+
     #[cfg(feature = "open-metrics")]
     /// Set the port for the OpenMetrics server. Defaults to a random port if not set
     pub fn metrics_server_port(&mut self, port: Option<u16>) {
@@ -144,19 +342,21 @@ impl NodeBuilder {
     ///
     /// Returns an error if there is a problem initializing the `SwarmDriver`.
     pub fn build_and_run(self) -> Result<RunningNode> {
-        // Using the signature as the seed of generating the reward_key
-        let sig_vec = match self.keypair.sign(b"generate reward seed") {
-            Ok(sig) => sig,
-            Err(_err) => return Err(Error::FailedToGenerateRewardKey),
-        };
-        let mut rng = sn_transfers::rng::from_vec(&sig_vec);
-
-        let reward_key = MainSecretKey::random_from_rng(&mut rng);
-        let reward_address = reward_key.main_pubkey();
+        let resource_monitor =
+            ResourceMonitor::new(self.root_dir.clone(), self.resource_requirements);
+        let report = resource_monitor.report();
+        if !report.can_join() {
+            error!("The node lacks the resources to join the network: {report:?}");
+            return Err(Error::InsufficientResources(Box::new(report)));
+        }
+        info!("Resources available to the node: {report:?}");
 
+        let reward_key = reward_key_of(&self.keypair)?;
         let mut wallet = HotWallet::load_from_main_key(&self.root_dir, reward_key)?;
         // store in case it's a fresh wallet created if none was found
         wallet.deposit_and_store_to_disk(&vec![])?;
+        // the key found in the wallet, which the node keeps across the rotations of its own key
+        let reward_address = wallet.address();
 
         let mut network_builder = NetworkBuilder::new(self.keypair, self.local, self.root_dir);
 
@@ -190,12 +390,59 @@ impl NodeBuilder {
         network_builder.metrics_server_port(self.metrics_server_port);
         network_builder.initial_peers(self.initial_peers.clone());
         network_builder.is_behind_home_network(self.is_behind_home_network);
+        if let Some(backend) = self.record_store_backend {
+            network_builder.record_store_backend(backend);
+        }
+        if let Some(kinds) = self.compressed_record_kinds {
+            network_builder.compressed_record_kinds(kinds);
+        }
+        if let Some((max_disk_usage, pruning_policy)) = self.disk_quota {
+            network_builder.disk_quota(max_disk_usage, pruning_policy);
+        }
+        network_builder.replication_limits(self.replication_limits);
+        network_builder.kademlia_params(self.kademlia_params)?;
+        if let Some(path) = self.peer_access_file {
+            network_builder.peer_access_file(path);
+        }
+        network_builder.request_rate_limits(self.request_rate_limits);
+        network_builder.transport_mode(self.transport_mode);
+        network_builder.dual_stack(self.dual_stack);
+        network_builder.nat_detection(self.nat_detection);
+        network_builder.relay_service(self.relay_service);
+        network_builder.pubsub(self.pubsub);
+        if let Some(network_key) = self.network_key {
+            network_builder.network_key(network_key);
+        }
+        #[cfg(feature = "local-discovery")]
+        if let Some(local_discovery) = self.local_discovery {
+            network_builder.local_discovery(local_discovery);
+        }
+        #[cfg(feature = "websockets")]
+        if let Some(websocket) = self.websocket {
+            network_builder.websocket(websocket);
+        }
+        #[cfg(feature = "webrtc")]
+        if let Some(webrtc) = self.webrtc {
+            network_builder.webrtc(webrtc);
+        }
+        let pricing = self.pricing.unwrap_or_else(|| Arc::new(DefaultPricing));
+        network_builder.pricing(Arc::clone(&pricing));
+        let replication_throttle = self
+            .replication_throttle
+            .unwrap_or_else(|| ReplicationThrottle::new(self.replication_limits.bytes_per_sec));
+        #[cfg(feature = "open-metrics")]
+        let mut replication_throttle = replication_throttle;
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &node_metrics {
+            replication_throttle.set_traffic_metric(metrics.replication_traffic.clone());
+        }
 
         #[cfg(feature = "upnp")]
         network_builder.upnp(self.upnp);
 
         let (network, network_event_receiver, swarm_driver) = network_builder.build_node()?;
         let node_events_channel = NodeEventsChannel::default();
+        let earnings = EarningsTracker::load(network.root_dir_path());
 
         let node = NodeInner {
             network: network.clone(),
@@ -205,6 +452,11 @@ impl NodeBuilder {
             #[cfg(feature = "open-metrics")]
             node_metrics,
             owner: self.owner,
+            replication_throttle,
+            resource_monitor: resource_monitor.clone(),
+            reward_forwarding: self.reward_forwarding,
+            earnings: earnings.clone(),
+            pricing,
         };
         let node = Node {
             inner: Arc::new(node),
@@ -212,6 +464,8 @@ impl NodeBuilder {
         let running_node = RunningNode {
             network,
             node_events_channel,
+            resource_monitor,
+            earnings,
         };
 
         // Run the node
@@ -242,6 +496,11 @@ struct NodeInner {
     /// If not set, there will be no payment forward to be undertaken
     owner: Option<String>,
     reward_address: MainPubkey,
+    replication_throttle: ReplicationThrottle,
+    resource_monitor: ResourceMonitor,
+    reward_forwarding: Option<RewardForwarding>,
+    earnings: EarningsTracker,
+    pricing: Arc<dyn PricingStrategy>,
 }
 
 impl Node {
@@ -260,6 +519,26 @@ impl Node {
         &self.inner.network
     }
 
+    /// Returns the throttle of the records fetched and served for the replication
+    pub(crate) fn replication_throttle(&self) -> &ReplicationThrottle {
+        &self.inner.replication_throttle
+    }
+
+    /// Returns the monitor of the resources available to the node
+    pub(crate) fn resource_monitor(&self) -> &ResourceMonitor {
+        &self.inner.resource_monitor
+    }
+
+    /// Returns the tracker of the rewards earned by the node
+    pub(crate) fn earnings(&self) -> &EarningsTracker {
+        &self.inner.earnings
+    }
+
+    /// Returns how the storing of a record is priced
+    pub(crate) fn pricing(&self) -> &Arc<dyn PricingStrategy> {
+        &self.inner.pricing
+    }
+
     #[cfg(feature = "open-metrics")]
     /// Returns a reference to the NodeMetrics if the `open-metrics` feature flag is enabled
     pub(crate) fn node_metrics(&self) -> Option<&NodeMetricsRecorder> {
@@ -298,6 +577,11 @@ impl Node {
         });
 
         let _handle = spawn(swarm_driver.run());
+        let _handle = spawn(self.clone().scrub_records_periodically());
+        let _handle = spawn(self.resource_monitor().clone().monitor_periodically());
+        if let Some(forwarding) = self.inner.reward_forwarding {
+            let _handle = spawn(self.clone().forward_rewards_periodically(forwarding));
+        }
         let _handle = spawn(async move {
             // use a random inactivity timeout to ensure that the nodes do not sync when messages
             // are being transmitted.
@@ -323,6 +607,17 @@ impl Node {
 
             let mut rolling_index = 0;
 
+            // use a random timeout to ensure not sync when transmit messages.
+            let storage_challenge_interval: u64 = rng.gen_range(
+                PERIODIC_STORAGE_CHALLENGE_INTERVAL_MAX_S / 2
+                    ..PERIODIC_STORAGE_CHALLENGE_INTERVAL_MAX_S,
+            );
+            let storage_challenge_time = Duration::from_secs(storage_challenge_interval);
+            debug!("StorageChallenge interval set to {storage_challenge_time:?}");
+
+            let mut storage_challenge_interval = tokio::time::interval(storage_challenge_time);
+            let _ = storage_challenge_interval.tick().await; // first tick completes immediately
+
             // use a random timeout to ensure not sync when transmit messages.
             let balance_forward_interval: u64 = rng.gen_range(
                 PERIODIC_REWARD_FORWARD_INTERVAL_MAX_S / 2..PERIODIC_REWARD_FORWARD_INTERVAL_MAX_S,
@@ -395,6 +690,17 @@ impl Node {
                             rolling_index += 1;
                         }
                     }
+                    // runs every storage_challenge_time time
+                    _ = storage_challenge_interval.tick() => {
+                        let start = Instant::now();
+                        debug!("Periodic storage challenge triggered");
+                        let network = self.network().clone();
+
+                        let _handle = spawn(async move {
+                            Self::try_storage_challenge(network).await;
+                            trace!("Periodic storage challenge took {:?}", start.elapsed());
+                        });
+                    }
                     // runs every balance_forward_interval time
                     _ = balance_forward_interval.tick() => {
                         if cfg!(feature = "reward-forward") {
@@ -520,6 +826,13 @@ impl Node {
             }
             NetworkEvent::KeysToFetchForReplication(keys) => {
                 event_header = "KeysToFetchForReplication";
+                if !self.resource_monitor().accepts_new_records() {
+                    warn!(
+                        "Not fetching {} keys for replication, as the node's resources fall short",
+                        keys.len()
+                    );
+                    return;
+                }
                 debug!("Going to fetch {:?} keys for replication", keys.len());
                 self.record_metrics(Marker::fetching_keys_for_replication(&keys));
 
@@ -527,31 +840,66 @@ impl Node {
                     error!("Error while trying to fetch replicated data {err:?}");
                 }
             }
-            NetworkEvent::QueryRequestReceived { query, channel } => {
+            NetworkEvent::QueryRequestReceived {
+                msg_id,
+                query,
+                channel,
+            } => {
                 event_header = "QueryRequestReceived";
                 let network = self.network().clone();
                 let payment_address = *self.reward_address();
-
-                let _handle = spawn(async move {
-                    let res = Self::handle_query(&network, query, payment_address).await;
-                    debug!("Sending response {res:?}");
-
-                    network.send_response(res, channel);
-                });
+                let events_channel = self.events_channel().clone();
+                let replication_throttle = self.replication_throttle().clone();
+                let accepts_new_records = self.resource_monitor().accepts_new_records();
+                // the span is correlated by the msg id with the one of the request on its sender
+                let span = info_span!("handle_query", %msg_id);
+
+                let _handle = spawn(
+                    async move {
+                        let res = Self::handle_query(
+                            &network,
+                            query,
+                            payment_address,
+                            events_channel,
+                            replication_throttle,
+                            accepts_new_records,
+                        )
+                        .await;
+                        debug!("Sending response {res:?}");
+
+                        network.send_response(res, channel);
+                    }
+                    .instrument(span),
+                );
             }
             NetworkEvent::UnverifiedRecord(record) => {
                 event_header = "UnverifiedRecord";
                 // queries can be long running and require validation, so we spawn a task to handle them
                 let self_clone = self.clone();
-                let _handle = spawn(async move {
-                    let key = PrettyPrintRecordKey::from(&record.key).into_owned();
-                    match self_clone.validate_and_store_record(record).await {
-                        Ok(()) => debug!("UnverifiedRecord {key} has been stored"),
-                        Err(err) => {
-                            self_clone.record_metrics(Marker::RecordRejected(&key, &err));
+                let key = PrettyPrintRecordKey::from(&record.key).into_owned();
+                // the span is correlated by the key with the one of the store on the swarm driver
+                let span = info_span!("validate_and_store_record", key = %key);
+                let _handle = spawn(
+                    async move {
+                        #[cfg(feature = "open-metrics")]
+                        let start = Instant::now();
+                        match self_clone.validate_and_store_record(record).await {
+                            Ok(()) => {
+                                debug!("UnverifiedRecord {key} has been stored");
+                                #[cfg(feature = "open-metrics")]
+                                if let Some(metrics) = self_clone.node_metrics() {
+                                    metrics
+                                        .put_record_latency
+                                        .observe(start.elapsed().as_secs_f64());
+                                }
+                            }
+                            Err(err) => {
+                                self_clone.record_metrics(Marker::RecordRejected(&key, &err));
+                            }
                         }
                     }
-                });
+                    .instrument(span),
+                );
             }
 
             NetworkEvent::TerminateNode { reason } => {
@@ -576,9 +924,10 @@ impl Node {
             NetworkEvent::QuoteVerification { quotes } => {
                 event_header = "QuoteVerification";
                 let network = self.network().clone();
+                let pricing = Arc::clone(self.pricing());
 
                 let _handle = spawn(async move {
-                    quotes_verification(&network, quotes).await;
+                    quotes_verification(&network, pricing.as_ref(), quotes).await;
                 });
             }
             NetworkEvent::ChunkProofVerification {
@@ -613,6 +962,11 @@ impl Node {
                     network.record_node_issues(peer_id, NodeIssue::FailedChunkProofCheck);
                 });
             }
+            NetworkEvent::PubSubMessage { topic, msg } => {
+                event_header = "PubSubMessage";
+                self.events_channel()
+                    .broadcast(NodeEvent::PubSubMessage { topic, msg });
+            }
         }
 
         trace!(
@@ -695,8 +1049,19 @@ impl Node {
         network: &Network,
         query: Query,
         payment_address: MainPubkey,
+        events_channel: NodeEventsChannel,
+        replication_throttle: ReplicationThrottle,
+        accepts_new_records: bool,
     ) -> Response {
         let resp: QueryResponse = match query {
+            Query::GetStoreCost(address) if !accepts_new_records => {
+                debug!("Refusing GetStoreCost request for {address:?}, as the node's resources fall short");
+                QueryResponse::GetStoreCost {
+                    quote: Err(ProtocolError::InsufficientResources),
+                    payment_address,
+                    peer_address: NetworkAddress::from_peer(network.peer_id()),
+                }
+            }
             Query::GetStoreCost(address) => {
                 debug!("Got GetStoreCost request for {address:?}");
                 let record_key = address.to_record_key();
@@ -746,6 +1111,9 @@ impl Node {
 
                 if let Some(record_key) = record_key {
                     if let Ok(Some(record)) = network.get_local_record(&record_key).await {
+                        replication_throttle
+                            .acquire(ReplicationDirection::Served, record.value.len())
+                            .await;
                         result = Ok((our_address, Bytes::from(record.value)));
                     }
                 }
@@ -768,6 +1136,22 @@ impl Node {
 
                 QueryResponse::GetChunkExistenceProof(result)
             }
+            Query::GetStorageProof { key, challenge } => {
+                debug!("Got GetStorageProof for chunk {key:?}");
+
+                let mut result = Err(ProtocolError::ChunkDoesNotExist(key.clone()));
+                if let Ok(Some(record)) = network.get_local_record(&key.to_record_key()).await {
+                    let proof = challenge.prove(&record.value);
+                    debug!("Storage proof for {key:?} under {challenge:?} is {proof:?}");
+                    result = Ok(proof)
+                } else {
+                    debug!(
+                        "Could not get StorageProof for {key:?} as we don't have the record locally."
+                    );
+                }
+
+                QueryResponse::GetStorageProof(result)
+            }
             Query::CheckNodeInProblem(target_address) => {
                 debug!("Got CheckNodeInProblem for peer {target_address:?}");
 
@@ -785,10 +1169,70 @@ impl Node {
                     is_in_trouble,
                 }
             }
+            Query::WatchRegister { key, tips } => {
+                debug!("Got WatchRegister for {key:?}");
+                QueryResponse::WatchRegister(
+                    Self::watch_register(network, &events_channel, key, tips).await,
+                )
+            }
         };
         Response::Query(resp)
     }
 
+    // Hold a Register watch till our replica has other tips than the watcher's, or the watch
+    // expires, returning our replica as serialised record.
+    async fn watch_register(
+        network: &Network,
+        events_channel: &NodeEventsChannel,
+        key: NetworkAddress,
+        tips: BTreeSet<EntryHash>,
+    ) -> std::result::Result<(NetworkAddress, Bytes), ProtocolError> {
+        let our_address = NetworkAddress::from_peer(network.peer_id());
+        let record_key = key.to_record_key();
+        // listen to the edits before reading our replica, so that none is missed in between
+        let mut events = events_channel.subscribe();
+        let expiry = tokio::time::sleep(REGISTER_WATCH_TIMEOUT);
+        tokio::pin!(expiry);
+
+        loop {
+            let Ok(Some(record)) = network.get_local_record(&record_key).await else {
+                return Err(ProtocolError::ReplicatedRecordNotFound {
+                    holder: Box::new(our_address),
+                    key: Box::new(key),
+                });
+            };
+            let replica_tips: BTreeSet<EntryHash> =
+                try_deserialize_record::<SignedRegister>(&record)?
+                    .register()
+                    .map_err(|_| ProtocolError::RecordParsingFailed)?
+                    .read()
+                    .into_iter()
+                    .map(|(hash, _)| hash)
+                    .collect();
+            if replica_tips != tips {
+                return Ok((our_address, Bytes::from(record.value)));
+            }
+
+            let edited = loop {
+                tokio::select! {
+                    _ = &mut expiry => break false,
+                    event = events.recv() => match event {
+                        Ok(NodeEvent::RegisterCreated(address) | NodeEvent::RegisterEdited(address))
+                            if NetworkAddress::from_register_address(address) == key => break true,
+                        // some events were missed, our replica may have been edited meanwhile
+                        Err(RecvError::Lagged(_)) => break true,
+                        Err(RecvError::Closed) => break false,
+                        Ok(_) => {}
+                    }
+                }
+            };
+            if !edited {
+                debug!("Register watch for {key:?} expired");
+                return Ok((our_address, Bytes::from(record.value)));
+            }
+        }
+    }
+
     async fn try_bad_nodes_check(network: Network, rolling_index: usize) {
         if let Ok(kbuckets) = network.get_kbuckets().await {
             let total_peers: usize = kbuckets.values().map(|peers| peers.len()).sum();
@@ -845,6 +1289,55 @@ impl Node {
         }
     }
 
+    // Challenges a random peer among the close group of a random chunk we hold to prove it holds
+    // the chunk as well, so that the peers discarding the records they are paid to keep get
+    // caught out.
+    async fn try_storage_challenge(network: Network) {
+        let chunks: Vec<_> = match network.get_all_local_record_addresses().await {
+            Ok(addresses) => addresses
+                .into_iter()
+                .filter(|(_, record_type)| *record_type == RecordType::Chunk)
+                .map(|(address, _)| address)
+                .collect(),
+            Err(err) => {
+                error!(
+                    "Failed to get the local record addresses for the storage challenge: {err:?}"
+                );
+                return;
+            }
+        };
+        let Some(key) = chunks.choose(&mut thread_rng()).cloned() else {
+            debug!("Skip the storage challenge as not holding any chunk");
+            return;
+        };
+        let peer_id = match network.get_close_group_local_peers(&key).await {
+            Ok(peers) => match peers.choose(&mut thread_rng()) {
+                Some(peer_id) => *peer_id,
+                None => {
+                    debug!("Skip the storage challenge as not knowing any peer close to {key:?}");
+                    return;
+                }
+            },
+            Err(err) => {
+                error!(
+                    "Failed to get the close group of {key:?} for the storage challenge: {err:?}"
+                );
+                return;
+            }
+        };
+
+        // The peer may only just have become responsible for the chunk, hence the challenge is
+        // repeated, giving the replication some time to reach it.
+        for _ in 0..MAX_CHUNK_PROOF_VERIFY_ATTEMPTS {
+            if storage_challenge_peer(&network, peer_id, &key).await {
+                return;
+            }
+            tokio::time::sleep(CHUNK_PROOF_VERIFY_RETRY_INTERVAL).await;
+        }
+        warn!("Peer {peer_id:?} failed the storage challenges for {key:?}");
+        network.record_node_issues(peer_id, NodeIssue::FailedStorageChallenge);
+    }
+
     /// Forward received rewards to another address
     fn try_forward_balance(
         network: Network,
@@ -1013,6 +1506,38 @@ async fn chunk_proof_verify_peer(
     true
 }
 
+// Returns false only when the peer failed to prove it holds the record, not when we don't hold
+// it ourselves anymore.
+async fn storage_challenge_peer(network: &Network, peer_id: PeerId, key: &NetworkAddress) -> bool {
+    let Ok(Some(record)) = network.get_local_record(&key.to_record_key()).await else {
+        debug!("Skip the storage challenge for {key:?} as we don't have the record locally.");
+        return true;
+    };
+    let challenge = StorageChallenge::new(thread_rng().gen::<u64>(), record.value.len());
+    let expected_proof = challenge.prove(&record.value);
+    debug!("To challenge peer {peer_id:?}, storage proof for {key:?} under {challenge:?} is {expected_proof:?}");
+
+    let request = Request::Query(Query::GetStorageProof {
+        key: key.clone(),
+        challenge,
+    });
+    match network.send_request(request, peer_id).await {
+        Ok(Response::Query(QueryResponse::GetStorageProof(Ok(proof)))) => {
+            if expected_proof.verify(&proof) {
+                debug!("Got a valid StorageProof of {key:?} from {peer_id:?}");
+                true
+            } else {
+                warn!("Got an invalid StorageProof of {key:?} from {peer_id:?}, the chunk might have been tampered?");
+                false
+            }
+        }
+        resp => {
+            debug!("Did not get a valid response for the StorageProof of {key:?} from {peer_id:?}: {resp:?}");
+            false
+        }
+    }
+}
+
 fn received_valid_chunk_proof(
     key: &NetworkAddress,
     expected_proof: &ChunkProof,
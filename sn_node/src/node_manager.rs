@@ -0,0 +1,220 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{replication_throttle::ReplicationThrottle, NodeBuilder, Result, RunningNode};
+use libp2p::{Multiaddr, PeerId};
+use sn_networking::{DiskUsage, ReplicationLimits};
+use sn_transfers::NanoTokens;
+use std::{path::PathBuf, time::Duration};
+
+/// Runs several nodes in the one process, for an operator running many nodes on a machine to
+/// not pay for a process per node.
+///
+/// The nodes share the runtime they're started on, its worker threads and timers, and the
+/// replication bandwidth. Each of them keeps its own identity, root dir and port, and so its own
+/// transports: a libp2p swarm owns its sockets and handshakes, which can't be shared with another.
+///
+/// `safenode --nodes <N>` runs the nodes with it.
+pub struct NodeManager {
+    nodes: Vec<RunningNode>,
+}
+
+/// The status of a node run by a `NodeManager`.
+#[derive(Clone, Debug)]
+pub struct NodeStatus {
+    /// The identity of the node.
+    pub peer_id: PeerId,
+    /// Where the node keeps its records and wallet.
+    pub root_dir: PathBuf,
+    /// Whether the node still responds, the fields below being empty if it doesn't.
+    pub is_running: bool,
+    /// The addresses the node listens on.
+    pub listeners: Vec<Multiaddr>,
+    /// The number of peers the node is connected to.
+    pub connected_peers: usize,
+    /// The records held by the node.
+    pub disk_usage: DiskUsage,
+    /// The rewards the node got paid.
+    pub reward_balance: NanoTokens,
+}
+
+/// The status of all the nodes run by a `NodeManager`, added up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodesSummary {
+    /// The number of nodes run.
+    pub nodes: usize,
+    /// The number of nodes still responding.
+    pub running_nodes: usize,
+    /// The records held by the nodes.
+    pub records: usize,
+    /// The space taken by the records held by the nodes, in bytes.
+    pub used_bytes: u64,
+    /// The rewards the nodes got paid.
+    pub reward_balance: NanoTokens,
+}
+
+impl NodeManager {
+    /// Runs a node for each of the builders, on the current runtime.
+    ///
+    /// The `replication_limits` are for all the nodes together, the bandwidth being shared by
+    /// them and each of them fetching as many records at the same time.
+    pub fn run(builders: Vec<NodeBuilder>, replication_limits: ReplicationLimits) -> Result<Self> {
+        let shared_throttle = ReplicationThrottle::new(replication_limits.bytes_per_sec);
+        let node_limits = ReplicationLimits {
+            // for each node to time its fetches out for its share of the bandwidth
+            bytes_per_sec: replication_limits
+                .bytes_per_sec
+                .map(|limit| (limit / builders.len().max(1) as u64).max(1)),
+            ..replication_limits
+        };
+
+        let mut nodes = Vec::with_capacity(builders.len());
+        for mut builder in builders {
+            builder.replication_limits(node_limits);
+            builder.replication_throttle(shared_throttle.clone());
+            let node = builder.build_and_run()?;
+            info!("Started node {} of the node manager", node.peer_id());
+            nodes.push(node);
+        }
+        Ok(Self { nodes })
+    }
+
+    /// Returns the nodes run.
+    pub fn nodes(&self) -> &[RunningNode] {
+        &self.nodes
+    }
+
+    /// Returns the node with the `PeerId`, if it's run by the manager.
+    pub fn node(&self, peer_id: &PeerId) -> Option<&RunningNode> {
+        self.nodes.iter().find(|node| node.peer_id() == *peer_id)
+    }
+
+    /// Returns the status of each of the nodes.
+    pub async fn status(&self) -> Vec<NodeStatus> {
+        futures::future::join_all(self.nodes.iter().map(node_status)).await
+    }
+
+    /// Returns the status of all the nodes, added up.
+    pub async fn summary(&self) -> NodesSummary {
+        NodesSummary::from(&self.status().await[..])
+    }
+
+    /// Hands the records of all the nodes over to their closest peers, for them to leave the
+    /// network together without their records being lost.
+    pub async fn leave_network(&self, handover_period: Duration) {
+        let results = futures::future::join_all(
+            self.nodes
+                .iter()
+                .map(|node| node.leave_network(handover_period)),
+        )
+        .await;
+        for (node, result) in self.nodes.iter().zip(results) {
+            if let Err(err) = result {
+                warn!(
+                    "Node {} failed to hand its records over before leaving: {err:?}",
+                    node.peer_id()
+                );
+            }
+        }
+    }
+}
+
+async fn node_status(node: &RunningNode) -> NodeStatus {
+    let peer_id = node.peer_id();
+    let reward_balance = node.get_node_wallet_balance().unwrap_or_else(|err| {
+        warn!("Failed to get the reward balance of node {peer_id}: {err:?}");
+        NanoTokens::zero()
+    });
+    let mut status = NodeStatus {
+        peer_id,
+        root_dir: node.root_dir_path(),
+        is_running: false,
+        listeners: vec![],
+        connected_peers: 0,
+        disk_usage: DiskUsage::default(),
+        reward_balance,
+    };
+
+    match (
+        node.get_swarm_local_state().await,
+        node.get_disk_usage().await,
+    ) {
+        (Ok(state), Ok(disk_usage)) => {
+            status.is_running = true;
+            status.listeners = state.listeners;
+            status.connected_peers = state.connected_peers.len();
+            status.disk_usage = disk_usage;
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            warn!("Node {peer_id} does not respond: {err:?}");
+        }
+    }
+    status
+}
+
+impl From<&[NodeStatus]> for NodesSummary {
+    fn from(statuses: &[NodeStatus]) -> Self {
+        let empty = NodesSummary {
+            nodes: 0,
+            running_nodes: 0,
+            records: 0,
+            used_bytes: 0,
+            reward_balance: NanoTokens::zero(),
+        };
+        statuses.iter().fold(empty, |mut summary, status| {
+            summary.nodes += 1;
+            summary.running_nodes += usize::from(status.is_running);
+            summary.records = summary.records.saturating_add(status.disk_usage.records);
+            summary.used_bytes = summary
+                .used_bytes
+                .saturating_add(status.disk_usage.used_bytes);
+            summary.reward_balance = summary.reward_balance.saturating_add(status.reward_balance);
+            summary
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_status_of_the_nodes_is_added_up() {
+        let status = |is_running, records, used_bytes, reward| NodeStatus {
+            peer_id: PeerId::random(),
+            root_dir: PathBuf::new(),
+            is_running,
+            listeners: vec![],
+            connected_peers: 0,
+            disk_usage: DiskUsage {
+                records,
+                used_bytes,
+                max_bytes: None,
+            },
+            reward_balance: NanoTokens::from(reward),
+        };
+        let statuses = [status(true, 3, 300, 10), status(false, 0, 0, 5)];
+
+        assert_eq!(
+            NodesSummary::from(&statuses[..]),
+            NodesSummary {
+                nodes: 2,
+                running_nodes: 1,
+                records: 3,
+                used_bytes: 300,
+                reward_balance: NanoTokens::from(15),
+            }
+        );
+
+        let rich = [status(true, 0, 0, u64::MAX), status(true, 0, 0, 1)];
+        assert_eq!(
+            NodesSummary::from(&rich[..]).reward_balance,
+            NanoTokens::from(u64::MAX)
+        );
+    }
+}
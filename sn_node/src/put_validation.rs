@@ -7,21 +7,28 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{node::Node, quote::verify_quote_for_storecost, Error, Marker, Result};
-use libp2p::kad::{Record, RecordKey};
+use libp2p::{
+    kad::{Record, RecordKey},
+    PeerId,
+};
 use sn_networking::{get_raw_signed_spends_from_record, GetRecordError, NetworkError};
 use sn_protocol::{
     storage::{
-        try_deserialize_record, try_serialize_record, Chunk, RecordHeader, RecordKind, RecordType,
-        SpendAddress,
+        try_deserialize_record, try_serialize_record, Chunk, ExpiringData, Pointer, RecordHeader,
+        RecordKind, RecordType, Scratchpad, SpendAddress,
     },
     NetworkAddress, PrettyPrintRecordKey,
 };
 use sn_registers::SignedRegister;
 use sn_transfers::{
-    calculate_royalties_fee, CashNote, CashNoteRedemption, HotWallet, NanoTokens, Payment,
-    SignedSpend, Transfer, TransferError, UniquePubkey, WalletError, NETWORK_ROYALTIES_PK,
+    calculate_royalties_fee, CashNote, CashNoteRedemption, HotWallet, MainPubkey, NanoTokens,
+    Payment, SignedSpend, Transfer, TransferError, UniquePubkey, WalletError,
+    NETWORK_ROYALTIES_SPLIT,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::{SystemTime, UNIX_EPOCH},
 };
-use std::collections::BTreeSet;
 use tokio::task::JoinSet;
 use xor_name::XorName;
 
@@ -29,6 +36,7 @@ impl Node {
     /// Validate a record and it's payment, and store the record to the RecordStore
     pub(crate) async fn validate_and_store_record(&self, record: Record) -> Result<()> {
         let record_header = RecordHeader::from_record(&record)?;
+        let payer = record.publisher;
 
         match record_header.kind {
             RecordKind::ChunkWithPayment => {
@@ -41,7 +49,12 @@ impl Node {
                 // Validate the payment and that we received what we asked.
                 // This stores any payments to disk
                 let payment_res = self
-                    .payment_for_us_exists_and_is_still_valid(&chunk.network_address(), payment)
+                    .payment_for_us_exists_and_is_still_valid(
+                        &chunk.network_address(),
+                        payment,
+                        record_header.kind,
+                        payer,
+                    )
                     .await;
 
                 // Now that we've taken any money passed to us, regardless of the payment's validity,
@@ -179,7 +192,12 @@ impl Node {
                 // However, if the register already presents, the incoming one maybe for edit only.
                 // Hence the corresponding payment error shall not be thrown out.
                 if let Err(err) = self
-                    .payment_for_us_exists_and_is_still_valid(&net_addr, payment)
+                    .payment_for_us_exists_and_is_still_valid(
+                        &net_addr,
+                        payment,
+                        record_header.kind,
+                        payer,
+                    )
                     .await
                 {
                     if already_exists {
@@ -204,6 +222,215 @@ impl Node {
                 }
                 res
             }
+            RecordKind::Pointer => {
+                let pointer = try_deserialize_record::<Pointer>(&record)?;
+
+                // make sure we already have this pointer locally
+                let net_addr = pointer.network_address();
+                let key = net_addr.to_record_key();
+                let pretty_key = PrettyPrintRecordKey::from(&key);
+                debug!("Got record to store without payment for pointer at {pretty_key:?}");
+                if !self
+                    .validate_key_and_existence(&net_addr, &record.key)
+                    .await?
+                {
+                    debug!("Ignore store without payment for pointer at {pretty_key:?}");
+                    return Err(Error::InvalidPutWithoutPayment(
+                        PrettyPrintRecordKey::from(&record.key).into_owned(),
+                    ));
+                }
+
+                let result = self.validate_and_store_pointer(pointer, true).await;
+                if result.is_ok() {
+                    debug!("Successfully stored pointer update at {pretty_key:?}");
+                    let content_hash = XorName::from_content(&record.value);
+
+                    // Notify replication_fetcher to mark the attempt as completed.
+                    // Send the notification earlier to avoid it got skipped due to:
+                    // the record becomes stored during the fetch because of other interleaved process.
+                    self.network().notify_fetch_completed(
+                        record.key.clone(),
+                        RecordType::NonChunk(content_hash),
+                    );
+                } else {
+                    warn!("Failed to store pointer update at {pretty_key:?}");
+                }
+                result
+            }
+            RecordKind::PointerWithPayment => {
+                let (payment, pointer) = try_deserialize_record::<(Payment, Pointer)>(&record)?;
+
+                let net_addr = pointer.network_address();
+                let pretty_key = PrettyPrintRecordKey::from(&record.key).into_owned();
+                let already_exists = self
+                    .validate_key_and_existence(&net_addr, &record.key)
+                    .await?;
+
+                // As for registers, the payment is taken even if the pointer already exists,
+                // in which case the incoming one may just repoint it.
+                if let Err(err) = self
+                    .payment_for_us_exists_and_is_still_valid(
+                        &net_addr,
+                        payment,
+                        record_header.kind,
+                        payer,
+                    )
+                    .await
+                {
+                    if already_exists {
+                        debug!("Payment of the incoming exists pointer {pretty_key:?} having error {err:?}");
+                    } else {
+                        error!("Payment of the incoming non-exist pointer {pretty_key:?} having error {err:?}");
+                        return Err(err);
+                    }
+                }
+
+                let res = self.validate_and_store_pointer(pointer, true).await;
+                if res.is_ok() {
+                    Marker::ValidPaidPointerPutFromClient(&pretty_key).log();
+                    let content_hash = XorName::from_content(&record.value);
+
+                    // Notify replication_fetcher to mark the attempt as completed.
+                    // Send the notification earlier to avoid it got skipped due to:
+                    // the record becomes stored during the fetch because of other interleaved process.
+                    self.network().notify_fetch_completed(
+                        record.key.clone(),
+                        RecordType::NonChunk(content_hash),
+                    );
+                }
+                res
+            }
+            RecordKind::Scratchpad => {
+                let scratchpad = try_deserialize_record::<Scratchpad>(&record)?;
+
+                // make sure we already have this scratchpad locally
+                let net_addr = scratchpad.network_address();
+                let key = net_addr.to_record_key();
+                let pretty_key = PrettyPrintRecordKey::from(&key);
+                debug!("Got record to store without payment for scratchpad at {pretty_key:?}");
+                if !self
+                    .validate_key_and_existence(&net_addr, &record.key)
+                    .await?
+                {
+                    debug!("Ignore store without payment for scratchpad at {pretty_key:?}");
+                    return Err(Error::InvalidPutWithoutPayment(
+                        PrettyPrintRecordKey::from(&record.key).into_owned(),
+                    ));
+                }
+
+                let result = self.validate_and_store_scratchpad(scratchpad, true).await;
+                if result.is_ok() {
+                    debug!("Successfully stored scratchpad update at {pretty_key:?}");
+                    let content_hash = XorName::from_content(&record.value);
+
+                    // Notify replication_fetcher to mark the attempt as completed.
+                    // Send the notification earlier to avoid it got skipped due to:
+                    // the record becomes stored during the fetch because of other interleaved process.
+                    self.network().notify_fetch_completed(
+                        record.key.clone(),
+                        RecordType::NonChunk(content_hash),
+                    );
+                } else {
+                    warn!("Failed to store scratchpad update at {pretty_key:?}");
+                }
+                result
+            }
+            RecordKind::ScratchpadWithPayment => {
+                let (payment, scratchpad) =
+                    try_deserialize_record::<(Payment, Scratchpad)>(&record)?;
+
+                let net_addr = scratchpad.network_address();
+                let pretty_key = PrettyPrintRecordKey::from(&record.key).into_owned();
+                let already_exists = self
+                    .validate_key_and_existence(&net_addr, &record.key)
+                    .await?;
+
+                // As for registers, the payment is taken even if the scratchpad already exists,
+                // in which case the incoming one may just overwrite it.
+                if let Err(err) = self
+                    .payment_for_us_exists_and_is_still_valid(
+                        &net_addr,
+                        payment,
+                        record_header.kind,
+                        payer,
+                    )
+                    .await
+                {
+                    if already_exists {
+                        debug!("Payment of the incoming exists scratchpad {pretty_key:?} having error {err:?}");
+                    } else {
+                        error!("Payment of the incoming non-exist scratchpad {pretty_key:?} having error {err:?}");
+                        return Err(err);
+                    }
+                }
+
+                let res = self.validate_and_store_scratchpad(scratchpad, true).await;
+                if res.is_ok() {
+                    Marker::ValidPaidScratchpadPutFromClient(&pretty_key).log();
+                    let content_hash = XorName::from_content(&record.value);
+
+                    // Notify replication_fetcher to mark the attempt as completed.
+                    // Send the notification earlier to avoid it got skipped due to:
+                    // the record becomes stored during the fetch because of other interleaved process.
+                    self.network().notify_fetch_completed(
+                        record.key.clone(),
+                        RecordType::NonChunk(content_hash),
+                    );
+                }
+                res
+            }
+            RecordKind::ExpiringDataWithPayment => {
+                let (payment, expiring_data) =
+                    try_deserialize_record::<(Payment, ExpiringData)>(&record)?;
+
+                let net_addr = expiring_data.network_address();
+                let pretty_key = PrettyPrintRecordKey::from(&record.key).into_owned();
+                let already_exists = self
+                    .validate_key_and_existence(&net_addr, &record.key)
+                    .await?;
+
+                // As for chunks, the payment is taken even if the data already exists.
+                let payment_res = self
+                    .payment_for_us_exists_and_is_still_valid(
+                        &net_addr,
+                        payment,
+                        record_header.kind,
+                        payer,
+                    )
+                    .await;
+
+                if already_exists {
+                    debug!("ExpiringData {pretty_key:?} already exists, payment extracted.");
+                    return Ok(());
+                }
+
+                payment_res?;
+
+                let res = self.validate_and_store_expiring_data(&expiring_data);
+                if res.is_ok() {
+                    Marker::ValidPaidExpiringDataPutFromClient(&pretty_key).log();
+                    let content_hash = XorName::from_content(&record.value);
+                    self.replicate_valid_fresh_record(
+                        record.key.clone(),
+                        RecordType::NonChunk(content_hash),
+                    );
+
+                    // Notify replication_fetcher to mark the attempt as completed.
+                    // Send the notification earlier to avoid it got skipped due to:
+                    // the record becomes stored during the fetch because of other interleaved process.
+                    self.network().notify_fetch_completed(
+                        record.key.clone(),
+                        RecordType::NonChunk(content_hash),
+                    );
+                }
+                res
+            }
+            RecordKind::ExpiringData => {
+                error!("ExpiringData should not be validated at this point");
+                Err(Error::InvalidPutWithoutPayment(
+                    PrettyPrintRecordKey::from(&record.key).into_owned(),
+                ))
+            }
         }
     }
 
@@ -212,8 +439,13 @@ impl Node {
         debug!("Storing record which was replicated to us {:?}", record.key);
         let record_header = RecordHeader::from_record(&record)?;
         match record_header.kind {
-            // A separate flow handles payment for chunks and registers
-            RecordKind::ChunkWithPayment | RecordKind::RegisterWithPayment => {
+            // A separate flow handles payment for chunks, registers, pointers, scratchpads and
+            // expiring data
+            RecordKind::ChunkWithPayment
+            | RecordKind::RegisterWithPayment
+            | RecordKind::PointerWithPayment
+            | RecordKind::ScratchpadWithPayment
+            | RecordKind::ExpiringDataWithPayment => {
                 warn!("Prepaid record came with Payment, which should be handled in another flow");
                 Err(Error::UnexpectedRecordWithPayment(
                     PrettyPrintRecordKey::from(&record.key).into_owned(),
@@ -256,6 +488,46 @@ impl Node {
                 }
                 self.validate_and_store_register(register, false).await
             }
+            RecordKind::Pointer => {
+                let pointer = try_deserialize_record::<Pointer>(&record)?;
+
+                // check if the deserialized value's PointerAddress matches the record's key
+                if record.key != pointer.network_address().to_record_key() {
+                    warn!(
+                        "Record's key does not match with the value's PointerAddress, ignoring PUT."
+                    );
+                    return Err(Error::RecordKeyMismatch);
+                }
+                self.validate_and_store_pointer(pointer, false).await
+            }
+            RecordKind::Scratchpad => {
+                let scratchpad = try_deserialize_record::<Scratchpad>(&record)?;
+
+                // check if the deserialized value's ScratchpadAddress matches the record's key
+                if record.key != scratchpad.network_address().to_record_key() {
+                    warn!(
+                        "Record's key does not match with the value's ScratchpadAddress, ignoring PUT."
+                    );
+                    return Err(Error::RecordKeyMismatch);
+                }
+                self.validate_and_store_scratchpad(scratchpad, false).await
+            }
+            RecordKind::ExpiringData => {
+                let expiring_data = try_deserialize_record::<ExpiringData>(&record)?;
+
+                let already_exists = self
+                    .validate_key_and_existence(&expiring_data.network_address(), &record.key)
+                    .await?;
+                if already_exists {
+                    debug!(
+                        "ExpiringData with addr {:?} already exists, do nothing",
+                        expiring_data.network_address()
+                    );
+                    return Ok(());
+                }
+
+                self.validate_and_store_expiring_data(&expiring_data)
+            }
         }
     }
 
@@ -363,6 +635,97 @@ impl Node {
 
         self.record_metrics(Marker::ValidRegisterRecordPutFromNetwork(&pretty_key));
 
+        let event = if present_locally {
+            crate::NodeEvent::RegisterEdited(*reg_addr)
+        } else {
+            crate::NodeEvent::RegisterCreated(*reg_addr)
+        };
+        self.events_channel().broadcast(event);
+
+        if with_payment {
+            self.replicate_valid_fresh_record(key, RecordType::NonChunk(content_hash));
+        }
+
+        Ok(())
+    }
+
+    /// Validate and store a `Pointer` to the RecordStore, unless the local version supersedes it.
+    pub(crate) async fn validate_and_store_pointer(
+        &self,
+        pointer: Pointer,
+        with_payment: bool,
+    ) -> Result<()> {
+        let addr = *pointer.address();
+        debug!("Validating and storing pointer {addr:?}");
+        pointer.verify()?;
+
+        let key = pointer.network_address().to_record_key();
+        let pretty_key = PrettyPrintRecordKey::from(&key).into_owned();
+
+        // only keep the newest version of the pointer
+        if let Some(record) = self.network().get_local_record(&key).await? {
+            let local_pointer: Pointer = try_deserialize_record(&record)?;
+            if !pointer.supersedes(&local_pointer) {
+                debug!("Pointer {pretty_key:?} is not newer than the local version");
+                return Ok(());
+            }
+        }
+
+        let record = Record {
+            key: key.clone(),
+            value: try_serialize_record(&pointer, RecordKind::Pointer)?.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        let content_hash = XorName::from_content(&record.value);
+
+        info!("Storing pointer {addr:?} with content of {content_hash:?} as Record locally");
+        self.network().put_local_record(record);
+
+        self.record_metrics(Marker::ValidPointerRecordPutFromNetwork(&pretty_key));
+
+        if with_payment {
+            self.replicate_valid_fresh_record(key, RecordType::NonChunk(content_hash));
+        }
+
+        Ok(())
+    }
+
+    /// Validate and store a `Scratchpad` to the RecordStore, unless the local version supersedes it.
+    pub(crate) async fn validate_and_store_scratchpad(
+        &self,
+        scratchpad: Scratchpad,
+        with_payment: bool,
+    ) -> Result<()> {
+        let addr = *scratchpad.address();
+        debug!("Validating and storing scratchpad {addr:?}");
+        scratchpad.verify()?;
+
+        let key = scratchpad.network_address().to_record_key();
+        let pretty_key = PrettyPrintRecordKey::from(&key).into_owned();
+
+        // only keep the newest version of the scratchpad
+        if let Some(record) = self.network().get_local_record(&key).await? {
+            let local_scratchpad: Scratchpad = try_deserialize_record(&record)?;
+            if !scratchpad.supersedes(&local_scratchpad) {
+                debug!("Scratchpad {pretty_key:?} is not newer than the local version");
+                return Ok(());
+            }
+        }
+
+        let record = Record {
+            key: key.clone(),
+            value: try_serialize_record(&scratchpad, RecordKind::Scratchpad)?.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        let content_hash = XorName::from_content(&record.value);
+
+        info!("Storing scratchpad {addr:?} with content of {content_hash:?} as Record locally");
+        self.network().put_local_record(record);
+
+        self.record_metrics(Marker::ValidScratchpadRecordPutFromNetwork(&pretty_key));
+
         if with_payment {
             self.replicate_valid_fresh_record(key, RecordType::NonChunk(content_hash));
         }
@@ -370,6 +733,36 @@ impl Node {
         Ok(())
     }
 
+    /// Validate and store an `ExpiringData` to the RecordStore, unless it has already expired.
+    pub(crate) fn validate_and_store_expiring_data(
+        &self,
+        expiring_data: &ExpiringData,
+    ) -> Result<()> {
+        let addr = *expiring_data.address();
+        debug!("Validating and storing expiring data {addr:?}");
+        expiring_data.verify(SystemTime::now())?;
+
+        let key = expiring_data.network_address().to_record_key();
+        let pretty_key = PrettyPrintRecordKey::from(&key).into_owned();
+
+        let record = Record {
+            key,
+            value: try_serialize_record(expiring_data, RecordKind::ExpiringData)?.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+
+        info!(
+            "Storing expiring data {addr:?}, expiring at {:?}, as Record locally",
+            expiring_data.expires_at()
+        );
+        self.network().put_local_record(record);
+
+        self.record_metrics(Marker::ValidExpiringDataRecordPutFromNetwork(&pretty_key));
+
+        Ok(())
+    }
+
     /// Validate and store `Vec<SignedSpend>` to the RecordStore
     /// If we already have a spend at this address, the Vec is extended and stored.
     pub(crate) async fn validate_merge_and_store_spends(
@@ -411,6 +804,48 @@ impl Node {
             }
         };
 
+        // a retried submission of spends we already hold is acknowledged as is,
+        // without fetching and validating them against the network again
+        let spend_addr = SpendAddress::from_unique_pubkey(unique_pubkey);
+        let local_spends = self.get_local_spends(spend_addr).await?;
+        let local_tokens: BTreeSet<_> = local_spends
+            .iter()
+            .map(SignedSpend::submission_token)
+            .collect();
+        if spends_for_key
+            .iter()
+            .all(|spend| local_tokens.contains(&spend.submission_token()))
+        {
+            debug!("Already holding all the spends submitted at {pretty_key:?}, acknowledging the retried submission");
+            return Ok(());
+        }
+
+        // a reclaimable CashNote is either spent by its recipient or refunded to its sender,
+        // whichever we hear of first, so that a late refund can't turn a redeemed CashNote
+        // into a double spend, nor a late redemption an expired one
+        if let Some(local_spend) = local_spends.first() {
+            if spends_for_key
+                .iter()
+                .any(|spend| spend.is_refund() != local_spend.is_refund())
+            {
+                warn!("Rejecting spend at {pretty_key:?} taking another redemption path than the one we hold");
+                return Err(Error::ReclaimableCashNoteAlreadyRedeemed(spend_addr));
+            }
+        }
+
+        // a refund is only valid once the deadline of its reclaim clause has passed on our clock,
+        // which the spends' own verification leaves out so it doesn't depend on the verifier
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        for spend in &spends_for_key {
+            if let Err(err) = spend.verify_reclaim_deadline(now) {
+                warn!("Rejecting spend at {pretty_key:?} refunded before its deadline");
+                return Err(err.into());
+            }
+        }
+
         // validate the signed spends against the network and the local knowledge
         debug!("Validating spends for {pretty_key:?} with unique key: {unique_pubkey:?}");
         let validated_spends = match self
@@ -454,15 +889,26 @@ impl Node {
 
     /// Gets CashNotes out of Transfers, this includes network verifications of the Transfers
     /// Rewraps the royalties transfers into encrypted Transfers ready to be sent directly to the beneficiary
+    /// Also returns the royalties received by each royalties beneficiary
     async fn cash_notes_from_transfers(
         &self,
         transfers: Vec<Transfer>,
         wallet: &HotWallet,
         pretty_key: PrettyPrintRecordKey<'static>,
-    ) -> Result<(NanoTokens, Vec<CashNote>, Vec<CashNoteRedemption>)> {
-        let royalties_pk = *NETWORK_ROYALTIES_PK;
+    ) -> Result<(
+        NanoTokens,
+        Vec<CashNote>,
+        Vec<CashNoteRedemption>,
+        BTreeMap<MainPubkey, NanoTokens>,
+    )> {
+        let royalties_pks: Vec<MainPubkey> = NETWORK_ROYALTIES_SPLIT
+            .beneficiaries()
+            .iter()
+            .map(|(main_pubkey, _)| *main_pubkey)
+            .collect();
         let mut cash_notes = vec![];
         let mut royalties_cash_notes_r = vec![];
+        let mut royalties_by_beneficiary = BTreeMap::new();
         let mut received_fee = NanoTokens::zero();
 
         for transfer in transfers {
@@ -482,7 +928,7 @@ impl Node {
                 Transfer::NetworkRoyalties(cashnote_redemptions) => {
                     match self
                         .network()
-                        .verify_cash_notes_redemptions(royalties_pk, &cashnote_redemptions)
+                        .verify_cash_notes_redemptions_to_any(&royalties_pks, &cashnote_redemptions)
                         .await
                     {
                         Ok(cash_notes) => {
@@ -491,6 +937,14 @@ impl Node {
                                 "{} network royalties payment cash notes found for record {pretty_key} for a total value of {received_royalties:?}",
                                 cash_notes.len()
                             );
+                            for cash_note in &cash_notes {
+                                let received = royalties_by_beneficiary
+                                    .entry(*cash_note.main_pubkey())
+                                    .or_insert_with(NanoTokens::zero);
+                                *received = received
+                                    .checked_add(cash_note.value()?)
+                                    .ok_or_else(|| Error::NumericOverflow)?;
+                            }
                             royalties_cash_notes_r.extend(cashnote_redemptions);
                             received_fee = received_fee
                                 .checked_add(received_royalties)
@@ -518,7 +972,12 @@ impl Node {
                 .checked_add(received_fee_to_our_node)
                 .ok_or_else(|| Error::NumericOverflow)?;
 
-            Ok((received_fee, cash_notes, royalties_cash_notes_r))
+            Ok((
+                received_fee,
+                cash_notes,
+                royalties_cash_notes_r,
+                royalties_by_beneficiary,
+            ))
         }
     }
 
@@ -527,6 +986,8 @@ impl Node {
         &self,
         address: &NetworkAddress,
         payment: Payment,
+        kind: RecordKind,
+        payer: Option<PeerId>,
     ) -> Result<()> {
         let key = address.to_record_key();
         let pretty_key = PrettyPrintRecordKey::from(&key).into_owned();
@@ -538,7 +999,7 @@ impl Node {
 
         // unpack transfer
         debug!("Unpacking incoming Transfers for record {pretty_key}");
-        let (received_fee, mut cash_notes, royalties_cash_notes_r) = self
+        let (received_fee, mut cash_notes, royalties_cash_notes_r, royalties_by_beneficiary) = self
             .cash_notes_from_transfers(payment.transfers, &wallet, pretty_key.clone())
             .await?;
 
@@ -561,7 +1022,7 @@ impl Node {
         debug!("Received payment of {received_fee:?} for {pretty_key}");
 
         // Notify `record_store` that the node received a payment.
-        self.network().notify_payment_received();
+        self.network().notify_payment_received(key.clone());
 
         // deposit the CashNotes in our wallet
         wallet.deposit_and_store_to_disk(&cash_notes)?;
@@ -570,12 +1031,15 @@ impl Node {
             "The new wallet balance is {new_balance}, after earning {}",
             new_balance - old_balance
         );
+        let earned = total_cash_notes_amount(&cash_notes)?;
+        self.earnings().record_payment(earned, kind, payer);
 
         #[cfg(feature = "open-metrics")]
         if let Some(node_metrics) = self.node_metrics() {
             let _ = node_metrics
                 .current_reward_wallet_balance
                 .set(new_balance as i64);
+            node_metrics.record_earnings(kind, earned);
         }
 
         if royalties_cash_notes_r.is_empty() {
@@ -604,6 +1068,22 @@ impl Node {
                 expected: expected_fee,
             });
         }
+
+        // and that every royalties beneficiary got its share
+        for (expected, beneficiary) in NETWORK_ROYALTIES_SPLIT.split(expected_royalties_fee) {
+            let paid = royalties_by_beneficiary
+                .get(&beneficiary)
+                .copied()
+                .unwrap_or_else(NanoTokens::zero);
+            if paid < expected {
+                debug!("Royalties paid to {beneficiary:?} insufficient for record {pretty_key}. {paid:?} is less than {expected:?}");
+                return Err(Error::RoyaltiesShareInsufficientAmount {
+                    beneficiary,
+                    paid,
+                    expected,
+                });
+            }
+        }
         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
         info!("Total payment of {received_fee:?} nanos accepted for record {pretty_key}");
 
@@ -8,7 +8,7 @@
 
 use crate::{node::Node, Error, Result};
 use libp2p::PeerId;
-use sn_networking::{calculate_cost_for_records, Network, NodeIssue};
+use sn_networking::{Network, NodeIssue, PricingStrategy};
 use sn_protocol::{error::Error as ProtocolError, storage::ChunkAddress, NetworkAddress};
 use sn_transfers::{NanoTokens, PaymentQuote, QuotingMetrics};
 use std::time::Duration;
@@ -82,7 +82,11 @@ pub(crate) fn verify_quote_for_storecost(
 // Following metrics will be considered as node's bad quote.
 //   1, Price calculation is incorrect
 //   2, QuoteMetrics doesn't match the historical quotes collected by self
-pub(crate) async fn quotes_verification(network: &Network, quotes: Vec<(PeerId, PaymentQuote)>) {
+pub(crate) async fn quotes_verification(
+    network: &Network,
+    pricing: &dyn PricingStrategy,
+    quotes: Vec<(PeerId, PaymentQuote)>,
+) {
     // Do nothing if self is not one of the quoters.
     if let Some((_, self_quote)) = quotes
         .iter()
@@ -118,7 +122,7 @@ pub(crate) async fn quotes_verification(network: &Network, quotes: Vec<(PeerId,
                 .collect();
 
             quotes_for_nodes_duty.retain(|(peer_id, quote)| {
-                let cost = calculate_cost_for_records(&quote.quoting_metrics);
+                let cost = pricing.store_cost(&quote.quoting_metrics);
                 let is_same_as_expected = quote.cost == NanoTokens::from(cost);
 
                 if !is_same_as_expected {
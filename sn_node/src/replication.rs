@@ -6,12 +6,16 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Result, node::Node};
+use crate::{
+    error::{Error, Result},
+    node::Node,
+    replication_throttle::ReplicationDirection,
+};
 use libp2p::{
     kad::{Quorum, Record, RecordKey},
     PeerId,
 };
-use sn_networking::{sort_peers_by_address, GetRecordCfg, Network, REPLICATION_PEERS_COUNT};
+use sn_networking::{sort_peers_by_address, GetRecordCfg, Network, NodeIssue};
 use sn_protocol::{
     messages::{Cmd, Query, QueryResponse, Request, Response},
     storage::RecordType,
@@ -64,6 +68,7 @@ impl Node {
                     None
                 };
 
+                let served_by_holder = record_opt.is_some();
                 let record = if let Some(record_content) = record_opt {
                     Record::new(key, record_content.to_vec())
                 } else {
@@ -85,11 +90,21 @@ impl Node {
                     }
                 };
 
+                node.replication_throttle()
+                    .acquire(ReplicationDirection::Fetched, record.value.len())
+                    .await;
                 debug!(
                     "Got Replication Record {pretty_key:?} from network, validating and storing it"
                 );
                 if let Err(err) = node.store_replicated_in_record(record).await {
                     error!("During store replication fetched {pretty_key:?}, got error {err:?}");
+                    // The failures down to our own network or storage aren't the holder's fault.
+                    if served_by_holder
+                        && !matches!(err, Error::Network(_) | Error::JoinErrorInAsyncThread(_))
+                    {
+                        node.network()
+                            .record_node_issues(holder, NodeIssue::InvalidRecord);
+                    }
                 } else {
                     debug!("Completed storing Replication Record {pretty_key:?} from network.");
                 }
@@ -160,7 +175,7 @@ impl Node {
             let sorted_based_on_addr = match sort_peers_by_address(
                 &closest_k_peers,
                 &data_addr,
-                REPLICATION_PEERS_COUNT,
+                network.replication_peers_count(),
             ) {
                 Ok(result) => result,
                 Err(err) => {
@@ -0,0 +1,119 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#[cfg(feature = "open-metrics")]
+use prometheus_client::metrics::{counter::Counter, family::Family};
+use sn_networking::{sleep, Instant};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// Whether a replicated record is fetched from a peer or served to one.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "open-metrics",
+    derive(prometheus_client::encoding::EncodeLabelValue)
+)]
+pub(crate) enum ReplicationDirection {
+    Fetched,
+    Served,
+}
+
+/// Caps the bytes per second of the records fetched and served for the replication, across all
+/// the concurrent fetches of the node.
+///
+/// Every record reserves the next free slot and waits until it is reached. A fetched record only
+/// has its size known once received, so it holds its fetch back, which delays the next ones.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ReplicationThrottle {
+    bytes_per_sec: Option<u64>,
+    // When the next byte is allowed to be transferred.
+    next_slot: Arc<Mutex<Option<Instant>>>,
+    #[cfg(feature = "open-metrics")]
+    traffic_metric: Option<Family<ReplicationTrafficLabels, Counter>>,
+}
+
+#[cfg(feature = "open-metrics")]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, prometheus_client::encoding::EncodeLabelSet)]
+pub(crate) struct ReplicationTrafficLabels {
+    direction: ReplicationDirection,
+}
+
+impl ReplicationThrottle {
+    pub(crate) fn new(bytes_per_sec: Option<u64>) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.filter(|limit| *limit > 0),
+            ..Default::default()
+        }
+    }
+
+    /// Set the metric the bytes of replication traffic are counted in.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn set_traffic_metric(&mut self, metric: Family<ReplicationTrafficLabels, Counter>) {
+        self.traffic_metric = Some(metric);
+    }
+
+    /// Waits until transferring a record of `size` bytes stays within the limit.
+    pub(crate) async fn acquire(&self, direction: ReplicationDirection, size: usize) {
+        #[cfg(feature = "open-metrics")]
+        if let Some(metric) = &self.traffic_metric {
+            let _ = metric
+                .get_or_create(&ReplicationTrafficLabels { direction })
+                .inc_by(size as u64);
+        }
+
+        let delay = self.reserve(size, Instant::now());
+        if !delay.is_zero() {
+            trace!("Delaying the {direction:?} record of {size} bytes by {delay:?} to stay within the replication limit");
+            sleep(delay).await;
+        }
+    }
+
+    /// Reserves the slot for `size` bytes, returning how long to wait from `now` for it.
+    fn reserve(&self, size: usize, now: Instant) -> Duration {
+        let Some(limit) = self.bytes_per_sec else {
+            return Duration::ZERO;
+        };
+
+        let mut next_slot = self.next_slot();
+        let slot = next_slot.map_or(now, |next| next.max(now));
+        *next_slot = Some(slot + Duration::from_secs_f64(size as f64 / limit as f64));
+        slot.saturating_duration_since(now)
+    }
+
+    fn next_slot(&self) -> MutexGuard<'_, Option<Instant>> {
+        match self.next_slot.lock() {
+            Ok(next_slot) => next_slot,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_spread_over_time_across_the_clones_of_the_throttle() {
+        let now = Instant::now();
+        let unlimited = ReplicationThrottle::new(Some(0));
+        assert_eq!(unlimited.reserve(1024 * 1024, now), Duration::ZERO);
+        assert_eq!(unlimited.reserve(1024 * 1024, now), Duration::ZERO);
+
+        let throttle = ReplicationThrottle::new(Some(1024));
+        let clone = throttle.clone();
+        assert_eq!(throttle.reserve(2048, now), Duration::ZERO);
+        assert_eq!(clone.reserve(1024, now), Duration::from_secs(2));
+        assert_eq!(throttle.reserve(512, now), Duration::from_secs(3));
+
+        // the slots left unused in the past aren't made up for.
+        let later = now + Duration::from_secs(10);
+        assert_eq!(clone.reserve(1024, later), Duration::ZERO);
+    }
+}
@@ -0,0 +1,279 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+use sysinfo::{Disks, System, MINIMUM_CPU_UPDATE_INTERVAL};
+
+/// Interval between the measures of the resources available to the node.
+const RESOURCE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// The weight of the latest measure in the average CPU usage, so that a short spike doesn't have
+// the node refuse new records.
+const CPU_USAGE_SMOOTHING: f32 = 0.3;
+
+/// The resources a node needs to hold records for the network.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResourceRequirements {
+    /// The memory left available to the node, in bytes.
+    pub min_available_memory: u64,
+    /// The disk left available to the node in its root dir, in bytes.
+    pub min_available_disk: u64,
+    /// The average CPU usage of the machine over which the node stops taking new records, in
+    /// percent.
+    pub max_cpu_usage: f32,
+}
+
+impl Default for ResourceRequirements {
+    fn default() -> Self {
+        Self {
+            min_available_memory: 256 * 1024 * 1024,
+            min_available_disk: 1024 * 1024 * 1024,
+            max_cpu_usage: 95.0,
+        }
+    }
+}
+
+/// The resources available to a node, measured at startup then periodically, and how they fall
+/// short of its requirements if they do.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuitabilityReport {
+    /// The memory available, in bytes.
+    pub available_memory: u64,
+    /// The disk available in the root dir of the node, in bytes, if the disk could be told.
+    pub available_disk: Option<u64>,
+    /// The average CPU usage of the machine, in percent.
+    pub cpu_usage: f32,
+    /// The requirements the resources fall short of.
+    pub shortfalls: Vec<ResourceShortfall>,
+}
+
+/// A requirement of the node the resources available fall short of.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResourceShortfall {
+    /// Less memory is available than required.
+    Memory {
+        /// The memory available, in bytes.
+        available: u64,
+        /// The memory required, in bytes.
+        required: u64,
+    },
+    /// Less disk is available than required.
+    Disk {
+        /// The disk available, in bytes.
+        available: u64,
+        /// The disk required, in bytes.
+        required: u64,
+    },
+    /// The CPU is used over the max.
+    Cpu {
+        /// The average CPU usage, in percent.
+        usage: f32,
+        /// The max CPU usage, in percent.
+        max: f32,
+    },
+}
+
+impl SuitabilityReport {
+    fn assess(
+        available_memory: u64,
+        available_disk: Option<u64>,
+        cpu_usage: f32,
+        requirements: &ResourceRequirements,
+    ) -> Self {
+        let mut shortfalls = vec![];
+        if available_memory < requirements.min_available_memory {
+            shortfalls.push(ResourceShortfall::Memory {
+                available: available_memory,
+                required: requirements.min_available_memory,
+            });
+        }
+        if let Some(available) = available_disk {
+            if available < requirements.min_available_disk {
+                shortfalls.push(ResourceShortfall::Disk {
+                    available,
+                    required: requirements.min_available_disk,
+                });
+            }
+        }
+        if cpu_usage > requirements.max_cpu_usage {
+            shortfalls.push(ResourceShortfall::Cpu {
+                usage: cpu_usage,
+                max: requirements.max_cpu_usage,
+            });
+        }
+        Self {
+            available_memory,
+            available_disk,
+            cpu_usage,
+            shortfalls,
+        }
+    }
+
+    /// Whether the node has the resources to take new records.
+    pub fn is_suitable(&self) -> bool {
+        self.shortfalls.is_empty()
+    }
+
+    /// Whether the node has the memory and the disk to join the network, the CPU usage being
+    /// expected to be high while the nodes of a machine start.
+    pub(crate) fn can_join(&self) -> bool {
+        self.shortfalls
+            .iter()
+            .all(|shortfall| matches!(shortfall, ResourceShortfall::Cpu { .. }))
+    }
+}
+
+/// Keeps the suitability report of the node up to date, which the node refuses new records by
+/// while its resources fall short.
+#[derive(Clone, Debug)]
+pub(crate) struct ResourceMonitor {
+    root_dir: PathBuf,
+    requirements: ResourceRequirements,
+    report: Arc<Mutex<SuitabilityReport>>,
+}
+
+impl ResourceMonitor {
+    /// Measures the resources available to the node with the root dir, blocking for the CPU
+    /// usage to be sampled.
+    pub(crate) fn new(root_dir: PathBuf, requirements: ResourceRequirements) -> Self {
+        let mut system = System::new();
+        let cpu_usage = measure_cpu_usage(&mut system);
+        let report = measure(&mut system, &root_dir, cpu_usage, &requirements);
+        Self {
+            root_dir,
+            requirements,
+            report: Arc::new(Mutex::new(report)),
+        }
+    }
+
+    /// Returns the latest suitability report.
+    pub(crate) fn report(&self) -> SuitabilityReport {
+        self.lock_report().clone()
+    }
+
+    /// Whether the node has the resources to take new records.
+    pub(crate) fn accepts_new_records(&self) -> bool {
+        self.lock_report().is_suitable()
+    }
+
+    /// Measures the resources available at each interval, for the report to stay up to date.
+    pub(crate) async fn monitor_periodically(self) {
+        let mut check_interval = tokio::time::interval(RESOURCE_CHECK_INTERVAL);
+        let _ = check_interval.tick().await; // first tick completes immediately
+        let mut system = System::new();
+        loop {
+            let _ = check_interval.tick().await;
+            let was_suitable = self.accepts_new_records();
+
+            system.refresh_cpu_usage();
+            let cpu_usage = average_cpu_usage(
+                self.lock_report().cpu_usage,
+                system.global_cpu_info().cpu_usage(),
+            );
+            let report = measure(&mut system, &self.root_dir, cpu_usage, &self.requirements);
+
+            match (was_suitable, report.is_suitable()) {
+                (true, false) => warn!(
+                    "The node stops taking new records as its resources fall short: {:?}",
+                    report.shortfalls
+                ),
+                (false, true) => info!("The node takes new records again: {report:?}"),
+                _ => debug!("Resources available to the node: {report:?}"),
+            }
+            *self.lock_report() = report;
+        }
+    }
+
+    fn lock_report(&self) -> MutexGuard<'_, SuitabilityReport> {
+        match self.report.lock() {
+            Ok(report) => report,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+fn measure(
+    system: &mut System,
+    root_dir: &Path,
+    cpu_usage: f32,
+    requirements: &ResourceRequirements,
+) -> SuitabilityReport {
+    system.refresh_memory();
+    SuitabilityReport::assess(
+        system.available_memory(),
+        available_disk(root_dir),
+        cpu_usage,
+        requirements,
+    )
+}
+
+// The CPU usage is only known from the second refresh on.
+fn measure_cpu_usage(system: &mut System) -> f32 {
+    system.refresh_cpu_usage();
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_cpu_usage();
+    system.global_cpu_info().cpu_usage()
+}
+
+fn average_cpu_usage(average: f32, latest: f32) -> f32 {
+    average + CPU_USAGE_SMOOTHING * (latest - average)
+}
+
+// The space available on the disk mounted the closest to the root dir.
+fn available_disk(root_dir: &Path) -> Option<u64> {
+    let root_dir = root_dir.canonicalize().ok()?;
+    Disks::new_with_refreshed_list()
+        .iter()
+        .filter(|disk| root_dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_short_of_memory_or_disk_do_not_join() {
+        let requirements = ResourceRequirements {
+            min_available_memory: 100,
+            min_available_disk: 1000,
+            max_cpu_usage: 90.0,
+        };
+
+        let report = SuitabilityReport::assess(100, Some(1000), 90.0, &requirements);
+        assert!(report.is_suitable() && report.can_join());
+
+        // the CPU being busy only holds the new records back
+        let report = SuitabilityReport::assess(100, None, 95.0, &requirements);
+        assert!(!report.is_suitable() && report.can_join());
+
+        let report = SuitabilityReport::assess(99, Some(999), 50.0, &requirements);
+        assert!(!report.can_join());
+        assert_eq!(
+            report.shortfalls,
+            vec![
+                ResourceShortfall::Memory {
+                    available: 99,
+                    required: 100
+                },
+                ResourceShortfall::Disk {
+                    available: 999,
+                    required: 1000
+                },
+            ]
+        );
+
+        // a spike of the CPU usage is smoothed out
+        assert_eq!(average_cpu_usage(50.0, 100.0), 65.0);
+    }
+}
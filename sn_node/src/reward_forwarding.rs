@@ -0,0 +1,232 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{node::Node, Result};
+use libp2p::kad::{Quorum, Record};
+use sn_networking::{Network, PutRecordCfg};
+use sn_protocol::{
+    storage::{try_serialize_record, RecordKind, SpendAddress},
+    NetworkAddress,
+};
+use sn_transfers::{CashNote, HotWallet, MainPubkey, NanoTokens, SignedSpend, Transfer};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const REWARD_FORWARDING_LOG_FILENAME: &str = "reward_forwarding.log";
+
+/// Forwards the rewards earned by the node to a cold wallet, for a long running node not to pile
+/// them up under its hot reward key.
+#[derive(Clone, Copy, Debug)]
+pub struct RewardForwarding {
+    /// The key of the cold wallet the rewards are forwarded to.
+    pub cold_wallet: MainPubkey,
+    /// The balance the rewards are forwarded from, for the node not to pay a transaction for
+    /// each of the payments it gets.
+    pub min_amount: NanoTokens,
+    /// How often the balance is checked.
+    pub interval: Duration,
+}
+
+/// An entry of the log of the rewards forwarded, which the node keeps in its root dir.
+#[derive(Clone, Debug)]
+pub struct ForwardedRewards {
+    /// When the rewards were forwarded.
+    pub forwarded_at: SystemTime,
+    /// The amount forwarded.
+    pub amount: NanoTokens,
+    /// The key of the cold wallet the rewards were forwarded to.
+    pub cold_wallet: MainPubkey,
+    /// The transfer for the cold wallet to receive the rewards with.
+    pub transfer: Transfer,
+}
+
+impl ForwardedRewards {
+    fn to_log_line(&self) -> Result<String> {
+        let secs = self
+            .forwarded_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Ok(format!(
+            "{secs} {} {} {}",
+            self.amount.as_nano(),
+            self.cold_wallet.to_hex(),
+            self.transfer.to_hex()?
+        ))
+    }
+
+    fn from_log_line(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let secs = fields.next()?.parse().ok()?;
+        let amount = fields.next()?.parse().ok()?;
+        let cold_wallet = MainPubkey::from_hex(fields.next()?).ok()?;
+        let transfer = Transfer::from_hex(fields.next()?).ok()?;
+        Some(Self {
+            forwarded_at: UNIX_EPOCH + Duration::from_secs(secs),
+            amount: NanoTokens::from(amount),
+            cold_wallet,
+            transfer,
+        })
+    }
+}
+
+/// Reads the log of the rewards forwarded from the root dir of a node, oldest first.
+pub(crate) fn read_forwarding_log(root_dir: &Path) -> Result<Vec<ForwardedRewards>> {
+    let log = match fs::read_to_string(root_dir.join(REWARD_FORWARDING_LOG_FILENAME)) {
+        Ok(log) => log,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(log
+        .lines()
+        .filter_map(|line| {
+            let entry = ForwardedRewards::from_log_line(line);
+            if entry.is_none() {
+                warn!("Skipping the invalid line of the reward forwarding log: {line:?}");
+            }
+            entry
+        })
+        .collect())
+}
+
+impl Node {
+    /// Forwards the balance of the reward wallet to the cold wallet at each interval, once it's
+    /// over the min amount.
+    pub(crate) async fn forward_rewards_periodically(self, forwarding: RewardForwarding) {
+        info!(
+            "Forwarding the rewards over {} to {} every {:?}",
+            forwarding.min_amount,
+            forwarding.cold_wallet.to_hex(),
+            forwarding.interval
+        );
+        let mut forward_interval = tokio::time::interval(forwarding.interval);
+        let _ = forward_interval.tick().await; // first tick completes immediately
+        loop {
+            let _ = forward_interval.tick().await;
+            if let Err(err) = forward_rewards(self.network(), &forwarding).await {
+                error!("Failed to forward the rewards to the cold wallet: {err:?}");
+            }
+        }
+    }
+}
+
+async fn forward_rewards(network: &Network, forwarding: &RewardForwarding) -> Result<()> {
+    let root_dir = network.root_dir_path();
+    let mut wallet = HotWallet::load_from(root_dir)?;
+
+    // The spends of a previous forwarding which didn't make it to the network are sent again,
+    // before anything else gets forwarded.
+    if !wallet.unconfirmed_spend_requests_exist() {
+        let balance = wallet.balance();
+        if balance.is_zero() || balance < forwarding.min_amount {
+            debug!(
+                "Not forwarding the reward balance of {balance}, under {}",
+                forwarding.min_amount
+            );
+            return Ok(());
+        }
+
+        let report = wallet.local_sweep_to(forwarding.cold_wallet)?;
+        if let Some(err) = &report.error {
+            warn!("Forwarded only part of the reward balance: {err:?}");
+        }
+        log_forwarded_rewards(root_dir, &report.cash_notes, report.swept_amount)?;
+    }
+
+    let spends: Vec<SignedSpend> = wallet
+        .unconfirmed_spend_requests()
+        .iter()
+        .cloned()
+        .collect();
+    send_spends(network, spends).await?;
+    wallet.clear_confirmed_spend_requests();
+    Ok(())
+}
+
+// Appends the transfers of the cash notes created for the cold wallet to the log, which is the
+// only place they're kept. They're logged as well in case the log can't be written.
+fn log_forwarded_rewards(
+    root_dir: &Path,
+    cash_notes: &[CashNote],
+    total: NanoTokens,
+) -> Result<()> {
+    let mut lines = String::new();
+    for cash_note in cash_notes {
+        let entry = ForwardedRewards {
+            forwarded_at: SystemTime::now(),
+            amount: cash_note.value()?,
+            cold_wallet: cash_note.main_pubkey,
+            transfer: Transfer::transfer_from_cash_note(cash_note)?,
+        };
+        let line = entry.to_log_line()?;
+        info!("Forwarded rewards: {line}");
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    info!("Forwarded {total} of rewards to the cold wallet");
+
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(root_dir.join(REWARD_FORWARDING_LOG_FILENAME))?;
+    log.write_all(lines.as_bytes())?;
+    Ok(())
+}
+
+async fn send_spends(network: &Network, spends: Vec<SignedSpend>) -> Result<()> {
+    let put_cfg = PutRecordCfg {
+        put_quorum: Quorum::Majority,
+        retry_strategy: None,
+        use_put_record_to: None,
+        verification: None,
+    };
+    for spend in spends {
+        let address = SpendAddress::from_unique_pubkey(spend.unique_pubkey());
+        let record = Record {
+            key: NetworkAddress::from_spend_address(address).to_record_key(),
+            value: try_serialize_record(&[spend], RecordKind::Spend)?.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        network.put_record(record, &put_cfg).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_transfers::MainSecretKey;
+
+    #[test]
+    fn forwarded_rewards_are_read_back_from_the_log() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert!(read_forwarding_log(dir.path())?.is_empty());
+
+        let cold_wallet = MainSecretKey::random().main_pubkey();
+        let entry = ForwardedRewards {
+            forwarded_at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            amount: NanoTokens::from(42),
+            cold_wallet,
+            transfer: Transfer::create(vec![], cold_wallet)?,
+        };
+        let log = format!("{}\nnot an entry\n", entry.to_log_line()?);
+        fs::write(dir.path().join(REWARD_FORWARDING_LOG_FILENAME), log)?;
+
+        let entries = read_forwarding_log(dir.path())?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].forwarded_at, entry.forwarded_at);
+        assert_eq!(entries[0].amount, entry.amount);
+        assert_eq!(entries[0].cold_wallet, cold_wallet);
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{node::Node, Error, Result};
+use libp2p::kad::{Quorum, Record, RecordKey};
+use sn_networking::GetRecordCfg;
+use sn_protocol::{
+    storage::{
+        try_deserialize_record, Chunk, ExpiringData, Pointer, RecordHeader, RecordKind, Scratchpad,
+    },
+    NetworkAddress, PrettyPrintRecordKey,
+};
+use sn_registers::SignedRegister;
+use sn_transfers::{SignedSpend, SpendAddress};
+use std::time::{Duration, SystemTime};
+use xor_name::XorName;
+
+/// Interval between the batches of records scrubbed.
+const SCRUB_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The most records scrubbed per batch, which keeps the scrubbing from competing with the serving
+/// of the records for the disk.
+const SCRUB_BATCH_SIZE: usize = 16;
+
+impl Node {
+    /// Goes through the stored records batch by batch, checking they still match their key,
+    /// to catch the ones corrupted on disk. Those are quarantined and fetched again from the
+    /// network.
+    pub(crate) async fn scrub_records_periodically(self) {
+        let mut scrub_interval = tokio::time::interval(SCRUB_INTERVAL);
+        let _ = scrub_interval.tick().await; // first tick completes immediately
+
+        // The last key scrubbed, the keys being scrubbed in order.
+        let mut last_scrubbed = None;
+        loop {
+            let _ = scrub_interval.tick().await;
+            let keys = match self.network().get_all_local_record_addresses().await {
+                Ok(addresses) => addresses
+                    .into_keys()
+                    .map(|address| address.to_record_key())
+                    .collect(),
+                Err(err) => {
+                    error!("Failed to get the local record addresses to scrub: {err:?}");
+                    continue;
+                }
+            };
+            let batch = next_batch(keys, last_scrubbed.as_ref(), SCRUB_BATCH_SIZE);
+            last_scrubbed = batch.last().cloned();
+            for key in batch {
+                self.scrub_record(key).await;
+            }
+        }
+    }
+
+    async fn scrub_record(&self, key: RecordKey) {
+        let pretty_key = PrettyPrintRecordKey::from(&key).into_owned();
+        let network = self.network();
+        match network.get_local_record(&key).await {
+            Ok(Some(record)) => match validate_stored_record(&record) {
+                Ok(()) => {
+                    trace!("Scrubbed record {pretty_key:?}");
+                    return;
+                }
+                Err(err) => warn!("Stored record {pretty_key:?} is corrupted: {err:?}"),
+            },
+            // The record could have been pruned since the keys were listed.
+            Ok(None) if !matches!(network.is_record_key_present_locally(&key).await, Ok(true)) => {
+                return
+            }
+            Ok(None) => warn!("Stored record {pretty_key:?} can't be read anymore"),
+            Err(err) => {
+                error!("Failed to get the local record {pretty_key:?} to scrub: {err:?}");
+                return;
+            }
+        }
+
+        network.quarantine_local_record(key.clone());
+        let get_cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: None,
+            target_record: None,
+            expected_holders: Default::default(),
+        };
+        match network.get_record_from_network(key, &get_cfg).await {
+            Ok(record) => match self.store_replicated_in_record(record).await {
+                Ok(()) => info!("Fetched the quarantined record {pretty_key:?} again"),
+                Err(err) => {
+                    error!("Failed to store the quarantined record {pretty_key:?} fetched again: {err:?}")
+                }
+            },
+            Err(err) => {
+                error!("Failed to fetch the quarantined record {pretty_key:?} again: {err:?}")
+            }
+        }
+    }
+}
+
+// Checks a stored record still matches its key, the records being stored without their payment.
+fn validate_stored_record(record: &Record) -> Result<()> {
+    let matches_key = match RecordHeader::from_record(record)?.kind {
+        RecordKind::Chunk => {
+            let chunk = try_deserialize_record::<Chunk>(record)?;
+            XorName::from_content(chunk.value()) == *chunk.name()
+                && chunk.network_address().to_record_key() == record.key
+        }
+        RecordKind::Spend => {
+            let spends = try_deserialize_record::<Vec<SignedSpend>>(record)?;
+            for spend in &spends {
+                spend.verify(spend.spent_tx_hash())?;
+            }
+            !spends.is_empty()
+                && spends.iter().all(|spend| {
+                    let address = SpendAddress::from_unique_pubkey(spend.unique_pubkey());
+                    NetworkAddress::from_spend_address(address).to_record_key() == record.key
+                })
+        }
+        RecordKind::Register => {
+            let register = try_deserialize_record::<SignedRegister>(record)?;
+            register.verify()?;
+            NetworkAddress::from_register_address(*register.address()).to_record_key() == record.key
+        }
+        RecordKind::Pointer => {
+            let pointer = try_deserialize_record::<Pointer>(record)?;
+            pointer.verify()?;
+            pointer.network_address().to_record_key() == record.key
+        }
+        RecordKind::Scratchpad => {
+            let scratchpad = try_deserialize_record::<Scratchpad>(record)?;
+            scratchpad.verify()?;
+            scratchpad.network_address().to_record_key() == record.key
+        }
+        RecordKind::ExpiringData => {
+            let expiring_data = try_deserialize_record::<ExpiringData>(record)?;
+            expiring_data.verify(SystemTime::now())?;
+            expiring_data.network_address().to_record_key() == record.key
+        }
+        RecordKind::ChunkWithPayment
+        | RecordKind::RegisterWithPayment
+        | RecordKind::PointerWithPayment
+        | RecordKind::ScratchpadWithPayment
+        | RecordKind::ExpiringDataWithPayment => {
+            return Err(Error::UnexpectedRecordWithPayment(
+                PrettyPrintRecordKey::from(&record.key).into_owned(),
+            ))
+        }
+    };
+    if matches_key {
+        Ok(())
+    } else {
+        Err(Error::RecordKeyMismatch)
+    }
+}
+
+// The keys following the last one scrubbed, wrapping around once all of them got scrubbed.
+fn next_batch(
+    mut keys: Vec<RecordKey>,
+    last_scrubbed: Option<&RecordKey>,
+    size: usize,
+) -> Vec<RecordKey> {
+    keys.sort_unstable_by(|a, b| a.as_ref().cmp(b.as_ref()));
+    let start = last_scrubbed.map_or(0, |last| {
+        keys.partition_point(|key| key.as_ref() <= last.as_ref())
+    });
+    keys.rotate_left(start);
+    keys.truncate(size);
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use sn_protocol::storage::try_serialize_record;
+
+    #[test]
+    fn records_are_scrubbed_in_turn() {
+        let keys: Vec<_> = (0..5u8).map(|i| RecordKey::new(&[i])).collect();
+
+        let batch = next_batch(keys.clone(), None, 2);
+        assert_eq!(batch, keys[..2]);
+        let batch = next_batch(keys.clone(), batch.last(), 2);
+        assert_eq!(batch, keys[2..4]);
+        let batch = next_batch(keys.clone(), batch.last(), 2);
+        assert_eq!(batch, [keys[4].clone(), keys[0].clone()]);
+
+        // a key gone since isn't in the way.
+        let batch = next_batch(keys[..3].to_vec(), Some(&keys[3]), 2);
+        assert_eq!(batch, keys[..2]);
+    }
+
+    #[test]
+    fn corrupted_chunks_are_told_apart() -> eyre::Result<()> {
+        let chunk = Chunk::new(Bytes::from_static(b"some chunk"));
+        let key = chunk.network_address().to_record_key();
+        let value = try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec();
+        assert!(validate_stored_record(&Record::new(key.clone(), value.clone())).is_ok());
+
+        let mut corrupted = value;
+        if let Some(byte) = corrupted.last_mut() {
+            *byte ^= 1;
+        }
+        assert!(validate_stored_record(&Record::new(key, corrupted)).is_err());
+        Ok(())
+    }
+}
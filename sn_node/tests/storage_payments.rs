@@ -11,6 +11,7 @@ mod common;
 use crate::common::{client::get_client_and_funded_wallet, random_content};
 use assert_fs::TempDir;
 use eyre::{eyre, Result};
+use futures::StreamExt;
 use libp2p::PeerId;
 use rand::Rng;
 use sn_client::{Error as ClientError, FilesDownload, Uploader, WalletClient};
@@ -336,6 +337,40 @@ async fn storage_payment_register_creation_succeeds() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn storage_payment_register_watch_gets_new_entries() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("storage_payments", true);
+
+    let paying_wallet_dir = TempDir::new()?;
+
+    let (client, paying_wallet) = get_client_and_funded_wallet(paying_wallet_dir.path()).await?;
+    let mut wallet_client = WalletClient::new(client.clone(), paying_wallet);
+
+    let mut rng = rand::thread_rng();
+    let xor_name = XorName::random(&mut rng);
+    let address = RegisterAddress::new(xor_name, client.signer_pk());
+    let (mut register, _cost, _royalties_fees) = client
+        .create_and_pay_for_register(xor_name, &mut wallet_client, true, Permissions::default())
+        .await?;
+
+    let mut watch = client.watch_register(address).await?;
+
+    for _ in 0..3 {
+        let random_entry = rng.gen::<[u8; 32]>().to_vec();
+        let entry_hash = register.write(&random_entry)?;
+        register.sync(&mut wallet_client, true, None).await?;
+
+        let (watched_hash, watched_entry) =
+            tokio::time::timeout(Duration::from_secs(30), watch.next())
+                .await?
+                .ok_or(eyre!("The Register watch ended"))?;
+        assert_eq!(watched_hash, entry_hash);
+        assert_eq!(watched_entry, random_entry);
+    }
+
+    Ok(())
+}
+
 #[tokio::test]
 #[ignore = "Test currently invalid as we always try to pay and upload registers if none found... need to check if this test is valid"]
 async fn storage_payment_register_creation_and_mutation_fails() -> Result<()> {
@@ -637,7 +637,9 @@ mod tests {
     use sn_service_management::{
         error::{Error as ServiceControlError, Result as ServiceControlResult},
         node::{NodeService, NodeServiceData},
-        rpc::{NetworkInfo, NodeInfo, RecordAddress, RpcActions},
+        rpc::{
+            Earnings, NetworkInfo, NodeInfo, PeerBandwidth, RecordAddress, RecordStats, RpcActions,
+        },
         UpgradeOptions, UpgradeResult,
     };
     use sn_transfers::NanoTokens;
@@ -656,7 +658,11 @@ mod tests {
             async fn node_info(&self) -> ServiceControlResult<NodeInfo>;
             async fn network_info(&self) -> ServiceControlResult<NetworkInfo>;
             async fn record_addresses(&self) -> ServiceControlResult<Vec<RecordAddress>>;
+            async fn record_stats(&self) -> ServiceControlResult<RecordStats>;
+            async fn earnings(&self) -> ServiceControlResult<Earnings>;
+            async fn bandwidth(&self) -> ServiceControlResult<Vec<PeerBandwidth>>;
             async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> ServiceControlResult<()>;
+            async fn rotate_identity(&self, delay_millis: u64) -> ServiceControlResult<PeerId>;
             async fn node_stop(&self, delay_millis: u64) -> ServiceControlResult<()>;
             async fn node_update(&self, delay_millis: u64) -> ServiceControlResult<()>;
             async fn is_node_connected_to_network(&self, timeout: std::time::Duration) -> ServiceControlResult<()>;
@@ -710,6 +716,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -813,6 +820,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -988,6 +996,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -1156,6 +1165,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -1242,6 +1252,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -1632,6 +1643,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -1851,6 +1863,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -1995,6 +2008,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -2274,6 +2288,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -2444,6 +2459,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -2597,6 +2613,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -2753,6 +2770,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -2906,6 +2924,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -3059,6 +3078,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -3215,6 +3235,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -3371,6 +3392,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -3527,6 +3549,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
@@ -3682,6 +3705,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                wallet_address: String::new(),
             })
         });
         mock_rpc_client
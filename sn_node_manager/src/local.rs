@@ -526,7 +526,9 @@ mod tests {
     use mockall::predicate::*;
     use sn_service_management::{
         error::Result as RpcResult,
-        rpc::{NetworkInfo, NodeInfo, RecordAddress, RpcActions},
+        rpc::{
+            Earnings, NetworkInfo, NodeInfo, PeerBandwidth, RecordAddress, RecordStats, RpcActions,
+        },
     };
     use std::str::FromStr;
 
@@ -537,7 +539,11 @@ mod tests {
             async fn node_info(&self) -> RpcResult<NodeInfo>;
             async fn network_info(&self) -> RpcResult<NetworkInfo>;
             async fn record_addresses(&self) -> RpcResult<Vec<RecordAddress>>;
+            async fn record_stats(&self) -> RpcResult<RecordStats>;
+            async fn earnings(&self) -> RpcResult<Earnings>;
+            async fn bandwidth(&self) -> RpcResult<Vec<PeerBandwidth>>;
             async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> RpcResult<()>;
+            async fn rotate_identity(&self, delay_millis: u64) -> RpcResult<PeerId>;
             async fn node_stop(&self, delay_millis: u64) -> RpcResult<()>;
             async fn node_update(&self, delay_millis: u64) -> RpcResult<()>;
             async fn is_node_connected_to_network(&self, timeout: std::time::Duration) -> RpcResult<()>;
@@ -586,6 +592,7 @@ mod tests {
                     version: "0.100.12".to_string(),
                     uptime: std::time::Duration::from_secs(1), // the service was just started
                     wallet_balance: 0,
+                    wallet_address: String::new(),
                 })
             });
         mock_rpc_client
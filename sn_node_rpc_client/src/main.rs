@@ -17,7 +17,11 @@ use sn_protocol::safenode_proto::{safe_node_client::SafeNodeClient, NodeEventsRe
 
 use sn_service_management::rpc::{RpcActions, RpcClient};
 
-use std::{net::SocketAddr, time::Duration};
+use sn_transfers::NanoTokens;
+use std::{
+    net::SocketAddr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio_stream::StreamExt;
 use tonic::Request;
 
@@ -43,6 +47,15 @@ enum Cmd {
     /// Note this blocks the app and it will print events as they are broadcasted by the node
     #[clap(name = "events")]
     Events,
+    /// Retrieve the number of records held by the node and the disk they take
+    #[clap(name = "records")]
+    Records,
+    /// Retrieve the rewards earned by the node, per day, per kind of record and per payer
+    #[clap(name = "earnings")]
+    Earnings,
+    /// Retrieve the bytes the node sent to and received from each of its peers, per protocol
+    #[clap(name = "bandwidth")]
+    Bandwidth,
     /// Restart the node after the specified delay
     #[clap(name = "restart")]
     Restart {
@@ -52,6 +65,14 @@ enum Cmd {
         /// Retain the node's PeerId by reusing the same root dir.
         retain_peer_id: bool,
     },
+    /// Move the node to a new key, announced to its closest peers, and restart it with it after
+    /// the specified delay. The node keeps its reward key.
+    #[clap(name = "rotate-identity")]
+    RotateIdentity {
+        /// Delay in milliseconds before restarting the node with its new key
+        #[clap(default_value = "0")]
+        delay_millis: u64,
+    },
     /// Stop the node after the specified delay
     #[clap(name = "stop")]
     Stop {
@@ -96,10 +117,14 @@ async fn main() -> Result<()> {
         Cmd::Info => node_info(addr).await,
         Cmd::Netinfo => network_info(addr).await,
         Cmd::Events => node_events(addr).await,
+        Cmd::Records => record_stats(addr).await,
+        Cmd::Earnings => earnings(addr).await,
+        Cmd::Bandwidth => bandwidth(addr).await,
         Cmd::Restart {
             delay_millis,
             retain_peer_id,
         } => node_restart(addr, delay_millis, retain_peer_id).await,
+        Cmd::RotateIdentity { delay_millis } => rotate_identity(addr, delay_millis).await,
         Cmd::Stop { delay_millis } => node_stop(addr, delay_millis).await,
         Cmd::Update { delay_millis } => node_update(addr, delay_millis).await,
         Cmd::Log { log_level } => update_log_level(addr, log_level).await,
@@ -119,6 +144,7 @@ pub async fn node_info(addr: SocketAddr) -> Result<()> {
     println!("PID: {}", node_info.pid);
     println!("Binary version: {}", node_info.version);
     println!("Time since last restart: {:?}", node_info.uptime);
+    println!("Wallet address: {}", node_info.wallet_address);
 
     Ok(())
 }
@@ -179,6 +205,76 @@ pub async fn record_addresses(addr: SocketAddr) -> Result<()> {
     Ok(())
 }
 
+pub async fn record_stats(addr: SocketAddr) -> Result<()> {
+    let endpoint = format!("https://{addr}");
+    let client = RpcClient::new(&endpoint);
+    let record_stats = client.record_stats().await?;
+
+    println!("Records held by the node: {}", record_stats.records);
+    println!(
+        "Disk used by the records: {} bytes",
+        record_stats.used_bytes
+    );
+    if let Some(max_bytes) = record_stats.max_bytes {
+        println!("Disk quota of the records: {max_bytes} bytes");
+    }
+
+    Ok(())
+}
+
+pub async fn earnings(addr: SocketAddr) -> Result<()> {
+    let endpoint = format!("https://{addr}");
+    let client = RpcClient::new(&endpoint);
+    let earnings = client.earnings().await?;
+
+    println!(
+        "Rewards earned: {} in {} payments",
+        NanoTokens::from(earnings.total),
+        earnings.payments
+    );
+    println!("Per day:");
+    let today = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / (24 * 3600);
+    for (day, amount) in earnings.per_day.into_iter().rev() {
+        println!(
+            "  {} days ago: {}",
+            today.saturating_sub(day),
+            NanoTokens::from(amount)
+        );
+    }
+    println!("Per kind of record:");
+    for (kind, amount) in earnings.per_record_kind {
+        println!("  {kind}: {}", NanoTokens::from(amount));
+    }
+    println!("Per payer:");
+    for (payer, amount) in earnings.per_payer {
+        println!("  {payer}: {}", NanoTokens::from(amount));
+    }
+
+    Ok(())
+}
+
+pub async fn bandwidth(addr: SocketAddr) -> Result<()> {
+    let endpoint = format!("https://{addr}");
+    let client = RpcClient::new(&endpoint);
+    let mut bandwidth = client.bandwidth().await?;
+    // The peers the node exchanges the most with first.
+    bandwidth.sort_by_key(|peer| std::cmp::Reverse(peer.sent + peer.received));
+
+    println!("Bandwidth per peer and protocol:");
+    for peer in bandwidth {
+        println!(
+            "  {} {}: {} bytes sent, {} bytes received",
+            peer.peer_id, peer.protocol, peer.sent, peer.received
+        );
+    }
+
+    Ok(())
+}
+
 pub async fn node_restart(addr: SocketAddr, delay_millis: u64, retain_peer_id: bool) -> Result<()> {
     let endpoint = format!("https://{addr}");
     let client = RpcClient::new(&endpoint);
@@ -190,6 +286,17 @@ pub async fn node_restart(addr: SocketAddr, delay_millis: u64, retain_peer_id: b
     Ok(())
 }
 
+pub async fn rotate_identity(addr: SocketAddr, delay_millis: u64) -> Result<()> {
+    let endpoint = format!("https://{addr}");
+    let client = RpcClient::new(&endpoint);
+    let new_peer_id = client.rotate_identity(delay_millis).await?;
+    println!(
+        "Node moves to the new PeerId {new_peer_id}, restarting in {:?}",
+        Duration::from_millis(delay_millis)
+    );
+    Ok(())
+}
+
 pub async fn node_stop(addr: SocketAddr, delay_millis: u64) -> Result<()> {
     let endpoint = format!("https://{addr}");
     let client = RpcClient::new(&endpoint);
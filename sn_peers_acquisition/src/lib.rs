@@ -48,8 +48,10 @@ pub struct PeersArgs {
     /// Peer(s) to use for bootstrap, in a 'multiaddr' format containing the peer ID.
     ///
     /// A multiaddr looks like
-    /// '/ip4/1.2.3.4/tcp/1200/tcp/p2p/12D3KooWRi6wF7yxWLuPSNskXc6kQ5cJ6eaymeMbCRdTnMesPgFx' where
-    /// `1.2.3.4` is the IP, `1200` is the port and the (optional) last part is the peer ID.
+    /// '/ip4/1.2.3.4/udp/1200/quic-v1/p2p/12D3KooWRi6wF7yxWLuPSNskXc6kQ5cJ6eaymeMbCRdTnMesPgFx' where
+    /// `1.2.3.4` is the IP, `1200` is the port and the (optional) last part is the peer ID. A peer
+    /// listening on TCP is given as '/ip4/1.2.3.4/tcp/1200/p2p/<peer ID>', and a plain
+    /// `1.2.3.4:1200` socket address is taken as a QUIC one.
     ///
     /// This argument can be provided multiple times to connect to multiple peers.
     ///
@@ -131,9 +133,11 @@ impl PeersArgs {
             return Err(Error::PeersNotObtained);
         };
 
-        // Randomly sort peers before we return them to avoid overly hitting any one peer
+        // Randomly sort peers before we return them to avoid overly hitting any one peer, the
+        // QUIC ones first as their connections are the quickest to set up.
         let mut rng = thread_rng();
         peers.shuffle(&mut rng);
+        peers.sort_by_key(|addr| !addr.iter().any(|protocol| protocol == Protocol::QuicV1));
 
         Ok(peers)
     }
@@ -158,20 +162,22 @@ impl PeersArgs {
     }
 }
 
-/// Parse strings like `1.2.3.4:1234` and `/ip4/1.2.3.4/tcp/1234` into a multiaddr.
+/// Parse strings like `1.2.3.4:1234`, `[::1]:1234`, `/ip4/1.2.3.4/udp/1234/quic-v1` and
+/// `/ip4/1.2.3.4/tcp/1234` into a multiaddr.
 pub fn parse_peer_addr(addr: &str) -> Result<Multiaddr> {
-    // Parse valid IPv4 socket address, e.g. `1.2.3.4:1234`.
-    if let Ok(addr) = addr.parse::<std::net::SocketAddrV4>() {
-        let start_addr = Multiaddr::from(*addr.ip());
+    // Parse valid socket address, e.g. `1.2.3.4:1234`.
+    if let Ok(addr) = addr.parse::<std::net::SocketAddr>() {
+        let start_addr = Multiaddr::from(addr.ip());
 
-        // Turn the address into a `/ip4/<ip>/udp/<port>/quic-v1` multiaddr.
-        #[cfg(not(feature = "websockets"))]
+        // Turn the address into a `/ip4/<ip>/udp/<port>/quic-v1` (or `/ip6/...`) multiaddr.
+        #[cfg(not(any(feature = "websockets", target_arch = "wasm32")))]
         let multiaddr = start_addr
             .with(Protocol::Udp(addr.port()))
             .with(Protocol::QuicV1);
 
-        // Turn the address into a `/ip4/<ip>/udp/<port>/websocket-websys-v1` multiaddr.
-        #[cfg(feature = "websockets")]
+        // Turn the address into a `/ip4/<ip>/tcp/<port>/ws` multiaddr, the browsers only
+        // connecting over WebSocket.
+        #[cfg(any(feature = "websockets", target_arch = "wasm32"))]
         let multiaddr = start_addr
             .with(Protocol::Tcp(addr.port()))
             .with(Protocol::Ws("/".into()));
@@ -179,9 +185,18 @@ pub fn parse_peer_addr(addr: &str) -> Result<Multiaddr> {
         return Ok(multiaddr);
     }
 
-    // Parse any valid multiaddr string
+    // Parse any valid multiaddr string, over a transport the peers listen on.
     if let Ok(addr) = addr.parse::<Multiaddr>() {
         debug!("Parsing a full multiaddr: {:?}", addr);
+        let has_transport = addr.iter().any(|protocol| {
+            matches!(
+                protocol,
+                Protocol::QuicV1 | Protocol::Tcp(_) | Protocol::P2pCircuit
+            )
+        });
+        if !has_transport {
+            return Err(Error::InvalidPeerAddr);
+        }
         return Ok(addr);
     }
 
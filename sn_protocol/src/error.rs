@@ -6,8 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{storage::RegisterAddress, NetworkAddress, PrettyPrintRecordKey};
+use crate::{
+    storage::{ExpiringDataAddress, PointerAddress, RegisterAddress, ScratchpadAddress},
+    NetworkAddress, PrettyPrintRecordKey,
+};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use thiserror::Error;
 
 /// A specialised `Result` type for protocol crate.
@@ -37,9 +41,39 @@ pub enum Error {
     #[error("The Register was already created by another owner: {0:?}")]
     RegisterAlreadyClaimed(bls::PublicKey),
 
+    // ---------- Pointer Errors
+    #[error("Pointer not found: {0:?}")]
+    PointerNotFound(Box<PointerAddress>),
+    #[error("The Pointer {0:?} can only be signed by its owner")]
+    PointerOwnerMismatch(Box<PointerAddress>),
+    #[error("The Pointer {0:?} was not signed by its owner")]
+    InvalidPointerSignature(Box<PointerAddress>),
+
+    // ---------- Scratchpad Errors
+    #[error("Scratchpad not found: {0:?}")]
+    ScratchpadNotFound(Box<ScratchpadAddress>),
+    #[error("The Scratchpad {0:?} can only be signed by its owner")]
+    ScratchpadOwnerMismatch(Box<ScratchpadAddress>),
+    #[error("The Scratchpad {0:?} was not signed by its owner")]
+    InvalidScratchpadSignature(Box<ScratchpadAddress>),
+    #[error("The Scratchpad data of {0} bytes is bigger than it can hold")]
+    ScratchpadTooBig(usize),
+
+    // ---------- ExpiringData Errors
+    #[error("ExpiringData not found: {0:?}")]
+    ExpiringDataNotFound(Box<ExpiringDataAddress>),
+    #[error("The ExpiringData {0:?} has expired")]
+    ExpiringDataExpired(Box<ExpiringDataAddress>),
+    #[error("The address {0:?} does not match the ExpiringData data and expiry")]
+    ExpiringDataAddressMismatch(Box<ExpiringDataAddress>),
+    #[error("The ExpiringData time to live of {0:?} is longer than it can be")]
+    ExpiringDataTtlTooLong(Duration),
+
     // ---------- payment errors
     #[error("There was an error getting the storecost from kademlia store")]
     GetStoreCostFailed,
+    #[error("The node lacks the resources to store more records")]
+    InsufficientResources,
     #[error("There was an error generating the payment quote")]
     QuoteGenerationFailed,
 
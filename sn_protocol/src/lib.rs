@@ -28,7 +28,10 @@ pub mod safenode_proto {
 }
 pub use error::Error;
 
-use self::storage::{ChunkAddress, RegisterAddress, SpendAddress};
+use self::storage::{
+    ChunkAddress, ExpiringDataAddress, PointerAddress, RegisterAddress, ScratchpadAddress,
+    SpendAddress,
+};
 use bytes::Bytes;
 use libp2p::{
     kad::{KBucketDistance as Distance, KBucketKey as Key, RecordKey},
@@ -80,6 +83,12 @@ pub enum NetworkAddress {
     RegisterAddress(RegisterAddress),
     /// The NetworkAddress is representing a RecordKey.
     RecordKey(Bytes),
+    /// The NetworkAddress is representing a PointerAddress.
+    PointerAddress(PointerAddress),
+    /// The NetworkAddress is representing a ScratchpadAddress.
+    ScratchpadAddress(ScratchpadAddress),
+    /// The NetworkAddress is representing an ExpiringDataAddress.
+    ExpiringDataAddress(ExpiringDataAddress),
 }
 
 impl NetworkAddress {
@@ -98,6 +107,21 @@ impl NetworkAddress {
         NetworkAddress::RegisterAddress(register_address)
     }
 
+    /// Return a `NetworkAddress` representation of the `PointerAddress`.
+    pub fn from_pointer_address(pointer_address: PointerAddress) -> Self {
+        NetworkAddress::PointerAddress(pointer_address)
+    }
+
+    /// Return a `NetworkAddress` representation of the `ScratchpadAddress`.
+    pub fn from_scratchpad_address(scratchpad_address: ScratchpadAddress) -> Self {
+        NetworkAddress::ScratchpadAddress(scratchpad_address)
+    }
+
+    /// Return a `NetworkAddress` representation of the `ExpiringDataAddress`.
+    pub fn from_expiring_data_address(expiring_data_address: ExpiringDataAddress) -> Self {
+        NetworkAddress::ExpiringDataAddress(expiring_data_address)
+    }
+
     /// Return a `NetworkAddress` representation of the `PeerId` by encapsulating its bytes.
     pub fn from_peer(peer_id: PeerId) -> Self {
         NetworkAddress::PeerId(Bytes::from(peer_id.to_bytes()))
@@ -119,6 +143,13 @@ impl NetworkAddress {
             NetworkAddress::RegisterAddress(register_address) => {
                 register_address.xorname().0.to_vec()
             }
+            NetworkAddress::PointerAddress(pointer_address) => pointer_address.xorname().0.to_vec(),
+            NetworkAddress::ScratchpadAddress(scratchpad_address) => {
+                scratchpad_address.xorname().0.to_vec()
+            }
+            NetworkAddress::ExpiringDataAddress(expiring_data_address) => {
+                expiring_data_address.xorname().0.to_vec()
+            }
         }
     }
 
@@ -139,6 +170,13 @@ impl NetworkAddress {
             NetworkAddress::SpendAddress(cash_note_address) => Some(*cash_note_address.xorname()),
             NetworkAddress::ChunkAddress(chunk_address) => Some(*chunk_address.xorname()),
             NetworkAddress::RegisterAddress(register_address) => Some(register_address.xorname()),
+            NetworkAddress::PointerAddress(pointer_address) => Some(pointer_address.xorname()),
+            NetworkAddress::ScratchpadAddress(scratchpad_address) => {
+                Some(scratchpad_address.xorname())
+            }
+            NetworkAddress::ExpiringDataAddress(expiring_data_address) => {
+                Some(*expiring_data_address.xorname())
+            }
             _ => None,
         }
     }
@@ -162,6 +200,15 @@ impl NetworkAddress {
             NetworkAddress::SpendAddress(cash_note_address) => {
                 RecordKey::new(cash_note_address.xorname())
             }
+            NetworkAddress::PointerAddress(pointer_address) => {
+                RecordKey::new(&pointer_address.xorname())
+            }
+            NetworkAddress::ScratchpadAddress(scratchpad_address) => {
+                RecordKey::new(&scratchpad_address.xorname())
+            }
+            NetworkAddress::ExpiringDataAddress(expiring_data_address) => {
+                RecordKey::new(expiring_data_address.xorname())
+            }
             NetworkAddress::PeerId(bytes) => RecordKey::new(bytes),
         }
     }
@@ -218,6 +265,18 @@ impl Debug for NetworkAddress {
                 "NetworkAddress::RegisterAddress({} - ",
                 &register_address.to_hex()[0..6]
             ),
+            NetworkAddress::PointerAddress(pointer_address) => format!(
+                "NetworkAddress::PointerAddress({} - ",
+                &pointer_address.to_hex()[0..6]
+            ),
+            NetworkAddress::ScratchpadAddress(scratchpad_address) => format!(
+                "NetworkAddress::ScratchpadAddress({} - ",
+                &scratchpad_address.to_hex()[0..6]
+            ),
+            NetworkAddress::ExpiringDataAddress(expiring_data_address) => format!(
+                "NetworkAddress::ExpiringDataAddress({} - ",
+                &expiring_data_address.to_hex()[0..6]
+            ),
             NetworkAddress::RecordKey(bytes) => format!(
                 "NetworkAddress::RecordKey({} - ",
                 &PrettyPrintRecordKey::from(&RecordKey::new(bytes)).no_kbucket_log()[0..6]
@@ -246,6 +305,15 @@ impl Display for NetworkAddress {
             NetworkAddress::RegisterAddress(addr) => {
                 write!(f, "NetworkAddress::RegisterAddress({addr:?})")
             }
+            NetworkAddress::PointerAddress(addr) => {
+                write!(f, "NetworkAddress::PointerAddress({addr:?})")
+            }
+            NetworkAddress::ScratchpadAddress(addr) => {
+                write!(f, "NetworkAddress::ScratchpadAddress({addr:?})")
+            }
+            NetworkAddress::ExpiringDataAddress(addr) => {
+                write!(f, "NetworkAddress::ExpiringDataAddress({addr:?})")
+            }
             NetworkAddress::RecordKey(key) => {
                 write!(f, "NetworkAddress::RecordKey({})", hex::encode(key))
             }
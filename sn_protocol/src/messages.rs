@@ -15,7 +15,7 @@ mod register;
 mod response;
 
 pub use self::{
-    chunk_proof::{ChunkProof, Nonce},
+    chunk_proof::{ChunkProof, Nonce, StorageChallenge},
     cmd::{Cmd, Hash},
     node_id::NodeId,
     query::Query,
@@ -26,6 +26,7 @@ pub use self::{
 use super::NetworkAddress;
 
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[allow(clippy::large_enum_variant)]
 /// A request to peers in the network
@@ -56,6 +57,35 @@ impl Request {
     }
 }
 
+/// The id of a request, which its sender and the peer handling it trace it under.
+///
+/// It's drawn at random by the sender, so that identical requests are told apart, and echoed in
+/// the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MsgId(u64);
+
+impl MsgId {
+    /// A new random id.
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+}
+
+impl fmt::Display for MsgId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// A `Request` or `Response` as sent to peers, with the id of the request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsgEnvelope<T> {
+    /// The id of the request, the one of the request answered for a response.
+    pub msg_id: MsgId,
+    /// The request or response.
+    pub msg: T,
+}
+
 impl std::fmt::Display for Response {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{self:?}")
@@ -34,6 +34,47 @@ impl ChunkProof {
     }
 }
 
+/// The most bytes of a record a `StorageChallenge` has the prover hash.
+const CHALLENGE_RANGE_LEN: u64 = 64 * 1024;
+
+/// A challenge for a node to prove it still holds a record, by hashing a range of the bytes of
+/// the record along with the nonce, i.e. hash(record[offset..offset + len] + nonce).
+///
+/// The range being picked by the verifier along with the nonce, the proof can't be computed
+/// ahead of time, nor from anything short of the whole record.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, PartialOrd)]
+pub struct StorageChallenge {
+    pub nonce: Nonce,
+    pub offset: u64,
+    pub len: u64,
+}
+
+impl StorageChallenge {
+    /// A challenge for a record of `record_len` bytes, the range being derived from the nonce.
+    pub fn new(nonce: Nonce, record_len: usize) -> Self {
+        let record_len = record_len as u64;
+        let offset = if record_len == 0 {
+            0
+        } else {
+            nonce % record_len
+        };
+        Self {
+            nonce,
+            offset,
+            len: (record_len - offset).min(CHALLENGE_RANGE_LEN),
+        }
+    }
+
+    /// The proof of holding the record, the range being cut to the bytes of the record.
+    pub fn prove(&self, record_value: &[u8]) -> ChunkProof {
+        let start = (self.offset as usize).min(record_value.len());
+        let end = start
+            .saturating_add(self.len as usize)
+            .min(record_value.len());
+        ChunkProof::new(&record_value[start..end], self.nonce)
+    }
+}
+
 fn sha3_256(input: &[u8]) -> [u8; 32] {
     use tiny_keccak::{Hasher, Sha3};
 
@@ -49,3 +90,33 @@ impl fmt::Debug for ChunkProof {
         f.debug_tuple("ChunkProof").field(&self.to_hex()).finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_challenges_cover_a_range_of_the_record() {
+        let record: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let challenge = StorageChallenge::new(150_000, record.len());
+        assert_eq!(challenge.offset, 150_000);
+        assert_eq!(challenge.len, 50_000);
+        assert!(challenge.prove(&record).verify(&challenge.prove(&record)));
+
+        // only the bytes in the range are proven.
+        let mut altered = record.clone();
+        altered[0] ^= 1;
+        assert!(challenge.prove(&record).verify(&challenge.prove(&altered)));
+        altered[160_000] ^= 1;
+        assert!(!challenge.prove(&record).verify(&challenge.prove(&altered)));
+
+        // nor can a proof be reused for another nonce.
+        let other = StorageChallenge::new(challenge.nonce + record.len() as u64, record.len());
+        assert_eq!(other.offset, challenge.offset);
+        assert!(!challenge.prove(&record).verify(&other.prove(&record)));
+
+        let challenge = StorageChallenge::new(7, 3);
+        assert_eq!((challenge.offset, challenge.len), (1, 2));
+        assert!(!challenge.prove(&record[..3]).verify(&challenge.prove(&[])));
+    }
+}
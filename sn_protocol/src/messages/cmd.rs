@@ -41,6 +41,21 @@ pub enum Cmd {
         bad_peer: NetworkAddress,
         bad_behaviour: String,
     },
+    /// Notify the peer that the sender is leaving the network, once it has handed its records over.
+    Departure {
+        /// The peer leaving, which has to be the sender.
+        peer: NetworkAddress,
+    },
+    /// Notify the peer that the sender is moving to a new key, for it not to take the sender
+    /// going away for churn.
+    KeyRotation {
+        /// The peer rotating its key, which has to be the sender.
+        peer: NetworkAddress,
+        /// The protobuf encoded public key the peer is moving to.
+        new_public_key: Vec<u8>,
+        /// The signature of the rotation by the new key.
+        signature: Vec<u8>,
+    },
 }
 
 impl std::fmt::Debug for Cmd {
@@ -69,6 +84,14 @@ impl std::fmt::Debug for Cmd {
                 .field("bad_peer", bad_peer)
                 .field("bad_behaviour", bad_behaviour)
                 .finish(),
+            Cmd::Departure { peer } => f
+                .debug_struct("Cmd::Departure")
+                .field("peer", peer)
+                .finish(),
+            Cmd::KeyRotation { peer, .. } => f
+                .debug_struct("Cmd::KeyRotation")
+                .field("peer", peer)
+                .finish(),
         }
     }
 }
@@ -80,6 +103,8 @@ impl Cmd {
             Cmd::Replicate { holder, .. } => holder.clone(),
             Cmd::QuoteVerification { target, .. } => target.clone(),
             Cmd::PeerConsideredAsBad { bad_peer, .. } => bad_peer.clone(),
+            Cmd::Departure { peer } => peer.clone(),
+            Cmd::KeyRotation { peer, .. } => peer.clone(),
         }
     }
 }
@@ -111,6 +136,16 @@ impl std::fmt::Display for Cmd {
                     f,
                     "Cmd::PeerConsideredAsBad({detected_by:?} consider peer {bad_peer:?} as bad, due to {bad_behaviour:?})")
             }
+            Cmd::Departure { peer } => {
+                write!(f, "Cmd::Departure({:?} is leaving)", peer.as_peer_id())
+            }
+            Cmd::KeyRotation { peer, .. } => {
+                write!(
+                    f,
+                    "Cmd::KeyRotation({:?} moves to a new key)",
+                    peer.as_peer_id()
+                )
+            }
         }
     }
 }
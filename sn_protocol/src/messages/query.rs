@@ -6,8 +6,13 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{messages::Nonce, NetworkAddress};
+use crate::{
+    messages::{Nonce, StorageChallenge},
+    NetworkAddress,
+};
 use serde::{Deserialize, Serialize};
+use sn_registers::EntryHash;
+use std::collections::BTreeSet;
 
 /// Data queries - retrieving data and inspecting their structure.
 ///
@@ -38,8 +43,30 @@ pub enum Query {
         /// The random nonce that the node uses to produce the Proof (i.e., hash(record+nonce))
         nonce: Nonce,
     },
+    /// Challenge the node to prove it still holds the chunk with the given NetworkAddress, by
+    /// hashing the range of its bytes given by the challenge into a [`GetStorageProof`].
+    ///
+    /// [`GetStorageProof`]: super::QueryResponse::GetStorageProof
+    GetStorageProof {
+        /// The Address of the chunk that we are trying to verify.
+        key: NetworkAddress,
+        /// The nonce and the range of bytes the node uses to produce the Proof
+        challenge: StorageChallenge,
+    },
     /// Queries close_group peers whether the target peer is a bad_node
     CheckNodeInProblem(NetworkAddress),
+    /// Wait for the Register at the given key to be edited.
+    ///
+    /// The peer holds the query till its replica of the Register has tips other than the given
+    /// ones, or the watch expires, then responds with a [`WatchRegister`] holding its replica.
+    ///
+    /// [`WatchRegister`]: super::QueryResponse::WatchRegister
+    WatchRegister {
+        /// Key of the Register to watch
+        key: NetworkAddress,
+        /// The tips of the Register known to the requester
+        tips: BTreeSet<EntryHash>,
+    },
 }
 
 impl Query {
@@ -51,6 +78,8 @@ impl Query {
             // and the destination shall be decided by the requester already.
             Query::GetReplicatedRecord { key, .. } => key.clone(),
             Query::GetChunkExistenceProof { key, .. } => key.clone(),
+            Query::GetStorageProof { key, .. } => key.clone(),
+            Query::WatchRegister { key, .. } => key.clone(),
         }
     }
 }
@@ -67,9 +96,15 @@ impl std::fmt::Display for Query {
             Query::GetChunkExistenceProof { key, nonce } => {
                 write!(f, "Query::GetChunkExistenceProof({key:?} {nonce:?})")
             }
+            Query::GetStorageProof { key, challenge } => {
+                write!(f, "Query::GetStorageProof({key:?} {challenge:?})")
+            }
             Query::CheckNodeInProblem(address) => {
                 write!(f, "Query::CheckNodeInProblem({address:?})")
             }
+            Query::WatchRegister { key, tips } => {
+                write!(f, "Query::WatchRegister({key:?} {} tips)", tips.len())
+            }
         }
     }
 }
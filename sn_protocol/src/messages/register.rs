@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use sn_registers::{Register, RegisterAddress, RegisterOp};
+use sn_registers::{PermissionsUpdate, Register, RegisterAddress, RegisterOp, RegisterSnapshot};
 
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +23,10 @@ pub enum RegisterCmd {
     },
     /// Edit the register
     Edit(RegisterOp),
+    /// Grant permissions on the register, signed by its owner
+    Grant(PermissionsUpdate),
+    /// Compact the register with a snapshot, signed by its owner
+    Snapshot(RegisterSnapshot),
 }
 
 /// Custom debug implementation to avoid printing the whole register
@@ -34,6 +38,10 @@ impl std::fmt::Debug for RegisterCmd {
                 write!(f, "RegisterCmd::Create({:?})", register.address())
             }
             RegisterCmd::Edit(op) => write!(f, "RegisterCmd::Edit({:?})", op.address()),
+            RegisterCmd::Grant(grant) => write!(f, "RegisterCmd::Grant({:?})", grant.address()),
+            RegisterCmd::Snapshot(snapshot) => {
+                write!(f, "RegisterCmd::Snapshot({:?})", snapshot.address())
+            }
         }
     }
 }
@@ -44,6 +52,8 @@ impl RegisterCmd {
         match self {
             Self::Create { register, .. } => *register.address(),
             Self::Edit(op) => op.address(),
+            Self::Grant(grant) => *grant.address(),
+            Self::Snapshot(snapshot) => *snapshot.address(),
         }
     }
 }
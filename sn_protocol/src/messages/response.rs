@@ -52,6 +52,18 @@ pub enum QueryResponse {
     ///
     /// [`GetChunkExistenceProof`]: crate::messages::Query::GetChunkExistenceProof
     GetChunkExistenceProof(Result<ChunkProof>),
+    // ===== StorageProof =====
+    //
+    /// Response to [`GetStorageProof`]
+    ///
+    /// [`GetStorageProof`]: crate::messages::Query::GetStorageProof
+    GetStorageProof(Result<ChunkProof>),
+    // ===== WatchRegister =====
+    //
+    /// Response to [`WatchRegister`], with the serialised Register record of the holder
+    ///
+    /// [`WatchRegister`]: crate::messages::Query::WatchRegister
+    WatchRegister(Result<(NetworkAddress, Bytes)>),
 }
 
 // Debug implementation for QueryResponse, to avoid printing Vec<u8>
@@ -94,6 +106,21 @@ impl Debug for QueryResponse {
             QueryResponse::GetChunkExistenceProof(proof) => {
                 write!(f, "GetChunkExistenceProof(proof: {proof:?})")
             }
+            QueryResponse::GetStorageProof(proof) => {
+                write!(f, "GetStorageProof(proof: {proof:?})")
+            }
+            QueryResponse::WatchRegister(result) => match result {
+                Ok((holder, data)) => {
+                    write!(
+                        f,
+                        "WatchRegister(Ok((holder: {holder:?}, datalen: {:?})))",
+                        data.len()
+                    )
+                }
+                Err(err) => {
+                    write!(f, "WatchRegister(Err({err:?}))")
+                }
+            },
         }
     }
 }
@@ -116,4 +143,14 @@ pub enum CmdResponse {
     //
     /// Response to the considered as bad notification
     PeerConsideredAsBad(Result<()>),
+    //
+    // ===== Departure =====
+    //
+    /// Response to the departure notification
+    Departure(Result<()>),
+    //
+    // ===== KeyRotation =====
+    //
+    /// Response to the key rotation notification
+    KeyRotation(Result<()>),
 }
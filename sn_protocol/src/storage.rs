@@ -8,16 +8,25 @@
 
 mod address;
 mod chunks;
+mod expiring_data;
 mod header;
+mod pointer;
+mod scratchpad;
 
 use crate::error::Error;
 use core::fmt;
 use std::{str::FromStr, time::Duration};
 
 pub use self::{
-    address::{ChunkAddress, RegisterAddress, SpendAddress},
+    address::{
+        ChunkAddress, ExpiringDataAddress, PointerAddress, RegisterAddress, ScratchpadAddress,
+        SpendAddress,
+    },
     chunks::Chunk,
+    expiring_data::{ExpiringData, MAX_EXPIRING_DATA_TTL},
     header::{try_deserialize_record, try_serialize_record, RecordHeader, RecordKind, RecordType},
+    pointer::Pointer,
+    scratchpad::{Scratchpad, MAX_SCRATCHPAD_SIZE},
 };
 
 /// Represents the strategy for retrying operations. This encapsulates both the duration it may take for an operation to
@@ -7,7 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod chunk;
+mod expiring_data;
+mod pointer;
+mod scratchpad;
 
-pub use self::chunk::ChunkAddress;
+pub use self::{
+    chunk::ChunkAddress, expiring_data::ExpiringDataAddress, pointer::PointerAddress,
+    scratchpad::ScratchpadAddress,
+};
 pub use sn_registers::RegisterAddress;
 pub use sn_transfers::SpendAddress;
@@ -0,0 +1,55 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bls::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::{fmt, hash::Hash};
+use xor_name::XorName;
+
+/// Prefixes the content the name of a Pointer is computed from,
+/// so that it differs from the name of a Register with the same meta and owner.
+const POINTER_NAME_PREFIX: &[u8] = b"sn_pointer";
+
+/// Address of a Pointer, derived from a user chosen meta and the owner of the Pointer.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct PointerAddress {
+    meta: XorName,
+    owner: PublicKey,
+}
+
+impl PointerAddress {
+    /// Creates a new PointerAddress.
+    pub fn new(meta: XorName, owner: PublicKey) -> Self {
+        Self { meta, owner }
+    }
+
+    /// Returns the name, used to locate the Pointer on the network.
+    pub fn xorname(&self) -> XorName {
+        XorName::from_content_parts(&[POINTER_NAME_PREFIX, &self.meta.0, &self.owner.to_bytes()])
+    }
+
+    /// Returns the user chosen meta.
+    pub fn meta(&self) -> XorName {
+        self.meta
+    }
+
+    /// Returns the owner.
+    pub fn owner(&self) -> PublicKey {
+        self.owner
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.xorname())
+    }
+}
+
+impl std::fmt::Debug for PointerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PointerAddress({})", &self.to_hex()[0..6])
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::ExpiringDataAddress;
+use crate::{error::Error, NetworkAddress};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use xor_name::XorName;
+
+/// The longest an ExpiringData can be held by the network for.
+pub const MAX_EXPIRING_DATA_TTL: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// How far ahead of the holders the clock of the storer can be.
+const CLOCK_SKEW_ALLOWANCE: Duration = Duration::from_secs(600);
+
+/// Prefixes the content the name of an ExpiringData is computed from,
+/// so that it differs from the name of a Chunk with the same data.
+const EXPIRING_DATA_NAME_PREFIX: &[u8] = b"sn_expiring_data";
+
+/// ExpiringData, immutable data held by the network until the expiry it was stored with, e.g.
+/// a message or a temporary upload.
+///
+/// The expiry is part of what the address is computed from, so it's committed to when the data
+/// is stored and can't be extended afterwards. The holders drop the data once it expires, and
+/// no longer serve it in the meantime.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub struct ExpiringData {
+    address: ExpiringDataAddress,
+    /// Seconds since the UNIX epoch the data expires at.
+    expires_at: u64,
+    data: Bytes,
+}
+
+impl ExpiringData {
+    /// Creates a new ExpiringData, to be held for `ttl` from now.
+    pub fn new(data: Bytes, ttl: Duration) -> Result<Self, Error> {
+        if ttl > MAX_EXPIRING_DATA_TTL {
+            return Err(Error::ExpiringDataTtlTooLong(ttl));
+        }
+        let expires_at = (SystemTime::now() + ttl)
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| Error::ExpiringDataTtlTooLong(ttl))?
+            .as_secs();
+        Ok(Self {
+            address: Self::address_for(&data, expires_at),
+            expires_at,
+            data,
+        })
+    }
+
+    /// Returns the address.
+    pub fn address(&self) -> &ExpiringDataAddress {
+        &self.address
+    }
+
+    /// Returns the NetworkAddress
+    pub fn network_address(&self) -> NetworkAddress {
+        NetworkAddress::ExpiringDataAddress(self.address)
+    }
+
+    /// Returns the data.
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Returns the time the data expires at.
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.expires_at)
+    }
+
+    /// Returns whether the data expired by `now`.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at() <= now
+    }
+
+    /// Checks the address matches the data and the expiry, and that the data is still to be held
+    /// at `now`, for no longer than it can be.
+    pub fn verify(&self, now: SystemTime) -> Result<(), Error> {
+        if self.address != Self::address_for(&self.data, self.expires_at) {
+            return Err(Error::ExpiringDataAddressMismatch(Box::new(self.address)));
+        }
+        if self.is_expired(now) {
+            return Err(Error::ExpiringDataExpired(Box::new(self.address)));
+        }
+        let ttl = self.expires_at().duration_since(now).unwrap_or_default();
+        if ttl > MAX_EXPIRING_DATA_TTL + CLOCK_SKEW_ALLOWANCE {
+            return Err(Error::ExpiringDataTtlTooLong(ttl));
+        }
+        Ok(())
+    }
+
+    fn address_for(data: &[u8], expires_at: u64) -> ExpiringDataAddress {
+        ExpiringDataAddress::new(XorName::from_content_parts(&[
+            EXPIRING_DATA_NAME_PREFIX,
+            &expires_at.to_be_bytes(),
+            data,
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiring_data_is_bound_to_its_expiry() -> Result<(), Error> {
+        let now = SystemTime::now();
+        let data = ExpiringData::new(Bytes::from_static(b"message"), Duration::from_secs(60))?;
+        data.verify(now)?;
+        assert!(!data.is_expired(now));
+        assert_eq!(
+            data.verify(now + Duration::from_secs(61)),
+            Err(Error::ExpiringDataExpired(Box::new(*data.address())))
+        );
+
+        // the expiry can't be extended without the address changing.
+        let mut extended = data.clone();
+        extended.expires_at += 3600;
+        assert_eq!(
+            extended.verify(now),
+            Err(Error::ExpiringDataAddressMismatch(Box::new(
+                *data.address()
+            )))
+        );
+
+        let too_long = MAX_EXPIRING_DATA_TTL + Duration::from_secs(1);
+        assert_eq!(
+            ExpiringData::new(Bytes::new(), too_long),
+            Err(Error::ExpiringDataTtlTooLong(too_long))
+        );
+        Ok(())
+    }
+}
@@ -29,13 +29,19 @@ pub struct RecordHeader {
     pub kind: RecordKind,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
 pub enum RecordKind {
     Chunk,
     ChunkWithPayment,
     Spend,
     Register,
     RegisterWithPayment,
+    Pointer,
+    PointerWithPayment,
+    Scratchpad,
+    ScratchpadWithPayment,
+    ExpiringData,
+    ExpiringDataWithPayment,
 }
 
 impl Serialize for RecordKind {
@@ -49,6 +55,12 @@ impl Serialize for RecordKind {
             Self::Spend => serializer.serialize_u32(2),
             Self::Register => serializer.serialize_u32(3),
             Self::RegisterWithPayment => serializer.serialize_u32(4),
+            Self::Pointer => serializer.serialize_u32(5),
+            Self::PointerWithPayment => serializer.serialize_u32(6),
+            Self::Scratchpad => serializer.serialize_u32(7),
+            Self::ScratchpadWithPayment => serializer.serialize_u32(8),
+            Self::ExpiringData => serializer.serialize_u32(9),
+            Self::ExpiringDataWithPayment => serializer.serialize_u32(10),
         }
     }
 }
@@ -65,6 +77,12 @@ impl<'de> Deserialize<'de> for RecordKind {
             2 => Ok(Self::Spend),
             3 => Ok(Self::Register),
             4 => Ok(Self::RegisterWithPayment),
+            5 => Ok(Self::Pointer),
+            6 => Ok(Self::PointerWithPayment),
+            7 => Ok(Self::Scratchpad),
+            8 => Ok(Self::ScratchpadWithPayment),
+            9 => Ok(Self::ExpiringData),
+            10 => Ok(Self::ExpiringDataWithPayment),
             _ => Err(serde::de::Error::custom(
                 "Unexpected integer for RecordKind variant",
             )),
@@ -185,6 +203,42 @@ mod tests {
         .try_serialize()?;
         assert_eq!(register.len(), RecordHeader::SIZE);
 
+        let pointer_with_payment = RecordHeader {
+            kind: RecordKind::PointerWithPayment,
+        }
+        .try_serialize()?;
+        assert_eq!(pointer_with_payment.len(), RecordHeader::SIZE);
+
+        let pointer = RecordHeader {
+            kind: RecordKind::Pointer,
+        }
+        .try_serialize()?;
+        assert_eq!(pointer.len(), RecordHeader::SIZE);
+
+        let scratchpad_with_payment = RecordHeader {
+            kind: RecordKind::ScratchpadWithPayment,
+        }
+        .try_serialize()?;
+        assert_eq!(scratchpad_with_payment.len(), RecordHeader::SIZE);
+
+        let scratchpad = RecordHeader {
+            kind: RecordKind::Scratchpad,
+        }
+        .try_serialize()?;
+        assert_eq!(scratchpad.len(), RecordHeader::SIZE);
+
+        let expiring_data_with_payment = RecordHeader {
+            kind: RecordKind::ExpiringDataWithPayment,
+        }
+        .try_serialize()?;
+        assert_eq!(expiring_data_with_payment.len(), RecordHeader::SIZE);
+
+        let expiring_data = RecordHeader {
+            kind: RecordKind::ExpiringData,
+        }
+        .try_serialize()?;
+        assert_eq!(expiring_data.len(), RecordHeader::SIZE);
+
         Ok(())
     }
 }
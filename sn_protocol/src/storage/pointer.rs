@@ -0,0 +1,127 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::PointerAddress;
+use crate::{error::Error, NetworkAddress};
+use bls::{SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+/// Pointer, a small record signed by its owner, pointing at any address on the network.
+///
+/// The owner can repoint it atomically by signing a new version with a higher counter, giving
+/// a stable entry point to mutable content, e.g. the latest version of a site or the current
+/// head of a log. The holders only keep the version with the highest counter.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub struct Pointer {
+    address: PointerAddress,
+    /// Version of the Pointer, incremented each time it is repointed.
+    counter: u64,
+    target: NetworkAddress,
+    /// Signature of the owner on the address, the counter and the target.
+    signature: Signature,
+}
+
+impl Pointer {
+    /// Creates a new version of the Pointer at the address, signing it with the owner key.
+    pub fn new(
+        address: PointerAddress,
+        counter: u64,
+        target: NetworkAddress,
+        owner: &SecretKey,
+    ) -> Result<Self, Error> {
+        if owner.public_key() != address.owner() {
+            return Err(Error::PointerOwnerMismatch(Box::new(address)));
+        }
+        let signature = owner.sign(Self::bytes_for_signing(&address, counter, &target)?);
+        Ok(Self {
+            address,
+            counter,
+            target,
+            signature,
+        })
+    }
+
+    /// Returns the address.
+    pub fn address(&self) -> &PointerAddress {
+        &self.address
+    }
+
+    /// Returns the NetworkAddress
+    pub fn network_address(&self) -> NetworkAddress {
+        NetworkAddress::PointerAddress(self.address)
+    }
+
+    /// Returns the version of the Pointer.
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    /// Returns the address the Pointer points at.
+    pub fn target(&self) -> &NetworkAddress {
+        &self.target
+    }
+
+    /// Checks the Pointer was signed by its owner.
+    pub fn verify(&self) -> Result<(), Error> {
+        let bytes = Self::bytes_for_signing(&self.address, self.counter, &self.target)?;
+        if !self.address.owner().verify(&self.signature, bytes) {
+            return Err(Error::InvalidPointerSignature(Box::new(self.address)));
+        }
+        Ok(())
+    }
+
+    /// Returns whether this version of the Pointer supersedes the other one, i.e. has a higher
+    /// counter. Versions signed with the same counter are ordered by signature, so that all the
+    /// holders keep the same one.
+    pub fn supersedes(&self, other: &Self) -> bool {
+        (self.counter, self.signature.to_bytes()) > (other.counter, other.signature.to_bytes())
+    }
+
+    fn bytes_for_signing(
+        address: &PointerAddress,
+        counter: u64,
+        target: &NetworkAddress,
+    ) -> Result<Vec<u8>, Error> {
+        rmp_serde::to_vec(&(address, counter, target)).map_err(|_| Error::RecordParsingFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ChunkAddress;
+    use bls::rand::thread_rng;
+    use xor_name::XorName;
+
+    #[test]
+    fn pointers_are_signed_and_versioned() -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let owner = SecretKey::random();
+        let address = PointerAddress::new(XorName::random(&mut rng), owner.public_key());
+        let target =
+            NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(&mut rng)));
+
+        let first = Pointer::new(address, 0, target.clone(), &owner)?;
+        first.verify()?;
+        let repointed = Pointer::new(address, 1, target, &owner)?;
+        assert!(repointed.supersedes(&first));
+        assert!(!first.supersedes(&repointed));
+
+        assert_eq!(
+            Pointer::new(address, 2, first.target().clone(), &SecretKey::random()),
+            Err(Error::PointerOwnerMismatch(Box::new(address)))
+        );
+        let mut forged = repointed;
+        forged.counter = 5;
+        assert_eq!(
+            forged.verify(),
+            Err(Error::InvalidPointerSignature(Box::new(address)))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::ScratchpadAddress;
+use crate::{error::Error, NetworkAddress};
+use bls::{SecretKey, Signature};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// Maximum size of the data a Scratchpad can hold.
+pub const MAX_SCRATCHPAD_SIZE: usize = 4 * 1024;
+
+/// Scratchpad, a small record of data signed by its owner, who can overwrite it.
+///
+/// Unlike a Register, it holds no history: each version signed with a higher counter replaces
+/// the previous one, so it suits data only the latest value of which matters, e.g. session
+/// state or app settings. The data is stored as is, so it should be encrypted if private.
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub struct Scratchpad {
+    address: ScratchpadAddress,
+    /// Version of the Scratchpad, incremented each time it is overwritten.
+    counter: u64,
+    data: Bytes,
+    /// Signature of the owner on the address, the counter and the data.
+    signature: Signature,
+}
+
+impl Scratchpad {
+    /// Creates a new version of the Scratchpad at the address, signing it with the owner key.
+    pub fn new(
+        address: ScratchpadAddress,
+        counter: u64,
+        data: Bytes,
+        owner: &SecretKey,
+    ) -> Result<Self, Error> {
+        if owner.public_key() != address.owner() {
+            return Err(Error::ScratchpadOwnerMismatch(Box::new(address)));
+        }
+        if data.len() > MAX_SCRATCHPAD_SIZE {
+            return Err(Error::ScratchpadTooBig(data.len()));
+        }
+        let signature = owner.sign(Self::bytes_for_signing(&address, counter, &data)?);
+        Ok(Self {
+            address,
+            counter,
+            data,
+            signature,
+        })
+    }
+
+    /// Returns the address.
+    pub fn address(&self) -> &ScratchpadAddress {
+        &self.address
+    }
+
+    /// Returns the NetworkAddress
+    pub fn network_address(&self) -> NetworkAddress {
+        NetworkAddress::ScratchpadAddress(self.address)
+    }
+
+    /// Returns the version of the Scratchpad.
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    /// Returns the data.
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Checks the Scratchpad was signed by its owner, and holds no more than it can.
+    pub fn verify(&self) -> Result<(), Error> {
+        if self.data.len() > MAX_SCRATCHPAD_SIZE {
+            return Err(Error::ScratchpadTooBig(self.data.len()));
+        }
+        let bytes = Self::bytes_for_signing(&self.address, self.counter, &self.data)?;
+        if !self.address.owner().verify(&self.signature, bytes) {
+            return Err(Error::InvalidScratchpadSignature(Box::new(self.address)));
+        }
+        Ok(())
+    }
+
+    /// Returns whether this version of the Scratchpad supersedes the other one, i.e. has a
+    /// higher counter. Versions signed with the same counter are ordered by signature, so that
+    /// all the holders keep the same one.
+    pub fn supersedes(&self, other: &Self) -> bool {
+        (self.counter, self.signature.to_bytes()) > (other.counter, other.signature.to_bytes())
+    }
+
+    fn bytes_for_signing(
+        address: &ScratchpadAddress,
+        counter: u64,
+        data: &Bytes,
+    ) -> Result<Vec<u8>, Error> {
+        rmp_serde::to_vec(&(address, counter, data)).map_err(|_| Error::RecordParsingFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::rand::thread_rng;
+    use xor_name::XorName;
+
+    #[test]
+    fn scratchpads_are_signed_bounded_and_overwritten() -> Result<(), Error> {
+        let owner = SecretKey::random();
+        let address =
+            ScratchpadAddress::new(XorName::random(&mut thread_rng()), owner.public_key());
+
+        let first = Scratchpad::new(address, 0, Bytes::from_static(b"settings"), &owner)?;
+        first.verify()?;
+        let overwritten = Scratchpad::new(address, 1, Bytes::new(), &owner)?;
+        assert!(overwritten.supersedes(&first));
+        assert!(!first.supersedes(&overwritten));
+
+        let too_big = Bytes::from(vec![0; MAX_SCRATCHPAD_SIZE + 1]);
+        assert_eq!(
+            Scratchpad::new(address, 2, too_big.clone(), &owner),
+            Err(Error::ScratchpadTooBig(MAX_SCRATCHPAD_SIZE + 1))
+        );
+        assert_eq!(
+            Scratchpad::new(address, 2, Bytes::new(), &SecretKey::random()),
+            Err(Error::ScratchpadOwnerMismatch(Box::new(address)))
+        );
+
+        let mut forged = first.clone();
+        forged.counter = 5;
+        assert_eq!(
+            forged.verify(),
+            Err(Error::InvalidScratchpadSignature(Box::new(address)))
+        );
+        let mut stuffed = first;
+        stuffed.data = too_big;
+        assert_eq!(
+            stuffed.verify(),
+            Err(Error::ScratchpadTooBig(MAX_SCRATCHPAD_SIZE + 1))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, Entry, Error, RegisterAddress};
+
+use bls::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+/// Marks the entries holding an `AuthoredEntry`, ahead of its serialised bytes.
+const AUTHORED_ENTRY_MARK: &[u8] = b"\0sn_register_authored\0";
+
+/// An entry signed by its author, who may be neither the owner of the Register
+/// nor the writer who wrote it onto the Register.
+///
+/// The author's key is recorded along with the entry, so that whoever reads a multi-writer
+/// Register can check who each entry comes from, whatever replica or writer relayed it.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct AuthoredEntry {
+    /// The address of the Register the entry is signed for, so it can't be replayed in others.
+    address: RegisterAddress,
+    content: Entry,
+    author: PublicKey,
+    /// The signature of the author on the address and the content.
+    signature: Signature,
+}
+
+impl AuthoredEntry {
+    /// Sign the content with the author's key, for the Register at the address.
+    pub fn new(address: RegisterAddress, content: Entry, author: &SecretKey) -> Result<Self> {
+        let signature = author.sign(Self::bytes_for_signing(&address, &content)?);
+        Ok(Self {
+            address,
+            content,
+            author: author.public_key(),
+            signature,
+        })
+    }
+
+    /// The address of the Register the entry is signed for.
+    pub fn address(&self) -> &RegisterAddress {
+        &self.address
+    }
+
+    /// The content signed by the author.
+    pub fn content(&self) -> &Entry {
+        &self.content
+    }
+
+    /// The key of the author.
+    pub fn author(&self) -> PublicKey {
+        self.author
+    }
+
+    /// Checks the entry was signed by its author for the Register at the address.
+    pub fn verify(&self, address: &RegisterAddress) -> Result<()> {
+        if &self.address != address {
+            return Err(Error::RegisterAddrMismatch {
+                dst_addr: Box::new(self.address),
+                reg_addr: Box::new(*address),
+            });
+        }
+        let bytes = Self::bytes_for_signing(&self.address, &self.content)?;
+        if !self.author.verify(&self.signature, bytes) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Serialise into the entry to write onto the Register.
+    pub fn to_entry(&self) -> Result<Entry> {
+        let bytes = rmp_serde::to_vec(self).map_err(|_| Error::SerialisationFailed)?;
+        Ok([AUTHORED_ENTRY_MARK, &bytes].concat())
+    }
+
+    /// Deserialise the entry read from a Register, returning `None` if it isn't an authored one.
+    /// Note the signature is not checked, see `verify`.
+    pub fn from_entry(entry: &[u8]) -> Result<Option<Self>> {
+        let Some(bytes) = entry.strip_prefix(AUTHORED_ENTRY_MARK) else {
+            return Ok(None);
+        };
+        rmp_serde::from_slice(bytes)
+            .map(Some)
+            .map_err(|_| Error::InvalidAuthoredEntry)
+    }
+
+    fn bytes_for_signing(address: &RegisterAddress, content: &Entry) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(&(address, content)).map_err(|_| Error::SerialisationFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authored_entries_are_attributed_and_verified() -> eyre::Result<()> {
+        let author_sk = SecretKey::random();
+        let address =
+            RegisterAddress::new(xor_name::rand::random(), SecretKey::random().public_key());
+
+        let authored = AuthoredEntry::new(address, b"hello".to_vec(), &author_sk)?;
+        authored.verify(&address)?;
+        let entry = authored.to_entry()?;
+        let decoded = AuthoredEntry::from_entry(&entry)?.expect("an authored entry");
+        assert_eq!(decoded, authored);
+        assert_eq!(decoded.author(), author_sk.public_key());
+
+        // plain entries aren't authored, while corrupted ones are invalid
+        assert_eq!(AuthoredEntry::from_entry(b"hello")?, None);
+        assert_eq!(
+            AuthoredEntry::from_entry(&entry[..entry.len() - 1]),
+            Err(Error::InvalidAuthoredEntry)
+        );
+
+        // the entry can't be replayed in another Register, nor its content tampered with
+        let other = RegisterAddress::new(xor_name::rand::random(), address.owner());
+        assert!(matches!(
+            authored.verify(&other),
+            Err(Error::RegisterAddrMismatch { .. })
+        ));
+        let mut forged = authored;
+        forged.content = b"goodbye".to_vec();
+        assert_eq!(forged.verify(&address), Err(Error::InvalidSignature));
+        Ok(())
+    }
+}
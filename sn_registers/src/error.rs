@@ -51,6 +51,9 @@ pub enum Error {
     /// Invalid Signature found in register op
     #[error("Invalid signature")]
     InvalidSignature,
+    /// The entry is marked as authored but doesn't hold an authored entry
+    #[error("Invalid authored entry")]
+    InvalidAuthoredEntry,
     /// Missing Signature when expecting one in register op
     #[error("Missing signature")]
     MissingSignature,
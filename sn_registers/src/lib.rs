@@ -7,18 +7,26 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod address;
+mod authored_entry;
 pub(crate) mod error;
+mod merkle_dag;
 mod metadata;
 mod permissions;
+mod policy;
 pub(crate) mod reg_crdt;
 pub(crate) mod register;
 mod register_op;
+mod snapshot;
 
 pub use self::{
     address::RegisterAddress,
+    authored_entry::AuthoredEntry,
     error::Error,
+    merkle_dag::MerkleDag,
     metadata::{Entry, EntryHash},
-    permissions::Permissions,
-    register::{Register, SignedRegister},
+    permissions::{Permissions, PermissionsUpdate},
+    policy::{ConflictPolicy, TimestampFn, Tip, TipReducer},
+    register::{Register, SignedRegister, MAX_REG_ENTRY_SIZE},
     register_op::RegisterOp,
+    snapshot::RegisterSnapshot,
 };
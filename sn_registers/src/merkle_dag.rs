@@ -0,0 +1,192 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::Entry;
+
+use crdts::merkle_reg::{Hash, Node as MerkleDagEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The entries of a Register, as a Merkle DAG: every entry is written atop the entries it
+/// supersedes, its children, and the entries no other one was written atop are the current ones.
+///
+/// This is the `MerkleReg` of `crdts`, which it's serialised as, but for the DAG being able to
+/// start from an entry whose children it doesn't hold, which is how a Register compacted by a
+/// snapshot starts from the cutoff entry.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MerkleDag {
+    /// The hashes of the current entries.
+    roots: BTreeSet<Hash>,
+    /// The entries whose children are held.
+    dag: BTreeMap<Hash, MerkleDagEntry<Entry>>,
+    /// The entries whose children aren't all held yet, which are taken once they are.
+    orphans: BTreeMap<Hash, MerkleDagEntry<Entry>>,
+}
+
+impl MerkleDag {
+    /// A DAG holding no entries.
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// A DAG starting from the entry, without its children.
+    pub(crate) fn with_root(entry: MerkleDagEntry<Entry>) -> Self {
+        let hash = entry.hash();
+        Self {
+            roots: BTreeSet::from([hash]),
+            dag: BTreeMap::from([(hash, entry)]),
+            orphans: BTreeMap::new(),
+        }
+    }
+
+    /// The current entries, along with their hashes.
+    pub fn read(&self) -> impl Iterator<Item = (Hash, &MerkleDagEntry<Entry>)> {
+        self.roots
+            .iter()
+            .filter_map(|root| self.dag.get(root).map(|entry| (*root, entry)))
+    }
+
+    /// The entry at `hash`, if held, be it an orphan.
+    ///
+    /// The history of the Register is walked by pairing this with the children of the entries.
+    pub fn node(&self, hash: Hash) -> Option<&MerkleDagEntry<Entry>> {
+        self.dag.get(&hash).or_else(|| self.orphans.get(&hash))
+    }
+
+    /// The entries whose children are held.
+    pub fn all_nodes(&self) -> impl Iterator<Item = &MerkleDagEntry<Entry>> {
+        self.dag.values()
+    }
+
+    /// The entries whose children aren't all held yet.
+    pub fn orphans(&self) -> impl Iterator<Item = &MerkleDagEntry<Entry>> {
+        self.orphans.values()
+    }
+
+    /// The children of the entry at `hash`, along with their hashes.
+    pub fn children(&self, hash: Hash) -> impl Iterator<Item = (Hash, &MerkleDagEntry<Entry>)> {
+        self.dag
+            .get(&hash)
+            .into_iter()
+            .flat_map(|entry| &entry.children)
+            .filter_map(|child| self.dag.get(child).map(|entry| (*child, entry)))
+    }
+
+    /// The number of entries whose children are held.
+    pub fn num_nodes(&self) -> usize {
+        self.dag.len()
+    }
+
+    /// The number of entries whose children aren't all held yet.
+    pub fn num_orphans(&self) -> usize {
+        self.orphans.len()
+    }
+
+    /// Adds the entry, as an orphan until its children are all held.
+    pub(crate) fn apply(&mut self, entry: MerkleDagEntry<Entry>) {
+        let hash = entry.hash();
+        if self.dag.contains_key(&hash) || self.orphans.contains_key(&hash) {
+            return;
+        }
+        if !self.holds_all(&entry.children) {
+            let _ = self.orphans.insert(hash, entry);
+            return;
+        }
+
+        for child in &entry.children {
+            let _ = self.roots.remove(child);
+        }
+        let _ = self.roots.insert(hash);
+        let _ = self.dag.insert(hash, entry);
+
+        let ready: Vec<Hash> = self
+            .orphans
+            .iter()
+            .filter(|(_, orphan)| self.holds_all(&orphan.children))
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in ready {
+            if let Some(orphan) = self.orphans.remove(&hash) {
+                self.apply(orphan);
+            }
+        }
+    }
+
+    /// Adds the entries of the other DAG, orphans included.
+    pub(crate) fn merge(&mut self, other: Self) {
+        for (_, entry) in other.dag.into_iter().chain(other.orphans) {
+            self.apply(entry);
+        }
+    }
+
+    fn holds_all(&self, hashes: &BTreeSet<Hash>) -> bool {
+        hashes.iter().all(|hash| self.dag.contains_key(hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crdts::{merkle_reg::MerkleReg, CmRDT};
+    use eyre::Result;
+
+    #[test]
+    fn merkle_dags_are_serialised_as_merkle_regs() -> Result<()> {
+        let mut reg = MerkleReg::new();
+        let first = reg.write(b"first".to_vec(), BTreeSet::new());
+        reg.apply(first.clone());
+        let second = reg.write(b"second".to_vec(), BTreeSet::from([first.hash()]));
+        // the child of the orphan is missing
+        let orphan = reg.write(b"orphan".to_vec(), BTreeSet::from([[0; 32]]));
+        reg.apply(second.clone());
+        reg.apply(orphan.clone());
+
+        let bytes = rmp_serde::to_vec(&reg)?;
+        let dag: MerkleDag = rmp_serde::from_slice(&bytes)?;
+        assert_eq!(
+            dag.read().map(|(hash, _)| hash).collect::<Vec<_>>(),
+            vec![second.hash()]
+        );
+        assert_eq!((dag.num_nodes(), dag.num_orphans()), (2, 1));
+        assert_eq!(dag.node(orphan.hash()), Some(&orphan));
+        assert_eq!(rmp_serde::to_vec(&dag)?, bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn merkle_dags_take_the_entries_written_atop_their_root() {
+        let root = MerkleDagEntry {
+            children: BTreeSet::from([[1; 32]]),
+            value: b"cutoff".to_vec(),
+        };
+        let mut dag = MerkleDag::with_root(root.clone());
+        let next = MerkleDagEntry {
+            children: BTreeSet::from([root.hash()]),
+            value: b"next".to_vec(),
+        };
+        let last = MerkleDagEntry {
+            children: BTreeSet::from([next.hash()]),
+            value: b"last".to_vec(),
+        };
+        // taken once the entry it was written atop is
+        dag.apply(last.clone());
+        assert_eq!(dag.num_orphans(), 1);
+        dag.apply(next.clone());
+        assert_eq!((dag.num_nodes(), dag.num_orphans()), (3, 0));
+        assert_eq!(
+            dag.read().map(|(hash, _)| hash).collect::<Vec<_>>(),
+            vec![last.hash()]
+        );
+        assert_eq!(
+            dag.children(next.hash())
+                .map(|(hash, _)| hash)
+                .collect::<Vec<_>>(),
+            vec![root.hash()]
+        );
+    }
+}
@@ -6,7 +6,8 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use bls::PublicKey;
+use crate::{error::Result, Error, RegisterAddress};
+use bls::{PublicKey, SecretKey, Signature};
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeSet, hash::Hash};
 
@@ -59,3 +60,63 @@ impl Permissions {
         }
     }
 }
+
+/// Permissions granted to more users after a Register was created, signed by its owner.
+///
+/// The grants of a Register only add to its permissions: they are merged like its entries, so
+/// that the replicas converge, and a writer can't be removed once granted.
+#[derive(Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
+pub struct PermissionsUpdate {
+    address: RegisterAddress,
+    permissions: Permissions,
+    /// The signature of the owner of the Register on the address and the permissions.
+    signature: Signature,
+}
+
+impl PermissionsUpdate {
+    /// Grants the permissions on the Register at the address, signing them with the owner key.
+    pub fn new(
+        address: RegisterAddress,
+        permissions: Permissions,
+        owner: &SecretKey,
+    ) -> Result<Self> {
+        if owner.public_key() != address.owner() {
+            return Err(Error::InvalidSecretKey);
+        }
+        let signature = owner.sign(Self::bytes_for_signing(&address, &permissions)?);
+        Ok(Self {
+            address,
+            permissions,
+            signature,
+        })
+    }
+
+    /// The address of the Register the permissions are granted on.
+    pub fn address(&self) -> &RegisterAddress {
+        &self.address
+    }
+
+    /// The permissions granted.
+    pub fn permissions(&self) -> &Permissions {
+        &self.permissions
+    }
+
+    /// Checks the update was signed by the owner of the Register at the address.
+    pub fn verify(&self, address: &RegisterAddress) -> Result<()> {
+        if &self.address != address {
+            return Err(Error::RegisterAddrMismatch {
+                dst_addr: Box::new(self.address),
+                reg_addr: Box::new(*address),
+            });
+        }
+        let bytes = Self::bytes_for_signing(&self.address, &self.permissions)?;
+        if !self.address.owner().verify(&self.signature, bytes) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    fn bytes_for_signing(address: &RegisterAddress, permissions: &Permissions) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(&(address, permissions)).map_err(|_| Error::SerialisationFailed)
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{Entry, EntryHash};
+
+use bls::PublicKey;
+use std::sync::Arc;
+
+/// Reads the timestamp of an entry, as written by the application, if it has one.
+pub type TimestampFn = Arc<dyn Fn(&Entry) -> Option<u64> + Send + Sync>;
+
+/// Picks the winner of two concurrent tips.
+pub type TipReducer = Arc<dyn for<'a> Fn(Tip<'a>, Tip<'a>) -> Tip<'a> + Send + Sync>;
+
+/// One of the latest entries of a Register, concurrent to the other tips.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tip<'a> {
+    /// The hash of the entry
+    pub hash: EntryHash,
+    /// The entry
+    pub entry: &'a Entry,
+    /// The key which wrote the entry, when known to the replica
+    pub writer: Option<PublicKey>,
+}
+
+/// How to pick a single current value among the tips of a Register, when it has branches.
+///
+/// All the policies break ties in favour of the greatest entry hash,
+/// so that all the replicas with the same tips pick the same value.
+#[derive(Clone)]
+pub enum ConflictPolicy {
+    /// The tip with the latest timestamp wins, the tips without one losing to the others.
+    LastWriterWins(TimestampFn),
+    /// A tip written by the owner of the Register wins over the ones written by others.
+    OwnerPriority,
+    /// The tips are reduced pairwise with the closure, in the order of their hashes.
+    Custom(TipReducer),
+}
+
+impl std::fmt::Debug for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LastWriterWins(_) => write!(f, "ConflictPolicy::LastWriterWins"),
+            Self::OwnerPriority => write!(f, "ConflictPolicy::OwnerPriority"),
+            Self::Custom(_) => write!(f, "ConflictPolicy::Custom"),
+        }
+    }
+}
+
+impl ConflictPolicy {
+    /// Pick the winner among the tips, written to the Register owned by `owner`.
+    pub fn resolve<'a>(
+        &self,
+        owner: PublicKey,
+        tips: impl IntoIterator<Item = Tip<'a>>,
+    ) -> Option<Tip<'a>> {
+        let mut tips: Vec<Tip<'a>> = tips.into_iter().collect();
+        tips.sort_by_key(|tip| tip.hash);
+        match self {
+            Self::LastWriterWins(timestamp) => tips
+                .into_iter()
+                .max_by_key(|tip| (timestamp(tip.entry), tip.hash)),
+            Self::OwnerPriority => tips
+                .into_iter()
+                .max_by_key(|tip| (tip.writer == Some(owner), tip.hash)),
+            Self::Custom(reducer) => tips.into_iter().reduce(|winner, tip| reducer(winner, tip)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bls::SecretKey;
+
+    #[test]
+    fn conflict_policies() {
+        let owner = SecretKey::random().public_key();
+        let other = SecretKey::random().public_key();
+        let (old, new, untimed) = (vec![0, 1], vec![0, 2], vec![9]);
+        let tips = [
+            Tip {
+                hash: EntryHash([1; 32]),
+                entry: &new,
+                writer: Some(other),
+            },
+            Tip {
+                hash: EntryHash([2; 32]),
+                entry: &old,
+                writer: Some(owner),
+            },
+            Tip {
+                hash: EntryHash([3; 32]),
+                entry: &untimed,
+                writer: None,
+            },
+        ];
+
+        let timestamp: TimestampFn = Arc::new(|entry: &Entry| match entry.as_slice() {
+            [0, time] => Some(*time as u64),
+            _ => None,
+        });
+        let lww = ConflictPolicy::LastWriterWins(timestamp);
+        assert_eq!(
+            lww.resolve(owner, tips).map(|tip| tip.hash),
+            Some(tips[0].hash)
+        );
+
+        let owner_priority = ConflictPolicy::OwnerPriority;
+        assert_eq!(
+            owner_priority.resolve(owner, tips).map(|tip| tip.hash),
+            Some(tips[1].hash)
+        );
+        // the greatest hash wins among the tips not written by the owner
+        assert_eq!(
+            owner_priority
+                .resolve(owner, [tips[0], tips[2]])
+                .map(|tip| tip.hash),
+            Some(tips[2].hash)
+        );
+
+        // e.g. the longest entry wins, whatever the order of the tips
+        let reducer: TipReducer = Arc::new(|winner, tip| {
+            if tip.entry.len() > winner.entry.len() {
+                tip
+            } else {
+                winner
+            }
+        });
+        let custom = ConflictPolicy::Custom(reducer);
+        let reversed: Vec<Tip> = tips.iter().rev().copied().collect();
+        assert_eq!(
+            custom.resolve(owner, tips).map(|tip| tip.hash),
+            Some(tips[0].hash)
+        );
+        assert_eq!(
+            custom.resolve(owner, reversed).map(|tip| tip.hash),
+            Some(tips[0].hash)
+        );
+
+        assert_eq!(ConflictPolicy::OwnerPriority.resolve(owner, []), None);
+    }
+}
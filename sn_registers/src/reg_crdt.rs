@@ -6,10 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Result, Entry, EntryHash, Error, RegisterAddress, RegisterOp};
+use crate::{
+    error::Result, merkle_dag::MerkleDag, Entry, EntryHash, Error, RegisterAddress, RegisterOp,
+    RegisterSnapshot,
+};
 
 use crdts::merkle_reg::Node as MerkleDagEntry;
-use crdts::{merkle_reg::MerkleReg, CmRDT, CvRDT};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeSet,
@@ -23,17 +25,17 @@ pub(crate) struct RegisterCrdt {
     /// Address on the network of this piece of data
     address: RegisterAddress,
     /// CRDT to store the actual data, i.e. the items of the Register.
-    data: MerkleReg<Entry>,
+    data: MerkleDag,
 }
 
 impl Display for RegisterCrdt {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "(")?;
-        for (i, entry) in self.data.read().values().enumerate() {
+        for (i, (_, node)) in self.data.read().enumerate() {
             if i > 0 {
                 write!(f, ", ")?;
             }
-            write!(f, "<{entry:?}>")?;
+            write!(f, "<{:?}>", node.value)?;
         }
         write!(f, ")")
     }
@@ -44,7 +46,7 @@ impl RegisterCrdt {
     pub(crate) fn new(address: RegisterAddress) -> Self {
         Self {
             address,
-            data: MerkleReg::new(),
+            data: MerkleDag::new(),
         }
     }
 
@@ -73,7 +75,10 @@ impl RegisterCrdt {
         let address = *self.address();
 
         let children_array: BTreeSet<[u8; 32]> = children.iter().map(|itr| itr.0).collect();
-        let crdt_op = self.data.write(entry, children_array);
+        let crdt_op = MerkleDagEntry {
+            children: children_array,
+            value: entry,
+        };
         self.data.apply(crdt_op.clone());
         let hash = crdt_op.hash();
 
@@ -108,7 +113,6 @@ impl RegisterCrdt {
     pub(crate) fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.data
             .read()
-            .hashes_and_nodes()
             .map(|(hash, node)| (EntryHash(hash), node.value.clone()))
             .collect()
     }
@@ -117,16 +121,162 @@ impl RegisterCrdt {
     pub fn children(&self, hash: &EntryHash) -> BTreeSet<(EntryHash, Entry)> {
         self.data
             .children(hash.0)
-            .hashes_and_nodes()
             .map(|(hash, node)| (EntryHash(hash), node.value.clone()))
             .collect()
     }
 
-    /// Access the underlying MerkleReg (e.g. for access to history)
+    /// Returns the entries `to` descends from, including itself, which `from` doesn't,
+    /// each after the ones it descends from, see `tips_history`.
+    pub(crate) fn entries_between(
+        &self,
+        from: EntryHash,
+        to: EntryHash,
+    ) -> Result<Vec<(EntryHash, Entry)>> {
+        let before = self.ancestors(from)?;
+        let until = self.ancestors(to)?;
+        let between = until.difference(&before).copied().collect();
+        Ok(self
+            .topological_order(&between)
+            .into_iter()
+            .filter_map(|hash| {
+                self.data
+                    .node(hash)
+                    .map(|node| (EntryHash(hash), node.value.clone()))
+            })
+            .collect())
+    }
+
+    /// Returns every entry in topological order, ties broken by hash, along with the branch tips
+    /// of the register holding that entry and the ones before it in that order.
+    ///
+    /// The order is the same on all the replicas, but isn't the order the entries were written in,
+    /// which the register doesn't know of.
+    pub(crate) fn tips_history(&self) -> Vec<(EntryHash, BTreeSet<EntryHash>)> {
+        let all = self.data.all_nodes().map(MerkleDagEntry::hash).collect();
+        let mut tips = BTreeSet::new();
+        self.topological_order(&all)
+            .into_iter()
+            .map(|hash| {
+                if let Some(node) = self.data.node(hash) {
+                    for child in &node.children {
+                        let _ = tips.remove(&EntryHash(*child));
+                    }
+                }
+                let _ = tips.insert(EntryHash(hash));
+                (EntryHash(hash), tips.clone())
+            })
+            .collect()
+    }
+
+    /// Returns the register as it was when the entry at `hash` was its only tip,
+    /// i.e. holding that entry and the ones it descends from.
+    pub(crate) fn state_at(&self, hash: EntryHash) -> Result<Self> {
+        let ancestors = self.ancestors(hash)?;
+        let mut data = MerkleDag::new();
+        for hash in self.topological_order(&ancestors) {
+            if let Some(node) = self.data.node(hash) {
+                data.apply(node.clone());
+            }
+        }
+        Ok(Self {
+            address: self.address,
+            data,
+        })
+    }
+
+    /// Returns the entry at `cutoff`, along with the hashes of the entries it descends from,
+    /// including the ones already compacted away by a previous snapshot.
+    pub(crate) fn compaction(
+        &self,
+        cutoff: EntryHash,
+        previous: Option<&RegisterSnapshot>,
+    ) -> Result<(MerkleDagEntry<Entry>, BTreeSet<EntryHash>)> {
+        let node = self
+            .data
+            .node(cutoff.0)
+            .ok_or(Error::NoSuchEntry(cutoff))?
+            .clone();
+        let mut compacted: BTreeSet<EntryHash> =
+            self.ancestors(cutoff)?.into_iter().map(EntryHash).collect();
+        let _ = compacted.remove(&cutoff);
+        if let Some(previous) = previous {
+            if compacted.contains(&previous.cutoff()) {
+                compacted.extend(previous.compacted());
+            }
+        }
+        Ok((node, compacted))
+    }
+
+    /// Returns the register compacted by the snapshot: holding the entry at its cutoff along
+    /// with the entries not covered by the snapshot.
+    pub(crate) fn compacted(&self, snapshot: &RegisterSnapshot) -> Result<Self> {
+        // the DAG only takes an entry once it holds the entries it was written atop, so it
+        // starts from the cutoff entry, without the entries compacted away
+        let mut data = MerkleDag::with_root(snapshot.cutoff_node().clone());
+        for node in self.data.all_nodes().chain(self.data.orphans()) {
+            if !snapshot.covers(EntryHash(node.hash())) {
+                data.apply(node.clone());
+            }
+        }
+        Ok(Self {
+            address: self.address,
+            data,
+        })
+    }
+
+    /// Access the underlying MerkleDag (e.g. for access to history)
     /// NOTE: This API is unstable and may be removed in the future
-    pub(crate) fn merkle_reg(&self) -> &MerkleReg<Entry> {
+    pub(crate) fn merkle_reg(&self) -> &MerkleDag {
         &self.data
     }
+
+    // The hashes of the entry and of all the entries it descends from.
+    fn ancestors(&self, hash: EntryHash) -> Result<BTreeSet<[u8; 32]>> {
+        if self.data.node(hash.0).is_none() {
+            return Err(Error::NoSuchEntry(hash));
+        }
+        let mut ancestors = BTreeSet::new();
+        let mut to_visit = vec![hash.0];
+        while let Some(hash) = to_visit.pop() {
+            if !ancestors.insert(hash) {
+                continue;
+            }
+            if let Some(node) = self.data.node(hash) {
+                to_visit.extend(node.children.iter().copied());
+            }
+        }
+        Ok(ancestors)
+    }
+
+    // Orders the hashes so that every entry comes after the entries it descends from,
+    // breaking ties by hash so that all the replicas agree on the order.
+    fn topological_order(&self, hashes: &BTreeSet<[u8; 32]>) -> Vec<[u8; 32]> {
+        let mut order = Vec::with_capacity(hashes.len());
+        let mut pending = hashes.clone();
+        let mut written = BTreeSet::new();
+        while !pending.is_empty() {
+            let ready: Vec<[u8; 32]> = pending
+                .iter()
+                .filter(|hash| {
+                    self.data.node(**hash).is_none_or(|node| {
+                        node.children
+                            .iter()
+                            .all(|child| written.contains(child) || !hashes.contains(child))
+                    })
+                })
+                .copied()
+                .collect();
+            if ready.is_empty() {
+                break; // a cycle, which the hashes of the entries rule out
+            }
+            for hash in ready {
+                let _ = pending.remove(&hash);
+                let _ = written.insert(hash);
+                order.push(hash);
+            }
+        }
+        order
+    }
 }
 
 #[cfg(test)]
@@ -226,4 +376,56 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn entry_history() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let address = RegisterAddress {
+            meta: XorName::random(&mut rng),
+            owner: SecretKey::random().public_key(),
+        };
+        let mut crdt = RegisterCrdt::new(address);
+
+        // entry_1 <- entry_2_1, entry_2_2 <- entry_3 (atop both), and entry_2_3 atop entry_1 last
+        let (hash_1, _, _) = crdt.write(vec![0x1], &BTreeSet::new())?;
+        let (hash_2_1, _, _) = crdt.write(vec![0x2, 0x1], &[hash_1].into_iter().collect())?;
+        let (hash_2_2, _, _) = crdt.write(vec![0x2, 0x2], &[hash_1].into_iter().collect())?;
+        let (hash_3, _, _) = crdt.write(vec![0x3], &[hash_2_1, hash_2_2].into_iter().collect())?;
+        let (hash_2_3, _, _) = crdt.write(vec![0x2, 0x3], &[hash_1].into_iter().collect())?;
+
+        let between: Vec<EntryHash> = crdt
+            .entries_between(hash_2_1, hash_3)?
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        assert_eq!(between, vec![hash_2_2, hash_3]);
+        assert!(crdt.entries_between(hash_3, hash_1)?.is_empty());
+        let unknown = EntryHash([0; 32]);
+        assert_eq!(
+            crdt.entries_between(unknown, hash_3),
+            Err(Error::NoSuchEntry(unknown))
+        );
+
+        let history = crdt.tips_history();
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[0], (hash_1, [hash_1].into_iter().collect()));
+        let (last, tips) = history.last().expect("history is not empty");
+        assert_eq!(*last, hash_3);
+        assert_eq!(*tips, [hash_2_3, hash_3].into_iter().collect());
+
+        let state = crdt.state_at(hash_3)?;
+        assert_eq!(state.size(), 4);
+        assert_eq!(
+            state
+                .read()
+                .into_iter()
+                .map(|(hash, _)| hash)
+                .collect::<Vec<_>>(),
+            vec![hash_3]
+        );
+        assert!(state.get(hash_2_3).is_none());
+        assert_eq!(crdt.state_at(unknown), Err(Error::NoSuchEntry(unknown)));
+
+        Ok(())
+    }
 }
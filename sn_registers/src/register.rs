@@ -7,24 +7,23 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    error::Result, reg_crdt::RegisterCrdt, Entry, EntryHash, Error, Permissions, RegisterAddress,
-    RegisterOp,
+    error::Result, reg_crdt::RegisterCrdt, AuthoredEntry, ConflictPolicy, Entry, EntryHash, Error,
+    MerkleDag, Permissions, PermissionsUpdate, RegisterAddress, RegisterOp, RegisterSnapshot, Tip,
 };
 
 use bls::{PublicKey, SecretKey, Signature};
-use crdts::merkle_reg::MerkleReg;
-use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
 use xor_name::XorName;
 
 /// Arbitrary maximum size of a register entry.
-const MAX_REG_ENTRY_SIZE: usize = 1024;
+pub const MAX_REG_ENTRY_SIZE: usize = 1024;
 
 /// Maximum number of entries of a register.
 const MAX_REG_NUM_ENTRIES: u16 = 1024;
 
 /// A Register on the SAFE Network
-#[derive(Clone, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 pub struct Register {
     /// CRDT data of the Register
     crdt: RegisterCrdt,
@@ -32,11 +31,81 @@ pub struct Register {
     /// Depending on the permissions, the owner can allow other users to write to the register
     /// Everyone can always read the Register because all data is public
     permissions: Permissions,
+    /// Permissions granted by the owner since the Register was created.
+    /// Not serialised while empty, so that the Registers created without are signed as before.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    grants: BTreeSet<PermissionsUpdate>,
+    /// Keys which wrote the entries known to this replica, learnt from the ops applied to it.
+    /// Not serialised while empty, so that the Registers without are signed as before.
+    #[serde(default)]
+    writers: BTreeMap<EntryHash, PublicKey>,
+    /// The snapshot the CRDT data was compacted with, if any.
+    #[serde(skip)]
+    snapshot: Option<RegisterSnapshot>,
+}
+
+impl Serialize for Register {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // As for the SignedRegister, the fields added since the creation of the type are only
+        // serialised when set, and a field can't be skipped ahead of one which is set.
+        let len = if !self.writers.is_empty() {
+            4
+        } else if !self.grants.is_empty() {
+            3
+        } else {
+            2
+        };
+        let mut state = serializer.serialize_struct("Register", len)?;
+        state.serialize_field("crdt", &self.crdt)?;
+        state.serialize_field("permissions", &self.permissions)?;
+        if len > 2 {
+            state.serialize_field("grants", &self.grants)?;
+        } else {
+            state.skip_field("grants")?;
+        }
+        if len > 3 {
+            state.serialize_field("writers", &self.writers)?;
+        } else {
+            state.skip_field("writers")?;
+        }
+        state.skip_field("snapshot")?;
+        state.end()
+    }
+}
+
+// The writers and the snapshot are what a replica learnt of the Register, not part of its data,
+// so two replicas holding the same entries compare equal whatever they learnt.
+impl PartialEq for Register {
+    fn eq(&self, other: &Self) -> bool {
+        self.crdt == other.crdt
+            && self.permissions == other.permissions
+            && self.grants == other.grants
+    }
+}
+
+impl Eq for Register {}
+
+impl PartialOrd for Register {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        (&self.crdt, &self.permissions, &self.grants).partial_cmp(&(
+            &other.crdt,
+            &other.permissions,
+            &other.grants,
+        ))
+    }
+}
+
+impl std::hash::Hash for Register {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.crdt.hash(state);
+        self.permissions.hash(state);
+        self.grants.hash(state);
+    }
 }
 
 /// A Signed Register on the SAFE Network
 /// This cryptographically secure version of the Register is used to make sure that the data cannot be tampered with
-#[derive(Clone, Debug, Serialize, Deserialize, PartialOrd, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Deserialize, PartialOrd, PartialEq, Eq, Hash)]
 pub struct SignedRegister {
     /// the base register we had at creation
     base_register: Register,
@@ -45,6 +114,42 @@ pub struct SignedRegister {
     /// operations to apply on this register,
     /// they contain a signature of the writer
     ops: BTreeSet<RegisterOp>,
+    /// permissions granted by the owner since the creation of the register
+    #[serde(default)]
+    grants: BTreeSet<PermissionsUpdate>,
+    /// latest snapshot published by the owner, the ops it covers being dropped
+    #[serde(default)]
+    snapshot: Option<RegisterSnapshot>,
+}
+
+impl Serialize for SignedRegister {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // The fields added since the creation of the type are only serialised when set, so that
+        // the SignedRegisters without are encoded as before. As the fields are encoded by position,
+        // a field can't be skipped ahead of one which is set.
+        let len = if self.snapshot.is_some() {
+            5
+        } else if !self.grants.is_empty() {
+            4
+        } else {
+            3
+        };
+        let mut state = serializer.serialize_struct("SignedRegister", len)?;
+        state.serialize_field("base_register", &self.base_register)?;
+        state.serialize_field("signature", &self.signature)?;
+        state.serialize_field("ops", &self.ops)?;
+        if len > 3 {
+            state.serialize_field("grants", &self.grants)?;
+        } else {
+            state.skip_field("grants")?;
+        }
+        if len > 4 {
+            state.serialize_field("snapshot", &self.snapshot)?;
+        } else {
+            state.skip_field("snapshot")?;
+        }
+        state.end()
+    }
 }
 
 impl SignedRegister {
@@ -54,6 +159,8 @@ impl SignedRegister {
             base_register,
             signature,
             ops: BTreeSet::new(),
+            grants: BTreeSet::new(),
+            snapshot: None,
         }
     }
 
@@ -68,8 +175,14 @@ impl SignedRegister {
             return Err(Error::InvalidSignature);
         }
 
+        for grant in self.base_register.grants.iter().chain(&self.grants) {
+            grant.verify(self.address())?;
+        }
+        if let Some(snapshot) = &self.snapshot {
+            snapshot.verify(self.address())?;
+        }
         for op in &self.ops {
-            self.base_register.check_register_op(op)?;
+            self.base_register.check_op_with_grants(op, &self.grants)?;
         }
         Ok(())
     }
@@ -87,6 +200,12 @@ impl SignedRegister {
     /// Return the Register after applying all the operations
     pub fn register(self) -> Result<Register> {
         let mut register = self.base_register;
+        for grant in self.grants {
+            register.grant(grant)?;
+        }
+        if let Some(snapshot) = self.snapshot {
+            register.compact(snapshot)?;
+        }
         for op in self.ops {
             register.apply_op(op)?;
         }
@@ -98,6 +217,8 @@ impl SignedRegister {
         self.base_register
             .verify_is_mergeable(&other.base_register)?;
         self.ops.extend(other.ops.clone());
+        self.grants.extend(other.grants.clone());
+        self.merge_snapshot(other.snapshot.as_ref());
         Ok(())
     }
 
@@ -108,6 +229,8 @@ impl SignedRegister {
             .verify_is_mergeable(&other.base_register)?;
         other.verify()?;
         self.ops.extend(other.ops.clone());
+        self.grants.extend(other.grants.clone());
+        self.merge_snapshot(other.snapshot.as_ref());
         Ok(())
     }
 
@@ -123,16 +246,53 @@ impl SignedRegister {
 
     /// Check and add an Op to the SignedRegister
     pub fn add_op(&mut self, op: RegisterOp) -> Result<()> {
-        self.base_register.check_register_op(&op)?;
+        self.base_register.check_op_with_grants(&op, &self.grants)?;
+        if let Some(snapshot) = &self.snapshot {
+            if snapshot.covers(op.entry_hash()) {
+                return Ok(()); // already compacted into the snapshot
+            }
+        }
         self.ops.insert(op);
         Ok(())
     }
 
-    /// Access the underlying MerkleReg (e.g. for access to history)
+    /// Check and add permissions granted by the owner to the SignedRegister
+    pub fn add_grant(&mut self, grant: PermissionsUpdate) -> Result<()> {
+        grant.verify(self.address())?;
+        self.grants.insert(grant);
+        Ok(())
+    }
+
+    /// Check and add a snapshot published by the owner to the SignedRegister,
+    /// dropping the ops it covers
+    pub fn add_snapshot(&mut self, snapshot: RegisterSnapshot) -> Result<()> {
+        snapshot.verify(self.address())?;
+        self.merge_snapshot(Some(&snapshot));
+        Ok(())
+    }
+
+    /// Return the latest snapshot published by the owner, if any.
+    pub fn snapshot(&self) -> Option<&RegisterSnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// Access the underlying MerkleDag (e.g. for access to history)
     /// NOTE: This API is unstable and may be removed in the future
-    pub fn merkle_reg(&self) -> &MerkleReg<Entry> {
+    pub fn merkle_reg(&self) -> &MerkleDag {
         self.base_register.merkle_reg()
     }
+
+    // Keep the newest of our snapshot and the given one, dropping the ops it covers.
+    fn merge_snapshot(&mut self, other: Option<&RegisterSnapshot>) {
+        let snapshot = match (&self.snapshot, other) {
+            (Some(ours), Some(theirs)) => ours.newest(theirs).clone(),
+            (Some(ours), None) => ours.clone(),
+            (None, Some(theirs)) => theirs.clone(),
+            (None, None) => return,
+        };
+        self.ops.retain(|op| !snapshot.covers(op.entry_hash()));
+        self.snapshot = Some(snapshot);
+    }
 }
 
 impl Register {
@@ -143,6 +303,9 @@ impl Register {
         Self {
             crdt: RegisterCrdt::new(address),
             permissions,
+            grants: BTreeSet::new(),
+            writers: BTreeMap::new(),
+            snapshot: None,
         }
     }
 
@@ -188,6 +351,16 @@ impl Register {
         self.crdt.get(hash).ok_or(Error::NoSuchEntry(hash))
     }
 
+    /// Return the entry corresponding to the provided 'hash' along with its author,
+    /// having checked the author's signature, or `None` if the entry isn't an authored one.
+    pub fn get_authored(&self, hash: EntryHash) -> Result<Option<AuthoredEntry>> {
+        let Some(authored) = AuthoredEntry::from_entry(self.get(hash)?)? else {
+            return Ok(None);
+        };
+        authored.verify(self.address())?;
+        Ok(Some(authored))
+    }
+
     /// Read the last entry, or entries when there are branches, if the register is not empty.
     pub fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.crdt.read()
@@ -198,11 +371,52 @@ impl Register {
         self.crdt.children(hash)
     }
 
+    /// Return the entries written after the entry at `from` up to the one at `to` included,
+    /// i.e. the ones `to` descends from but `from` doesn't, each after the ones it descends from.
+    pub fn entries_between(
+        &self,
+        from: EntryHash,
+        to: EntryHash,
+    ) -> Result<Vec<(EntryHash, Entry)>> {
+        self.crdt.entries_between(from, to)
+    }
+
+    /// Return every entry after the entries it descends from, along with the branch tips of the
+    /// Register holding that entry and the ones before it.
+    /// Concurrent entries are ordered by hash, so that all the replicas agree on the history,
+    /// which is thus not the order they were written in.
+    pub fn tips_history(&self) -> Vec<(EntryHash, BTreeSet<EntryHash>)> {
+        self.crdt.tips_history()
+    }
+
+    /// Return the Register holding only the entry at `hash` and the ones it descends from.
+    pub fn state_at(&self, hash: EntryHash) -> Result<Self> {
+        Ok(Self {
+            crdt: self.crdt.state_at(hash)?,
+            permissions: self.permissions.clone(),
+            grants: self.grants.clone(),
+            writers: self.writers.clone(),
+            snapshot: self.snapshot.clone(),
+        })
+    }
+
     /// Return the permission.
     pub fn permissions(&self) -> &Permissions {
         &self.permissions
     }
 
+    /// Return the permissions granted by the owner since the Register was created.
+    pub fn grants(&self) -> &BTreeSet<PermissionsUpdate> {
+        &self.grants
+    }
+
+    /// Add permissions granted by the owner, checking they were signed by the owner.
+    pub fn grant(&mut self, grant: PermissionsUpdate) -> Result<()> {
+        grant.verify(self.address())?;
+        self.grants.insert(grant);
+        Ok(())
+    }
+
     /// Write an entry to the Register, returning the generated
     /// CRDT operation so the caller can sign and broadcast it to other replicas,
     /// along with the hash of the entry just written.
@@ -213,33 +427,122 @@ impl Register {
         signer: &SecretKey,
     ) -> Result<(EntryHash, RegisterOp)> {
         self.check_entry_and_reg_sizes(&entry)?;
+        self.check_entry_author(&entry)?;
         // check permissions before writing on the underlying CRDT
         self.check_user_permissions(signer.public_key())?;
         let (hash, address, crdt_op) = self.crdt.write(entry, children)?;
         let op = RegisterOp::new(address, crdt_op, signer);
+        self.writers.insert(hash, op.source);
         Ok((hash, op))
     }
 
     /// Apply a signed data CRDT operation.
     pub fn apply_op(&mut self, op: RegisterOp) -> Result<()> {
         self.check_entry_and_reg_sizes(&op.crdt_op.value)?;
+        self.check_entry_author(&op.crdt_op.value)?;
         self.check_register_op(&op)?;
-        self.crdt.apply_op(op)
+        let (hash, source) = (op.entry_hash(), op.source);
+        if let Some(snapshot) = &self.snapshot {
+            if snapshot.covers(hash) {
+                return Ok(()); // already compacted into the snapshot
+            }
+        }
+        self.crdt.apply_op(op)?;
+        self.writers.insert(hash, source);
+        Ok(())
     }
 
     /// Merge another Register into this one.
     pub fn merge(&mut self, other: &Self) -> Result<()> {
         self.verify_is_mergeable(other)?;
-        self.crdt.merge(other.crdt.clone());
+        if let Some(snapshot) = &other.snapshot {
+            self.compact(snapshot.clone())?;
+        }
+        let other_crdt = match &self.snapshot {
+            Some(snapshot) => other.crdt.compacted(snapshot)?,
+            None => other.crdt.clone(),
+        };
+        self.crdt.merge(other_crdt);
+        self.grants.extend(other.grants.clone());
+        self.writers.extend(other.writers.clone());
+        self.writers
+            .retain(|hash, _| self.crdt.get(*hash).is_some());
+        Ok(())
+    }
+
+    /// Snapshot the Register at the entry at `cutoff`, signing the snapshot with the owner key.
+    /// Once published, the replicas drop the entries the cutoff entry descends from, along with
+    /// their ops, see `compact`.
+    pub fn snapshot_at(&self, cutoff: EntryHash, owner: &SecretKey) -> Result<RegisterSnapshot> {
+        let (node, compacted) = self.crdt.compaction(cutoff, self.snapshot.as_ref())?;
+        RegisterSnapshot::new(*self.address(), node, compacted, owner)
+    }
+
+    /// Compact the Register with a snapshot published by the owner, dropping the entries
+    /// the snapshot covers but its cutoff entry. Does nothing if the Register was already
+    /// compacted with a newer snapshot.
+    pub fn compact(&mut self, snapshot: RegisterSnapshot) -> Result<()> {
+        snapshot.verify(self.address())?;
+        if let Some(current) = &self.snapshot {
+            if current.newest(&snapshot) == current {
+                return Ok(());
+            }
+        }
+        self.crdt = self.crdt.compacted(&snapshot)?;
+        self.writers
+            .retain(|hash, _| *hash == snapshot.cutoff() || !snapshot.covers(*hash));
+        self.snapshot = Some(snapshot);
         Ok(())
     }
 
+    /// Return the snapshot the Register was compacted with, if any.
+    pub fn snapshot(&self) -> Option<&RegisterSnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// Return the key which wrote the entry at `hash`, if known to this replica.
+    pub fn writer(&self, hash: EntryHash) -> Option<PublicKey> {
+        self.writers.get(&hash).copied()
+    }
+
+    /// Resolve the branches of the Register into a single current entry, following the policy.
+    /// Returns `None` if the Register is empty.
+    pub fn resolve(&self, policy: &ConflictPolicy) -> Option<(EntryHash, Entry)> {
+        let tips = self.read();
+        let winner = policy.resolve(
+            self.owner(),
+            tips.iter().map(|(hash, entry)| Tip {
+                hash: *hash,
+                entry,
+                writer: self.writer(*hash),
+            }),
+        )?;
+        Some((winner.hash, winner.entry.clone()))
+    }
+
     /// Check if a register op is valid for our current register
     pub fn check_register_op(&self, op: &RegisterOp) -> Result<()> {
-        if self.permissions.can_anyone_write() {
+        self.check_op_with_grants(op, &BTreeSet::new())
+    }
+
+    // Checks the op against the permissions of the register, along with the extra grants.
+    fn check_op_with_grants(
+        &self,
+        op: &RegisterOp,
+        extra_grants: &BTreeSet<PermissionsUpdate>,
+    ) -> Result<()> {
+        if self
+            .all_permissions(extra_grants)
+            .any(|permissions| permissions.can_anyone_write())
+        {
             return Ok(()); // anyone can write, so no need to check the signature
         }
-        self.check_user_permissions(op.source)?;
+        if !self
+            .all_permissions(extra_grants)
+            .any(|permissions| permissions.can_write(&op.source))
+        {
+            return Err(Error::AccessDenied(op.source));
+        }
         op.verify_signature(&op.source)
     }
 
@@ -249,16 +552,19 @@ impl Register {
     /// `Ok(())` if the user can write to this register
     /// `Err::AccessDenied` if the user cannot write to this register
     pub fn check_user_permissions(&self, requester: PublicKey) -> Result<()> {
-        if self.permissions.can_write(&requester) {
+        if self
+            .all_permissions(&BTreeSet::new())
+            .any(|permissions| permissions.can_write(&requester))
+        {
             Ok(())
         } else {
             Err(Error::AccessDenied(requester))
         }
     }
 
-    /// Access the underlying MerkleReg (e.g. for access to history)
+    /// Access the underlying MerkleDag (e.g. for access to history)
     /// NOTE: This API is unstable and may be removed in the future
-    pub fn merkle_reg(&self) -> &MerkleReg<Entry> {
+    pub fn merkle_reg(&self) -> &MerkleDag {
         self.crdt.merkle_reg()
     }
 
@@ -281,6 +587,27 @@ impl Register {
         Ok(())
     }
 
+    // Private helper to check the signature of the author of the given Entry, if it's authored.
+    fn check_entry_author(&self, entry: &Entry) -> Result<()> {
+        match AuthoredEntry::from_entry(entry)? {
+            Some(authored) => authored.verify(self.address()),
+            None => Ok(()),
+        }
+    }
+
+    // The permissions the register was created with, followed by the ones granted since.
+    fn all_permissions<'a>(
+        &'a self,
+        extra_grants: &'a BTreeSet<PermissionsUpdate>,
+    ) -> impl Iterator<Item = &'a Permissions> {
+        std::iter::once(&self.permissions).chain(
+            self.grants
+                .iter()
+                .chain(extra_grants)
+                .map(PermissionsUpdate::permissions),
+        )
+    }
+
     // Private helper to check if this Register is mergeable with another
     fn verify_is_mergeable(&self, other: &Self) -> Result<()> {
         if self.address() != other.address() || self.permissions != other.permissions {
@@ -295,6 +622,9 @@ impl Register {
         Register {
             crdt: RegisterCrdt::new(address),
             permissions: Permissions::AnyoneCanWrite,
+            grants: BTreeSet::new(),
+            writers: BTreeMap::new(),
+            snapshot: None,
         }
     }
 }
@@ -304,7 +634,8 @@ mod tests {
     use crate::RegisterOp;
 
     use super::{
-        EntryHash, Error, Permissions, Register, RegisterAddress, Result, MAX_REG_NUM_ENTRIES,
+        AuthoredEntry, ConflictPolicy, EntryHash, Error, Permissions, PermissionsUpdate, Register,
+        RegisterAddress, Result, SignedRegister, MAX_REG_NUM_ENTRIES,
     };
 
     use bls::SecretKey;
@@ -441,6 +772,69 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_grants() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let other_user_sk = SecretKey::random();
+        let other_user = other_user_sk.public_key();
+        let item = random_register_entry();
+
+        let mut replica1 = Register::new(
+            owner_sk.public_key(),
+            xor_name::rand::random(),
+            Permissions::default(),
+        );
+        let base = replica1.clone();
+        let mut signed = base.clone().into_signed(&owner_sk)?;
+        // the encoding of the registers without grants is unchanged.
+        let decoded: Register = rmp_serde::from_slice(&base.bytes()?)?;
+        assert_eq!(decoded, base);
+
+        // only the owner can grant permissions.
+        let grant = Permissions::new_with([other_user]);
+        assert_eq!(
+            PermissionsUpdate::new(*base.address(), grant.clone(), &other_user_sk),
+            Err(Error::InvalidSecretKey)
+        );
+        let other_address = RegisterAddress::new(xor_name::rand::random(), owner_sk.public_key());
+        let misdirected = PermissionsUpdate::new(other_address, grant.clone(), &owner_sk)?;
+        assert!(replica1.grant(misdirected.clone()).is_err());
+        assert!(signed.add_grant(misdirected).is_err());
+
+        let update = PermissionsUpdate::new(*base.address(), grant, &owner_sk)?;
+        assert!(replica1
+            .write(item.clone(), &BTreeSet::new(), &other_user_sk)
+            .is_err());
+        replica1.grant(update.clone())?;
+        let (_, op) = replica1.write(item.clone(), &BTreeSet::new(), &other_user_sk)?;
+
+        // the op is only accepted by the replicas which got the grant...
+        assert_eq!(
+            signed.add_op(op.clone()),
+            Err(Error::AccessDenied(other_user))
+        );
+        let mut replica2 = base.clone();
+        assert_eq!(
+            replica2.apply_op(op.clone()),
+            Err(Error::AccessDenied(other_user))
+        );
+        // ...which merging replicas passes on.
+        replica2.merge(&replica1)?;
+        replica2.apply_op(op.clone())?;
+        assert_eq!(replica2.read(), replica1.read());
+
+        let mut signed_with_grant = base.into_signed(&owner_sk)?;
+        signed_with_grant.add_grant(update)?;
+        signed_with_grant.add_op(op)?;
+        signed_with_grant.verify()?;
+        signed.verified_merge(&signed_with_grant)?;
+        signed.verify()?;
+        let decoded: SignedRegister = rmp_serde::from_slice(&rmp_serde::to_vec(&signed)?)?;
+        assert_eq!(decoded, signed);
+        assert_eq!(signed.register()?.read(), replica1.read());
+        Ok(())
+    }
+
     #[test]
     fn register_concurrent_write_ops() -> eyre::Result<()> {
         let authority_sk1 = SecretKey::random();
@@ -483,6 +877,150 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_resolve_concurrent_writes() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let writer_sk = SecretKey::random();
+        let perms = Permissions::new_with([writer_sk.public_key()]);
+        let mut replica1 = Register::new(owner_sk.public_key(), xor_name::rand::random(), perms);
+        let mut replica2 = replica1.clone();
+        assert_eq!(replica1.resolve(&ConflictPolicy::OwnerPriority), None);
+
+        let (owner_hash, op1) = replica1.write(vec![1], &BTreeSet::new(), &owner_sk)?;
+        let (writer_hash, op2) = replica2.write(vec![2], &BTreeSet::new(), &writer_sk)?;
+        replica1.apply_op(op2)?;
+        replica2.apply_op(op1)?;
+
+        // the writers are learnt from the ops, so both replicas resolve to the owner's entry
+        assert_eq!(replica1.writer(writer_hash), Some(writer_sk.public_key()));
+        assert_eq!(replica2.writer(owner_hash), Some(owner_sk.public_key()));
+        for replica in [&replica1, &replica2] {
+            assert_eq!(
+                replica.resolve(&ConflictPolicy::OwnerPriority),
+                Some((owner_hash, vec![1]))
+            );
+        }
+
+        // the writers are kept over the wire, and left out of the comparisons
+        let signed = replica1.clone().into_signed(&owner_sk)?;
+        let received: SignedRegister = rmp_serde::from_slice(&rmp_serde::to_vec(&signed)?)?;
+        received.verify()?;
+        assert_eq!(received, signed);
+        let register = received.register()?;
+        assert_eq!(register.writer(owner_hash), Some(owner_sk.public_key()));
+        assert_eq!(register.writer(writer_hash), Some(writer_sk.public_key()));
+        let mut forgetful = replica1.clone();
+        forgetful.writers.clear();
+        assert_eq!(forgetful, replica1);
+        Ok(())
+    }
+
+    #[test]
+    fn register_authored_entries() -> eyre::Result<()> {
+        let writer_sk = SecretKey::random();
+        let mut replica1 = create_reg_replica_with(
+            xor_name::rand::random(),
+            Some(SecretKey::random()),
+            Some(Permissions::new_with([writer_sk.public_key()])),
+        );
+        let mut replica2 = replica1.clone();
+        let author_sk = SecretKey::random();
+
+        // the writer relays the entry of the author, who is attributed it on the other replicas
+        let authored = AuthoredEntry::new(*replica1.address(), vec![1], &author_sk)?;
+        let (hash, op) = replica1.write(authored.to_entry()?, &BTreeSet::new(), &writer_sk)?;
+        replica2.apply_op(op)?;
+        let attributed = replica2.get_authored(hash)?.expect("an authored entry");
+        assert_eq!(attributed.author(), author_sk.public_key());
+        assert_eq!(attributed.content(), &vec![1]);
+        assert_eq!(replica2.writer(hash), Some(writer_sk.public_key()));
+
+        // plain entries aren't attributed
+        let (plain_hash, _) = replica1.write(vec![2], &BTreeSet::new(), &writer_sk)?;
+        assert_eq!(replica1.get_authored(plain_hash)?, None);
+
+        // entries authored for another Register are rejected
+        let other = RegisterAddress::new(xor_name::rand::random(), replica1.owner());
+        let replayed = AuthoredEntry::new(other, vec![3], &author_sk)?;
+        assert!(matches!(
+            replica1.write(replayed.to_entry()?, &BTreeSet::new(), &writer_sk),
+            Err(Error::RegisterAddrMismatch { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn register_snapshots() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let base = Register::new(
+            owner_sk.public_key(),
+            xor_name::rand::random(),
+            Permissions::default(),
+        );
+        let mut signed = base.clone().into_signed(&owner_sk)?;
+        let mut register = base;
+
+        let mut hashes = vec![];
+        let mut ops = vec![];
+        for entry in 0..4 {
+            let children = hashes.last().copied().into_iter().collect();
+            let (hash, op) = register.write(vec![entry], &children, &owner_sk)?;
+            signed.add_op(op.clone())?;
+            hashes.push(hash);
+            ops.push(op);
+        }
+        let uncompacted = register.clone();
+
+        // the snapshot drops the ops of the entries up to its cutoff
+        let snapshot = register.snapshot_at(hashes[2], &owner_sk)?;
+        assert_eq!(snapshot.cutoff(), hashes[2]);
+        assert_eq!(snapshot.compacted(), &hashes[..2].iter().copied().collect());
+        assert!(matches!(
+            register.snapshot_at(hashes[2], &SecretKey::random()),
+            Err(Error::InvalidSecretKey)
+        ));
+        signed.add_snapshot(snapshot.clone())?;
+        signed.add_op(ops[0].clone())?;
+        assert_eq!(signed.ops.len(), 1);
+        signed.verify()?;
+        let decoded: SignedRegister = rmp_serde::from_slice(&rmp_serde::to_vec(&signed)?)?;
+        assert_eq!(decoded, signed);
+
+        let compacted = signed.register()?;
+        assert_eq!(compacted.read(), register.read());
+        assert_eq!(compacted.size(), 2);
+        assert_eq!(compacted.get(hashes[2])?, &vec![2]);
+        assert!(compacted.get(hashes[1]).is_err());
+
+        // the entries written since apply atop the compacted Register, unlike the compacted ones
+        let (hash, op) = register.write(vec![4], &[hashes[3]].into(), &owner_sk)?;
+        let mut replica = compacted.clone();
+        replica.apply_op(op)?;
+        replica.apply_op(ops[0].clone())?;
+        assert_eq!(replica.read(), register.read());
+        assert_eq!(replica.size(), 3);
+        assert_eq!(
+            replica.read().into_iter().next().map(|(h, _)| h),
+            Some(hash)
+        );
+
+        // merging a compacted replica compacts the Register
+        let mut merged = uncompacted;
+        merged.merge(&replica)?;
+        assert_eq!(merged.read(), register.read());
+        assert_eq!(merged.size(), 3);
+        assert_eq!(merged.snapshot(), Some(&snapshot));
+
+        // a later snapshot supersedes the previous one, and compacts its entries too
+        let later = replica.snapshot_at(hashes[3], &owner_sk)?;
+        assert_eq!(later.compacted(), &hashes[..3].iter().copied().collect());
+        merged.compact(later.clone())?;
+        merged.compact(snapshot)?;
+        assert_eq!(merged.snapshot(), Some(&later));
+        assert_eq!(merged.size(), 2);
+        Ok(())
+    }
+
     #[test]
     fn register_get_by_hash() -> eyre::Result<()> {
         let (sk, register) = &mut create_reg_replicas(1)[0];
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Result, Entry, Error, RegisterAddress};
+use crate::{error::Result, Entry, EntryHash, Error, RegisterAddress};
 
 use bls::{PublicKey, SecretKey};
 use crdts::merkle_reg::Node as MerkleDagEntry;
@@ -59,6 +59,11 @@ impl RegisterOp {
         self.address
     }
 
+    /// the hash of the entry the operation writes
+    pub(crate) fn entry_hash(&self) -> EntryHash {
+        EntryHash(self.crdt_op.hash())
+    }
+
     /// the entity that generated the operation
     pub fn source(&self) -> PublicKey {
         self.source
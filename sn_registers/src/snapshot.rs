@@ -0,0 +1,121 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, Entry, EntryHash, Error, RegisterAddress};
+
+use bls::{SecretKey, Signature};
+use crdts::merkle_reg::Node as MerkleDagEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A compacted state of a Register, signed by its owner.
+///
+/// The snapshot holds the entry at its cutoff, along with the hashes of the entries it descends
+/// from, which the replicas holding the snapshot drop along with their ops. The clients fetching
+/// a long-lived Register then only get the cutoff entry and the ones written since, instead of
+/// its whole history. Entries written atop the compacted ones, other than the cutoff, are dropped.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct RegisterSnapshot {
+    address: RegisterAddress,
+    /// The entry at the cutoff, along with the hashes of the entries it was written atop.
+    cutoff: MerkleDagEntry<Entry>,
+    /// The hashes of the entries the cutoff entry descends from.
+    compacted: BTreeSet<EntryHash>,
+    /// The signature of the owner of the Register on the address, the cutoff and compacted entries.
+    signature: Signature,
+}
+
+impl RegisterSnapshot {
+    /// Snapshots the Register at the address, signing it with the owner key.
+    pub(crate) fn new(
+        address: RegisterAddress,
+        cutoff: MerkleDagEntry<Entry>,
+        compacted: BTreeSet<EntryHash>,
+        owner: &SecretKey,
+    ) -> Result<Self> {
+        if owner.public_key() != address.owner() {
+            return Err(Error::InvalidSecretKey);
+        }
+        let signature = owner.sign(Self::bytes_for_signing(&address, &cutoff, &compacted)?);
+        Ok(Self {
+            address,
+            cutoff,
+            compacted,
+            signature,
+        })
+    }
+
+    /// The address of the Register the snapshot is of.
+    pub fn address(&self) -> &RegisterAddress {
+        &self.address
+    }
+
+    /// The hash of the entry at the cutoff.
+    pub fn cutoff(&self) -> EntryHash {
+        EntryHash(self.cutoff.hash())
+    }
+
+    /// The entry at the cutoff.
+    pub fn entry(&self) -> &Entry {
+        &self.cutoff.value
+    }
+
+    /// The hashes of the entries compacted away.
+    pub fn compacted(&self) -> &BTreeSet<EntryHash> {
+        &self.compacted
+    }
+
+    /// Whether the entry at `hash` is held by the snapshot, i.e. it's the cutoff entry or was
+    /// compacted away.
+    pub fn covers(&self, hash: EntryHash) -> bool {
+        self.cutoff() == hash || self.compacted.contains(&hash)
+    }
+
+    /// Checks the snapshot was signed by the owner of the Register at the address.
+    pub fn verify(&self, address: &RegisterAddress) -> Result<()> {
+        if &self.address != address {
+            return Err(Error::RegisterAddrMismatch {
+                dst_addr: Box::new(self.address),
+                reg_addr: Box::new(*address),
+            });
+        }
+        let bytes = Self::bytes_for_signing(&self.address, &self.cutoff, &self.compacted)?;
+        if !self.address.owner().verify(&self.signature, bytes) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Returns the most compacted of the two snapshots, so that all replicas keep the same one:
+    /// the one whose compacted entries include the cutoff of the other, or when they were taken
+    /// on concurrent branches, the one compacting the most entries.
+    pub(crate) fn newest<'a>(&'a self, other: &'a Self) -> &'a Self {
+        if self.compacted.contains(&other.cutoff()) {
+            self
+        } else if other.compacted.contains(&self.cutoff()) {
+            other
+        } else if (self.compacted.len(), self.cutoff()) >= (other.compacted.len(), other.cutoff()) {
+            self
+        } else {
+            other
+        }
+    }
+
+    /// The entry at the cutoff, as held in the CRDT.
+    pub(crate) fn cutoff_node(&self) -> &MerkleDagEntry<Entry> {
+        &self.cutoff
+    }
+
+    fn bytes_for_signing(
+        address: &RegisterAddress,
+        cutoff: &MerkleDagEntry<Entry>,
+        compacted: &BTreeSet<EntryHash>,
+    ) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(&(address, cutoff, compacted)).map_err(|_| Error::SerialisationFailed)
+    }
+}
@@ -37,12 +37,20 @@ pub enum Error {
     RpcNetworkInfoError(String),
     #[error("Could not restart node through RPC: {0}")]
     RpcNodeRestartError(String),
+    #[error("Could not obtain bandwidth through RPC: {0}")]
+    RpcBandwidthError(String),
+    #[error("Could not obtain earnings through RPC: {0}")]
+    RpcEarningsError(String),
+    #[error("Could not rotate the identity of the node through RPC: {0}")]
+    RpcRotateIdentityError(String),
     #[error("Could not stop node through RPC: {0}")]
     RpcNodeStopError(String),
     #[error("Could not update node through RPC: {0}")]
     RpcNodeUpdateError(String),
     #[error("Could not obtain record addresses through RPC: {0}")]
     RpcRecordAddressError(String),
+    #[error("Could not obtain record stats through RPC: {0}")]
+    RpcRecordStatsError(String),
     #[error("Could not find process at '{0}'")]
     ServiceProcessNotFound(String),
     #[error("The service '{0}' does not exists and cannot be removed.")]
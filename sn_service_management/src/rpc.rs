@@ -11,12 +11,13 @@ use async_trait::async_trait;
 use libp2p::{kad::RecordKey, Multiaddr, PeerId};
 use sn_protocol::{
     safenode_proto::{
-        safe_node_client::SafeNodeClient, NetworkInfoRequest, NodeInfoRequest,
-        RecordAddressesRequest, RestartRequest, StopRequest, UpdateLogLevelRequest, UpdateRequest,
+        safe_node_client::SafeNodeClient, BandwidthRequest, EarningsRequest, NetworkInfoRequest,
+        NodeInfoRequest, RecordAddressesRequest, RecordStatsRequest, RestartRequest,
+        RotateIdentityRequest, StopRequest, UpdateLogLevelRequest, UpdateRequest,
     },
     CLOSE_GROUP_SIZE,
 };
-use std::{net::SocketAddr, path::PathBuf, str::FromStr};
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf, str::FromStr};
 use tokio::time::Duration;
 use tonic::Request;
 use tracing::error;
@@ -30,6 +31,7 @@ pub struct NodeInfo {
     pub version: String,
     pub uptime: Duration,
     pub wallet_balance: u64,
+    pub wallet_address: String,
 }
 
 #[derive(Debug, Clone)]
@@ -43,12 +45,44 @@ pub struct RecordAddress {
     pub key: RecordKey,
 }
 
+#[derive(Debug, Clone)]
+pub struct RecordStats {
+    pub records: u64,
+    pub used_bytes: u64,
+    pub max_bytes: Option<u64>,
+}
+
+/// The rewards earned by a node, in nanos.
+#[derive(Debug, Clone)]
+pub struct Earnings {
+    pub total: u64,
+    pub payments: u64,
+    /// Keyed by the number of days since the unix epoch.
+    pub per_day: BTreeMap<u64, u64>,
+    pub per_record_kind: BTreeMap<String, u64>,
+    /// Keyed by the `PeerId` the paid records were published with.
+    pub per_payer: BTreeMap<String, u64>,
+}
+
+/// The bytes a node sent to and received from one of its peers over a protocol.
+#[derive(Debug, Clone)]
+pub struct PeerBandwidth {
+    pub peer_id: PeerId,
+    pub protocol: String,
+    pub sent: u64,
+    pub received: u64,
+}
+
 #[async_trait]
 pub trait RpcActions: Sync {
     async fn node_info(&self) -> Result<NodeInfo>;
     async fn network_info(&self) -> Result<NetworkInfo>;
     async fn record_addresses(&self) -> Result<Vec<RecordAddress>>;
+    async fn record_stats(&self) -> Result<RecordStats>;
+    async fn earnings(&self) -> Result<Earnings>;
+    async fn bandwidth(&self) -> Result<Vec<PeerBandwidth>>;
     async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> Result<()>;
+    async fn rotate_identity(&self, delay_millis: u64) -> Result<PeerId>;
     async fn node_stop(&self, delay_millis: u64) -> Result<()>;
     async fn node_update(&self, delay_millis: u64) -> Result<()>;
     async fn is_node_connected_to_network(&self, timeout: Duration) -> Result<()>;
@@ -138,6 +172,7 @@ impl RpcActions for RpcClient {
             version: node_info_resp.bin_version.clone(),
             uptime: Duration::from_secs(node_info_resp.uptime_secs),
             wallet_balance: node_info_resp.wallet_balance,
+            wallet_address: node_info_resp.wallet_address.clone(),
         };
         Ok(node_info)
     }
@@ -187,6 +222,66 @@ impl RpcActions for RpcClient {
         Ok(record_addresses)
     }
 
+    async fn record_stats(&self) -> Result<RecordStats> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .record_stats(Request::new(RecordStatsRequest {}))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain record stats through RPC: {e:?}");
+                Error::RpcRecordStatsError(e.to_string())
+            })?;
+        let record_stats = response.get_ref();
+        Ok(RecordStats {
+            records: record_stats.records,
+            used_bytes: record_stats.used_bytes,
+            max_bytes: (record_stats.max_bytes > 0).then_some(record_stats.max_bytes),
+        })
+    }
+
+    async fn earnings(&self) -> Result<Earnings> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .earnings(Request::new(EarningsRequest {}))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain earnings through RPC: {e:?}");
+                Error::RpcEarningsError(e.to_string())
+            })?;
+        let earnings = response.into_inner();
+        Ok(Earnings {
+            total: earnings.total,
+            payments: earnings.payments,
+            per_day: earnings.per_day.into_iter().collect(),
+            per_record_kind: earnings.per_record_kind.into_iter().collect(),
+            per_payer: earnings.per_payer.into_iter().collect(),
+        })
+    }
+
+    async fn bandwidth(&self) -> Result<Vec<PeerBandwidth>> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .bandwidth(Request::new(BandwidthRequest {}))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain bandwidth through RPC: {e:?}");
+                Error::RpcBandwidthError(e.to_string())
+            })?;
+        response
+            .into_inner()
+            .bandwidth
+            .into_iter()
+            .map(|bandwidth| {
+                Ok(PeerBandwidth {
+                    peer_id: PeerId::from_bytes(&bandwidth.peer)?,
+                    protocol: bandwidth.protocol,
+                    sent: bandwidth.sent,
+                    received: bandwidth.received,
+                })
+            })
+            .collect()
+    }
+
     async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> Result<()> {
         let mut client = self.connect_with_retry().await?;
         let _response = client
@@ -202,6 +297,19 @@ impl RpcActions for RpcClient {
         Ok(())
     }
 
+    async fn rotate_identity(&self, delay_millis: u64) -> Result<PeerId> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .rotate_identity(Request::new(RotateIdentityRequest { delay_millis }))
+            .await
+            .map_err(|e| {
+                error!("Could not rotate the identity of the node through RPC: {e:?}");
+                Error::RpcRotateIdentityError(e.to_string())
+            })?;
+        let peer_id = PeerId::from_bytes(&response.into_inner().new_peer_id)?;
+        Ok(peer_id)
+    }
+
     async fn node_stop(&self, delay_millis: u64) -> Result<()> {
         let mut client = self.connect_with_retry().await?;
         let _response = client
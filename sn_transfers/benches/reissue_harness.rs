@@ -0,0 +1,123 @@
+// Copyright 2024 MaidSafe.net limited.
+
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Runs the parameterized reissue benchmark and prints its results as JSON lines.
+//!
+//! ```text
+//! cargo bench -p sn_transfers --features test-utils --bench reissue_harness -- \
+//!     --inputs 100 --outputs 1 --input-keys 10 --output-keys 1 --mode parallel,serial
+//! ```
+//!
+//! Every comma separated value of `--inputs`, `--outputs`, `--input-keys`, `--output-keys`
+//! and `--mode` is combined with all the others, giving one result line per configuration.
+
+use sn_transfers::test_utils::{run_reissue_bench, ReissueBenchConfig, VerificationMode};
+use std::{fmt::Debug, str::FromStr};
+
+struct Args {
+    inputs: Vec<usize>,
+    outputs: Vec<usize>,
+    input_keys: Vec<usize>,
+    output_keys: Vec<usize>,
+    modes: Vec<VerificationMode>,
+    iterations: usize,
+}
+
+fn parse_list<T: FromStr>(flag: &str, value: &str) -> Result<Vec<T>, String>
+where
+    T::Err: Debug,
+{
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .parse()
+                .map_err(|err| format!("Invalid value {item:?} for {flag}: {err:?}"))
+        })
+        .collect()
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        inputs: vec![1, 100],
+        outputs: vec![100, 1],
+        input_keys: vec![1],
+        output_keys: vec![1],
+        modes: vec![
+            VerificationMode::Serial,
+            VerificationMode::Parallel,
+            VerificationMode::Aggregated,
+        ],
+        iterations: 100,
+    };
+
+    let mut argv = std::env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        // `cargo bench` passes `--bench` to every harness-less bench target
+        if flag == "--bench" {
+            continue;
+        }
+        let value = argv
+            .next()
+            .ok_or_else(|| format!("Missing value for {flag}"))?;
+        match flag.as_str() {
+            "--inputs" => args.inputs = parse_list(&flag, &value)?,
+            "--outputs" => args.outputs = parse_list(&flag, &value)?,
+            "--input-keys" => args.input_keys = parse_list(&flag, &value)?,
+            "--output-keys" => args.output_keys = parse_list(&flag, &value)?,
+            "--mode" => args.modes = parse_list(&flag, &value)?,
+            "--iterations" => {
+                args.iterations = value
+                    .parse()
+                    .map_err(|err| format!("Invalid value {value:?} for {flag}: {err:?}"))?
+            }
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+    Ok(args)
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+
+    for &inputs in &args.inputs {
+        for &outputs in &args.outputs {
+            for &input_keys in &args.input_keys {
+                for &output_keys in &args.output_keys {
+                    for &mode in &args.modes {
+                        let config = ReissueBenchConfig {
+                            inputs,
+                            outputs,
+                            input_keys,
+                            output_keys,
+                            mode,
+                            iterations: args.iterations,
+                            ..Default::default()
+                        };
+                        match run_reissue_bench(config) {
+                            Ok(result) => match serde_json::to_string(&result) {
+                                Ok(json) => println!("{json}"),
+                                Err(err) => eprintln!("Failed to serialize result: {err}"),
+                            },
+                            Err(err) => eprintln!(
+                                "Bench with {inputs} inputs, {outputs} outputs, {input_keys} \
+                                 input keys, {output_keys} output keys in {mode} mode failed: {err}"
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -10,6 +10,7 @@ mod address;
 mod builder;
 mod cashnote;
 mod hash;
+mod input_verification;
 mod nano;
 mod signed_spend;
 mod spend_reason;
@@ -23,11 +24,15 @@ pub use address::SpendAddress;
 pub use builder::UnsignedTransfer;
 pub use cashnote::CashNote;
 pub use hash::Hash;
+pub use input_verification::{InputFailure, InputsVerificationReport};
 pub use nano::NanoTokens;
-pub use signed_spend::{SignedSpend, Spend};
+pub use signed_spend::{SignedSpend, Spend, SpendSubmissionToken};
 pub use spend_reason::SpendReason;
-pub use transaction::Transaction;
-pub use unique_keys::{DerivationIndex, DerivedSecretKey, MainPubkey, MainSecretKey, UniquePubkey};
+pub use transaction::{ReclaimClause, Transaction};
+pub use unique_keys::{
+    DerivationIndex, DerivedSecretKey, MainPubkey, MainSecretKey, UniquePubkey, MAIN_PUBKEY_HRP,
+    UNIQUE_PUBKEY_HRP,
+};
 
 #[cfg(test)]
 pub(crate) mod tests {
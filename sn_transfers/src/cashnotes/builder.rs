@@ -8,7 +8,7 @@
 
 use super::{
     spend_reason::SpendReason,
-    transaction::{Output, Transaction},
+    transaction::{Output, ReclaimClause, Transaction},
     CashNote, DerivationIndex, DerivedSecretKey, Input, MainPubkey, NanoTokens, SignedSpend, Spend,
     UniquePubkey,
 };
@@ -90,6 +90,21 @@ impl TransactionBuilder {
         self
     }
 
+    /// Add an output which the sender can reclaim as per the given clause
+    pub fn add_reclaimable_output(
+        mut self,
+        token: NanoTokens,
+        main_pubkey: MainPubkey,
+        derivation_index: DerivationIndex,
+        reclaim: ReclaimClause,
+    ) -> Self {
+        self = self.add_output(token, main_pubkey, derivation_index);
+        if let Some(output) = self.outputs.last_mut() {
+            output.reclaim = Some(reclaim);
+        }
+        self
+    }
+
     /// Add a list of outputs given the tokens, the MainPubkey and the DerivationIndex
     pub fn add_outputs(
         mut self,
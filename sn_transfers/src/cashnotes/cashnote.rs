@@ -11,7 +11,10 @@ use super::{
     Transaction, UniquePubkey,
 };
 
-use crate::{Result, TransferError};
+use crate::{
+    versioned::{from_versioned_bytes, to_versioned_bytes, Versioned},
+    Result, TransferError,
+};
 
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
@@ -168,20 +171,23 @@ impl CashNote {
     }
 
     /// Deserializes a `CashNote` represented as a hex string to a `CashNote`.
+    /// Hex strings written by older versions of this crate are upgraded.
     pub fn from_hex(hex: &str) -> Result<Self, TransferError> {
         let mut bytes =
             hex::decode(hex).map_err(|e| TransferError::HexDeserializationFailed(e.to_string()))?;
         bytes.reverse();
-        let cashnote: CashNote = rmp_serde::from_slice(&bytes)
-            .map_err(|e| TransferError::HexDeserializationFailed(e.to_string()))?;
-        Ok(cashnote)
+        from_versioned_bytes(&bytes)
     }
 
-    /// Serialize this `CashNote` instance to a hex string.
+    /// Serialize this `CashNote` instance to a version tagged hex string.
     pub fn to_hex(&self) -> Result<String, TransferError> {
-        let mut serialized = rmp_serde::to_vec(&self)
+        let mut serialized = to_versioned_bytes(self)
             .map_err(|e| TransferError::HexSerializationFailed(e.to_string()))?;
         serialized.reverse();
         Ok(hex::encode(serialized))
     }
 }
+
+impl Versioned for CashNote {
+    const VERSION: u8 = 1;
+}
@@ -0,0 +1,213 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#[cfg(test)]
+use super::SignedSpend;
+use super::{NanoTokens, UniquePubkey};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// Why an input of a transaction failed verification against its signed spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFailure {
+    /// No signed spend was provided for the input.
+    MissingSpend,
+    /// The spend was not signed by the key of the CashNote being spent.
+    BadSignature,
+    /// The amount of the spend differs from the input, or from the output that created it.
+    AmountMismatch {
+        /// The amount of the input in the transaction.
+        expected: NanoTokens,
+        /// The amount claimed by the spend.
+        claimed: NanoTokens,
+    },
+    /// The CashNote being spent is not an output of the spend's parent tx.
+    WrongParentTx,
+    /// The spend was not spent in the transaction being verified.
+    WrongSpentTx,
+}
+
+impl fmt::Display for InputFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSpend => write!(f, "missing signed spend"),
+            Self::BadSignature => write!(f, "bad signature"),
+            Self::AmountMismatch { expected, claimed } => {
+                write!(
+                    f,
+                    "amount mismatch, expected {expected}, spend claims {claimed}"
+                )
+            }
+            Self::WrongParentTx => write!(f, "not an output of its parent tx"),
+            Self::WrongSpentTx => write!(f, "spent in another tx"),
+        }
+    }
+}
+
+/// The outcome of verifying the inputs of a transaction against their signed spends,
+/// listing every failing input rather than stopping at the first one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputsVerificationReport {
+    /// The inputs which failed verification, and why.
+    pub failed_inputs: BTreeMap<UniquePubkey, InputFailure>,
+    /// Signed spends which don't correspond to any input of the transaction.
+    pub unexpected_spends: BTreeSet<UniquePubkey>,
+}
+
+impl InputsVerificationReport {
+    /// Returns true if all inputs were verified and no unexpected spends were given.
+    pub fn is_valid(&self) -> bool {
+        self.failed_inputs.is_empty() && self.unexpected_spends.is_empty()
+    }
+}
+
+impl fmt::Display for InputsVerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut issues = self
+            .failed_inputs
+            .iter()
+            .map(|(unique_pubkey, failure)| format!("input {unique_pubkey}: {failure}"))
+            .chain(
+                self.unexpected_spends
+                    .iter()
+                    .map(|unique_pubkey| format!("spend {unique_pubkey}: not an input of the tx")),
+            )
+            .peekable();
+        if issues.peek().is_none() {
+            return write!(f, "all inputs are valid");
+        }
+        write!(f, "{}", issues.collect::<Vec<_>>().join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        create_first_cash_note_from_key, CashNote, DerivationIndex, MainSecretKey, OfflineTransfer,
+        Result, SpendReason, TransferError,
+    };
+
+    // Returns a transfer spending two CashNotes, along with a transfer spending another CashNote.
+    fn two_input_transfer() -> Result<(OfflineTransfer, OfflineTransfer)> {
+        let mut rng = crate::rng::from_seed([3u8; 32]);
+        let genesis_key = MainSecretKey::random_from_rng(&mut rng);
+        let owner = MainSecretKey::random_from_rng(&mut rng);
+        let genesis =
+            create_first_cash_note_from_key(&genesis_key).expect("Genesis creation to succeed.");
+
+        let recipients = (0..3)
+            .map(|_| {
+                (
+                    NanoTokens::from(100),
+                    owner.main_pubkey(),
+                    DerivationIndex::random(&mut rng),
+                )
+            })
+            .collect();
+        let funding = OfflineTransfer::new(
+            vec![(genesis.clone(), Some(genesis.derived_key(&genesis_key)?))],
+            recipients,
+            genesis_key.main_pubkey(),
+            SpendReason::default(),
+        )?;
+
+        let spend = |cash_notes: &[CashNote], amount: u64| -> Result<OfflineTransfer> {
+            let inputs = cash_notes
+                .iter()
+                .map(|cash_note| Ok((cash_note.clone(), Some(cash_note.derived_key(&owner)?))))
+                .collect::<Result<_>>()?;
+            OfflineTransfer::new(
+                inputs,
+                vec![(
+                    NanoTokens::from(amount),
+                    genesis_key.main_pubkey(),
+                    DerivationIndex([9u8; 32]),
+                )],
+                owner.main_pubkey(),
+                SpendReason::default(),
+            )
+        };
+        let cash_notes = &funding.cash_notes_for_recipient;
+        Ok((spend(&cash_notes[..2], 150)?, spend(&cash_notes[2..], 50)?))
+    }
+
+    #[test]
+    fn valid_inputs_give_a_valid_report() -> Result<()> {
+        let (transfer, _) = two_input_transfer()?;
+        let report = transfer
+            .tx
+            .verify_inputs_spent(&transfer.all_spend_requests);
+        assert!(report.is_valid(), "{report}");
+        Ok(())
+    }
+
+    #[test]
+    fn every_failing_input_is_reported() -> Result<()> {
+        let (transfer, other) = two_input_transfer()?;
+        let mut spends = transfer.all_spend_requests.clone();
+        let missing = *spends.remove(0).unique_pubkey();
+        let unexpected = *other.all_spend_requests[0].unique_pubkey();
+
+        let mut report = transfer
+            .tx
+            .verify_inputs_spent(spends.iter().chain(&other.all_spend_requests));
+        assert_eq!(
+            report.failed_inputs.remove(&missing),
+            Some(InputFailure::MissingSpend)
+        );
+        assert!(report.failed_inputs.is_empty());
+        assert_eq!(report.unexpected_spends, BTreeSet::from([unexpected]));
+
+        assert!(matches!(
+            transfer.tx.verify_against_inputs_spent(&spends),
+            Err(TransferError::InvalidInputs(report)) if report.failed_inputs.len() == 1
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_spends_report_the_reason() -> Result<()> {
+        let (transfer, other) = two_input_transfer()?;
+        let tx = &transfer.tx;
+        let first = transfer.all_spend_requests[0].clone();
+        let failure_of = |signed_spend: SignedSpend| {
+            let key = *signed_spend.unique_pubkey();
+            let spends = [signed_spend, transfer.all_spend_requests[1].clone()];
+            tx.verify_inputs_spent(&spends)
+                .failed_inputs
+                .get(&key)
+                .copied()
+        };
+
+        let mut bad_signature = first.clone();
+        bad_signature.derived_key_sig = other.all_spend_requests[0].derived_key_sig.clone();
+        assert_eq!(failure_of(bad_signature), Some(InputFailure::BadSignature));
+
+        let mut bad_amount = first.clone();
+        bad_amount.spend.amount = NanoTokens::from(1);
+        assert_eq!(
+            failure_of(bad_amount),
+            Some(InputFailure::AmountMismatch {
+                expected: NanoTokens::from(100),
+                claimed: NanoTokens::from(1),
+            })
+        );
+
+        let mut bad_parent = first.clone();
+        bad_parent.spend.parent_tx = other.tx.clone();
+        assert_eq!(failure_of(bad_parent), Some(InputFailure::WrongParentTx));
+
+        let mut bad_spent_tx = first;
+        bad_spent_tx.spend.spent_tx = other.tx.clone();
+        assert_eq!(failure_of(bad_spent_tx), Some(InputFailure::WrongSpentTx));
+        Ok(())
+    }
+}
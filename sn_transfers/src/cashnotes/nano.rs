@@ -50,6 +50,11 @@ impl NanoTokens {
         self.0.checked_add(rhs.0).map(Self::from)
     }
 
+    /// Computes `self + rhs`, saturating at the maximum amount instead of overflowing.
+    pub fn saturating_add(self, rhs: NanoTokens) -> NanoTokens {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
     /// Computes `self - rhs`, returning `None` if overflow occurred.
     pub fn checked_sub(self, rhs: NanoTokens) -> Option<NanoTokens> {
         self.0.checked_sub(rhs.0).map(Self::from)
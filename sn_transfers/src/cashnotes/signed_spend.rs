@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::spend_reason::SpendReason;
-use super::{Hash, NanoTokens, Transaction, UniquePubkey};
+use super::{Hash, NanoTokens, ReclaimClause, Transaction, UniquePubkey};
 use crate::{DerivationIndex, Result, Signature, SpendAddress, TransferError};
 
 use custom_debug::Debug;
@@ -25,6 +25,27 @@ pub struct SignedSpend {
     pub derived_key_sig: Signature,
 }
 
+/// Identifies a submission of a `SignedSpend` to the network.
+///
+/// It is derived from the whole signed spend rather than sent along with it, so every retry of
+/// the same spend carries the same token, while a different spend of the same CashNote doesn't.
+/// This lets nodes acknowledge a retried submission without validating it again, and wallets
+/// re-send their unconfirmed spends after a timeout without risking a conflicting spend.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, std::hash::Hash, Serialize, Deserialize)]
+pub struct SpendSubmissionToken(Hash);
+
+impl std::fmt::Debug for SpendSubmissionToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SpendSubmissionToken({})", self.0.to_hex())
+    }
+}
+
+impl std::fmt::Display for SpendSubmissionToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.to_hex())
+    }
+}
+
 impl SignedSpend {
     /// Get public key of input CashNote.
     pub fn unique_pubkey(&self) -> &UniquePubkey {
@@ -69,17 +90,28 @@ impl SignedSpend {
         bytes
     }
 
+    /// Get the token identifying submissions of this exact SignedSpend.
+    pub fn submission_token(&self) -> SpendSubmissionToken {
+        let mut bytes = self.to_bytes();
+        for derivation_index in &self.spend.network_royalties {
+            bytes.extend(derivation_index.0);
+        }
+        SpendSubmissionToken(Hash::hash(&bytes))
+    }
+
     /// Verify a SignedSpend
     ///
     /// Checks that
     /// - the spend was indeed spent for the given Tx
-    /// - it was signed by the DerivedSecretKey that owns the CashNote for this Spend
+    /// - it was signed by the DerivedSecretKey that owns the CashNote for this Spend,
+    ///   or by the refund key of a reclaimable CashNote
     /// - the signature is valid
     /// - its value didn't change between the two transactions it is involved in (creation and spending)
     ///
     /// It does NOT check:
     /// - if the spend exists on the Network
     /// - the spend's parents and if they exist on the Network
+    /// - the deadline of a refund, which depends on the time, see `verify_reclaim_deadline`
     pub fn verify(&self, spent_tx_hash: Hash) -> Result<()> {
         // verify that input spent_tx_hash matches self.spent_tx_hash
         if spent_tx_hash != self.spent_tx_hash() {
@@ -144,12 +176,54 @@ impl SignedSpend {
             .unique_pubkey
             .verify(&self.derived_key_sig, self.spend.to_bytes_for_signing())
         {
+            return Ok(());
+        }
+
+        // or it's a refund of a reclaimable CashNote, signed by the refund key
+        if self.is_refund() {
             Ok(())
         } else {
             Err(TransferError::InvalidSpendSignature(*self.unique_pubkey()))
         }
     }
 
+    /// Verify that a refund of a reclaimable CashNote was made once its deadline had passed at
+    /// `now`, in seconds since the unix epoch. The other spends are always valid.
+    ///
+    /// It's left out of `verify`, so that the validity of a spend doesn't depend on the clock of
+    /// the verifier: the nodes check it when a spend is put, from their own clock.
+    pub fn verify_reclaim_deadline(&self, now: u64) -> Result<()> {
+        match self.reclaim_clause() {
+            Some(reclaim)
+                if self.is_signed_by(&reclaim.refund_key) && !reclaim.has_expired(now) =>
+            {
+                Err(TransferError::ReclaimBeforeDeadline(*self.unique_pubkey()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns true if this spend is a refund of a reclaimable CashNote, made by its sender,
+    /// rather than a spend by its recipient. The deadline is not checked.
+    pub fn is_refund(&self) -> bool {
+        self.reclaim_clause()
+            .is_some_and(|reclaim| self.is_signed_by(&reclaim.refund_key))
+    }
+
+    /// The reclaim clause of the CashNote being spent, if its sender can reclaim it.
+    pub fn reclaim_clause(&self) -> Option<ReclaimClause> {
+        self.spend
+            .parent_tx
+            .outputs
+            .iter()
+            .find(|o| o.unique_pubkey == self.spend.unique_pubkey)
+            .and_then(|o| o.reclaim)
+    }
+
+    fn is_signed_by(&self, key: &UniquePubkey) -> bool {
+        key.verify(&self.derived_key_sig, self.spend.to_bytes_for_signing())
+    }
+
     /// Verify the parents of this Spend, making sure the input parent_spends are ancestors of self.
     /// - Also handles the case of parent double spends.
     /// - verifies that the parent_spends where spent in our spend's parent_tx.
@@ -287,3 +361,39 @@ impl Ord for Spend {
         self.unique_pubkey.cmp(&other.unique_pubkey)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_first_cash_note_from_key, MainSecretKey};
+
+    #[test]
+    fn submission_token_only_matches_the_same_spend() -> Result<()> {
+        let key = MainSecretKey::random();
+        let cash_note =
+            create_first_cash_note_from_key(&key).expect("Genesis creation to succeed.");
+        let signed_spend = cash_note
+            .parent_spends
+            .first()
+            .cloned()
+            .ok_or(TransferError::CashNoteHasNoParentSpends)?;
+
+        let retried = signed_spend.clone();
+        assert_eq!(signed_spend.submission_token(), retried.submission_token());
+
+        let mut other = signed_spend.clone();
+        other.spend.reason = SpendReason::NetworkData(xor_name::XorName([1u8; 32]));
+        assert_ne!(signed_spend.submission_token(), other.submission_token());
+
+        let mut with_royalties = signed_spend.clone();
+        with_royalties
+            .spend
+            .network_royalties
+            .push(DerivationIndex([1u8; 32]));
+        assert_ne!(
+            signed_spend.submission_token(),
+            with_royalties.submission_token()
+        );
+        Ok(())
+    }
+}
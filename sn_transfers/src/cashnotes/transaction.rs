@@ -4,9 +4,12 @@
 // This SAFE Network Software is licensed under the BSD-3-Clause license.
 // Please see the LICENSE file for more details.
 
-use super::{NanoTokens, SignedSpend, UniquePubkey};
+use super::{InputFailure, InputsVerificationReport, NanoTokens, SignedSpend, UniquePubkey};
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, collections::BTreeSet};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+};
 use tiny_keccak::{Hasher, Sha3};
 
 use crate::TransferError;
@@ -39,10 +42,40 @@ impl Input {
     }
 }
 
+/// Lets the sender of an output take it back with a refund spend, signed by the refund key,
+/// if the recipient hasn't spent it by the deadline.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub struct ReclaimClause {
+    /// The key signing the refund spend, derived from the sender's key.
+    pub refund_key: UniquePubkey,
+    /// Seconds since the unix epoch from which the output can be reclaimed.
+    pub deadline: u64,
+}
+
+impl ReclaimClause {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut v: Vec<u8> = Default::default();
+        v.extend("reclaim".as_bytes());
+        v.extend(self.refund_key.to_bytes().as_ref());
+        v.extend(self.deadline.to_be_bytes());
+        v
+    }
+
+    /// Returns true if the deadline has passed at `now`, in seconds since the unix epoch, so the
+    /// output can be reclaimed.
+    pub fn has_expired(&self, now: u64) -> bool {
+        now >= self.deadline
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
 pub struct Output {
     pub unique_pubkey: UniquePubkey,
     pub amount: NanoTokens,
+    /// Set if the sender can reclaim the output, it's left out of the serialised output
+    /// otherwise, so outputs created before reclaimable transfers keep their encoding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reclaim: Option<ReclaimClause>,
 }
 
 impl Output {
@@ -50,6 +83,7 @@ impl Output {
         Self {
             unique_pubkey,
             amount: NanoTokens::from(amount),
+            reclaim: None,
         }
     }
 
@@ -57,6 +91,9 @@ impl Output {
         let mut v: Vec<u8> = Default::default();
         v.extend(self.unique_pubkey.to_bytes().as_ref());
         v.extend(self.amount.to_bytes());
+        if let Some(reclaim) = &self.reclaim {
+            v.extend(reclaim.to_bytes());
+        }
         v
     }
 
@@ -161,6 +198,8 @@ impl Transaction {
     /// - the inputs and outputs are different
     /// - the inputs have a corresponding signed spend
     /// - those signed spends are valid and refer to this transaction
+    ///
+    /// If any input fails, the returned `TransferError::InvalidInputs` details all failing inputs.
     pub fn verify_against_inputs_spent<'a, T>(&self, signed_spends: T) -> Result<()>
     where
         T: IntoIterator<Item = &'a SignedSpend> + Clone,
@@ -170,22 +209,6 @@ impl Transaction {
             return Err(TransferError::MissingTxInputs);
         }
 
-        // check spends match the inputs
-        let input_keys = self
-            .inputs
-            .iter()
-            .map(|i| i.unique_pubkey())
-            .collect::<BTreeSet<_>>();
-        let signed_spend_keys = signed_spends
-            .clone()
-            .into_iter()
-            .map(|s| s.unique_pubkey())
-            .collect::<BTreeSet<_>>();
-        if input_keys != signed_spend_keys {
-            debug!("SpendsDoNotMatchInputs: {input_keys:#?} != {signed_spend_keys:#?}");
-            return Err(TransferError::SpendsDoNotMatchInputs);
-        }
-
         // Verify that each output is unique
         let output_pks: BTreeSet<&UniquePubkey> =
             self.outputs.iter().map(|o| (o.unique_pubkey())).collect();
@@ -205,16 +228,69 @@ impl Transaction {
             return Err(TransferError::UniquePubkeyNotUniqueInTx);
         }
 
-        // Verify that each signed spend is valid and was spent in this transaction
-        let spent_tx_hash = self.hash();
-        for s in signed_spends {
-            s.verify(spent_tx_hash)?;
+        // Verify that each input has a valid signed spend, spent in this transaction
+        let report = self.verify_inputs_spent(signed_spends);
+        if !report.is_valid() {
+            debug!("Invalid inputs for tx {:?}: {report}", self.hash());
+            return Err(TransferError::InvalidInputs(report));
         }
 
         // Verify that the transaction is balanced
         self.verify_balanced()
     }
 
+    /// Verifies each input of the transaction against its signed spend, reporting every
+    /// input that failed and why, along with any signed spend that isn't an input of this tx.
+    ///
+    /// Unlike `verify_against_inputs_spent`, it doesn't check the balance or uniqueness of the
+    /// inputs and outputs.
+    pub fn verify_inputs_spent<'a, T>(&self, signed_spends: T) -> InputsVerificationReport
+    where
+        T: IntoIterator<Item = &'a SignedSpend>,
+    {
+        let mut spends_by_key: BTreeMap<&UniquePubkey, Vec<&SignedSpend>> = BTreeMap::new();
+        for signed_spend in signed_spends {
+            spends_by_key
+                .entry(signed_spend.unique_pubkey())
+                .or_default()
+                .push(signed_spend);
+        }
+
+        let spent_tx_hash = self.hash();
+        let mut report = InputsVerificationReport::default();
+        for input in &self.inputs {
+            let failure = match spends_by_key.remove(input.unique_pubkey()) {
+                None => Some(InputFailure::MissingSpend),
+                Some(spends) => spends.into_iter().find_map(|signed_spend| {
+                    let failure = match signed_spend.verify(spent_tx_hash) {
+                        Ok(()) if signed_spend.spend.amount != input.amount => {
+                            InputFailure::AmountMismatch {
+                                expected: input.amount,
+                                claimed: signed_spend.spend.amount,
+                            }
+                        }
+                        Ok(()) => return None,
+                        Err(TransferError::InvalidSpendValue(_)) => InputFailure::AmountMismatch {
+                            expected: input.amount,
+                            claimed: signed_spend.spend.amount,
+                        },
+                        Err(TransferError::InvalidParentTx(_)) => InputFailure::WrongParentTx,
+                        Err(TransferError::InvalidSpendSignature(_)) => InputFailure::BadSignature,
+                        // a hash mismatch, or not being an input of its own spent tx
+                        Err(_) => InputFailure::WrongSpentTx,
+                    };
+                    Some(failure)
+                }),
+            };
+            if let Some(failure) = failure {
+                let _ = report.failed_inputs.insert(input.unique_pubkey, failure);
+            }
+        }
+        report.unexpected_spends = spends_by_key.into_keys().copied().collect();
+
+        report
+    }
+
     /// Deserializes a `Transaction` represented as a hex string to a `Transaction`.
     pub fn from_hex(hex: &str) -> Result<Self> {
         let mut bytes =
@@ -9,9 +9,15 @@
 use crate::rand::{distributions::Standard, Rng, RngCore};
 use crate::wallet::{Error, Result};
 
+use bech32::{primitives::decode::CheckedHrpstring, Bech32m, Hrp};
 use bls::{serde_impl::SerdeSecret, PublicKey, SecretKey, PK_SIZE};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{fmt, str::FromStr};
+
+/// The human readable part of a `MainPubkey` address.
+pub const MAIN_PUBKEY_HRP: &str = "safe";
+/// The human readable part of a `UniquePubkey` address.
+pub const UNIQUE_PUBKEY_HRP: &str = "safeid";
 
 /// This is used to generate a new UniquePubkey
 /// from a MainPubkey, and the corresponding
@@ -70,6 +76,30 @@ impl UniquePubkey {
         let public_key = bls_public_from_hex(hex)?;
         Ok(Self::new(public_key))
     }
+
+    /// Encodes the key as a checksummed bech32m address, e.g. `safeid1...`.
+    pub fn to_address(&self) -> String {
+        bls_public_to_address(UNIQUE_PUBKEY_HRP, &self.0)
+    }
+
+    /// Parses a bech32m address made with `to_address`, detecting typos and truncation.
+    pub fn from_address(address: &str) -> Result<Self> {
+        let public_key = bls_public_from_address(UNIQUE_PUBKEY_HRP, address)?;
+        Ok(Self::new(public_key))
+    }
+}
+
+/// Parses either a bech32m address or a hex encoded key.
+impl FromStr for UniquePubkey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if is_hex_key(s) {
+            Self::from_hex(s)
+        } else {
+            Self::from_address(s)
+        }
+    }
 }
 
 /// Custom implementation of Serialize and Deserialize for UniquePubkey to make it an actionable
@@ -238,6 +268,36 @@ impl MainPubkey {
         let public_key = bls_public_from_hex(hex)?;
         Ok(Self::new(public_key))
     }
+
+    /// Encodes the key as a checksummed bech32m address, e.g. `safe1...`.
+    /// This is the format to share with others to receive payments.
+    pub fn to_address(&self) -> String {
+        bls_public_to_address(MAIN_PUBKEY_HRP, &self.0)
+    }
+
+    /// Parses a bech32m address made with `to_address`, detecting typos and truncation.
+    pub fn from_address(address: &str) -> Result<Self> {
+        let public_key = bls_public_from_address(MAIN_PUBKEY_HRP, address)?;
+        Ok(Self::new(public_key))
+    }
+
+    /// Returns true if the string is a valid `MainPubkey` address.
+    pub fn is_valid_address(address: &str) -> bool {
+        Self::from_address(address).is_ok()
+    }
+}
+
+/// Parses either a bech32m address or a hex encoded key.
+impl FromStr for MainPubkey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if is_hex_key(s) {
+            Self::from_hex(s)
+        } else {
+            Self::from_address(s)
+        }
+    }
 }
 
 impl std::fmt::Debug for MainPubkey {
@@ -326,6 +386,45 @@ fn bls_public_from_hex<T: AsRef<[u8]>>(hex: T) -> Result<bls::PublicKey> {
     Ok(pk)
 }
 
+fn bls_public_to_address(hrp: &str, public_key: &PublicKey) -> String {
+    // The HRPs are valid constants, and the data length is far below the bech32m limit.
+    bech32::encode::<Bech32m>(Hrp::parse_unchecked(hrp), &public_key.to_bytes()).unwrap_or_default()
+}
+
+/// Construct a BLS public key from a bech32m address with the expected HRP.
+fn bls_public_from_address(expected_hrp: &str, address: &str) -> Result<bls::PublicKey> {
+    let address = address.trim();
+    let checked = CheckedHrpstring::new::<Bech32m>(address)
+        .map_err(|err| Error::InvalidAddress(err.to_string()))?;
+    let hrp = checked.hrp().to_lowercase();
+    if hrp != expected_hrp {
+        return Err(Error::AddressHrpMismatch {
+            expected: expected_hrp.to_string(),
+            found: hrp,
+        });
+    }
+
+    let bytes: Vec<u8> = checked.byte_iter().collect();
+    let bytes_fixed_len: [u8; bls::PK_SIZE] = bytes.as_slice().try_into().map_err(|_| {
+        Error::InvalidAddress(format!(
+            "expected a {} byte key, found {} bytes",
+            bls::PK_SIZE,
+            bytes.len()
+        ))
+    })?;
+    let pk = bls::PublicKey::from_bytes(bytes_fixed_len)?;
+
+    // reject non zero padding bits, so each key has a single valid address
+    if !address.eq_ignore_ascii_case(&bls_public_to_address(expected_hrp, &pk)) {
+        return Err(Error::InvalidAddress("non canonical encoding".to_string()));
+    }
+    Ok(pk)
+}
+
+fn is_hex_key(s: &str) -> bool {
+    s.len() == bls::PK_SIZE * 2 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -389,4 +488,65 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_pubkeys_address_conversion() -> eyre::Result<()> {
+        let main_pubkey = MainSecretKey::random().main_pubkey();
+        let unique_pubkey =
+            main_pubkey.new_unique_pubkey(&DerivationIndex::random(&mut rand::thread_rng()));
+
+        let main_address = main_pubkey.to_address();
+        let unique_address = unique_pubkey.to_address();
+        assert!(main_address.starts_with("safe1"));
+        assert!(unique_address.starts_with("safeid1"));
+
+        assert_eq!(MainPubkey::from_address(&main_address)?, main_pubkey);
+        assert_eq!(UniquePubkey::from_address(&unique_address)?, unique_pubkey);
+        assert_eq!(
+            MainPubkey::from_address(&main_address.to_uppercase())?,
+            main_pubkey
+        );
+        assert!(MainPubkey::is_valid_address(&main_address));
+
+        // both formats are accepted when parsing
+        assert_eq!(main_address.parse::<MainPubkey>()?, main_pubkey);
+        assert_eq!(main_pubkey.to_hex().parse::<MainPubkey>()?, main_pubkey);
+        assert_eq!(
+            unique_pubkey.to_hex().parse::<UniquePubkey>()?,
+            unique_pubkey
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_errors_are_detected() {
+        let main_pubkey = MainSecretKey::random().main_pubkey();
+        let address = main_pubkey.to_address();
+
+        // a typo
+        let mut typo = address.clone().into_bytes();
+        let last = typo.len() - 1;
+        typo[last] = if typo[last] == b'q' { b'p' } else { b'q' };
+        let typo = String::from_utf8(typo).expect("address to be ascii");
+        assert!(matches!(
+            MainPubkey::from_address(&typo),
+            Err(Error::InvalidAddress(_))
+        ));
+
+        // truncation
+        assert!(matches!(
+            MainPubkey::from_address(&address[..address.len() - 4]),
+            Err(Error::InvalidAddress(_))
+        ));
+
+        // the address of another key type
+        let unique_address = main_pubkey
+            .new_unique_pubkey(&DerivationIndex([0u8; 32]))
+            .to_address();
+        assert!(matches!(
+            MainPubkey::from_address(&unique_address),
+            Err(Error::AddressHrpMismatch { .. })
+        ));
+        assert!(!MainPubkey::is_valid_address(&unique_address));
+    }
 }
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{Hash, NanoTokens, UniquePubkey};
+use crate::{Hash, InputsVerificationReport, NanoTokens, UniquePubkey};
 use thiserror::Error;
 
 /// Specialisation of `std::Result`.
@@ -35,6 +35,8 @@ pub enum TransferError {
     DoubleSpentParent,
     #[error("Invalid Spend Signature for {0:?}")]
     InvalidSpendSignature(UniquePubkey),
+    #[error("Refund Spend for {0:?} was made before the deadline of its reclaim clause")]
+    ReclaimBeforeDeadline(UniquePubkey),
     #[error("Transaction hash is different from the hash in the the Spend: {0:?} != {1:?}")]
     TransactionHashMismatch(Hash, Hash),
     #[error("CashNote ciphers are not present in transaction outputs.")]
@@ -63,10 +65,12 @@ pub enum TransferError {
     UnbalancedTransaction,
     #[error("The CashNote tx must have at least one input.")]
     MissingTxInputs,
-    #[error("The spends don't match the inputs of the Transaction.")]
-    SpendsDoNotMatchInputs,
+    #[error("Transaction inputs failed verification: {0}")]
+    InvalidInputs(InputsVerificationReport),
     #[error("Overflow occurred while adding values")]
     NumericOverflow,
+    #[error("A transfer needs at least one recipient")]
+    NoRecipients,
     #[error("Not enough balance, {0} available, {1} required")]
     NotEnoughBalance(NanoTokens, NanoTokens),
     #[error("CashNoteHasNoParentSpends: {0}")]
@@ -85,6 +89,12 @@ pub enum TransferError {
     TransferSerializationFailed,
     #[error("Transfer deserialisation failed")]
     TransferDeserializationFailed,
+    #[error("Serialised data has version {found}, only versions up to {supported} are supported")]
+    UnsupportedSerialisationVersion { found: u8, supported: u8 },
+    #[error("Failed to deserialise versioned data: {0}")]
+    VersionedDeserialisationFailed(String),
+    #[error("Invalid network royalties split: {0}")]
+    InvalidRoyaltiesSplit(String),
 
     #[error("Bls error: {0}")]
     Blsttc(#[from] bls::error::Error),
@@ -6,17 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::wallet::HotWallet;
-
+#[cfg(feature = "wallet")]
+use crate::wallet::{HotWallet, Result as WalletResult};
 use crate::{
-    wallet::Result as WalletResult, CashNote, DerivationIndex, Input, MainPubkey, MainSecretKey,
-    NanoTokens, Output, SignedSpend, SpendReason, Transaction, TransactionBuilder,
-    TransferError as CashNoteError, UniquePubkey,
+    CashNote, DerivationIndex, Input, MainPubkey, MainSecretKey, NanoTokens, Output, SignedSpend,
+    SpendReason, Transaction, TransactionBuilder, TransferError as CashNoteError, UniquePubkey,
 };
 
 use bls::SecretKey;
 use lazy_static::lazy_static;
-use std::{fmt::Debug, path::PathBuf};
+use std::fmt::Debug;
+#[cfg(feature = "wallet")]
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// Number of tokens in the Genesis CashNote.
@@ -102,6 +103,7 @@ lazy_static! {
         tx.outputs = vec![Output {
             unique_pubkey: *GENESIS_SPEND_UNIQUE_KEY,
             amount: NanoTokens::from(GENESIS_CASHNOTE_AMOUNT),
+            reclaim: None,
         }];
         tx
     };
@@ -159,6 +161,7 @@ pub fn is_genesis_spend(spend: &SignedSpend) -> bool {
         && spend.spend.amount == NanoTokens::from(GENESIS_CASHNOTE_AMOUNT)
 }
 
+#[cfg(feature = "wallet")]
 pub fn load_genesis_wallet() -> Result<HotWallet, Error> {
     info!("Loading genesis...");
     if let Ok(wallet) = get_existing_genesis_wallet() {
@@ -182,6 +185,7 @@ pub fn load_genesis_wallet() -> Result<HotWallet, Error> {
     Ok(genesis_wallet)
 }
 
+#[cfg(feature = "wallet")]
 fn create_genesis_wallet() -> HotWallet {
     let root_dir = get_genesis_dir();
     let wallet_dir = root_dir.join("wallet");
@@ -194,6 +198,7 @@ fn create_genesis_wallet() -> HotWallet {
         .expect("Faucet wallet (after genesis) shall be created successfully.")
 }
 
+#[cfg(feature = "wallet")]
 fn get_existing_genesis_wallet() -> WalletResult<HotWallet> {
     let root_dir = get_genesis_dir();
 
@@ -255,6 +260,7 @@ pub fn create_first_cash_note_from_key(
 
 // We need deterministic and fix path for the faucet wallet.
 // Otherwise the test instances will not be able to find the same faucet instance.
+#[cfg(feature = "wallet")]
 pub fn get_faucet_data_dir() -> PathBuf {
     let mut data_dirs = dirs_next::data_dir().expect("A homedir to exist.");
     data_dirs.push("safe");
@@ -266,6 +272,7 @@ pub fn get_faucet_data_dir() -> PathBuf {
 
 // We need deterministic and fix path for the genesis wallet.
 // Otherwise the test instances will not be able to find the same genesis instance.
+#[cfg(feature = "wallet")]
 fn get_genesis_dir() -> PathBuf {
     let mut data_dirs = dirs_next::data_dir().expect("A homedir to exist.");
     data_dirs.push("safe");
@@ -6,35 +6,61 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+//! The transfer logic of the Safe Network.
+//!
+//! Key derivation, `CashNote` construction and transfer verification make up the core of the
+//! crate, which has no filesystem or async runtime dependencies and builds for
+//! `wasm32-unknown-unknown`, so browser wallets can create and verify transfers client-side.
+//! The in-memory `MemWallet` is part of that core, while the on-disk wallets and the
+//! genesis/faucet wallet helpers are behind the default `wallet` feature:
+//!
+//! ```text
+//! cargo build -p sn_transfers --no-default-features --target wasm32-unknown-unknown
+//! ```
+
 #[macro_use]
 extern crate tracing;
 
 mod cashnotes;
 mod error;
 mod genesis;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 mod transfers;
+mod versioned;
 mod wallet;
 
 pub(crate) use cashnotes::{Input, Output, TransactionBuilder};
 
 /// Types used in the public API
 pub use cashnotes::{
-    CashNote, DerivationIndex, DerivedSecretKey, Hash, MainPubkey, MainSecretKey, NanoTokens,
-    SignedSpend, Spend, SpendAddress, SpendReason, Transaction, UniquePubkey, UnsignedTransfer,
+    CashNote, DerivationIndex, DerivedSecretKey, Hash, InputFailure, InputsVerificationReport,
+    MainPubkey, MainSecretKey, NanoTokens, ReclaimClause, SignedSpend, Spend, SpendAddress,
+    SpendReason, SpendSubmissionToken, Transaction, UniquePubkey, UnsignedTransfer,
+    MAIN_PUBKEY_HRP, UNIQUE_PUBKEY_HRP,
 };
 pub use error::{Result, TransferError};
 /// Utilities exposed
 pub use genesis::{
-    calculate_royalties_fee, create_first_cash_note_from_key, get_faucet_data_dir, get_genesis_sk,
-    is_genesis_parent_tx, is_genesis_spend, load_genesis_wallet, Error as GenesisError,
-    GENESIS_CASHNOTE, GENESIS_CASHNOTE_PARENT_TX, GENESIS_PK, GENESIS_SPEND_UNIQUE_KEY,
-    TOTAL_SUPPLY,
+    calculate_royalties_fee, create_first_cash_note_from_key, get_genesis_sk, is_genesis_parent_tx,
+    is_genesis_spend, Error as GenesisError, GENESIS_CASHNOTE, GENESIS_CASHNOTE_PARENT_TX,
+    GENESIS_PK, GENESIS_SPEND_UNIQUE_KEY, TOTAL_SUPPLY,
+};
+#[cfg(feature = "wallet")]
+pub use genesis::{get_faucet_data_dir, load_genesis_wallet};
+pub use transfers::{
+    create_chained_transfers, create_unsigned_transfer, CashNoteRedemption, CashNotesAndSecretKey,
+    OfflineTransfer, RoyaltiesSplit, Transfer, MAX_OUTPUTS_PER_TX,
+};
+#[cfg(feature = "wallet")]
+pub use wallet::{
+    bls_secret_from_hex, wallet_lockfile_name, AbsorbReport, AddressBook, HotWallet,
+    ReclaimableTransfer, SweepReport, WalletApi, WatchOnlyWallet, MAX_INPUTS_PER_SWEEP_TX,
+    WALLET_DIR_NAME,
 };
-pub use transfers::{CashNoteRedemption, OfflineTransfer, Transfer};
 pub use wallet::{
-    bls_secret_from_hex, wallet_lockfile_name, Error as WalletError, HotWallet, Payment,
-    PaymentQuote, QuotingMetrics, Result as WalletResult, WalletApi, WatchOnlyWallet,
-    QUOTE_EXPIRATION_SECS, WALLET_DIR_NAME,
+    Error as WalletError, MemWallet, Payment, PaymentDetails, PaymentQuote, QuotingMetrics,
+    Result as WalletResult, WalletEvent, WalletEventsBroadcaster, QUOTE_EXPIRATION_SECS,
 };
 
 use bls::SecretKey;
@@ -108,6 +134,24 @@ lazy_static! {
             Err(err) => panic!("Failed to parse default network royalties PK: {err:?}"),
         }
     };
+    /// How network royalties are shared, as `<key>:<weight>` pairs separated by commas.
+    /// Defaults to paying everything to `NETWORK_ROYALTIES_PK`.
+    pub static ref NETWORK_ROYALTIES_SPLIT: RoyaltiesSplit = {
+        let split = std::env::var("NETWORK_ROYALTIES_SPLIT")
+            .ok()
+            .or_else(|| option_env!("NETWORK_ROYALTIES_SPLIT").map(str::to_string));
+
+        match split {
+            Some(split) => {
+                warn!("Using NETWORK_ROYALTIES_SPLIT: {split}");
+                match split.parse() {
+                    Ok(split) => split,
+                    Err(err) => panic!("Failed to parse network royalties split: {err:?}"),
+                }
+            }
+            None => RoyaltiesSplit::single(*NETWORK_ROYALTIES_PK),
+        }
+    };
 }
 
 lazy_static! {
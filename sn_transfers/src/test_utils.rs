@@ -0,0 +1,338 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Deterministic fixtures and proptest strategies for the transfer types, so downstream
+//! crates can test their handling code without building a genesis CashNote by hand.
+
+mod reissue_bench;
+
+pub use reissue_bench::{
+    run_reissue_bench, ReissueBenchConfig, ReissueBenchResult, VerificationMode,
+};
+
+use crate::{
+    create_first_cash_note_from_key, rng, CashNote, DerivationIndex, DerivedSecretKey, Input,
+    MainPubkey, MainSecretKey, NanoTokens, OfflineTransfer, Result, SignedSpend, SpendReason,
+    Transaction, TransactionBuilder, Transfer, TransferError,
+};
+use proptest::{
+    arbitrary::Arbitrary,
+    collection::vec,
+    prelude::{any, BoxedStrategy, Strategy},
+};
+use rand::rngs::StdRng;
+
+/// The maximum amount of a single output generated by the strategies in this module.
+const MAX_ARBITRARY_OUTPUT_AMOUNT: u64 = 1_000_000_000;
+/// The maximum number of outputs generated by the strategies in this module.
+const MAX_ARBITRARY_OUTPUTS: usize = 5;
+
+/// A valid transfer from a genesis CashNote, with all the keys involved.
+#[derive(custom_debug::Debug)]
+pub struct TransferFixture {
+    /// The key owning the genesis CashNote, which also receives the change.
+    #[debug(skip)]
+    pub sender_key: MainSecretKey,
+    /// The key owning all the created recipient CashNotes.
+    #[debug(skip)]
+    pub recipient_key: MainSecretKey,
+    /// The CashNote spent by the transfer.
+    pub genesis: CashNote,
+    /// The transfer itself.
+    pub transfer: OfflineTransfer,
+}
+
+impl TransferFixture {
+    /// The CashNotes created for the recipient.
+    pub fn recipient_cash_notes(&self) -> &[CashNote] {
+        &self.transfer.cash_notes_for_recipient
+    }
+
+    /// The signed spends of the transfer's inputs.
+    pub fn signed_spends(&self) -> &[SignedSpend] {
+        &self.transfer.all_spend_requests
+    }
+
+    /// The transaction of the transfer.
+    pub fn tx(&self) -> &Transaction {
+        &self.transfer.tx
+    }
+
+    /// An encrypted Transfer to the recipient for each recipient CashNote.
+    pub fn transfers(&self) -> Result<Vec<Transfer>> {
+        self.recipient_cash_notes()
+            .iter()
+            .map(Transfer::transfer_from_cash_note)
+            .collect()
+    }
+}
+
+/// Builds a `TransferFixture`. All keys and derivation indexes are derived from
+/// the seed, so the same seed and amounts always give the same fixture.
+pub struct TransferFixtureBuilder {
+    seed: [u8; 32],
+    output_amounts: Vec<u64>,
+    reason: SpendReason,
+}
+
+impl TransferFixtureBuilder {
+    /// Creates a builder for a transfer with a single output of 100 nanos.
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self {
+            seed,
+            output_amounts: vec![100],
+            reason: SpendReason::default(),
+        }
+    }
+
+    /// Sets the amounts of the outputs sent to the recipient.
+    pub fn with_output_amounts(mut self, amounts: impl IntoIterator<Item = u64>) -> Self {
+        self.output_amounts = amounts.into_iter().collect();
+        self
+    }
+
+    /// Sets the reason of the spends.
+    pub fn with_reason(mut self, reason: SpendReason) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    /// Builds the fixture, failing if the amounts exceed the genesis amount.
+    pub fn build(self) -> Result<TransferFixture> {
+        let mut rng = rng::from_seed(self.seed);
+        let sender_key = MainSecretKey::random_from_rng(&mut rng);
+        let recipient_key = MainSecretKey::random_from_rng(&mut rng);
+        let genesis = create_first_cash_note_from_key(&sender_key)
+            .map_err(|err| TransferError::CashNoteReissueFailed(err.to_string()))?;
+
+        let transfer = reissue(
+            &genesis,
+            &sender_key,
+            &recipient_key,
+            &self.output_amounts,
+            self.reason,
+            &mut rng,
+        )?;
+
+        Ok(TransferFixture {
+            sender_key,
+            recipient_key,
+            genesis,
+            transfer,
+        })
+    }
+}
+
+// Spends the given CashNote to the recipient, with one output per amount,
+// sending the change back to the owner of the CashNote.
+fn reissue(
+    cash_note: &CashNote,
+    owner_key: &MainSecretKey,
+    recipient_key: &MainSecretKey,
+    amounts: &[u64],
+    reason: SpendReason,
+    rng: &mut StdRng,
+) -> Result<OfflineTransfer> {
+    let recipients = amounts
+        .iter()
+        .map(|amount| (NanoTokens::from(*amount), recipient_key.main_pubkey()))
+        .collect();
+    let input = (cash_note.clone(), cash_note.derived_key(owner_key)?);
+    reissue_many(
+        vec![input],
+        recipients,
+        owner_key.main_pubkey(),
+        reason,
+        rng,
+    )
+}
+
+// Spends all the given CashNotes to the recipients, sending any change to `change_to`.
+// Unlike `OfflineTransfer::new`, all derivation indexes are taken from the given rng.
+fn reissue_many(
+    inputs: Vec<(CashNote, DerivedSecretKey)>,
+    recipients: Vec<(NanoTokens, MainPubkey)>,
+    change_to: MainPubkey,
+    reason: SpendReason,
+    rng: &mut StdRng,
+) -> Result<OfflineTransfer> {
+    let mut input_amount = NanoTokens::zero();
+    for (cash_note, _) in &inputs {
+        input_amount = input_amount
+            .checked_add(cash_note.value()?)
+            .ok_or(TransferError::ExcessiveNanoValue)?;
+    }
+    let total_output = recipients
+        .iter()
+        .try_fold(NanoTokens::zero(), |total, (amount, _)| {
+            total.checked_add(*amount)
+        })
+        .ok_or(TransferError::ExcessiveNanoValue)?;
+    let change = input_amount
+        .checked_sub(total_output)
+        .ok_or(TransferError::NotEnoughBalance(input_amount, total_output))?;
+
+    let mut tx_builder = TransactionBuilder::default();
+    for (cash_note, derived_key) in inputs {
+        let input = Input {
+            unique_pubkey: cash_note.unique_pubkey(),
+            amount: cash_note.value()?,
+        };
+        tx_builder = tx_builder.add_input(
+            input,
+            Some(derived_key),
+            cash_note.parent_tx.clone(),
+            cash_note.derivation_index(),
+        );
+    }
+    for (amount, main_pubkey) in recipients {
+        tx_builder = tx_builder.add_output(amount, main_pubkey, DerivationIndex::random(rng));
+    }
+    let change_index = DerivationIndex::random(rng);
+    let change_id = change_to.new_unique_pubkey(&change_index);
+    if !change.is_zero() {
+        tx_builder = tx_builder.add_output(change, change_to, change_index);
+    }
+
+    let cash_note_builder = tx_builder.build(reason, vec![]);
+    let tx = cash_note_builder.spent_tx.clone();
+    let all_spend_requests = cash_note_builder
+        .signed_spends()
+        .into_iter()
+        .cloned()
+        .collect();
+
+    let mut change_cash_note = None;
+    let mut cash_notes_for_recipient = vec![];
+    for (created, _amount) in cash_note_builder.build()? {
+        if created.unique_pubkey() == change_id {
+            change_cash_note = Some(created);
+        } else {
+            cash_notes_for_recipient.push(created);
+        }
+    }
+
+    Ok(OfflineTransfer {
+        tx,
+        cash_notes_for_recipient,
+        change_cash_note,
+        all_spend_requests,
+    })
+}
+
+/// A strategy generating valid `TransferFixture`s with up to `MAX_ARBITRARY_OUTPUTS` outputs.
+pub fn arb_transfer_fixture() -> impl Strategy<Value = TransferFixture> {
+    (
+        any::<[u8; 32]>(),
+        vec(1..=MAX_ARBITRARY_OUTPUT_AMOUNT, 1..=MAX_ARBITRARY_OUTPUTS),
+    )
+        .prop_map(|(seed, amounts)| {
+            TransferFixtureBuilder::new(seed)
+                .with_output_amounts(amounts)
+                .build()
+                .expect("Arbitrary amounts are always covered by the genesis amount")
+        })
+}
+
+impl Arbitrary for CashNote {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        arb_transfer_fixture()
+            .prop_flat_map(|fixture| {
+                proptest::sample::select(fixture.transfer.cash_notes_for_recipient)
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for SignedSpend {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        arb_transfer_fixture()
+            .prop_flat_map(|fixture| proptest::sample::select(fixture.transfer.all_spend_requests))
+            .boxed()
+    }
+}
+
+impl Arbitrary for Transaction {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        arb_transfer_fixture()
+            .prop_map(|fixture| fixture.transfer.tx)
+            .boxed()
+    }
+}
+
+impl Arbitrary for Transfer {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        any::<CashNote>()
+            .prop_map(|cash_note| {
+                Transfer::transfer_from_cash_note(&cash_note)
+                    .expect("Arbitrary CashNotes can always be encrypted")
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn fixtures_are_deterministic() -> Result<()> {
+        let fixture_a = TransferFixtureBuilder::new([7u8; 32])
+            .with_output_amounts([1, 2, 3])
+            .build()?;
+        let fixture_b = TransferFixtureBuilder::new([7u8; 32])
+            .with_output_amounts([1, 2, 3])
+            .build()?;
+
+        assert_eq!(fixture_a.tx(), fixture_b.tx());
+        assert_eq!(fixture_a.recipient_cash_notes().len(), 3);
+        assert_eq!(
+            fixture_a.recipient_cash_notes(),
+            fixture_b.recipient_cash_notes()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fixture_fails_when_amounts_exceed_genesis() {
+        let result = TransferFixtureBuilder::new([0u8; 32])
+            .with_output_amounts([u64::MAX / 2, u64::MAX / 2])
+            .build();
+        assert!(matches!(result, Err(TransferError::NotEnoughBalance(_, _))));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn arbitrary_fixtures_verify(fixture in arb_transfer_fixture()) {
+            prop_assert!(fixture.tx().verify_against_inputs_spent(fixture.signed_spends()).is_ok());
+            for cash_note in fixture.recipient_cash_notes() {
+                prop_assert!(cash_note.verify(&fixture.recipient_key).is_ok());
+            }
+        }
+
+        #[test]
+        fn arbitrary_transfers_are_encrypted(transfer in any::<Transfer>()) {
+            prop_assert!(matches!(transfer, Transfer::Encrypted(_)));
+        }
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A parameterized reissue benchmark, timing the verification of a transaction
+//! with a configurable number of inputs, outputs and owning keys.
+
+use super::reissue_many;
+use crate::{
+    create_first_cash_note_from_key, rng, MainSecretKey, NanoTokens, Result, SignedSpend,
+    SpendReason, Transaction, TransferError,
+};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// The value of each CashNote used as an input of the benchmarked transaction.
+const INPUT_AMOUNT: u64 = 1_000_000;
+
+/// How the signed spends of the benchmarked transaction are verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationMode {
+    /// Each signed spend is verified one after the other.
+    Serial,
+    /// The signed spends are verified concurrently on the rayon thread pool.
+    Parallel,
+    /// The whole transaction is verified at once against all its signed spends,
+    /// which also checks the balance and uniqueness of inputs and outputs.
+    Aggregated,
+}
+
+impl FromStr for VerificationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "serial" => Ok(Self::Serial),
+            "parallel" => Ok(Self::Parallel),
+            "aggregated" => Ok(Self::Aggregated),
+            other => Err(format!(
+                "Unknown verification mode {other:?}, expected serial, parallel or aggregated"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for VerificationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serial => write!(f, "serial"),
+            Self::Parallel => write!(f, "parallel"),
+            Self::Aggregated => write!(f, "aggregated"),
+        }
+    }
+}
+
+/// The parameters of a reissue benchmark run.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReissueBenchConfig {
+    /// Number of CashNotes spent by the benchmarked transaction.
+    pub inputs: usize,
+    /// Number of outputs created by the benchmarked transaction.
+    pub outputs: usize,
+    /// Number of distinct keys owning the inputs.
+    pub input_keys: usize,
+    /// Number of distinct keys receiving the outputs.
+    pub output_keys: usize,
+    /// How the signed spends are verified.
+    pub mode: VerificationMode,
+    /// Number of timed verifications.
+    pub iterations: usize,
+    /// Seed for all keys and derivation indexes, so runs are comparable.
+    pub seed: [u8; 32],
+}
+
+impl Default for ReissueBenchConfig {
+    fn default() -> Self {
+        Self {
+            inputs: 1,
+            outputs: 100,
+            input_keys: 1,
+            output_keys: 100,
+            mode: VerificationMode::Aggregated,
+            iterations: 100,
+            seed: [0u8; 32],
+        }
+    }
+}
+
+/// The timings of a reissue benchmark run, in nanoseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReissueBenchResult {
+    /// The parameters of the run.
+    pub config: ReissueBenchConfig,
+    /// Time taken to build the inputs and the benchmarked transaction.
+    pub setup_ns: u128,
+    pub min_ns: u128,
+    pub max_ns: u128,
+    pub mean_ns: u128,
+    pub median_ns: u128,
+    /// Mean time per signed spend.
+    pub mean_per_input_ns: u128,
+}
+
+/// Builds a transaction as described by the config, and times its verification.
+pub fn run_reissue_bench(config: ReissueBenchConfig) -> Result<ReissueBenchResult> {
+    if config.inputs == 0
+        || config.outputs == 0
+        || config.input_keys == 0
+        || config.output_keys == 0
+        || config.iterations == 0
+    {
+        return Err(TransferError::CashNoteReissueFailed(
+            "All reissue bench parameters must be non zero".to_string(),
+        ));
+    }
+
+    let setup_start = Instant::now();
+    let (tx, signed_spends) = build_bench_tx(&config)?;
+    let setup_ns = setup_start.elapsed().as_nanos();
+
+    let mut timings = Vec::with_capacity(config.iterations);
+    for _ in 0..config.iterations {
+        let start = Instant::now();
+        verify(&tx, &signed_spends, config.mode)?;
+        timings.push(start.elapsed());
+    }
+    timings.sort();
+
+    let total: Duration = timings.iter().sum();
+    let mean_ns = total.as_nanos() / timings.len() as u128;
+    Ok(ReissueBenchResult {
+        setup_ns,
+        min_ns: timings[0].as_nanos(),
+        max_ns: timings[timings.len() - 1].as_nanos(),
+        mean_ns,
+        median_ns: timings[timings.len() / 2].as_nanos(),
+        mean_per_input_ns: mean_ns / signed_spends.len() as u128,
+        config,
+    })
+}
+
+// Spends genesis to `inputs` CashNotes owned by `input_keys` keys, then returns
+// the tx spending all those to `outputs` outputs owned by `output_keys` keys.
+fn build_bench_tx(config: &ReissueBenchConfig) -> Result<(Transaction, Vec<SignedSpend>)> {
+    let mut rng = rng::from_seed(config.seed);
+    let genesis_key = MainSecretKey::random_from_rng(&mut rng);
+    let input_keys: Vec<_> = (0..config.input_keys)
+        .map(|_| MainSecretKey::random_from_rng(&mut rng))
+        .collect();
+    let output_keys: Vec<_> = (0..config.output_keys)
+        .map(|_| MainSecretKey::random_from_rng(&mut rng).main_pubkey())
+        .collect();
+
+    let genesis = create_first_cash_note_from_key(&genesis_key)
+        .map_err(|err| TransferError::CashNoteReissueFailed(err.to_string()))?;
+    let funding_recipients = (0..config.inputs)
+        .map(|i| {
+            let key = &input_keys[i % input_keys.len()];
+            (NanoTokens::from(INPUT_AMOUNT), key.main_pubkey())
+        })
+        .collect();
+    let funding = reissue_many(
+        vec![(genesis.clone(), genesis.derived_key(&genesis_key)?)],
+        funding_recipients,
+        genesis_key.main_pubkey(),
+        SpendReason::default(),
+        &mut rng,
+    )?;
+
+    let mut inputs = vec![];
+    for cash_note in funding.cash_notes_for_recipient {
+        let owner = input_keys
+            .iter()
+            .find(|key| key.main_pubkey() == cash_note.main_pubkey)
+            .ok_or(TransferError::MainSecretKeyDoesNotMatchMainPubkey)?;
+        let derived_key = cash_note.derived_key(owner)?;
+        inputs.push((cash_note, derived_key));
+    }
+
+    // spread the whole input amount over the outputs, the last taking the remainder
+    let total = INPUT_AMOUNT * config.inputs as u64;
+    let per_output = total / config.outputs as u64;
+    let recipients = (0..config.outputs)
+        .map(|i| {
+            let amount = if i == config.outputs - 1 {
+                total - per_output * (config.outputs as u64 - 1)
+            } else {
+                per_output
+            };
+            (NanoTokens::from(amount), output_keys[i % output_keys.len()])
+        })
+        .collect();
+    let transfer = reissue_many(
+        inputs,
+        recipients,
+        genesis_key.main_pubkey(),
+        SpendReason::default(),
+        &mut rng,
+    )?;
+
+    Ok((transfer.tx, transfer.all_spend_requests))
+}
+
+fn verify(tx: &Transaction, signed_spends: &[SignedSpend], mode: VerificationMode) -> Result<()> {
+    let tx_hash = tx.hash();
+    match mode {
+        VerificationMode::Serial => signed_spends
+            .iter()
+            .try_for_each(|signed_spend| signed_spend.verify(tx_hash)),
+        VerificationMode::Parallel => signed_spends
+            .par_iter()
+            .try_for_each(|signed_spend| signed_spend.verify(tx_hash)),
+        VerificationMode::Aggregated => tx.verify_against_inputs_spent(signed_spends),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bench_runs_in_every_mode() -> Result<()> {
+        for mode in [
+            VerificationMode::Serial,
+            VerificationMode::Parallel,
+            VerificationMode::Aggregated,
+        ] {
+            let config = ReissueBenchConfig {
+                inputs: 3,
+                outputs: 5,
+                input_keys: 2,
+                output_keys: 2,
+                mode,
+                iterations: 2,
+                ..Default::default()
+            };
+            let result = run_reissue_bench(config)?;
+            assert!(result.min_ns <= result.median_ns);
+            assert!(result.median_ns <= result.max_ns);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bench_rejects_empty_configs() {
+        let config = ReissueBenchConfig {
+            inputs: 0,
+            ..Default::default()
+        };
+        assert!(run_reissue_bench(config).is_err());
+    }
+}
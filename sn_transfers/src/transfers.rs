@@ -28,7 +28,12 @@
 //! A cash_note transaction is the lower layer concept where the blinded inputs and outputs are specified.
 
 mod offline_transfer;
+mod royalties;
 mod transfer;
 
-pub use offline_transfer::{create_unsigned_transfer, CashNotesAndSecretKey, OfflineTransfer};
+pub use offline_transfer::{
+    create_chained_transfers, create_unsigned_transfer, CashNotesAndSecretKey, OfflineTransfer,
+    MAX_OUTPUTS_PER_TX,
+};
+pub use royalties::RoyaltiesSplit;
 pub use transfer::{CashNoteRedemption, Transfer};
@@ -7,15 +7,19 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    cashnotes::{CashNoteBuilder, UnsignedTransfer},
-    rng, CashNote, DerivationIndex, DerivedSecretKey, Input, MainPubkey, NanoTokens, Result,
-    SignedSpend, SpendReason, Transaction, TransactionBuilder, TransferError, UniquePubkey,
-    NETWORK_ROYALTIES_PK,
+    cashnotes::{CashNoteBuilder, ReclaimClause, UnsignedTransfer},
+    rng, CashNote, DerivationIndex, DerivedSecretKey, Input, MainPubkey, MainSecretKey, NanoTokens,
+    Result, SignedSpend, SpendReason, Transaction, TransactionBuilder, TransferError, UniquePubkey,
+    NETWORK_ROYALTIES_SPLIT,
 };
 
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
+/// The maximum number of outputs (including the change output) a single transaction
+/// created by `create_chained_transfers` will hold.
+pub const MAX_OUTPUTS_PER_TX: usize = 256;
+
 /// List of CashNotes, with (optionally when needed) their corresponding derived owning secret key.
 pub type CashNotesAndSecretKey = Vec<(CashNote, Option<DerivedSecretKey>)>;
 
@@ -91,6 +95,40 @@ impl OfflineTransfer {
         recipients: Vec<(NanoTokens, MainPubkey, DerivationIndex)>,
         change_to: MainPubkey,
         input_reason_hash: SpendReason,
+    ) -> Result<Self> {
+        Self::new_with_reclaim(
+            available_cash_notes,
+            recipients,
+            change_to,
+            input_reason_hash,
+            None,
+        )
+    }
+
+    /// Like `new`, but the cash_notes created for the recipients can be reclaimed by the sender
+    /// as per the given clause, if they are not spent by its deadline. The change is not reclaimable.
+    pub fn new_reclaimable(
+        available_cash_notes: CashNotesAndSecretKey,
+        recipients: Vec<(NanoTokens, MainPubkey, DerivationIndex)>,
+        change_to: MainPubkey,
+        input_reason_hash: SpendReason,
+        reclaim: ReclaimClause,
+    ) -> Result<Self> {
+        Self::new_with_reclaim(
+            available_cash_notes,
+            recipients,
+            change_to,
+            input_reason_hash,
+            Some(reclaim),
+        )
+    }
+
+    fn new_with_reclaim(
+        available_cash_notes: CashNotesAndSecretKey,
+        recipients: Vec<(NanoTokens, MainPubkey, DerivationIndex)>,
+        change_to: MainPubkey,
+        input_reason_hash: SpendReason,
+        reclaim: Option<ReclaimClause>,
     ) -> Result<Self> {
         let total_output_amount = recipients
             .iter()
@@ -111,6 +149,7 @@ impl OfflineTransfer {
             cash_notes_to_spend,
             recipients,
             change: (change_amount, change_to),
+            reclaim,
         };
 
         create_offline_transfer_with(selected_inputs, input_reason_hash)
@@ -128,6 +167,8 @@ struct TransferInputs {
     pub recipients: Vec<(NanoTokens, MainPubkey, DerivationIndex)>,
     /// Any surplus amount after spending the necessary input cash_notes.
     pub change: (NanoTokens, MainPubkey),
+    /// Lets the sender reclaim the cash_notes created for the recipients.
+    pub reclaim: Option<ReclaimClause>,
 }
 
 /// A function for creating an unsigned transfer of tokens.
@@ -152,13 +193,14 @@ pub fn create_unsigned_transfer(
         cash_notes_to_spend,
         recipients,
         change: (change_amount, change_to),
+        reclaim: None,
     };
 
     // gather the network_royalties derivation indexes
     let network_royalties: Vec<DerivationIndex> = selected_inputs
         .recipients
         .iter()
-        .filter(|(_, main_pubkey, _)| *main_pubkey == *NETWORK_ROYALTIES_PK)
+        .filter(|(_, main_pubkey, _)| NETWORK_ROYALTIES_SPLIT.is_beneficiary(main_pubkey))
         .map(|(_, _, derivation_index)| *derivation_index)
         .collect();
 
@@ -168,6 +210,112 @@ pub fn create_unsigned_transfer(
     tx_builder.build_unsigned_transfer(reason_hash, network_royalties, change_id)
 }
 
+/// A function for creating a sequence of linked offline transfers, for when the recipients
+/// can't safely be held by a single transaction.
+///
+/// The recipients are split into batches of at most `MAX_OUTPUTS_PER_TX - 1` outputs,
+/// leaving room for a change output. The first transfer spends the selected input cash_notes,
+/// and each subsequent transfer spends the (intermediate) change cash_note of the previous one.
+/// The last transfer's change cash_note holds the actual surplus.
+///
+/// The transfers are returned in order, and their spend requests must be sent to the network
+/// in that same order, as each one spends the change created by its predecessor.
+/// It is an error not to pass any recipient.
+pub fn create_chained_transfers(
+    available_cash_notes: CashNotesAndSecretKey,
+    recipients: Vec<(NanoTokens, MainPubkey, DerivationIndex)>,
+    change_key: &MainSecretKey,
+    reason: SpendReason,
+) -> Result<Vec<OfflineTransfer>> {
+    create_chained_transfers_with_limit(
+        available_cash_notes,
+        recipients,
+        change_key,
+        reason,
+        MAX_OUTPUTS_PER_TX,
+    )
+}
+
+fn create_chained_transfers_with_limit(
+    available_cash_notes: CashNotesAndSecretKey,
+    recipients: Vec<(NanoTokens, MainPubkey, DerivationIndex)>,
+    change_key: &MainSecretKey,
+    reason: SpendReason,
+    max_outputs: usize,
+) -> Result<Vec<OfflineTransfer>> {
+    if max_outputs < 2 {
+        return Err(TransferError::CashNoteReissueFailed(
+            "A chained transfer requires room for at least one recipient and a change output."
+                .to_string(),
+        ));
+    }
+    if recipients.is_empty() {
+        return Err(TransferError::NoRecipients);
+    }
+    let change_to = change_key.main_pubkey();
+
+    let total_output_amount = recipients
+        .iter()
+        .try_fold(NanoTokens::zero(), |total, (amount, _, _)| {
+            total.checked_add(*amount)
+        })
+        .ok_or(TransferError::ExcessiveNanoValue)?;
+
+    let (mut cash_notes_to_spend, final_change_amount) =
+        select_inputs(available_cash_notes, total_output_amount)?;
+
+    let batches: Vec<_> = recipients
+        .chunks(max_outputs - 1)
+        .map(|batch| batch.to_vec())
+        .collect();
+
+    // What is still owed to the recipients of the later batches, and to us as final change.
+    let mut remaining_amount = total_output_amount
+        .checked_add(final_change_amount)
+        .ok_or(TransferError::ExcessiveNanoValue)?;
+
+    let mut transfers = Vec::with_capacity(batches.len());
+    let num_batches = batches.len();
+    for (i, batch) in batches.into_iter().enumerate() {
+        let batch_amount = batch
+            .iter()
+            .try_fold(NanoTokens::zero(), |total, (amount, _, _)| {
+                total.checked_add(*amount)
+            })
+            .ok_or(TransferError::ExcessiveNanoValue)?;
+        remaining_amount = remaining_amount
+            .checked_sub(batch_amount)
+            .ok_or(TransferError::NumericOverflow)?;
+
+        let selected_inputs = TransferInputs {
+            cash_notes_to_spend,
+            recipients: batch,
+            change: (remaining_amount, change_to),
+            reclaim: None,
+        };
+        let transfer = create_offline_transfer_with(selected_inputs, reason.clone())?;
+
+        let is_last = i + 1 == num_batches;
+        cash_notes_to_spend = match &transfer.change_cash_note {
+            Some(change_cash_note) if !is_last => {
+                let derived_key = change_cash_note.derived_key(change_key)?;
+                vec![(change_cash_note.clone(), Some(derived_key))]
+            }
+            None if !is_last => {
+                return Err(TransferError::CashNoteReissueFailed(
+                    "No intermediate change was created to fund the next transfer in the chain."
+                        .to_string(),
+                ));
+            }
+            _ => vec![],
+        };
+
+        transfers.push(transfer);
+    }
+
+    Ok(transfers)
+}
+
 /// Select the necessary number of cash_notes from those that we were passed.
 fn select_inputs(
     available_cash_notes: CashNotesAndSecretKey,
@@ -238,6 +386,7 @@ fn create_transaction_builder_with(
 )> {
     let TransferInputs {
         change: (change, change_to),
+        reclaim,
         ..
     } = selected_inputs;
 
@@ -265,9 +414,16 @@ fn create_transaction_builder_with(
     }
 
     // Build the transaction and create change cash_note if needed
-    let mut tx_builder = TransactionBuilder::default()
-        .add_inputs(inputs)
-        .add_outputs(selected_inputs.recipients);
+    let mut tx_builder = TransactionBuilder::default().add_inputs(inputs);
+    tx_builder = match reclaim {
+        Some(reclaim) => selected_inputs.recipients.into_iter().fold(
+            tx_builder,
+            |tx_builder, (token, main_pubkey, derivation_index)| {
+                tx_builder.add_reclaimable_output(token, main_pubkey, derivation_index, reclaim)
+            },
+        ),
+        None => tx_builder.add_outputs(selected_inputs.recipients),
+    };
     let mut rng = rng::thread_rng();
     let derivation_index = DerivationIndex::random(&mut rng);
     let change_id = change_to.new_unique_pubkey(&derivation_index);
@@ -292,7 +448,7 @@ fn create_offline_transfer_with(
     let network_royalties: Vec<DerivationIndex> = selected_inputs
         .recipients
         .iter()
-        .filter(|(_, main_pubkey, _)| *main_pubkey == *NETWORK_ROYALTIES_PK)
+        .filter(|(_, main_pubkey, _)| NETWORK_ROYALTIES_SPLIT.is_beneficiary(main_pubkey))
         .map(|(_, _, derivation_index)| *derivation_index)
         .collect();
 
@@ -350,3 +506,116 @@ fn create_offline_transfer_with(
         all_spend_requests,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::{create_first_cash_note_from_key, GENESIS_CASHNOTE_AMOUNT};
+
+    #[test]
+    fn chained_transfers_split_recipients_and_link_change() -> Result<()> {
+        let mut rng = rng::from_seed([0u8; 32]);
+        let sender_key = MainSecretKey::random_from_rng(&mut rng);
+        let genesis =
+            create_first_cash_note_from_key(&sender_key).expect("Genesis creation to succeed.");
+        let derived_key = genesis.derived_key(&sender_key)?;
+
+        let recipients: Vec<_> = (0..7)
+            .map(|_| {
+                (
+                    NanoTokens::from(10),
+                    MainSecretKey::random_from_rng(&mut rng).main_pubkey(),
+                    DerivationIndex::random(&mut rng),
+                )
+            })
+            .collect();
+
+        let transfers = create_chained_transfers_with_limit(
+            vec![(genesis, Some(derived_key))],
+            recipients,
+            &sender_key,
+            SpendReason::default(),
+            3,
+        )?;
+
+        // 7 recipients, 2 per transfer
+        assert_eq!(transfers.len(), 4);
+        for transfer in &transfers {
+            assert!(transfer.tx.outputs.len() <= 3);
+        }
+
+        // each transfer spends the change of the previous one
+        for pair in transfers.windows(2) {
+            let change = pair[0]
+                .change_cash_note
+                .as_ref()
+                .expect("intermediate change to exist");
+            assert_eq!(pair[1].tx.inputs.len(), 1);
+            assert_eq!(pair[1].tx.inputs[0].unique_pubkey, change.unique_pubkey());
+        }
+
+        let paid: u64 = transfers
+            .iter()
+            .flat_map(|t| t.cash_notes_for_recipient.iter())
+            .map(|cn| cn.value().map(|v| v.as_nano()).unwrap_or_default())
+            .sum();
+        assert_eq!(paid, 70);
+
+        let final_change = transfers
+            .last()
+            .and_then(|t| t.change_cash_note.as_ref())
+            .expect("final change to exist");
+        assert_eq!(
+            final_change.value()?.as_nano(),
+            GENESIS_CASHNOTE_AMOUNT - 70
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn chained_transfers_fit_in_one_tx_when_below_limit() -> Result<()> {
+        let mut rng = rng::from_seed([1u8; 32]);
+        let sender_key = MainSecretKey::random_from_rng(&mut rng);
+        let genesis =
+            create_first_cash_note_from_key(&sender_key).expect("Genesis creation to succeed.");
+        let derived_key = genesis.derived_key(&sender_key)?;
+
+        let recipient = (
+            NanoTokens::from(100),
+            MainSecretKey::random_from_rng(&mut rng).main_pubkey(),
+            DerivationIndex::random(&mut rng),
+        );
+
+        let transfers = create_chained_transfers(
+            vec![(genesis, Some(derived_key))],
+            vec![recipient],
+            &sender_key,
+            SpendReason::default(),
+        )?;
+
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].cash_notes_for_recipient.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn chained_transfers_need_a_recipient() -> Result<()> {
+        let mut rng = rng::from_seed([2u8; 32]);
+        let sender_key = MainSecretKey::random_from_rng(&mut rng);
+        let genesis =
+            create_first_cash_note_from_key(&sender_key).expect("Genesis creation to succeed.");
+        let derived_key = genesis.derived_key(&sender_key)?;
+
+        let result = create_chained_transfers(
+            vec![(genesis, Some(derived_key))],
+            vec![],
+            &sender_key,
+            SpendReason::default(),
+        );
+        assert_eq!(result.err(), Some(TransferError::NoRecipients));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{MainPubkey, NanoTokens, Result, TransferError};
+use std::{fmt, str::FromStr};
+
+/// How the network royalties of a payment are shared between beneficiaries,
+/// each of them receiving a part proportional to its weight.
+///
+/// Payers and nodes must use the same split: payers create one royalties output per
+/// beneficiary, and nodes check every beneficiary was paid at least its share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoyaltiesSplit {
+    beneficiaries: Vec<(MainPubkey, u64)>,
+}
+
+impl RoyaltiesSplit {
+    /// Creates a split between the given beneficiaries and their weights.
+    /// Fails if there is no beneficiary, a weight is zero, or a beneficiary is given twice.
+    pub fn new(beneficiaries: Vec<(MainPubkey, u64)>) -> Result<Self> {
+        if beneficiaries.is_empty() {
+            return Err(TransferError::InvalidRoyaltiesSplit(
+                "no beneficiary given".to_string(),
+            ));
+        }
+        for (i, (main_pubkey, weight)) in beneficiaries.iter().enumerate() {
+            if *weight == 0 {
+                return Err(TransferError::InvalidRoyaltiesSplit(format!(
+                    "beneficiary {main_pubkey:?} has a zero weight"
+                )));
+            }
+            if beneficiaries[..i].iter().any(|(pk, _)| pk == main_pubkey) {
+                return Err(TransferError::InvalidRoyaltiesSplit(format!(
+                    "beneficiary {main_pubkey:?} is given more than once"
+                )));
+            }
+        }
+        Ok(Self { beneficiaries })
+    }
+
+    /// A split paying all the royalties to a single beneficiary.
+    pub fn single(main_pubkey: MainPubkey) -> Self {
+        Self {
+            beneficiaries: vec![(main_pubkey, 1)],
+        }
+    }
+
+    /// The beneficiaries and their weights.
+    pub fn beneficiaries(&self) -> &[(MainPubkey, u64)] {
+        &self.beneficiaries
+    }
+
+    /// Returns true if the key is one of the beneficiaries.
+    pub fn is_beneficiary(&self, main_pubkey: &MainPubkey) -> bool {
+        self.beneficiaries.iter().any(|(pk, _)| pk == main_pubkey)
+    }
+
+    /// Splits the amount between the beneficiaries, in the order they were given.
+    /// Shares are rounded down and the remainder goes to the first beneficiary,
+    /// so the shares always add up to the amount. Empty shares are left out.
+    pub fn split(&self, amount: NanoTokens) -> Vec<(NanoTokens, MainPubkey)> {
+        let total_weight: u128 = self
+            .beneficiaries
+            .iter()
+            .map(|(_, weight)| *weight as u128)
+            .sum();
+        let mut shares: Vec<_> = self
+            .beneficiaries
+            .iter()
+            .map(|(main_pubkey, weight)| {
+                // the share is at most the amount, so it always fits in a u64
+                let share = amount.as_nano() as u128 * *weight as u128 / total_weight;
+                (share as u64, *main_pubkey)
+            })
+            .collect();
+        let distributed: u64 = shares.iter().map(|(share, _)| share).sum();
+        if let Some((first, _)) = shares.first_mut() {
+            *first += amount.as_nano() - distributed;
+        }
+        shares
+            .into_iter()
+            .filter(|(share, _)| *share > 0)
+            .map(|(share, main_pubkey)| (NanoTokens::from(share), main_pubkey))
+            .collect()
+    }
+}
+
+/// Parses a comma separated list of `<key>:<weight>`, where the key is a hex encoded
+/// public key or an address.
+impl FromStr for RoyaltiesSplit {
+    type Err = TransferError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let beneficiaries = s
+            .split(',')
+            .map(|beneficiary| {
+                let (key, weight) = beneficiary.trim().split_once(':').ok_or_else(|| {
+                    TransferError::InvalidRoyaltiesSplit(format!(
+                        "expected <key>:<weight>, got {beneficiary:?}"
+                    ))
+                })?;
+                let main_pubkey = MainPubkey::from_str(key)
+                    .map_err(|err| TransferError::InvalidRoyaltiesSplit(err.to_string()))?;
+                let weight = weight.parse().map_err(|err| {
+                    TransferError::InvalidRoyaltiesSplit(format!(
+                        "invalid weight {weight:?}: {err}"
+                    ))
+                })?;
+                Ok((main_pubkey, weight))
+            })
+            .collect::<Result<_>>()?;
+        Self::new(beneficiaries)
+    }
+}
+
+impl fmt::Display for RoyaltiesSplit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let beneficiaries: Vec<_> = self
+            .beneficiaries
+            .iter()
+            .map(|(main_pubkey, weight)| format!("{}:{weight}", main_pubkey.to_hex()))
+            .collect();
+        write!(f, "{}", beneficiaries.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MainSecretKey;
+
+    #[test]
+    fn shares_add_up_to_the_amount() -> Result<()> {
+        let (a, b, c) = (
+            MainSecretKey::random().main_pubkey(),
+            MainSecretKey::random().main_pubkey(),
+            MainSecretKey::random().main_pubkey(),
+        );
+        let split = RoyaltiesSplit::new(vec![(a, 2), (b, 1), (c, 1)])?;
+
+        assert_eq!(
+            split.split(NanoTokens::from(103)),
+            vec![
+                (NanoTokens::from(53), a),
+                (NanoTokens::from(25), b),
+                (NanoTokens::from(25), c)
+            ]
+        );
+        assert_eq!(
+            split.split(NanoTokens::from(1)),
+            vec![(NanoTokens::from(1), a)]
+        );
+        assert!(split.split(NanoTokens::zero()).is_empty());
+        assert_eq!(
+            RoyaltiesSplit::single(a).split(NanoTokens::from(u64::MAX)),
+            vec![(NanoTokens::from(u64::MAX), a)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn split_is_parsed_and_validated() -> Result<()> {
+        let (a, b) = (
+            MainSecretKey::random().main_pubkey(),
+            MainSecretKey::random().main_pubkey(),
+        );
+        let split = RoyaltiesSplit::new(vec![(a, 3), (b, 1)])?;
+        assert_eq!(RoyaltiesSplit::from_str(&split.to_string())?, split);
+        assert_eq!(
+            RoyaltiesSplit::from_str(&format!("{}:3, {}:1", a.to_address(), b.to_hex()))?,
+            split
+        );
+
+        for invalid in [
+            String::new(),
+            a.to_hex(),
+            format!("{}:0", a.to_hex()),
+            format!("{}:1,{}:2", a.to_hex(), a.to_hex()),
+        ] {
+            assert!(matches!(
+                RoyaltiesSplit::from_str(&invalid),
+                Err(TransferError::InvalidRoyaltiesSplit(_))
+            ));
+        }
+        Ok(())
+    }
+}
@@ -16,6 +16,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
 use crate::error::{Result, TransferError};
+use crate::versioned::{from_versioned_bytes, to_versioned_bytes, Versioned};
 
 /// Transfer sent to a recipient
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash)]
@@ -65,12 +66,15 @@ impl Transfer {
         Ok(t)
     }
 
-    /// This function is used to create a Network Royalties Transfer from a CashNote
-    /// can be done offline, and sent to the recipient.
+    /// This function is used to create a Network Royalties Transfer from the CashNotes
+    /// paying each royalties beneficiary, can be done offline, and sent to the recipient.
     /// Note that this type of transfer is not encrypted
-    pub(crate) fn royalties_transfer_from_cash_note(cash_note: &CashNote) -> Result<Self> {
-        let cnr = CashNoteRedemption::from_cash_note(cash_note)?;
-        Ok(Self::NetworkRoyalties(vec![cnr]))
+    pub(crate) fn royalties_transfer_from_cash_notes(cash_notes: &[CashNote]) -> Result<Self> {
+        let cnrs = cash_notes
+            .iter()
+            .map(CashNoteRedemption::from_cash_note)
+            .collect::<Result<_>>()?;
+        Ok(Self::NetworkRoyalties(cnrs))
     }
 
     /// Create a new transfer
@@ -105,24 +109,27 @@ impl Transfer {
     }
 
     /// Deserializes a `Transfer` represented as a hex string to a `Transfer`.
+    /// Hex strings written by older versions of this crate are upgraded.
     pub fn from_hex(hex: &str) -> Result<Self> {
         let mut bytes =
             hex::decode(hex).map_err(|_| TransferError::TransferDeserializationFailed)?;
         bytes.reverse();
-        let transfer: Self = rmp_serde::from_slice(&bytes)
-            .map_err(|_| TransferError::TransferDeserializationFailed)?;
-        Ok(transfer)
+        from_versioned_bytes(&bytes)
     }
 
     /// Serialize this `Transfer` instance to a readable hex string that a human can copy paste
     pub fn to_hex(&self) -> Result<String> {
         let mut serialized =
-            rmp_serde::to_vec(&self).map_err(|_| TransferError::TransferSerializationFailed)?;
+            to_versioned_bytes(self).map_err(|_| TransferError::TransferSerializationFailed)?;
         serialized.reverse();
         Ok(hex::encode(serialized))
     }
 }
 
+impl Versioned for Transfer {
+    const VERSION: u8 = 1;
+}
+
 /// Unspent Transaction (Tx) Output
 /// Information can be used by the Tx recipient of this output
 /// to check that they received money and to spend it
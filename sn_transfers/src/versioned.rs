@@ -0,0 +1,255 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Version tagged serialisation for the formats that outlive a release: hex encoded CashNotes
+//! and Transfers, and the wallet files.
+//!
+//! A versioned payload is a 4 byte header followed by the MessagePack encoding of the value.
+//! The header starts with 0xc1, a byte MessagePack never uses, so a payload written before
+//! versioning was introduced (version 0) can always be told apart from a tagged one.
+//!
+//! When the layout of one of these types changes, bump its `VERSION`, and decode the previous
+//! layouts in `upgrade`, converting them to the current type.
+
+use crate::{Result, TransferError};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A byte that is never used by MessagePack, marking the start of a versioned payload.
+const VERSION_MARKER: u8 = 0xc1;
+const VERSION_MAGIC: [u8; 2] = *b"SN";
+const HEADER_LEN: usize = 4;
+
+/// A type whose serialised form carries a version tag.
+pub(crate) trait Versioned: Serialize + DeserializeOwned {
+    /// The version written by `to_versioned_bytes`.
+    const VERSION: u8;
+
+    /// Decodes a payload written by an older version of this type.
+    /// Version 0 is the untagged format used before versioning.
+    ///
+    /// By default, version 0 is decoded as the current layout, which holds for the types whose
+    /// layout is unchanged since versioning was introduced, at version 1, as well as for the ones
+    /// introduced along with it.
+    fn upgrade(version: u8, payload: &[u8]) -> Result<Self> {
+        match version {
+            0 => deserialise(payload),
+            _ => Err(TransferError::UnsupportedSerialisationVersion {
+                found: version,
+                supported: Self::VERSION,
+            }),
+        }
+    }
+}
+
+/// Serialises the value with a header holding its current version.
+pub(crate) fn to_versioned_bytes<T: Versioned>(
+    value: &T,
+) -> std::result::Result<Vec<u8>, rmp_serde::encode::Error> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.push(VERSION_MARKER);
+    bytes.extend_from_slice(&VERSION_MAGIC);
+    bytes.push(T::VERSION);
+    value.serialize(&mut rmp_serde::Serializer::new(&mut bytes))?;
+    Ok(bytes)
+}
+
+/// Deserialises a value written by any version up to the current one,
+/// upgrading it from older versions if needed.
+pub(crate) fn from_versioned_bytes<T: Versioned>(bytes: &[u8]) -> Result<T> {
+    let (version, payload) = match bytes {
+        [VERSION_MARKER, m0, m1, version, payload @ ..] if [*m0, *m1] == VERSION_MAGIC => {
+            (*version, payload)
+        }
+        _ => (0, bytes),
+    };
+
+    if version == T::VERSION {
+        deserialise(payload)
+    } else if version < T::VERSION {
+        debug!(
+            "Upgrading serialised {} from version {version} to {}",
+            std::any::type_name::<T>(),
+            T::VERSION
+        );
+        T::upgrade(version, payload)
+    } else {
+        Err(TransferError::UnsupportedSerialisationVersion {
+            found: version,
+            supported: T::VERSION,
+        })
+    }
+}
+
+/// Deserialises a MessagePack payload, for use by `Versioned::upgrade` implementations
+/// whose older layouts are still compatible with the current type.
+pub(crate) fn deserialise<T: DeserializeOwned>(payload: &[u8]) -> Result<T> {
+    rmp_serde::from_slice(payload)
+        .map_err(|err| TransferError::VersionedDeserialisationFailed(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "wallet")]
+    use crate::SignedSpend;
+    use crate::{
+        create_first_cash_note_from_key, rng, CashNote, CashNoteRedemption, MainSecretKey, Transfer,
+    };
+    use serde::Deserialize;
+    #[cfg(feature = "wallet")]
+    use std::collections::BTreeSet;
+
+    macro_rules! golden {
+        ($name:literal) => {
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/", $name)).trim()
+        };
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Old {
+        amount: u64,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct New {
+        amount: u64,
+        memo: String,
+    }
+
+    impl Versioned for New {
+        const VERSION: u8 = 2;
+
+        fn upgrade(version: u8, payload: &[u8]) -> Result<Self> {
+            match version {
+                0 | 1 => {
+                    let old: Old = deserialise(payload)?;
+                    Ok(New {
+                        amount: old.amount,
+                        memo: String::new(),
+                    })
+                }
+                _ => Err(TransferError::UnsupportedSerialisationVersion {
+                    found: version,
+                    supported: Self::VERSION,
+                }),
+            }
+        }
+    }
+
+    #[test]
+    fn current_version_round_trips() -> Result<()> {
+        let new = New {
+            amount: 7,
+            memo: "memo".to_string(),
+        };
+        let bytes = to_versioned_bytes(&new).expect("serialisation to succeed");
+        assert_eq!(&bytes[..HEADER_LEN], &[VERSION_MARKER, b'S', b'N', 2]);
+        assert_eq!(from_versioned_bytes::<New>(&bytes)?, new);
+        Ok(())
+    }
+
+    #[test]
+    fn untagged_payloads_are_upgraded() -> Result<()> {
+        let old = rmp_serde::to_vec(&Old { amount: 7 }).expect("serialisation to succeed");
+        let upgraded: New = from_versioned_bytes(&old)?;
+        assert_eq!(
+            upgraded,
+            New {
+                amount: 7,
+                memo: String::new()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn future_versions_are_rejected() {
+        let mut bytes = to_versioned_bytes(&New {
+            amount: 7,
+            memo: String::new(),
+        })
+        .expect("serialisation to succeed");
+        bytes[HEADER_LEN - 1] = 3;
+        assert_eq!(
+            from_versioned_bytes::<New>(&bytes),
+            Err(TransferError::UnsupportedSerialisationVersion {
+                found: 3,
+                supported: 2
+            })
+        );
+    }
+
+    // The values stored in the golden files, all derived from a fixed seed.
+    fn golden_cash_note() -> CashNote {
+        let key = MainSecretKey::random_from_rng(&mut rng::from_seed([1u8; 32]));
+        create_first_cash_note_from_key(&key).expect("Genesis creation to succeed.")
+    }
+
+    fn golden_transfer() -> Result<Transfer> {
+        let redemption = CashNoteRedemption::from_cash_note(&golden_cash_note())?;
+        Ok(Transfer::NetworkRoyalties(vec![redemption]))
+    }
+
+    fn from_hex_file<T: Versioned>(hex: &str) -> Result<T> {
+        let bytes = hex::decode(hex).map_err(|err| {
+            TransferError::VersionedDeserialisationFailed(format!("invalid golden file: {err}"))
+        })?;
+        from_versioned_bytes(&bytes)
+    }
+
+    #[test]
+    fn cash_note_golden_files() -> Result<()> {
+        let cash_note = golden_cash_note();
+        assert_eq!(CashNote::from_hex(golden!("cash_note_v0.hex"))?, cash_note);
+        assert_eq!(CashNote::from_hex(golden!("cash_note_v1.hex"))?, cash_note);
+        assert_eq!(cash_note.to_hex()?, golden!("cash_note_v1.hex"));
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_golden_files() -> Result<()> {
+        let transfer = golden_transfer()?;
+        assert_eq!(Transfer::from_hex(golden!("transfer_v0.hex"))?, transfer);
+        assert_eq!(Transfer::from_hex(golden!("transfer_v1.hex"))?, transfer);
+        assert_eq!(transfer.to_hex()?, golden!("transfer_v1.hex"));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "wallet")]
+    fn unconfirmed_spend_requests_golden_files() -> Result<()> {
+        let spends = golden_cash_note().parent_spends;
+        let v0: BTreeSet<SignedSpend> =
+            from_hex_file(golden!("unconfirmed_spend_requests_v0.hex"))?;
+        let v1: BTreeSet<SignedSpend> =
+            from_hex_file(golden!("unconfirmed_spend_requests_v1.hex"))?;
+        assert_eq!(v0, spends);
+        assert_eq!(v1, spends);
+        let bytes = to_versioned_bytes(&spends).expect("serialisation to succeed");
+        assert_eq!(
+            hex::encode(bytes),
+            golden!("unconfirmed_spend_requests_v1.hex")
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "wallet")]
+    #[test]
+    fn wallet_golden_files() -> Result<()> {
+        use crate::wallet::KeyLessWallet;
+
+        let balance = golden_cash_note().value()?;
+        let v0: KeyLessWallet = from_hex_file(golden!("wallet_v0.hex"))?;
+        let v1: KeyLessWallet = from_hex_file(golden!("wallet_v1.hex"))?;
+        assert_eq!(v0.balance(), balance);
+        assert_eq!(v1.balance(), balance);
+        let bytes = to_versioned_bytes(&v0).expect("serialisation to succeed");
+        assert_eq!(hex::encode(bytes), golden!("wallet_v1.hex"));
+        Ok(())
+    }
+}
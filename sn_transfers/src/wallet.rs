@@ -52,37 +52,66 @@
 //! which eventually clears from the mempool and becomes spendable again.
 //!
 
+// The error type is also used by the key types, and the in-memory wallet only needs the
+// transfers core, so these are built without the `wallet` feature.
+mod data_payments;
+mod error;
+mod events;
+mod mem_wallet;
+
+#[cfg(feature = "wallet")]
+mod address_book;
+#[cfg(feature = "wallet")]
 mod api;
+#[cfg(feature = "wallet")]
 mod authentication;
-mod data_payments;
+#[cfg(feature = "wallet")]
 mod encryption;
-mod error;
+#[cfg(feature = "wallet")]
 mod hot_wallet;
+#[cfg(feature = "wallet")]
 mod keys;
+#[cfg(feature = "wallet")]
 mod wallet_file;
+#[cfg(feature = "wallet")]
 mod watch_only;
 
+#[cfg(feature = "wallet")]
 pub use self::{
+    address_book::AddressBook,
     api::{WalletApi, WALLET_DIR_NAME},
-    data_payments::{Payment, PaymentQuote, QuotingMetrics, QUOTE_EXPIRATION_SECS},
-    error::{Error, Result},
-    hot_wallet::HotWallet,
+    hot_wallet::{
+        AbsorbReport, HotWallet, ReclaimableTransfer, SweepReport, MAX_INPUTS_PER_SWEEP_TX,
+    },
     keys::bls_secret_from_hex,
     wallet_file::wallet_lockfile_name,
     watch_only::WatchOnlyWallet,
 };
+pub use self::{
+    data_payments::{Payment, PaymentDetails, PaymentQuote, QuotingMetrics, QUOTE_EXPIRATION_SECS},
+    error::{Error, Result},
+    events::{WalletEvent, WalletEventsBroadcaster},
+    mem_wallet::MemWallet,
+};
+#[cfg(feature = "wallet")]
 pub(crate) use keys::store_new_keypair;
 
-use crate::{NanoTokens, UniquePubkey};
+#[cfg(feature = "wallet")]
+use crate::{versioned::from_versioned_bytes, NanoTokens, TransferError, UniquePubkey};
+#[cfg(feature = "wallet")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "wallet")]
 use std::{collections::BTreeMap, fs, path::Path};
+#[cfg(feature = "wallet")]
 use wallet_file::wallet_file_name;
 
+#[cfg(feature = "wallet")]
 #[derive(Default, Serialize, Deserialize)]
 pub(super) struct KeyLessWallet {
     available_cash_notes: BTreeMap<UniquePubkey, NanoTokens>,
 }
 
+#[cfg(feature = "wallet")]
 impl KeyLessWallet {
     /// Returns `Some(KeyLessWallet)` or None if file doesn't exist.
     /// If the file is being written to, it will wait until the write is complete before reading.
@@ -100,8 +129,12 @@ impl KeyLessWallet {
         while wallet.is_none() && attempts < 10 {
             info!("Attempting to read wallet file");
             match fs::read(&path) {
-                Ok(data) => match rmp_serde::from_slice(&data) {
+                Ok(data) => match from_versioned_bytes(&data) {
                     Ok(deserialized_wallet) => wallet = Some(deserialized_wallet),
+                    // a newer wallet file won't become readable by waiting for it
+                    Err(err @ TransferError::UnsupportedSerialisationVersion { .. }) => {
+                        return Err(Error::from(err))
+                    }
                     Err(_) => {
                         attempts += 1;
                         info!("Attempt {attempts} to read wallet file failed... ");
@@ -0,0 +1,244 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+use crate::{
+    versioned::{from_versioned_bytes, to_versioned_bytes, Versioned},
+    MainPubkey,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+// Filename for storing the address book in the wallet dir.
+const ADDRESS_BOOK_FILE_NAME: &str = "address_book";
+
+/// Labels for the `MainPubkey`s a wallet pays to, stored in the wallet dir,
+/// so a recipient can be referred to by name instead of by its address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressBook {
+    path: PathBuf,
+    entries: BTreeMap<String, MainPubkey>,
+}
+
+impl Versioned for BTreeMap<String, MainPubkey> {
+    const VERSION: u8 = 1;
+}
+
+impl AddressBook {
+    /// Loads the address book of the wallet at the given dir, or an empty one if there is none yet.
+    pub fn load_from(wallet_dir: &Path) -> Result<Self> {
+        let path = wallet_dir.join(ADDRESS_BOOK_FILE_NAME);
+        let entries = if path.is_file() {
+            from_versioned_bytes(&fs::read(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// Adds a label for the given key, failing if the label is already used.
+    pub fn add(&mut self, label: &str, main_pubkey: MainPubkey) -> Result<()> {
+        validate_label(label)?;
+        if self.entries.contains_key(label) {
+            return Err(Error::AddressBookLabelExists(label.to_string()));
+        }
+        self.entries.insert(label.to_string(), main_pubkey);
+        self.store()
+    }
+
+    /// Removes the label, returning the key it was given to.
+    pub fn remove(&mut self, label: &str) -> Result<MainPubkey> {
+        let main_pubkey = self
+            .entries
+            .remove(label)
+            .ok_or_else(|| Error::AddressBookLabelNotFound(label.to_string()))?;
+        self.store()?;
+        Ok(main_pubkey)
+    }
+
+    /// Returns the key with the given label.
+    pub fn lookup(&self, label: &str) -> Option<MainPubkey> {
+        self.entries.get(label).copied()
+    }
+
+    /// Returns the labels given to the key.
+    pub fn labels_of(&self, main_pubkey: &MainPubkey) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(_, pk)| *pk == main_pubkey)
+            .map(|(label, _)| label.as_str())
+            .collect()
+    }
+
+    /// All labels and their keys, ordered by label.
+    pub fn entries(&self) -> &BTreeMap<String, MainPubkey> {
+        &self.entries
+    }
+
+    /// Resolves a recipient given on the command line or similar: a label
+    /// from the address book, an address, or a hex encoded public key.
+    pub fn resolve(&self, label_or_address: &str) -> Result<MainPubkey> {
+        match self.lookup(label_or_address) {
+            Some(main_pubkey) => Ok(main_pubkey),
+            None => MainPubkey::from_str(label_or_address).map_err(|err| {
+                if is_valid_label(label_or_address) {
+                    Error::AddressBookLabelNotFound(label_or_address.to_string())
+                } else {
+                    err
+                }
+            }),
+        }
+    }
+
+    /// Writes all entries to the given file as a JSON object of labels to addresses.
+    pub fn export(&self, path: &Path) -> Result<()> {
+        let addresses: BTreeMap<&str, String> = self
+            .entries
+            .iter()
+            .map(|(label, main_pubkey)| (label.as_str(), main_pubkey.to_address()))
+            .collect();
+        let json = serde_json::to_string_pretty(&addresses)
+            .map_err(|err| Error::AddressBookImportExport(err.to_string()))?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Adds the entries of a file written by `export`, returning how many were new.
+    /// Nothing is imported if any label is invalid or already given to another key.
+    pub fn import(&mut self, path: &Path) -> Result<usize> {
+        let json = fs::read_to_string(path)?;
+        let addresses: BTreeMap<String, String> = serde_json::from_str(&json)
+            .map_err(|err| Error::AddressBookImportExport(err.to_string()))?;
+
+        let mut new_entries = BTreeMap::new();
+        for (label, address) in addresses {
+            validate_label(&label)?;
+            let main_pubkey = MainPubkey::from_str(&address)?;
+            match self.entries.get(&label) {
+                Some(existing) if *existing == main_pubkey => {}
+                Some(_) => return Err(Error::AddressBookLabelExists(label)),
+                None => {
+                    new_entries.insert(label, main_pubkey);
+                }
+            }
+        }
+
+        let imported = new_entries.len();
+        if imported > 0 {
+            self.entries.extend(new_entries);
+            self.store()?;
+        }
+        Ok(imported)
+    }
+
+    fn store(&self) -> Result<()> {
+        fs::write(&self.path, to_versioned_bytes(&self.entries)?)?;
+        Ok(())
+    }
+}
+
+// A label must be usable as a command line argument, and must not be mistaken for a key.
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && !label.chars().any(char::is_whitespace)
+        && MainPubkey::from_str(label).is_err()
+}
+
+fn validate_label(label: &str) -> Result<()> {
+    if is_valid_label(label) {
+        Ok(())
+    } else {
+        Err(Error::InvalidAddressBookLabel(label.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MainSecretKey;
+    use assert_fs::TempDir;
+
+    #[test]
+    fn entries_are_persisted() -> Result<()> {
+        let dir = TempDir::new().expect("Should be able to create a temp dir.");
+        let alice = MainSecretKey::random().main_pubkey();
+        let bob = MainSecretKey::random().main_pubkey();
+
+        let mut book = AddressBook::load_from(&dir)?;
+        book.add("alice", alice)?;
+        book.add("bob", bob)?;
+        assert!(matches!(
+            book.add("alice", bob),
+            Err(Error::AddressBookLabelExists(_))
+        ));
+        assert_eq!(book.remove("bob")?, bob);
+
+        let book = AddressBook::load_from(&dir)?;
+        assert_eq!(book.lookup("alice"), Some(alice));
+        assert_eq!(book.lookup("bob"), None);
+        assert_eq!(book.labels_of(&alice), vec!["alice"]);
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_accepts_labels_and_addresses() -> Result<()> {
+        let dir = TempDir::new().expect("Should be able to create a temp dir.");
+        let alice = MainSecretKey::random().main_pubkey();
+        let mut book = AddressBook::load_from(&dir)?;
+        book.add("alice", alice)?;
+
+        assert_eq!(book.resolve("alice")?, alice);
+        assert_eq!(book.resolve(&alice.to_address())?, alice);
+        assert_eq!(book.resolve(&alice.to_hex())?, alice);
+        assert!(matches!(
+            book.resolve("carol"),
+            Err(Error::AddressBookLabelNotFound(_))
+        ));
+
+        for label in ["", "two words", &alice.to_address(), &alice.to_hex()] {
+            assert!(matches!(
+                book.add(label, alice),
+                Err(Error::InvalidAddressBookLabel(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn import_is_all_or_nothing() -> Result<()> {
+        let dir = TempDir::new().expect("Should be able to create a temp dir.");
+        let alice = MainSecretKey::random().main_pubkey();
+        let bob = MainSecretKey::random().main_pubkey();
+        let export_path = dir.join("exported.json");
+
+        fs::create_dir_all(dir.join("a"))?;
+        let mut book = AddressBook::load_from(&dir.join("a"))?;
+        book.add("alice", alice)?;
+        book.add("bob", bob)?;
+        book.export(&export_path)?;
+
+        fs::create_dir_all(dir.join("b"))?;
+        let mut other = AddressBook::load_from(&dir.join("b"))?;
+        other.add("bob", alice)?;
+        assert!(matches!(
+            other.import(&export_path),
+            Err(Error::AddressBookLabelExists(_))
+        ));
+        assert_eq!(other.lookup("alice"), None);
+
+        other.remove("bob")?;
+        other.add("alice", alice)?;
+        assert_eq!(other.import(&export_path)?, 1);
+        assert_eq!(other.entries(), book.entries());
+        Ok(())
+    }
+}
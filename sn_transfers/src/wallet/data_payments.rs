@@ -6,10 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{MainPubkey, NanoTokens, Transfer};
+use super::{Error, Result};
+use crate::{
+    calculate_royalties_fee, transfers::CashNotesAndSecretKey, CashNote, DerivationIndex,
+    MainPubkey, NanoTokens, OfflineTransfer, Transfer, NETWORK_ROYALTIES_SPLIT,
+};
+#[cfg(feature = "wallet")]
 use libp2p::{identity::PublicKey, PeerId};
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::SystemTime,
+};
 use xor_name::XorName;
 
 /// The time in seconds that a quote is valid for
@@ -46,14 +54,157 @@ pub struct PaymentDetails {
 
 impl PaymentDetails {
     /// create a Payment for a PaymentDetails
+    /// The royalties transfer is left out when the fee rounded down to nothing.
     pub fn to_payment(&self) -> Payment {
+        let mut transfers = vec![self.transfer.0.clone()];
+        if !matches!(&self.royalties.0, Transfer::NetworkRoyalties(cnrs) if cnrs.is_empty()) {
+            transfers.push(self.royalties.0.clone());
+        }
         Payment {
-            transfers: vec![self.transfer.0.clone(), self.royalties.0.clone()],
+            transfers,
             quote: self.quote.clone(),
         }
     }
 }
 
+/// The transfer paying for a set of content addresses, along with the payment details for each.
+pub(super) struct StoragePayment {
+    pub offline_transfer: OfflineTransfer,
+    pub payments: Vec<(XorName, PaymentDetails)>,
+    pub storage_cost: NanoTokens,
+    pub royalties_fees: NanoTokens,
+}
+
+/// Creates the transfer paying for each content address in the price map, along with the
+/// network royalties, spending from the given cash_notes and sending any change to `change_to`.
+pub(super) fn create_storage_payment(
+    price_map: &BTreeMap<XorName, (MainPubkey, PaymentQuote, Vec<u8>)>,
+    available_cash_notes: CashNotesAndSecretKey,
+    change_to: MainPubkey,
+) -> Result<StoragePayment> {
+    let mut rng = &mut rand::thread_rng();
+    let mut storage_cost = NanoTokens::zero();
+    let mut royalties_fees = NanoTokens::zero();
+
+    // create random derivation indexes for recipients
+    let mut recipients_by_xor = BTreeMap::new();
+    for (xorname, (main_pubkey, quote, peer_id_bytes)) in price_map.iter() {
+        let storage_payee = (
+            quote.cost,
+            *main_pubkey,
+            DerivationIndex::random(&mut rng),
+            peer_id_bytes.clone(),
+        );
+        let royalties_fee = calculate_royalties_fee(quote.cost);
+        // one output per royalties beneficiary
+        let royalties_payees: Vec<_> = NETWORK_ROYALTIES_SPLIT
+            .split(royalties_fee)
+            .into_iter()
+            .map(|(amount, main_pubkey)| (amount, main_pubkey, DerivationIndex::random(&mut rng)))
+            .collect();
+
+        storage_cost = storage_cost
+            .checked_add(quote.cost)
+            .ok_or(Error::TotalPriceTooHigh)?;
+        royalties_fees = royalties_fees
+            .checked_add(royalties_fee)
+            .ok_or(Error::TotalPriceTooHigh)?;
+
+        recipients_by_xor.insert(xorname, (storage_payee, royalties_payees));
+    }
+
+    // create offline transfers
+    let recipients = recipients_by_xor
+        .values()
+        .flat_map(|(node, roy)| std::iter::once((node.0, node.1, node.2)).chain(roy.clone()))
+        .collect();
+
+    let spend_reason = Default::default();
+    let offline_transfer =
+        OfflineTransfer::new(available_cash_notes, recipients, change_to, spend_reason)?;
+    trace!(
+        "create_storage_payment created offline_transfer with {} cashnotes",
+        offline_transfer.cash_notes_for_recipient.len()
+    );
+
+    let mut payments = Vec::with_capacity(recipients_by_xor.len());
+    let mut cashnotes_to_use: HashSet<CashNote> = offline_transfer
+        .cash_notes_for_recipient
+        .iter()
+        .cloned()
+        .collect();
+    for (xorname, recipients_info) in recipients_by_xor {
+        let (storage_payee, royalties_payees) = recipients_info;
+        let (pay_amount, node_key, _, peer_id_bytes) = storage_payee;
+        let cash_note_for_node = cashnotes_to_use
+            .iter()
+            .find(|cash_note| {
+                cash_note.value() == Ok(pay_amount) && cash_note.main_pubkey() == &node_key
+            })
+            .ok_or(Error::CouldNotSendMoney(format!(
+                "No cashnote found to pay node for {xorname:?}"
+            )))?
+            .clone();
+        cashnotes_to_use.remove(&cash_note_for_node);
+        let transfer_amount = cash_note_for_node.value()?;
+        let transfer_for_node = Transfer::transfer_from_cash_note(&cash_note_for_node)?;
+        trace!(
+            "Created transaction regarding {xorname:?} paying {transfer_amount:?} to {node_key:?}."
+        );
+
+        let mut cash_notes_for_royalties = Vec::with_capacity(royalties_payees.len());
+        for (royalties_amount, royalties_key, _) in royalties_payees {
+            let cash_note_for_royalties = cashnotes_to_use
+                .iter()
+                .find(|cash_note| {
+                    cash_note.value() == Ok(royalties_amount)
+                        && cash_note.main_pubkey() == &royalties_key
+                })
+                .ok_or(Error::CouldNotSendMoney(format!(
+                    "No cashnote found to pay royalties to {royalties_key:?} for {xorname:?}"
+                )))?
+                .clone();
+            cashnotes_to_use.remove(&cash_note_for_royalties);
+            trace!("Created network royalties cnr regarding {xorname:?} paying {royalties_amount:?} to {royalties_key:?}.");
+            cash_notes_for_royalties.push(cash_note_for_royalties);
+        }
+        let royalties = Transfer::royalties_transfer_from_cash_notes(&cash_notes_for_royalties)?;
+        let royalties_amount =
+            cash_notes_for_royalties
+                .iter()
+                .try_fold(NanoTokens::zero(), |total, cash_note| {
+                    total
+                        .checked_add(cash_note.value()?)
+                        .ok_or(Error::TotalPriceTooHigh)
+                })?;
+
+        let quote = price_map
+            .get(xorname)
+            .ok_or(Error::CouldNotSendMoney(format!(
+                "No quote found for {xorname:?}"
+            )))?
+            .1
+            .clone();
+        let payment = PaymentDetails {
+            recipient: node_key,
+            peer_id_bytes,
+            transfer: (transfer_for_node, transfer_amount),
+            royalties: (royalties, royalties_amount),
+            quote,
+        };
+
+        payments.push((*xorname, payment));
+    }
+    trace!("create_storage_payment matched {} payments", payments.len());
+
+    Ok(StoragePayment {
+        offline_transfer,
+        payments,
+        storage_cost,
+        royalties_fees,
+    })
+}
+
 /// A generic type for signatures
 pub type QuoteSignature = Vec<u8>;
 
@@ -148,6 +299,7 @@ impl PaymentQuote {
     }
 
     /// Check self is signed by the claimed peer
+    #[cfg(feature = "wallet")]
     pub fn check_is_signed_by_claimed_peer(&self, claimed_peer: PeerId) -> bool {
         let pub_key = if let Ok(pub_key) = PublicKey::try_decode_protobuf(&self.pub_key) {
             pub_key
@@ -269,6 +421,7 @@ impl PaymentQuote {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "wallet")]
     use libp2p::identity::Keypair;
     use std::{thread::sleep, time::Duration};
 
@@ -282,6 +435,20 @@ mod tests {
     }
 
     #[test]
+    fn test_empty_royalties_are_left_out_of_the_payment() {
+        let transfer = Transfer::NetworkRoyalties(vec![]);
+        let details = PaymentDetails {
+            recipient: crate::MainSecretKey::random().main_pubkey(),
+            peer_id_bytes: vec![],
+            transfer: (transfer.clone(), NanoTokens::zero()),
+            royalties: (transfer, NanoTokens::zero()),
+            quote: PaymentQuote::zero(),
+        };
+        assert_eq!(details.to_payment().transfers.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "wallet")]
     fn test_is_signed_by_claimed_peer() {
         let keypair = Keypair::generate_ed25519();
         let peer_id = keypair.public().to_peer_id();
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::UniquePubkey;
+use crate::{SpendAddress, UniquePubkey};
 use std::{collections::BTreeSet, path::PathBuf};
 use thiserror::Error;
 use xor_name::XorName;
@@ -25,6 +25,10 @@ pub enum Error {
     #[error("Double spend attempted with cashnotes: {0:?}")]
     DoubleSpendAttemptedForCashNotes(BTreeSet<UniquePubkey>),
 
+    /// The wallets being merged hold different spends of the same cashnotes
+    #[error("Conflicting spend records for the spends at: {0:?}")]
+    ConflictingSpendRecords(BTreeSet<SpendAddress>),
+
     /// Address provided is of the wrong type
     #[error("Invalid address type")]
     InvalidAddressType,
@@ -82,6 +86,24 @@ pub enum Error {
     /// Failed to decode a hex string to a key
     #[error("Could not decode hex string to key")]
     FailedToDecodeHexToKey,
+    /// The address is malformed, has a typo, or was truncated
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    /// The address is valid, but for another type of key
+    #[error("The address is for a different type of key, expected the {expected:?} prefix, found {found:?}")]
+    AddressHrpMismatch { expected: String, found: String },
+    /// The label is already given to a key in the address book
+    #[error("The label {0:?} is already in the address book")]
+    AddressBookLabelExists(String),
+    /// The label is not in the address book
+    #[error("The label {0:?} is not in the address book")]
+    AddressBookLabelNotFound(String),
+    /// The label is empty, contains whitespace, or could be mistaken for a key
+    #[error("Invalid address book label: {0:?}")]
+    InvalidAddressBookLabel(String),
+    /// Failed to export or import the address book
+    #[error("Failed to export or import the address book: {0}")]
+    AddressBookImportExport(String),
     /// Failed to serialize a main key to hex
     #[error("Could not serialize main key to hex: {0}")]
     FailedToHexEncodeKey(String),
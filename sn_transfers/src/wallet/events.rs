@@ -0,0 +1,71 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{NanoTokens, UniquePubkey};
+use serde::{Deserialize, Serialize};
+use std::sync::{
+    mpsc::{channel, Receiver, Sender},
+    Arc, Mutex,
+};
+
+/// Channel where events will be broadcasted by the wallet.
+#[derive(Clone, Debug, Default)]
+pub struct WalletEventsBroadcaster(Arc<Mutex<Vec<Sender<WalletEvent>>>>);
+
+impl WalletEventsBroadcaster {
+    /// Returns a new receiver to listen to the channel.
+    /// Multiple receivers can be actively listening.
+    pub fn subscribe(&self) -> Receiver<WalletEvent> {
+        let (sender, receiver) = channel();
+        if let Ok(mut senders) = self.0.lock() {
+            senders.push(sender);
+        }
+        receiver
+    }
+
+    /// Whether anyone is listening to the channel.
+    #[cfg(feature = "wallet")]
+    pub(crate) fn has_listeners(&self) -> bool {
+        self.0
+            .lock()
+            .map(|senders| !senders.is_empty())
+            .unwrap_or(false)
+    }
+
+    // Broadcast a new event, meant to be a helper only used by the wallet's internals.
+    // Listeners that have dropped their receiver are removed.
+    pub(crate) fn broadcast(&self, event: WalletEvent) {
+        if let Ok(mut senders) = self.0.lock() {
+            senders.retain(|sender| sender.send(event.clone()).is_ok());
+        } else {
+            trace!("Could not broadcast WalletEvent {event:?}, as the listeners lock is poisoned");
+        }
+    }
+}
+
+/// Type of events broadcasted by the wallet to the public API.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WalletEvent {
+    /// The balance of the wallet has changed.
+    BalanceChanged { old: NanoTokens, new: NanoTokens },
+    /// A CashNote has been deposited into the wallet.
+    CashNoteReceived {
+        unique_pubkey: UniquePubkey,
+        amount: NanoTokens,
+    },
+    /// A spend made by this wallet has been confirmed by the network.
+    SpendConfirmed { unique_pubkey: UniquePubkey },
+    /// A spend made by this wallet has been dropped as it could not be confirmed by the network.
+    SpendFailed { unique_pubkey: UniquePubkey },
+    /// A transaction of a sweep has been created, see `HotWallet::local_sweep_to`.
+    SweepProgress {
+        swept_cash_notes: usize,
+        total_cash_notes: usize,
+        swept_amount: NanoTokens,
+    },
+}
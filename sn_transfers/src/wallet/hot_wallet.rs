@@ -8,12 +8,14 @@
 
 use super::{
     api::{WalletApi, WALLET_DIR_NAME},
-    data_payments::{PaymentDetails, PaymentQuote},
+    data_payments::{create_storage_payment, PaymentQuote, StoragePayment},
+    events::{WalletEvent, WalletEventsBroadcaster},
     keys::{get_main_key_from_disk, store_new_keypair},
     wallet_file::{
-        get_confirmed_spend, get_unconfirmed_spend_requests, has_confirmed_spend,
-        load_created_cash_note, remove_cash_notes, remove_unconfirmed_spend_requests,
-        store_created_cash_notes, store_unconfirmed_spend_requests,
+        get_confirmed_spend, get_confirmed_spend_records, get_reclaimable_transfers,
+        get_unconfirmed_spend_requests, has_confirmed_spend, load_created_cash_note,
+        remove_cash_notes, remove_unconfirmed_spend_requests, store_confirmed_spend_record,
+        store_created_cash_notes, store_reclaimable_transfers, store_unconfirmed_spend_requests,
     },
     watch_only::WatchOnlyWallet,
     Error, Result,
@@ -25,24 +27,89 @@ use crate::wallet::keys::{
     store_main_secret_key,
 };
 use crate::{
-    calculate_royalties_fee,
     cashnotes::UnsignedTransfer,
     transfers::{CashNotesAndSecretKey, OfflineTransfer},
     CashNote, CashNoteRedemption, DerivationIndex, DerivedSecretKey, MainPubkey, MainSecretKey,
-    NanoTokens, SignedSpend, Spend, SpendAddress, SpendReason, Transaction, Transfer, UniquePubkey,
-    WalletError, NETWORK_ROYALTIES_PK,
+    NanoTokens, ReclaimClause, SignedSpend, Spend, SpendAddress, SpendReason, Transaction,
+    Transfer, TransferError, UniquePubkey, WalletError,
 };
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, BTreeSet, HashSet},
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
     fs::File,
     path::{Path, PathBuf},
-    time::Instant,
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 use xor_name::XorName;
 
 /// A locked file handle, that when dropped releases the lock.
 pub type WalletExclusiveAccess = File;
 
+/// What was merged into a wallet by `HotWallet::absorb`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AbsorbReport {
+    /// CashNotes which were only available in the absorbed wallet.
+    pub cash_notes: BTreeSet<UniquePubkey>,
+    /// CashNotes which were available in this wallet, but already spent by the absorbed one.
+    pub spent_cash_notes: BTreeSet<UniquePubkey>,
+    /// Spends, confirmed or not, which were only known to the absorbed wallet.
+    pub spends: BTreeSet<SpendAddress>,
+}
+
+/// The maximum number of CashNotes spent by a single transaction created by `HotWallet::local_sweep_to`.
+pub const MAX_INPUTS_PER_SWEEP_TX: usize = 64;
+
+/// The outcome of `HotWallet::local_sweep_to`.
+#[derive(Debug)]
+pub struct SweepReport {
+    /// The CashNotes created for the cold key, one per transaction.
+    pub cash_notes: Vec<CashNote>,
+    /// How many of the wallet's CashNotes were spent.
+    pub swept_cash_notes: usize,
+    /// How many CashNotes were available when the sweep started.
+    pub total_cash_notes: usize,
+    /// The total amount sent to the cold key.
+    pub swept_amount: NanoTokens,
+    /// The error which stopped the sweep before all CashNotes were spent, if any.
+    pub error: Option<Error>,
+}
+
+impl SweepReport {
+    /// Returns true if all available CashNotes were swept.
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none() && self.swept_cash_notes == self.total_cash_notes
+    }
+}
+
+/// A transfer made with `HotWallet::local_send_reclaimable`, which we can take back
+/// if its recipient hasn't spent it by the deadline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReclaimableTransfer {
+    /// The CashNote created for the recipient.
+    pub cash_note: CashNote,
+    /// The index deriving the refund key from our main key.
+    pub refund_index: DerivationIndex,
+}
+
+impl ReclaimableTransfer {
+    /// The clause of the CashNote's output allowing us to reclaim it.
+    pub fn reclaim_clause(&self) -> Option<ReclaimClause> {
+        self.cash_note
+            .parent_tx
+            .outputs
+            .iter()
+            .find(|output| output.unique_pubkey == self.cash_note.unique_pubkey())
+            .and_then(|output| output.reclaim)
+    }
+
+    /// Returns true if the deadline has passed at `now`, in seconds since the unix epoch, so the
+    /// CashNote can be reclaimed.
+    pub fn has_expired(&self, now: u64) -> bool {
+        self.reclaim_clause()
+            .is_some_and(|reclaim| reclaim.has_expired(now))
+    }
+}
+
 /// A hot-wallet.
 pub struct HotWallet {
     /// The secret key with which we can access
@@ -55,6 +122,8 @@ pub struct HotWallet {
     unconfirmed_spend_requests: BTreeSet<SignedSpend>,
     /// Handles authentication of (encrypted) wallets.
     authentication_manager: AuthenticationManager,
+    /// Channel where wallet events are broadcasted to listeners.
+    events: WalletEventsBroadcaster,
 }
 
 impl HotWallet {
@@ -71,6 +140,12 @@ impl HotWallet {
         self.watchonly_wallet.api().wallet_dir()
     }
 
+    /// Returns a new receiver of the events broadcasted by this wallet,
+    /// so callers can react to changes instead of polling the wallet dir.
+    pub fn subscribe_to_events(&self) -> std::sync::mpsc::Receiver<WalletEvent> {
+        self.events.subscribe()
+    }
+
     /// Returns whether a wallet in the specified directory is encrypted or not.
     pub fn is_encrypted(root_dir: &Path) -> bool {
         let wallet_dir = root_dir.join(WALLET_DIR_NAME);
@@ -97,8 +172,10 @@ impl HotWallet {
             ));
         }
 
-        // if it's a matching key, we can overwrite our wallet
+        // if it's a matching key, we can overwrite our wallet, keeping our listeners
+        let events = std::mem::take(&mut self.events);
         *self = wallet;
+        self.events = events;
         Ok(())
     }
 
@@ -199,7 +276,9 @@ impl HotWallet {
     where
         T: IntoIterator<Item = &'a UniquePubkey>,
     {
+        let old_balance = self.balance();
         self.watchonly_wallet.mark_notes_as_spent(unique_pubkeys);
+        self.broadcast_balance_change(old_balance);
     }
 
     pub fn unconfirmed_spend_requests_exist(&self) -> bool {
@@ -208,7 +287,7 @@ impl HotWallet {
 
     /// Try to load any new cash_notes from the `cash_notes dir` in the wallet dir.
     pub fn try_load_cash_notes(&mut self) -> Result<()> {
-        self.watchonly_wallet.try_load_cash_notes()
+        self.with_deposit_events(|wallet| wallet.watchonly_wallet.try_load_cash_notes())
     }
 
     /// Loads a serialized wallet from a path and given main key.
@@ -241,6 +320,7 @@ impl HotWallet {
             watchonly_wallet,
             unconfirmed_spend_requests,
             authentication_manager: AuthenticationManager::new(wallet_dir),
+            events: Default::default(),
         })
     }
 
@@ -317,14 +397,142 @@ impl HotWallet {
         Ok(())
     }
 
+    /// Merges another copy of this wallet, e.g. from another machine, into this one.
+    /// The other wallet is left untouched, and must have the same main key.
+    ///
+    /// CashNotes are de-duplicated by their `UniquePubkey`, and those spent in either wallet
+    /// stay spent. Confirmed and unconfirmed spends are merged, failing before anything is
+    /// changed if the two wallets hold different spends of the same CashNote.
+    /// Derivation indexes are random, so there is no derivation state to reconcile.
+    pub fn absorb(&mut self, other_wallet_dir: &Path) -> Result<AbsorbReport> {
+        let other_pubkey = get_main_pubkey(other_wallet_dir)?
+            .ok_or_else(|| Error::PubkeyNotFound(other_wallet_dir.to_path_buf()))?;
+        if other_pubkey != self.address() {
+            return Err(Error::PubKeyMismatch(other_wallet_dir.to_path_buf()));
+        }
+
+        let mut report = AbsorbReport::default();
+        self.with_deposit_events(|wallet| {
+            report = wallet.absorb_inner(other_wallet_dir)?;
+            Ok(())
+        })?;
+        Ok(report)
+    }
+
+    fn absorb_inner(&mut self, other_wallet_dir: &Path) -> Result<AbsorbReport> {
+        // lock and load from disk to make sure we're up to date and others can't modify the wallet concurrently
+        let exclusive_access = self.lock()?;
+        self.reload()?;
+        let wallet_dir = self.watchonly_wallet.wallet_dir().to_path_buf();
+
+        let other = WatchOnlyWallet::load_from_path(other_wallet_dir)?;
+        let other_unconfirmed =
+            get_unconfirmed_spend_requests(other_wallet_dir)?.unwrap_or_default();
+        let other_confirmed = get_confirmed_spend_records(other_wallet_dir)?;
+        let mut confirmed = get_confirmed_spend_records(&wallet_dir)?;
+
+        // All spend records of each wallet, as stored in the confirmed spends dir.
+        let records = |confirmed: &BTreeMap<SpendAddress, Vec<u8>>,
+                       unconfirmed: &BTreeSet<SignedSpend>| {
+            let mut records = confirmed.clone();
+            for spend in unconfirmed {
+                records
+                    .entry(spend.address())
+                    .or_insert_with(|| spend.to_bytes());
+            }
+            records
+        };
+        let ours = records(&confirmed, &self.unconfirmed_spend_requests);
+        let theirs = records(&other_confirmed, &other_unconfirmed);
+
+        let conflicts: BTreeSet<_> = theirs
+            .iter()
+            .filter(|(address, record)| ours.get(address).is_some_and(|ours| ours != *record))
+            .map(|(address, _)| *address)
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(Error::ConflictingSpendRecords(conflicts));
+        }
+
+        let mut report = AbsorbReport {
+            spends: theirs
+                .keys()
+                .filter(|address| !ours.contains_key(address))
+                .copied()
+                .collect(),
+            ..Default::default()
+        };
+
+        for (address, record) in other_confirmed {
+            if let Entry::Vacant(entry) = confirmed.entry(address) {
+                store_confirmed_spend_record(&wallet_dir, &address, &record)?;
+                entry.insert(record);
+            }
+        }
+        // spends confirmed by the other wallet no longer need to be sent
+        let unconfirmed_before = self.unconfirmed_spend_requests.clone();
+        self.unconfirmed_spend_requests
+            .retain(|spend| !confirmed.contains_key(&spend.address()));
+        for spend in other_unconfirmed {
+            if !confirmed.contains_key(&spend.address()) {
+                self.unconfirmed_spend_requests.insert(spend);
+            }
+        }
+        if self.unconfirmed_spend_requests != unconfirmed_before {
+            self.store_unconfirmed_spend_requests()?;
+        }
+
+        let is_spent = |unique_pubkey: &UniquePubkey| {
+            let address = SpendAddress::from_unique_pubkey(unique_pubkey);
+            ours.contains_key(&address) || theirs.contains_key(&address)
+        };
+        report.spent_cash_notes = self
+            .watchonly_wallet
+            .available_cash_notes()
+            .keys()
+            .filter(|unique_pubkey| is_spent(unique_pubkey))
+            .copied()
+            .collect();
+        self.watchonly_wallet
+            .mark_notes_as_spent(&report.spent_cash_notes);
+
+        let mut cash_notes = vec![];
+        for unique_pubkey in other.available_cash_notes().keys() {
+            if self.cash_note_presents(unique_pubkey) || is_spent(unique_pubkey) {
+                continue;
+            }
+            match load_created_cash_note(unique_pubkey, other_wallet_dir) {
+                Some(cash_note) => cash_notes.push(cash_note),
+                None => warn!(
+                    "Skipping CashNote {unique_pubkey:?} as the absorbed wallet doesn't have it"
+                ),
+            }
+        }
+        self.watchonly_wallet.deposit(&cash_notes)?;
+        self.store_cash_notes_to_disk(&cash_notes)?;
+        report.cash_notes = cash_notes
+            .iter()
+            .map(|cash_note| cash_note.unique_pubkey())
+            .collect();
+
+        self.store(exclusive_access)?;
+        Ok(report)
+    }
+
     /// To remove a specific spend from the requests, if eg, we see one spend is _bad_
     pub fn clear_specific_spend_request(&mut self, unique_pub_key: UniquePubkey) {
         if let Err(error) = self.remove_cash_notes_from_disk(vec![&unique_pub_key]) {
             warn!("Could not clean spend {unique_pub_key:?} due to {error:?}");
         }
 
+        let before = self.unconfirmed_spend_requests.len();
         self.unconfirmed_spend_requests
-            .retain(|signed_spend| signed_spend.spend.unique_pubkey.ne(&unique_pub_key))
+            .retain(|signed_spend| signed_spend.spend.unique_pubkey.ne(&unique_pub_key));
+        if self.unconfirmed_spend_requests.len() != before {
+            self.events.broadcast(WalletEvent::SpendFailed {
+                unique_pubkey: unique_pub_key,
+            });
+        }
     }
 
     /// Once spends are verified we can clear them and clean up
@@ -340,7 +548,12 @@ impl HotWallet {
         // Also need to remove unconfirmed_spend_requests from disk if was pre-loaded.
         let _ = self.remove_unconfirmed_spend_requests();
 
-        self.unconfirmed_spend_requests = Default::default();
+        let confirmed = std::mem::take(&mut self.unconfirmed_spend_requests);
+        for signed_spend in confirmed {
+            self.events.broadcast(WalletEvent::SpendConfirmed {
+                unique_pubkey: signed_spend.spend.unique_pubkey,
+            });
+        }
     }
 
     pub fn balance(&self) -> NanoTokens {
@@ -450,6 +663,242 @@ impl HotWallet {
         Ok(created_cash_notes)
     }
 
+    /// Spends all available cash_notes to the given key, e.g. a cold wallet, in as many
+    /// transactions as needed, each spending up to `MAX_INPUTS_PER_SWEEP_TX` cash_notes.
+    ///
+    /// A `WalletEvent::SweepProgress` is broadcasted after each transaction. Each transaction is
+    /// stored along with its unconfirmed spends as soon as it's created, so if one fails the
+    /// previous ones are kept, the remaining cash_notes stay available, and the error is
+    /// returned in the report. An error is only returned if no transaction could be created.
+    ///
+    /// Like `local_send`, this is offline only: the spends are left unconfirmed and still need
+    /// to be sent to the network, which `WalletClient::sweep_to` of `sn_client` does.
+    pub fn local_sweep_to(&mut self, cold_pubkey: MainPubkey) -> Result<SweepReport> {
+        let (available_cash_notes, exclusive_access) = self.available_cash_notes()?;
+        drop(exclusive_access);
+
+        // cash_notes with no value, or whose value can't be worked out, can't be swept
+        let cash_notes: CashNotesAndSecretKey = available_cash_notes
+            .into_iter()
+            .filter(|(cash_note, _)| matches!(cash_note.value(), Ok(value) if !value.is_zero()))
+            .collect();
+
+        let mut report = SweepReport {
+            cash_notes: vec![],
+            swept_cash_notes: 0,
+            total_cash_notes: cash_notes.len(),
+            swept_amount: NanoTokens::zero(),
+            error: None,
+        };
+        for batch in cash_notes.chunks(MAX_INPUTS_PER_SWEEP_TX) {
+            match self.sweep_batch(batch.to_vec(), cold_pubkey) {
+                Ok(Some((swept_cash_notes, cash_note))) => {
+                    report.swept_cash_notes += swept_cash_notes;
+                    report.swept_amount = report
+                        .swept_amount
+                        .checked_add(cash_note.value()?)
+                        .ok_or(TransferError::ExcessiveNanoValue)?;
+                    report.cash_notes.push(cash_note);
+                    info!(
+                        "Swept {}/{} cash_notes, {} sent to {cold_pubkey:?}",
+                        report.swept_cash_notes, report.total_cash_notes, report.swept_amount
+                    );
+                    self.events.broadcast(WalletEvent::SweepProgress {
+                        swept_cash_notes: report.swept_cash_notes,
+                        total_cash_notes: report.total_cash_notes,
+                        swept_amount: report.swept_amount,
+                    });
+                }
+                Ok(None) => {}
+                Err(err) if report.cash_notes.is_empty() => return Err(err),
+                Err(err) => {
+                    warn!(
+                        "Sweep stopped after {}/{} cash_notes: {err:?}",
+                        report.swept_cash_notes, report.total_cash_notes
+                    );
+                    report.error = Some(err);
+                    break;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Spends the batch of cash_notes to the cold key in a single transaction, returning the
+    // number of cash_notes spent and the created cash_note, if any of them was still available.
+    fn sweep_batch(
+        &mut self,
+        batch: CashNotesAndSecretKey,
+        cold_pubkey: MainPubkey,
+    ) -> Result<Option<(usize, CashNote)>> {
+        // lock and load from disk to make sure we're up to date and others can't modify the wallet concurrently
+        let exclusive_access = self.lock()?;
+        self.reload()?;
+
+        // some of them may have been spent by another process since the sweep started
+        let batch: CashNotesAndSecretKey = batch
+            .into_iter()
+            .filter(|(cash_note, _)| {
+                self.watchonly_wallet
+                    .available_cash_notes()
+                    .contains_key(&cash_note.unique_pubkey())
+            })
+            .collect();
+        if batch.is_empty() {
+            return Ok(None);
+        }
+
+        let amount = batch
+            .iter()
+            .try_fold(NanoTokens::zero(), |total, (cash_note, _)| {
+                total
+                    .checked_add(cash_note.value()?)
+                    .ok_or(TransferError::ExcessiveNanoValue)
+            })?;
+        let swept_cash_notes = batch.len();
+        let to = vec![(
+            amount,
+            cold_pubkey,
+            DerivationIndex::random(&mut rand::rngs::OsRng),
+        )];
+        let transfer = OfflineTransfer::new(batch, to, self.address(), SpendReason::default())?;
+        let cash_note = transfer
+            .cash_notes_for_recipient
+            .first()
+            .cloned()
+            .ok_or_else(|| {
+                Error::CouldNotSendMoney("No cash_note created for the sweep".to_string())
+            })?;
+
+        self.update_local_wallet(transfer, exclusive_access, true)?;
+        self.store_unconfirmed_spend_requests()?;
+
+        Ok(Some((swept_cash_notes, cash_note)))
+    }
+
+    /// Make a transfer like `local_send`, except that we can take back any created cash_note
+    /// which hasn't been spent by the deadline, in seconds since the unix epoch, using
+    /// `reclaim_expired_transfers`. The transfers are kept until reclaimed or forgotten.
+    pub fn local_send_reclaimable(
+        &mut self,
+        to: Vec<(NanoTokens, MainPubkey)>,
+        deadline: u64,
+        reason: Option<SpendReason>,
+    ) -> Result<Vec<CashNote>> {
+        let mut rng = &mut rand::rngs::OsRng;
+        // create a unique key for each output
+        let to_unique_keys: Vec<_> = to
+            .into_iter()
+            .map(|(amount, address)| (amount, address, DerivationIndex::random(&mut rng)))
+            .collect();
+        let refund_index = DerivationIndex::random(&mut rng);
+        let reclaim = ReclaimClause {
+            refund_key: self.address().new_unique_pubkey(&refund_index),
+            deadline,
+        };
+
+        let (available_cash_notes, exclusive_access) = self.available_cash_notes()?;
+        let transfer = OfflineTransfer::new_reclaimable(
+            available_cash_notes,
+            to_unique_keys,
+            self.address(),
+            reason.unwrap_or_default(),
+            reclaim,
+        )?;
+
+        let created_cash_notes = transfer.cash_notes_for_recipient.clone();
+        self.update_local_wallet(transfer, exclusive_access, true)?;
+
+        // only tracked once sent, so a failed send doesn't leave transfers to reclaim behind
+        let _exclusive_access = self.lock()?;
+        let wallet_dir = self.watchonly_wallet.wallet_dir();
+        let mut reclaimable_transfers = get_reclaimable_transfers(wallet_dir)?;
+        for cash_note in &created_cash_notes {
+            let _ = reclaimable_transfers.insert(
+                cash_note.unique_pubkey(),
+                ReclaimableTransfer {
+                    cash_note: cash_note.clone(),
+                    refund_index,
+                },
+            );
+        }
+        store_reclaimable_transfers(wallet_dir, &reclaimable_transfers)?;
+
+        trace!("Releasing wallet lock"); // by dropping _exclusive_access
+        Ok(created_cash_notes)
+    }
+
+    /// The transfers made with `local_send_reclaimable` which haven't been reclaimed or forgotten yet.
+    pub fn reclaimable_transfers(&self) -> Result<BTreeMap<UniquePubkey, ReclaimableTransfer>> {
+        get_reclaimable_transfers(self.watchonly_wallet.wallet_dir())
+    }
+
+    /// Stops tracking a reclaimable transfer, e.g. once its recipient has spent it.
+    pub fn forget_reclaimable_transfer(&mut self, unique_pubkey: &UniquePubkey) -> Result<()> {
+        let _exclusive_access = self.lock()?;
+        let wallet_dir = self.watchonly_wallet.wallet_dir();
+        let mut reclaimable_transfers = get_reclaimable_transfers(wallet_dir)?;
+        if reclaimable_transfers.remove(unique_pubkey).is_some() {
+            store_reclaimable_transfers(wallet_dir, &reclaimable_transfers)?;
+        }
+        Ok(())
+    }
+
+    /// Takes back the cash_notes of all reclaimable transfers whose deadline has passed, with
+    /// one refund transaction each, so a refund rejected by the network doesn't affect the others.
+    /// Returns the cash_notes created for us, which are deposited in the wallet.
+    ///
+    /// A refund is rejected by the network if the recipient has already spent the cash_note,
+    /// so the caller should check for that beforehand and forget those transfers.
+    pub fn reclaim_expired_transfers(&mut self) -> Result<Vec<CashNote>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let mut refunded_cash_notes = vec![];
+        for (unique_pubkey, reclaimable) in self.reclaimable_transfers()? {
+            if !reclaimable.has_expired(now) {
+                continue;
+            }
+
+            // lock and load from disk to make sure we're up to date and others can't modify the wallet concurrently
+            let exclusive_access = self.lock()?;
+            self.reload()?;
+
+            let amount = reclaimable.cash_note.value()?;
+            let refund_key = self.key.derive_key(&reclaimable.refund_index);
+            let to = vec![(
+                amount,
+                self.address(),
+                DerivationIndex::random(&mut rand::rngs::OsRng),
+            )];
+            let transfer = OfflineTransfer::new(
+                vec![(reclaimable.cash_note, Some(refund_key))],
+                to,
+                self.address(),
+                SpendReason::default(),
+            )?;
+            let created_cash_notes = transfer.cash_notes_for_recipient.clone();
+            self.update_local_wallet(transfer, exclusive_access, true)?;
+
+            // only forgotten once refunded, so a failed refund can be retried
+            {
+                let _exclusive_access = self.lock()?;
+                let wallet_dir = self.watchonly_wallet.wallet_dir();
+                let mut reclaimable_transfers = get_reclaimable_transfers(wallet_dir)?;
+                let _ = reclaimable_transfers.remove(&unique_pubkey);
+                store_reclaimable_transfers(wallet_dir, &reclaimable_transfers)?;
+            }
+            self.store_unconfirmed_spend_requests()?;
+            self.deposit_and_store_to_disk(&created_cash_notes)?;
+            info!("Reclaimed {amount} from expired transfer {unique_pubkey:?}");
+            refunded_cash_notes.extend(created_cash_notes);
+        }
+
+        Ok(refunded_cash_notes)
+    }
+
     /// Prepare a signed transaction in local wallet and return all created cash_notes
     pub fn prepare_signed_transfer(
         &mut self,
@@ -535,49 +984,6 @@ impl HotWallet {
         &mut self,
         price_map: &BTreeMap<XorName, (MainPubkey, PaymentQuote, Vec<u8>)>,
     ) -> Result<(NanoTokens, NanoTokens)> {
-        let mut rng = &mut rand::thread_rng();
-        let mut storage_cost = NanoTokens::zero();
-        let mut royalties_fees = NanoTokens::zero();
-
-        let start = Instant::now();
-
-        // create random derivation indexes for recipients
-        let mut recipients_by_xor = BTreeMap::new();
-        for (xorname, (main_pubkey, quote, peer_id_bytes)) in price_map.iter() {
-            let storage_payee = (
-                quote.cost,
-                *main_pubkey,
-                DerivationIndex::random(&mut rng),
-                peer_id_bytes.clone(),
-            );
-            let royalties_fee = calculate_royalties_fee(quote.cost);
-            let royalties_payee = (
-                royalties_fee,
-                *NETWORK_ROYALTIES_PK,
-                DerivationIndex::random(&mut rng),
-            );
-
-            storage_cost = storage_cost
-                .checked_add(quote.cost)
-                .ok_or(WalletError::TotalPriceTooHigh)?;
-            royalties_fees = royalties_fees
-                .checked_add(royalties_fee)
-                .ok_or(WalletError::TotalPriceTooHigh)?;
-
-            recipients_by_xor.insert(xorname, (storage_payee, royalties_payee));
-        }
-
-        // create offline transfers
-        let recipients = recipients_by_xor
-            .values()
-            .flat_map(|(node, roy)| vec![(node.0, node.1, node.2), *roy])
-            .collect();
-
-        trace!(
-            "local_send_storage_payment prepared in {:?}",
-            start.elapsed()
-        );
-
         let start = Instant::now();
         let (available_cash_notes, exclusive_access) = self.available_cash_notes()?;
         trace!(
@@ -587,79 +993,19 @@ impl HotWallet {
         );
         debug!("Available CashNotes: {:#?}", available_cash_notes);
 
-        let spend_reason = Default::default();
-        let start = Instant::now();
-        let offline_transfer = OfflineTransfer::new(
-            available_cash_notes,
-            recipients,
-            self.address(),
-            spend_reason,
-        )?;
-        trace!(
-            "local_send_storage_payment created offline_transfer with {} cashnotes in {:?}",
-            offline_transfer.cash_notes_for_recipient.len(),
-            start.elapsed()
-        );
+        let StoragePayment {
+            offline_transfer,
+            payments,
+            storage_cost,
+            royalties_fees,
+        } = create_storage_payment(price_map, available_cash_notes, self.address())?;
 
         let start = Instant::now();
         // cache transfer payments in the wallet
-        let mut cashnotes_to_use: HashSet<CashNote> = offline_transfer
-            .cash_notes_for_recipient
-            .iter()
-            .cloned()
-            .collect();
-        for (xorname, recipients_info) in recipients_by_xor {
-            let (storage_payee, royalties_payee) = recipients_info;
-            let (pay_amount, node_key, _, peer_id_bytes) = storage_payee;
-            let cash_note_for_node = cashnotes_to_use
-                .iter()
-                .find(|cash_note| {
-                    cash_note.value() == Ok(pay_amount) && cash_note.main_pubkey() == &node_key
-                })
-                .ok_or(Error::CouldNotSendMoney(format!(
-                    "No cashnote found to pay node for {xorname:?}"
-                )))?
-                .clone();
-            cashnotes_to_use.remove(&cash_note_for_node);
-            let transfer_amount = cash_note_for_node.value()?;
-            let transfer_for_node = Transfer::transfer_from_cash_note(&cash_note_for_node)?;
-            trace!("Created transaction regarding {xorname:?} paying {transfer_amount:?} to {node_key:?}.");
-
-            let royalties_key = royalties_payee.1;
-            let royalties_amount = royalties_payee.0;
-            let cash_note_for_royalties = cashnotes_to_use
-                .iter()
-                .find(|cash_note| {
-                    cash_note.value() == Ok(royalties_amount)
-                        && cash_note.main_pubkey() == &royalties_key
-                })
-                .ok_or(Error::CouldNotSendMoney(format!(
-                    "No cashnote found to pay royalties for {xorname:?}"
-                )))?
-                .clone();
-            cashnotes_to_use.remove(&cash_note_for_royalties);
-            let royalties = Transfer::royalties_transfer_from_cash_note(&cash_note_for_royalties)?;
-            let royalties_amount = cash_note_for_royalties.value()?;
-            trace!("Created network royalties cnr regarding {xorname:?} paying {royalties_amount:?} to {royalties_key:?}.");
-
-            let quote = price_map
-                .get(xorname)
-                .ok_or(Error::CouldNotSendMoney(format!(
-                    "No quote found for {xorname:?}"
-                )))?
-                .1
-                .clone();
-            let payment = PaymentDetails {
-                recipient: node_key,
-                peer_id_bytes,
-                transfer: (transfer_for_node, transfer_amount),
-                royalties: (royalties, royalties_amount),
-                quote,
-            };
-
+        for (xorname, payment) in payments {
             let _ = self
                 .watchonly_wallet
-                .insert_payment_transaction(*xorname, payment);
+                .insert_payment_transaction(xorname, payment);
         }
         trace!(
             "local_send_storage_payment completed payments insertion in {:?}",
@@ -692,6 +1038,19 @@ impl HotWallet {
         transfer: OfflineTransfer,
         exclusive_access: WalletExclusiveAccess,
         insert_into_pending_spends: bool,
+    ) -> Result<()> {
+        let old_balance = self.balance();
+        let result =
+            self.update_local_wallet_inner(transfer, exclusive_access, insert_into_pending_spends);
+        self.broadcast_balance_change(old_balance);
+        result
+    }
+
+    fn update_local_wallet_inner(
+        &mut self,
+        transfer: OfflineTransfer,
+        exclusive_access: WalletExclusiveAccess,
+        insert_into_pending_spends: bool,
     ) -> Result<()> {
         // First of all, update client local state.
         let spent_unique_pubkeys: BTreeSet<_> = transfer
@@ -742,15 +1101,62 @@ impl HotWallet {
 
     /// Deposit the given cash_notes on the wallet (without storing them to disk).
     pub fn deposit(&mut self, received_cash_notes: &Vec<CashNote>) -> Result<()> {
-        self.watchonly_wallet.deposit(received_cash_notes)
+        self.with_deposit_events(|wallet| wallet.watchonly_wallet.deposit(received_cash_notes))
     }
 
     /// Store the given cash_notes to the `cash_notes` dir in the wallet dir.
     /// Update and store the updated wallet to disk
     /// This function locks the wallet to prevent concurrent processes from writing to it
     pub fn deposit_and_store_to_disk(&mut self, received_cash_notes: &Vec<CashNote>) -> Result<()> {
-        self.watchonly_wallet
-            .deposit_and_store_to_disk(received_cash_notes)
+        self.with_deposit_events(|wallet| {
+            wallet
+                .watchonly_wallet
+                .deposit_and_store_to_disk(received_cash_notes)
+        })
+    }
+
+    /// Runs the given deposit operation, broadcasting an event for every newly available
+    /// cash_note, and for the resulting balance change.
+    fn with_deposit_events<F>(&mut self, deposit: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        if !self.events.has_listeners() {
+            return deposit(self);
+        }
+
+        let known_cash_notes: BTreeSet<_> = self
+            .watchonly_wallet
+            .available_cash_notes()
+            .keys()
+            .copied()
+            .collect();
+        let old_balance = self.balance();
+
+        let result = deposit(self);
+
+        for (unique_pubkey, amount) in self.watchonly_wallet.available_cash_notes() {
+            if !known_cash_notes.contains(unique_pubkey) {
+                self.events.broadcast(WalletEvent::CashNoteReceived {
+                    unique_pubkey: *unique_pubkey,
+                    amount: *amount,
+                });
+            }
+        }
+        self.broadcast_balance_change(old_balance);
+
+        result
+    }
+
+    /// Broadcasts a `BalanceChanged` event if the balance differs from the given one.
+    fn broadcast_balance_change(&self, old_balance: NanoTokens) {
+        let new_balance = self.balance();
+        if new_balance != old_balance {
+            self.events.broadcast(WalletEvent::BalanceChanged {
+                old: old_balance,
+                new: new_balance,
+            });
+        }
     }
 
     pub fn unwrap_transfer(&self, transfer: &Transfer) -> Result<Vec<CashNoteRedemption>> {
@@ -803,23 +1209,25 @@ impl HotWallet {
             watchonly_wallet,
             unconfirmed_spend_requests,
             authentication_manager: AuthenticationManager::new(wallet_dir.to_path_buf()),
+            events: Default::default(),
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
 
-    use super::HotWallet;
-    use crate::wallet::authentication::AuthenticationManager;
+    use super::{HotWallet, MAX_INPUTS_PER_SWEEP_TX};
+    use crate::wallet::{authentication::AuthenticationManager, events::WalletEvent};
     use crate::{
         genesis::{create_first_cash_note_from_key, GENESIS_CASHNOTE_AMOUNT},
         wallet::{
             data_payments::PaymentQuote, hot_wallet::WALLET_DIR_NAME, wallet_file::store_wallet,
             watch_only::WatchOnlyWallet, KeyLessWallet,
         },
-        MainSecretKey, NanoTokens, SpendAddress,
+        DerivationIndex, MainSecretKey, NanoTokens, OfflineTransfer, SpendAddress, SpendReason,
+        TransferError, WalletError,
     };
     use assert_fs::TempDir;
     use eyre::Result;
@@ -860,6 +1268,7 @@ mod tests {
             watchonly_wallet: WatchOnlyWallet::new(main_pubkey, &dir, KeyLessWallet::default()),
             unconfirmed_spend_requests: Default::default(),
             authentication_manager: AuthenticationManager::new(dir.to_path_buf()),
+            events: Default::default(),
         };
 
         assert_eq!(main_pubkey, deposit_only.address());
@@ -888,6 +1297,7 @@ mod tests {
             watchonly_wallet: WatchOnlyWallet::new(main_pubkey, &dir, KeyLessWallet::default()),
             unconfirmed_spend_requests: Default::default(),
             authentication_manager: AuthenticationManager::new(dir.to_path_buf()),
+            events: Default::default(),
         };
 
         deposit_only.deposit_and_store_to_disk(&vec![])?;
@@ -914,6 +1324,7 @@ mod tests {
             watchonly_wallet: WatchOnlyWallet::new(main_pubkey, &dir, KeyLessWallet::default()),
             unconfirmed_spend_requests: Default::default(),
             authentication_manager: AuthenticationManager::new(dir.to_path_buf()),
+            events: Default::default(),
         };
 
         deposit_only.deposit_and_store_to_disk(&vec![genesis])?;
@@ -936,6 +1347,7 @@ mod tests {
             watchonly_wallet: WatchOnlyWallet::new(main_pubkey, &dir, KeyLessWallet::default()),
             unconfirmed_spend_requests: Default::default(),
             authentication_manager: AuthenticationManager::new(dir.to_path_buf()),
+            events: Default::default(),
         };
 
         local_wallet.deposit_and_store_to_disk(&vec![genesis])?;
@@ -960,6 +1372,7 @@ mod tests {
             watchonly_wallet: WatchOnlyWallet::new(main_pubkey, &dir, KeyLessWallet::default()),
             unconfirmed_spend_requests: Default::default(),
             authentication_manager: AuthenticationManager::new(dir.to_path_buf()),
+            events: Default::default(),
         };
 
         deposit_only.deposit_and_store_to_disk(&vec![genesis_0.clone()])?;
@@ -1217,6 +1630,54 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn events_are_broadcasted_on_deposit_and_send() -> Result<()> {
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+        let mut wallet = HotWallet::create_from_key(&root_dir, MainSecretKey::random(), None)?;
+        let events = wallet.subscribe_to_events();
+
+        let cash_note =
+            create_first_cash_note_from_key(&wallet.key).expect("Genesis creation to succeed.");
+        let unique_pubkey = cash_note.unique_pubkey();
+        wallet.deposit_and_store_to_disk(&vec![cash_note])?;
+
+        let genesis_amount = NanoTokens::from(GENESIS_CASHNOTE_AMOUNT);
+        assert_eq!(
+            events.try_recv()?,
+            WalletEvent::CashNoteReceived {
+                unique_pubkey,
+                amount: genesis_amount,
+            }
+        );
+        assert_eq!(
+            events.try_recv()?,
+            WalletEvent::BalanceChanged {
+                old: NanoTokens::zero(),
+                new: genesis_amount,
+            }
+        );
+
+        let to = vec![(NanoTokens::from(100), MainSecretKey::random().main_pubkey())];
+        let _created_cash_notes = wallet.local_send(to, None)?;
+        assert_eq!(
+            events.try_recv()?,
+            WalletEvent::BalanceChanged {
+                old: genesis_amount,
+                new: NanoTokens::from(GENESIS_CASHNOTE_AMOUNT - 100),
+            }
+        );
+
+        wallet.clear_confirmed_spend_requests();
+        assert_eq!(
+            events.try_recv()?,
+            WalletEvent::SpendConfirmed { unique_pubkey }
+        );
+        assert!(events.try_recv().is_err());
+
+        Ok(())
+    }
+
     /// --------------------------------
     /// <-------> Encryption <--------->
     /// --------------------------------
@@ -1282,6 +1743,240 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn absorb_merges_cash_notes_and_spends() -> Result<()> {
+        let key = MainSecretKey::random();
+        let (dir_a, dir_b) = (create_temp_dir(), create_temp_dir());
+        let mut wallet_a = HotWallet::create_from_key(
+            dir_a.path(),
+            MainSecretKey::new(key.secret_key().clone()),
+            None,
+        )?;
+        let mut wallet_b = HotWallet::create_from_key(
+            dir_b.path(),
+            MainSecretKey::new(key.secret_key().clone()),
+            None,
+        )?;
+
+        let genesis = create_first_cash_note_from_key(&key).expect("Genesis creation to succeed.");
+        wallet_a.deposit_and_store_to_disk(&vec![genesis.clone()])?;
+        wallet_b.deposit_and_store_to_disk(&vec![genesis.clone()])?;
+
+        // only wallet A spends the genesis CashNote
+        let send_amount = 100;
+        let recipient = MainSecretKey::random().main_pubkey();
+        let _ = wallet_a.local_send(vec![(NanoTokens::from(send_amount), recipient)], None)?;
+        wallet_a.store_unconfirmed_spend_requests()?;
+
+        let report = wallet_b.absorb(&dir_a.path().join(WALLET_DIR_NAME))?;
+        assert_eq!(
+            report.spent_cash_notes,
+            BTreeSet::from([genesis.unique_pubkey()])
+        );
+        assert_eq!(report.cash_notes.len(), 1);
+        assert_eq!(
+            report.spends,
+            BTreeSet::from([SpendAddress::from_unique_pubkey(&genesis.unique_pubkey())])
+        );
+        assert_eq!(wallet_b.balance(), wallet_a.balance());
+        assert_eq!(
+            wallet_b.unconfirmed_spend_requests(),
+            wallet_a.unconfirmed_spend_requests()
+        );
+
+        // the merge is persisted, and absorbing again changes nothing
+        let mut reloaded = HotWallet::load_from(dir_b.path())?;
+        assert_eq!(reloaded.balance(), wallet_a.balance());
+        let report = reloaded.absorb(&dir_a.path().join(WALLET_DIR_NAME))?;
+        assert_eq!(report, Default::default());
+        Ok(())
+    }
+
+    #[test]
+    fn absorb_rejects_conflicting_spends() -> Result<()> {
+        let key = MainSecretKey::random();
+        let (dir_a, dir_b) = (create_temp_dir(), create_temp_dir());
+        let mut wallet_a = HotWallet::create_from_key(
+            dir_a.path(),
+            MainSecretKey::new(key.secret_key().clone()),
+            None,
+        )?;
+        let mut wallet_b = HotWallet::create_from_key(
+            dir_b.path(),
+            MainSecretKey::new(key.secret_key().clone()),
+            None,
+        )?;
+
+        let genesis = create_first_cash_note_from_key(&key).expect("Genesis creation to succeed.");
+        wallet_a.deposit_and_store_to_disk(&vec![genesis.clone()])?;
+        wallet_b.deposit_and_store_to_disk(&vec![genesis.clone()])?;
+
+        // both wallets spend the same CashNote differently
+        let recipient = MainSecretKey::random().main_pubkey();
+        let _ = wallet_a.local_send(vec![(NanoTokens::from(100), recipient)], None)?;
+        let _ = wallet_b.local_send(vec![(NanoTokens::from(200), recipient)], None)?;
+        wallet_a.store_unconfirmed_spend_requests()?;
+        wallet_b.store_unconfirmed_spend_requests()?;
+        let balance = wallet_b.balance();
+
+        let result = wallet_b.absorb(&dir_a.path().join(WALLET_DIR_NAME));
+        assert!(matches!(
+            result,
+            Err(WalletError::ConflictingSpendRecords(conflicts)) if conflicts.len() == 1
+        ));
+        assert_eq!(HotWallet::load_from(dir_b.path())?.balance(), balance);
+
+        let other = create_temp_dir();
+        let _ = HotWallet::create_from_key(other.path(), MainSecretKey::random(), None)?;
+        assert!(matches!(
+            wallet_b.absorb(&other.path().join(WALLET_DIR_NAME)),
+            Err(WalletError::PubKeyMismatch(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn sweep_spends_all_cash_notes_in_batches() -> Result<()> {
+        let dir = create_temp_dir();
+        let mut wallet = HotWallet::create_from_key(dir.path(), MainSecretKey::random(), None)?;
+        let genesis =
+            create_first_cash_note_from_key(&wallet.key).expect("Genesis creation to succeed.");
+        wallet.deposit_and_store_to_disk(&vec![genesis])?;
+
+        // split the balance into more cash_notes than a single sweep tx can spend
+        let address = wallet.address();
+        let to = vec![(NanoTokens::from(10), address); MAX_INPUTS_PER_SWEEP_TX * 2];
+        let created_cash_notes = wallet.local_send(to, None)?;
+        wallet.deposit_and_store_to_disk(&created_cash_notes)?;
+        wallet.clear_confirmed_spend_requests();
+        let balance = wallet.balance();
+        let events = wallet.subscribe_to_events();
+
+        let cold_pubkey = MainSecretKey::random().main_pubkey();
+        let report = wallet.local_sweep_to(cold_pubkey)?;
+        assert!(report.is_complete(), "{report:?}");
+        assert_eq!(report.total_cash_notes, MAX_INPUTS_PER_SWEEP_TX * 2 + 1);
+        assert_eq!(report.cash_notes.len(), 3);
+        assert_eq!(report.swept_amount, balance);
+        assert!(report
+            .cash_notes
+            .iter()
+            .all(|cash_note| cash_note.main_pubkey() == &cold_pubkey));
+        assert_eq!(wallet.balance(), NanoTokens::zero());
+
+        // the spends to send to the network are persisted
+        let reloaded = HotWallet::load_from(dir.path())?;
+        assert_eq!(
+            reloaded.unconfirmed_spend_requests().len(),
+            report.total_cash_notes
+        );
+
+        let progress: Vec<_> = events
+            .try_iter()
+            .filter_map(|event| match event {
+                WalletEvent::SweepProgress {
+                    swept_cash_notes, ..
+                } => Some(swept_cash_notes),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            progress,
+            vec![
+                MAX_INPUTS_PER_SWEEP_TX,
+                MAX_INPUTS_PER_SWEEP_TX * 2,
+                MAX_INPUTS_PER_SWEEP_TX * 2 + 1
+            ]
+        );
+
+        // nothing is left to sweep
+        let report = wallet.local_sweep_to(cold_pubkey)?;
+        assert!(report.is_complete());
+        assert!(report.cash_notes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn expired_transfers_are_reclaimed() -> Result<()> {
+        let dir = create_temp_dir();
+        let mut sender = HotWallet::create_from_key(dir.path(), MainSecretKey::random(), None)?;
+        let genesis =
+            create_first_cash_note_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit_and_store_to_disk(&vec![genesis])?;
+        let balance = sender.balance();
+
+        // a deadline in the past, so the transfer can be reclaimed right away
+        let recipient = MainSecretKey::random();
+        let to = vec![(NanoTokens::from(100), recipient.main_pubkey())];
+        let sent = sender.local_send_reclaimable(to, 0, None)?;
+        sender.clear_confirmed_spend_requests();
+        assert_eq!(sender.reclaimable_transfers()?.len(), 1);
+
+        // the recipient can still spend it
+        let recipient_spend = OfflineTransfer::new(
+            vec![(sent[0].clone(), Some(sent[0].derived_key(&recipient)?))],
+            vec![(
+                NanoTokens::from(100),
+                recipient.main_pubkey(),
+                DerivationIndex([1u8; 32]),
+            )],
+            recipient.main_pubkey(),
+            SpendReason::default(),
+        )?;
+        assert!(!recipient_spend.all_spend_requests[0].is_refund());
+
+        let refunded = sender.reclaim_expired_transfers()?;
+        assert_eq!(refunded.len(), 1);
+        assert_eq!(refunded[0].value()?, NanoTokens::from(100));
+        assert_eq!(sender.balance(), balance);
+        assert!(sender.reclaimable_transfers()?.is_empty());
+        assert!(sender
+            .unconfirmed_spend_requests()
+            .iter()
+            .all(|spend| spend.is_refund() && spend.unique_pubkey() == &sent[0].unique_pubkey()));
+        Ok(())
+    }
+
+    #[test]
+    fn transfers_cannot_be_reclaimed_before_their_deadline() -> Result<()> {
+        let dir = create_temp_dir();
+        let mut sender = HotWallet::create_from_key(dir.path(), MainSecretKey::random(), None)?;
+        let genesis =
+            create_first_cash_note_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit_and_store_to_disk(&vec![genesis])?;
+
+        let to = vec![(NanoTokens::from(100), MainSecretKey::random().main_pubkey())];
+        let sent = sender.local_send_reclaimable(to, u64::MAX, None)?;
+        assert!(sender.reclaim_expired_transfers()?.is_empty());
+
+        // a refund signed with the refund key is rejected by the nodes until the deadline
+        let reclaimable = sender.reclaimable_transfers()?[&sent[0].unique_pubkey()].clone();
+        let refund = OfflineTransfer::new(
+            vec![(
+                reclaimable.cash_note,
+                Some(sender.key.derive_key(&reclaimable.refund_index)),
+            )],
+            vec![(
+                NanoTokens::from(100),
+                sender.address(),
+                DerivationIndex([1u8; 32]),
+            )],
+            sender.address(),
+            SpendReason::default(),
+        )?;
+        let refund_spend = &refund.all_spend_requests[0];
+        assert!(refund_spend.is_refund());
+        assert!(matches!(
+            refund_spend.verify_reclaim_deadline(u64::MAX - 1),
+            Err(TransferError::ReclaimBeforeDeadline(_))
+        ));
+        assert!(refund_spend.verify_reclaim_deadline(u64::MAX).is_ok());
+
+        sender.forget_reclaimable_transfer(&sent[0].unique_pubkey())?;
+        assert!(sender.reclaimable_transfers()?.is_empty());
+        Ok(())
+    }
+
     fn create_temp_dir() -> TempDir {
         TempDir::new().expect("Should be able to create a temp dir.")
     }
@@ -0,0 +1,451 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    data_payments::{create_storage_payment, PaymentDetails, PaymentQuote, StoragePayment},
+    events::{WalletEvent, WalletEventsBroadcaster},
+    Error, Result,
+};
+use crate::{
+    cashnotes::UnsignedTransfer,
+    transfers::{create_unsigned_transfer, CashNotesAndSecretKey, OfflineTransfer},
+    CashNote, CashNoteRedemption, DerivationIndex, DerivedSecretKey, MainPubkey, MainSecretKey,
+    NanoTokens, SignedSpend, Spend, SpendAddress, SpendReason, Transaction, Transfer, UniquePubkey,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use xor_name::XorName;
+
+/// A wallet held entirely in memory.
+///
+/// It offers the same API as the `HotWallet`, but without any filesystem dependency,
+/// so transfer flows can be exercised in tests and embedded (eg. WASM) targets.
+/// Nothing is persisted: all state is lost when the wallet is dropped.
+pub struct MemWallet {
+    /// The secret key with which we can access
+    /// all the tokens in the available_cash_notes.
+    key: MainSecretKey,
+    /// The cash_notes we own that are not yet spent, with their value.
+    available_cash_notes: BTreeMap<UniquePubkey, (CashNote, NanoTokens)>,
+    /// These have not yet been successfully sent to the network
+    /// and need to be, to reach network validity.
+    unconfirmed_spend_requests: BTreeSet<SignedSpend>,
+    /// The spends that have been confirmed by the network.
+    confirmed_spends: BTreeMap<SpendAddress, SignedSpend>,
+    /// The payments made for each content address, the most recent one last.
+    payments: BTreeMap<XorName, Vec<PaymentDetails>>,
+    /// Channel where wallet events are broadcasted to listeners.
+    events: WalletEventsBroadcaster,
+}
+
+impl MemWallet {
+    /// Creates an empty wallet for the given key.
+    pub fn new(key: MainSecretKey) -> Self {
+        Self {
+            key,
+            available_cash_notes: Default::default(),
+            unconfirmed_spend_requests: Default::default(),
+            confirmed_spends: Default::default(),
+            payments: Default::default(),
+            events: Default::default(),
+        }
+    }
+
+    #[cfg(feature = "test-utils")]
+    pub fn key(&self) -> &MainSecretKey {
+        &self.key
+    }
+
+    pub fn address(&self) -> MainPubkey {
+        self.key.main_pubkey()
+    }
+
+    /// The total value of the available cash_notes, capped at `u64::MAX` nanos.
+    pub fn balance(&self) -> NanoTokens {
+        let balance = self
+            .available_cash_notes
+            .values()
+            .fold(0u64, |balance, (_cash_note, value)| {
+                balance.saturating_add(value.as_nano())
+            });
+        NanoTokens::from(balance)
+    }
+
+    /// Returns a new receiver of the events broadcasted by this wallet.
+    pub fn subscribe_to_events(&self) -> std::sync::mpsc::Receiver<WalletEvent> {
+        self.events.subscribe()
+    }
+
+    /// Deposit the given cash_notes on the wallet.
+    /// CashNotes not belonging to this wallet are skipped.
+    pub fn deposit(&mut self, received_cash_notes: &Vec<CashNote>) -> Result<()> {
+        let old_balance = self.balance();
+        let main_pubkey = self.address();
+
+        for cash_note in received_cash_notes {
+            let id = cash_note.unique_pubkey();
+
+            if cash_note.derived_pubkey(&main_pubkey).is_err() {
+                debug!("skipping: cash_note is not our key");
+                continue;
+            }
+
+            let value = cash_note.value()?;
+            if self
+                .available_cash_notes
+                .insert(id, (cash_note.clone(), value))
+                .is_none()
+            {
+                self.events.broadcast(WalletEvent::CashNoteReceived {
+                    unique_pubkey: id,
+                    amount: value,
+                });
+            }
+        }
+
+        self.broadcast_balance_change(old_balance);
+        Ok(())
+    }
+
+    /// Checks whether the specified cash_note already presents
+    pub fn cash_note_presents(&self, id: &UniquePubkey) -> bool {
+        self.available_cash_notes.contains_key(id)
+    }
+
+    /// Returns all available cash_notes, with the derived keys to spend them.
+    pub fn available_cash_notes(&self) -> CashNotesAndSecretKey {
+        let mut available_cash_notes = vec![];
+        for (cash_note, _value) in self.available_cash_notes.values() {
+            if let Ok(derived_key) = cash_note.derived_key(&self.key) {
+                available_cash_notes.push((cash_note.clone(), Some(derived_key)));
+            } else {
+                warn!(
+                    "Skipping CashNote {:?} because we don't have the key to spend it",
+                    cash_note.unique_pubkey()
+                );
+            }
+        }
+        available_cash_notes
+    }
+
+    /// Remove referenced CashNotes from available_cash_notes
+    pub fn mark_notes_as_spent<'a, T>(&mut self, unique_pubkeys: T)
+    where
+        T: IntoIterator<Item = &'a UniquePubkey>,
+    {
+        let old_balance = self.balance();
+        for k in unique_pubkeys {
+            self.available_cash_notes.remove(k);
+        }
+        self.broadcast_balance_change(old_balance);
+    }
+
+    pub fn unconfirmed_spend_requests(&self) -> &BTreeSet<SignedSpend> {
+        &self.unconfirmed_spend_requests
+    }
+
+    pub fn unconfirmed_spend_requests_exist(&self) -> bool {
+        !self.unconfirmed_spend_requests.is_empty()
+    }
+
+    /// To remove a specific spend from the requests, if eg, we see one spend is _bad_
+    pub fn clear_specific_spend_request(&mut self, unique_pub_key: UniquePubkey) {
+        let before = self.unconfirmed_spend_requests.len();
+        self.unconfirmed_spend_requests
+            .retain(|signed_spend| signed_spend.spend.unique_pubkey.ne(&unique_pub_key));
+        if self.unconfirmed_spend_requests.len() != before {
+            self.events.broadcast(WalletEvent::SpendFailed {
+                unique_pubkey: unique_pub_key,
+            });
+        }
+    }
+
+    /// Once spends are verified we can clear them, keeping them as confirmed spends.
+    pub fn clear_confirmed_spend_requests(&mut self) {
+        let confirmed = std::mem::take(&mut self.unconfirmed_spend_requests);
+        for signed_spend in confirmed {
+            self.events.broadcast(WalletEvent::SpendConfirmed {
+                unique_pubkey: signed_spend.spend.unique_pubkey,
+            });
+            let _ = self
+                .confirmed_spends
+                .insert(signed_spend.address(), signed_spend);
+        }
+    }
+
+    /// Get a confirmed spend.
+    pub fn get_confirmed_spend(&self, spend_addr: SpendAddress) -> Option<SignedSpend> {
+        self.confirmed_spends.get(&spend_addr).cloned()
+    }
+
+    /// Check whether have the specific confirmed spend.
+    pub fn has_confirmed_spend(&self, spend_addr: SpendAddress) -> bool {
+        self.confirmed_spends.contains_key(&spend_addr)
+    }
+
+    pub fn sign(
+        &self,
+        spends: impl IntoIterator<Item = (Spend, DerivationIndex)>,
+    ) -> BTreeSet<SignedSpend> {
+        spends
+            .into_iter()
+            .map(|(spend, dindex)| {
+                let derived_sk = self.key.derive_key(&dindex);
+                let derived_key_sig = derived_sk.sign(&spend.to_bytes_for_signing());
+                SignedSpend {
+                    spend,
+                    derived_key_sig,
+                }
+            })
+            .collect()
+    }
+
+    pub fn build_unsigned_transaction(
+        &self,
+        to: Vec<(NanoTokens, MainPubkey)>,
+        reason: Option<SpendReason>,
+    ) -> Result<UnsignedTransfer> {
+        let mut rng = &mut rand::rngs::OsRng;
+        // create a unique key for each output
+        let to_unique_keys: Vec<_> = to
+            .into_iter()
+            .map(|(amount, address)| (amount, address, DerivationIndex::random(&mut rng)))
+            .collect();
+
+        let available_cash_notes = self
+            .available_cash_notes
+            .values()
+            .map(|(cash_note, _value)| (cash_note.clone(), None))
+            .collect();
+
+        let unsigned_transfer = create_unsigned_transfer(
+            available_cash_notes,
+            to_unique_keys,
+            self.address(),
+            reason.unwrap_or_default(),
+        )?;
+
+        Ok(unsigned_transfer)
+    }
+
+    /// Make a transfer and return all created cash_notes
+    pub fn local_send(
+        &mut self,
+        to: Vec<(NanoTokens, MainPubkey)>,
+        reason: Option<SpendReason>,
+    ) -> Result<Vec<CashNote>> {
+        let mut rng = &mut rand::rngs::OsRng;
+        // create a unique key for each output
+        let to_unique_keys: Vec<_> = to
+            .into_iter()
+            .map(|(amount, address)| (amount, address, DerivationIndex::random(&mut rng)))
+            .collect();
+
+        let transfer = OfflineTransfer::new(
+            self.available_cash_notes(),
+            to_unique_keys,
+            self.address(),
+            reason.unwrap_or_default(),
+        )?;
+
+        let created_cash_notes = transfer.cash_notes_for_recipient.clone();
+        self.update_local_wallet(transfer)?;
+
+        Ok(created_cash_notes)
+    }
+
+    /// Prepare a signed transaction in local wallet and return all created cash_notes
+    pub fn prepare_signed_transfer(
+        &mut self,
+        signed_spends: BTreeSet<SignedSpend>,
+        tx: Transaction,
+        change_id: UniquePubkey,
+        output_details: BTreeMap<UniquePubkey, (MainPubkey, DerivationIndex)>,
+    ) -> Result<Vec<CashNote>> {
+        let transfer =
+            OfflineTransfer::from_transaction(signed_spends, tx, change_id, output_details)?;
+
+        let created_cash_notes = transfer.cash_notes_for_recipient.clone();
+        self.update_local_wallet(transfer)?;
+
+        Ok(created_cash_notes)
+    }
+
+    /// Performs a payment for each content address.
+    /// Includes payment of network royalties.
+    /// Returns the amount paid for storage, including the network royalties fee paid.
+    pub fn local_send_storage_payment(
+        &mut self,
+        price_map: &BTreeMap<XorName, (MainPubkey, PaymentQuote, Vec<u8>)>,
+    ) -> Result<(NanoTokens, NanoTokens)> {
+        let StoragePayment {
+            offline_transfer,
+            payments,
+            storage_cost,
+            royalties_fees,
+        } = create_storage_payment(price_map, self.available_cash_notes(), self.address())?;
+
+        for (xorname, payment) in payments {
+            self.payments.entry(xorname).or_default().push(payment);
+        }
+        self.update_local_wallet(offline_transfer)?;
+
+        Ok((storage_cost, royalties_fees))
+    }
+
+    /// Returns the most recent PaymentDetails for the given xorname if any.
+    pub fn get_recent_payment(&self, xorname: &XorName) -> Result<PaymentDetails> {
+        self.payments
+            .get(xorname)
+            .and_then(|payments| payments.last())
+            .cloned()
+            .ok_or(Error::NoPaymentForAddress(*xorname))
+    }
+
+    /// Remove the payment_details of the given XorName.
+    pub fn remove_payment_for_xorname(&mut self, name: &XorName) {
+        let _ = self.payments.remove(name);
+    }
+
+    pub fn unwrap_transfer(&self, transfer: &Transfer) -> Result<Vec<CashNoteRedemption>> {
+        transfer
+            .cashnote_redemptions(&self.key)
+            .map_err(|_| Error::FailedToDecypherTransfer)
+    }
+
+    pub fn derive_key(&self, derivation_index: &DerivationIndex) -> DerivedSecretKey {
+        self.key.derive_key(derivation_index)
+    }
+
+    fn update_local_wallet(&mut self, transfer: OfflineTransfer) -> Result<()> {
+        let old_balance = self.balance();
+
+        for input in &transfer.tx.inputs {
+            let _ = self.available_cash_notes.remove(input.unique_pubkey());
+        }
+
+        if let Some(cash_note) = transfer.change_cash_note {
+            let value = cash_note.value()?;
+            let _ = self
+                .available_cash_notes
+                .insert(cash_note.unique_pubkey(), (cash_note, value));
+        }
+
+        for request in transfer.all_spend_requests {
+            self.unconfirmed_spend_requests.insert(request);
+        }
+
+        self.broadcast_balance_change(old_balance);
+        Ok(())
+    }
+
+    /// Broadcasts a `BalanceChanged` event if the balance differs from the given one.
+    fn broadcast_balance_change(&self, old_balance: NanoTokens) {
+        let new_balance = self.balance();
+        if new_balance != old_balance {
+            self.events.broadcast(WalletEvent::BalanceChanged {
+                old: old_balance,
+                new: new_balance,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::{create_first_cash_note_from_key, GENESIS_CASHNOTE_AMOUNT};
+    use eyre::Result;
+
+    #[test]
+    fn deposit_only_adds_cash_notes_belonging_to_the_wallet() -> Result<()> {
+        let key = MainSecretKey::random();
+        let genesis = create_first_cash_note_from_key(&key).expect("Genesis creation to succeed.");
+        let others = create_first_cash_note_from_key(&MainSecretKey::random())
+            .expect("Genesis creation to succeed.");
+        let mut wallet = MemWallet::new(key);
+
+        wallet.deposit(&vec![genesis.clone(), others])?;
+        assert_eq!(GENESIS_CASHNOTE_AMOUNT, wallet.balance().as_nano());
+
+        // deposit is idempotent
+        wallet.deposit(&vec![genesis])?;
+        assert_eq!(GENESIS_CASHNOTE_AMOUNT, wallet.balance().as_nano());
+
+        Ok(())
+    }
+
+    #[test]
+    fn send_between_mem_wallets() -> Result<()> {
+        let sender_key = MainSecretKey::random();
+        let genesis =
+            create_first_cash_note_from_key(&sender_key).expect("Genesis creation to succeed.");
+        let mut sender = MemWallet::new(sender_key);
+        sender.deposit(&vec![genesis])?;
+
+        let mut recipient = MemWallet::new(MainSecretKey::random());
+        let send_amount = 100;
+        let to = vec![(NanoTokens::from(send_amount), recipient.address())];
+        let created_cash_notes = sender.local_send(to, None)?;
+
+        assert_eq!(
+            GENESIS_CASHNOTE_AMOUNT - send_amount,
+            sender.balance().as_nano()
+        );
+        assert_eq!(1, sender.unconfirmed_spend_requests().len());
+
+        recipient.deposit(&created_cash_notes)?;
+        assert_eq!(send_amount, recipient.balance().as_nano());
+
+        let spend_address = sender
+            .unconfirmed_spend_requests()
+            .iter()
+            .map(|spend| spend.address())
+            .next()
+            .expect("There to be an unconfirmed spend.");
+        sender.clear_confirmed_spend_requests();
+        assert!(!sender.unconfirmed_spend_requests_exist());
+        assert!(sender.has_confirmed_spend(spend_address));
+
+        Ok(())
+    }
+
+    #[test]
+    fn storage_payment_is_kept_in_memory() -> Result<()> {
+        let key = MainSecretKey::random();
+        let genesis = create_first_cash_note_from_key(&key).expect("Genesis creation to succeed.");
+        let mut wallet = MemWallet::new(key);
+        wallet.deposit(&vec![genesis])?;
+
+        let mut rng = bls::rand::thread_rng();
+        let xorname = XorName::random(&mut rng);
+        let node_key = MainSecretKey::random().main_pubkey();
+        let price_map = BTreeMap::from([(
+            xorname,
+            (
+                node_key,
+                PaymentQuote::test_dummy(xorname, 100.into()),
+                vec![],
+            ),
+        )]);
+
+        let (storage_cost, royalties) = wallet.local_send_storage_payment(&price_map)?;
+        assert_eq!(storage_cost.as_nano(), 100);
+
+        let payment = wallet.get_recent_payment(&xorname)?;
+        assert_eq!(payment.recipient, node_key);
+        assert_eq!(
+            GENESIS_CASHNOTE_AMOUNT - storage_cost.as_nano() - royalties.as_nano(),
+            wallet.balance().as_nano()
+        );
+
+        wallet.remove_payment_for_xorname(&xorname);
+        assert!(wallet.get_recent_payment(&xorname).is_err());
+
+        Ok(())
+    }
+}
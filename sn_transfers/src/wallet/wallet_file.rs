@@ -8,12 +8,15 @@
 
 use super::{
     error::{Error, Result},
+    hot_wallet::ReclaimableTransfer,
     KeyLessWallet,
 };
-use crate::{CashNote, SignedSpend, SpendAddress, UniquePubkey};
-use serde::Serialize;
+use crate::{
+    versioned::{from_versioned_bytes, to_versioned_bytes, Versioned},
+    CashNote, SignedSpend, SpendAddress, UniquePubkey,
+};
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fs,
     path::{Path, PathBuf},
 };
@@ -24,13 +27,12 @@ const WALLET_LOCK_FILE_NAME: &str = "wallet.lock";
 const CASHNOTES_DIR_NAME: &str = "cash_notes";
 const UNCONFIRMED_TX_NAME: &str = "unconfirmed_spend_requests";
 const CONFIRMED_SPENDS_DIR_NAME: &str = "confirmed_spends";
+const RECLAIMABLE_TRANSFERS_FILE_NAME: &str = "reclaimable_transfers";
 
 /// Writes the `KeyLessWallet` to the specified path.
 pub(super) fn store_wallet(wallet_dir: &Path, wallet: &KeyLessWallet) -> Result<()> {
     let wallet_path = wallet_dir.join(WALLET_FILE_NAME);
-    let mut file = fs::File::create(wallet_path)?;
-    let mut serialiser = rmp_serde::encode::Serializer::new(&mut file);
-    wallet.serialize(&mut serialiser)?;
+    fs::write(wallet_path, to_versioned_bytes(wallet)?)?;
     Ok(())
 }
 
@@ -51,9 +53,10 @@ pub(super) fn store_unconfirmed_spend_requests(
 ) -> Result<()> {
     let unconfirmed_spend_requests_path = wallet_dir.join(UNCONFIRMED_TX_NAME);
 
-    let mut file = fs::File::create(unconfirmed_spend_requests_path)?;
-    let mut serialiser = rmp_serde::encode::Serializer::new(&mut file);
-    unconfirmed_spend_requests.serialize(&mut serialiser)?;
+    fs::write(
+        unconfirmed_spend_requests_path,
+        to_versioned_bytes(unconfirmed_spend_requests)?,
+    )?;
     Ok(())
 }
 
@@ -98,6 +101,46 @@ pub(super) fn get_confirmed_spend(
     Ok(Some(confirmed_spend))
 }
 
+/// Returns the raw records of all the confirmed spends, by their address.
+pub(super) fn get_confirmed_spend_records(
+    wallet_dir: &Path,
+) -> Result<BTreeMap<SpendAddress, Vec<u8>>> {
+    let spends_dir = wallet_dir.join(CONFIRMED_SPENDS_DIR_NAME);
+    let mut records = BTreeMap::new();
+    if !spends_dir.is_dir() {
+        return Ok(records);
+    }
+
+    for entry in fs::read_dir(&spends_dir)? {
+        let path = entry?.path();
+        let address = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| SpendAddress::from_hex(name).ok());
+        match address {
+            Some(address) if path.is_file() => {
+                records.insert(address, fs::read(&path)?);
+            }
+            _ => debug!("Skipping unexpected entry in the confirmed spends dir: {path:?}"),
+        }
+    }
+    Ok(records)
+}
+
+/// Writes the raw record of a confirmed spend.
+pub(super) fn store_confirmed_spend_record(
+    wallet_dir: &Path,
+    spend_addr: &SpendAddress,
+    record: &[u8],
+) -> Result<()> {
+    let spends_dir = wallet_dir.join(CONFIRMED_SPENDS_DIR_NAME);
+    fs::create_dir_all(&spends_dir)?;
+    let spend_file_path = spends_dir.join(spend_addr.to_hex());
+    debug!("Writing confirmed_spend instance to: {spend_file_path:?}");
+    fs::write(spend_file_path, record)?;
+    Ok(())
+}
+
 /// Returns whether a spend is put as `confirmed`.
 ///
 /// Note: due to the disk operations' async behaviour.
@@ -119,12 +162,44 @@ pub(super) fn get_unconfirmed_spend_requests(
         return Ok(None);
     }
 
-    let file = fs::File::open(&path)?;
-    let unconfirmed_spend_requests = rmp_serde::from_read(&file)?;
+    let unconfirmed_spend_requests = from_versioned_bytes(&fs::read(&path)?)?;
 
     Ok(Some(unconfirmed_spend_requests))
 }
 
+impl Versioned for KeyLessWallet {
+    const VERSION: u8 = 1;
+}
+
+impl Versioned for BTreeSet<SignedSpend> {
+    const VERSION: u8 = 1;
+}
+
+/// Writes the sent transfers we may have to reclaim to the specified path.
+pub(super) fn store_reclaimable_transfers(
+    wallet_dir: &Path,
+    reclaimable_transfers: &BTreeMap<UniquePubkey, ReclaimableTransfer>,
+) -> Result<()> {
+    let path = wallet_dir.join(RECLAIMABLE_TRANSFERS_FILE_NAME);
+    fs::write(path, to_versioned_bytes(reclaimable_transfers)?)?;
+    Ok(())
+}
+
+/// Returns the sent transfers we may have to reclaim, if any.
+pub(super) fn get_reclaimable_transfers(
+    wallet_dir: &Path,
+) -> Result<BTreeMap<UniquePubkey, ReclaimableTransfer>> {
+    let path = wallet_dir.join(RECLAIMABLE_TRANSFERS_FILE_NAME);
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(from_versioned_bytes(&fs::read(&path)?)?)
+}
+
+impl Versioned for BTreeMap<UniquePubkey, ReclaimableTransfer> {
+    const VERSION: u8 = 1;
+}
+
 /// Hex encode and write each `CashNote` to a separate file in respective
 /// recipient public address dir in the created cash_notes dir. Each file is named after the cash_note id.
 pub(super) fn store_created_cash_notes<'a, T>(